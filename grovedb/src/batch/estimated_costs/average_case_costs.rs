@@ -373,7 +373,7 @@ mod tests {
             OperationCost {
                 seek_count: 3,
                 storage_cost: StorageCost {
-                    added_bytes: 115,
+                    added_bytes: 116,
                     replaced_bytes: 0,
                     removed_bytes: NoStorageRemoval,
                 },
@@ -438,7 +438,7 @@ mod tests {
             OperationCost {
                 seek_count: 3,
                 storage_cost: StorageCost {
-                    added_bytes: 119,
+                    added_bytes: 120,
                     replaced_bytes: 0,
                     removed_bytes: NoStorageRemoval,
                 },
@@ -498,7 +498,7 @@ mod tests {
             OperationCost {
                 seek_count: 3,
                 storage_cost: StorageCost {
-                    added_bytes: 149,
+                    added_bytes: 150,
                     replaced_bytes: 0,
                     removed_bytes: NoStorageRemoval,
                 },
@@ -558,28 +558,29 @@ mod tests {
         // 1 to insert
         // 1 to insert node above
 
-        // Replaced parent Value -> 76
+        // Replaced parent Value -> 77
+        //   1 for the element format version
         //   1 for the flag option (but no flags)
         //   1 for the enum type
         //   1 for an empty option
         // 32 for node hash
         // 32 for value hash
-        // 1 byte for the value_size (required space for 75)
+        // 1 byte for the value_size (required space for 76)
 
         // Loaded
         // For root key 1 byte
-        // For root tree item 69 bytes
+        // For root tree item 70 bytes
 
         assert_eq!(
             average_case_cost,
             OperationCost {
                 seek_count: 5, // todo: why is this 5
                 storage_cost: StorageCost {
-                    added_bytes: 115,
-                    replaced_bytes: 106,
+                    added_bytes: 116,
+                    replaced_bytes: 107,
                     removed_bytes: NoStorageRemoval,
                 },
-                storage_loaded_bytes: 109,
+                storage_loaded_bytes: 110,
                 hash_node_calls: 8,
             }
         );
@@ -659,8 +660,8 @@ mod tests {
             OperationCost {
                 seek_count: 6,
                 storage_cost: StorageCost {
-                    added_bytes: 115,
-                    replaced_bytes: 75,
+                    added_bytes: 116,
+                    replaced_bytes: 76,
                     removed_bytes: NoStorageRemoval,
                 },
                 storage_loaded_bytes: 173,
@@ -723,10 +724,10 @@ mod tests {
                 seek_count: 41,
                 storage_cost: StorageCost {
                     added_bytes: 0,
-                    replaced_bytes: 5625,
+                    replaced_bytes: 5628,
                     removed_bytes: NoStorageRemoval,
                 },
-                storage_loaded_bytes: 7669,
+                storage_loaded_bytes: 7670,
                 hash_node_calls: 79,
             }
         );
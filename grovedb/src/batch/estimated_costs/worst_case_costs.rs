@@ -314,7 +314,7 @@ mod tests {
             OperationCost {
                 seek_count: 5,
                 storage_cost: StorageCost {
-                    added_bytes: 115,
+                    added_bytes: 116,
                     replaced_bytes: 65535, // todo: verify
                     removed_bytes: NoStorageRemoval,
                 },
@@ -367,7 +367,7 @@ mod tests {
             OperationCost {
                 seek_count: 4,
                 storage_cost: StorageCost {
-                    added_bytes: 119,
+                    added_bytes: 120,
                     replaced_bytes: 0,
                     removed_bytes: NoStorageRemoval,
                 },
@@ -420,7 +420,7 @@ mod tests {
             OperationCost {
                 seek_count: 4,
                 storage_cost: StorageCost {
-                    added_bytes: 149,
+                    added_bytes: 150,
                     replaced_bytes: 0,
                     removed_bytes: NoStorageRemoval,
                 },
@@ -477,7 +477,7 @@ mod tests {
             OperationCost {
                 seek_count: 38,
                 storage_cost: StorageCost {
-                    added_bytes: 115,
+                    added_bytes: 116,
                     replaced_bytes: 2228190, // todo: verify
                     removed_bytes: NoStorageRemoval,
                 },
@@ -532,8 +532,8 @@ mod tests {
             OperationCost {
                 seek_count: 7,
                 storage_cost: StorageCost {
-                    added_bytes: 115,
-                    replaced_bytes: 81996,
+                    added_bytes: 116,
+                    replaced_bytes: 81997,
                     removed_bytes: NoStorageRemoval,
                 },
                 storage_loaded_bytes: 65964,
@@ -54,7 +54,7 @@ mod single_sum_item_insert_cost_tests;
 use core::fmt;
 use std::{
     cmp::Ordering,
-    collections::{btree_map::Entry, hash_map::Entry as HashMapEntry, BTreeMap, HashMap},
+    collections::{btree_map::Entry, hash_map::Entry as HashMapEntry, BTreeMap, HashMap, HashSet},
     hash::{Hash, Hasher},
     ops::{Add, AddAssign},
     slice::Iter,
@@ -87,7 +87,7 @@ use grovedb_storage::{
     rocksdb_storage::{PrefixedRocksDbStorageContext, PrefixedRocksDbTransactionContext},
     Storage, StorageBatch, StorageContext,
 };
-use grovedb_visualize::{Drawer, Visualize};
+use grovedb_visualize::{DebugByteVectors, Drawer, Visualize};
 use integer_encoding::VarInt;
 use itertools::Itertools;
 use key_info::{KeyInfo, KeyInfo::KnownKey};
@@ -99,11 +99,11 @@ use crate::batch::estimated_costs::EstimatedCostsType;
 use crate::{
     batch::{batch_structure::BatchStructure, mode::BatchRunMode},
     element::{MaxReferenceHop, SUM_ITEM_COST_SIZE, SUM_TREE_COST_SIZE, TREE_COST_SIZE},
-    operations::get::MAX_REFERENCE_HOPS,
+    operations::{change_journal::ChangedKeys, get::MAX_REFERENCE_HOPS},
     reference_path::{
         path_from_reference_path_type, path_from_reference_qualified_path_type, ReferencePathType,
     },
-    Element, ElementFlags, Error, GroveDb, Transaction, TransactionArg,
+    Element, ElementFlags, Error, GroveDb, PathErrorDetail, Transaction, TransactionArg,
 };
 
 /// Operations
@@ -549,6 +549,58 @@ impl GroveDbOp {
         }
     }
 
+    /// Collapses operations targeting the same path and key into a single
+    /// operation, so that applying the batch doesn't depend on the order of
+    /// repeated operations.
+    ///
+    /// The last operation on a target wins and takes the place of the first
+    /// operation on that target; other operations keep their relative order.
+    /// A later `Patch` or `RefreshReference` on an already targeted key is
+    /// rejected since both depend on the element stored before the batch, as
+    /// are repeated internal `ReplaceTreeRootKey` and `InsertTreeWithRootHash`
+    /// operations.
+    pub fn dedupe_ops(ops: Vec<GroveDbOp>) -> Result<Vec<GroveDbOp>, Error> {
+        let mut positions: BTreeMap<(KeyInfoPath, KeyInfo), usize> = BTreeMap::new();
+        let mut deduped: Vec<GroveDbOp> = Vec::with_capacity(ops.len());
+
+        for op in ops {
+            match positions.entry((op.path.clone(), op.key.clone())) {
+                Entry::Vacant(entry) => {
+                    entry.insert(deduped.len());
+                    deduped.push(op);
+                }
+                Entry::Occupied(entry) => {
+                    let previous = &mut deduped[*entry.get()];
+                    match (&previous.op, &op.op) {
+                        (Op::ReplaceTreeRootKey { .. }, _)
+                        | (Op::InsertTreeWithRootHash { .. }, _)
+                        | (_, Op::ReplaceTreeRootKey { .. })
+                        | (_, Op::InsertTreeWithRootHash { .. }) => {
+                            return Err(Error::InvalidBatchOperation(
+                                "internal tree operations can not target the same key as other \
+                                 operations",
+                            ));
+                        }
+                        (_, Op::Patch { .. }) => {
+                            return Err(Error::InvalidBatchOperation(
+                                "a patch can not follow another operation on the same key",
+                            ));
+                        }
+                        (_, Op::RefreshReference { .. }) => {
+                            return Err(Error::InvalidBatchOperation(
+                                "a reference refresh can not follow another operation on the \
+                                 same key",
+                            ));
+                        }
+                        _ => *previous = op,
+                    }
+                }
+            }
+        }
+
+        Ok(deduped)
+    }
+
     /// Verify consistency of operations
     pub fn verify_consistency_of_operations(ops: &Vec<GroveDbOp>) -> GroveDbOpConsistencyResults {
         let ops_len = ops.len();
@@ -721,6 +773,8 @@ where
     ///   already checked to be a valid path.
     /// * `recursions_allowed`: The maximum allowed hop count to reach the
     ///   target element.
+    /// * `max_hops`: The hop count allowed for the whole reference chain,
+    ///   reported when it is exceeded.
     ///
     /// # Returns
     ///
@@ -748,6 +802,7 @@ where
         qualified_path: &[Vec<u8>],
         ops_by_qualified_paths: &'a BTreeMap<Vec<Vec<u8>>, Op>,
         recursions_allowed: u8,
+        max_hops: u8,
         intermediate_reference_info: Option<&'a ReferencePathType>,
     ) -> CostResult<CryptoHash, Error> {
         let mut cost = OperationCost::default();
@@ -796,8 +851,10 @@ where
             );
             self.follow_reference_get_value_hash(
                 path.as_slice(),
+                qualified_path,
                 ops_by_qualified_paths,
                 recursions_allowed - 1,
+                max_hops,
             )
         } else {
             // Here the element being referenced doesn't change in the same batch
@@ -846,8 +903,10 @@ where
                     );
                     self.follow_reference_get_value_hash(
                         path.as_slice(),
+                        qualified_path,
                         ops_by_qualified_paths,
                         recursions_allowed - 1,
+                        max_hops,
                     )
                 }
                 Element::Tree(..) | Element::SumTree(..) => Err(Error::InvalidBatchOperation(
@@ -868,15 +927,25 @@ where
     /// insert ref_3 and another operation to change something in the
     /// reference chain in the same batch.
     /// All these has to be taken into account.
+    ///
+    /// `referencing_path` is the qualified path of the reference pointing to
+    /// `qualified_path`, and `max_hops` the hops allowed for the whole chain,
+    /// both reported if the chain is too long.
     fn follow_reference_get_value_hash<'a>(
         &'a mut self,
         qualified_path: &[Vec<u8>],
+        referencing_path: &[Vec<u8>],
         ops_by_qualified_paths: &'a BTreeMap<Vec<Vec<u8>>, Op>,
         recursions_allowed: u8,
+        max_hops: u8,
     ) -> CostResult<CryptoHash, Error> {
         let mut cost = OperationCost::default();
         if recursions_allowed == 0 {
-            return Err(Error::ReferenceLimit).wrap_with_cost(cost);
+            return Err(Error::ReferenceChainTooLong {
+                hops: max_hops as usize,
+                last_path: referencing_path.to_vec(),
+            })
+            .wrap_with_cost(cost);
         }
         // If the element being referenced changes in the same batch
         // we need to set the value_hash based on the new change and not the old state.
@@ -905,8 +974,10 @@ where
                             );
                             self.follow_reference_get_value_hash(
                                 path.as_slice(),
+                                qualified_path,
                                 ops_by_qualified_paths,
                                 recursions_allowed - 1,
+                                max_hops,
                             )
                         }
                         Element::Tree(..) | Element::SumTree(..) => {
@@ -932,6 +1003,7 @@ where
                         qualified_path,
                         ops_by_qualified_paths,
                         recursions_allowed,
+                        max_hops,
                         reference_info,
                     )
                 }
@@ -947,6 +1019,7 @@ where
                 qualified_path,
                 ops_by_qualified_paths,
                 recursions_allowed,
+                max_hops,
                 None,
             )
         }
@@ -1027,6 +1100,10 @@ where
                                     .get_feature_type(is_sum_tree)
                                     .wrap_with_cost(OperationCost::default())
                             );
+                            cost_return_on_error_no_add!(
+                                &cost,
+                                path_reference.validate_at(path.as_slice())
+                            );
                             let path_reference = cost_return_on_error!(
                                 &mut cost,
                                 path_from_reference_path_type(
@@ -1043,12 +1120,18 @@ where
                                 .wrap_with_cost(cost);
                             }
 
+                            let mut reference_qualified_path = path.to_vec();
+                            reference_qualified_path.push(key_info.get_key_clone());
+                            let max_hops =
+                                element_max_reference_hop.unwrap_or(MAX_REFERENCE_HOPS as u8);
                             let referenced_element_value_hash = cost_return_on_error!(
                                 &mut cost,
                                 self.follow_reference_get_value_hash(
                                     path_reference.as_slice(),
+                                    &reference_qualified_path,
                                     ops_by_qualified_paths,
-                                    element_max_reference_hop.unwrap_or(MAX_REFERENCE_HOPS as u8)
+                                    max_hops,
+                                    max_hops
                                 )
                             );
 
@@ -1175,12 +1258,17 @@ where
                         .wrap_with_cost(cost);
                     }
 
+                    let mut reference_qualified_path = path.to_vec();
+                    reference_qualified_path.push(key_info.get_key_clone());
+                    let max_hops = max_reference_hop.unwrap_or(MAX_REFERENCE_HOPS as u8);
                     let referenced_element_value_hash = cost_return_on_error!(
                         &mut cost,
                         self.follow_reference_get_value_hash(
                             path_reference.as_slice(),
+                            &reference_qualified_path,
                             ops_by_qualified_paths,
-                            max_reference_hop.unwrap_or(MAX_REFERENCE_HOPS as u8)
+                            max_hops,
+                            max_hops
                         )
                     );
 
@@ -1356,12 +1444,12 @@ where
                     }
                 },
             )
-            .map_err(|e| Error::CorruptedData(e.to_string()))
+            .map_err(Error::from_merk_apply)
         );
         let r = merk
             .root_hash_key_and_sum()
             .add_cost(cost)
-            .map_err(Error::MerkError);
+            .map_err(Error::from_merk_sum);
         // We need to reinsert the merk
         self.merks.insert(path.clone(), merk);
         r
@@ -1696,12 +1784,90 @@ impl GroveDb {
         Ok(()).wrap_with_cost(cost)
     }
 
+    /// Checks that the subtree targeted by every operation either exists or is
+    /// inserted by the batch itself. All missing subtrees are reported in a
+    /// single error.
+    fn verify_paths_of_operations_exist(
+        &self,
+        ops: &[GroveDbOp],
+        transaction: TransactionArg,
+    ) -> CostResult<(), Error> {
+        let mut cost = OperationCost::default();
+
+        let inserted_trees: HashSet<Vec<Vec<u8>>> = ops
+            .iter()
+            .filter(|op| match &op.op {
+                Op::Insert { element } | Op::Replace { element } => element.is_tree(),
+                Op::InsertTreeWithRootHash { .. } => true,
+                _ => false,
+            })
+            .map(|op| {
+                let mut path = op.path.to_path();
+                path.push(op.key.get_key_clone());
+                path
+            })
+            .collect();
+
+        let mut checked_paths = HashSet::new();
+        let mut missing_paths = Vec::new();
+        for op in ops {
+            let path = op.path.to_path();
+            if inserted_trees.contains(&path) || !checked_paths.insert(path.clone()) {
+                continue;
+            }
+            let subtree_path: SubtreePath<Vec<u8>> = path.as_slice().into();
+            match self
+                .check_subtree_exists_path_not_found(subtree_path, transaction)
+                .unwrap_add_cost(&mut cost)
+            {
+                Ok(()) => {}
+                Err(Error::PathNotFound(_))
+                | Err(Error::PathParentLayerNotFound(_))
+                | Err(Error::InvalidParentLayerPath(_))
+                | Err(Error::CorruptedPath(_)) => missing_paths.push(path),
+                Err(e) => return Err(e).wrap_with_cost(cost),
+            }
+        }
+
+        if missing_paths.is_empty() {
+            Ok(()).wrap_with_cost(cost)
+        } else {
+            // The first missing path is reported, the message lists them all
+            let path = missing_paths[0].clone();
+            let message = format!(
+                "batch operations target subtrees that don't exist: {:?}",
+                missing_paths
+                    .into_iter()
+                    .map(DebugByteVectors)
+                    .collect::<Vec<_>>()
+            );
+            Err(Error::PathNotFound(PathErrorDetail::new(
+                path, None, message,
+            )))
+            .wrap_with_cost(cost)
+        }
+    }
+
     /// Applies batch on GroveDB
     pub fn apply_batch(
         &self,
         ops: Vec<GroveDbOp>,
         batch_apply_options: Option<BatchApplyOptions>,
         transaction: TransactionArg,
+    ) -> CostResult<(), Error> {
+        self.observe_cost(
+            "apply_batch",
+            self.apply_batch_unobserved(ops, batch_apply_options, transaction),
+        )
+    }
+
+    /// Applies batch on GroveDB without reporting its cost to the cost
+    /// observer
+    fn apply_batch_unobserved(
+        &self,
+        ops: Vec<GroveDbOp>,
+        batch_apply_options: Option<BatchApplyOptions>,
+        transaction: TransactionArg,
     ) -> CostResult<(), Error> {
         self.apply_batch_with_element_flags_update(
             ops,
@@ -1717,6 +1883,44 @@ impl GroveDb {
         )
     }
 
+    /// Get the cost [`GroveDb::apply_batch`] would return for `ops` without
+    /// committing them, so a fee can be checked before the batch is applied.
+    /// The batch is applied in a throwaway transaction, or inside of a
+    /// savepoint of `transaction` which is rolled back afterwards.
+    ///
+    /// The projected cost is returned as the value, while the cost of the
+    /// preview itself only includes the reads and hashing done for it.
+    pub fn preview_batch_cost(
+        &self,
+        ops: &[GroveDbOp],
+        transaction: TransactionArg,
+    ) -> CostResult<OperationCost, Error> {
+        let mut projected_cost = OperationCost::default();
+
+        let result = if let Some(tx) = transaction {
+            let savepoint = cost_return_on_error_no_add!(&projected_cost, self.savepoint(tx));
+            let result = self
+                .apply_batch_unobserved(ops.to_vec(), None, Some(tx))
+                .unwrap_add_cost(&mut projected_cost);
+            cost_return_on_error_no_add!(
+                &projected_cost,
+                self.rollback_to_savepoint(tx, savepoint)
+            );
+            result
+        } else {
+            // Dropping the transaction discards the batch
+            let tx = cost_return_on_error_no_add!(&projected_cost, self.try_start_transaction());
+            self.apply_batch_unobserved(ops.to_vec(), None, Some(&tx))
+                .unwrap_add_cost(&mut projected_cost)
+        };
+
+        let cost = OperationCost {
+            storage_cost: Default::default(),
+            ..projected_cost.clone()
+        };
+        result.map(|_| projected_cost).wrap_with_cost(cost)
+    }
+
     /// Applies batch on GroveDB
     pub fn apply_partial_batch(
         &self,
@@ -1896,10 +2100,33 @@ impl GroveDb {
             }
         }
 
+        let validate_paths_first = batch_apply_options
+            .as_ref()
+            .map(|batch_options| batch_options.validate_paths_first)
+            .unwrap_or(false);
+
+        if validate_paths_first {
+            cost_return_on_error!(
+                &mut cost,
+                self.verify_paths_of_operations_exist(&ops, transaction)
+            );
+        }
+
+        let changes = self.change_journal_retention.map(|_| {
+            ops.iter()
+                .map(|op| (op.path.to_path(), op.key.get_key_clone()))
+                .collect()
+        });
+
         // `StorageBatch` allows us to collect operations on different subtrees before
         // execution
         let storage_batch = StorageBatch::new();
 
+        cost_return_on_error!(
+            &mut cost,
+            self.clear_tombstoned_subtrees_of_ops(&ops, transaction, &storage_batch)
+        );
+
         // With the only one difference (if there is a transaction) do the following:
         // 2. If nothing left to do and we were on a non-leaf subtree or we're done with
         //    one subtree and moved to another then add propagation operation to the
@@ -1932,9 +2159,7 @@ impl GroveDb {
             // TODO: compute batch costs
             cost_return_on_error!(
                 &mut cost,
-                self.db
-                    .commit_multi_context_batch(storage_batch, Some(tx))
-                    .map_err(|e| e.into())
+                self.commit_multi_context_batch(storage_batch, changes, Some(tx))
             );
         } else {
             cost_return_on_error!(
@@ -1953,9 +2178,7 @@ impl GroveDb {
             // TODO: compute batch costs
             cost_return_on_error!(
                 &mut cost,
-                self.db
-                    .commit_multi_context_batch(storage_batch, None)
-                    .map_err(|e| e.into())
+                self.commit_multi_context_batch(storage_batch, changes, None)
             );
         }
         Ok(()).wrap_with_cost(cost)
@@ -1994,6 +2217,24 @@ impl GroveDb {
             return Ok(()).wrap_with_cost(cost);
         }
 
+        if transaction.is_none() && self.change_journal_retention.is_some() {
+            // The change journal entry holds the root hash once the write batch is
+            // applied, so it's applied in a transaction the entry can be added to
+            let tx = cost_return_on_error_no_add!(&cost, self.try_start_transaction());
+            cost_return_on_error!(
+                &mut cost,
+                self.apply_partial_batch_with_element_flags_update(
+                    ops,
+                    batch_apply_options,
+                    update_element_flags_function,
+                    split_removal_bytes_function,
+                    add_on_operations,
+                    Some(&tx),
+                )
+            );
+            return self.commit_transaction(tx).add_cost(cost);
+        }
+
         let mut batch_apply_options = batch_apply_options.unwrap_or_default();
         if batch_apply_options.batch_pause_height.is_none() {
             // we default to pausing at the root tree, which is the most common case
@@ -2016,10 +2257,22 @@ impl GroveDb {
             }
         }
 
+        if batch_apply_options.validate_paths_first {
+            cost_return_on_error!(
+                &mut cost,
+                self.verify_paths_of_operations_exist(&ops, transaction)
+            );
+        }
+
         // `StorageBatch` allows us to collect operations on different subtrees before
         // execution
         let storage_batch = StorageBatch::new();
 
+        cost_return_on_error!(
+            &mut cost,
+            self.clear_tombstoned_subtrees_of_ops(&ops, transaction, &storage_batch)
+        );
+
         // With the only one difference (if there is a transaction) do the following:
         // 2. If nothing left to do and we were on a non-leaf subtree or we're done with
         //    one subtree and moved to another then add propagation operation to the
@@ -2031,6 +2284,12 @@ impl GroveDb {
         // 6. Add root leaves save operation to the batch
         // 7. Apply storage_cost batch
         if let Some(tx) = transaction {
+            let mut changes: Option<ChangedKeys> = self.change_journal_retention.map(|_| {
+                ops.iter()
+                    .map(|op| (op.path.to_path(), op.key.get_key_clone()))
+                    .collect()
+            });
+
             let left_over_operations = cost_return_on_error!(
                 &mut cost,
                 self.apply_body(
@@ -2070,6 +2329,13 @@ impl GroveDb {
                 &cost,
                 add_on_operations(&total_current_costs, &left_over_operations)
             );
+            if let Some(changes) = changes.as_mut() {
+                changes.extend(
+                    new_operations
+                        .iter()
+                        .map(|op| (op.path.to_path(), op.key.get_key_clone())),
+                );
+            }
 
             // we are trying to finalize
             batch_apply_options.batch_pause_height = None;
@@ -2112,6 +2378,13 @@ impl GroveDb {
                     .commit_db_write_batch(write_batch, pending_costs, Some(tx))
                     .map_err(|e| e.into())
             );
+
+            // The transaction holds the applied writes, so the journal entry can
+            // only be made now
+            cost_return_on_error!(
+                &mut cost,
+                self.commit_multi_context_batch(StorageBatch::new(), changes, Some(tx))
+            );
         } else {
             let left_over_operations = cost_return_on_error!(
                 &mut cost,
@@ -2407,6 +2680,7 @@ mod tests {
                     disable_operation_consistency_check: true,
                     base_root_storage_is_free: true,
                     batch_pause_height: None,
+                    validate_paths_first: false,
                 }),
                 None
             )
@@ -3157,6 +3431,7 @@ mod tests {
                     disable_operation_consistency_check: false,
                     base_root_storage_is_free: true,
                     batch_pause_height: None,
+                    validate_paths_first: false,
                 }),
                 None
             )
@@ -3196,6 +3471,7 @@ mod tests {
                     deleting_non_empty_trees_returns_error: true,
                     base_root_storage_is_free: true,
                     batch_pause_height: None,
+                    validate_paths_first: false,
                 }),
                 None
             )
@@ -3229,6 +3505,7 @@ mod tests {
                     disable_operation_consistency_check: false,
                     base_root_storage_is_free: true,
                     batch_pause_height: None,
+                    validate_paths_first: false,
                 }),
                 None
             )
@@ -3236,6 +3513,120 @@ mod tests {
             .is_err());
     }
 
+    #[test]
+    fn test_batch_validate_paths_first() {
+        let db = make_test_grovedb();
+        let options = BatchApplyOptions {
+            validate_paths_first: true,
+            ..Default::default()
+        };
+        let ops = vec![
+            GroveDbOp::insert_op(
+                vec![TEST_LEAF.to_vec()],
+                b"key1".to_vec(),
+                Element::new_item(b"ayy".to_vec()),
+            ),
+            GroveDbOp::insert_op(
+                vec![TEST_LEAF.to_vec(), b"missing".to_vec()],
+                b"key2".to_vec(),
+                Element::new_item(b"ayy".to_vec()),
+            ),
+            GroveDbOp::insert_op(
+                vec![b"missing_leaf".to_vec()],
+                b"key3".to_vec(),
+                Element::new_item(b"ayy".to_vec()),
+            ),
+        ];
+        match db.apply_batch(ops, Some(options.clone()), None).unwrap() {
+            Err(Error::PathNotFound(detail)) => {
+                assert_eq!(detail.path, vec![TEST_LEAF.to_vec(), b"missing".to_vec()]);
+                assert!(detail.message.contains("str: missing]"));
+                assert!(detail.message.contains("str: missing_leaf]"));
+            }
+            result => panic!("expected missing paths to be reported, got {:?}", result),
+        }
+        assert!(matches!(
+            db.get([TEST_LEAF].as_ref(), b"key1", None).unwrap(),
+            Err(Error::PathKeyNotFound(_))
+        ));
+
+        // Subtrees inserted by the batch itself are valid targets
+        let ops = vec![
+            GroveDbOp::insert_op(
+                vec![TEST_LEAF.to_vec()],
+                b"innertree".to_vec(),
+                Element::empty_tree(),
+            ),
+            GroveDbOp::insert_op(
+                vec![TEST_LEAF.to_vec(), b"innertree".to_vec()],
+                b"key".to_vec(),
+                Element::new_item(b"ayy".to_vec()),
+            ),
+        ];
+        db.apply_batch(ops, Some(options), None)
+            .unwrap()
+            .expect("paths of the batch exist");
+        assert_eq!(
+            db.get([TEST_LEAF, b"innertree"].as_ref(), b"key", None)
+                .unwrap()
+                .expect("successful get"),
+            Element::new_item(b"ayy".to_vec())
+        );
+    }
+
+    #[test]
+    fn test_batch_propagates_shared_ancestors_once() {
+        let per_op_db = make_test_grovedb();
+        let batch_db = make_test_grovedb();
+        let subtrees: [&[u8]; 3] = [b"tree1", b"tree2", b"tree3"];
+        for db in [&per_op_db, &batch_db] {
+            for subtree in subtrees {
+                db.insert(
+                    [TEST_LEAF].as_ref(),
+                    subtree,
+                    Element::empty_tree(),
+                    None,
+                    None,
+                )
+                .unwrap()
+                .expect("successful subtree insert");
+            }
+        }
+
+        let mut ops = Vec::new();
+        let mut per_op_cost = OperationCost::default();
+        for subtree in subtrees {
+            for i in 0u8..3 {
+                per_op_db
+                    .insert(
+                        [TEST_LEAF, subtree].as_ref(),
+                        &[i],
+                        Element::new_item(vec![i]),
+                        None,
+                        None,
+                    )
+                    .unwrap_add_cost(&mut per_op_cost)
+                    .expect("successful insert");
+                ops.push(GroveDbOp::insert_op(
+                    vec![TEST_LEAF.to_vec(), subtree.to_vec()],
+                    vec![i],
+                    Element::new_item(vec![i]),
+                ));
+            }
+        }
+
+        let batch_cost = batch_db
+            .apply_batch(ops, None, None)
+            .cost_as_result()
+            .expect("successful batch apply");
+
+        assert_eq!(
+            batch_db.root_hash(None).unwrap().unwrap(),
+            per_op_db.root_hash(None).unwrap().unwrap()
+        );
+        assert!(batch_cost.hash_node_calls < per_op_cost.hash_node_calls);
+    }
+
     #[test]
     fn test_merk_data_is_deleted() {
         let db = make_test_grovedb();
@@ -3503,7 +3894,157 @@ mod tests {
         ];
         assert!(matches!(
             db.apply_batch(batch, None, None).unwrap(),
-            Err(Error::ReferenceLimit)
+            Err(Error::ReferenceChainTooLong { hops: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn test_dedupe_ops_insert_then_insert_keeps_last() {
+        let ops = vec![
+            GroveDbOp::insert_op(
+                vec![TEST_LEAF.to_vec()],
+                b"key".to_vec(),
+                Element::new_item(b"first".to_vec()),
+            ),
+            GroveDbOp::insert_op(
+                vec![TEST_LEAF.to_vec()],
+                b"key".to_vec(),
+                Element::new_item(b"second".to_vec()),
+            ),
+        ];
+        let deduped = GroveDbOp::dedupe_ops(ops).expect("expected to dedupe ops");
+        assert_eq!(
+            deduped,
+            vec![GroveDbOp::insert_op(
+                vec![TEST_LEAF.to_vec()],
+                b"key".to_vec(),
+                Element::new_item(b"second".to_vec()),
+            )]
+        );
+
+        let db = make_test_grovedb();
+        db.apply_batch(deduped, None, None)
+            .unwrap()
+            .expect("expected to apply deduped batch");
+        assert_eq!(
+            db.get([TEST_LEAF].as_ref(), b"key", None)
+                .unwrap()
+                .expect("expected to get item"),
+            Element::new_item(b"second".to_vec())
+        );
+    }
+
+    #[test]
+    fn test_dedupe_ops_insert_then_delete_keeps_delete() {
+        let ops = vec![
+            GroveDbOp::insert_op(
+                vec![TEST_LEAF.to_vec()],
+                b"key".to_vec(),
+                Element::new_item(b"value".to_vec()),
+            ),
+            GroveDbOp::delete_op(vec![TEST_LEAF.to_vec()], b"key".to_vec()),
+        ];
+        let deduped = GroveDbOp::dedupe_ops(ops).expect("expected to dedupe ops");
+        assert_eq!(
+            deduped,
+            vec![GroveDbOp::delete_op(
+                vec![TEST_LEAF.to_vec()],
+                b"key".to_vec()
+            )]
+        );
+    }
+
+    #[test]
+    fn test_dedupe_ops_keeps_unrelated_ops() {
+        let ops = vec![
+            GroveDbOp::insert_op(
+                vec![TEST_LEAF.to_vec()],
+                b"key".to_vec(),
+                Element::new_item(b"value".to_vec()),
+            ),
+            GroveDbOp::delete_op(vec![ANOTHER_TEST_LEAF.to_vec()], b"key".to_vec()),
+        ];
+        let deduped = GroveDbOp::dedupe_ops(ops.clone()).expect("expected to dedupe ops");
+        assert_eq!(deduped, ops);
+    }
+
+    #[test]
+    fn test_dedupe_ops_rejects_patch_after_insert() {
+        let ops = vec![
+            GroveDbOp::insert_op(
+                vec![TEST_LEAF.to_vec()],
+                b"key".to_vec(),
+                Element::new_item(b"value".to_vec()),
+            ),
+            GroveDbOp::patch_op(
+                vec![TEST_LEAF.to_vec()],
+                b"key".to_vec(),
+                Element::new_item(b"longer value".to_vec()),
+                7,
+            ),
+        ];
+        assert!(matches!(
+            GroveDbOp::dedupe_ops(ops),
+            Err(Error::InvalidBatchOperation(_))
         ));
     }
+
+    #[test]
+    fn test_preview_batch_cost_matches_apply_batch() {
+        let db = make_test_grovedb();
+        db.insert(
+            [TEST_LEAF].as_ref(),
+            b"existing",
+            Element::new_item(b"ayy".to_vec()),
+            None,
+            None,
+        )
+        .unwrap()
+        .expect("successful insert");
+        let root_hash = db.root_hash(None).unwrap().unwrap();
+
+        let ops = vec![
+            GroveDbOp::insert_op(
+                vec![TEST_LEAF.to_vec()],
+                b"new".to_vec(),
+                Element::new_item(b"value".to_vec()),
+            ),
+            GroveDbOp::insert_op(
+                vec![ANOTHER_TEST_LEAF.to_vec()],
+                b"tree".to_vec(),
+                Element::empty_tree(),
+            ),
+            GroveDbOp::delete_op(vec![TEST_LEAF.to_vec()], b"existing".to_vec()),
+        ];
+
+        let previewed_cost = db
+            .preview_batch_cost(&ops, None)
+            .unwrap()
+            .expect("expected to preview the batch cost");
+        assert_eq!(db.root_hash(None).unwrap().unwrap(), root_hash);
+        assert!(db
+            .get([TEST_LEAF].as_ref(), b"existing", None)
+            .unwrap()
+            .is_ok());
+
+        let tx = db.start_transaction();
+        assert_eq!(
+            db.preview_batch_cost(&ops, Some(&tx))
+                .unwrap()
+                .expect("expected to preview the batch cost"),
+            previewed_cost
+        );
+        assert!(db
+            .get([TEST_LEAF].as_ref(), b"new", Some(&tx))
+            .unwrap()
+            .is_err());
+        drop(tx);
+
+        let applied_cost = db
+            .apply_batch(ops, None, None)
+            .cost_as_result()
+            .expect("expected to apply the batch");
+        assert_eq!(previewed_cost, applied_cost);
+        assert_ne!(db.root_hash(None).unwrap().unwrap(), root_hash);
+    }
 }
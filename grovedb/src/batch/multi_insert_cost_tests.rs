@@ -215,7 +215,8 @@ mod tests {
         // 4 bytes for the key
         // 1 byte for key_size (required space for 36)
 
-        // Value -> 38
+        // Value -> 39
+        //   1 for the element format version
         //   1 for the flag option (but no flags)
         //   1 for the enum type
         //   1 for empty tree value
@@ -231,7 +232,7 @@ mod tests {
         // Child Heights 2
         // Sum 1
 
-        // Total (37 + 38 + 40) * 2 = 230
+        // Total (37 + 39 + 40) * 2 = 232
 
         // Hashes
         // 2 trees
@@ -241,7 +242,7 @@ mod tests {
             OperationCost {
                 seek_count: 4,
                 storage_cost: StorageCost {
-                    added_bytes: 230,
+                    added_bytes: 232,
                     replaced_bytes: 0,
                     removed_bytes: NoStorageRemoval,
                 },
@@ -274,7 +275,8 @@ mod tests {
         // 4 bytes for the key
         // 1 byte for key_size (required space for 36)
 
-        // Value -> 37
+        // Value -> 39
+        //   1 for the element format version
         //   1 for the flag option (but no flags)
         //   1 for the enum type
         //   1 for empty tree value
@@ -290,7 +292,7 @@ mod tests {
         // Child Heights 2
         // Sum 1
 
-        // Total (37 + 38 + 40) * 2 = 230
+        // Total (37 + 39 + 40) * 2 = 232
 
         // Hashes
         // 2 trees
@@ -302,7 +304,7 @@ mod tests {
             OperationCost {
                 seek_count: 4,
                 storage_cost: StorageCost {
-                    added_bytes: 230,
+                    added_bytes: 232,
                     replaced_bytes: 0,
                     removed_bytes: NoStorageRemoval,
                 },
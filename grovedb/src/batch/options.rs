@@ -53,6 +53,9 @@ pub struct BatchApplyOptions {
     /// At what height do we want to pause applying batch operations
     /// Most of the time this should be not set
     pub batch_pause_height: Option<u8>,
+    /// Check that the subtree of every operation exists, or is inserted by the
+    /// batch, before anything is applied
+    pub validate_paths_first: bool,
 }
 
 #[cfg(feature = "full")]
@@ -66,6 +69,7 @@ impl Default for BatchApplyOptions {
             disable_operation_consistency_check: false,
             base_root_storage_is_free: true,
             batch_pause_height: None,
+            validate_paths_first: false,
         }
     }
 }
@@ -89,6 +93,7 @@ impl BatchApplyOptions {
             deleting_non_empty_trees_returns_error: self.deleting_non_empty_trees_returns_error,
             base_root_storage_is_free: self.base_root_storage_is_free,
             validate_tree_at_path_exists: false,
+            defer_child_cleanup: false,
         }
     }
 
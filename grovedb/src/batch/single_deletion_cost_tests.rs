@@ -59,14 +59,15 @@ mod tests {
             .cost_as_result()
             .expect("expected to delete successfully");
 
-        // Explanation for 113 storage_written_bytes
+        // Explanation for 114 storage_written_bytes
 
         // Key -> 37 bytes
         // 32 bytes for the key prefix
         // 4 bytes for the key
         // 1 byte for key_size (required space for 36)
 
-        // Value -> 37
+        // Value -> 38
+        //   1 for the element format version
         //   1 for the flag option (but no flags)
         //   1 for the enum type
         //   1 for empty tree value
@@ -80,7 +81,7 @@ mod tests {
         // Key Length 1
         // Child Heights 2
         // Sum 1
-        // Total 37 + 37 + 39 = 113
+        // Total 37 + 38 + 39 = 114
 
         assert_eq!(
             insertion_cost.storage_cost.added_bytes,
@@ -176,14 +177,15 @@ mod tests {
             .cost_as_result()
             .expect("expected to delete successfully");
 
-        // Explanation for 113 storage_written_bytes
+        // Explanation for 114 storage_written_bytes
 
         // Key -> 37 bytes
         // 32 bytes for the key prefix
         // 4 bytes for the key
         // 1 byte for key_size (required space for 36)
 
-        // Value -> 37
+        // Value -> 38
+        //   1 for the element format version
         //   1 for the flag option (but no flags)
         //   1 for the enum type
         //   1 for empty tree value
@@ -197,7 +199,7 @@ mod tests {
         // Key Length 1
         // Child Heights 2
         // Sum 1
-        // Total 37 + 37 + 39 = 113
+        // Total 37 + 38 + 39 = 114
 
         assert_eq!(
             insertion_cost.storage_cost.added_bytes,
@@ -324,7 +326,8 @@ mod tests {
         // 4 bytes for the key
         // 1 byte for key_size (required space for 36)
 
-        // Value -> 42
+        // Value -> 43
+        //   1 for the element format version
         //   1 for the flag option (but no flags)
         //   1 for the flags size
         //   3 bytes for flags
@@ -342,9 +345,9 @@ mod tests {
         // Child Heights 2
         // Sum 1
 
-        // Total 37 + 42 + 40 = 119
+        // Total 37 + 43 + 40 = 120
 
-        assert_eq!(insertion_cost.storage_cost.added_bytes, 119);
+        assert_eq!(insertion_cost.storage_cost.added_bytes, 120);
         assert_eq!(
             insertion_cost.storage_cost.added_bytes,
             non_batch_cost
@@ -413,7 +416,8 @@ mod tests {
         // 4 bytes for the key
         // 1 byte for key_size (required space for 36)
 
-        // Value -> 41
+        // Value -> 42
+        //   1 for the element format version
         //   1 for the flag option (but no flags)
         //   1 for the flags size
         //   2 bytes for flags
@@ -430,9 +434,9 @@ mod tests {
         // Key Length 1
         // Child Heights 2
         // Sum 1
-        // Total 37 + 41 + 40 = 118
+        // Total 37 + 42 + 40 = 119
 
-        assert_eq!(insertion_cost.storage_cost.added_bytes, 118);
+        assert_eq!(insertion_cost.storage_cost.added_bytes, 119);
         assert_eq!(
             insertion_cost.storage_cost.added_bytes,
             non_batch_cost
@@ -569,7 +573,8 @@ mod tests {
         // 4 bytes for the key
         // 1 byte for key_size (required space for 36)
 
-        // Value -> 42
+        // Value -> 43
+        //   1 for the element format version
         //   1 for the flag option
         //   1 for flags size
         //   3 for flag bytes
@@ -586,9 +591,9 @@ mod tests {
         // Key Length 1
         // Child Heights 2
         // Sum 1
-        // Total 37 + 42 + 40 = 119
+        // Total 37 + 43 + 40 = 120
 
-        assert_eq!(insertion_cost.storage_cost.added_bytes, 119);
+        assert_eq!(insertion_cost.storage_cost.added_bytes, 120);
 
         assert_eq!(
             insertion_cost.storage_cost.added_bytes,
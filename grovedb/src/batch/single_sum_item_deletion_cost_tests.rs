@@ -144,7 +144,7 @@ mod tests {
             .cost_as_result()
             .expect("expected to delete successfully");
 
-        assert_eq!(insertion_cost.storage_cost.added_bytes, 128);
+        assert_eq!(insertion_cost.storage_cost.added_bytes, 129);
         assert_eq!(
             insertion_cost.storage_cost.added_bytes,
             non_batch_cost
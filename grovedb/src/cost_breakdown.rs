@@ -0,0 +1,177 @@
+// MIT LICENSE
+//
+// Copyright (c) 2021 Dash Core Group
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Cost breakdown
+//! Groups the fields of an `OperationCost` into read, write and compute costs
+
+#[cfg(feature = "full")]
+use grovedb_costs::{storage_cost::StorageCost, OperationCost};
+
+#[cfg(feature = "full")]
+use crate::GroveDb;
+
+#[cfg(feature = "full")]
+/// Costs of reading from storage
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ReadCost {
+    /// How many storage seeks were done
+    pub seek_count: u16,
+    /// How many bytes were loaded from storage
+    pub loaded_bytes: u32,
+}
+
+#[cfg(feature = "full")]
+/// Operation cost split into semantic buckets
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct CostBreakdown {
+    read: ReadCost,
+    write: StorageCost,
+    hash_node_calls: u32,
+}
+
+#[cfg(feature = "full")]
+impl CostBreakdown {
+    /// Costs of reading from storage: seeks and loaded bytes
+    pub fn read(&self) -> &ReadCost {
+        &self.read
+    }
+
+    /// Costs of writing to storage: added, replaced and removed bytes
+    pub fn write(&self) -> &StorageCost {
+        &self.write
+    }
+
+    /// Compute costs: how many times node hashing was done
+    pub fn hash_node_calls(&self) -> u32 {
+        self.hash_node_calls
+    }
+
+    /// Total bytes written to storage, both added and replaced
+    pub fn written_bytes(&self) -> u32 {
+        self.write.added_bytes + self.write.replaced_bytes
+    }
+
+    /// Total bytes removed from storage
+    pub fn removed_bytes(&self) -> u32 {
+        self.write.removed_bytes.total_removed_bytes()
+    }
+}
+
+#[cfg(feature = "full")]
+impl From<&OperationCost> for CostBreakdown {
+    fn from(cost: &OperationCost) -> Self {
+        CostBreakdown {
+            read: ReadCost {
+                seek_count: cost.seek_count,
+                loaded_bytes: cost.storage_loaded_bytes,
+            },
+            write: cost.storage_cost.clone(),
+            hash_node_calls: cost.hash_node_calls,
+        }
+    }
+}
+
+#[cfg(feature = "full")]
+impl From<CostBreakdown> for OperationCost {
+    fn from(breakdown: CostBreakdown) -> Self {
+        OperationCost {
+            seek_count: breakdown.read.seek_count,
+            storage_cost: breakdown.write,
+            storage_loaded_bytes: breakdown.read.loaded_bytes,
+            hash_node_calls: breakdown.hash_node_calls,
+        }
+    }
+}
+
+#[cfg(feature = "full")]
+impl GroveDb {
+    /// Split an operation cost into read, write and compute costs
+    pub fn categorize_cost(cost: &OperationCost) -> CostBreakdown {
+        cost.into()
+    }
+}
+
+#[cfg(feature = "full")]
+#[cfg(test)]
+mod tests {
+    use grovedb_costs::{
+        storage_cost::{removal::StorageRemovedBytes::BasicStorageRemoval, StorageCost},
+        OperationCost,
+    };
+    use pretty_assertions::assert_eq;
+
+    use crate::{
+        cost_breakdown::ReadCost,
+        tests::{common::EMPTY_PATH, make_empty_grovedb},
+        Element, GroveDb,
+    };
+
+    #[test]
+    fn test_categorize_delete_one_item_cost() {
+        let db = make_empty_grovedb();
+        let tx = db.start_transaction();
+
+        db.insert(
+            EMPTY_PATH,
+            b"key1",
+            Element::new_item(b"cat".to_vec()),
+            None,
+            Some(&tx),
+        )
+        .unwrap()
+        .expect("expected to insert");
+
+        let cost = db
+            .delete(EMPTY_PATH, b"key1", None, Some(&tx))
+            .cost_as_result()
+            .expect("expected to delete");
+
+        let breakdown = GroveDb::categorize_cost(&cost);
+
+        assert_eq!(
+            breakdown.read(),
+            &ReadCost {
+                seek_count: 6,
+                loaded_bytes: 156,
+            }
+        );
+        assert_eq!(
+            breakdown.write(),
+            &StorageCost {
+                added_bytes: 0,
+                replaced_bytes: 0,
+                removed_bytes: BasicStorageRemoval(150)
+            }
+        );
+        assert_eq!(breakdown.written_bytes(), 0);
+        assert_eq!(breakdown.removed_bytes(), 150);
+        assert_eq!(breakdown.hash_node_calls(), 0);
+
+        assert_eq!(OperationCost::from(breakdown), cost);
+    }
+}
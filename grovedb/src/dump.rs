@@ -0,0 +1,442 @@
+// MIT LICENSE
+//
+// Copyright (c) 2021 Dash Core Group
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Dump
+//! Export of a whole GroveDb into a portable stream and import of such a
+//! stream into an empty GroveDb.
+//!
+//! A dump starts with a magic and a format version followed by records, each
+//! made of a tag and length-prefixed fields: the root key of the root subtree,
+//! every Merk node of every subtree along with its subtree path and finally
+//! the root hash. Merk nodes are copied as stored, so the imported GroveDb has
+//! the same tree shapes and therefore the same root hash, elements, flags and
+//! references. The import fails if it ends up with another root hash.
+//! Auxiliary data and metadata are not part of a dump.
+
+use std::io::{Read, Write};
+
+use grovedb_costs::{
+    cost_return_on_error, cost_return_on_error_no_add, CostResult, CostsExt, OperationCost,
+};
+use grovedb_merk::ROOT_KEY_KEY;
+use grovedb_path::SubtreePath;
+use grovedb_storage::{RawIterator, Storage, StorageBatch, StorageContext};
+use integer_encoding::{VarIntReader, VarIntWriter};
+
+use crate::{
+    helpers::raw_decode, util::storage_context_optional_tx, CryptoHash, Error, GroveDb,
+    TransactionArg,
+};
+
+const DUMP_MAGIC: &[u8] = b"GROVEDUMP";
+const DUMP_VERSION: u32 = 1;
+
+/// Record holding the root key of the root subtree
+const RECORD_ROOT_KEY: u8 = 0;
+/// Record holding a subtree path, a key and the Merk node stored under it
+const RECORD_NODE: u8 = 1;
+/// Last record, holding the root hash of the dumped GroveDb
+const RECORD_ROOT_HASH: u8 = 2;
+
+impl GroveDb {
+    /// Writes every subtree and element of GroveDb into `writer` and returns
+    /// the number of Merk nodes written. The dump can be loaded into an empty
+    /// GroveDb with [`GroveDb::import_dump`].
+    pub fn export_dump<W: Write>(
+        &self,
+        mut writer: W,
+        transaction: TransactionArg,
+    ) -> CostResult<u64, Error> {
+        let mut cost = OperationCost::default();
+        let mut entries = 0;
+
+        cost_return_on_error_no_add!(&cost, write_header(&mut writer));
+        if let Some(root_key) = cost_return_on_error!(&mut cost, self.base_root_key(transaction)) {
+            cost_return_on_error_no_add!(
+                &cost,
+                write_tag(&mut writer, RECORD_ROOT_KEY)
+                    .and_then(|_| write_bytes(&mut writer, &root_key))
+            );
+        }
+
+        let mut queue: Vec<Vec<Vec<u8>>> = vec![vec![]];
+        while let Some(path) = queue.pop() {
+            let subtree_path: SubtreePath<Vec<u8>> = path.as_slice().into();
+            storage_context_optional_tx!(self.db, subtree_path, None, transaction, storage, {
+                let storage = storage.unwrap_add_cost(&mut cost);
+                let mut raw_iter = storage.raw_iter();
+                raw_iter.seek_to_first().unwrap_add_cost(&mut cost);
+                while raw_iter.valid().unwrap_add_cost(&mut cost) {
+                    let key = raw_iter.key().unwrap_add_cost(&mut cost);
+                    let value = raw_iter.value().unwrap_add_cost(&mut cost);
+                    if let (Some(key), Some(value)) = (key, value) {
+                        let element = cost_return_on_error_no_add!(&cost, raw_decode(value));
+                        cost_return_on_error_no_add!(
+                            &cost,
+                            write_node(&mut writer, &path, key, value)
+                        );
+                        entries += 1;
+                        if element.is_tree() {
+                            let mut child_path = path.clone();
+                            child_path.push(key.to_vec());
+                            queue.push(child_path);
+                        }
+                    }
+                    raw_iter.next().unwrap_add_cost(&mut cost);
+                }
+            })
+        }
+
+        let root_hash = cost_return_on_error!(&mut cost, self.root_hash(transaction));
+        cost_return_on_error_no_add!(
+            &cost,
+            write_tag(&mut writer, RECORD_ROOT_HASH)
+                .and_then(|_| write_bytes(&mut writer, &root_hash))
+        );
+
+        cost_return_on_error_no_add!(
+            &cost,
+            writer
+                .flush()
+                .map_err(|_| Error::InternalError("unable to write dump"))
+        );
+        Ok(entries).wrap_with_cost(cost)
+    }
+
+    /// Loads a dump written by [`GroveDb::export_dump`]. GroveDb must be empty;
+    /// the dump is applied in a single transaction, so nothing is written if
+    /// it turns out to be malformed or the imported data doesn't have the
+    /// root hash the dump was made with.
+    pub fn import_dump<R: Read>(&self, mut reader: R) -> CostResult<(), Error> {
+        let mut cost = OperationCost::default();
+
+        if cost_return_on_error!(&mut cost, self.base_root_key(None)).is_some() {
+            return Err(Error::InvalidInput(
+                "a dump can only be imported into an empty GroveDb",
+            ))
+            .wrap_with_cost(cost);
+        }
+        cost_return_on_error_no_add!(&cost, read_header(&mut reader));
+
+        let tx = cost_return_on_error_no_add!(&cost, self.try_start_transaction());
+        let mut expected_root_hash = None;
+        let mut changes = self.change_journal_retention.map(|_| Vec::new());
+        while let Some(tag) = cost_return_on_error_no_add!(&cost, read_tag(&mut reader)) {
+            if expected_root_hash.is_some() {
+                return Err(Error::CorruptedData(
+                    "malformed dump: records after the root hash".to_owned(),
+                ))
+                .wrap_with_cost(cost);
+            }
+            match tag {
+                RECORD_ROOT_KEY => {
+                    let root_key = cost_return_on_error_no_add!(&cost, read_bytes(&mut reader));
+                    let storage = self
+                        .db
+                        .get_immediate_storage_context(SubtreePath::empty(), &tx)
+                        .unwrap_add_cost(&mut cost);
+                    cost_return_on_error!(
+                        &mut cost,
+                        storage
+                            .put_root(ROOT_KEY_KEY, &root_key, None)
+                            .map_err(Into::into)
+                    );
+                }
+                RECORD_NODE => {
+                    let (path, key, value) =
+                        cost_return_on_error_no_add!(&cost, read_node(&mut reader));
+                    cost_return_on_error_no_add!(&cost, raw_decode(&value));
+                    let storage = self
+                        .db
+                        .get_immediate_storage_context(SubtreePath::from(path.as_slice()), &tx)
+                        .unwrap_add_cost(&mut cost);
+                    cost_return_on_error!(
+                        &mut cost,
+                        storage.put(&key, &value, None, None).map_err(Into::into)
+                    );
+                    if let Some(changes) = changes.as_mut() {
+                        changes.push((path, key));
+                    }
+                }
+                RECORD_ROOT_HASH => {
+                    let root_hash = cost_return_on_error_no_add!(&cost, read_bytes(&mut reader));
+                    expected_root_hash = Some(cost_return_on_error_no_add!(
+                        &cost,
+                        CryptoHash::try_from(root_hash).map_err(|_| Error::CorruptedData(
+                            "malformed dump: invalid root hash".to_owned()
+                        ))
+                    ));
+                }
+                _ => {
+                    return Err(Error::CorruptedData(format!(
+                        "malformed dump: unknown record tag {}",
+                        tag
+                    )))
+                    .wrap_with_cost(cost)
+                }
+            }
+        }
+
+        let Some(expected_root_hash) = expected_root_hash else {
+            return Err(Error::CorruptedData(
+                "malformed dump: root hash is missing".to_owned(),
+            ))
+            .wrap_with_cost(cost);
+        };
+        let root_hash = cost_return_on_error!(&mut cost, self.root_hash(Some(&tx)));
+        if root_hash != expected_root_hash {
+            return Err(Error::CorruptedData(format!(
+                "imported dump has root hash {}, expected {}",
+                hex::encode(root_hash),
+                hex::encode(expected_root_hash)
+            )))
+            .wrap_with_cost(cost);
+        }
+
+        cost_return_on_error!(
+            &mut cost,
+            self.commit_multi_context_batch(StorageBatch::new(), changes, Some(&tx))
+        );
+        self.commit_transaction(tx).add_cost(cost)
+    }
+
+    /// Root key of the root subtree as stored, `None` if GroveDb is empty
+    fn base_root_key(&self, transaction: TransactionArg) -> CostResult<Option<Vec<u8>>, Error> {
+        storage_context_optional_tx!(self.db, SubtreePath::empty(), None, transaction, storage, {
+            storage
+                .flat_map(|s| s.get_root(ROOT_KEY_KEY))
+                .map_err(Into::into)
+        })
+    }
+}
+
+fn write_header<W: Write>(writer: &mut W) -> Result<(), Error> {
+    writer
+        .write_all(DUMP_MAGIC)
+        .and_then(|_| writer.write_varint(DUMP_VERSION))
+        .map(|_| ())
+        .map_err(|_| Error::InternalError("unable to write dump"))
+}
+
+fn write_tag<W: Write>(writer: &mut W, tag: u8) -> Result<(), Error> {
+    writer
+        .write_all(&[tag])
+        .map_err(|_| Error::InternalError("unable to write dump"))
+}
+
+fn write_bytes<W: Write>(writer: &mut W, bytes: &[u8]) -> Result<(), Error> {
+    writer
+        .write_varint(bytes.len() as u64)
+        .and_then(|_| writer.write_all(bytes))
+        .map_err(|_| Error::InternalError("unable to write dump"))
+}
+
+fn write_node<W: Write>(
+    writer: &mut W,
+    path: &[Vec<u8>],
+    key: &[u8],
+    value: &[u8],
+) -> Result<(), Error> {
+    write_tag(writer, RECORD_NODE)?;
+    writer
+        .write_varint(path.len() as u64)
+        .map_err(|_| Error::InternalError("unable to write dump"))?;
+    for segment in path {
+        write_bytes(writer, segment)?;
+    }
+    write_bytes(writer, key)?;
+    write_bytes(writer, value)
+}
+
+fn read_header<R: Read>(reader: &mut R) -> Result<(), Error> {
+    let mut magic = [0; DUMP_MAGIC.len()];
+    reader.read_exact(&mut magic).map_err(malformed)?;
+    if magic != DUMP_MAGIC {
+        return Err(Error::CorruptedData(
+            "malformed dump: not a GroveDb dump".to_owned(),
+        ));
+    }
+    let version: u32 = reader.read_varint().map_err(malformed)?;
+    if version != DUMP_VERSION {
+        return Err(Error::CorruptedData(format!(
+            "malformed dump: unsupported version {}",
+            version
+        )));
+    }
+    Ok(())
+}
+
+/// Reads the tag of the next record, `None` at the end of the dump
+fn read_tag<R: Read>(reader: &mut R) -> Result<Option<u8>, Error> {
+    let mut tag = [0];
+    match reader.read(&mut tag).map_err(malformed)? {
+        0 => Ok(None),
+        _ => Ok(Some(tag[0])),
+    }
+}
+
+fn read_bytes<R: Read>(reader: &mut R) -> Result<Vec<u8>, Error> {
+    let len: u64 = reader.read_varint().map_err(malformed)?;
+    let mut bytes = Vec::new();
+    reader
+        .take(len)
+        .read_to_end(&mut bytes)
+        .map_err(malformed)?;
+    if bytes.len() as u64 != len {
+        return Err(Error::CorruptedData(
+            "malformed dump: record is truncated".to_owned(),
+        ));
+    }
+    Ok(bytes)
+}
+
+fn read_node<R: Read>(reader: &mut R) -> Result<(Vec<Vec<u8>>, Vec<u8>, Vec<u8>), Error> {
+    let segments_count: u64 = reader.read_varint().map_err(malformed)?;
+    let path = (0..segments_count)
+        .map(|_| read_bytes(reader))
+        .collect::<Result<Vec<_>, _>>()?;
+    let key = read_bytes(reader)?;
+    let value = read_bytes(reader)?;
+    Ok((path, key, value))
+}
+
+fn malformed(e: std::io::Error) -> Error {
+    Error::CorruptedData(format!("malformed dump: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use crate::{
+        reference_path::ReferencePathType,
+        tests::{make_deep_tree, make_empty_grovedb, make_test_grovedb, TEST_LEAF},
+        Element, Error,
+    };
+
+    #[test]
+    fn test_dump_round_trip() {
+        let db = make_deep_tree();
+        db.insert(
+            [TEST_LEAF].as_ref(),
+            b"flagged",
+            Element::new_item_with_flags(b"value".to_vec(), Some(vec![1, 2, 3])),
+            None,
+            None,
+        )
+        .unwrap()
+        .expect("successful insert");
+        let reference = Element::new_reference(ReferencePathType::AbsolutePathReference(vec![
+            TEST_LEAF.to_vec(),
+            b"flagged".to_vec(),
+        ]));
+        db.insert([TEST_LEAF].as_ref(), b"ref", reference.clone(), None, None)
+            .unwrap()
+            .expect("successful insert");
+
+        let mut dump = Vec::new();
+        let entries = db
+            .export_dump(&mut dump, None)
+            .unwrap()
+            .expect("successful export");
+        assert!(entries > 0);
+
+        let imported = make_empty_grovedb();
+        imported
+            .import_dump(dump.as_slice())
+            .unwrap()
+            .expect("successful import");
+
+        assert_eq!(
+            imported.root_hash(None).unwrap().unwrap(),
+            db.root_hash(None).unwrap().unwrap()
+        );
+        assert_eq!(
+            imported
+                .get_raw([TEST_LEAF].as_ref().into(), b"ref", None)
+                .unwrap()
+                .expect("successful get"),
+            reference
+        );
+        assert_eq!(
+            imported
+                .get([TEST_LEAF].as_ref(), b"ref", None)
+                .unwrap()
+                .expect("successful get"),
+            Element::new_item_with_flags(b"value".to_vec(), Some(vec![1, 2, 3]))
+        );
+        assert_eq!(
+            imported
+                .get([TEST_LEAF, b"innertree"].as_ref(), b"key1", None)
+                .unwrap()
+                .expect("successful get"),
+            db.get([TEST_LEAF, b"innertree"].as_ref(), b"key1", None)
+                .unwrap()
+                .expect("successful get")
+        );
+        assert!(imported.verify_grovedb().is_empty());
+    }
+
+    #[test]
+    fn test_import_dump_rejects_bad_input() {
+        let db = make_test_grovedb();
+        let mut dump = Vec::new();
+        db.export_dump(&mut dump, None)
+            .unwrap()
+            .expect("successful export");
+
+        // GroveDb must be empty
+        assert!(matches!(
+            db.import_dump(dump.as_slice()).unwrap(),
+            Err(Error::InvalidInput(_))
+        ));
+
+        let imported = make_empty_grovedb();
+        assert!(matches!(
+            imported.import_dump(&b"not a dump"[..]).unwrap(),
+            Err(Error::CorruptedData(_))
+        ));
+        assert!(matches!(
+            imported.import_dump(&dump[..dump.len() - 1]).unwrap(),
+            Err(Error::CorruptedData(_))
+        ));
+        // The root hash record is a tag, its length and the hash
+        assert!(matches!(
+            imported.import_dump(&dump[..dump.len() - 34]).unwrap(),
+            Err(Error::CorruptedData(_))
+        ));
+        let mut tampered = dump.clone();
+        *tampered.last_mut().expect("dump is not empty") ^= 1;
+        assert!(matches!(
+            imported.import_dump(tampered.as_slice()).unwrap(),
+            Err(Error::CorruptedData(_))
+        ));
+        assert!(imported.root_hash(None).unwrap().is_ok());
+        assert_eq!(imported.base_root_key(None).unwrap().unwrap(), None);
+    }
+}
@@ -33,7 +33,7 @@
 use crate::{
     element::{MaxReferenceHop, SumValue},
     reference_path::ReferencePathType,
-    Element, ElementFlags,
+    Element, ElementFlags, Error,
 };
 
 impl Element {
@@ -120,6 +120,18 @@ impl Element {
         Element::Reference(reference_path, max_reference_hop, flags)
     }
 
+    #[cfg(feature = "full")]
+    /// Set element to a reference with max hops and flags, checking that the
+    /// reference path can't resolve to an empty path
+    pub fn try_new_reference(
+        reference_path: ReferencePathType,
+        max_reference_hop: MaxReferenceHop,
+        flags: Option<ElementFlags>,
+    ) -> Result<Self, Error> {
+        reference_path.validate()?;
+        Ok(Element::Reference(reference_path, max_reference_hop, flags))
+    }
+
     #[cfg(feature = "full")]
     /// Set element to a tree without flags
     pub fn new_tree(maybe_root_key: Option<Vec<u8>>) -> Self {
@@ -44,7 +44,7 @@ use integer_encoding::VarInt;
 
 use crate::element::{SUM_ITEM_COST_SIZE, SUM_TREE_COST_SIZE, TREE_COST_SIZE};
 #[cfg(feature = "full")]
-use crate::{Element, Error, Hash};
+use crate::{Element, Error, Hash, PathErrorDetail};
 
 impl Element {
     #[cfg(feature = "full")]
@@ -58,9 +58,10 @@ impl Element {
         Self::get_optional(merk, key.as_ref(), allow_cache).map(|result| {
             let value = result?;
             value.ok_or_else(|| {
-                Error::PathKeyNotFound(format!(
-                    "key not found in Merk for get: {}",
-                    hex::encode(key)
+                Error::PathKeyNotFound(PathErrorDetail::new(
+                    vec![],
+                    Some(key.as_ref().to_vec()),
+                    format!("key not found in Merk for get: {}", hex::encode(&key)),
                 ))
             })
         })
@@ -106,9 +107,13 @@ impl Element {
         Self::get_optional_from_storage(storage, key.as_ref()).map(|result| {
             let value = result?;
             value.ok_or_else(|| {
-                Error::PathKeyNotFound(format!(
-                    "key not found in Merk for get from storage: {}",
-                    hex::encode(key)
+                Error::PathKeyNotFound(PathErrorDetail::new(
+                    vec![],
+                    Some(key.as_ref().to_vec()),
+                    format!(
+                        "key not found in Merk for get from storage: {}",
+                        hex::encode(&key)
+                    ),
                 ))
             })
         })
@@ -309,7 +314,7 @@ mod tests {
             OperationCost {
                 seek_count: 1,
                 storage_cost: Default::default(),
-                storage_loaded_bytes: 75,
+                storage_loaded_bytes: 76,
                 hash_node_calls: 0,
             }
         );
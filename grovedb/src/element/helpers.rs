@@ -39,11 +39,11 @@ use grovedb_merk::{
 use integer_encoding::VarInt;
 
 #[cfg(any(feature = "full", feature = "verify"))]
-use crate::{element::SUM_ITEM_COST_SIZE, Element, Error};
+use crate::{element::SUM_ITEM_COST_SIZE, reference_path::ReferencePathType, Element, Error};
 #[cfg(feature = "full")]
 use crate::{
-    element::{SUM_TREE_COST_SIZE, TREE_COST_SIZE},
-    reference_path::{path_from_reference_path_type, ReferencePathType},
+    element::{serialize::ELEMENT_FORMAT_TAG_SIZE, SUM_TREE_COST_SIZE, TREE_COST_SIZE},
+    reference_path::path_from_reference_path_type,
     ElementFlags,
 };
 
@@ -110,6 +110,21 @@ impl Element {
         matches!(self, Element::SumItem(..))
     }
 
+    #[cfg(any(feature = "full", feature = "verify"))]
+    /// Check if the element is a reference
+    pub fn is_reference(&self) -> bool {
+        matches!(self, Element::Reference(..))
+    }
+
+    #[cfg(any(feature = "full", feature = "verify"))]
+    /// Gives the reference path of the Reference element type
+    pub fn as_reference_path(&self) -> Option<&ReferencePathType> {
+        match self {
+            Element::Reference(reference_path, ..) => Some(reference_path),
+            _ => None,
+        }
+    }
+
     #[cfg(feature = "full")]
     /// Get the tree feature type
     pub fn get_feature_type(&self, parent_is_sum_tree: bool) -> Result<TreeFeatureType, Error> {
@@ -155,6 +170,29 @@ impl Element {
         }
     }
 
+    #[cfg(any(feature = "full", feature = "verify"))]
+    /// Check if two elements are of the same type and hold the same payload
+    /// (item bytes or sum value, reference path and max hops, tree root key and
+    /// sum), ignoring element flags
+    pub fn eq_ignoring_flags(&self, other: &Element) -> bool {
+        match (self, other) {
+            (Element::Item(value, _), Element::Item(other_value, _)) => value == other_value,
+            (
+                Element::Reference(path, max_hop, _),
+                Element::Reference(other_path, other_max_hop, _),
+            ) => path == other_path && max_hop == other_max_hop,
+            (Element::Tree(root_key, _), Element::Tree(other_root_key, _)) => {
+                root_key == other_root_key
+            }
+            (Element::SumItem(value, _), Element::SumItem(other_value, _)) => value == other_value,
+            (
+                Element::SumTree(root_key, sum, _),
+                Element::SumTree(other_root_key, other_sum, _),
+            ) => root_key == other_root_key && sum == other_sum,
+            _ => false,
+        }
+    }
+
     #[cfg(feature = "full")]
     /// Get the size of an element in bytes
     #[deprecated]
@@ -203,7 +241,11 @@ impl Element {
     #[cfg(feature = "full")]
     /// Get the required item space
     pub fn required_item_space(len: u32, flag_len: u32) -> u32 {
-        len + len.required_space() as u32 + flag_len + flag_len.required_space() as u32 + 1
+        len + len.required_space() as u32
+            + flag_len
+            + flag_len.required_space() as u32
+            + 1
+            + ELEMENT_FORMAT_TAG_SIZE as u32
     }
 
     #[cfg(feature = "full")]
@@ -218,21 +260,24 @@ impl Element {
         // to follow non absolute references, we need the path they are stored at
         // this information is lost during the aggregation phase.
         Ok(match &self {
-            Element::Reference(reference_path_type, ..) => match reference_path_type {
-                ReferencePathType::AbsolutePathReference(..) => self,
-                _ => {
-                    // Element is a reference and is not absolute.
-                    // build the stored path for this reference
-                    let absolute_path =
-                        path_from_reference_path_type(reference_path_type.clone(), path, key)?;
-                    // return an absolute reference that contains this info
-                    Element::Reference(
-                        ReferencePathType::AbsolutePathReference(absolute_path),
-                        None,
-                        None,
-                    )
+            Element::Reference(reference_path_type, max_reference_hop, _) => {
+                match reference_path_type {
+                    ReferencePathType::AbsolutePathReference(..) => self,
+                    _ => {
+                        // Element is a reference and is not absolute.
+                        // build the stored path for this reference
+                        let absolute_path =
+                            path_from_reference_path_type(reference_path_type.clone(), path, key)?;
+                        // return an absolute reference that contains this info, keeping the hops
+                        // limit it is followed with
+                        Element::Reference(
+                            ReferencePathType::AbsolutePathReference(absolute_path),
+                            *max_reference_hop,
+                            None,
+                        )
+                    }
                 }
-            },
+            }
             _ => self,
         })
     }
@@ -316,3 +361,74 @@ pub fn raw_decode(bytes: &[u8]) -> Result<Element, Error> {
     let element: Element = Element::deserialize(tree.value_as_slice())?;
     Ok(element)
 }
+
+#[cfg(feature = "full")]
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_eq_ignoring_flags() {
+        let item = Element::new_item(b"value".to_vec());
+        let item_with_flags = Element::new_item_with_flags(b"value".to_vec(), Some(vec![1, 2]));
+        let other_item = Element::new_item_with_flags(b"other".to_vec(), Some(vec![1, 2]));
+
+        assert_ne!(item, item_with_flags);
+        assert!(item.eq_ignoring_flags(&item_with_flags));
+        assert!(item_with_flags.eq_ignoring_flags(&item));
+        assert!(!item_with_flags.eq_ignoring_flags(&other_item));
+
+        let tree = Element::new_tree_with_flags(Some(b"root".to_vec()), Some(vec![3]));
+        assert!(tree.eq_ignoring_flags(&Element::new_tree(Some(b"root".to_vec()))));
+        assert!(!tree.eq_ignoring_flags(&Element::new_tree(None)));
+
+        let reference = Element::new_reference_with_flags(
+            ReferencePathType::AbsolutePathReference(vec![b"a".to_vec()]),
+            Some(vec![4]),
+        );
+        assert!(reference.eq_ignoring_flags(&Element::new_reference(
+            ReferencePathType::AbsolutePathReference(vec![b"a".to_vec()])
+        )));
+        assert!(!reference.eq_ignoring_flags(&Element::new_reference(
+            ReferencePathType::AbsolutePathReference(vec![b"b".to_vec()])
+        )));
+
+        assert!(!Element::new_sum_item(5).eq_ignoring_flags(&Element::new_sum_item(6)));
+        assert!(!item.eq_ignoring_flags(&Element::new_sum_item(5)));
+    }
+
+    #[test]
+    fn test_variant_predicates() {
+        let reference_path = ReferencePathType::SiblingReference(b"key".to_vec());
+        let item = Element::new_item(b"value".to_vec());
+        let sum_item = Element::new_sum_item(5);
+        let reference = Element::new_reference(reference_path.clone());
+        let tree = Element::empty_tree();
+        let sum_tree = Element::empty_sum_tree();
+
+        // Both kinds of trees are subtrees
+        assert!(tree.is_tree() && sum_tree.is_tree());
+        assert!(!item.is_tree() && !sum_item.is_tree() && !reference.is_tree());
+
+        assert!(sum_tree.is_sum_tree());
+        assert!(!tree.is_sum_tree() && !item.is_sum_tree() && !sum_item.is_sum_tree());
+        assert!(!reference.is_sum_tree());
+
+        // Sum items are items
+        assert!(item.is_item() && sum_item.is_item());
+        assert!(!reference.is_item() && !tree.is_item() && !sum_tree.is_item());
+
+        assert!(sum_item.is_sum_item());
+        assert!(!item.is_sum_item() && !reference.is_sum_item());
+        assert!(!tree.is_sum_item() && !sum_tree.is_sum_item());
+
+        assert!(reference.is_reference());
+        assert!(!item.is_reference() && !sum_item.is_reference());
+        assert!(!tree.is_reference() && !sum_tree.is_reference());
+
+        assert_eq!(reference.as_reference_path(), Some(&reference_path));
+        for element in [&item, &sum_item, &tree, &sum_tree] {
+            assert_eq!(element.as_reference_path(), None);
+        }
+    }
+}
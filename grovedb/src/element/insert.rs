@@ -93,7 +93,7 @@ impl Element {
                     .map_err(|e| MerkError::ClientCorruptionError(e.to_string()))
             },
         )
-        .map_err(|e| Error::CorruptedData(e.to_string()))
+        .map_err(Error::from_merk_apply)
     }
 
     #[cfg(feature = "full")]
@@ -288,7 +288,7 @@ impl Element {
                     .map_err(|e| MerkError::ClientCorruptionError(e.to_string()))
             },
         )
-        .map_err(|e| Error::CorruptedData(e.to_string()))
+        .map_err(Error::from_merk_apply)
     }
 
     #[cfg(feature = "full")]
@@ -357,7 +357,7 @@ impl Element {
                     .map_err(|e| MerkError::ClientCorruptionError(e.to_string()))
             },
         )
-        .map_err(|e| Error::CorruptedData(e.to_string()))
+        .map_err(Error::from_merk_apply)
     }
 
     #[cfg(feature = "full")]
@@ -44,10 +44,19 @@ pub(crate) mod helpers;
 mod insert;
 #[cfg(any(feature = "full", feature = "verify"))]
 mod query;
+#[cfg(feature = "full")]
+pub(crate) use query::ElementsIterator;
 #[cfg(any(feature = "full", feature = "verify"))]
 mod serialize;
 #[cfg(feature = "full")]
-use core::fmt;
+use core::{
+    fmt,
+    ops::{BitOr, BitOrAssign},
+};
+#[cfg(feature = "full")]
+pub use serialize::ELEMENT_WRITE_FORMAT_VERSION;
+#[cfg(any(feature = "full", feature = "verify"))]
+pub use serialize::{ELEMENT_FORMAT_V0, ELEMENT_FORMAT_V1};
 
 #[cfg(any(feature = "full", feature = "verify"))]
 use grovedb_merk::estimated_costs::SUM_VALUE_EXTRA_COST;
@@ -71,18 +80,19 @@ pub type ElementFlags = Vec<u8>;
 pub type MaxReferenceHop = Option<u8>;
 
 #[cfg(feature = "full")]
-/// The cost of a tree
-pub const TREE_COST_SIZE: u32 = LAYER_COST_SIZE; // 3
+/// The cost of a tree, including the element format version tag
+pub const TREE_COST_SIZE: u32 = LAYER_COST_SIZE; // 4
 #[cfg(any(feature = "full", feature = "verify"))]
 /// The cost of a sum item
 ///
-/// It is 11 because we have 9 bytes for the sum value
+/// It is 12 because we have 9 bytes for the sum value
+/// 1 byte for the element format version tag
 /// 1 byte for the item type
 /// 1 byte for the flags option
-pub const SUM_ITEM_COST_SIZE: u32 = SUM_VALUE_EXTRA_COST + 2; // 11
+pub const SUM_ITEM_COST_SIZE: u32 = SUM_VALUE_EXTRA_COST + 3; // 12
 #[cfg(feature = "full")]
-/// The cost of a sum tree
-pub const SUM_TREE_COST_SIZE: u32 = SUM_LAYER_COST_SIZE; // 12
+/// The cost of a sum tree, including the element format version tag
+pub const SUM_TREE_COST_SIZE: u32 = SUM_LAYER_COST_SIZE; // 13
 
 #[cfg(any(feature = "full", feature = "verify"))]
 /// int 64 sum value
@@ -118,3 +128,58 @@ impl fmt::Debug for Element {
         f.write_str(&String::from_utf8_lossy(&v))
     }
 }
+
+#[cfg(feature = "full")]
+/// Set of element variants used to filter subtree scans, combined with `|`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ElementTypeFilter(u8);
+
+#[cfg(feature = "full")]
+impl ElementTypeFilter {
+    /// Matches `Element::Item`
+    pub const ITEM: Self = Self(1);
+    /// Matches `Element::Reference`
+    pub const REFERENCE: Self = Self(1 << 1);
+    /// Matches `Element::Tree`
+    pub const TREE: Self = Self(1 << 2);
+    /// Matches `Element::SumItem`
+    pub const SUM_ITEM: Self = Self(1 << 3);
+    /// Matches `Element::SumTree`
+    pub const SUM_TREE: Self = Self(1 << 4);
+    /// Matches every element
+    pub const ALL: Self =
+        Self(Self::ITEM.0 | Self::REFERENCE.0 | Self::TREE.0 | Self::SUM_ITEM.0 | Self::SUM_TREE.0);
+
+    /// Check if every variant of `other` is in the filter
+    pub const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Check if the element's variant is in the filter
+    pub fn matches(self, element: &Element) -> bool {
+        let variant = match element {
+            Element::Item(..) => Self::ITEM,
+            Element::Reference(..) => Self::REFERENCE,
+            Element::Tree(..) => Self::TREE,
+            Element::SumItem(..) => Self::SUM_ITEM,
+            Element::SumTree(..) => Self::SUM_TREE,
+        };
+        self.contains(variant)
+    }
+}
+
+#[cfg(feature = "full")]
+impl BitOr for ElementTypeFilter {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+#[cfg(feature = "full")]
+impl BitOrAssign for ElementTypeFilter {
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.0 |= rhs.0;
+    }
+}
@@ -37,7 +37,7 @@ use grovedb_costs::{
 #[cfg(feature = "full")]
 use grovedb_merk::proofs::query::query_item::QueryItem;
 #[cfg(any(feature = "full", feature = "verify"))]
-use grovedb_merk::proofs::Query;
+use grovedb_merk::proofs::{query::SubqueryBranch, Query};
 use grovedb_path::SubtreePath;
 #[cfg(feature = "full")]
 use grovedb_storage::{rocksdb_storage::RocksDbStorage, RawIterator, StorageContext};
@@ -54,7 +54,7 @@ use crate::{
         },
     },
     util::{merk_optional_tx, storage_context_optional_tx},
-    Error, PathQuery, TransactionArg,
+    Error, PathQuery, QueryPlanStep, TransactionArg,
 };
 #[cfg(any(feature = "full", feature = "verify"))]
 use crate::{Element, SizedQuery};
@@ -72,6 +72,7 @@ where
     pub path: &'a [&'a [u8]],
     pub subquery_path: Option<Path>,
     pub subquery: Option<Query>,
+    pub subquery_limit: Option<u16>,
     pub left_to_right: bool,
     pub allow_get_raw: bool,
     pub allow_cache: bool,
@@ -79,6 +80,7 @@ where
     pub results: &'a mut Vec<QueryResultElement>,
     pub limit: &'a mut Option<u16>,
     pub offset: &'a mut Option<u16>,
+    pub max_result_bytes: &'a mut Option<u32>,
 }
 
 impl Element {
@@ -134,7 +136,8 @@ impl Element {
 
     #[cfg(feature = "full")]
     /// Returns a vector of result elements and the number of skipped items
-    /// based on given query
+    /// based on given query. Results stop before the first one that doesn't
+    /// fit into the `max_result_bytes` budget of the query.
     pub fn get_query_apply_function(
         storage: &RocksDbStorage,
         path: &[&[u8]],
@@ -144,6 +147,34 @@ impl Element {
         result_type: QueryResultType,
         transaction: TransactionArg,
         add_element_function: fn(PathQueryPushArgs) -> CostResult<(), Error>,
+    ) -> CostResult<(QueryResultElements, u16), Error> {
+        let mut max_result_bytes = sized_query.max_result_bytes;
+        Self::get_query_apply_function_within_budget(
+            storage,
+            path,
+            sized_query,
+            allow_get_raw,
+            allow_cache,
+            result_type,
+            transaction,
+            add_element_function,
+            &mut max_result_bytes,
+        )
+    }
+
+    #[cfg(feature = "full")]
+    /// Same as [Element::get_query_apply_function], but the result bytes
+    /// budget left is shared with the caller, so subqueries use it up too
+    fn get_query_apply_function_within_budget(
+        storage: &RocksDbStorage,
+        path: &[&[u8]],
+        sized_query: &SizedQuery,
+        allow_get_raw: bool,
+        allow_cache: bool,
+        result_type: QueryResultType,
+        transaction: TransactionArg,
+        add_element_function: fn(PathQueryPushArgs) -> CostResult<(), Error>,
+        max_result_bytes: &mut Option<u32>,
     ) -> CostResult<(QueryResultElements, u16), Error> {
         let mut cost = OperationCost::default();
 
@@ -166,6 +197,7 @@ impl Element {
                         transaction,
                         &mut limit,
                         &mut offset,
+                        max_result_bytes,
                         allow_get_raw,
                         allow_cache,
                         result_type,
@@ -189,6 +221,7 @@ impl Element {
                         transaction,
                         &mut limit,
                         &mut offset,
+                        max_result_bytes,
                         allow_get_raw,
                         allow_cache,
                         result_type,
@@ -298,6 +331,7 @@ impl Element {
             path,
             subquery_path,
             subquery,
+            subquery_limit,
             left_to_right,
             allow_get_raw,
             allow_cache,
@@ -305,8 +339,13 @@ impl Element {
             results,
             limit,
             offset,
+            max_result_bytes,
         } = args;
         if element.is_tree() {
+            let step = cost_return_on_error_no_add!(
+                &cost,
+                QueryPlanStep::from_subquery_branch(subquery_path, subquery)
+            );
             let mut path_vec = path.to_vec();
             let key = cost_return_on_error_no_add!(
                 &cost,
@@ -316,38 +355,55 @@ impl Element {
             );
             path_vec.push(key);
 
-            if let Some(subquery) = subquery {
-                if let Some(subquery_path) = &subquery_path {
+            match &step {
+                QueryPlanStep::Subquery {
+                    path: subquery_path,
+                    query: subquery,
+                } => {
                     path_vec.extend(subquery_path.iter().map(|k| k.as_slice()));
-                }
 
-                let inner_query = SizedQuery::new(subquery, *limit, *offset);
-                let path_vec_owned = path_vec.iter().map(|x| x.to_vec()).collect();
-                let inner_path_query = PathQuery::new(path_vec_owned, inner_query);
+                    // A branch limit caps what this element's subquery may return,
+                    // but never beyond what is left of the global limit
+                    let inner_limit = match (subquery_limit, *limit) {
+                        (Some(branch_limit), Some(global_limit)) => {
+                            Some(branch_limit.min(global_limit))
+                        }
+                        (branch_limit, global_limit) => branch_limit.or(global_limit),
+                    };
+                    let inner_query = SizedQuery::new(subquery.clone(), inner_limit, *offset);
 
-                let (mut sub_elements, skipped) = cost_return_on_error!(
-                    &mut cost,
-                    Element::get_path_query(
-                        storage,
-                        &inner_path_query,
-                        allow_cache,
-                        result_type,
-                        transaction
-                    )
-                );
+                    let (mut sub_elements, skipped) = cost_return_on_error!(
+                        &mut cost,
+                        Element::get_query_apply_function_within_budget(
+                            storage,
+                            path_vec.as_slice(),
+                            &inner_query,
+                            false,
+                            allow_cache,
+                            result_type,
+                            transaction,
+                            Element::path_query_push,
+                            max_result_bytes,
+                        )
+                    );
 
-                if let Some(limit) = limit {
-                    *limit = limit.saturating_sub(sub_elements.len() as u16);
-                }
-                if let Some(offset) = offset {
-                    *offset = offset.saturating_sub(skipped);
+                    if let Some(limit) = limit {
+                        *limit = limit.saturating_sub(sub_elements.len() as u16);
+                    }
+                    // A subquery stopped by the bytes budget stops this query as well
+                    if *max_result_bytes == Some(0) {
+                        *limit = Some(0);
+                    }
+                    if let Some(offset) = offset {
+                        *offset = offset.saturating_sub(skipped);
+                    }
+                    results.append(&mut sub_elements.elements);
                 }
-                results.append(&mut sub_elements.elements);
-            } else if let Some(subquery_path) = subquery_path {
-                if offset.unwrap_or(0) == 0 {
-                    if let Some((subquery_path_last_key, subquery_path_front_keys)) =
-                        &subquery_path.split_last()
-                    {
+                QueryPlanStep::Key {
+                    path: subquery_path_front_keys,
+                    key: subquery_path_last_key,
+                } => {
+                    if offset.unwrap_or(0) == 0 {
                         path_vec.extend(subquery_path_front_keys.iter().map(|k| k.as_slice()));
 
                         let subtree_path: SubtreePath<_> = path_vec.as_slice().into();
@@ -430,46 +486,53 @@ impl Element {
                                 );
                             }
                         }
-                    } else {
-                        return Err(Error::CorruptedCodeExecution(
-                            "subquery_paths can not be empty",
-                        ))
-                        .wrap_with_cost(cost);
-                    };
 
-                    if let Some(limit) = limit {
-                        *limit -= 1;
+                        if let Some(result) = results.last() {
+                            if !Self::take_from_byte_budget(result.element(), max_result_bytes) {
+                                results.pop();
+                                *limit = Some(0);
+                                return Ok(()).wrap_with_cost(cost);
+                            }
+                        }
+
+                        if let Some(limit) = limit {
+                            *limit -= 1;
+                        }
+                    } else if let Some(offset) = offset {
+                        *offset -= 1;
                     }
-                } else if let Some(offset) = offset {
-                    *offset -= 1;
                 }
-            } else if allow_get_raw {
-                cost_return_on_error_no_add!(
-                    &cost,
-                    Element::basic_push(PathQueryPushArgs {
-                        storage,
-                        transaction,
-                        key: Some(key),
-                        element,
-                        path,
-                        subquery_path,
-                        subquery,
-                        left_to_right,
-                        allow_get_raw,
-                        allow_cache,
-                        result_type,
-                        results,
-                        limit,
-                        offset,
-                    })
-                );
-            } else {
-                return Err(Error::InvalidPath(
-                    "you must provide a subquery or a subquery_path when interacting with a Tree \
-                     of trees"
-                        .to_owned(),
-                ))
-                .wrap_with_cost(cost);
+                QueryPlanStep::Element if allow_get_raw => {
+                    cost_return_on_error_no_add!(
+                        &cost,
+                        Element::basic_push(PathQueryPushArgs {
+                            storage,
+                            transaction,
+                            key: Some(key),
+                            element,
+                            path,
+                            subquery_path: None,
+                            subquery: None,
+                            subquery_limit,
+                            left_to_right,
+                            allow_get_raw,
+                            allow_cache,
+                            result_type,
+                            results,
+                            limit,
+                            offset,
+                            max_result_bytes,
+                        })
+                    );
+                }
+                QueryPlanStep::Element => {
+                    return Err(Error::InvalidPath(
+                        "you must provide a subquery or a subquery_path when interacting with a \
+                         Tree of trees"
+                            .to_owned(),
+                    ))
+                    .wrap_with_cost(cost);
+                }
             }
         } else {
             cost_return_on_error_no_add!(
@@ -482,6 +545,7 @@ impl Element {
                     path,
                     subquery_path,
                     subquery,
+                    subquery_limit,
                     left_to_right,
                     allow_get_raw,
                     allow_cache,
@@ -489,6 +553,7 @@ impl Element {
                     results,
                     limit,
                     offset,
+                    max_result_bytes,
                 })
             );
         }
@@ -502,32 +567,32 @@ impl Element {
         sized_query: &SizedQuery,
         key: &[u8],
     ) -> (Option<Path>, Option<Query>) {
+        let subquery_branch = Self::subquery_branch_for_sized_query(sized_query, key);
+        let subquery_path = subquery_branch.subquery_path.clone();
+        let subquery = subquery_branch
+            .subquery
+            .as_ref()
+            .map(|query| *query.clone());
+        (subquery_path, subquery)
+    }
+
+    #[cfg(any(feature = "full", feature = "verify"))]
+    /// Returns the subquery branch that applies to a key: the first matching
+    /// conditional branch, or the default branch otherwise
+    pub fn subquery_branch_for_sized_query<'a>(
+        sized_query: &'a SizedQuery,
+        key: &[u8],
+    ) -> &'a SubqueryBranch {
         if let Some(conditional_subquery_branches) =
             &sized_query.query.conditional_subquery_branches
         {
             for (query_item, subquery_branch) in conditional_subquery_branches {
                 if query_item.contains(key) {
-                    let subquery_path = subquery_branch.subquery_path.clone();
-                    let subquery = subquery_branch
-                        .subquery
-                        .as_ref()
-                        .map(|query| *query.clone());
-                    return (subquery_path, subquery);
+                    return subquery_branch;
                 }
             }
         }
-        let subquery_path = sized_query
-            .query
-            .default_subquery_branch
-            .subquery_path
-            .clone();
-        let subquery = sized_query
-            .query
-            .default_subquery_branch
-            .subquery
-            .as_ref()
-            .map(|query| *query.clone());
-        (subquery_path, subquery)
+        &sized_query.query.default_subquery_branch
     }
 
     #[cfg(feature = "full")]
@@ -541,6 +606,7 @@ impl Element {
         transaction: TransactionArg,
         limit: &mut Option<u16>,
         offset: &mut Option<u16>,
+        max_result_bytes: &mut Option<u32>,
         allow_get_raw: bool,
         allow_cache: bool,
         result_type: QueryResultType,
@@ -566,6 +632,8 @@ impl Element {
                     Ok(element) => {
                         let (subquery_path, subquery) =
                             Self::subquery_paths_and_value_for_sized_query(sized_query, key);
+                        let subquery_limit =
+                            Self::subquery_branch_for_sized_query(sized_query, key).limit;
                         add_element_function(PathQueryPushArgs {
                             storage,
                             transaction,
@@ -574,6 +642,7 @@ impl Element {
                             path,
                             subquery_path,
                             subquery,
+                            subquery_limit,
                             left_to_right: sized_query.query.left_to_right,
                             allow_get_raw,
                             allow_cache,
@@ -581,6 +650,7 @@ impl Element {
                             results,
                             limit,
                             offset,
+                            max_result_bytes,
                         })
                         .unwrap_add_cost(&mut cost)
                     }
@@ -619,6 +689,8 @@ impl Element {
                         .expect("key should exist");
                     let (subquery_path, subquery) =
                         Self::subquery_paths_and_value_for_sized_query(sized_query, key);
+                    let subquery_limit =
+                        Self::subquery_branch_for_sized_query(sized_query, key).limit;
                     cost_return_on_error!(
                         &mut cost,
                         add_element_function(PathQueryPushArgs {
@@ -629,6 +701,7 @@ impl Element {
                             path,
                             subquery_path,
                             subquery,
+                            subquery_limit,
                             left_to_right: sized_query.query.left_to_right,
                             allow_get_raw,
                             allow_cache,
@@ -636,6 +709,7 @@ impl Element {
                             results,
                             limit,
                             offset,
+                            max_result_bytes,
                         })
                     );
                     if sized_query.query.left_to_right {
@@ -661,12 +735,17 @@ impl Element {
             results,
             limit,
             offset,
+            max_result_bytes,
             ..
         } = args;
 
         let element = element.convert_if_reference_to_absolute_reference(path, key)?;
 
         if offset.unwrap_or(0) == 0 {
+            if !Self::take_from_byte_budget(&element, max_result_bytes) {
+                *limit = Some(0);
+                return Ok(());
+            }
             match result_type {
                 QueryResultType::QueryElementResultType => {
                     results.push(QueryResultElement::ElementResultItem(element));
@@ -697,6 +776,26 @@ impl Element {
         Ok(())
     }
 
+    #[cfg(feature = "full")]
+    /// Takes the serialized size of a result element from the bytes budget
+    /// left. If the element doesn't fit, the budget is used up so no later
+    /// result is taken either, and `false` is returned.
+    fn take_from_byte_budget(element: &Element, max_result_bytes: &mut Option<u32>) -> bool {
+        match max_result_bytes {
+            None => true,
+            Some(left) => {
+                let size = element.serialized_size() as u32;
+                if size <= *left {
+                    *left -= size;
+                    true
+                } else {
+                    *left = 0;
+                    false
+                }
+            }
+        }
+    }
+
     #[cfg(feature = "full")]
     /// Iterator
     pub fn iterator<I: RawIterator>(mut raw_iter: I) -> CostContext<ElementsIterator<I>> {
@@ -35,15 +35,60 @@ use bincode::Options;
 #[cfg(any(feature = "full", feature = "verify"))]
 use crate::{Element, Error};
 
+/// Flag set on the leading byte of elements serialized with a format version
+/// tag, the remaining bits hold the version. Untagged version 0 elements start
+/// with their variant index, which never has this bit set.
+#[cfg(any(feature = "full", feature = "verify"))]
+const ELEMENT_FORMAT_TAG: u8 = 0x80;
+
+/// Untagged element format, a plain bincode encoding of the element
+#[cfg(any(feature = "full", feature = "verify"))]
+pub const ELEMENT_FORMAT_V0: u8 = 0;
+
+/// Element format led by a format version tag byte
+#[cfg(any(feature = "full", feature = "verify"))]
+pub const ELEMENT_FORMAT_V1: u8 = 1;
+
+/// Format version [Element::serialize] writes
+#[cfg(feature = "full")]
+pub const ELEMENT_WRITE_FORMAT_VERSION: u8 = ELEMENT_FORMAT_V1;
+
+/// Bytes the format version tag adds to every element written in
+/// [ELEMENT_WRITE_FORMAT_VERSION]. The fixed cost sizes of trees, sum trees
+/// and sum items count it as well.
+#[cfg(feature = "full")]
+pub(crate) const ELEMENT_FORMAT_TAG_SIZE: usize =
+    if ELEMENT_WRITE_FORMAT_VERSION == ELEMENT_FORMAT_V0 {
+        0
+    } else {
+        1
+    };
+
 impl Element {
     #[cfg(feature = "full")]
     /// Serializes self. Returns vector of u8s.
     pub fn serialize(&self) -> Result<Vec<u8>, Error> {
-        bincode::DefaultOptions::default()
+        self.serialize_with_version(ELEMENT_WRITE_FORMAT_VERSION)
+    }
+
+    #[cfg(feature = "full")]
+    /// Serializes self in the given format version
+    pub fn serialize_with_version(&self, version: u8) -> Result<Vec<u8>, Error> {
+        let body = bincode::DefaultOptions::default()
             .with_varint_encoding()
             .reject_trailing_bytes()
             .serialize(self)
-            .map_err(|_| Error::CorruptedData(String::from("unable to serialize element")))
+            .map_err(|_| Error::CorruptedData(String::from("unable to serialize element")))?;
+        match version {
+            ELEMENT_FORMAT_V0 => Ok(body),
+            ELEMENT_FORMAT_V1 => {
+                let mut bytes = Vec::with_capacity(body.len() + 1);
+                bytes.push(ELEMENT_FORMAT_TAG | ELEMENT_FORMAT_V1);
+                bytes.extend(body);
+                Ok(bytes)
+            }
+            _ => Err(Error::NotSupported("unknown element format version")),
+        }
     }
 
     #[cfg(feature = "full")]
@@ -54,15 +99,29 @@ impl Element {
             .reject_trailing_bytes()
             .serialized_size(self)
             .unwrap() as usize // this should not be able to error
+            + ELEMENT_FORMAT_TAG_SIZE
     }
 
     #[cfg(any(feature = "full", feature = "verify"))]
-    /// Deserializes given bytes and sets as self
+    /// Deserializes given bytes and sets as self. Both untagged version 0 data
+    /// and tagged data are accepted. Reference paths aren't validated, stored
+    /// elements have to stay readable whatever they hold.
     pub fn deserialize(bytes: &[u8]) -> Result<Self, Error> {
+        let body = match bytes.first() {
+            Some(&tag) if tag & ELEMENT_FORMAT_TAG != 0 => match tag & !ELEMENT_FORMAT_TAG {
+                ELEMENT_FORMAT_V1 => &bytes[1..],
+                _ => {
+                    return Err(Error::CorruptedData(String::from(
+                        "unknown element format version",
+                    )))
+                }
+            },
+            _ => bytes,
+        };
         bincode::DefaultOptions::default()
             .with_varint_encoding()
             .reject_trailing_bytes()
-            .deserialize(bytes)
+            .deserialize(body)
             .map_err(|_| Error::CorruptedData(String::from("unable to deserialize element")))
     }
 }
@@ -79,41 +138,41 @@ mod tests {
     fn test_serialization() {
         let empty_tree = Element::empty_tree();
         let serialized = empty_tree.serialize().expect("expected to serialize");
-        assert_eq!(serialized.len(), 3);
+        assert_eq!(serialized.len(), 4);
         assert_eq!(serialized.len(), empty_tree.serialized_size());
         // The tree is fixed length 32 bytes, so it's enum 2 then 32 bytes of zeroes
-        assert_eq!(hex::encode(serialized), "020000");
+        assert_eq!(hex::encode(serialized), "81020000");
 
         let empty_tree = Element::new_tree_with_flags(None, Some(vec![5]));
         let serialized = empty_tree.serialize().expect("expected to serialize");
-        assert_eq!(serialized.len(), 5);
+        assert_eq!(serialized.len(), 6);
         assert_eq!(serialized.len(), empty_tree.serialized_size());
-        assert_eq!(hex::encode(serialized), "0200010105");
+        assert_eq!(hex::encode(serialized), "810200010105");
 
         let item = Element::new_item(hex::decode("abcdef").expect("expected to decode"));
         let serialized = item.serialize().expect("expected to serialize");
-        assert_eq!(serialized.len(), 6);
+        assert_eq!(serialized.len(), 7);
         assert_eq!(serialized.len(), item.serialized_size());
         // The item is variable length 3 bytes, so it's enum 2 then 32 bytes of zeroes
-        assert_eq!(hex::encode(serialized), "0003abcdef00");
+        assert_eq!(hex::encode(serialized), "810003abcdef00");
 
         assert_eq!(hex::encode(5.encode_var_vec()), "0a");
 
         let item = Element::new_sum_item(5);
         let serialized = item.serialize().expect("expected to serialize");
-        assert_eq!(serialized.len(), 3);
+        assert_eq!(serialized.len(), 4);
         assert_eq!(serialized.len(), item.serialized_size());
         // The item is variable length 3 bytes, so it's enum 2 then 32 bytes of zeroes
-        assert_eq!(hex::encode(serialized), "030a00");
+        assert_eq!(hex::encode(serialized), "81030a00");
 
         let item = Element::new_item_with_flags(
             hex::decode("abcdef").expect("expected to decode"),
             Some(vec![1]),
         );
         let serialized = item.serialize().expect("expected to serialize");
-        assert_eq!(serialized.len(), 8);
+        assert_eq!(serialized.len(), 9);
         assert_eq!(serialized.len(), item.serialized_size());
-        assert_eq!(hex::encode(serialized), "0003abcdef010101");
+        assert_eq!(hex::encode(serialized), "810003abcdef010101");
 
         let reference = Element::new_reference(ReferencePathType::AbsolutePathReference(vec![
             vec![0],
@@ -121,11 +180,11 @@ mod tests {
             vec![5],
         ]));
         let serialized = reference.serialize().expect("expected to serialize");
-        assert_eq!(serialized.len(), 12);
+        assert_eq!(serialized.len(), 13);
         assert_eq!(serialized.len(), reference.serialized_size());
         // The item is variable length 2 bytes, so it's enum 1 then 1 byte for length,
         // then 1 byte for 0, then 1 byte 02 for abcd, then 1 byte '1' for 05
-        assert_eq!(hex::encode(serialized), "010003010002abcd01050000");
+        assert_eq!(hex::encode(serialized), "81010003010002abcd01050000");
 
         let reference = Element::new_reference_with_flags(
             ReferencePathType::AbsolutePathReference(vec![
@@ -136,8 +195,144 @@ mod tests {
             Some(vec![1, 2, 3]),
         );
         let serialized = reference.serialize().expect("expected to serialize");
-        assert_eq!(serialized.len(), 16);
+        assert_eq!(serialized.len(), 17);
         assert_eq!(serialized.len(), reference.serialized_size());
-        assert_eq!(hex::encode(serialized), "010003010002abcd0105000103010203");
+        assert_eq!(
+            hex::encode(serialized),
+            "81010003010002abcd0105000103010203"
+        );
+    }
+
+    #[test]
+    fn test_deserialize_keeps_invalid_reference_paths() {
+        let reference = Element::new_reference(ReferencePathType::UpstreamRootHeightReference(
+            1,
+            vec![b"key".to_vec()],
+        ));
+        let serialized = reference.serialize().expect("expected to serialize");
+        let deserialized = Element::deserialize(&serialized).expect("expected to deserialize");
+        assert_eq!(deserialized, reference);
+
+        // Invalid references are rejected on insert, not when reading them
+        let empty_path = Element::new_reference(ReferencePathType::AbsolutePathReference(vec![]));
+        let serialized = empty_path.serialize().expect("expected to serialize");
+        assert_eq!(
+            Element::deserialize(&serialized).expect("expected to deserialize"),
+            empty_path
+        );
+
+        let out_of_range = Element::new_reference(ReferencePathType::UpstreamRootHeightReference(
+            3,
+            vec![b"key".to_vec()],
+        ));
+        let serialized = out_of_range.serialize().expect("expected to serialize");
+        let Element::Reference(reference_path, ..) =
+            Element::deserialize(&serialized).expect("expected to deserialize")
+        else {
+            panic!("expected a reference");
+        };
+        assert!(reference_path.validate_at(&[b"a", b"b", b"c"]).is_ok());
+        assert!(matches!(
+            reference_path.validate_at(&[b"a", b"b"]),
+            Err(Error::InvalidPath(_))
+        ));
+    }
+
+    fn all_variants() -> Vec<Element> {
+        vec![
+            Element::new_item(b"value".to_vec()),
+            Element::new_item_with_flags(b"value".to_vec(), Some(vec![1])),
+            Element::new_reference(ReferencePathType::AbsolutePathReference(vec![
+                b"a".to_vec(),
+                b"b".to_vec(),
+            ])),
+            Element::new_reference_with_max_hops_and_flags(
+                ReferencePathType::SiblingReference(b"key".to_vec()),
+                Some(3),
+                Some(vec![2, 3]),
+            ),
+            Element::empty_tree(),
+            Element::new_tree_with_flags(Some(b"root".to_vec()), Some(vec![5])),
+            Element::new_sum_item(-7),
+            Element::new_sum_item_with_flags(7, Some(vec![1])),
+            Element::empty_sum_tree(),
+            Element::new_sum_tree_with_flags_and_sum_value(Some(b"root".to_vec()), 42, None),
+        ]
+    }
+
+    #[test]
+    fn test_deserialize_version_0() {
+        // Untagged bytes written before elements had a format version
+        let corpus = [
+            ("020000", Element::empty_tree()),
+            (
+                "0200010105",
+                Element::new_tree_with_flags(None, Some(vec![5])),
+            ),
+            ("0003abcdef00", Element::new_item(vec![0xab, 0xcd, 0xef])),
+            ("030a00", Element::new_sum_item(5)),
+            ("04000000", Element::empty_sum_tree()),
+            (
+                "0003abcdef010101",
+                Element::new_item_with_flags(vec![0xab, 0xcd, 0xef], Some(vec![1])),
+            ),
+            (
+                "010003010002abcd01050000",
+                Element::new_reference(ReferencePathType::AbsolutePathReference(vec![
+                    vec![0],
+                    vec![0xab, 0xcd],
+                    vec![5],
+                ])),
+            ),
+        ];
+        for (bytes, element) in corpus {
+            let bytes = hex::decode(bytes).expect("expected to decode");
+            assert_eq!(
+                Element::deserialize(&bytes).expect("expected to deserialize"),
+                element
+            );
+        }
+
+        for element in all_variants() {
+            let bytes = element
+                .serialize_with_version(ELEMENT_FORMAT_V0)
+                .expect("expected to serialize");
+            assert_eq!(
+                Element::deserialize(&bytes).expect("expected to deserialize"),
+                element
+            );
+        }
+    }
+
+    #[test]
+    fn test_version_1_round_trip() {
+        for element in all_variants() {
+            let untagged = element
+                .serialize_with_version(ELEMENT_FORMAT_V0)
+                .expect("expected to serialize");
+            let tagged = element
+                .serialize_with_version(ELEMENT_FORMAT_V1)
+                .expect("expected to serialize");
+            assert_eq!(tagged[0], 0x81);
+            assert_eq!(&tagged[1..], untagged.as_slice());
+            assert_eq!(
+                Element::deserialize(&tagged).expect("expected to deserialize"),
+                element
+            );
+        }
+
+        // Versions this code doesn't know about are rejected
+        let mut unknown = Element::empty_tree()
+            .serialize_with_version(ELEMENT_FORMAT_V1)
+            .expect("expected to serialize");
+        unknown[0] = 0x82;
+        assert!(matches!(
+            Element::deserialize(&unknown),
+            Err(Error::CorruptedData(_))
+        ));
+        assert!(matches!(
+            Element::empty_tree().serialize_with_version(2),
+            Err(Error::NotSupported(_))
+        ));
     }
 }
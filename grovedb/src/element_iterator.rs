@@ -0,0 +1,226 @@
+// MIT LICENSE
+//
+// Copyright (c) 2021 Dash Core Group
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Element iterator
+//! Lazy scan over the elements of a subtree, decoding them as they are read
+
+use grovedb_costs::{cost_return_on_error, CostContext, CostResult, CostsExt, OperationCost};
+use grovedb_path::SubtreePath;
+use grovedb_storage::{
+    rocksdb_storage::{PrefixedRocksDbStorageContext, PrefixedRocksDbTransactionContext},
+    Storage, StorageContext,
+};
+
+use crate::{
+    element::ElementsIterator, query_result_type::KeyElementPair, Element, Error, GroveDb,
+    TransactionArg,
+};
+
+/// Elements of the scanned subtree, depending on whether it was opened on a
+/// transaction
+enum SubtreeElements<'db> {
+    Transactional(
+        ElementsIterator<
+            <PrefixedRocksDbTransactionContext<'db> as StorageContext<'db>>::RawIterator,
+        >,
+    ),
+    NonTransactional(
+        ElementsIterator<<PrefixedRocksDbStorageContext<'db> as StorageContext<'db>>::RawIterator>,
+    ),
+}
+
+/// Iterator over the key-element pairs of a subtree in key order.
+///
+/// Elements are read from storage and decoded one at a time, each item comes
+/// with the cost of reading it. References are returned as is and not
+/// followed. The iteration ends after the first error.
+pub struct ElementIterator<'db> {
+    elements: SubtreeElements<'db>,
+    done: bool,
+}
+
+impl<'db> Iterator for ElementIterator<'db> {
+    type Item = CostResult<KeyElementPair, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let CostContext { value, cost } = match &mut self.elements {
+            SubtreeElements::Transactional(elements) => elements.next_element(),
+            SubtreeElements::NonTransactional(elements) => elements.next_element(),
+        };
+        match value {
+            Ok(Some(pair)) => Some(Ok(pair).wrap_with_cost(cost)),
+            Ok(None) => {
+                self.done = true;
+                None
+            }
+            Err(e) => {
+                self.done = true;
+                Some(Err(e).wrap_with_cost(cost))
+            }
+        }
+    }
+}
+
+impl GroveDb {
+    /// Get a lazy iterator over the key-element pairs of the subtree at
+    /// `path`. The returned cost covers checking that the subtree exists and
+    /// seeking to its first element, the rest is paid by each item.
+    pub fn subtree_element_iter<'db, 'b, B, P>(
+        &'db self,
+        path: P,
+        transaction: TransactionArg<'db, 'db>,
+    ) -> CostResult<ElementIterator<'db>, Error>
+    where
+        B: AsRef<[u8]> + 'b,
+        P: Into<SubtreePath<'b, B>>,
+    {
+        let mut cost = OperationCost::default();
+        let path = path.into();
+
+        cost_return_on_error!(
+            &mut cost,
+            self.check_subtree_exists_path_not_found(path.clone(), transaction)
+        );
+
+        let elements = if let Some(tx) = transaction {
+            let storage = self
+                .db
+                .get_transactional_storage_context(path, None, tx)
+                .unwrap_add_cost(&mut cost);
+            SubtreeElements::Transactional(
+                Element::iterator(storage.raw_iter()).unwrap_add_cost(&mut cost),
+            )
+        } else {
+            let storage = self
+                .db
+                .get_storage_context(path, None)
+                .unwrap_add_cost(&mut cost);
+            SubtreeElements::NonTransactional(
+                Element::iterator(storage.raw_iter()).unwrap_add_cost(&mut cost),
+            )
+        };
+
+        Ok(ElementIterator {
+            elements,
+            done: false,
+        })
+        .wrap_with_cost(cost)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        reference_path::ReferencePathType,
+        tests::{make_test_grovedb, TEST_LEAF},
+        Element, Error,
+    };
+
+    #[test]
+    fn test_subtree_element_iter() {
+        let db = make_test_grovedb();
+        let elements = [
+            (b"a".to_vec(), Element::new_item(b"ayy".to_vec())),
+            (b"b".to_vec(), Element::empty_tree()),
+            (
+                b"c".to_vec(),
+                Element::new_reference(ReferencePathType::SiblingReference(b"a".to_vec())),
+            ),
+            (b"d".to_vec(), Element::empty_sum_tree()),
+        ];
+        for (key, element) in elements.iter() {
+            db.insert([TEST_LEAF].as_ref(), key, element.clone(), None, None)
+                .unwrap()
+                .expect("successful insert");
+        }
+        db.insert(
+            [TEST_LEAF, b"d"].as_ref(),
+            b"e",
+            Element::new_sum_item(5),
+            None,
+            None,
+        )
+        .unwrap()
+        .expect("successful insert");
+
+        let tx = db.start_transaction();
+        db.insert(
+            [TEST_LEAF].as_ref(),
+            b"f",
+            Element::new_item(b"uncommitted".to_vec()),
+            None,
+            Some(&tx),
+        )
+        .unwrap()
+        .expect("successful insert");
+
+        let iterated = db
+            .subtree_element_iter([TEST_LEAF].as_ref(), None)
+            .unwrap()
+            .expect("expected to open an iterator")
+            .map(|pair| pair.unwrap().expect("expected to decode an element"))
+            .collect::<Vec<_>>();
+        assert_eq!(iterated.len(), elements.len());
+        for ((key, element), (expected_key, expected_element)) in iterated.iter().zip(&elements) {
+            assert_eq!(key, expected_key);
+            assert_eq!(
+                std::mem::discriminant(element),
+                std::mem::discriminant(expected_element)
+            );
+        }
+        assert_eq!(iterated[0].1, elements[0].1);
+        assert_eq!(iterated[2].1, elements[2].1);
+        assert_eq!(iterated[3].1.sum_value_or_default(), 5);
+
+        let iterated_keys = db
+            .subtree_element_iter([TEST_LEAF].as_ref(), Some(&tx))
+            .unwrap()
+            .expect("expected to open an iterator")
+            .map(|pair| pair.unwrap().expect("expected to decode an element").0)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            iterated_keys,
+            vec![
+                b"a".to_vec(),
+                b"b".to_vec(),
+                b"c".to_vec(),
+                b"d".to_vec(),
+                b"f".to_vec()
+            ]
+        );
+
+        assert!(matches!(
+            db.subtree_element_iter([TEST_LEAF, b"missing"].as_ref(), None)
+                .unwrap(),
+            Err(Error::PathNotFound(_))
+        ));
+    }
+}
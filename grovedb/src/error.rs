@@ -28,6 +28,9 @@
 
 //! GroveDB Errors
 
+#[cfg(any(feature = "full", feature = "verify"))]
+use std::fmt;
+
 /// GroveDB Errors
 #[cfg(any(feature = "full", feature = "verify"))]
 #[derive(Debug, thiserror::Error)]
@@ -39,6 +42,14 @@ pub enum Error {
     #[error("reference hops limit exceeded")]
     /// Reference limit
     ReferenceLimit,
+    #[error("reference chain too long: {hops} hops followed, last visited path {last_path:?}")]
+    /// Reference chain reached the hops limit without a cycle being detected
+    ReferenceChainTooLong {
+        /// Number of references followed
+        hops: usize,
+        /// Qualified path of the last reference visited
+        last_path: Vec<Vec<u8>>,
+    },
     #[error("missing reference {0}")]
     /// Missing reference
     MissingReference(String),
@@ -60,15 +71,15 @@ pub enum Error {
     /// The path key not found could represent a valid query, just where the
     /// path key isn't there
     #[error("path key not found: {0}")]
-    PathKeyNotFound(String),
+    PathKeyNotFound(PathErrorDetail),
     /// The path not found could represent a valid query, just where the path
     /// isn't there
     #[error("path not found: {0}")]
-    PathNotFound(String),
+    PathNotFound(PathErrorDetail),
     /// The path not found could represent a valid query, just where the parent
     /// path merk isn't there
     #[error("path parent layer not found: {0}")]
-    PathParentLayerNotFound(String),
+    PathParentLayerNotFound(PathErrorDetail),
 
     /// The path's item by key referenced was not found
     #[error("corrupted referenced path key not found: {0}")]
@@ -96,6 +107,19 @@ pub enum Error {
     #[error("invalid query: {0}")]
     /// Invalid query
     InvalidQuery(&'static str),
+    #[error("query has subqueries nested deeper than the limit of {max_depth}")]
+    /// Query subqueries are nested deeper than the database allows
+    QueryTooDeep {
+        /// Maximum subquery depth allowed
+        max_depth: usize,
+    },
+    #[error("proof includes more than the limit of {max_nodes} nodes")]
+    /// Proof generation was stopped for including more Merk nodes than the
+    /// database allows
+    ProofTooLarge {
+        /// Maximum number of nodes allowed in a proof
+        max_nodes: usize,
+    },
     #[error("missing parameter: {0}")]
     /// Missing parameter
     MissingParameter(&'static str),
@@ -107,7 +131,13 @@ pub enum Error {
     // Irrecoverable errors
     #[error("storage_cost error: {0}")]
     /// Storage error
-    StorageError(#[from] grovedb_storage::error::Error),
+    StorageError(grovedb_storage::error::Error),
+
+    #[cfg(feature = "full")]
+    #[error("database is opened read only")]
+    /// Write attempted on a database opened with
+    /// [`GroveDb::open_read_only`](crate::GroveDb::open_read_only)
+    ReadOnly,
 
     #[error("data corruption error: {0}")]
     /// Corrupted data
@@ -158,8 +188,75 @@ pub enum Error {
     /// Not supported
     NotSupported(&'static str),
 
+    #[error("overflow error: {0}")]
+    /// A sum tree aggregate does not fit in an i64
+    Overflow(&'static str),
+
+    #[error("not a sum tree: {0}")]
+    /// A sum operation was requested on a subtree which isn't a sum tree
+    NotASumTree(PathErrorDetail),
+
     // Merk errors
     #[error("merk error: {0}")]
     /// Merk error
     MerkError(grovedb_merk::error::Error),
 }
+
+/// Path, and key if any, a path error was raised for
+#[cfg(any(feature = "full", feature = "verify"))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathErrorDetail {
+    /// Path of the subtree, empty for the root tree or when the error was
+    /// raised where the path isn't known
+    pub path: Vec<Vec<u8>>,
+    /// Key within the subtree, `None` if the subtree itself is missing
+    pub key: Option<Vec<u8>>,
+    /// Message the error is displayed with
+    pub message: String,
+}
+
+#[cfg(any(feature = "full", feature = "verify"))]
+impl PathErrorDetail {
+    /// New path error detail
+    pub fn new(path: Vec<Vec<u8>>, key: Option<Vec<u8>>, message: String) -> Self {
+        Self { path, key, message }
+    }
+}
+
+#[cfg(any(feature = "full", feature = "verify"))]
+impl fmt::Display for PathErrorDetail {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+#[cfg(feature = "full")]
+impl From<grovedb_storage::error::Error> for Error {
+    fn from(error: grovedb_storage::error::Error) -> Self {
+        match error {
+            grovedb_storage::error::Error::ReadOnly => Error::ReadOnly,
+            e => Error::StorageError(e),
+        }
+    }
+}
+
+#[cfg(feature = "full")]
+impl Error {
+    /// Converts an error from applying operations to a Merk, keeping sum
+    /// overflows apart from corrupted data
+    pub(crate) fn from_merk_apply(error: grovedb_merk::error::Error) -> Self {
+        match error {
+            grovedb_merk::error::Error::Overflow(e) => Error::Overflow(e),
+            e => Error::CorruptedData(e.to_string()),
+        }
+    }
+
+    /// Converts an error from computing a Merk root hash and sum, keeping sum
+    /// overflows apart from other Merk errors
+    pub(crate) fn from_merk_sum(error: grovedb_merk::error::Error) -> Self {
+        match error {
+            grovedb_merk::error::Error::Overflow(e) => Error::Overflow(e),
+            e => Error::MerkError(e),
+        }
+    }
+}
@@ -145,12 +145,20 @@ extern crate core;
 
 #[cfg(feature = "full")]
 pub mod batch;
+#[cfg(feature = "full")]
+pub mod cost_breakdown;
+#[cfg(feature = "full")]
+mod dump;
 #[cfg(any(feature = "full", feature = "verify"))]
 pub mod element;
+#[cfg(feature = "full")]
+mod element_iterator;
 #[cfg(any(feature = "full", feature = "verify"))]
 pub mod error;
 #[cfg(feature = "estimated_costs")]
 mod estimated_costs;
+#[cfg(feature = "full")]
+mod merk_cache;
 #[cfg(any(feature = "full", feature = "verify"))]
 pub mod operations;
 #[cfg(any(feature = "full", feature = "verify"))]
@@ -161,6 +169,8 @@ pub mod query_result_type;
 pub mod reference_path;
 #[cfg(feature = "full")]
 mod replication;
+#[cfg(feature = "full")]
+mod subtree_handle;
 #[cfg(all(test, feature = "full"))]
 mod tests;
 #[cfg(feature = "full")]
@@ -170,7 +180,12 @@ mod versioning;
 mod visualize;
 
 #[cfg(feature = "full")]
-use std::{collections::HashMap, option::Option::None, path::Path};
+use std::{
+    collections::{HashMap, HashSet},
+    option::Option::None,
+    path::Path,
+    sync::{atomic::AtomicBool, Mutex},
+};
 
 #[cfg(any(feature = "full", feature = "verify"))]
 use element::helpers;
@@ -179,8 +194,17 @@ pub use element::Element;
 #[cfg(feature = "full")]
 pub use element::ElementFlags;
 #[cfg(feature = "full")]
+pub use element::ElementTypeFilter;
+#[cfg(feature = "full")]
+pub use element::ELEMENT_WRITE_FORMAT_VERSION;
+#[cfg(any(feature = "full", feature = "verify"))]
+pub use element::{ELEMENT_FORMAT_V0, ELEMENT_FORMAT_V1};
+#[cfg(feature = "full")]
+pub use element_iterator::ElementIterator;
+#[cfg(feature = "full")]
 use grovedb_costs::{
-    cost_return_on_error, cost_return_on_error_no_add, CostResult, CostsExt, OperationCost,
+    cost_return_on_error, cost_return_on_error_no_add, CostContext, CostResult, CostsExt,
+    OperationCost,
 };
 #[cfg(feature = "estimated_costs")]
 pub use grovedb_merk::estimated_costs::{
@@ -197,7 +221,7 @@ pub use grovedb_merk::proofs::Query;
 use grovedb_merk::{
     self,
     tree::{combine_hash, value_hash},
-    BatchEntry, CryptoHash, KVIterator, Merk,
+    BatchEntry, CryptoHash, Error as MerkError, KVIterator, Merk,
 };
 use grovedb_path::SubtreePath;
 #[cfg(feature = "full")]
@@ -205,33 +229,79 @@ use grovedb_storage::rocksdb_storage::PrefixedRocksDbImmediateStorageContext;
 #[cfg(feature = "full")]
 use grovedb_storage::rocksdb_storage::RocksDbStorage;
 #[cfg(feature = "full")]
+pub use grovedb_storage::rocksdb_storage::{ChangesetColumnFamily, ChangesetEntry, WriteOptions};
+#[cfg(feature = "full")]
+pub use grovedb_storage::SavepointId;
+#[cfg(feature = "full")]
+use grovedb_storage::{key_successor, Storage, StorageContext};
+#[cfg(feature = "full")]
 use grovedb_storage::{
     rocksdb_storage::{PrefixedRocksDbStorageContext, PrefixedRocksDbTransactionContext},
     StorageBatch,
 };
 #[cfg(feature = "full")]
-use grovedb_storage::{Storage, StorageContext};
-#[cfg(feature = "full")]
 use grovedb_visualize::DebugByteVectors;
+#[cfg(feature = "full")]
+pub use query::QueryPlanStep;
 #[cfg(any(feature = "full", feature = "verify"))]
-pub use query::{PathQuery, SizedQuery};
+pub use query::{Cursor, PathQuery, SizedQuery};
 #[cfg(feature = "full")]
 pub use replication::{BufferedRestorer, Restorer, SiblingsChunkProducer, SubtreeChunkProducer};
+#[cfg(feature = "full")]
+pub use subtree_handle::SubtreeHandle;
 
 #[cfg(any(feature = "full", feature = "verify"))]
-pub use crate::error::Error;
+pub use crate::error::{Error, PathErrorDetail};
 #[cfg(feature = "full")]
 use crate::helpers::raw_decode;
 #[cfg(feature = "full")]
+use crate::merk_cache::MerkCache;
+#[cfg(feature = "full")]
+use crate::operations::change_journal::ChangedKeys;
+#[cfg(feature = "full")]
 use crate::util::{root_merk_optional_tx, storage_context_optional_tx};
 
 #[cfg(feature = "full")]
 type Hash = [u8; 32];
 
+#[cfg(feature = "full")]
+/// Callback receiving the name and cost of an operation
+pub type CostObserver = Box<dyn Fn(&str, &OperationCost) + Send + Sync>;
+
+#[cfg(feature = "full")]
+/// Summary of a committed transaction or write done without a transaction
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommitSummary {
+    /// Root hash of the database once the writes are committed
+    pub root_hash: CryptoHash,
+    /// Number of subtrees which data was written by the commit
+    pub affected_subtrees: usize,
+}
+
+#[cfg(feature = "full")]
+/// Callback invoked after writes are committed
+pub type PostCommitHook = Box<dyn Fn(&CommitSummary) + Send + Sync>;
+
 /// GroveDb
 pub struct GroveDb {
     #[cfg(feature = "full")]
     db: RocksDbStorage,
+    #[cfg(feature = "full")]
+    cost_observer: Option<CostObserver>,
+    #[cfg(feature = "full")]
+    post_commit_hook: Option<PostCommitHook>,
+    #[cfg(feature = "full")]
+    change_journal_retention: Option<usize>,
+    #[cfg(feature = "full")]
+    merk_cache: Mutex<MerkCache>,
+    #[cfg(feature = "full")]
+    max_subquery_depth: Option<usize>,
+    #[cfg(feature = "full")]
+    max_proof_nodes: Option<usize>,
+    #[cfg(feature = "full")]
+    interrupted_commit: bool,
+    #[cfg(feature = "full")]
+    gc_pending: AtomicBool,
 }
 
 /// Transaction
@@ -246,7 +316,147 @@ impl GroveDb {
     /// Opens a given path
     pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
         let db = RocksDbStorage::default_rocksdb_with_path(path)?;
-        Ok(GroveDb { db })
+        let mut grove_db = Self::with_storage(db);
+        grove_db.interrupted_commit = grove_db.db.has_interrupted_commit()?;
+        if grove_db.interrupted_commit {
+            grove_db.db.clear_interrupted_commit()?;
+        }
+        Ok(grove_db)
+    }
+
+    /// Opens an existing GroveDB for replicas and analysis tools, every write
+    /// is rejected with [`Error::ReadOnly`] while reads and proofs work as
+    /// usual. There are no transactions on it, see
+    /// [`GroveDb::try_start_transaction`]. The database isn't locked, so any
+    /// number of read only handles can be opened next to a writable one, each
+    /// seeing the data as of when it was opened.
+    pub fn open_read_only<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let db = RocksDbStorage::read_only_rocksdb_with_path(path)?;
+        let mut grove_db = Self::with_storage(db);
+        grove_db.interrupted_commit = grove_db.db.has_interrupted_commit()?;
+        Ok(grove_db)
+    }
+
+    fn with_storage(db: RocksDbStorage) -> Self {
+        let grove_db = GroveDb {
+            db,
+            cost_observer: None,
+            post_commit_hook: None,
+            change_journal_retention: None,
+            merk_cache: Mutex::new(MerkCache::new(0)),
+            max_subquery_depth: None,
+            max_proof_nodes: None,
+            interrupted_commit: false,
+            gc_pending: AtomicBool::new(true),
+        };
+        // Inserted trees only look for leftovers of deleted ones while some
+        // are waiting to be collected
+        let gc_pending = grove_db.has_gc_tombstones(None).unwrap().unwrap_or(true);
+        grove_db
+            .gc_pending
+            .store(gc_pending, std::sync::atomic::Ordering::Release);
+        grove_db
+    }
+
+    /// Returns `true` if a commit was found interrupted when the database was
+    /// opened. Batches are written atomically, so the database is at its state
+    /// from before that commit and the writes it carried have to be redone.
+    pub fn had_interrupted_commit(&self) -> bool {
+        self.interrupted_commit
+    }
+
+    /// Returns `true` if the database was opened with
+    /// [`GroveDb::open_read_only`]
+    pub fn is_read_only(&self) -> bool {
+        self.db.is_read_only()
+    }
+
+    /// Registers a callback invoked with the operation name and its cost after
+    /// every `get`, `insert`, `delete` and `apply_batch` call, replacing the
+    /// previous one
+    pub fn set_cost_observer(&mut self, observer: CostObserver) {
+        self.cost_observer = Some(observer);
+    }
+
+    /// Unregisters the cost observer
+    pub fn clear_cost_observer(&mut self) {
+        self.cost_observer = None;
+    }
+
+    /// Registers a callback invoked after every successful
+    /// [commit](GroveDb::commit_transaction) of a transaction and every write
+    /// done without a transaction, replacing the previous one. Rolled back
+    /// transactions don't invoke it.
+    pub fn set_post_commit_hook(&mut self, hook: PostCommitHook) {
+        self.post_commit_hook = Some(hook);
+    }
+
+    /// Unregisters the post commit hook
+    pub fn clear_post_commit_hook(&mut self) {
+        self.post_commit_hook = None;
+    }
+
+    /// Starts recording the keys changed by every committed write along with
+    /// the resulting root hash, so they can be listed with
+    /// [GroveDb::changes_since]. Only the latest `retention` entries, at least
+    /// one, are kept.
+    pub fn enable_change_journal(&mut self, retention: usize) {
+        self.change_journal_retention = Some(retention.max(1));
+    }
+
+    /// Stops recording changes, already recorded entries are kept
+    pub fn disable_change_journal(&mut self) {
+        self.change_journal_retention = None;
+    }
+
+    /// Sets how many subtrees opened without a transaction have their root key
+    /// cached, sparing a read of their parent tree element when they're opened
+    /// again. Subtrees opened while writing aren't cached, as they may see
+    /// uncommitted data. The cache is emptied whenever data is committed. Zero,
+    /// the default, disables it.
+    pub fn set_merk_cache_capacity(&mut self, capacity: usize) {
+        self.merk_cache = Mutex::new(MerkCache::new(capacity));
+    }
+
+    /// Limits how deeply subqueries of the path queries executed or proven
+    /// may be nested, queries going deeper fail with [Error::QueryTooDeep].
+    /// A query without subqueries has a depth of zero, each subquery or
+    /// subquery path adds one level. Useful when serving untrusted queries.
+    pub fn set_max_subquery_depth(&mut self, max_depth: usize) {
+        self.max_subquery_depth = Some(max_depth);
+    }
+
+    /// Removes the limit on subquery depth
+    pub fn clear_max_subquery_depth(&mut self) {
+        self.max_subquery_depth = None;
+    }
+
+    /// Limits how many Merk nodes a single proof may include, generating a
+    /// proof going past it fails with [Error::ProofTooLarge]. Bounds the work
+    /// done for queries matching enormous result sets.
+    pub fn set_max_proof_nodes(&mut self, max_nodes: usize) {
+        self.max_proof_nodes = Some(max_nodes);
+    }
+
+    /// Removes the limit on proof nodes
+    pub fn clear_max_proof_nodes(&mut self) {
+        self.max_proof_nodes = None;
+    }
+
+    /// Checks the path query against the subquery depth limit, if one is set
+    fn check_subquery_depth(&self, path_query: &PathQuery) -> Result<(), Error> {
+        match self.max_subquery_depth {
+            Some(max_depth) => path_query.check_subquery_depth(max_depth),
+            None => Ok(()),
+        }
+    }
+
+    /// Reports the cost of an operation to the cost observer, if one is set
+    fn observe_cost<T>(&self, operation: &str, result: CostContext<T>) -> CostContext<T> {
+        if let Some(observer) = &self.cost_observer {
+            observer(operation, &result.cost);
+        }
+        result
     }
 
     /// Opens the transactional Merk at the given path. Returns CostResult.
@@ -368,6 +578,25 @@ impl GroveDb {
             .unwrap_add_cost(&mut cost);
 
         if let Some((parent_path, parent_key)) = path.derive_parent() {
+            // Storage contexts of a batch read its pending writes, which may never be
+            // committed, so the cache is only used for reads of committed data
+            let generation = self.db.write_generation();
+            let cached = if batch.is_none() {
+                self.merk_cache
+                    .lock()
+                    .ok()
+                    .and_then(|mut cache| cache.get(&path, generation))
+            } else {
+                None
+            };
+            if let Some((root_key, is_sum_tree)) = cached {
+                return Merk::open_layered_with_root_key(storage, root_key, is_sum_tree)
+                    .map_err(|_| {
+                        Error::CorruptedData("cannot open a subtree with given root key".to_owned())
+                    })
+                    .add_cost(cost);
+            }
+
             let parent_storage = self
                 .db
                 .get_storage_context(parent_path.clone(), batch)
@@ -385,6 +614,13 @@ impl GroveDb {
             );
             let is_sum_tree = element.is_sum_tree();
             if let Element::Tree(root_key, _) | Element::SumTree(root_key, ..) = element {
+                // Without a batch only committed data is read, so the root key stays
+                // valid until the generation read before the parent element moves on
+                if batch.is_none() {
+                    if let Ok(mut cache) = self.merk_cache.lock() {
+                        cache.insert(&path, (root_key.clone(), is_sum_tree), generation);
+                    }
+                }
                 Merk::open_layered_with_root_key(storage, root_key, is_sum_tree)
                     .map_err(|_| {
                         Error::CorruptedData("cannot open a subtree with given root key".to_owned())
@@ -423,6 +659,9 @@ impl GroveDb {
 
     /// Returns root hash of GroveDb.
     /// Will be `None` if GroveDb is empty.
+    /// If a transaction is provided the root hash is read through its view and
+    /// includes its uncommitted changes, otherwise only committed data is
+    /// taken into account.
     pub fn root_hash(&self, transaction: TransactionArg) -> CostResult<Hash, Error> {
         let mut cost = OperationCost {
             ..Default::default()
@@ -434,6 +673,13 @@ impl GroveDb {
         })
     }
 
+    /// Returns root hash of GroveDb as seen inside the `transaction`,
+    /// reflecting changes that weren't committed yet. Useful to negotiate
+    /// proofs with clients in the middle of a transaction.
+    pub fn root_hash_in_transaction(&self, transaction: &Transaction) -> CostResult<Hash, Error> {
+        self.root_hash(Some(transaction))
+    }
+
     /// Method to propagate updated subtree key changes one level up inside a
     /// transaction
     fn propagate_changes_with_batch_transaction<'b, B: AsRef<[u8]>>(
@@ -466,7 +712,9 @@ impl GroveDb {
             );
             let (root_hash, root_key, sum) = cost_return_on_error!(
                 &mut cost,
-                child_tree.root_hash_key_and_sum().map_err(Error::MerkError)
+                child_tree
+                    .root_hash_key_and_sum()
+                    .map_err(Error::from_merk_sum)
             );
             cost_return_on_error!(
                 &mut cost,
@@ -513,7 +761,9 @@ impl GroveDb {
             );
             let (root_hash, root_key, sum) = cost_return_on_error!(
                 &mut cost,
-                child_tree.root_hash_key_and_sum().map_err(Error::MerkError)
+                child_tree
+                    .root_hash_key_and_sum()
+                    .map_err(Error::from_merk_sum)
             );
             cost_return_on_error!(
                 &mut cost,
@@ -558,7 +808,9 @@ impl GroveDb {
             );
             let (root_hash, root_key, sum) = cost_return_on_error!(
                 &mut cost,
-                child_tree.root_hash_key_and_sum().map_err(Error::MerkError)
+                child_tree
+                    .root_hash_key_and_sum()
+                    .map_err(Error::from_merk_sum)
             );
             cost_return_on_error!(
                 &mut cost,
@@ -675,16 +927,20 @@ impl GroveDb {
             })
             .map_ok(|subtree_opt| {
                 subtree_opt.ok_or_else(|| {
-                    let key = hex::encode(key.as_ref());
-                    Error::PathKeyNotFound(format!(
+                    let message = format!(
                         "can't find subtree with key {} in parent during propagation (subtree is \
                          {})",
-                        key,
+                        hex::encode(key.as_ref()),
                         if subtree.root_key().is_some() {
                             "not empty"
                         } else {
                             "empty"
                         }
+                    );
+                    Error::PathKeyNotFound(PathErrorDetail::new(
+                        vec![],
+                        Some(key.as_ref().to_vec()),
+                        message,
                     ))
                 })
             })
@@ -704,8 +960,39 @@ impl GroveDb {
         Ok(self.db.flush()?)
     }
 
+    /// Compacts the storage range of the subtree at `path`, to reclaim the
+    /// space held by its deleted data right away, for instance after the
+    /// subtree was deleted or emptied.
+    pub fn compact_subtree<'b, B, P>(&self, path: P) -> Result<(), Error>
+    where
+        B: AsRef<[u8]> + 'b,
+        P: Into<SubtreePath<'b, B>>,
+    {
+        let prefix = RocksDbStorage::build_prefix(path.into()).unwrap();
+        let end = key_successor(&prefix);
+        Ok(self.db.compact_range(Some(&prefix), end.as_deref())?)
+    }
+
+    /// Sets whether writes are synced and whether they go through the
+    /// write-ahead log. Applies to batches committed and transactions started
+    /// afterwards; by default the WAL is used without syncing every write.
+    pub fn set_write_options(&mut self, write_options: WriteOptions) {
+        self.db.set_write_options(write_options);
+    }
+
+    /// Durability policy used for writes
+    pub fn write_options(&self) -> WriteOptions {
+        self.db.write_options()
+    }
+
     /// Starts database transaction. Please note that you have to start
-    /// underlying storage transaction manually.
+    /// underlying storage transaction manually. Transactions are optimistic:
+    /// conflicting writes are detected when committing.
+    ///
+    /// # Panics
+    ///
+    /// Panics on a database opened with [`GroveDb::open_read_only`], use
+    /// [`GroveDb::try_start_transaction`] when that's possible.
     ///
     /// ## Examples:
     /// ```
@@ -760,13 +1047,108 @@ impl GroveDb {
     /// # }
     /// ```
     pub fn start_transaction(&self) -> Transaction {
-        self.db.start_transaction()
+        self.try_start_transaction()
+            .expect("cannot start a transaction on a read only database")
+    }
+
+    /// Starts database transaction like [`GroveDb::start_transaction`], but
+    /// fails with [`Error::ReadOnly`] on a database opened with
+    /// [`GroveDb::open_read_only`] instead of panicking.
+    pub fn try_start_transaction(&self) -> Result<Transaction, Error> {
+        Ok(self.db.start_transaction()?)
     }
 
     /// Commits previously started db transaction. For more details on the
     /// transaction usage, please check [`GroveDb::start_transaction`]
     pub fn commit_transaction(&self, transaction: Transaction) -> CostResult<(), Error> {
-        self.db.commit_transaction(transaction).map_err(Into::into)
+        let hook = match &self.post_commit_hook {
+            Some(hook) => hook,
+            None => return self.db.commit_transaction(transaction).map_err(Into::into),
+        };
+
+        let mut cost = OperationCost::default();
+        // The hook is skipped if the summary can't be made, the transaction is
+        // committed anyway
+        let affected_subtrees = self
+            .transaction_changeset(&transaction)
+            .ok()
+            .map(|changeset| {
+                changeset
+                    .iter()
+                    .filter(|entry| {
+                        matches!(
+                            entry.column_family,
+                            ChangesetColumnFamily::Default | ChangesetColumnFamily::Roots
+                        )
+                    })
+                    .filter_map(ChangesetEntry::subtree_prefix)
+                    .collect::<HashSet<_>>()
+                    .len()
+            });
+        cost_return_on_error!(
+            &mut cost,
+            self.db.commit_transaction(transaction).map_err(Into::into)
+        );
+        if let Some(affected_subtrees) = affected_subtrees {
+            self.invoke_post_commit_hook(hook, affected_subtrees)
+                .unwrap_add_cost(&mut cost);
+        }
+        Ok(()).wrap_with_cost(cost)
+    }
+
+    /// Commits a multi-context batch along with the change journal entry of the
+    /// `changes` it makes, see [`GroveDb::record_changes`]. Batches committed
+    /// without a transaction invoke the post commit hook, ones committed in a
+    /// transaction are reported once it's committed.
+    pub(crate) fn commit_multi_context_batch(
+        &self,
+        batch: StorageBatch,
+        changes: Option<ChangedKeys>,
+        transaction: TransactionArg,
+    ) -> CostResult<(), Error> {
+        let mut cost = OperationCost::default();
+
+        cost_return_on_error!(&mut cost, self.record_changes(changes, &batch, transaction));
+
+        let hook = match (&self.post_commit_hook, transaction) {
+            (Some(hook), None) => hook,
+            _ => {
+                return self
+                    .db
+                    .commit_multi_context_batch(batch, transaction)
+                    .map_err(Into::into)
+                    .add_cost(cost)
+            }
+        };
+
+        let affected_subtrees = self.db.batch_affected_subtrees(&batch);
+        cost_return_on_error!(
+            &mut cost,
+            self.db
+                .commit_multi_context_batch(batch, None)
+                .map_err(Into::into)
+        );
+        self.invoke_post_commit_hook(hook, affected_subtrees)
+            .unwrap_add_cost(&mut cost);
+        Ok(()).wrap_with_cost(cost)
+    }
+
+    /// Invokes the post commit hook with the root hash of committed data. The
+    /// writes are already committed at this point, so the hook is skipped
+    /// rather than failing the commit if the root hash can't be computed.
+    fn invoke_post_commit_hook(
+        &self,
+        hook: &PostCommitHook,
+        affected_subtrees: usize,
+    ) -> CostContext<()> {
+        self.root_hash(None).map(|root_hash| {
+            if let Ok(root_hash) = root_hash {
+                hook(&CommitSummary {
+                    root_hash,
+                    affected_subtrees,
+                });
+            }
+        })
     }
 
     /// Rollbacks previously started db transaction to initial state.
@@ -776,6 +1158,40 @@ impl GroveDb {
         Ok(self.db.rollback_transaction(transaction)?)
     }
 
+    /// Returns the storage writes a transaction would apply on commit, so they
+    /// can be shipped elsewhere as a diff. Entries keep the order the writes
+    /// were made in, and keys carry their subtree prefixes.
+    pub fn transaction_changeset(
+        &self,
+        transaction: &Transaction,
+    ) -> Result<Vec<ChangesetEntry>, Error> {
+        Ok(self.db.transaction_changeset(transaction)?)
+    }
+
+    /// Returns `true` if the transaction didn't record any storage writes, so
+    /// committing it can be skipped. An insert followed by a delete of the same
+    /// key still leaves the transaction non-empty.
+    pub fn transaction_is_empty(&self, transaction: &Transaction) -> bool {
+        self.db.transaction_is_empty(transaction)
+    }
+
+    /// Creates a savepoint inside of a transaction, to undo a failed part of a
+    /// multi-step operation without aborting the whole transaction use
+    /// [`GroveDb::rollback_to_savepoint`].
+    pub fn savepoint(&self, transaction: &Transaction) -> Result<SavepointId, Error> {
+        Ok(self.db.savepoint(transaction)?)
+    }
+
+    /// Undo all changes done inside of the transaction since the savepoint was
+    /// created. For more details please check [`GroveDb::savepoint`]
+    pub fn rollback_to_savepoint(
+        &self,
+        transaction: &Transaction,
+        savepoint: SavepointId,
+    ) -> Result<(), Error> {
+        Ok(self.db.rollback_to(transaction, savepoint)?)
+    }
+
     /// Method to visualize hash mismatch after verification
     pub fn visualize_verify_grovedb(&self) -> HashMap<String, (String, String, String)> {
         self.verify_grovedb()
@@ -852,4 +1268,94 @@ impl GroveDb {
         }
         issues
     }
+
+    /// Checks the subtree at `path` and all subtrees below it for on-disk
+    /// corruption. Every node is re-read from storage and its hash is
+    /// recomputed from the value and the children's hashes; `false` is
+    /// returned if any recomputed hash differs from the stored one.
+    /// References are only checked as part of their Merk's structure, as their
+    /// value hashes depend on the referenced items.
+    pub fn verify_subtree_integrity<'b, B, P>(
+        &self,
+        path: P,
+        transaction: TransactionArg,
+    ) -> CostResult<bool, Error>
+    where
+        B: AsRef<[u8]> + 'b,
+        P: Into<SubtreePath<'b, B>>,
+    {
+        let mut cost = OperationCost::default();
+        let path: SubtreePath<B> = path.into();
+
+        if let Some(tx) = transaction {
+            let merk = cost_return_on_error!(
+                &mut cost,
+                self.open_transactional_merk_at_path(path.clone(), tx, None)
+            );
+            self.verify_merk_integrity(&merk, &path, &|p| {
+                self.open_transactional_merk_at_path(p, tx, None)
+            })
+            .add_cost(cost)
+        } else {
+            let merk = cost_return_on_error!(
+                &mut cost,
+                self.open_non_transactional_merk_at_path(path.clone(), None)
+            );
+            self.verify_merk_integrity(&merk, &path, &|p| {
+                self.open_non_transactional_merk_at_path(p, None)
+            })
+            .add_cost(cost)
+        }
+    }
+
+    /// Verifies integrity of the given merk, checking value hashes of its
+    /// elements and descending into its subtrees.
+    fn verify_merk_integrity<'db, S, B, F>(
+        &self,
+        merk: &Merk<S>,
+        path: &SubtreePath<B>,
+        open_merk: &F,
+    ) -> CostResult<bool, Error>
+    where
+        S: StorageContext<'db>,
+        B: AsRef<[u8]>,
+        F: for<'p> Fn(SubtreePath<'p, B>) -> CostResult<Merk<S>, Error>,
+    {
+        merk.verify_integrity(|key, value, stored_value_hash| {
+            let mut cost = OperationCost::default();
+
+            let element = cost_return_on_error_no_add!(
+                &cost,
+                Element::deserialize(value)
+                    .map_err(|e| MerkError::ClientCorruptionError(e.to_string()))
+            );
+            let actual_value_hash = value_hash(value).unwrap_add_cost(&mut cost);
+            let expected_value_hash = match element {
+                Element::Item(..) | Element::SumItem(..) => actual_value_hash,
+                Element::Tree(..) | Element::SumTree(..) => {
+                    let child_path = path.derive_owned_with_child(key.to_vec());
+                    let child_path_ref = SubtreePath::from(&child_path);
+                    let child_merk = cost_return_on_error!(
+                        &mut cost,
+                        open_merk(child_path_ref.clone())
+                            .map_err(|e| MerkError::ClientCorruptionError(e.to_string()))
+                    );
+                    let child_is_intact = cost_return_on_error!(
+                        &mut cost,
+                        self.verify_merk_integrity(&child_merk, &child_path_ref, open_merk)
+                            .map_err(|e| MerkError::ClientCorruptionError(e.to_string()))
+                    );
+                    if !child_is_intact {
+                        return Ok(false).wrap_with_cost(cost);
+                    }
+                    let child_root_hash = child_merk.root_hash().unwrap_add_cost(&mut cost);
+                    combine_hash(&actual_value_hash, &child_root_hash).unwrap_add_cost(&mut cost)
+                }
+                Element::Reference(..) => return Ok(true).wrap_with_cost(cost),
+            };
+
+            Ok(&expected_value_hash == stored_value_hash).wrap_with_cost(cost)
+        })
+        .map_err(Error::MerkError)
+    }
 }
@@ -0,0 +1,153 @@
+// MIT LICENSE
+//
+// Copyright (c) 2021 Dash Core Group
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Cache of what's needed to open the Merks of subtrees
+
+#[cfg(feature = "full")]
+use std::collections::HashMap;
+
+#[cfg(feature = "full")]
+use grovedb_path::SubtreePath;
+
+/// Cached subtree: its root key and whether it's a sum tree
+#[cfg(feature = "full")]
+pub(crate) type CachedSubtree = (Option<Vec<u8>>, bool);
+
+/// Least recently used cache of subtrees opened without a transaction.
+///
+/// Merks borrow the storage they're opened on, so they can't be kept by
+/// `GroveDb` itself. What's cached instead is the root key found in the parent
+/// tree element, which spares reading that element from storage when a subtree
+/// is opened again. All entries are dropped once the storage
+/// [write generation](grovedb_storage::rocksdb_storage::RocksDbStorage::write_generation)
+/// changes.
+#[cfg(feature = "full")]
+pub(crate) struct MerkCache {
+    capacity: usize,
+    generation: u64,
+    tick: u64,
+    entries: HashMap<Vec<Vec<u8>>, (CachedSubtree, u64)>,
+}
+
+#[cfg(feature = "full")]
+impl MerkCache {
+    /// Create a cache keeping up to `capacity` subtrees, zero disables it
+    pub(crate) fn new(capacity: usize) -> Self {
+        MerkCache {
+            capacity,
+            generation: 0,
+            tick: 0,
+            entries: HashMap::new(),
+        }
+    }
+
+    /// Get the cached subtree at `path` if storage wasn't written since it
+    /// was cached
+    pub(crate) fn get<B: AsRef<[u8]>>(
+        &mut self,
+        path: &SubtreePath<B>,
+        generation: u64,
+    ) -> Option<CachedSubtree> {
+        if self.capacity == 0 {
+            return None;
+        }
+        self.sync_generation(generation);
+        self.tick += 1;
+        let tick = self.tick;
+        self.entries
+            .get_mut(&path.to_vec())
+            .map(|(subtree, last_used)| {
+                *last_used = tick;
+                subtree.clone()
+            })
+    }
+
+    /// Cache the subtree at `path`, read from storage at the given write
+    /// generation, evicting the least recently used one if the cache is full
+    pub(crate) fn insert<B: AsRef<[u8]>>(
+        &mut self,
+        path: &SubtreePath<B>,
+        subtree: CachedSubtree,
+        generation: u64,
+    ) {
+        if self.capacity == 0 || generation < self.generation {
+            return;
+        }
+        self.sync_generation(generation);
+        let path = path.to_vec();
+        if !self.entries.contains_key(&path) && self.entries.len() >= self.capacity {
+            if let Some(least_recently_used) = self
+                .entries
+                .iter()
+                .min_by_key(|(_, (_, last_used))| *last_used)
+                .map(|(path, _)| path.clone())
+            {
+                self.entries.remove(&least_recently_used);
+            }
+        }
+        self.tick += 1;
+        self.entries.insert(path, (subtree, self.tick));
+    }
+
+    /// Drop all entries if storage was written since they were cached
+    fn sync_generation(&mut self, generation: u64) {
+        if generation != self.generation {
+            self.entries.clear();
+            self.generation = generation;
+        }
+    }
+}
+
+#[cfg(feature = "full")]
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_least_recently_used_subtree_is_evicted() {
+        let a: SubtreePath<_> = [b"a"].as_ref().into();
+        let b: SubtreePath<_> = [b"b"].as_ref().into();
+        let c: SubtreePath<_> = [b"c"].as_ref().into();
+
+        let mut cache = MerkCache::new(2);
+        cache.insert(&a, (Some(b"root_a".to_vec()), false), 0);
+        cache.insert(&b, (Some(b"root_b".to_vec()), true), 0);
+        assert_eq!(cache.get(&a, 0), Some((Some(b"root_a".to_vec()), false)));
+
+        cache.insert(&c, (None, false), 0);
+        assert_eq!(cache.get(&b, 0), None);
+        assert_eq!(cache.get(&a, 0), Some((Some(b"root_a".to_vec()), false)));
+        assert_eq!(cache.get(&c, 0), Some((None, false)));
+
+        // Writes to storage invalidate everything
+        assert_eq!(cache.get(&a, 1), None);
+        // Subtrees read before the last write aren't cached
+        cache.insert(&a, (Some(b"root_a".to_vec()), false), 0);
+        assert_eq!(cache.get(&a, 1), None);
+    }
+}
@@ -31,12 +31,24 @@
 #[cfg(feature = "full")]
 pub(crate) mod auxiliary;
 #[cfg(feature = "full")]
+pub mod change_journal;
+#[cfg(feature = "full")]
 pub mod delete;
 #[cfg(feature = "full")]
+pub mod diff;
+#[cfg(feature = "full")]
+pub(crate) mod element_flags;
+#[cfg(feature = "full")]
+pub(crate) mod garbage_collection;
+#[cfg(feature = "full")]
 pub(crate) mod get;
 #[cfg(feature = "full")]
 pub mod insert;
 #[cfg(feature = "full")]
 pub(crate) mod is_empty_tree;
+#[cfg(feature = "full")]
+pub(crate) mod migrate;
+#[cfg(feature = "full")]
+pub(crate) mod move_element;
 #[cfg(any(feature = "full", feature = "verify"))]
 pub mod proof;
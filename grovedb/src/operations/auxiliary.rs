@@ -64,10 +64,8 @@ impl GroveDb {
             );
         });
 
-        self.db
-            .commit_multi_context_batch(batch, transaction)
+        self.commit_multi_context_batch(batch, None, transaction)
             .add_cost(cost)
-            .map_err(Into::into)
     }
 
     /// Delete op for aux storage
@@ -91,10 +89,8 @@ impl GroveDb {
             );
         });
 
-        self.db
-            .commit_multi_context_batch(batch, transaction)
+        self.commit_multi_context_batch(batch, None, transaction)
             .add_cost(cost)
-            .map_err(Into::into)
     }
 
     /// Get op for aux storage
@@ -0,0 +1,227 @@
+// MIT LICENSE
+//
+// Copyright (c) 2021 Dash Core Group
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Opt-in journal of changed keys, used to sync incrementally from a known
+//! root hash
+
+#[cfg(feature = "full")]
+use bincode::Options;
+#[cfg(feature = "full")]
+use grovedb_costs::{
+    cost_return_on_error, cost_return_on_error_no_add, CostResult, CostsExt, OperationCost,
+};
+#[cfg(feature = "full")]
+use grovedb_storage::{RawIterator, StorageBatch, StorageContext};
+
+#[cfg(feature = "full")]
+use crate::{
+    util::{meta_storage_context_optional_tx, root_merk_optional_tx},
+    CryptoHash, Error, GroveDb, TransactionArg,
+};
+
+/// Metadata key prefix of change journal entries, the rest of the key is the
+/// big endian sequence number of the entry
+#[cfg(feature = "full")]
+const CHANGE_JOURNAL_PREFIX: &[u8] = b"change_journal";
+
+/// Paths and keys changed by a write
+#[cfg(feature = "full")]
+pub type ChangedKeys = Vec<(Vec<Vec<u8>>, Vec<u8>)>;
+
+/// Root hash after a write and the keys it changed
+#[cfg(feature = "full")]
+type JournalEntry = (CryptoHash, ChangedKeys);
+
+#[cfg(feature = "full")]
+fn journal_key(sequence: u64) -> Vec<u8> {
+    let mut key = CHANGE_JOURNAL_PREFIX.to_vec();
+    key.extend(sequence.to_be_bytes());
+    key
+}
+
+#[cfg(feature = "full")]
+fn journal_sequence(key: &[u8]) -> Result<u64, Error> {
+    key.strip_prefix(CHANGE_JOURNAL_PREFIX)
+        .and_then(|sequence| sequence.try_into().ok())
+        .map(u64::from_be_bytes)
+        .ok_or_else(|| Error::CorruptedData("invalid change journal key".to_owned()))
+}
+
+/// Sequence number of the change journal entry the iterator points to, `None`
+/// if it doesn't point to one
+#[cfg(feature = "full")]
+fn journal_sequence_at(iter: &impl RawIterator) -> CostResult<Option<u64>, Error> {
+    let mut cost = OperationCost::default();
+
+    if !iter.valid().unwrap_add_cost(&mut cost) {
+        return Ok(None).wrap_with_cost(cost);
+    }
+    iter.key()
+        .unwrap_add_cost(&mut cost)
+        .filter(|key| key.starts_with(CHANGE_JOURNAL_PREFIX))
+        .map(journal_sequence)
+        .transpose()
+        .wrap_with_cost(cost)
+}
+
+#[cfg(feature = "full")]
+fn journal_options() -> impl Options {
+    bincode::DefaultOptions::default()
+        .with_varint_encoding()
+        .reject_trailing_bytes()
+}
+
+#[cfg(feature = "full")]
+impl GroveDb {
+    /// Appends the root hash and the keys changed by a write to the change
+    /// journal, if it's enabled, dropping the oldest entries past the
+    /// retention. The entry is added to the `batch` holding the write, so both
+    /// are committed at once. Writes which don't change any element, like
+    /// ones of aux data, give `None` and aren't recorded.
+    pub(crate) fn record_changes(
+        &self,
+        changes: Option<ChangedKeys>,
+        batch: &StorageBatch,
+        transaction: TransactionArg,
+    ) -> CostResult<(), Error> {
+        let mut cost = OperationCost::default();
+
+        let (Some(retention), Some(changes)) = (self.change_journal_retention, changes) else {
+            return Ok(()).wrap_with_cost(cost);
+        };
+        // The write isn't committed yet, so the root hash is read through the batch
+        let mut root_hash = CryptoHash::default();
+        root_merk_optional_tx!(&mut cost, self.db, Some(batch), transaction, subtree, {
+            root_hash = subtree.root_hash().unwrap_add_cost(&mut cost);
+        });
+        let entry = cost_return_on_error_no_add!(
+            &cost,
+            journal_options()
+                .serialize(&(root_hash, changes))
+                .map_err(|e| {
+                    Error::CorruptedData(format!("unable to serialize change journal entry: {e}"))
+                })
+        );
+        let bounds = cost_return_on_error!(&mut cost, self.journal_bounds(transaction));
+
+        meta_storage_context_optional_tx!(self.db, Some(batch), transaction, meta_storage, {
+            let meta_storage = meta_storage.unwrap_add_cost(&mut cost);
+            let next_sequence = bounds.map_or(0, |(_, last)| last + 1);
+            cost_return_on_error_no_add!(
+                &cost,
+                meta_storage
+                    .put_meta(journal_key(next_sequence), &entry, None)
+                    .unwrap_add_cost(&mut cost)
+                    .map_err(|e| e.into())
+            );
+            // Usually only the oldest entry expires, more of them do if the
+            // retention was lowered
+            if let Some((first, _)) = bounds {
+                let retained_from = (next_sequence + 1).saturating_sub(retention as u64);
+                for sequence in first..retained_from {
+                    cost_return_on_error_no_add!(
+                        &cost,
+                        meta_storage
+                            .delete_meta(journal_key(sequence), None)
+                            .unwrap_add_cost(&mut cost)
+                            .map_err(|e| e.into())
+                    );
+                }
+            }
+        });
+
+        Ok(()).wrap_with_cost(cost)
+    }
+
+    /// Returns the paths and keys changed since the database had the given
+    /// root hash, in the order they were written. Requires the
+    /// [change journal](GroveDb::enable_change_journal) to have been enabled
+    /// since then, and the entry of `root_hash` to still be retained.
+    pub fn changes_since(
+        &self,
+        root_hash: &CryptoHash,
+        transaction: TransactionArg,
+    ) -> CostResult<ChangedKeys, Error> {
+        let mut cost = OperationCost::default();
+        let mut changes = None;
+
+        meta_storage_context_optional_tx!(self.db, None, transaction, meta_storage, {
+            let mut iter = meta_storage.unwrap_add_cost(&mut cost).raw_iter_meta();
+            iter.seek(CHANGE_JOURNAL_PREFIX).unwrap_add_cost(&mut cost);
+            while iter.valid().unwrap_add_cost(&mut cost) {
+                if !iter
+                    .key()
+                    .unwrap_add_cost(&mut cost)
+                    .map_or(false, |key| key.starts_with(CHANGE_JOURNAL_PREFIX))
+                {
+                    break;
+                }
+                let value = iter.value().unwrap_add_cost(&mut cost).unwrap_or_default();
+                let (entry_root_hash, entry_changes): JournalEntry = cost_return_on_error_no_add!(
+                    &cost,
+                    journal_options().deserialize(value).map_err(|e| {
+                        Error::CorruptedData(format!("invalid change journal entry: {e}"))
+                    })
+                );
+                // The same state may be reached several times, changes since its
+                // latest occurrence are enough to catch up
+                if &entry_root_hash == root_hash {
+                    changes = Some(Vec::new());
+                } else if let Some(changes) = changes.as_mut() {
+                    changes.extend(entry_changes);
+                }
+                iter.next().unwrap_add_cost(&mut cost);
+            }
+        });
+
+        changes
+            .ok_or(Error::InvalidInput(
+                "root hash is not retained in the change journal",
+            ))
+            .wrap_with_cost(cost)
+    }
+
+    /// Get the sequence numbers of the first and the last change journal
+    /// entries, `None` if the journal is empty
+    fn journal_bounds(&self, transaction: TransactionArg) -> CostResult<Option<(u64, u64)>, Error> {
+        let mut cost = OperationCost::default();
+        let mut bounds = None;
+
+        meta_storage_context_optional_tx!(self.db, None, transaction, meta_storage, {
+            let mut iter = meta_storage.unwrap_add_cost(&mut cost).raw_iter_meta();
+            iter.seek(CHANGE_JOURNAL_PREFIX).unwrap_add_cost(&mut cost);
+            let first = cost_return_on_error!(&mut cost, journal_sequence_at(&iter));
+            iter.seek_last_for_prefix(CHANGE_JOURNAL_PREFIX)
+                .unwrap_add_cost(&mut cost);
+            let last = cost_return_on_error!(&mut cost, journal_sequence_at(&iter));
+            bounds = first.zip(last);
+        });
+
+        Ok(bounds).wrap_with_cost(cost)
+    }
+}
@@ -36,18 +36,22 @@ mod delete_up_tree;
 mod worst_case;
 
 #[cfg(feature = "full")]
-use std::collections::{BTreeSet, HashMap};
+use std::collections::{BTreeSet, HashMap, VecDeque};
 
 #[cfg(feature = "full")]
 pub use delete_up_tree::DeleteUpTreeOptions;
 #[cfg(feature = "full")]
 use grovedb_costs::{
-    cost_return_on_error,
+    cost_return_on_error, cost_return_on_error_no_add,
     storage_cost::removal::{StorageRemovedBytes, StorageRemovedBytes::BasicStorageRemoval},
     CostResult, CostsExt, OperationCost,
 };
 #[cfg(feature = "full")]
-use grovedb_merk::{Error as MerkError, Merk, MerkOptions};
+use grovedb_merk::{
+    tree::NULL_HASH,
+    Error as MerkError, Merk, MerkOptions, TreeFeatureType,
+    TreeFeatureType::{BasicMerk, SummedMerk},
+};
 use grovedb_path::SubtreePath;
 #[cfg(feature = "full")]
 use grovedb_storage::{
@@ -75,6 +79,10 @@ pub struct DeleteOptions {
     pub base_root_storage_is_free: bool,
     /// Validate tree at path exists
     pub validate_tree_at_path_exists: bool,
+    /// When deleting a non empty tree, only remove it from its parent and
+    /// leave clearing its storage and the storage of its descendants to
+    /// [GroveDb::collect_garbage]
+    pub defer_child_cleanup: bool,
 }
 
 #[cfg(feature = "full")]
@@ -85,6 +93,7 @@ impl Default for DeleteOptions {
             deleting_non_empty_trees_returns_error: true,
             base_root_storage_is_free: true,
             validate_tree_at_path_exists: false,
+            defer_child_cleanup: false,
         }
     }
 }
@@ -101,6 +110,7 @@ impl DeleteOptions {
 #[cfg(feature = "full")]
 impl GroveDb {
     /// Delete an element at a specified subtree path and key.
+    /// An empty key is a valid key, as it is for insertion.
     pub fn delete<'b, B, P>(
         &self,
         path: P,
@@ -112,12 +122,16 @@ impl GroveDb {
         B: AsRef<[u8]> + 'b,
         P: Into<SubtreePath<'b, B>>,
     {
+        let path: SubtreePath<B> = path.into();
         let options = options.unwrap_or_default();
         let batch = StorageBatch::new();
+        let changes = self
+            .change_journal_retention
+            .map(|_| vec![(path.to_vec(), key.to_vec())]);
 
         let collect_costs = self
             .delete_internal(
-                path.into(),
+                path,
                 key,
                 &options,
                 transaction,
@@ -131,11 +145,116 @@ impl GroveDb {
             )
             .map_ok(|_| ());
 
-        collect_costs.flat_map_ok(|_| {
-            self.db
-                .commit_multi_context_batch(batch, transaction)
-                .map_err(Into::into)
-        })
+        let result = collect_costs
+            .flat_map_ok(|_| self.commit_multi_context_batch(batch, changes, transaction));
+        self.observe_cost("delete", result)
+    }
+
+    /// Removes everything stored under the tree at `path` and `key` while
+    /// keeping the tree element itself, including its flags, in place.
+    /// Descendant subtrees are removed recursively, as a delete with
+    /// `allow_deleting_non_empty_trees` would, and the hashes up to the root
+    /// are updated. Returns the number of removed descendant elements.
+    pub fn clear_subtree<'b, B, P>(
+        &self,
+        path: P,
+        key: &[u8],
+        transaction: TransactionArg,
+    ) -> CostResult<u32, Error>
+    where
+        B: AsRef<[u8]> + 'b,
+        P: Into<SubtreePath<'b, B>>,
+    {
+        let path: SubtreePath<B> = path.into();
+        if let Some(transaction) = transaction {
+            self.clear_subtree_on_transaction(path, key, transaction)
+        } else {
+            let transaction = cost_return_on_error_no_add!(
+                &OperationCost::default(),
+                self.try_start_transaction()
+            );
+            self.clear_subtree_on_transaction(path, key, &transaction)
+                .flat_map_ok(|removed| self.commit_transaction(transaction).map_ok(|_| removed))
+        }
+    }
+
+    fn clear_subtree_on_transaction<B: AsRef<[u8]>>(
+        &self,
+        path: SubtreePath<B>,
+        key: &[u8],
+        transaction: &Transaction,
+    ) -> CostResult<u32, Error> {
+        let mut cost = OperationCost::default();
+
+        let element = cost_return_on_error!(
+            &mut cost,
+            self.get_raw(path.clone(), key, Some(transaction))
+        );
+        let empty_tree = match element {
+            Element::Tree(_, flags) => Element::empty_tree_with_flags(flags),
+            Element::SumTree(_, _, flags) => Element::empty_sum_tree_with_flags(flags),
+            _ => {
+                return Err(Error::InvalidPath("only trees can be cleared".to_owned()))
+                    .wrap_with_cost(cost)
+            }
+        };
+
+        let batch = StorageBatch::new();
+        let subtree_path = path.derive_owned_with_child(key);
+        let subtrees_paths = cost_return_on_error!(
+            &mut cost,
+            self.find_subtrees(&SubtreePath::from(&subtree_path), Some(transaction))
+        );
+        let mut removed: u32 = 0;
+        for subtree_path in subtrees_paths {
+            let p: SubtreePath<_> = subtree_path.as_slice().into();
+            let mut storage = self
+                .db
+                .get_transactional_storage_context(p, Some(&batch), transaction)
+                .unwrap_add_cost(&mut cost);
+            let mut raw_iter = Element::iterator(storage.raw_iter()).unwrap_add_cost(&mut cost);
+            while cost_return_on_error!(&mut cost, raw_iter.next_element()).is_some() {
+                removed += 1;
+            }
+            drop(raw_iter);
+            cost_return_on_error!(
+                &mut cost,
+                storage.clear().map_err(|e| {
+                    Error::CorruptedData(format!("unable to cleanup tree from storage: {e}"))
+                })
+            );
+        }
+        if removed == 0 {
+            return Ok(0).wrap_with_cost(cost);
+        }
+
+        // The tree element is updated in place to point to no root, and the
+        // change is propagated up to the root once
+        let mut parent_merk = cost_return_on_error!(
+            &mut cost,
+            self.open_transactional_merk_at_path(path.clone(), transaction, Some(&batch))
+        );
+        cost_return_on_error!(
+            &mut cost,
+            empty_tree.insert_subtree(&mut parent_merk, key, NULL_HASH, None)
+        );
+        let mut merk_cache: HashMap<SubtreePath<B>, Merk<PrefixedRocksDbTransactionContext>> =
+            HashMap::default();
+        merk_cache.insert(path.clone(), parent_merk);
+        cost_return_on_error!(
+            &mut cost,
+            self.propagate_changes_with_transaction(merk_cache, path.clone(), transaction, &batch)
+        );
+
+        let changes = self
+            .change_journal_retention
+            .map(|_| vec![(path.to_vec(), key.to_vec())]);
+        cost_return_on_error!(
+            &mut cost,
+            self.commit_multi_context_batch(batch, changes, Some(transaction))
+        );
+
+        Ok(removed).wrap_with_cost(cost)
     }
 
     /// Delete element with sectional storage function
@@ -156,6 +275,9 @@ impl GroveDb {
     ) -> CostResult<(), Error> {
         let options = options.unwrap_or_default();
         let batch = StorageBatch::new();
+        let changes = self
+            .change_journal_retention
+            .map(|_| vec![(path.to_vec(), key.to_vec())]);
 
         let collect_costs = self
             .delete_internal(
@@ -184,11 +306,7 @@ impl GroveDb {
             )
             .map_ok(|_| ());
 
-        collect_costs.flat_map_ok(|_| {
-            self.db
-                .commit_multi_context_batch(batch, transaction)
-                .map_err(Into::into)
-        })
+        collect_costs.flat_map_ok(|_| self.commit_multi_context_batch(batch, changes, transaction))
     }
 
     /// Delete if an empty tree
@@ -202,10 +320,14 @@ impl GroveDb {
         B: AsRef<[u8]> + 'b,
         P: Into<SubtreePath<'b, B>>,
     {
+        let path: SubtreePath<B> = path.into();
         let batch = StorageBatch::new();
+        let changes = self
+            .change_journal_retention
+            .map(|_| vec![(path.to_vec(), key.to_vec())]);
 
         let collect_costs = self.delete_if_empty_tree_with_sectional_storage_function(
-            path.into(),
+            path,
             key,
             transaction,
             &mut |_, removed_key_bytes, removed_value_bytes| {
@@ -217,11 +339,11 @@ impl GroveDb {
             &batch,
         );
 
-        collect_costs.flat_map_ok(|r| {
-            self.db
-                .commit_multi_context_batch(batch, transaction)
-                .map_err(Into::into)
-                .map_ok(|_| r)
+        collect_costs.flat_map_ok(|deleted| {
+            // Nothing changes if the tree wasn't empty
+            let changes = changes.filter(|_| deleted);
+            self.commit_multi_context_batch(batch, changes, transaction)
+                .map_ok(|_| deleted)
         })
     }
 
@@ -464,25 +586,32 @@ impl GroveDb {
                     Ok(false).wrap_with_cost(cost)
                 };
             } else if !is_empty {
-                let subtrees_paths = cost_return_on_error!(
-                    &mut cost,
-                    self.find_subtrees(&subtree_merk_path_ref, Some(transaction))
-                );
-                for subtree_path in subtrees_paths {
-                    let p: SubtreePath<_> = subtree_path.as_slice().into();
-                    let mut storage = self
-                        .db
-                        .get_transactional_storage_context(p, Some(batch), transaction)
-                        .unwrap_add_cost(&mut cost);
-
+                if options.defer_child_cleanup {
                     cost_return_on_error!(
                         &mut cost,
-                        storage.clear().map_err(|e| {
-                            Error::CorruptedData(format!(
-                                "unable to cleanup tree from storage: {e}",
-                            ))
-                        })
+                        self.add_gc_tombstone(&subtree_merk_path_ref, Some(transaction), batch)
                     );
+                } else {
+                    let subtrees_paths = cost_return_on_error!(
+                        &mut cost,
+                        self.find_subtrees(&subtree_merk_path_ref, Some(transaction))
+                    );
+                    for subtree_path in subtrees_paths {
+                        let p: SubtreePath<_> = subtree_path.as_slice().into();
+                        let mut storage = self
+                            .db
+                            .get_transactional_storage_context(p, Some(batch), transaction)
+                            .unwrap_add_cost(&mut cost);
+
+                        cost_return_on_error!(
+                            &mut cost,
+                            storage.clear().map_err(|e| {
+                                Error::CorruptedData(format!(
+                                    "unable to cleanup tree from storage: {e}",
+                                ))
+                            })
+                        );
+                    }
                 }
                 // todo: verify why we need to open the same? merk again
                 let storage = self
@@ -501,7 +630,7 @@ impl GroveDb {
                         Error::CorruptedData("cannot open a subtree with given root key".to_owned())
                     })
                 );
-                // We are deleting a tree, a tree uses 3 bytes
+                // We are deleting a tree, a tree uses 4 bytes
                 cost_return_on_error!(
                     &mut cost,
                     Element::delete_with_sectioned_removal_bytes(
@@ -528,7 +657,7 @@ impl GroveDb {
                     )
                 );
             } else {
-                // We are deleting a tree, a tree uses 3 bytes
+                // We are deleting a tree, a tree uses 4 bytes
                 cost_return_on_error!(
                     &mut cost,
                     Element::delete_with_sectioned_removal_bytes(
@@ -624,12 +753,19 @@ impl GroveDb {
                     Ok(false).wrap_with_cost(cost)
                 };
             } else {
-                if !is_empty {
+                if !is_empty && options.defer_child_cleanup {
+                    cost_return_on_error!(
+                        &mut cost,
+                        self.add_gc_tombstone(&SubtreePath::from(&subtree_merk_path), None, batch)
+                    );
+                } else if !is_empty {
                     let subtrees_paths = cost_return_on_error!(
                         &mut cost,
                         self.find_subtrees(&SubtreePath::from(&subtree_merk_path), None)
                     );
                     // TODO: dumb traversal should not be tolerated
+                    // `find_subtrees` returns paths shallow-to-deep, so iterating in reverse
+                    // clears the deepest subtrees first
                     for subtree_path in subtrees_paths.into_iter().rev() {
                         let p: SubtreePath<_> = subtree_path.as_slice().into();
                         let mut inner_subtree_to_delete_from = cost_return_on_error!(
@@ -684,6 +820,11 @@ impl GroveDb {
     /// Finds keys which are trees for a given subtree recursively.
     /// One element means a key of a `merk`, n > 1 elements mean relative path
     /// for a deeply nested subtree.
+    ///
+    /// Subtrees are traversed breadth-first, so the returned paths are ordered
+    /// shallow-to-deep, starting with `path` itself; subtrees at the same depth
+    /// keep the key order of their parents. Callers that need to process the
+    /// deepest subtrees first should iterate the result in reverse.
     pub(crate) fn find_subtrees<B: AsRef<[u8]>>(
         &self,
         path: &SubtreePath<B>,
@@ -702,10 +843,10 @@ impl GroveDb {
         // Update: there are pinned views into RocksDB to return slices of data, perhaps
         // there is something for iterators
 
-        let mut queue: Vec<Vec<Vec<u8>>> = vec![path.to_vec()];
-        let mut result: Vec<Vec<Vec<u8>>> = queue.clone();
+        let mut queue: VecDeque<Vec<Vec<u8>>> = VecDeque::from([path.to_vec()]);
+        let mut result: Vec<Vec<Vec<u8>>> = vec![path.to_vec()];
 
-        while let Some(q) = queue.pop() {
+        while let Some(q) = queue.pop_front() {
             let subtree_path: SubtreePath<Vec<u8>> = q.as_slice().into();
             // Get the correct subtree with q_ref as path
             storage_context_optional_tx!(self.db, subtree_path, None, transaction, storage, {
@@ -717,7 +858,7 @@ impl GroveDb {
                     if value.is_tree() {
                         let mut sub_path = q.clone();
                         sub_path.push(key.to_vec());
-                        queue.push(sub_path.clone());
+                        queue.push_back(sub_path.clone());
                         result.push(sub_path);
                     }
                 }
@@ -725,6 +866,104 @@ impl GroveDb {
         }
         Ok(result).wrap_with_cost(cost)
     }
+
+    /// Finds subtrees for a given subtree recursively, like `find_subtrees`,
+    /// and reports the feature type of each one: `SummedMerk` with the
+    /// aggregated sum for sum trees and `BasicMerk` for other trees.
+    ///
+    /// Types are taken from the elements met during the scan, so only `path`
+    /// itself needs an extra lookup in its parent.
+    pub fn find_subtrees_typed<B: AsRef<[u8]>>(
+        &self,
+        path: &SubtreePath<B>,
+        transaction: TransactionArg,
+    ) -> CostResult<Vec<(Vec<Vec<u8>>, TreeFeatureType)>, Error> {
+        let mut cost = OperationCost::default();
+
+        let root_feature_type = match path.derive_parent() {
+            Some((parent_path, key)) => subtree_feature_type(&cost_return_on_error!(
+                &mut cost,
+                self.get_raw(parent_path, key, transaction)
+            )),
+            None => BasicMerk,
+        };
+
+        let mut queue: VecDeque<Vec<Vec<u8>>> = VecDeque::from([path.to_vec()]);
+        let mut result = vec![(path.to_vec(), root_feature_type)];
+
+        while let Some(q) = queue.pop_front() {
+            let subtree_path: SubtreePath<Vec<u8>> = q.as_slice().into();
+            storage_context_optional_tx!(self.db, subtree_path, None, transaction, storage, {
+                let storage = storage.unwrap_add_cost(&mut cost);
+                let mut raw_iter = Element::iterator(storage.raw_iter()).unwrap_add_cost(&mut cost);
+                while let Some((key, value)) =
+                    cost_return_on_error!(&mut cost, raw_iter.next_element())
+                {
+                    if value.is_tree() {
+                        let mut sub_path = q.clone();
+                        sub_path.push(key.to_vec());
+                        queue.push_back(sub_path.clone());
+                        result.push((sub_path, subtree_feature_type(&value)));
+                    }
+                }
+            })
+        }
+        Ok(result).wrap_with_cost(cost)
+    }
+
+    /// Visits subtrees under `root_path`, including `root_path` itself, in the
+    /// same breadth-first order as `find_subtrees` without collecting them.
+    ///
+    /// `f` is called with the absolute path of each discovered subtree.
+    /// Returning `Ok(false)` skips the descendants of that subtree, while an
+    /// error stops the walk and is returned as is.
+    pub fn walk_subtrees<B, F>(
+        &self,
+        root_path: &SubtreePath<B>,
+        transaction: TransactionArg,
+        mut f: F,
+    ) -> CostResult<(), Error>
+    where
+        B: AsRef<[u8]>,
+        F: FnMut(&[Vec<u8>]) -> Result<bool, Error>,
+    {
+        let mut cost = OperationCost::default();
+
+        let root_path = root_path.to_vec();
+        if !cost_return_on_error_no_add!(&cost, f(&root_path)) {
+            return Ok(()).wrap_with_cost(cost);
+        }
+        let mut queue: VecDeque<Vec<Vec<u8>>> = VecDeque::from([root_path]);
+
+        while let Some(q) = queue.pop_front() {
+            let subtree_path: SubtreePath<Vec<u8>> = q.as_slice().into();
+            storage_context_optional_tx!(self.db, subtree_path, None, transaction, storage, {
+                let storage = storage.unwrap_add_cost(&mut cost);
+                let mut raw_iter = Element::iterator(storage.raw_iter()).unwrap_add_cost(&mut cost);
+                while let Some((key, value)) =
+                    cost_return_on_error!(&mut cost, raw_iter.next_element())
+                {
+                    if value.is_tree() {
+                        let mut sub_path = q.clone();
+                        sub_path.push(key.to_vec());
+                        if cost_return_on_error_no_add!(&cost, f(&sub_path)) {
+                            queue.push_back(sub_path);
+                        }
+                    }
+                }
+            })
+        }
+        Ok(()).wrap_with_cost(cost)
+    }
+}
+
+#[cfg(feature = "full")]
+/// Feature type of the subtree held by a tree element
+fn subtree_feature_type(element: &Element) -> TreeFeatureType {
+    match element {
+        Element::SumTree(_, sum_value, _) => SummedMerk(*sum_value),
+        _ => BasicMerk,
+    }
 }
 
 #[cfg(feature = "full")]
@@ -737,6 +976,7 @@ mod tests {
     use pretty_assertions::assert_eq;
 
     use crate::{
+        batch::GroveDbOp,
         operations::delete::{delete_up_tree::DeleteUpTreeOptions, DeleteOptions},
         tests::{
             common::EMPTY_PATH, make_empty_grovedb, make_test_grovedb, ANOTHER_TEST_LEAF, TEST_LEAF,
@@ -824,7 +1064,9 @@ mod tests {
             .unwrap(),
             Err(Error::PathParentLayerNotFound(_))
         ));
-        transaction.commit().expect("cannot commit transaction");
+        db.commit_transaction(transaction)
+            .unwrap()
+            .expect("cannot commit transaction");
         assert!(matches!(
             db.get([TEST_LEAF].as_ref(), b"key1", None).unwrap(),
             Err(Error::PathKeyNotFound(_))
@@ -1120,7 +1362,9 @@ mod tests {
             .unwrap(),
             Err(Error::PathParentLayerNotFound(_))
         ));
-        transaction.commit().expect("cannot commit transaction");
+        db.commit_transaction(transaction)
+            .unwrap()
+            .expect("cannot commit transaction");
         assert!(matches!(
             db.get([TEST_LEAF].as_ref(), b"key1", None).unwrap(),
             Err(Error::PathKeyNotFound(_))
@@ -1130,6 +1374,302 @@ mod tests {
             .expect("expected to get key4");
     }
 
+    #[test]
+    fn test_deferred_cleanup_of_deleted_subtrees() {
+        use grovedb_storage::{RawIterator, Storage, StorageContext};
+
+        let db = make_test_grovedb();
+        let storage_is_empty = |path: &[&[u8]]| {
+            let storage = db.db.get_storage_context(path.into(), None).unwrap();
+            let mut iter = storage.raw_iter();
+            iter.seek_to_first().unwrap();
+            !iter.valid().unwrap()
+        };
+
+        db.insert(
+            [TEST_LEAF].as_ref(),
+            b"key1",
+            Element::empty_tree(),
+            None,
+            None,
+        )
+        .unwrap()
+        .expect("successful subtree 1 insert");
+        db.insert(
+            [TEST_LEAF, b"key1"].as_ref(),
+            b"key2",
+            Element::empty_tree(),
+            None,
+            None,
+        )
+        .unwrap()
+        .expect("successful subtree 2 insert");
+        db.insert(
+            [TEST_LEAF, b"key1", b"key2"].as_ref(),
+            b"key3",
+            Element::new_item(b"ayy".to_vec()),
+            None,
+            None,
+        )
+        .unwrap()
+        .expect("successful value insert");
+
+        db.delete(
+            [TEST_LEAF].as_ref(),
+            b"key1",
+            Some(DeleteOptions {
+                allow_deleting_non_empty_trees: true,
+                deleting_non_empty_trees_returns_error: false,
+                defer_child_cleanup: true,
+                ..Default::default()
+            }),
+            None,
+        )
+        .unwrap()
+        .expect("unable to delete subtree");
+
+        // The parent link is gone right away, but descendants are still on disk
+        assert!(matches!(
+            db.get([TEST_LEAF].as_ref(), b"key1", None).unwrap(),
+            Err(Error::PathKeyNotFound(_))
+        ));
+        assert!(!storage_is_empty(&[TEST_LEAF, b"key1"]));
+        assert!(!storage_is_empty(&[TEST_LEAF, b"key1", b"key2"]));
+
+        // Garbage collection is incremental
+        assert_eq!(
+            db.collect_garbage(1, None)
+                .unwrap()
+                .expect("garbage collection failed"),
+            1
+        );
+        assert!(storage_is_empty(&[TEST_LEAF, b"key1"]));
+        assert!(!storage_is_empty(&[TEST_LEAF, b"key1", b"key2"]));
+
+        assert_eq!(
+            db.collect_garbage(10, None)
+                .unwrap()
+                .expect("garbage collection failed"),
+            1
+        );
+        assert!(storage_is_empty(&[TEST_LEAF, b"key1", b"key2"]));
+        assert_eq!(
+            db.collect_garbage(10, None)
+                .unwrap()
+                .expect("garbage collection failed"),
+            0
+        );
+
+        assert!(!storage_is_empty(&[TEST_LEAF]));
+        assert!(db.verify_grovedb().is_empty());
+    }
+
+    #[test]
+    fn test_deferred_cleanup_of_deleted_subtrees_with_transaction() {
+        use grovedb_storage::{RawIterator, Storage, StorageContext};
+
+        let db = make_test_grovedb();
+        let transaction = db.start_transaction();
+
+        db.insert(
+            [TEST_LEAF].as_ref(),
+            b"key1",
+            Element::empty_tree(),
+            None,
+            Some(&transaction),
+        )
+        .unwrap()
+        .expect("successful subtree insert");
+        db.insert(
+            [TEST_LEAF, b"key1"].as_ref(),
+            b"key2",
+            Element::new_item(b"ayy".to_vec()),
+            None,
+            Some(&transaction),
+        )
+        .unwrap()
+        .expect("successful value insert");
+
+        db.delete(
+            [TEST_LEAF].as_ref(),
+            b"key1",
+            Some(DeleteOptions {
+                allow_deleting_non_empty_trees: true,
+                defer_child_cleanup: true,
+                ..Default::default()
+            }),
+            Some(&transaction),
+        )
+        .unwrap()
+        .expect("unable to delete subtree");
+
+        // Nothing is pending outside of the transaction
+        assert_eq!(
+            db.collect_garbage(10, None)
+                .unwrap()
+                .expect("garbage collection failed"),
+            0
+        );
+        assert_eq!(
+            db.collect_garbage(10, Some(&transaction))
+                .unwrap()
+                .expect("garbage collection failed"),
+            1
+        );
+
+        let storage = db
+            .db
+            .get_transactional_storage_context(
+                [TEST_LEAF, b"key1"].as_ref().into(),
+                None,
+                &transaction,
+            )
+            .unwrap();
+        let mut iter = storage.raw_iter();
+        iter.seek_to_first().unwrap();
+        assert!(!iter.valid().unwrap());
+    }
+
+    #[test]
+    fn test_reinserted_tree_does_not_see_deleted_subtree() {
+        use grovedb_storage::{RawIterator, Storage, StorageContext};
+
+        use crate::{PathQuery, Query};
+
+        let db = make_test_grovedb();
+        let stored_keys = |path: &[&[u8]]| {
+            let storage = db.db.get_storage_context(path.into(), None).unwrap();
+            let mut iter = storage.raw_iter();
+            iter.seek_to_first().unwrap();
+            let mut keys = Vec::new();
+            while iter.valid().unwrap() {
+                keys.push(
+                    iter.key()
+                        .unwrap()
+                        .expect("valid iterator has a key")
+                        .to_vec(),
+                );
+                iter.next().unwrap();
+            }
+            keys
+        };
+        let query_keys = |path: &[&[u8]]| {
+            let mut query = Query::new();
+            query.insert_all();
+            let path_query = PathQuery::new_unsized(
+                path.iter().map(|segment| segment.to_vec()).collect(),
+                query,
+            );
+            db.query_raw(
+                &path_query,
+                true,
+                crate::query_result_type::QueryResultType::QueryKeyElementPairResultType,
+                None,
+            )
+            .unwrap()
+            .expect("successful query")
+            .0
+            .to_keys()
+        };
+
+        db.insert(
+            [TEST_LEAF].as_ref(),
+            b"key1",
+            Element::empty_tree(),
+            None,
+            None,
+        )
+        .unwrap()
+        .expect("successful subtree insert");
+        db.insert(
+            [TEST_LEAF, b"key1"].as_ref(),
+            b"key2",
+            Element::empty_tree(),
+            None,
+            None,
+        )
+        .unwrap()
+        .expect("successful subtree insert");
+        for (path, key) in [
+            (vec![TEST_LEAF, b"key1"], b"old1"),
+            (vec![TEST_LEAF, b"key1"], b"old2"),
+            (vec![TEST_LEAF, b"key1", b"key2"], b"old3"),
+        ] {
+            db.insert(
+                path.as_slice(),
+                key,
+                Element::new_item(b"ayy".to_vec()),
+                None,
+                None,
+            )
+            .unwrap()
+            .expect("successful value insert");
+        }
+
+        db.delete(
+            [TEST_LEAF].as_ref(),
+            b"key1",
+            Some(DeleteOptions {
+                allow_deleting_non_empty_trees: true,
+                defer_child_cleanup: true,
+                ..Default::default()
+            }),
+            None,
+        )
+        .unwrap()
+        .expect("unable to delete subtree");
+
+        // Both trees come back before their storage was collected
+        db.insert(
+            [TEST_LEAF].as_ref(),
+            b"key1",
+            Element::empty_tree(),
+            None,
+            None,
+        )
+        .unwrap()
+        .expect("successful subtree insert");
+        assert!(stored_keys(&[TEST_LEAF, b"key1"]).is_empty());
+        db.insert(
+            [TEST_LEAF, b"key1"].as_ref(),
+            b"new",
+            Element::new_item(b"ayy".to_vec()),
+            None,
+            None,
+        )
+        .unwrap()
+        .expect("successful value insert");
+        db.apply_batch(
+            vec![GroveDbOp::insert_op(
+                vec![TEST_LEAF.to_vec(), b"key1".to_vec()],
+                b"key2".to_vec(),
+                Element::empty_tree(),
+            )],
+            None,
+            None,
+        )
+        .unwrap()
+        .expect("successful batch");
+        assert!(stored_keys(&[TEST_LEAF, b"key1", b"key2"]).is_empty());
+
+        assert_eq!(
+            db.collect_garbage(10, None)
+                .unwrap()
+                .expect("garbage collection failed"),
+            0
+        );
+        assert_eq!(
+            query_keys(&[TEST_LEAF, b"key1"]),
+            vec![b"key2".to_vec(), b"new".to_vec()]
+        );
+        assert_eq!(
+            stored_keys(&[TEST_LEAF, b"key1"]),
+            vec![b"key2".to_vec(), b"new".to_vec()]
+        );
+        assert!(query_keys(&[TEST_LEAF, b"key1", b"key2"]).is_empty());
+        assert!(db.verify_grovedb().is_empty());
+    }
+
     #[test]
     fn test_recurring_deletion_through_subtrees_without_transaction() {
         let element = Element::new_item(b"ayy".to_vec());
@@ -1254,7 +1794,8 @@ mod tests {
         // 4 bytes for the key
         // 1 byte for key_size (required space for 36)
 
-        // Value -> 72
+        // Value -> 73
+        //   1 for the element format version
         //   1 for the flag option (but no flags)
         //   1 for the enum type item
         //   3 for "cat"
@@ -1262,7 +1803,7 @@ mod tests {
         //   1 for Basic Merk
         // 32 for node hash
         // 32 for value hash (trees have this for free)
-        // 1 byte for the value_size (required space for 70)
+        // 1 byte for the value_size (required space for 71)
 
         // Parent Hook -> 40
         // Key Bytes 4
@@ -1271,7 +1812,7 @@ mod tests {
         // Child Heights 2
         // Sum 1
 
-        // Total 37 + 72 + 40 = 149
+        // Total 37 + 73 + 40 = 150
 
         // Hash node calls
         // everything is empty, so no need for hashes?
@@ -1282,14 +1823,44 @@ mod tests {
                 storage_cost: StorageCost {
                     added_bytes: 0,
                     replaced_bytes: 0,
-                    removed_bytes: BasicStorageRemoval(149)
+                    removed_bytes: BasicStorageRemoval(150)
                 },
-                storage_loaded_bytes: 154, // todo: verify this
+                storage_loaded_bytes: 156, // todo: verify this
                 hash_node_calls: 0,
             }
         );
     }
 
+    #[test]
+    fn test_delete_one_large_item_cost() {
+        let db = make_empty_grovedb();
+        let tx = db.start_transaction();
+
+        let insertion_cost = db
+            .insert(
+                EMPTY_PATH,
+                b"key1",
+                Element::new_item(vec![7u8; 20000]),
+                None,
+                Some(&tx),
+            )
+            .cost_as_result()
+            .expect("expected to insert");
+
+        let cost = db
+            .delete(EMPTY_PATH, b"key1", None, Some(&tx))
+            .cost_as_result()
+            .expect("expected to delete");
+
+        // The value size takes 3 bytes of required space here, see
+        // test_one_insert_large_item_cost for the full breakdown
+        assert_eq!(insertion_cost.storage_cost.added_bytes, 20151);
+        assert_eq!(
+            cost.storage_cost.removed_bytes,
+            BasicStorageRemoval(insertion_cost.storage_cost.added_bytes)
+        );
+    }
+
     #[test]
     fn test_delete_one_sum_item_cost() {
         let db = make_empty_grovedb();
@@ -1332,7 +1903,8 @@ mod tests {
         // 4 bytes for the key
         // 1 byte for key_size (required space for 36)
 
-        // Value -> 85
+        // Value -> 86
+        //   1 for the element format version
         //   1 for the flag option (but no flags)
         //   1 for the enum type sum item
         //   9 for the sum item
@@ -1348,7 +1920,7 @@ mod tests {
         // Child Heights 2
         // Summed Merk 9
 
-        // Total 37 + 85 + 48 = 170
+        // Total 37 + 86 + 48 = 171
 
         // Hash node calls
         // everything is empty, so no need for hashes?
@@ -1358,10 +1930,10 @@ mod tests {
                 seek_count: 8, // todo: verify this
                 storage_cost: StorageCost {
                     added_bytes: 0,
-                    replaced_bytes: 91,
-                    removed_bytes: BasicStorageRemoval(170)
+                    replaced_bytes: 92,
+                    removed_bytes: BasicStorageRemoval(171)
                 },
-                storage_loaded_bytes: 418, // todo: verify this
+                storage_loaded_bytes: 423, // todo: verify this
                 hash_node_calls: 5,
             }
         );
@@ -1409,7 +1981,8 @@ mod tests {
         // 4 bytes for the key
         // 1 byte for key_size (required space for 36)
 
-        // Value -> 82
+        // Value -> 83
+        //   1 for the element format version
         //   1 for the flag option (but no flags)
         //   1 for the enum type sum item
         //   5 for the item
@@ -1426,7 +1999,7 @@ mod tests {
         // Child Heights 2
         // Summed Merk 9
 
-        // Total 37 + 82 + 48 = 167
+        // Total 37 + 83 + 48 = 168
 
         // Hash node calls
         // everything is empty, so no need for hashes?
@@ -1436,12 +2009,99 @@ mod tests {
                 seek_count: 8, // todo: verify this
                 storage_cost: StorageCost {
                     added_bytes: 0,
-                    replaced_bytes: 91,
-                    removed_bytes: BasicStorageRemoval(167)
+                    replaced_bytes: 92,
+                    removed_bytes: BasicStorageRemoval(168)
                 },
-                storage_loaded_bytes: 418, // todo: verify this
+                storage_loaded_bytes: 423, // todo: verify this
                 hash_node_calls: 5,
             }
         );
     }
+
+    #[test]
+    fn test_clear_subtree() {
+        let db = make_test_grovedb();
+        let flags = Some(b"flags".to_vec());
+        db.insert(
+            [TEST_LEAF].as_ref(),
+            b"tree",
+            Element::empty_tree_with_flags(flags.clone()),
+            None,
+            None,
+        )
+        .unwrap()
+        .expect("successful subtree insert");
+        db.insert(
+            [TEST_LEAF, b"tree"].as_ref(),
+            b"key1",
+            Element::new_item(b"value1".to_vec()),
+            None,
+            None,
+        )
+        .unwrap()
+        .expect("successful item insert");
+        db.insert(
+            [TEST_LEAF, b"tree"].as_ref(),
+            b"inner",
+            Element::empty_tree(),
+            None,
+            None,
+        )
+        .unwrap()
+        .expect("successful subtree insert");
+        db.insert(
+            [TEST_LEAF, b"tree", b"inner"].as_ref(),
+            b"key2",
+            Element::new_item(b"value2".to_vec()),
+            None,
+            None,
+        )
+        .unwrap()
+        .expect("successful item insert");
+
+        let removed = db
+            .clear_subtree([TEST_LEAF].as_ref(), b"tree", None)
+            .unwrap()
+            .expect("expected to clear subtree");
+        assert_eq!(removed, 3);
+
+        let element = db
+            .get([TEST_LEAF].as_ref(), b"tree", None)
+            .unwrap()
+            .expect("expected the tree to still exist");
+        assert_eq!(element, Element::empty_tree_with_flags(flags.clone()));
+        assert!(matches!(
+            db.get([TEST_LEAF, b"tree"].as_ref(), b"key1", None)
+                .unwrap(),
+            Err(Error::PathKeyNotFound(_))
+        ));
+        assert!(db
+            .get([TEST_LEAF, b"tree", b"inner"].as_ref(), b"key2", None)
+            .unwrap()
+            .is_err());
+
+        // The root hash must match a tree that was never populated
+        let fresh_db = make_test_grovedb();
+        fresh_db
+            .insert(
+                [TEST_LEAF].as_ref(),
+                b"tree",
+                Element::empty_tree_with_flags(flags),
+                None,
+                None,
+            )
+            .unwrap()
+            .expect("successful subtree insert");
+        assert_eq!(
+            db.root_hash(None).unwrap().unwrap(),
+            fresh_db.root_hash(None).unwrap().unwrap()
+        );
+
+        // Clearing an already empty tree removes nothing
+        let removed = db
+            .clear_subtree([TEST_LEAF].as_ref(), b"tree", None)
+            .unwrap()
+            .expect("expected to clear subtree");
+        assert_eq!(removed, 0);
+    }
 }
@@ -0,0 +1,217 @@
+// MIT LICENSE
+//
+// Copyright (c) 2021 Dash Core Group
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Subtree differences between two GroveDb instances
+
+#[cfg(feature = "full")]
+use std::cmp::Ordering;
+
+#[cfg(feature = "full")]
+use grovedb_costs::{cost_return_on_error, CostResult, CostsExt, OperationCost};
+#[cfg(feature = "full")]
+use grovedb_path::SubtreePath;
+#[cfg(feature = "full")]
+use grovedb_storage::{Storage, StorageContext};
+
+#[cfg(feature = "full")]
+use crate::{util::storage_context_optional_tx, Element, Error, GroveDb, TransactionArg};
+
+/// Keys of a subtree that differ between two GroveDb instances, in ascending
+/// key order
+#[cfg(feature = "full")]
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SubtreeDiff {
+    /// Keys present only in the subtree of the instance the diff was called on
+    pub only_in_self: Vec<Vec<u8>>,
+    /// Keys present only in the subtree of the other instance
+    pub only_in_other: Vec<Vec<u8>>,
+    /// Keys present in both subtrees but with different elements
+    pub differing_values: Vec<Vec<u8>>,
+}
+
+#[cfg(feature = "full")]
+impl SubtreeDiff {
+    /// Returns `true` if both subtrees hold the same elements
+    pub fn is_empty(&self) -> bool {
+        self.only_in_self.is_empty()
+            && self.only_in_other.is_empty()
+            && self.differing_values.is_empty()
+    }
+}
+
+#[cfg(feature = "full")]
+impl GroveDb {
+    /// Compares the elements of the subtree at `path` with the elements of the
+    /// same subtree of `other`, walking both in key order. Child subtrees are
+    /// compared as elements only, their content isn't diffed.
+    ///
+    /// The transaction is used to read this instance only, `other` is read
+    /// without a transaction.
+    pub fn diff_subtree<'b, B, P>(
+        &self,
+        other: &GroveDb,
+        path: P,
+        transaction: TransactionArg,
+    ) -> CostResult<SubtreeDiff, Error>
+    where
+        B: AsRef<[u8]> + 'b,
+        P: Into<SubtreePath<'b, B>>,
+    {
+        let mut cost = OperationCost::default();
+        let path: SubtreePath<B> = path.into();
+
+        cost_return_on_error!(
+            &mut cost,
+            self.check_subtree_exists_path_not_found(path.clone(), transaction)
+        );
+        cost_return_on_error!(
+            &mut cost,
+            other.check_subtree_exists_path_not_found(path.clone(), None)
+        );
+
+        let mut diff = SubtreeDiff::default();
+        storage_context_optional_tx!(self.db, path.clone(), None, transaction, storage, {
+            let storage = storage.unwrap_add_cost(&mut cost);
+            let other_storage = other
+                .db
+                .get_storage_context(path, None)
+                .unwrap_add_cost(&mut cost);
+            let mut iter = Element::iterator(storage.raw_iter()).unwrap_add_cost(&mut cost);
+            let mut other_iter =
+                Element::iterator(other_storage.raw_iter()).unwrap_add_cost(&mut cost);
+
+            let mut current = cost_return_on_error!(&mut cost, iter.next_element());
+            let mut other_current = cost_return_on_error!(&mut cost, other_iter.next_element());
+            loop {
+                let (advance, advance_other) = match (&current, &other_current) {
+                    (None, None) => break,
+                    (Some((key, _)), None) => {
+                        diff.only_in_self.push(key.clone());
+                        (true, false)
+                    }
+                    (None, Some((other_key, _))) => {
+                        diff.only_in_other.push(other_key.clone());
+                        (false, true)
+                    }
+                    (Some((key, element)), Some((other_key, other_element))) => {
+                        match key.cmp(other_key) {
+                            Ordering::Less => {
+                                diff.only_in_self.push(key.clone());
+                                (true, false)
+                            }
+                            Ordering::Greater => {
+                                diff.only_in_other.push(other_key.clone());
+                                (false, true)
+                            }
+                            Ordering::Equal => {
+                                if element != other_element {
+                                    diff.differing_values.push(key.clone());
+                                }
+                                (true, true)
+                            }
+                        }
+                    }
+                };
+                if advance {
+                    current = cost_return_on_error!(&mut cost, iter.next_element());
+                }
+                if advance_other {
+                    other_current = cost_return_on_error!(&mut cost, other_iter.next_element());
+                }
+            }
+        });
+
+        Ok(diff).wrap_with_cost(cost)
+    }
+}
+
+#[cfg(feature = "full")]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{make_test_grovedb, TEST_LEAF};
+
+    #[test]
+    fn test_diff_subtree() {
+        let db = make_test_grovedb();
+        let other_db = make_test_grovedb();
+
+        for (key, value) in [
+            (b"a".as_ref(), b"same".as_ref()),
+            (b"b", b"value"),
+            (b"c", b"same"),
+            (b"d", b"only here"),
+        ] {
+            db.insert(
+                [TEST_LEAF].as_ref(),
+                key,
+                Element::new_item(value.to_vec()),
+                None,
+                None,
+            )
+            .unwrap()
+            .expect("successful insert");
+        }
+        for (key, value) in [
+            (b"a".as_ref(), b"same".as_ref()),
+            (b"b", b"other value"),
+            (b"bb", b"only there"),
+            (b"c", b"same"),
+        ] {
+            other_db
+                .insert(
+                    [TEST_LEAF].as_ref(),
+                    key,
+                    Element::new_item(value.to_vec()),
+                    None,
+                    None,
+                )
+                .unwrap()
+                .expect("successful insert");
+        }
+
+        let diff = db
+            .diff_subtree(&other_db, [TEST_LEAF].as_ref(), None)
+            .unwrap()
+            .expect("successful diff");
+        assert_eq!(
+            diff,
+            SubtreeDiff {
+                only_in_self: vec![b"d".to_vec()],
+                only_in_other: vec![b"bb".to_vec()],
+                differing_values: vec![b"b".to_vec()],
+            }
+        );
+
+        let same = db
+            .diff_subtree(&db, [TEST_LEAF].as_ref(), None)
+            .unwrap()
+            .expect("successful diff");
+        assert!(same.is_empty());
+    }
+}
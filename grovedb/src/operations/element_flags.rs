@@ -0,0 +1,282 @@
+// MIT LICENSE
+//
+// Copyright (c) 2021 Dash Core Group
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Element flags operations
+
+#[cfg(feature = "full")]
+use std::collections::HashMap;
+
+#[cfg(feature = "full")]
+use grovedb_costs::{cost_return_on_error, CostResult, CostsExt, OperationCost};
+#[cfg(feature = "full")]
+use grovedb_merk::Merk;
+use grovedb_path::SubtreePath;
+#[cfg(feature = "full")]
+use grovedb_storage::{
+    rocksdb_storage::{PrefixedRocksDbStorageContext, PrefixedRocksDbTransactionContext},
+    StorageBatch,
+};
+
+#[cfg(feature = "full")]
+use crate::{Element, ElementFlags, Error, GroveDb, TransactionArg};
+
+#[cfg(feature = "full")]
+impl GroveDb {
+    /// Get the flags of the element at the specified path and key.
+    /// References are not followed, so the flags of the reference itself are
+    /// returned.
+    pub fn get_element_flags<'b, B, P>(
+        &self,
+        path: P,
+        key: &[u8],
+        transaction: TransactionArg,
+    ) -> CostResult<Option<ElementFlags>, Error>
+    where
+        B: AsRef<[u8]> + 'b,
+        P: Into<SubtreePath<'b, B>>,
+    {
+        self.get_raw(path.into(), key, transaction)
+            .map_ok(|element| element.get_flags_owned())
+    }
+
+    /// Replace the flags of the element at the specified path and key while
+    /// preserving its value. For trees the root key and the root hash of the
+    /// subtree are kept, so its contents are left untouched.
+    pub fn set_element_flags<'b, B, P>(
+        &self,
+        path: P,
+        key: &[u8],
+        flags: Option<ElementFlags>,
+        transaction: TransactionArg,
+    ) -> CostResult<(), Error>
+    where
+        B: AsRef<[u8]> + 'b,
+        P: Into<SubtreePath<'b, B>>,
+    {
+        let mut cost = OperationCost::default();
+        let path: SubtreePath<B> = path.into();
+
+        let mut element =
+            cost_return_on_error!(&mut cost, self.get_raw(path.clone(), key, transaction));
+        if element.get_flags() == &flags {
+            return Ok(()).wrap_with_cost(cost);
+        }
+        *element.get_flags_mut() = flags;
+
+        if !element.is_tree() {
+            // Items and references can simply be reinserted, the cost of the value is
+            // recomputed including the new flags
+            return self
+                .insert(path, key, element, None, transaction)
+                .add_cost(cost);
+        }
+
+        let batch = StorageBatch::new();
+        let subtree_path = path.derive_owned_with_child(key);
+        let changes = self
+            .change_journal_retention
+            .map(|_| vec![(path.to_vec(), key.to_vec())]);
+
+        if let Some(transaction) = transaction {
+            let mut parent_merk = cost_return_on_error!(
+                &mut cost,
+                self.open_transactional_merk_at_path(path.clone(), transaction, Some(&batch))
+            );
+            let subtree_root_hash = cost_return_on_error!(
+                &mut cost,
+                self.open_transactional_merk_at_path(
+                    SubtreePath::from(&subtree_path),
+                    transaction,
+                    Some(&batch)
+                )
+            )
+            .root_hash()
+            .unwrap_add_cost(&mut cost);
+            cost_return_on_error!(
+                &mut cost,
+                element.insert_subtree(&mut parent_merk, key, subtree_root_hash, None)
+            );
+            let mut merk_cache: HashMap<SubtreePath<B>, Merk<PrefixedRocksDbTransactionContext>> =
+                HashMap::default();
+            merk_cache.insert(path.clone(), parent_merk);
+            cost_return_on_error!(
+                &mut cost,
+                self.propagate_changes_with_transaction(merk_cache, path, transaction, &batch)
+            );
+        } else {
+            let mut parent_merk = cost_return_on_error!(
+                &mut cost,
+                self.open_non_transactional_merk_at_path(path.clone(), Some(&batch))
+            );
+            let subtree_root_hash = cost_return_on_error!(
+                &mut cost,
+                self.open_non_transactional_merk_at_path(
+                    SubtreePath::from(&subtree_path),
+                    Some(&batch)
+                )
+            )
+            .root_hash()
+            .unwrap_add_cost(&mut cost);
+            cost_return_on_error!(
+                &mut cost,
+                element.insert_subtree(&mut parent_merk, key, subtree_root_hash, None)
+            );
+            let mut merk_cache: HashMap<SubtreePath<B>, Merk<PrefixedRocksDbStorageContext>> =
+                HashMap::default();
+            merk_cache.insert(path.clone(), parent_merk);
+            cost_return_on_error!(
+                &mut cost,
+                self.propagate_changes_without_transaction(merk_cache, path, &batch)
+            );
+        }
+
+        self.commit_multi_context_batch(batch, changes, transaction)
+            .add_cost(cost)
+    }
+}
+
+#[cfg(feature = "full")]
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use crate::{
+        tests::{make_test_grovedb, TEST_LEAF},
+        Element,
+    };
+
+    #[test]
+    fn test_set_and_get_item_flags() {
+        let db = make_test_grovedb();
+        db.insert(
+            [TEST_LEAF].as_ref(),
+            b"key",
+            Element::new_item(b"value".to_vec()),
+            None,
+            None,
+        )
+        .unwrap()
+        .expect("successful insert");
+
+        assert_eq!(
+            db.get_element_flags([TEST_LEAF].as_ref(), b"key", None)
+                .unwrap()
+                .expect("expected to get flags"),
+            None
+        );
+
+        db.set_element_flags([TEST_LEAF].as_ref(), b"key", Some(vec![1, 2, 3]), None)
+            .unwrap()
+            .expect("expected to set flags");
+
+        assert_eq!(
+            db.get_element_flags([TEST_LEAF].as_ref(), b"key", None)
+                .unwrap()
+                .expect("expected to get flags"),
+            Some(vec![1, 2, 3])
+        );
+        assert_eq!(
+            db.get([TEST_LEAF].as_ref(), b"key", None)
+                .unwrap()
+                .expect("expected to get element"),
+            Element::new_item_with_flags(b"value".to_vec(), Some(vec![1, 2, 3]))
+        );
+
+        db.set_element_flags([TEST_LEAF].as_ref(), b"key", None, None)
+            .unwrap()
+            .expect("expected to clear flags");
+
+        assert_eq!(
+            db.get([TEST_LEAF].as_ref(), b"key", None)
+                .unwrap()
+                .expect("expected to get element"),
+            Element::new_item(b"value".to_vec())
+        );
+    }
+
+    #[test]
+    fn test_set_and_get_tree_flags_preserves_contents() {
+        let db = make_test_grovedb();
+        db.insert(
+            [TEST_LEAF].as_ref(),
+            b"tree",
+            Element::empty_tree(),
+            None,
+            None,
+        )
+        .unwrap()
+        .expect("successful tree insert");
+        db.insert(
+            [TEST_LEAF, b"tree"].as_ref(),
+            b"key",
+            Element::new_item(b"value".to_vec()),
+            None,
+            None,
+        )
+        .unwrap()
+        .expect("successful item insert");
+
+        let root_hash = db.root_hash(None).unwrap().unwrap();
+
+        let transaction = db.start_transaction();
+        db.set_element_flags(
+            [TEST_LEAF].as_ref(),
+            b"tree",
+            Some(vec![7, 7]),
+            Some(&transaction),
+        )
+        .unwrap()
+        .expect("expected to set flags");
+
+        // Not visible outside of the transaction yet
+        assert_eq!(
+            db.get_element_flags([TEST_LEAF].as_ref(), b"tree", None)
+                .unwrap()
+                .expect("expected to get flags"),
+            None
+        );
+        db.commit_transaction(transaction)
+            .unwrap()
+            .expect("expected to commit transaction");
+
+        assert_eq!(
+            db.get_element_flags([TEST_LEAF].as_ref(), b"tree", None)
+                .unwrap()
+                .expect("expected to get flags"),
+            Some(vec![7, 7])
+        );
+        assert_eq!(
+            db.get([TEST_LEAF, b"tree"].as_ref(), b"key", None)
+                .unwrap()
+                .expect("expected the subtree contents to be kept"),
+            Element::new_item(b"value".to_vec())
+        );
+        assert_ne!(root_hash, db.root_hash(None).unwrap().unwrap());
+        assert!(db.verify_grovedb().is_empty());
+    }
+}
@@ -0,0 +1,322 @@
+// MIT LICENSE
+//
+// Copyright (c) 2021 Dash Core Group
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Deferred cleanup of deleted subtrees
+
+#[cfg(feature = "full")]
+use std::sync::atomic::Ordering;
+
+#[cfg(feature = "full")]
+use grovedb_costs::{
+    cost_return_on_error, cost_return_on_error_no_add, CostContext, CostResult, CostsExt,
+    OperationCost,
+};
+#[cfg(feature = "full")]
+use grovedb_path::{decode_subtree_path, SubtreePath};
+#[cfg(feature = "full")]
+use grovedb_storage::{RawIterator, StorageBatch, StorageContext};
+
+#[cfg(feature = "full")]
+use crate::{
+    batch::{GroveDbOp, Op},
+    util::{meta_storage_context_optional_tx, storage_context_optional_tx},
+    Element, Error, GroveDb, TransactionArg,
+};
+
+/// Metadata key prefix of tombstones marking subtrees which storage is waiting
+/// to be cleared, the rest of the key is the encoded subtree path
+#[cfg(feature = "full")]
+const GC_TOMBSTONE_PREFIX: &[u8] = b"gc_tombstone";
+
+#[cfg(feature = "full")]
+fn tombstone_key<B: AsRef<[u8]>>(path: &SubtreePath<B>) -> Vec<u8> {
+    let mut key = GC_TOMBSTONE_PREFIX.to_vec();
+    key.extend(path.encode());
+    key
+}
+
+#[cfg(feature = "full")]
+impl GroveDb {
+    /// Record a tombstone for the subtree at `path` that was unlinked from its
+    /// parent, so its storage and the storage of its descendants will be
+    /// cleared by [GroveDb::collect_garbage].
+    pub(crate) fn add_gc_tombstone<B: AsRef<[u8]>>(
+        &self,
+        path: &SubtreePath<B>,
+        transaction: TransactionArg,
+        batch: &StorageBatch,
+    ) -> CostResult<(), Error> {
+        let mut cost = OperationCost::default();
+
+        meta_storage_context_optional_tx!(self.db, Some(batch), transaction, meta_storage, {
+            cost_return_on_error_no_add!(
+                &cost,
+                meta_storage
+                    .unwrap_add_cost(&mut cost)
+                    .put_meta(tombstone_key(path), &[], None)
+                    .unwrap_add_cost(&mut cost)
+                    .map_err(|e| e.into())
+            );
+        });
+        self.gc_pending.store(true, Ordering::Release);
+
+        Ok(()).wrap_with_cost(cost)
+    }
+
+    /// Drop the tombstone of the subtree at `path`
+    fn remove_gc_tombstone<B: AsRef<[u8]>>(
+        &self,
+        path: &SubtreePath<B>,
+        transaction: TransactionArg,
+        batch: &StorageBatch,
+    ) -> CostResult<(), Error> {
+        let mut cost = OperationCost::default();
+
+        meta_storage_context_optional_tx!(self.db, Some(batch), transaction, meta_storage, {
+            cost_return_on_error_no_add!(
+                &cost,
+                meta_storage
+                    .unwrap_add_cost(&mut cost)
+                    .delete_meta(tombstone_key(path), None)
+                    .unwrap_add_cost(&mut cost)
+                    .map_err(|e| e.into())
+            );
+        });
+
+        Ok(()).wrap_with_cost(cost)
+    }
+
+    /// Returns `true` if any subtree is waiting to be collected
+    pub(crate) fn has_gc_tombstones(&self, transaction: TransactionArg) -> CostResult<bool, Error> {
+        self.next_gc_tombstone(transaction)
+            .map_ok(|path| path.is_some())
+    }
+
+    /// Clears the storage left behind by a deleted subtree at `path` before a
+    /// tree is inserted there again, so the new tree starts empty. Child
+    /// subtrees of the deleted one get tombstones of their own.
+    ///
+    /// Nothing is read unless tombstones were recorded or found at open.
+    pub(crate) fn clear_tombstoned_subtree<B: AsRef<[u8]>>(
+        &self,
+        path: &SubtreePath<B>,
+        transaction: TransactionArg,
+        batch: &StorageBatch,
+    ) -> CostResult<(), Error> {
+        let mut cost = OperationCost::default();
+
+        if !self.gc_pending.load(Ordering::Acquire) {
+            return Ok(()).wrap_with_cost(cost);
+        }
+
+        let has_tombstone =
+            meta_storage_context_optional_tx!(self.db, None, transaction, meta_storage, {
+                cost_return_on_error!(
+                    &mut cost,
+                    meta_storage
+                        .unwrap_add_cost(&mut cost)
+                        .get_meta(tombstone_key(path))
+                        .map_err(Into::into)
+                )
+                .is_some()
+            });
+        if has_tombstone {
+            cost_return_on_error!(
+                &mut cost,
+                self.clear_deleted_subtree(path, transaction, batch)
+            );
+            cost_return_on_error!(
+                &mut cost,
+                self.remove_gc_tombstone(path, transaction, batch)
+            );
+        }
+
+        Ok(()).wrap_with_cost(cost)
+    }
+
+    /// Clears storage of up to `max_prefixes` subtrees left behind by deletes
+    /// done with [defer_child_cleanup](crate::operations::delete::DeleteOptions::defer_child_cleanup)
+    /// and returns how many subtrees were cleared. Subtrees are processed top
+    /// down: clearing a subtree schedules its child subtrees, so the function
+    /// can be called repeatedly until it returns less than `max_prefixes`.
+    ///
+    /// A tree inserted again at a deleted path clears the storage left behind
+    /// when it is inserted, so it never sees nodes of the deleted tree.
+    pub fn collect_garbage(
+        &self,
+        max_prefixes: usize,
+        transaction: TransactionArg,
+    ) -> CostResult<usize, Error> {
+        let mut cost = OperationCost::default();
+        let mut cleared = 0;
+
+        while cleared < max_prefixes {
+            let Some(path) = cost_return_on_error!(&mut cost, self.next_gc_tombstone(transaction))
+            else {
+                break;
+            };
+            let path = SubtreePath::from(path.as_slice());
+
+            let batch = StorageBatch::new();
+
+            if !self
+                .is_live_tree(&path, transaction)
+                .unwrap_add_cost(&mut cost)
+            {
+                cost_return_on_error!(
+                    &mut cost,
+                    self.clear_deleted_subtree(&path, transaction, &batch)
+                );
+                cleared += 1;
+            }
+
+            cost_return_on_error!(
+                &mut cost,
+                self.remove_gc_tombstone(&path, transaction, &batch)
+            );
+
+            // Only storage no element refers to anymore is cleared, so there are no
+            // changes to journal
+            cost_return_on_error!(
+                &mut cost,
+                self.commit_multi_context_batch(batch, None, transaction)
+            );
+        }
+
+        Ok(cleared).wrap_with_cost(cost)
+    }
+
+    /// Same as [GroveDb::clear_tombstoned_subtree] for every tree inserted by
+    /// batch operations
+    pub(crate) fn clear_tombstoned_subtrees_of_ops(
+        &self,
+        ops: &[GroveDbOp],
+        transaction: TransactionArg,
+        batch: &StorageBatch,
+    ) -> CostResult<(), Error> {
+        let mut cost = OperationCost::default();
+
+        for op in ops {
+            if let Op::Insert { element } | Op::Replace { element } = &op.op {
+                if element.is_tree() {
+                    let mut subtree_path = op.path.to_path();
+                    subtree_path.push(op.key.get_key_clone());
+                    cost_return_on_error!(
+                        &mut cost,
+                        self.clear_tombstoned_subtree(
+                            &SubtreePath::from(subtree_path.as_slice()),
+                            transaction,
+                            batch
+                        )
+                    );
+                }
+            }
+        }
+
+        Ok(()).wrap_with_cost(cost)
+    }
+
+    /// Get the path of the first pending tombstone, if any
+    fn next_gc_tombstone(
+        &self,
+        transaction: TransactionArg,
+    ) -> CostResult<Option<Vec<Vec<u8>>>, Error> {
+        let mut cost = OperationCost::default();
+
+        meta_storage_context_optional_tx!(self.db, None, transaction, meta_storage, {
+            let mut iter = meta_storage.unwrap_add_cost(&mut cost).raw_iter_meta();
+            iter.seek(GC_TOMBSTONE_PREFIX).unwrap_add_cost(&mut cost);
+            if !iter.valid().unwrap_add_cost(&mut cost) {
+                return Ok(None).wrap_with_cost(cost);
+            }
+            let Some(encoded_path) = iter
+                .key()
+                .unwrap_add_cost(&mut cost)
+                .and_then(|key| key.strip_prefix(GC_TOMBSTONE_PREFIX))
+            else {
+                return Ok(None).wrap_with_cost(cost);
+            };
+            let path = cost_return_on_error_no_add!(
+                &cost,
+                decode_subtree_path(encoded_path).map_err(|e| {
+                    Error::CorruptedData(format!("invalid garbage collection tombstone: {e}"))
+                })
+            );
+
+            Ok(Some(path.to_vec())).wrap_with_cost(cost)
+        })
+    }
+
+    /// Check if a tree is linked from its parent at `path`
+    fn is_live_tree<B: AsRef<[u8]>>(
+        &self,
+        path: &SubtreePath<B>,
+        transaction: TransactionArg,
+    ) -> CostContext<bool> {
+        let Some((parent_path, key)) = path.derive_parent() else {
+            return true.wrap_with_cost(Default::default());
+        };
+        self.get_raw(parent_path, key, transaction)
+            .map(|result| matches!(result, Ok(element) if element.is_tree()))
+    }
+
+    /// Delete all data entries of the subtree at `path` and add tombstones for
+    /// its child subtrees
+    fn clear_deleted_subtree<B: AsRef<[u8]>>(
+        &self,
+        path: &SubtreePath<B>,
+        transaction: TransactionArg,
+        batch: &StorageBatch,
+    ) -> CostResult<(), Error> {
+        let mut cost = OperationCost::default();
+
+        let mut child_keys = Vec::new();
+        storage_context_optional_tx!(self.db, path.clone(), Some(batch), transaction, storage, {
+            let storage = storage.unwrap_add_cost(&mut cost);
+            let mut raw_iter = Element::iterator(storage.raw_iter()).unwrap_add_cost(&mut cost);
+            while let Some((key, element)) =
+                cost_return_on_error!(&mut cost, raw_iter.next_element())
+            {
+                cost_return_on_error!(&mut cost, storage.delete(&key, None).map_err(Into::into));
+                if element.is_tree() {
+                    child_keys.push(key);
+                }
+            }
+        });
+
+        for key in child_keys {
+            let child_path = path.derive_owned_with_child(key);
+            cost_return_on_error!(
+                &mut cost,
+                self.add_gc_tombstone(&SubtreePath::from(&child_path), transaction, batch)
+            );
+        }
+
+        Ok(()).wrap_with_cost(cost)
+    }
+}
@@ -36,31 +36,43 @@ mod query;
 mod worst_case;
 
 #[cfg(feature = "full")]
-use std::collections::HashSet;
+use std::{cell::Cell, collections::HashMap};
 
 use grovedb_costs::cost_return_on_error_no_add;
 #[cfg(feature = "full")]
-use grovedb_costs::{cost_return_on_error, CostResult, CostsExt, OperationCost};
+use grovedb_costs::{cost_return_on_error, CostContext, CostResult, CostsExt, OperationCost};
+#[cfg(feature = "full")]
+use grovedb_merk::Merk;
 use grovedb_path::SubtreePath;
 #[cfg(feature = "full")]
-use grovedb_storage::StorageContext;
+use grovedb_storage::{RawIterator, StorageContext};
 
 #[cfg(feature = "full")]
 use crate::{
+    element::MaxReferenceHop,
+    helpers::raw_decode,
     reference_path::{path_from_reference_path_type, path_from_reference_qualified_path_type},
-    util::storage_context_optional_tx,
-    Element, Error, GroveDb, Transaction, TransactionArg,
+    util::{merk_optional_tx, storage_context_optional_tx},
+    Element, Error, GroveDb, PathErrorDetail, Transaction, TransactionArg,
 };
 
 #[cfg(feature = "full")]
 /// Limit of possible indirections
 pub const MAX_REFERENCE_HOPS: usize = 10;
 
+#[cfg(feature = "full")]
+thread_local! {
+    /// Visited paths of [`GroveDb::follow_reference`], pooled so following
+    /// references doesn't allocate a new collection on every call
+    static VISITED_REFERENCE_PATHS: Cell<Vec<Vec<Vec<u8>>>> = Cell::new(Vec::new());
+}
+
 #[cfg(feature = "full")]
 impl GroveDb {
     /// Get an element from the backing store
     /// Merk Caching is on by default
     /// use get_caching_optional if no caching is desired
+    /// An empty key is a valid key, as it is for insertion and deletion
     pub fn get<'b, B, P>(
         &self,
         path: P,
@@ -71,7 +83,10 @@ impl GroveDb {
         B: AsRef<[u8]> + 'b,
         P: Into<SubtreePath<'b, B>>,
     {
-        self.get_caching_optional(path.into(), key, true, transaction)
+        self.observe_cost(
+            "get",
+            self.get_caching_optional(path.into(), key, true, transaction),
+        )
     }
 
     /// Get an element from the backing store
@@ -89,19 +104,41 @@ impl GroveDb {
             &mut cost,
             self.get_raw_caching_optional(path.clone(), key, allow_cache, transaction)
         ) {
-            Element::Reference(reference_path, ..) => {
+            Element::Reference(reference_path, max_reference_hop, _) => {
                 let path_owned = cost_return_on_error!(
                     &mut cost,
                     path_from_reference_path_type(reference_path, &path.to_vec(), Some(key))
                         .wrap_with_cost(OperationCost::default())
                 );
-                self.follow_reference(path_owned.as_slice().into(), allow_cache, transaction)
-                    .add_cost(cost)
+                self.follow_reference_with_max_hops(
+                    path_owned.as_slice().into(),
+                    max_reference_hop,
+                    allow_cache,
+                    transaction,
+                )
+                .add_cost(cost)
             }
             other => Ok(other).wrap_with_cost(cost),
         }
     }
 
+    /// Get the encoded size of the element at `key`. If the element is a
+    /// reference its chain is followed, within the hop limit, and the size of
+    /// the final element is returned; the cost includes every hop.
+    pub fn resolved_element_size<'b, B, P>(
+        &self,
+        path: P,
+        key: &[u8],
+        transaction: TransactionArg,
+    ) -> CostResult<u32, Error>
+    where
+        B: AsRef<[u8]> + 'b,
+        P: Into<SubtreePath<'b, B>>,
+    {
+        self.get_caching_optional(path.into(), key, true, transaction)
+            .map_ok(|element| element.serialized_size() as u32)
+    }
+
     /// Return the Element that a reference points to.
     /// If the reference points to another reference, keep following until
     /// base element is reached.
@@ -110,12 +147,45 @@ impl GroveDb {
         path: SubtreePath<B>,
         allow_cache: bool,
         transaction: TransactionArg,
+    ) -> CostResult<Element, Error> {
+        self.follow_reference_with_max_hops(path, None, allow_cache, transaction)
+    }
+
+    /// Return the Element that a reference points to, fetching at most
+    /// `max_reference_hop` elements starting from `path`, the reference's own
+    /// annotation, or [`MAX_REFERENCE_HOPS`] if it has none. A reference
+    /// annotated with one hop must point right at its base element.
+    pub fn follow_reference_with_max_hops<B: AsRef<[u8]>>(
+        &self,
+        path: SubtreePath<B>,
+        max_reference_hop: MaxReferenceHop,
+        allow_cache: bool,
+        transaction: TransactionArg,
+    ) -> CostResult<Element, Error> {
+        let max_hops = max_hops_of(max_reference_hop);
+        let mut visited = VISITED_REFERENCE_PATHS.with(Cell::take);
+        let result =
+            self.follow_reference_visiting(path, max_hops, allow_cache, transaction, &mut visited);
+        visited.clear();
+        VISITED_REFERENCE_PATHS.with(|pool| pool.set(visited));
+        result
+    }
+
+    /// Follows references from `path` for at most `max_hops` fetches, keeping
+    /// the paths of the references already fetched in `visited` to detect
+    /// cycles
+    fn follow_reference_visiting<B: AsRef<[u8]>>(
+        &self,
+        path: SubtreePath<B>,
+        max_hops: usize,
+        allow_cache: bool,
+        transaction: TransactionArg,
+        visited: &mut Vec<Vec<Vec<u8>>>,
     ) -> CostResult<Element, Error> {
         let mut cost = OperationCost::default();
 
-        let mut hops_left = MAX_REFERENCE_HOPS;
+        let mut hops_left = max_hops;
         let mut current_element;
-        let mut visited = HashSet::new();
         // TODO, still have to do because of references handling
         let mut current_path = path.to_vec();
 
@@ -127,36 +197,318 @@ impl GroveDb {
                 current_element = cost_return_on_error!(
                     &mut cost,
                     self.get_raw_caching_optional(path_slice.into(), key, allow_cache, transaction)
-                        .map_err(|e| match e {
-                            Error::PathParentLayerNotFound(p) => {
-                                Error::CorruptedReferencePathParentLayerNotFound(p)
-                            }
-                            Error::PathKeyNotFound(p) => {
-                                Error::CorruptedReferencePathKeyNotFound(p)
-                            }
-                            Error::PathNotFound(p) => {
-                                Error::CorruptedReferencePathNotFound(p)
-                            }
-                            _ => e,
-                        })
+                        .map_err(reference_target_error)
                 )
             } else {
                 return Err(Error::CorruptedPath("empty path")).wrap_with_cost(cost);
             }
-            visited.insert(current_path.clone());
             match current_element {
                 Element::Reference(reference_path, ..) => {
-                    current_path = cost_return_on_error!(
+                    let next_path = cost_return_on_error!(
                         &mut cost,
                         path_from_reference_qualified_path_type(reference_path, &current_path)
                             .wrap_with_cost(OperationCost::default())
-                    )
+                    );
+                    // The path is moved rather than cloned into the visited
+                    // paths, the last one is the last reference followed
+                    visited.push(std::mem::replace(&mut current_path, next_path));
                 }
                 other => return Ok(other).wrap_with_cost(cost),
             }
             hops_left -= 1;
         }
-        Err(Error::ReferenceLimit).wrap_with_cost(cost)
+        Err(Error::ReferenceChainTooLong {
+            hops: max_hops,
+            last_path: visited.pop().unwrap_or_default(),
+        })
+        .wrap_with_cost(cost)
+    }
+
+    /// Resolves a batch of `(path, key)` locations to the elements they
+    /// ultimately point to, following references like [`GroveDb::get`] does,
+    /// within the max hops of each starting reference. Results are
+    /// index-aligned with `refs`. Resolved chains are cached across the batch,
+    /// so references sharing an intermediate reference only walk it once.
+    pub fn follow_references(
+        &self,
+        refs: &[(Vec<Vec<u8>>, Vec<u8>)],
+        transaction: TransactionArg,
+    ) -> CostResult<Vec<Element>, Error> {
+        let mut cost = OperationCost::default();
+
+        // Qualified path -> resolved element, the number of fetches it took
+        // to resolve it from that path and the max hops of the element stored
+        // there, which applies when a chain starts from it
+        let mut resolved: HashMap<Vec<Vec<u8>>, (Element, usize, MaxReferenceHop)> = HashMap::new();
+        let mut results = Vec::with_capacity(refs.len());
+
+        for (path, key) in refs {
+            let mut current_path = path.clone();
+            current_path.push(key.clone());
+            let mut chain: Vec<(Vec<Vec<u8>>, MaxReferenceHop)> = Vec::new();
+            let mut max_hops = MAX_REFERENCE_HOPS;
+
+            // The starting element is fetched on top of the hops allowed to
+            // follow references, as in `get`
+            let (element, tail_fetches) = loop {
+                if let Some((element, fetches, max_reference_hop)) = resolved.get(&current_path) {
+                    if chain.is_empty() {
+                        max_hops = max_hops_of(*max_reference_hop);
+                    }
+                    if chain.len() + fetches > max_hops + 1 {
+                        return Err(Error::ReferenceChainTooLong {
+                            hops: max_hops,
+                            last_path: current_path,
+                        })
+                        .wrap_with_cost(cost);
+                    }
+                    break (element.clone(), *fetches);
+                }
+                if chain.iter().any(|(path, _)| path == &current_path) {
+                    return Err(Error::CyclicReference).wrap_with_cost(cost);
+                }
+                if chain.len() > max_hops {
+                    return Err(Error::ReferenceChainTooLong {
+                        hops: max_hops,
+                        last_path: chain.pop().map(|(path, _)| path).unwrap_or_default(),
+                    })
+                    .wrap_with_cost(cost);
+                }
+
+                let (key, path_slice) = cost_return_on_error_no_add!(
+                    &cost,
+                    current_path
+                        .split_last()
+                        .ok_or(Error::CorruptedPath("empty path"))
+                );
+                let fetched =
+                    self.get_raw_caching_optional(path_slice.into(), key, true, transaction);
+                let current_element = if chain.is_empty() {
+                    cost_return_on_error!(&mut cost, fetched)
+                } else {
+                    cost_return_on_error!(&mut cost, fetched.map_err(reference_target_error))
+                };
+                let max_reference_hop = match current_element {
+                    Element::Reference(_, max_reference_hop, _) => max_reference_hop,
+                    _ => None,
+                };
+                if chain.is_empty() {
+                    max_hops = max_hops_of(max_reference_hop);
+                }
+                chain.push((current_path.clone(), max_reference_hop));
+
+                match current_element {
+                    Element::Reference(reference_path, ..) => {
+                        current_path = cost_return_on_error_no_add!(
+                            &cost,
+                            path_from_reference_qualified_path_type(reference_path, &current_path)
+                        );
+                    }
+                    other => break (other, 0),
+                }
+            };
+
+            let chain_len = chain.len();
+            for (position, (chain_path, max_reference_hop)) in chain.into_iter().enumerate() {
+                resolved.insert(
+                    chain_path,
+                    (
+                        element.clone(),
+                        chain_len - position + tail_fetches,
+                        max_reference_hop,
+                    ),
+                );
+            }
+            results.push(element);
+        }
+
+        Ok(results).wrap_with_cost(cost)
+    }
+
+    /// Gets the elements at `(path, key)` locations which may belong to
+    /// different subtrees, following references like [`GroveDb::get`] does.
+    /// Requests to the same subtree share a single opened Merk. Results are
+    /// index-aligned with `requests` and hold per-request errors, so a missing
+    /// key or subtree doesn't fail the other requests.
+    pub fn get_multi(
+        &self,
+        requests: &[(Vec<Vec<u8>>, Vec<u8>)],
+        transaction: TransactionArg,
+    ) -> CostResult<Vec<Result<Element, Error>>, Error> {
+        let mut cost = OperationCost::default();
+
+        let mut requests_by_path: HashMap<&[Vec<u8>], Vec<usize>> = HashMap::new();
+        for (index, (path, _)) in requests.iter().enumerate() {
+            requests_by_path
+                .entry(path.as_slice())
+                .or_default()
+                .push(index);
+        }
+
+        let mut results: Vec<Option<Result<Element, Error>>> =
+            requests.iter().map(|_| None).collect();
+        for (path, indices) in requests_by_path {
+            let subtree_path: SubtreePath<Vec<u8>> = path.into();
+            let elements = if let Some(tx) = transaction {
+                self.open_transactional_merk_at_path(subtree_path.clone(), tx, None)
+                    .map_err(|e| parent_layer_not_found(e, &subtree_path))
+                    .flat_map_ok(|merk| {
+                        self.get_multi_from_merk(&merk, path, requests, &indices, transaction)
+                            .map(Ok)
+                    })
+            } else {
+                self.open_non_transactional_merk_at_path(subtree_path.clone(), None)
+                    .map_err(|e| parent_layer_not_found(e, &subtree_path))
+                    .flat_map_ok(|merk| {
+                        self.get_multi_from_merk(&merk, path, requests, &indices, transaction)
+                            .map(Ok)
+                    })
+            }
+            .unwrap_add_cost(&mut cost);
+
+            match elements {
+                Ok(elements) => {
+                    for (index, element) in indices.into_iter().zip(elements) {
+                        results[index] = Some(element);
+                    }
+                }
+                // A missing subtree, or a path which isn't one, only fails the
+                // requests made to it
+                Err(Error::PathParentLayerNotFound(detail)) => {
+                    for index in indices {
+                        results[index] = Some(Err(Error::PathParentLayerNotFound(detail.clone())));
+                    }
+                }
+                Err(Error::CorruptedPath(message)) => {
+                    for index in indices {
+                        results[index] = Some(Err(Error::CorruptedPath(message)));
+                    }
+                }
+                Err(e) => return Err(e).wrap_with_cost(cost),
+            }
+        }
+
+        Ok(results.into_iter().flatten().collect()).wrap_with_cost(cost)
+    }
+
+    /// Gets the elements of the requests at `indices`, made to the subtree at
+    /// `path` which `merk` is opened for, following references
+    fn get_multi_from_merk<'db, S: StorageContext<'db>>(
+        &self,
+        merk: &Merk<S>,
+        path: &[Vec<u8>],
+        requests: &[(Vec<Vec<u8>>, Vec<u8>)],
+        indices: &[usize],
+        transaction: TransactionArg,
+    ) -> CostContext<Vec<Result<Element, Error>>> {
+        let mut cost = OperationCost::default();
+        let subtree_path: SubtreePath<Vec<u8>> = path.into();
+
+        let elements = indices
+            .iter()
+            .map(|&index| {
+                let key = requests[index].1.as_slice();
+                match Element::get(merk, key, true)
+                    .map_err(|e| key_not_found_at(e, &subtree_path))
+                    .unwrap_add_cost(&mut cost)
+                {
+                    Ok(Element::Reference(reference_path, max_reference_hop, _)) => {
+                        match path_from_reference_path_type(reference_path, path, Some(key)) {
+                            Ok(target_path) => self
+                                .follow_reference_with_max_hops(
+                                    target_path.as_slice().into(),
+                                    max_reference_hop,
+                                    true,
+                                    transaction,
+                                )
+                                .unwrap_add_cost(&mut cost),
+                            Err(e) => Err(e),
+                        }
+                    }
+                    other => other,
+                }
+            })
+            .collect();
+
+        elements.wrap_with_cost(cost)
+    }
+
+    /// Scans the subtree at `path`, and its descendants if `recursive` is set,
+    /// for references which cannot be resolved: their target is missing, the
+    /// chain is cyclic or exceeds the hop limit. Returns the path and key of
+    /// each such reference.
+    pub fn find_dangling_references<'b, B, P>(
+        &self,
+        path: P,
+        recursive: bool,
+        transaction: TransactionArg,
+    ) -> CostResult<Vec<(Vec<Vec<u8>>, Vec<u8>)>, Error>
+    where
+        B: AsRef<[u8]> + 'b,
+        P: Into<SubtreePath<'b, B>>,
+    {
+        let mut cost = OperationCost::default();
+        let path: SubtreePath<B> = path.into();
+
+        cost_return_on_error!(
+            &mut cost,
+            self.check_subtree_exists_path_not_found(path.clone(), transaction)
+        );
+
+        let mut dangling = Vec::new();
+        let mut subtrees = vec![path.to_vec()];
+        while let Some(subtree_path) = subtrees.pop() {
+            let mut references = Vec::new();
+            storage_context_optional_tx!(
+                self.db,
+                subtree_path.as_slice().into(),
+                None,
+                transaction,
+                storage,
+                {
+                    let storage = storage.unwrap_add_cost(&mut cost);
+                    let mut raw_iter =
+                        Element::iterator(storage.raw_iter()).unwrap_add_cost(&mut cost);
+                    while let Some((key, element)) =
+                        cost_return_on_error!(&mut cost, raw_iter.next_element())
+                    {
+                        match element {
+                            Element::Reference(reference_path, ..) => {
+                                references.push((key, reference_path))
+                            }
+                            Element::Tree(..) | Element::SumTree(..) if recursive => {
+                                let mut child_path = subtree_path.clone();
+                                child_path.push(key);
+                                subtrees.push(child_path);
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+            );
+
+            for (key, reference_path) in references {
+                let resolved = path_from_reference_path_type(
+                    reference_path,
+                    &subtree_path,
+                    Some(key.as_slice()),
+                )
+                .wrap_with_cost(OperationCost::default())
+                .flat_map_ok(|target_path| {
+                    self.follow_reference(target_path.as_slice().into(), true, transaction)
+                })
+                .unwrap_add_cost(&mut cost);
+                match resolved {
+                    Ok(_) => {}
+                    Err(e) if is_unresolvable_reference_error(&e) => {
+                        dangling.push((subtree_path.clone(), key))
+                    }
+                    Err(e) => return Err(e).wrap_with_cost(cost),
+                }
+            }
+        }
+
+        Ok(dangling).wrap_with_cost(cost)
     }
 
     /// Get Element at specified path and key
@@ -229,16 +581,13 @@ impl GroveDb {
 
         let merk_to_get_from = cost_return_on_error!(
             &mut cost,
-            self.open_transactional_merk_at_path(path, transaction, None)
-                .map_err(|e| match e {
-                    Error::InvalidParentLayerPath(s) => {
-                        Error::PathParentLayerNotFound(s)
-                    }
-                    _ => e,
-                })
+            self.open_transactional_merk_at_path(path.clone(), transaction, None)
+                .map_err(|e| parent_layer_not_found(e, &path))
         );
 
-        Element::get(&merk_to_get_from, key, allow_cache).add_cost(cost)
+        Element::get(&merk_to_get_from, key, allow_cache)
+            .map_err(|e| key_not_found_at(e, &path))
+            .add_cost(cost)
     }
 
     /// Get tree item without following references
@@ -251,11 +600,8 @@ impl GroveDb {
     ) -> CostResult<Option<Element>, Error> {
         let mut cost = OperationCost::default();
         let merk_result = self
-            .open_transactional_merk_at_path(path, transaction, None)
-            .map_err(|e| match e {
-                Error::InvalidParentLayerPath(s) => Error::PathParentLayerNotFound(s),
-                _ => e,
-            })
+            .open_transactional_merk_at_path(path.clone(), transaction, None)
+            .map_err(|e| parent_layer_not_found(e, &path))
             .unwrap_add_cost(&mut cost);
         let merk = cost_return_on_error_no_add!(
             &cost,
@@ -285,16 +631,13 @@ impl GroveDb {
 
         let merk_to_get_from = cost_return_on_error!(
             &mut cost,
-            self.open_non_transactional_merk_at_path(path, None)
-                .map_err(|e| match e {
-                    Error::InvalidParentLayerPath(s) => {
-                        Error::PathParentLayerNotFound(s)
-                    }
-                    _ => e,
-                })
+            self.open_non_transactional_merk_at_path(path.clone(), None)
+                .map_err(|e| parent_layer_not_found(e, &path))
         );
 
-        Element::get(&merk_to_get_from, key, allow_cache).add_cost(cost)
+        Element::get(&merk_to_get_from, key, allow_cache)
+            .map_err(|e| key_not_found_at(e, &path))
+            .add_cost(cost)
     }
 
     /// Get tree item without following references
@@ -307,11 +650,8 @@ impl GroveDb {
         let mut cost = OperationCost::default();
 
         let merk_result = self
-            .open_non_transactional_merk_at_path(path, None)
-            .map_err(|e| match e {
-                Error::InvalidParentLayerPath(s) => Error::PathParentLayerNotFound(s),
-                _ => e,
-            })
+            .open_non_transactional_merk_at_path(path.clone(), None)
+            .map_err(|e| parent_layer_not_found(e, &path))
             .unwrap_add_cost(&mut cost);
         let merk = cost_return_on_error_no_add!(
             &cost,
@@ -348,16 +688,143 @@ impl GroveDb {
         })
     }
 
+    /// Get the root key of the subtree's Merk, `None` if the subtree is empty
+    pub fn subtree_root_key<'b, B, P>(
+        &self,
+        path: P,
+        transaction: TransactionArg,
+    ) -> CostResult<Option<Vec<u8>>, Error>
+    where
+        B: AsRef<[u8]> + 'b,
+        P: Into<SubtreePath<'b, B>>,
+    {
+        let mut cost = OperationCost::default();
+        let path: SubtreePath<B> = path.into();
+
+        cost_return_on_error!(
+            &mut cost,
+            self.check_subtree_exists_path_not_found(path.clone(), transaction)
+        );
+        merk_optional_tx!(&mut cost, self.db, path, None, transaction, subtree, {
+            Ok(subtree.root_key()).wrap_with_cost(cost)
+        })
+    }
+
+    /// Get the greatest key of the subtree at `path`, `None` if the subtree is
+    /// empty
+    pub fn last_key_in_subtree<'b, B, P>(
+        &self,
+        path: P,
+        transaction: TransactionArg,
+    ) -> CostResult<Option<Vec<u8>>, Error>
+    where
+        B: AsRef<[u8]> + 'b,
+        P: Into<SubtreePath<'b, B>>,
+    {
+        let mut cost = OperationCost::default();
+        let path: SubtreePath<B> = path.into();
+
+        cost_return_on_error!(
+            &mut cost,
+            self.check_subtree_exists_path_not_found(path.clone(), transaction)
+        );
+        storage_context_optional_tx!(self.db, path, None, transaction, storage, {
+            let storage = storage.unwrap_add_cost(&mut cost);
+            let mut raw_iter = storage.raw_iter();
+            raw_iter
+                .seek_last_for_prefix(&[])
+                .unwrap_add_cost(&mut cost);
+            let last_key = raw_iter
+                .key()
+                .unwrap_add_cost(&mut cost)
+                .map(|key| key.to_vec());
+            Ok(last_key).wrap_with_cost(cost)
+        })
+    }
+
+    /// Get the aggregated sum of the sum items in the subtree at `path`,
+    /// `0` for an empty sum tree. Fails with [`Error::NotASumTree`] if the
+    /// subtree is not a sum tree.
+    pub fn subtree_sum<'b, B, P>(
+        &self,
+        path: P,
+        transaction: TransactionArg,
+    ) -> CostResult<i64, Error>
+    where
+        B: AsRef<[u8]> + 'b,
+        P: Into<SubtreePath<'b, B>>,
+    {
+        let mut cost = OperationCost::default();
+        let path: SubtreePath<B> = path.into();
+
+        cost_return_on_error!(&mut cost, self.require_sum_tree(path.clone(), transaction));
+        merk_optional_tx!(&mut cost, self.db, path, None, transaction, subtree, {
+            subtree
+                .sum()
+                .map(Option::unwrap_or_default)
+                .map_err(Error::MerkError)
+                .wrap_with_cost(cost)
+        })
+    }
+
+    /// Checks that the subtree at `path` is a sum tree, failing with
+    /// [`Error::NotASumTree`] otherwise. Operations on sums use it as a
+    /// precondition, so they don't read a basic tree as a sum of zero.
+    pub fn require_sum_tree<'b, B, P>(
+        &self,
+        path: P,
+        transaction: TransactionArg,
+    ) -> CostResult<(), Error>
+    where
+        B: AsRef<[u8]> + 'b,
+        P: Into<SubtreePath<'b, B>>,
+    {
+        let path: SubtreePath<B> = path.into();
+
+        self.get_subtree_element(path.clone(), transaction, || subtree_not_found(&path))
+            .flat_map_ok(|element| {
+                // The root tree has no element and is never a sum tree
+                if matches!(element, Some(Element::SumTree(..))) {
+                    Ok(()).wrap_with_cost(OperationCost::default())
+                } else {
+                    let path = path.to_vec();
+                    let message = format!(
+                        "subtree at path {:?} is not a sum tree",
+                        path.iter().map(hex::encode).collect::<Vec<String>>()
+                    );
+                    Err(Error::NotASumTree(PathErrorDetail::new(
+                        path, None, message,
+                    )))
+                    .wrap_with_cost(OperationCost::default())
+                }
+            })
+    }
+
     fn check_subtree_exists<B: AsRef<[u8]>>(
         &self,
         path: SubtreePath<B>,
         transaction: TransactionArg,
         error_fn: impl FnOnce() -> Error,
     ) -> CostResult<(), Error> {
+        self.get_subtree_element(path, transaction, error_fn)
+            .map_ok(|_| ())
+    }
+
+    /// Get the element of the subtree at `path` from its parent, `None` for
+    /// the root tree which has no parent. Fails with `error_fn` if there is no
+    /// subtree at `path`.
+    fn get_subtree_element<B: AsRef<[u8]>>(
+        &self,
+        path: SubtreePath<B>,
+        transaction: TransactionArg,
+        error_fn: impl FnOnce() -> Error,
+    ) -> CostResult<Option<Element>, Error> {
         let mut cost = OperationCost::default();
 
         if let Some((parent_path, parent_key)) = path.derive_parent() {
-            let element = if let Some(transaction) = transaction {
+            let element = if parent_path.is_root() {
+                self.get_root_tree_element(parent_key, transaction)
+            } else if let Some(transaction) = transaction {
                 let merk_to_get_from = cost_return_on_error!(
                     &mut cost,
                     self.open_transactional_merk_at_path(parent_path, transaction, None)
@@ -374,15 +841,43 @@ impl GroveDb {
             }
             .unwrap_add_cost(&mut cost);
             match element {
-                Ok(Element::Tree(..)) | Ok(Element::SumTree(..)) => Ok(()).wrap_with_cost(cost),
+                Ok(element) if element.is_tree() => Ok(Some(element)).wrap_with_cost(cost),
                 Ok(_) | Err(Error::PathKeyNotFound(_)) => Err(error_fn()).wrap_with_cost(cost),
                 Err(e) => Err(e).wrap_with_cost(cost),
             }
         } else {
-            Ok(()).wrap_with_cost(cost)
+            Ok(None).wrap_with_cost(cost)
         }
     }
 
+    /// Get an element of the root tree by reading its node right from storage,
+    /// which is cheaper than opening the root tree Merk to look the key up.
+    ///
+    /// The roots column family can't be used instead: it keeps the root keys
+    /// of cleared subtrees, so it doesn't tell whether a subtree still exists.
+    fn get_root_tree_element(
+        &self,
+        key: &[u8],
+        transaction: TransactionArg,
+    ) -> CostResult<Element, Error> {
+        let mut cost = OperationCost::default();
+        let root_path: SubtreePath<[u8; 0]> = SubtreePath::empty();
+
+        storage_context_optional_tx!(self.db, root_path, None, transaction, storage, {
+            let storage = storage.unwrap_add_cost(&mut cost);
+            let node = cost_return_on_error!(&mut cost, storage.get(key).map_err(|e| e.into()));
+            match node {
+                Some(bytes) => raw_decode(&bytes).wrap_with_cost(cost),
+                None => Err(Error::PathKeyNotFound(PathErrorDetail::new(
+                    vec![],
+                    Some(key.to_vec()),
+                    format!("key not found in root tree: {}", hex::encode(key)),
+                )))
+                .wrap_with_cost(cost),
+            }
+        })
+    }
+
     /// Check that subtree exists with path not found error
     pub(crate) fn check_subtree_exists_path_not_found<'b, B>(
         &self,
@@ -392,15 +887,7 @@ impl GroveDb {
     where
         B: AsRef<[u8]> + 'b,
     {
-        self.check_subtree_exists(path.clone(), transaction, || {
-            Error::PathNotFound(format!(
-                "subtree doesn't exist at path {:?}",
-                path.to_vec()
-                    .into_iter()
-                    .map(hex::encode)
-                    .collect::<Vec<String>>()
-            ))
-        })
+        self.check_subtree_exists(path.clone(), transaction, || subtree_not_found(&path))
     }
 
     /// Check subtree exists with invalid path error
@@ -414,3 +901,77 @@ impl GroveDb {
         })
     }
 }
+
+#[cfg(feature = "full")]
+/// Number of hops a reference annotated with `max_reference_hop` may be
+/// followed for, never more than [`MAX_REFERENCE_HOPS`]
+fn max_hops_of(max_reference_hop: MaxReferenceHop) -> usize {
+    max_reference_hop.map_or(MAX_REFERENCE_HOPS, |hops| {
+        (hops as usize).min(MAX_REFERENCE_HOPS)
+    })
+}
+
+#[cfg(feature = "full")]
+/// Reports that there is no subtree at `path`
+fn subtree_not_found<B: AsRef<[u8]>>(path: &SubtreePath<B>) -> Error {
+    let path = path.to_vec();
+    let message = format!(
+        "subtree doesn't exist at path {:?}",
+        path.iter().map(hex::encode).collect::<Vec<String>>()
+    );
+    Error::PathNotFound(PathErrorDetail::new(path, None, message))
+}
+
+#[cfg(feature = "full")]
+/// Maps errors met while fetching a reference target to their corrupted
+/// reference counterparts
+fn reference_target_error(error: Error) -> Error {
+    match error {
+        Error::PathParentLayerNotFound(p) => {
+            Error::CorruptedReferencePathParentLayerNotFound(p.message)
+        }
+        Error::PathKeyNotFound(p) => Error::CorruptedReferencePathKeyNotFound(p.message),
+        Error::PathNotFound(p) => Error::CorruptedReferencePathNotFound(p.message),
+        _ => error,
+    }
+}
+
+#[cfg(feature = "full")]
+/// Reports a missing parent layer met while opening the subtree at `path`
+fn parent_layer_not_found<B: AsRef<[u8]>>(error: Error, path: &SubtreePath<B>) -> Error {
+    match error {
+        Error::InvalidParentLayerPath(s) => {
+            Error::PathParentLayerNotFound(PathErrorDetail::new(path.to_vec(), None, s))
+        }
+        _ => error,
+    }
+}
+
+#[cfg(feature = "full")]
+/// Fills in the path of a key not found in the subtree at `path`, which the
+/// Merk the key was looked up in doesn't know
+fn key_not_found_at<B: AsRef<[u8]>>(error: Error, path: &SubtreePath<B>) -> Error {
+    match error {
+        Error::PathKeyNotFound(mut detail) => {
+            detail.path = path.to_vec();
+            Error::PathKeyNotFound(detail)
+        }
+        _ => error,
+    }
+}
+
+#[cfg(feature = "full")]
+/// Checks if the error means a reference can't be resolved rather than
+/// storage failing to serve it
+fn is_unresolvable_reference_error(error: &Error) -> bool {
+    matches!(
+        error,
+        Error::CorruptedReferencePathKeyNotFound(_)
+            | Error::CorruptedReferencePathNotFound(_)
+            | Error::CorruptedReferencePathParentLayerNotFound(_)
+            | Error::CyclicReference
+            | Error::ReferenceLimit
+            | Error::ReferenceChainTooLong { .. }
+            | Error::InvalidInput(_)
+    )
+}
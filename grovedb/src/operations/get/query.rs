@@ -34,14 +34,21 @@ use grovedb_costs::{
     cost_return_on_error, cost_return_on_error_no_add, CostResult, CostsExt, OperationCost,
 };
 #[cfg(feature = "full")]
+use grovedb_merk::{CryptoHash, Merk};
+#[cfg(feature = "full")]
+use grovedb_path::SubtreePath;
+#[cfg(feature = "full")]
+use grovedb_storage::StorageContext;
+#[cfg(feature = "full")]
 use integer_encoding::VarInt;
 
 use crate::query_result_type::PathKeyOptionalElementTrio;
 #[cfg(feature = "full")]
 use crate::{
-    query_result_type::{QueryResultElement, QueryResultElements, QueryResultType},
+    query_result_type::{KeyElementPair, QueryResultElement, QueryResultElements, QueryResultType},
     reference_path::ReferencePathType,
-    Element, Error, GroveDb, PathQuery, TransactionArg,
+    util::storage_context_optional_tx,
+    Cursor, Element, ElementTypeFilter, Error, GroveDb, PathQuery, TransactionArg,
 };
 
 #[cfg(feature = "full")]
@@ -67,7 +74,11 @@ impl GroveDb {
         let results_wrapped = elements
             .into_iterator()
             .map(|result_item| match result_item {
-                QueryResultElement::ElementResultItem(Element::Reference(reference_path, ..)) => {
+                QueryResultElement::ElementResultItem(Element::Reference(
+                    reference_path,
+                    max_reference_hop,
+                    _,
+                )) => {
                     match reference_path {
                         ReferencePathType::AbsolutePathReference(absolute_path) => {
                             // While `map` on iterator is lazy, we should accumulate costs even if
@@ -75,8 +86,9 @@ impl GroveDb {
                             // external costs accumulator instead of
                             // returning costs from `map` call.
                             let maybe_item = self
-                                .follow_reference(
+                                .follow_reference_with_max_hops(
                                     absolute_path.as_slice().into(),
+                                    max_reference_hop,
                                     allow_cache,
                                     transaction,
                                 )
@@ -150,7 +162,7 @@ where {
         transaction: TransactionArg,
     ) -> Result<Element, Error> {
         match element {
-            Element::Reference(reference_path, ..) => {
+            Element::Reference(reference_path, max_reference_hop, _) => {
                 match reference_path {
                     ReferencePathType::AbsolutePathReference(absolute_path) => {
                         // While `map` on iterator is lazy, we should accumulate costs
@@ -159,8 +171,9 @@ where {
                         // external costs accumulator instead of
                         // returning costs from `map` call.
                         let maybe_item = self
-                            .follow_reference(
+                            .follow_reference_with_max_hops(
                                 absolute_path.as_slice().into(),
+                                max_reference_hop,
                                 allow_cache,
                                 transaction,
                             )
@@ -199,8 +212,11 @@ where {
             self.query_raw(path_query, allow_cache, result_type, transaction)
         );
 
+        // Tree elements are never references, so their hashes stay in place
+        let subtree_root_hashes = elements.subtree_root_hashes;
         let results_wrapped = elements
-            .into_iterator()
+            .elements
+            .into_iter()
             .map(|result_item| {
                 result_item.map_element(|element| {
                     self.follow_element(element, allow_cache, &mut cost, transaction)
@@ -209,7 +225,14 @@ where {
             .collect::<Result<Vec<QueryResultElement>, Error>>();
 
         let results = cost_return_on_error_no_add!(&cost, results_wrapped);
-        Ok((QueryResultElements { elements: results }, skipped)).wrap_with_cost(cost)
+        Ok((
+            QueryResultElements {
+                elements: results,
+                subtree_root_hashes,
+            },
+            skipped,
+        ))
+        .wrap_with_cost(cost)
     }
 
     /// Queries the backing store and returns element items by their value,
@@ -237,7 +260,7 @@ where {
             .map(|result_item| match result_item {
                 QueryResultElement::ElementResultItem(element) => {
                     match element {
-                        Element::Reference(reference_path, ..) => {
+                        Element::Reference(reference_path, max_reference_hop, _) => {
                             match reference_path {
                                 ReferencePathType::AbsolutePathReference(absolute_path) => {
                                     // While `map` on iterator is lazy, we should accumulate costs
@@ -246,8 +269,9 @@ where {
                                     // external costs accumulator instead of
                                     // returning costs from `map` call.
                                     let maybe_item = self
-                                        .follow_reference(
+                                        .follow_reference_with_max_hops(
                                             absolute_path.as_slice().into(),
+                                            max_reference_hop,
                                             allow_cache,
                                             transaction,
                                         )
@@ -307,7 +331,7 @@ where {
             .map(|result_item| match result_item {
                 QueryResultElement::ElementResultItem(element) => {
                     match element {
-                        Element::Reference(reference_path, ..) => {
+                        Element::Reference(reference_path, max_reference_hop, _) => {
                             match reference_path {
                                 ReferencePathType::AbsolutePathReference(absolute_path) => {
                                     // While `map` on iterator is lazy, we should accumulate costs
@@ -316,8 +340,9 @@ where {
                                     // external costs accumulator instead of
                                     // returning costs from `map` call.
                                     let maybe_item = self
-                                        .follow_reference(
+                                        .follow_reference_with_max_hops(
                                             absolute_path.as_slice().into(),
+                                            max_reference_hop,
                                             allow_cache,
                                             transaction,
                                         )
@@ -363,7 +388,171 @@ where {
         result_type: QueryResultType,
         transaction: TransactionArg,
     ) -> CostResult<(QueryResultElements, u16), Error> {
-        Element::get_raw_path_query(&self.db, path_query, allow_cache, result_type, transaction)
+        cost_return_on_error_default!(self.check_subquery_depth(path_query));
+        if !path_query.query.include_subtree_hashes {
+            return Element::get_raw_path_query(
+                &self.db,
+                path_query,
+                allow_cache,
+                result_type,
+                transaction,
+            );
+        }
+
+        let mut cost = OperationCost::default();
+
+        // Subtrees are located by the path and key of their element, so those are
+        // kept until the hashes are collected
+        let (elements, skipped) = cost_return_on_error!(
+            &mut cost,
+            Element::get_raw_path_query(
+                &self.db,
+                path_query,
+                allow_cache,
+                QueryResultType::QueryPathKeyElementTrioResultType,
+                transaction,
+            )
+        );
+        let subtree_root_hashes = cost_return_on_error!(
+            &mut cost,
+            self.subtree_root_hashes(&elements.elements, transaction)
+        );
+        let elements = elements
+            .elements
+            .into_iter()
+            .map(|result_item| match (result_type, result_item) {
+                (
+                    QueryResultType::QueryElementResultType,
+                    QueryResultElement::PathKeyElementTrioResultItem((_, _, element)),
+                ) => QueryResultElement::ElementResultItem(element),
+                (
+                    QueryResultType::QueryKeyElementPairResultType,
+                    QueryResultElement::PathKeyElementTrioResultItem((_, key, element)),
+                ) => QueryResultElement::KeyElementPairResultItem((key, element)),
+                (_, result_item) => result_item,
+            })
+            .collect();
+
+        Ok((
+            QueryResultElements {
+                elements,
+                subtree_root_hashes,
+            },
+            skipped,
+        ))
+        .wrap_with_cost(cost)
+    }
+
+    /// Returns the path query with its [`max_result_bytes`] budget turned into
+    /// a limit: the number of results which fit into the budget, as returned
+    /// by [`GroveDb::query_raw`]. Proofs can only be bounded by a limit, so
+    /// [`GroveDb::prove_query`] proves this query in place of one with a
+    /// budget, and the proof is verified with it. Queries without a budget
+    /// are returned as is.
+    ///
+    /// [`max_result_bytes`]: crate::SizedQuery::max_result_bytes
+    pub fn limit_to_result_bytes(
+        &self,
+        path_query: &PathQuery,
+        transaction: TransactionArg,
+    ) -> CostResult<PathQuery, Error> {
+        let mut cost = OperationCost::default();
+        let mut limited_query = path_query.clone();
+        if path_query.query.max_result_bytes.is_none() {
+            return Ok(limited_query).wrap_with_cost(cost);
+        }
+
+        let (results, _) = cost_return_on_error!(
+            &mut cost,
+            self.query_raw(
+                path_query,
+                true,
+                QueryResultType::QueryElementResultType,
+                transaction
+            )
+        );
+        let fitting = cost_return_on_error_no_add!(
+            &cost,
+            u16::try_from(results.len()).map_err(|_| Error::NotSupported(
+                "more results fit into the result bytes than a limit can hold"
+            ))
+        );
+        limited_query.query.limit = Some(fitting);
+        limited_query.query.max_result_bytes = None;
+        Ok(limited_query).wrap_with_cost(cost)
+    }
+
+    /// Root hashes of the subtrees of tree elements among path key element
+    /// trio results, `None` for other elements
+    fn subtree_root_hashes(
+        &self,
+        results: &[QueryResultElement],
+        transaction: TransactionArg,
+    ) -> CostResult<Vec<Option<CryptoHash>>, Error> {
+        let mut cost = OperationCost::default();
+
+        let mut hashes = Vec::with_capacity(results.len());
+        for result_item in results {
+            let QueryResultElement::PathKeyElementTrioResultItem((path, key, element)) =
+                result_item
+            else {
+                hashes.push(None);
+                continue;
+            };
+            let (root_key, is_sum_tree) = match element {
+                Element::Tree(root_key, _) => (root_key.clone(), false),
+                Element::SumTree(root_key, ..) => (root_key.clone(), true),
+                _ => {
+                    hashes.push(None);
+                    continue;
+                }
+            };
+            let mut subtree_path_vec = path.clone();
+            subtree_path_vec.push(key.clone());
+            let subtree_path: SubtreePath<Vec<u8>> = subtree_path_vec.as_slice().into();
+            storage_context_optional_tx!(self.db, subtree_path, None, transaction, storage, {
+                let subtree = cost_return_on_error!(
+                    &mut cost,
+                    Merk::open_layered_with_root_key(
+                        storage.unwrap_add_cost(&mut cost),
+                        root_key,
+                        is_sum_tree
+                    )
+                    .map(|merk_res| merk_res
+                        .map_err(|_| Error::CorruptedData("cannot open a subtree".to_owned())))
+                );
+                hashes.push(Some(subtree.root_hash().unwrap_add_cost(&mut cost)));
+            });
+        }
+        Ok(hashes).wrap_with_cost(cost)
+    }
+
+    /// Returns a page of key element pairs of a path query and the cursor to
+    /// query the next page with, see [`SizedQuery::after_cursor`]. The limit
+    /// sets the page size, a cursor is only returned for a page filling it.
+    ///
+    /// [`SizedQuery::after_cursor`]: crate::SizedQuery::after_cursor
+    pub fn query_raw_with_cursor(
+        &self,
+        path_query: &PathQuery,
+        allow_cache: bool,
+        transaction: TransactionArg,
+    ) -> CostResult<(Vec<KeyElementPair>, Option<Cursor>), Error> {
+        cost_return_on_error_default!(path_query.check_cursor_pagination());
+        self.query_raw(
+            path_query,
+            allow_cache,
+            QueryResultType::QueryKeyElementPairResultType,
+            transaction,
+        )
+        .map_ok(|(elements, _)| {
+            let key_elements = elements.to_key_elements();
+            let cursor = path_query.page_cursor(
+                key_elements.len(),
+                key_elements.last().map(|(key, _)| key.as_slice()),
+            );
+            (key_elements, cursor)
+        })
     }
 
     /// Splits the result set of a path query by query path.
@@ -374,6 +563,7 @@ where {
         allow_cache: bool,
         transaction: TransactionArg,
     ) -> CostResult<Vec<PathKeyOptionalElementTrio>, Error> {
+        cost_return_on_error_default!(self.check_subquery_depth(path_query));
         let max_results = cost_return_on_error_default!(path_query.query.limit.ok_or(
             Error::NotSupported("limits must be set in query_keys_optional",)
         )) as usize;
@@ -417,6 +607,7 @@ where {
         allow_cache: bool,
         transaction: TransactionArg,
     ) -> CostResult<Vec<PathKeyOptionalElementTrio>, Error> {
+        cost_return_on_error_default!(self.check_subquery_depth(path_query));
         let max_results = cost_return_on_error_default!(path_query.query.limit.ok_or(
             Error::NotSupported("limits must be set in query_raw_keys_optional",)
         )) as usize;
@@ -452,6 +643,43 @@ where {
             .collect())
         .wrap_with_cost(cost)
     }
+
+    /// Scans the whole subtree at `path` and returns, in key order, its
+    /// elements whose variant is in `types`. References are returned as
+    /// stored, without being followed.
+    pub fn query_subtree_by_type<'b, B, P>(
+        &self,
+        path: P,
+        types: ElementTypeFilter,
+        transaction: TransactionArg,
+    ) -> CostResult<Vec<(Vec<u8>, Element)>, Error>
+    where
+        B: AsRef<[u8]> + 'b,
+        P: Into<SubtreePath<'b, B>>,
+    {
+        let mut cost = OperationCost::default();
+        let path: SubtreePath<B> = path.into();
+
+        cost_return_on_error!(
+            &mut cost,
+            self.check_subtree_exists_path_not_found(path.clone(), transaction)
+        );
+
+        let mut results = Vec::new();
+        storage_context_optional_tx!(self.db, path, None, transaction, storage, {
+            let storage = storage.unwrap_add_cost(&mut cost);
+            let mut raw_iter = Element::iterator(storage.raw_iter()).unwrap_add_cost(&mut cost);
+            while let Some((key, element)) =
+                cost_return_on_error!(&mut cost, raw_iter.next_element())
+            {
+                if types.matches(&element) {
+                    results.push((key, element));
+                }
+            }
+        });
+
+        Ok(results).wrap_with_cost(cost)
+    }
 }
 
 #[cfg(feature = "full")]
@@ -465,7 +693,7 @@ mod tests {
     use crate::{
         reference_path::ReferencePathType::AbsolutePathReference,
         tests::{make_test_grovedb, ANOTHER_TEST_LEAF, TEST_LEAF},
-        Element, PathQuery, SizedQuery,
+        Element, ElementTypeFilter, PathQuery, SizedQuery,
     };
 
     #[test]
@@ -1529,4 +1757,56 @@ mod tests {
             None
         ); // because we didn't query for it
     }
+
+    #[test]
+    fn test_query_subtree_by_type() {
+        let db = make_test_grovedb();
+
+        let elements = [
+            (b"a".to_vec(), Element::new_item(b"item a".to_vec())),
+            (
+                b"b".to_vec(),
+                Element::new_reference(AbsolutePathReference(vec![
+                    TEST_LEAF.to_vec(),
+                    b"a".to_vec(),
+                ])),
+            ),
+            (b"c".to_vec(), Element::empty_tree()),
+            (b"d".to_vec(), Element::empty_sum_tree()),
+            (b"e".to_vec(), Element::new_item(b"item e".to_vec())),
+        ];
+        for (key, element) in elements.iter() {
+            db.insert([TEST_LEAF].as_ref(), key, element.clone(), None, None)
+                .unwrap()
+                .expect("successful insert");
+        }
+
+        let select = |types: ElementTypeFilter| {
+            db.query_subtree_by_type([TEST_LEAF].as_ref(), types, None)
+                .unwrap()
+                .expect("successful subtree scan")
+                .into_iter()
+                .map(|(key, _)| key)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            select(ElementTypeFilter::ITEM),
+            vec![b"a".to_vec(), b"e".to_vec()]
+        );
+        assert_eq!(select(ElementTypeFilter::REFERENCE), vec![b"b".to_vec()]);
+        assert_eq!(select(ElementTypeFilter::TREE), vec![b"c".to_vec()]);
+        assert_eq!(select(ElementTypeFilter::SUM_TREE), vec![b"d".to_vec()]);
+        assert_eq!(
+            select(ElementTypeFilter::TREE | ElementTypeFilter::SUM_TREE),
+            vec![b"c".to_vec(), b"d".to_vec()]
+        );
+        assert_eq!(select(ElementTypeFilter::default()), Vec::<Vec<u8>>::new());
+
+        let all = db
+            .query_subtree_by_type([TEST_LEAF].as_ref(), ElementTypeFilter::ALL, None)
+            .unwrap()
+            .expect("successful subtree scan");
+        assert_eq!(all, elements.to_vec());
+    }
 }
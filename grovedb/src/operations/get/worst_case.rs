@@ -31,12 +31,16 @@
 #[cfg(feature = "full")]
 use grovedb_costs::OperationCost;
 #[cfg(feature = "full")]
+use grovedb_merk::estimated_costs::worst_case_costs::{
+    WorstCaseLayerInformation, MERK_BIGGEST_VALUE_SIZE,
+};
+#[cfg(feature = "full")]
 use grovedb_storage::rocksdb_storage::RocksDbStorage;
 
 #[cfg(feature = "full")]
 use crate::{
     batch::{key_info::KeyInfo, KeyInfoPath},
-    GroveDb,
+    GroveDb, PathQuery, Query, QueryItem,
 };
 
 #[cfg(feature = "full")]
@@ -96,4 +100,151 @@ impl GroveDb {
         );
         cost
     }
+
+    /// Worst case cost for running a path query without following references
+    ///
+    /// Every subtree on the query path is opened, then each range item is
+    /// assumed to match as many elements of the biggest possible size as
+    /// `layer_info` allows, and every matched element goes through all the
+    /// subquery branches of the query. Only the limit and the offset of the
+    /// top layer and the limits of subquery branches cap the number of
+    /// elements.
+    pub fn worst_case_query_cost(
+        path_query: &PathQuery,
+        layer_info: &WorstCaseLayerInformation,
+    ) -> OperationCost {
+        let mut cost = OperationCost::default();
+        let mut path = KeyInfoPath::default();
+        GroveDb::add_worst_case_get_merk_at_path::<RocksDbStorage>(&mut cost, &path, true);
+        for segment in path_query.path.iter() {
+            let key = KeyInfo::KnownKey(segment.clone());
+            GroveDb::add_worst_case_get_raw_cost::<RocksDbStorage>(
+                &mut cost,
+                &path,
+                &key,
+                MERK_BIGGEST_VALUE_SIZE,
+                true,
+            );
+            path.push(key);
+            GroveDb::add_worst_case_get_merk_at_path::<RocksDbStorage>(&mut cost, &path, true);
+        }
+
+        let max_results = path_query
+            .query
+            .limit
+            .map(|limit| limit as u32 + path_query.query.offset.unwrap_or_default() as u32);
+        let layer_cost =
+            worst_case_query_layer_cost(&path, &path_query.query.query, max_results, layer_info);
+        add_repeated_cost(&mut cost, &layer_cost, 1);
+        cost
+    }
+}
+
+/// Worst case cost of querying elements of an already opened subtree and
+/// running subqueries on them
+#[cfg(feature = "full")]
+fn worst_case_query_layer_cost(
+    path: &KeyInfoPath,
+    query: &Query,
+    max_results: Option<u32>,
+    layer_info: &WorstCaseLayerInformation,
+) -> OperationCost {
+    let max_elements = match layer_info {
+        WorstCaseLayerInformation::MaxElementsNumber(n) => *n,
+        WorstCaseLayerInformation::NumberOfLevels(levels) => {
+            1u32.checked_shl(*levels).map_or(u32::MAX, |n| n - 1)
+        }
+    };
+    let mut elements_count = query
+        .items
+        .iter()
+        .fold(0u32, |count, item| match item {
+            QueryItem::Key(_) => count.saturating_add(1),
+            _ => count.saturating_add(max_elements),
+        })
+        .min(max_elements);
+    if let Some(max_results) = max_results {
+        elements_count = elements_count.min(max_results);
+    }
+
+    let key = KeyInfo::MaxKeySize {
+        unique_id: vec![],
+        max_size: u8::MAX,
+    };
+    let mut element_cost = OperationCost::default();
+    GroveDb::add_worst_case_get_raw_cost::<RocksDbStorage>(
+        &mut element_cost,
+        path,
+        &key,
+        MERK_BIGGEST_VALUE_SIZE,
+        true,
+    );
+
+    let branches = std::iter::once(&query.default_subquery_branch).chain(
+        query
+            .conditional_subquery_branches
+            .iter()
+            .flat_map(|branches| branches.values()),
+    );
+    for branch in branches {
+        if branch.subquery_path.is_none() && branch.subquery.is_none() {
+            continue;
+        }
+        let mut branch_cost = OperationCost::default();
+        let mut subquery_path = path.clone();
+        subquery_path.push(key.clone());
+        GroveDb::add_worst_case_get_merk_at_path::<RocksDbStorage>(
+            &mut branch_cost,
+            &subquery_path,
+            true,
+        );
+        for segment in branch.subquery_path.iter().flatten() {
+            let segment_key = KeyInfo::KnownKey(segment.clone());
+            GroveDb::add_worst_case_get_raw_cost::<RocksDbStorage>(
+                &mut branch_cost,
+                &subquery_path,
+                &segment_key,
+                MERK_BIGGEST_VALUE_SIZE,
+                true,
+            );
+            subquery_path.push(segment_key);
+            GroveDb::add_worst_case_get_merk_at_path::<RocksDbStorage>(
+                &mut branch_cost,
+                &subquery_path,
+                true,
+            );
+        }
+        // Without a subquery the last segment of the subquery path is the
+        // queried key, already counted above
+        if let Some(subquery) = &branch.subquery {
+            let subquery_cost = worst_case_query_layer_cost(
+                &subquery_path,
+                subquery,
+                branch.limit.map(u32::from),
+                layer_info,
+            );
+            add_repeated_cost(&mut branch_cost, &subquery_cost, 1);
+        }
+        add_repeated_cost(&mut element_cost, &branch_cost, 1);
+    }
+
+    let mut cost = OperationCost::default();
+    add_repeated_cost(&mut cost, &element_cost, elements_count);
+    cost
+}
+
+/// Adds `times` the loading costs of `other` to `cost`, saturating instead of
+/// overflowing as estimates of big layers are easily out of bounds
+#[cfg(feature = "full")]
+fn add_repeated_cost(cost: &mut OperationCost, other: &OperationCost, times: u32) {
+    let times_u16 = u16::try_from(times).unwrap_or(u16::MAX);
+    cost.seek_count = cost
+        .seek_count
+        .saturating_add(other.seek_count.saturating_mul(times_u16));
+    cost.storage_loaded_bytes = cost
+        .storage_loaded_bytes
+        .saturating_add(other.storage_loaded_bytes.saturating_mul(times));
+    cost.hash_node_calls = cost
+        .hash_node_calls
+        .saturating_add(other.hash_node_calls.saturating_mul(times));
 }
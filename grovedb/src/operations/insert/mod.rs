@@ -90,6 +90,7 @@ impl InsertOptions {
 impl GroveDb {
     /// Insert a GroveDB element given a path to the subtree and the key to
     /// insert at
+    /// An empty key is a valid key and can also hold a subtree
     pub fn insert<'b, B, P>(
         &self,
         path: P,
@@ -104,6 +105,9 @@ impl GroveDb {
     {
         let subtree_path: SubtreePath<B> = path.into();
         let batch = StorageBatch::new();
+        let changes = self
+            .change_journal_retention
+            .map(|_| vec![(subtree_path.to_vec(), key.to_vec())]);
 
         let collect_costs = if let Some(transaction) = transaction {
             self.insert_on_transaction(
@@ -124,13 +128,219 @@ impl GroveDb {
             )
         };
 
-        collect_costs.flat_map_ok(|_| {
-            self.db
-                .commit_multi_context_batch(batch, transaction)
-                .map_err(Into::into)
+        let result = collect_costs
+            .flat_map_ok(|_| self.commit_multi_context_batch(batch, changes, transaction));
+        self.observe_cost("insert", result)
+    }
+
+    /// Insert multiple GroveDB elements into the subtree at the given path.
+    /// All items must target the same subtree: its Merk is opened only once,
+    /// every element is added to it and changes are propagated up the tree a
+    /// single time, which is cheaper than calling [`GroveDb::insert`] for each
+    /// item.
+    pub fn insert_many<'b, B, P, I>(
+        &self,
+        path: P,
+        items: I,
+        options: Option<InsertOptions>,
+        transaction: TransactionArg,
+    ) -> CostResult<(), Error>
+    where
+        B: AsRef<[u8]> + 'b,
+        P: Into<SubtreePath<'b, B>>,
+        I: IntoIterator<Item = (Vec<u8>, Element)>,
+    {
+        let subtree_path: SubtreePath<B> = path.into();
+        let options = options.unwrap_or_default();
+        let batch = StorageBatch::new();
+        let mut changes = self.change_journal_retention.map(|_| Vec::new());
+        let items = items.into_iter().inspect(|(key, _)| {
+            if let Some(changes) = changes.as_mut() {
+                changes.push((subtree_path.to_vec(), key.clone()));
+            }
+        });
+
+        let collect_costs = if let Some(transaction) = transaction {
+            self.insert_many_on_transaction(
+                subtree_path.clone(),
+                items,
+                &options,
+                transaction,
+                &batch,
+            )
+        } else {
+            self.insert_many_without_transaction(subtree_path.clone(), items, &options, &batch)
+        };
+
+        collect_costs.flat_map_ok(|_| self.commit_multi_context_batch(batch, changes, transaction))
+    }
+
+    /// Insert a GroveDB element and return the raw element previously stored
+    /// at `key`, or `None` if the slot was empty.
+    ///
+    /// The previous element is read from the same opened subtree the insert
+    /// goes to, and references are returned as is rather than followed.
+    pub fn insert_and_return_previous<'b, B, P>(
+        &self,
+        path: P,
+        key: &[u8],
+        element: Element,
+        options: Option<InsertOptions>,
+        transaction: TransactionArg,
+    ) -> CostResult<Option<Element>, Error>
+    where
+        B: AsRef<[u8]> + 'b,
+        P: Into<SubtreePath<'b, B>>,
+    {
+        let subtree_path: SubtreePath<B> = path.into();
+        let options = options.unwrap_or_default();
+        let batch = StorageBatch::new();
+        let changes = self
+            .change_journal_retention
+            .map(|_| vec![(subtree_path.to_vec(), key.to_vec())]);
+
+        let collect_costs = if let Some(transaction) = transaction {
+            self.insert_and_return_previous_on_transaction(
+                subtree_path,
+                key,
+                element,
+                &options,
+                transaction,
+                &batch,
+            )
+        } else {
+            self.insert_and_return_previous_without_transaction(
+                subtree_path,
+                key,
+                element,
+                &options,
+                &batch,
+            )
+        };
+
+        collect_costs.flat_map_ok(|previous| {
+            self.commit_multi_context_batch(batch, changes, transaction)
+                .map_ok(|_| previous)
         })
     }
 
+    /// Returns the root hash GroveDb would have after inserting `element` at
+    /// `key`, without keeping the insert. The insert is applied inside the
+    /// given transaction (or a temporary one) and rolled back once the root
+    /// hash has been computed.
+    pub fn insert_projected_root<'b, B, P>(
+        &self,
+        path: P,
+        key: &[u8],
+        element: Element,
+        transaction: TransactionArg,
+    ) -> CostResult<[u8; 32], Error>
+    where
+        B: AsRef<[u8]> + 'b,
+        P: Into<SubtreePath<'b, B>>,
+    {
+        let mut cost = OperationCost::default();
+
+        if let Some(transaction) = transaction {
+            let savepoint = cost_return_on_error_no_add!(&cost, self.savepoint(transaction));
+            let projected_root = self
+                .insert(path, key, element, None, Some(transaction))
+                .flat_map_ok(|_| self.root_hash(Some(transaction)))
+                .unwrap_add_cost(&mut cost);
+            cost_return_on_error_no_add!(&cost, self.rollback_to_savepoint(transaction, savepoint));
+            projected_root.wrap_with_cost(cost)
+        } else {
+            // Dropping the transaction without committing discards the insert
+            let transaction = cost_return_on_error_no_add!(&cost, self.try_start_transaction());
+            let projected_root = self
+                .insert(path, key, element, None, Some(&transaction))
+                .flat_map_ok(|_| self.root_hash(Some(&transaction)))
+                .unwrap_add_cost(&mut cost);
+            projected_root.wrap_with_cost(cost)
+        }
+    }
+
+    /// Insert a GroveDB element, first inserting an empty tree for every
+    /// missing subtree along the path, from the root down. Everything is done
+    /// in the given transaction, rolled back to where it was on failure, or in
+    /// a temporary one committed once the element is inserted, so missing
+    /// trees are only kept if the insert succeeds.
+    ///
+    /// Each created tree costs as much as a separate [`GroveDb::insert`] of
+    /// an empty tree, changes being propagated up to the root every time, on
+    /// top of a read checking whether each path segment exists.
+    pub fn insert_with_parents<'b, B, P>(
+        &self,
+        path: P,
+        key: &[u8],
+        element: Element,
+        options: Option<InsertOptions>,
+        transaction: TransactionArg,
+    ) -> CostResult<(), Error>
+    where
+        B: AsRef<[u8]> + 'b,
+        P: Into<SubtreePath<'b, B>>,
+    {
+        let mut cost = OperationCost::default();
+        let path = path.into().to_vec();
+
+        if let Some(transaction) = transaction {
+            // Trees created before a failure are undone, the rest of the
+            // caller's transaction is kept
+            let savepoint = cost_return_on_error_no_add!(&cost, self.savepoint(transaction));
+            let result = self
+                .insert_with_parents_on_transaction(&path, key, element, options, transaction)
+                .unwrap_add_cost(&mut cost);
+            if result.is_err() {
+                cost_return_on_error_no_add!(
+                    &cost,
+                    self.rollback_to_savepoint(transaction, savepoint)
+                );
+            }
+            result.wrap_with_cost(cost)
+        } else {
+            let transaction = cost_return_on_error_no_add!(&cost, self.try_start_transaction());
+            cost_return_on_error!(
+                &mut cost,
+                self.insert_with_parents_on_transaction(&path, key, element, options, &transaction)
+            );
+            self.commit_transaction(transaction).add_cost(cost)
+        }
+    }
+
+    fn insert_with_parents_on_transaction(
+        &self,
+        path: &[Vec<u8>],
+        key: &[u8],
+        element: Element,
+        options: Option<InsertOptions>,
+        transaction: &Transaction,
+    ) -> CostResult<(), Error> {
+        let mut cost = OperationCost::default();
+
+        for (depth, segment) in path.iter().enumerate() {
+            let parent_path = &path[..depth];
+            if !cost_return_on_error!(
+                &mut cost,
+                self.has_raw(parent_path, segment, Some(transaction))
+            ) {
+                cost_return_on_error!(
+                    &mut cost,
+                    self.insert(
+                        parent_path,
+                        segment,
+                        Element::empty_tree(),
+                        None,
+                        Some(transaction)
+                    )
+                );
+            }
+        }
+
+        self.insert(path, key, element, options, Some(transaction))
+            .add_cost(cost)
+    }
+
     fn insert_on_transaction<'db, 'b, B: AsRef<[u8]>>(
         &self,
         path: SubtreePath<'b, B>,
@@ -192,6 +402,162 @@ impl GroveDb {
         Ok(()).wrap_with_cost(cost)
     }
 
+    fn insert_many_on_transaction<'db, 'b, B, I>(
+        &self,
+        path: SubtreePath<'b, B>,
+        items: I,
+        options: &InsertOptions,
+        transaction: &'db Transaction,
+        batch: &StorageBatch,
+    ) -> CostResult<(), Error>
+    where
+        B: AsRef<[u8]>,
+        I: IntoIterator<Item = (Vec<u8>, Element)>,
+    {
+        let mut cost = OperationCost::default();
+
+        let mut merk_cache: HashMap<SubtreePath<'b, B>, Merk<PrefixedRocksDbTransactionContext>> =
+            HashMap::default();
+
+        let mut merk = cost_return_on_error!(
+            &mut cost,
+            self.open_transactional_merk_at_path(path.clone(), transaction, Some(batch))
+        );
+        for (key, element) in items {
+            cost_return_on_error!(
+                &mut cost,
+                self.add_element_to_merk_on_transaction(
+                    &mut merk,
+                    &path,
+                    &key,
+                    element,
+                    options,
+                    transaction,
+                    batch
+                )
+            );
+        }
+        merk_cache.insert(path.clone(), merk);
+        cost_return_on_error!(
+            &mut cost,
+            self.propagate_changes_with_transaction(merk_cache, path, transaction, batch)
+        );
+
+        Ok(()).wrap_with_cost(cost)
+    }
+
+    fn insert_many_without_transaction<'b, B, I>(
+        &self,
+        path: SubtreePath<'b, B>,
+        items: I,
+        options: &InsertOptions,
+        batch: &StorageBatch,
+    ) -> CostResult<(), Error>
+    where
+        B: AsRef<[u8]>,
+        I: IntoIterator<Item = (Vec<u8>, Element)>,
+    {
+        let mut cost = OperationCost::default();
+
+        let mut merk_cache: HashMap<SubtreePath<'b, B>, Merk<PrefixedRocksDbStorageContext>> =
+            HashMap::default();
+
+        let path_vec = path.to_vec();
+        let mut merk = cost_return_on_error!(
+            &mut cost,
+            self.open_non_transactional_merk_at_path(path.clone(), Some(batch))
+        );
+        for (key, element) in items {
+            cost_return_on_error!(
+                &mut cost,
+                self.add_element_to_merk_without_transaction(
+                    &mut merk, &path_vec, &key, element, options, batch
+                )
+            );
+        }
+        merk_cache.insert(path.clone(), merk);
+        cost_return_on_error!(
+            &mut cost,
+            self.propagate_changes_without_transaction(merk_cache, path, batch)
+        );
+
+        Ok(()).wrap_with_cost(cost)
+    }
+
+    fn insert_and_return_previous_on_transaction<'db, 'b, B: AsRef<[u8]>>(
+        &self,
+        path: SubtreePath<'b, B>,
+        key: &[u8],
+        element: Element,
+        options: &InsertOptions,
+        transaction: &'db Transaction,
+        batch: &StorageBatch,
+    ) -> CostResult<Option<Element>, Error> {
+        let mut cost = OperationCost::default();
+
+        let mut merk_cache: HashMap<SubtreePath<'b, B>, Merk<PrefixedRocksDbTransactionContext>> =
+            HashMap::default();
+
+        let mut merk = cost_return_on_error!(
+            &mut cost,
+            self.open_transactional_merk_at_path(path.clone(), transaction, Some(batch))
+        );
+        let previous = cost_return_on_error!(&mut cost, Element::get_optional(&merk, key, true));
+        cost_return_on_error!(
+            &mut cost,
+            self.add_element_to_merk_on_transaction(
+                &mut merk,
+                &path,
+                key,
+                element,
+                options,
+                transaction,
+                batch
+            )
+        );
+        merk_cache.insert(path.clone(), merk);
+        cost_return_on_error!(
+            &mut cost,
+            self.propagate_changes_with_transaction(merk_cache, path, transaction, batch)
+        );
+
+        Ok(previous).wrap_with_cost(cost)
+    }
+
+    fn insert_and_return_previous_without_transaction<'b, B: AsRef<[u8]>>(
+        &self,
+        path: SubtreePath<'b, B>,
+        key: &[u8],
+        element: Element,
+        options: &InsertOptions,
+        batch: &StorageBatch,
+    ) -> CostResult<Option<Element>, Error> {
+        let mut cost = OperationCost::default();
+
+        let mut merk_cache: HashMap<SubtreePath<'b, B>, Merk<PrefixedRocksDbStorageContext>> =
+            HashMap::default();
+
+        let path_vec = path.to_vec();
+        let mut merk = cost_return_on_error!(
+            &mut cost,
+            self.open_non_transactional_merk_at_path(path.clone(), Some(batch))
+        );
+        let previous = cost_return_on_error!(&mut cost, Element::get_optional(&merk, key, true));
+        cost_return_on_error!(
+            &mut cost,
+            self.add_element_to_merk_without_transaction(
+                &mut merk, &path_vec, key, element, options, batch
+            )
+        );
+        merk_cache.insert(path.clone(), merk);
+        cost_return_on_error!(
+            &mut cost,
+            self.propagate_changes_without_transaction(merk_cache, path, batch)
+        );
+
+        Ok(previous).wrap_with_cost(cost)
+    }
+
     /// Add subtree to another subtree.
     /// We want to add a new empty merk to another merk at a key
     /// first make sure other merk exist
@@ -212,8 +578,37 @@ impl GroveDb {
             &mut cost,
             self.open_transactional_merk_at_path(path.clone(), transaction, Some(batch))
         );
-        // if we don't allow a tree override then we should check
+        cost_return_on_error!(
+            &mut cost,
+            self.add_element_to_merk_on_transaction(
+                &mut subtree_to_insert_into,
+                &path,
+                key,
+                element,
+                &options,
+                transaction,
+                batch
+            )
+        );
 
+        Ok(subtree_to_insert_into).wrap_with_cost(cost)
+    }
+
+    /// Add an element to an already opened transactional Merk located at the
+    /// given path, changes are not propagated up the tree.
+    fn add_element_to_merk_on_transaction<'db, B: AsRef<[u8]>>(
+        &'db self,
+        subtree_to_insert_into: &mut Merk<PrefixedRocksDbTransactionContext<'db>>,
+        path: &SubtreePath<B>,
+        key: &[u8],
+        element: Element,
+        options: &InsertOptions,
+        transaction: &'db Transaction,
+        batch: &'db StorageBatch,
+    ) -> CostResult<(), Error> {
+        let mut cost = OperationCost::default();
+
+        // if we don't allow a tree override then we should check
         if options.checks_for_override() {
             let maybe_element_bytes = cost_return_on_error!(
                 &mut cost,
@@ -248,6 +643,7 @@ impl GroveDb {
         match element {
             Element::Reference(ref reference_path, ..) => {
                 let path = path.to_vec(); // TODO: need for support for references in path library
+                cost_return_on_error_no_add!(&cost, reference_path.validate_at(&path));
                 let reference_path = cost_return_on_error!(
                     &mut cost,
                     path_from_reference_path_type(reference_path.clone(), &path, Some(key))
@@ -290,7 +686,7 @@ impl GroveDb {
                 cost_return_on_error!(
                     &mut cost,
                     element.insert_reference(
-                        &mut subtree_to_insert_into,
+                        subtree_to_insert_into,
                         key,
                         referenced_element_value_hash,
                         Some(options.as_merk_options()),
@@ -304,10 +700,19 @@ impl GroveDb {
                     ))
                     .wrap_with_cost(cost);
                 } else {
+                    let subtree_path = path.derive_owned_with_child(key);
+                    cost_return_on_error!(
+                        &mut cost,
+                        self.clear_tombstoned_subtree(
+                            &SubtreePath::from(&subtree_path),
+                            Some(transaction),
+                            batch
+                        )
+                    );
                     cost_return_on_error!(
                         &mut cost,
                         element.insert_subtree(
-                            &mut subtree_to_insert_into,
+                            subtree_to_insert_into,
                             key,
                             NULL_HASH,
                             Some(options.as_merk_options())
@@ -318,16 +723,12 @@ impl GroveDb {
             _ => {
                 cost_return_on_error!(
                     &mut cost,
-                    element.insert(
-                        &mut subtree_to_insert_into,
-                        key,
-                        Some(options.as_merk_options())
-                    )
+                    element.insert(subtree_to_insert_into, key, Some(options.as_merk_options()))
                 );
             }
         }
 
-        Ok(subtree_to_insert_into).wrap_with_cost(cost)
+        Ok(()).wrap_with_cost(cost)
     }
 
     /// Add an empty tree or item to a parent tree.
@@ -348,6 +749,33 @@ impl GroveDb {
             &mut cost,
             self.open_non_transactional_merk_at_path(path.into(), Some(batch))
         );
+        cost_return_on_error!(
+            &mut cost,
+            self.add_element_to_merk_without_transaction(
+                &mut subtree_to_insert_into,
+                path,
+                key,
+                element,
+                &options,
+                batch
+            )
+        );
+
+        Ok(subtree_to_insert_into).wrap_with_cost(cost)
+    }
+
+    /// Add an element to an already opened Merk located at the given path,
+    /// changes are not propagated up the tree.
+    fn add_element_to_merk_without_transaction<'db, B: AsRef<[u8]>>(
+        &'db self,
+        subtree_to_insert_into: &mut Merk<PrefixedRocksDbStorageContext<'db>>,
+        path: &[B],
+        key: &[u8],
+        element: Element,
+        options: &InsertOptions,
+        batch: &'db StorageBatch,
+    ) -> CostResult<(), Error> {
+        let mut cost = OperationCost::default();
 
         if options.checks_for_override() {
             let maybe_element_bytes = cost_return_on_error!(
@@ -382,6 +810,7 @@ impl GroveDb {
 
         match element {
             Element::Reference(ref reference_path, ..) => {
+                cost_return_on_error_no_add!(&cost, reference_path.validate_at(path));
                 let reference_path = cost_return_on_error!(
                     &mut cost,
                     path_from_reference_path_type(reference_path.clone(), path, Some(key))
@@ -422,7 +851,7 @@ impl GroveDb {
                 cost_return_on_error!(
                     &mut cost,
                     element.insert_reference(
-                        &mut subtree_to_insert_into,
+                        subtree_to_insert_into,
                         key,
                         referenced_element_value_hash,
                         Some(options.as_merk_options())
@@ -436,10 +865,20 @@ impl GroveDb {
                     ))
                     .wrap_with_cost(cost);
                 } else {
+                    let parent_path: SubtreePath<B> = path.into();
+                    let subtree_path = parent_path.derive_owned_with_child(key);
+                    cost_return_on_error!(
+                        &mut cost,
+                        self.clear_tombstoned_subtree(
+                            &SubtreePath::from(&subtree_path),
+                            None,
+                            batch
+                        )
+                    );
                     cost_return_on_error!(
                         &mut cost,
                         element.insert_subtree(
-                            &mut subtree_to_insert_into,
+                            subtree_to_insert_into,
                             key,
                             NULL_HASH,
                             Some(options.as_merk_options())
@@ -450,16 +889,12 @@ impl GroveDb {
             _ => {
                 cost_return_on_error!(
                     &mut cost,
-                    element.insert(
-                        &mut subtree_to_insert_into,
-                        key,
-                        Some(options.as_merk_options())
-                    )
+                    element.insert(subtree_to_insert_into, key, Some(options.as_merk_options()))
                 );
             }
         }
 
-        Ok(subtree_to_insert_into).wrap_with_cost(cost)
+        Ok(()).wrap_with_cost(cost)
     }
 
     /// Insert if not exists
@@ -535,6 +970,7 @@ mod tests {
 
     use crate::{
         operations::insert::InsertOptions,
+        reference_path::ReferencePathType,
         tests::{common::EMPTY_PATH, make_empty_grovedb, make_test_grovedb, TEST_LEAF},
         Element, Error,
     };
@@ -554,6 +990,95 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_insert_with_parents() {
+        let db = make_test_grovedb();
+        let element = Element::new_item(b"ayy".to_vec());
+
+        db.insert_with_parents(
+            [TEST_LEAF, b"a", b"b", b"c"].as_ref(),
+            b"key",
+            element.clone(),
+            None,
+            None,
+        )
+        .unwrap()
+        .expect("successful insert");
+        assert_eq!(
+            db.get([TEST_LEAF, b"a", b"b", b"c"].as_ref(), b"key", None)
+                .unwrap()
+                .expect("successful get"),
+            element
+        );
+        for (path, key) in [
+            (vec![TEST_LEAF], b"a"),
+            (vec![TEST_LEAF, b"a"], b"b"),
+            (vec![TEST_LEAF, b"a", b"b"], b"c"),
+        ] {
+            assert!(db
+                .get(path.as_slice(), key, None)
+                .unwrap()
+                .expect("successful get")
+                .is_tree());
+        }
+
+        // Existing trees are kept, missing ones are only created in the
+        // transaction
+        let transaction = db.start_transaction();
+        db.insert_with_parents(
+            [TEST_LEAF, b"a", b"d"].as_ref(),
+            b"key",
+            element.clone(),
+            None,
+            Some(&transaction),
+        )
+        .unwrap()
+        .expect("successful insert");
+        assert_eq!(
+            db.get([TEST_LEAF, b"a", b"b", b"c"].as_ref(), b"key", None)
+                .unwrap()
+                .expect("successful get"),
+            element
+        );
+        assert!(db
+            .get([TEST_LEAF, b"a"].as_ref(), b"d", None)
+            .unwrap()
+            .is_err());
+        assert_eq!(
+            db.get([TEST_LEAF, b"a", b"d"].as_ref(), b"key", Some(&transaction))
+                .unwrap()
+                .expect("successful get"),
+            element
+        );
+
+        // A failed insert doesn't leave its trees in the caller's transaction
+        let missing_reference =
+            Element::new_reference(ReferencePathType::AbsolutePathReference(vec![
+                TEST_LEAF.to_vec(),
+                b"missing".to_vec(),
+            ]));
+        assert!(db
+            .insert_with_parents(
+                [TEST_LEAF, b"e", b"f"].as_ref(),
+                b"key",
+                missing_reference,
+                None,
+                Some(&transaction),
+            )
+            .unwrap()
+            .is_err());
+        assert!(db
+            .get([TEST_LEAF].as_ref(), b"e", Some(&transaction))
+            .unwrap()
+            .is_err());
+        assert_eq!(
+            db.get([TEST_LEAF, b"a", b"d"].as_ref(), b"key", Some(&transaction))
+                .unwrap()
+                .expect("successful get"),
+            element
+        );
+    }
+
     #[test]
     fn test_non_root_insert_subtree_then_insert_item_without_transaction() {
         let db = make_test_grovedb();
@@ -719,7 +1244,8 @@ mod tests {
         // 4 bytes for the key
         // 1 byte for key_size (required space for 36)
 
-        // Value -> 72
+        // Value -> 73
+        //   1 for the element format version
         //   1 for the flag option (but no flags)
         //   1 for the enum type item
         //   3 for "cat"
@@ -727,7 +1253,7 @@ mod tests {
         // 32 for node hash
         // 32 for value hash (trees have this for free)
         // 1 for Basic merk
-        // 1 byte for the value_size (required space for 70)
+        // 1 byte for the value_size (required space for 71)
 
         // Parent Hook -> 40
         // Key Bytes 4
@@ -736,7 +1262,7 @@ mod tests {
         // Basic Merk 1
         // Child Heights 2
 
-        // Total 37 + 72 + 40 = 149
+        // Total 37 + 73 + 40 = 150
 
         // Hash node calls
         // 1 for the kv_digest_to_kv_hash hash
@@ -746,7 +1272,7 @@ mod tests {
             OperationCost {
                 seek_count: 3, // 1 to get tree, 1 to insert, 1 to insert into root tree
                 storage_cost: StorageCost {
-                    added_bytes: 149,
+                    added_bytes: 150,
                     replaced_bytes: 0,
                     removed_bytes: NoStorageRemoval
                 },
@@ -756,6 +1282,66 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_one_insert_large_item_cost() {
+        let db = make_empty_grovedb();
+        let tx = db.start_transaction();
+
+        let cost = db
+            .insert(
+                EMPTY_PATH,
+                b"key1",
+                Element::new_item(vec![7u8; 20000]),
+                None,
+                Some(&tx),
+            )
+            .cost_as_result()
+            .expect("should insert");
+        // Explanation for 20151 storage_written_bytes
+
+        // Key -> 37 bytes
+        // 32 bytes for the key prefix
+        // 4 bytes for the key
+        // 1 byte for key_size (required space for 36)
+
+        // Value -> 20074
+        //     1 for the element format version
+        //     1 for the flag option (but no flags)
+        //     1 for the enum type item
+        // 20000 for the item
+        //     3 for the item length (bincode varint above 250)
+        //    32 for node hash
+        //    32 for value hash
+        //     1 for Basic merk
+        //     3 bytes for the value_size (required space for 20071)
+
+        // Parent Hook -> 40
+        // Key Bytes 4
+        // Hash Size 32
+        // Key Length 1
+        // Basic Merk 1
+        // Child Heights 2
+
+        // Total 37 + 20074 + 40 = 20151
+
+        // Hash node calls
+        // 313 for the value hash (20006 bytes and a 3 byte length in 64 byte blocks)
+        // 1 for the kv_digest_to_kv_hash hash
+        assert_eq!(
+            cost,
+            OperationCost {
+                seek_count: 3, // 1 to get tree, 1 to insert, 1 to insert into root tree
+                storage_cost: StorageCost {
+                    added_bytes: 20151,
+                    replaced_bytes: 0,
+                    removed_bytes: NoStorageRemoval
+                },
+                storage_loaded_bytes: 0,
+                hash_node_calls: 314,
+            }
+        );
+    }
+
     #[test]
     fn test_one_insert_sum_item_in_sum_tree_cost() {
         let db = make_empty_grovedb();
@@ -782,7 +1368,8 @@ mod tests {
         // 4 bytes for the key
         // 1 byte for key_size (required space for 36)
 
-        // Value -> 85
+        // Value -> 86
+        //   1 for the element format version
         //   1 for the enum type item
         //   9 for the value (encoded var vec)
         //   1 for the flag option (but no flags)
@@ -798,17 +1385,17 @@ mod tests {
         // Summed Merk 9
         // Child Heights 2
 
-        // Total 37 + 85 + 48 = 170
+        // Total 37 + 86 + 48 = 171
         assert_eq!(
             cost,
             OperationCost {
                 seek_count: 5,
                 storage_cost: StorageCost {
-                    added_bytes: 170,
-                    replaced_bytes: 84, // todo: verify
+                    added_bytes: 171,
+                    replaced_bytes: 85, // todo: verify
                     removed_bytes: NoStorageRemoval
                 },
-                storage_loaded_bytes: 156,
+                storage_loaded_bytes: 158,
                 hash_node_calls: 8,
             }
         );
@@ -850,7 +1437,8 @@ mod tests {
         // 4 bytes for the key
         // 1 byte for key_size (required space for 36)
 
-        // Value -> 85
+        // Value -> 86
+        //   1 for the element format version
         //   1 for the flag option (but no flags)
         //   1 for the enum type item
         //   9 for the value (encoded var vec)
@@ -866,17 +1454,17 @@ mod tests {
         // Summed Merk 9
         // Child Heights 2
 
-        // Total 37 + 85 + 48 = 170
+        // Total 37 + 86 + 48 = 171
         assert_eq!(
             cost,
             OperationCost {
                 seek_count: 7,
                 storage_cost: StorageCost {
-                    added_bytes: 170,
-                    replaced_bytes: 209, // todo: verify
+                    added_bytes: 171,
+                    replaced_bytes: 210, // todo: verify
                     removed_bytes: NoStorageRemoval
                 },
-                storage_loaded_bytes: 232,
+                storage_loaded_bytes: 235,
                 hash_node_calls: 10,
             }
         );
@@ -919,7 +1507,8 @@ mod tests {
         // 4 bytes for the key
         // 1 byte for key_size (required space for 36)
 
-        // Value -> 85
+        // Value -> 86
+        //   1 for the element format version
         //   1 for the flag option (but no flags)
         //   1 for the enum type item
         //   9 for the value (encoded var vec)
@@ -935,17 +1524,17 @@ mod tests {
         // Summed Merk 9
         // Child Heights 2
 
-        // Total 37 + 85 + 48 = 170
+        // Total 37 + 86 + 48 = 171
         assert_eq!(
             cost,
             OperationCost {
                 seek_count: 7,
                 storage_cost: StorageCost {
-                    added_bytes: 170,
-                    replaced_bytes: 211, // todo: verify
+                    added_bytes: 171,
+                    replaced_bytes: 212, // todo: verify
                     removed_bytes: NoStorageRemoval
                 },
-                storage_loaded_bytes: 237,
+                storage_loaded_bytes: 240,
                 hash_node_calls: 10,
             }
         );
@@ -972,7 +1561,8 @@ mod tests {
         // 4 bytes for the key
         // 1 byte for key_size (required space for 36)
 
-        // Value -> 76
+        // Value -> 77
+        //   1 for the element format version
         //   1 for the flag option
         //   3 for flags
         //   1 for flags length
@@ -982,7 +1572,7 @@ mod tests {
         //   1 for basic merk
         // 32 for node hash
         // 32 for value hash (trees have this for free)
-        // 1 byte for the value_size (required space for 70)
+        // 1 byte for the value_size (required space for 75)
 
         // Parent Hook -> 40
         // Key Bytes 4
@@ -990,7 +1580,7 @@ mod tests {
         // Key Length 1
         // Child Heights 2
         // Sum 1
-        // Total 37 + 76 + 40 = 153
+        // Total 37 + 77 + 40 = 154
 
         // Hash node calls
         // 1 for the kv_digest_to_kv_hash hash
@@ -1000,7 +1590,7 @@ mod tests {
             OperationCost {
                 seek_count: 3, // 1 to get tree, 1 to insert, 1 to insert into root tree
                 storage_cost: StorageCost {
-                    added_bytes: 153,
+                    added_bytes: 154,
                     replaced_bytes: 0,
                     removed_bytes: NoStorageRemoval
                 },
@@ -1025,7 +1615,8 @@ mod tests {
         // 4 bytes for the key
         // 1 byte for key_size (required space for 36)
 
-        // Value -> 38
+        // Value -> 39
+        //   1 for the element format version
         //   1 for the flag option (but no flags)
         //   1 for the enum type tree
         //   1 for empty option
@@ -1042,7 +1633,7 @@ mod tests {
         // Child Heights 2
         // Sum 1
 
-        // Total 37 + 38 + 40 = 115
+        // Total 37 + 39 + 40 = 116
 
         // Hash node calls
         // 1 for the node hash
@@ -1052,7 +1643,7 @@ mod tests {
             OperationCost {
                 seek_count: 3, // 1 to get tree, 1 to insert, 1 to insert into root tree
                 storage_cost: StorageCost {
-                    added_bytes: 115,
+                    added_bytes: 116,
                     replaced_bytes: 0,
                     removed_bytes: NoStorageRemoval
                 },
@@ -1083,7 +1674,8 @@ mod tests {
         // 4 bytes for the key
         // 1 byte for key_size (required space for 36)
 
-        // Value -> 47
+        // Value -> 48
+        //   1 for the element format version
         //   1 for the flag option (but no flags)
         //   1 for the enum type tree
         //   1 for empty option
@@ -1101,7 +1693,7 @@ mod tests {
         // Child Heights 2
         // Sum 1
 
-        // Total 37 + 47 + 40 = 124
+        // Total 37 + 48 + 40 = 125
 
         // Hash node calls
         // 1 for the node hash
@@ -1111,7 +1703,7 @@ mod tests {
             OperationCost {
                 seek_count: 3, // 1 to get tree, 1 to insert, 1 to insert into root tree
                 storage_cost: StorageCost {
-                    added_bytes: 124,
+                    added_bytes: 125,
                     replaced_bytes: 0,
                     removed_bytes: NoStorageRemoval
                 },
@@ -1142,7 +1734,8 @@ mod tests {
         // 4 bytes for the key
         // 1 byte for key_size (required space for 36)
 
-        // Value -> 42
+        // Value -> 43
+        //   1 for the element format version
         //   1 for the flag option
         //   1 byte for flag size
         //   3 bytes for flags
@@ -1161,7 +1754,7 @@ mod tests {
         // Child Heights 2
         // Sum 1
 
-        // Total 37 + 42 + 40 = 119
+        // Total 37 + 43 + 40 = 120
 
         // Hash node calls
         // 1 for the kv_digest_to_kv_hash hash
@@ -1173,7 +1766,7 @@ mod tests {
             OperationCost {
                 seek_count: 3, // 1 to get tree, 1 to insert, 1 to insert into root tree
                 storage_cost: StorageCost {
-                    added_bytes: 119,
+                    added_bytes: 120,
                     replaced_bytes: 0,
                     removed_bytes: NoStorageRemoval
                 },
@@ -1210,7 +1803,8 @@ mod tests {
         // 4 bytes for the key
         // 1 byte for key_size (required space for 36)
 
-        // Value -> 73
+        // Value -> 74
+        //   1 for the element format version
         //   1 for the flag option (but no flags)
         //   1 for the enum type
         //   1 for size of test bytes
@@ -1218,7 +1812,7 @@ mod tests {
         //   1 for a basic merk
         // 32 for node hash
         // 32 for value hash
-        // 1 byte for the value_size (required space for 72)
+        // 1 byte for the value_size (required space for 73)
 
         // Parent Hook -> 40
         // Key Bytes 4
@@ -1227,11 +1821,12 @@ mod tests {
         // Child Heights 2
         // Sum 1
 
-        // Total 37 + 73 + 40 = 150
+        // Total 37 + 74 + 40 = 151
 
         // Explanation for replaced bytes
 
-        // Replaced parent Value -> 78
+        // Replaced parent Value -> 79
+        //   1 for the element format version
         //   1 for the flag option (but no flags)
         //   1 for the enum type
         //   1 for an empty option
@@ -1244,11 +1839,11 @@ mod tests {
             OperationCost {
                 seek_count: 5, // todo: verify this
                 storage_cost: StorageCost {
-                    added_bytes: 150,
-                    replaced_bytes: 78,
+                    added_bytes: 151,
+                    replaced_bytes: 79,
                     removed_bytes: NoStorageRemoval
                 },
-                storage_loaded_bytes: 152, // todo: verify this
+                storage_loaded_bytes: 154, // todo: verify this
                 hash_node_calls: 8,        // todo: verify this
             }
         );
@@ -1277,7 +1872,8 @@ mod tests {
         // 4 bytes for the key
         // 1 byte for key_size (required space for 36)
 
-        // Value -> 79
+        // Value -> 80
+        //   1 for the element format version
         //   1 for the flag option
         //   1 for flags byte size
         //   5 for flags bytes
@@ -1287,7 +1883,7 @@ mod tests {
         //   1 for a basic merk
         // 32 for node hash
         // 32 for value hash
-        // 1 byte for the value_size (required space for 77)
+        // 1 byte for the value_size (required space for 78)
 
         // Parent Hook -> 40
         // Key Bytes 4
@@ -1296,7 +1892,7 @@ mod tests {
         // Child Heights 2
         // Sum 1
 
-        // Total 37 + 79 + 40 = 156
+        // Total 37 + 80 + 40 = 157
 
         // Hash node calls
         // 1 for the kv_digest_to_kv_hash hash
@@ -1307,7 +1903,7 @@ mod tests {
             OperationCost {
                 seek_count: 3, // todo: verify this
                 storage_cost: StorageCost {
-                    added_bytes: 156,
+                    added_bytes: 157,
                     replaced_bytes: 0,
                     removed_bytes: NoStorageRemoval
                 },
@@ -1344,7 +1940,8 @@ mod tests {
         // 4 bytes for the key
         // 1 byte for key_size (required space for 36)
 
-        // Value -> 79
+        // Value -> 80
+        //   1 for the element format version
         //   1 for the flag option
         //   1 for flags byte size
         //   5 for flags bytes
@@ -1354,7 +1951,7 @@ mod tests {
         //   1 for the basic merk
         // 32 for node hash
         // 32 for value hash
-        // 1 byte for the value_size (required space for 78)
+        // 1 byte for the value_size (required space for 79)
 
         // Parent Hook -> 40
         // Key Bytes 4
@@ -1363,11 +1960,12 @@ mod tests {
         // Child Heights 2
         // Sum 1
 
-        // Total 37 + 79 + 40 = 156
+        // Total 37 + 80 + 40 = 157
 
         // Explanation for replaced bytes
 
-        // Replaced parent Value -> 78
+        // Replaced parent Value -> 79
+        //   1 for the element format version
         //   1 for the flag option (but no flags)
         //   1 for the enum type
         //   1 for an empty option
@@ -1392,11 +1990,11 @@ mod tests {
             OperationCost {
                 seek_count: 5, // todo: verify this
                 storage_cost: StorageCost {
-                    added_bytes: 156,
-                    replaced_bytes: 78,
+                    added_bytes: 157,
+                    replaced_bytes: 79,
                     removed_bytes: NoStorageRemoval
                 },
-                storage_loaded_bytes: 152, // todo: verify this
+                storage_loaded_bytes: 154, // todo: verify this
                 hash_node_calls: 8,
             }
         );
@@ -1435,7 +2033,8 @@ mod tests {
         // 4 bytes for the key
         // 1 byte for key_size (required space for 36)
 
-        // Value -> 79
+        // Value -> 80
+        //   1 for the element format version
         //   1 for the flag option
         //   1 for flags byte size
         //   5 for flags bytes
@@ -1445,7 +2044,7 @@ mod tests {
         //   1 for basic merk
         // 32 for node hash
         // 32 for value hash
-        // 1 byte for the value_size (required space for 78)
+        // 1 byte for the value_size (required space for 79)
 
         // Parent Hook -> 40
         // Key Bytes 4
@@ -1453,11 +2052,12 @@ mod tests {
         // Key Length 1
         // Child Heights 2
         // Sum 1
-        // Total 37 + 79 + 40 = 156
+        // Total 37 + 80 + 40 = 157
 
         // Explanation for replaced bytes
 
-        // Replaced parent Value -> 82
+        // Replaced parent Value -> 83
+        //   1 for the element format version
         //   1 for the flag option
         //   3 bytes for flags
         //   1 for flags size
@@ -1485,11 +2085,11 @@ mod tests {
             OperationCost {
                 seek_count: 5, // todo: verify this
                 storage_cost: StorageCost {
-                    added_bytes: 156,
-                    replaced_bytes: 82,
+                    added_bytes: 157,
+                    replaced_bytes: 83,
                     removed_bytes: NoStorageRemoval
                 },
-                storage_loaded_bytes: 160, // todo: verify this
+                storage_loaded_bytes: 162, // todo: verify this
                 hash_node_calls: 8,
             }
         );
@@ -1523,7 +2123,8 @@ mod tests {
 
         // Explanation for 110 replaced bytes
 
-        // Value -> 72
+        // Value -> 73
+        //   1 for the element format version
         //   1 for the flag option (but no flags)
         //   1 for the enum type item
         //   3 for "cat"
@@ -1531,7 +2132,7 @@ mod tests {
         //   1 for basic merk
         // 32 for node hash
         // 32 for value hash (trees have this for free)
-        // 1 byte for the value_size (required space for 71)
+        // 1 byte for the value_size (required space for 72)
 
         // Parent Hook -> 40
         // Key Bytes 4
@@ -1540,7 +2141,7 @@ mod tests {
         // Child Heights 2
         // Sum 1
 
-        // 72 + 40 = 112
+        // 73 + 40 = 113
 
         // Hash node calls
         // 1 for the kv_digest_to_kv_hash hash
@@ -1552,10 +2153,10 @@ mod tests {
                 seek_count: 3, // todo: verify this
                 storage_cost: StorageCost {
                     added_bytes: 0,
-                    replaced_bytes: 112,
+                    replaced_bytes: 113,
                     removed_bytes: NoStorageRemoval
                 },
-                storage_loaded_bytes: 77,
+                storage_loaded_bytes: 78,
                 hash_node_calls: 2,
             }
         );
@@ -1596,10 +2197,10 @@ mod tests {
                 seek_count: 6, // todo: verify this
                 storage_cost: StorageCost {
                     added_bytes: 0,
-                    replaced_bytes: 190,
+                    replaced_bytes: 192,
                     removed_bytes: NoStorageRemoval
                 },
-                storage_loaded_bytes: 230, // todo verify this
+                storage_loaded_bytes: 233, // todo verify this
                 hash_node_calls: 8,
             }
         );
@@ -1646,10 +2247,10 @@ mod tests {
                 seek_count: 6, // todo: verify this
                 storage_cost: StorageCost {
                     added_bytes: 0,
-                    replaced_bytes: 248,
+                    replaced_bytes: 250,
                     removed_bytes: NoStorageRemoval
                 },
-                storage_loaded_bytes: 266, // todo verify this
+                storage_loaded_bytes: 269, // todo verify this
                 hash_node_calls: 9,
             }
         );
@@ -1707,10 +2308,10 @@ mod tests {
                 seek_count: 9, // todo: verify this
                 storage_cost: StorageCost {
                     added_bytes: 0,
-                    replaced_bytes: 405, // todo: verify this
+                    replaced_bytes: 407, // todo: verify this
                     removed_bytes: NoStorageRemoval
                 },
-                storage_loaded_bytes: 487, // todo verify this
+                storage_loaded_bytes: 492, // todo verify this
                 hash_node_calls: 11,
             }
         );
@@ -1757,10 +2358,10 @@ mod tests {
                 seek_count: 6, // todo: verify this
                 storage_cost: StorageCost {
                     added_bytes: 0,
-                    replaced_bytes: 248,
+                    replaced_bytes: 250,
                     removed_bytes: NoStorageRemoval
                 },
-                storage_loaded_bytes: 276, // todo verify this
+                storage_loaded_bytes: 279, // todo verify this
                 hash_node_calls: 9,
             }
         );
@@ -1801,10 +2402,10 @@ mod tests {
                 seek_count: 6, // todo: verify this
                 storage_cost: StorageCost {
                     added_bytes: 1,
-                    replaced_bytes: 191, // todo: verify this
+                    replaced_bytes: 193, // todo: verify this
                     removed_bytes: NoStorageRemoval
                 },
-                storage_loaded_bytes: 231,
+                storage_loaded_bytes: 234,
                 hash_node_calls: 8,
             }
         );
@@ -1851,7 +2452,8 @@ mod tests {
 
         // Explanation for replaced bytes
 
-        // Replaced parent Value -> 78
+        // Replaced parent Value -> 79
+        //   1 for the element format version
         //   1 for the flag option (but no flags)
         //   1 for the enum type tree
         //   1 for empty option
@@ -1862,7 +2464,8 @@ mod tests {
         // 2 byte for the value_size (required space for 98 + x where x can be up to
         // 256)
 
-        // Replaced current tree -> 78
+        // Replaced current tree -> 79
+        //   1 for the element format version
         //   1 for the flag option (but no flags)
         //   1 for the enum type tree
         //   1 for empty option
@@ -1879,12 +2482,207 @@ mod tests {
                 seek_count: 6, // todo: verify this
                 storage_cost: StorageCost {
                     added_bytes: 4,
-                    replaced_bytes: 156,
+                    replaced_bytes: 158,
                     removed_bytes: NoStorageRemoval
                 },
-                storage_loaded_bytes: 227,
+                storage_loaded_bytes: 230,
                 hash_node_calls: 9, // todo: verify this
             }
         );
     }
+
+    #[test]
+    fn test_insert_many_matches_individual_inserts_with_fewer_seeks() {
+        let items: Vec<(Vec<u8>, Element)> = (0..50u32)
+            .map(|i| {
+                (
+                    format!("key{i}").into_bytes(),
+                    Element::new_item(format!("value{i}").into_bytes()),
+                )
+            })
+            .collect();
+
+        let db_individual = make_test_grovedb();
+        let mut individual_cost = OperationCost::default();
+        for (key, element) in items.clone() {
+            db_individual
+                .insert([TEST_LEAF].as_ref(), &key, element, None, None)
+                .unwrap_add_cost(&mut individual_cost)
+                .expect("successful insert");
+        }
+
+        let db_many = make_test_grovedb();
+        let many_cost = db_many
+            .insert_many([TEST_LEAF].as_ref(), items, None, None)
+            .cost_as_result()
+            .expect("successful insert many");
+
+        assert_eq!(
+            db_individual.root_hash(None).unwrap().unwrap(),
+            db_many.root_hash(None).unwrap().unwrap()
+        );
+        assert!(many_cost.seek_count < individual_cost.seek_count);
+        assert_eq!(
+            db_many
+                .get([TEST_LEAF].as_ref(), b"key42", None)
+                .unwrap()
+                .expect("successful get"),
+            Element::new_item(b"value42".to_vec())
+        );
+    }
+
+    #[test]
+    fn test_insert_projected_root_predicts_insert() {
+        let db = make_test_grovedb();
+        let initial_root = db.root_hash(None).unwrap().expect("cannot get root hash");
+
+        let tx = db.start_transaction();
+        db.insert(
+            [TEST_LEAF].as_ref(),
+            b"key",
+            Element::new_item(b"value".to_vec()),
+            None,
+            Some(&tx),
+        )
+        .unwrap()
+        .expect("successful insert");
+        let actual_root = db
+            .root_hash(Some(&tx))
+            .unwrap()
+            .expect("cannot get root hash");
+        db.rollback_transaction(&tx)
+            .expect("cannot rollback transaction");
+        assert_ne!(actual_root, initial_root);
+
+        let projected_root = db
+            .insert_projected_root(
+                [TEST_LEAF].as_ref(),
+                b"key",
+                Element::new_item(b"value".to_vec()),
+                None,
+            )
+            .unwrap()
+            .expect("cannot project root hash");
+        assert_eq!(projected_root, actual_root);
+        assert_eq!(
+            db.root_hash(None).unwrap().expect("cannot get root hash"),
+            initial_root
+        );
+
+        // Inside a transaction the projection leaves earlier changes intact
+        db.insert(
+            [TEST_LEAF].as_ref(),
+            b"other",
+            Element::new_item(b"value".to_vec()),
+            None,
+            Some(&tx),
+        )
+        .unwrap()
+        .expect("successful insert");
+        let tx_root = db
+            .root_hash(Some(&tx))
+            .unwrap()
+            .expect("cannot get root hash");
+        db.insert_projected_root(
+            [TEST_LEAF].as_ref(),
+            b"key",
+            Element::new_item(b"value".to_vec()),
+            Some(&tx),
+        )
+        .unwrap()
+        .expect("cannot project root hash");
+        assert_eq!(
+            db.root_hash(Some(&tx))
+                .unwrap()
+                .expect("cannot get root hash"),
+            tx_root
+        );
+        assert!(db
+            .get([TEST_LEAF].as_ref(), b"key", Some(&tx))
+            .unwrap()
+            .is_err());
+    }
+
+    #[test]
+    fn test_insert_and_return_previous() {
+        let db = make_test_grovedb();
+
+        let previous = db
+            .insert_and_return_previous(
+                [TEST_LEAF].as_ref(),
+                b"key",
+                Element::new_item(b"first".to_vec()),
+                None,
+                None,
+            )
+            .unwrap()
+            .expect("successful insert");
+        assert_eq!(previous, None);
+
+        let previous = db
+            .insert_and_return_previous(
+                [TEST_LEAF].as_ref(),
+                b"key",
+                Element::new_item(b"second".to_vec()),
+                None,
+                None,
+            )
+            .unwrap()
+            .expect("successful insert");
+        assert_eq!(previous, Some(Element::new_item(b"first".to_vec())));
+        assert_eq!(
+            db.get([TEST_LEAF].as_ref(), b"key", None)
+                .unwrap()
+                .expect("successful get"),
+            Element::new_item(b"second".to_vec())
+        );
+
+        // Inside a transaction the previous element is the transactional one
+        let tx = db.start_transaction();
+        let previous = db
+            .insert_and_return_previous(
+                [TEST_LEAF].as_ref(),
+                b"key",
+                Element::new_item(b"third".to_vec()),
+                None,
+                Some(&tx),
+            )
+            .unwrap()
+            .expect("successful insert");
+        assert_eq!(previous, Some(Element::new_item(b"second".to_vec())));
+    }
+
+    #[test]
+    fn test_insert_and_return_previous_returns_raw_reference() {
+        let db = make_test_grovedb();
+        db.insert(
+            [TEST_LEAF].as_ref(),
+            b"target",
+            Element::new_item(b"value".to_vec()),
+            None,
+            None,
+        )
+        .unwrap()
+        .expect("successful insert");
+
+        let reference = Element::new_reference(ReferencePathType::AbsolutePathReference(vec![
+            TEST_LEAF.to_vec(),
+            b"target".to_vec(),
+        ]));
+        db.insert([TEST_LEAF].as_ref(), b"ref", reference.clone(), None, None)
+            .unwrap()
+            .expect("successful insert");
+
+        let previous = db
+            .insert_and_return_previous(
+                [TEST_LEAF].as_ref(),
+                b"ref",
+                Element::new_item(b"replaced".to_vec()),
+                None,
+                None,
+            )
+            .unwrap()
+            .expect("successful insert");
+        assert_eq!(previous, Some(reference));
+    }
 }
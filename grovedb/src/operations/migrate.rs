@@ -0,0 +1,181 @@
+// MIT LICENSE
+//
+// Copyright (c) 2021 Dash Core Group
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Rewriting the elements of a subtree
+
+#[cfg(feature = "full")]
+use grovedb_costs::{cost_return_on_error, CostResult, CostsExt, OperationCost};
+#[cfg(feature = "full")]
+use grovedb_path::SubtreePath;
+#[cfg(feature = "full")]
+use grovedb_storage::StorageContext;
+
+#[cfg(feature = "full")]
+use crate::{
+    batch::GroveDbOp, util::storage_context_optional_tx, Element, Error, GroveDb, TransactionArg,
+};
+
+#[cfg(feature = "full")]
+impl GroveDb {
+    /// Passes every element of the subtree at `path` to `f` and writes back
+    /// what it returns: the element is replaced with `Ok(Some(element))` and
+    /// deleted with `Ok(None)`. Unchanged elements are not rewritten, and the
+    /// changes are applied as a single batch so hashes are propagated once.
+    ///
+    /// Child subtrees are not passed to `f`, their elements carry root keys
+    /// and hashes maintained by GroveDB. If `f` fails nothing is written.
+    pub fn migrate_subtree<'b, B, P, F>(
+        &self,
+        path: P,
+        transaction: TransactionArg,
+        mut f: F,
+    ) -> CostResult<(), Error>
+    where
+        B: AsRef<[u8]> + 'b,
+        P: Into<SubtreePath<'b, B>>,
+        F: FnMut(Element) -> Result<Option<Element>, Error>,
+    {
+        let mut cost = OperationCost::default();
+        let path: SubtreePath<B> = path.into();
+
+        cost_return_on_error!(
+            &mut cost,
+            self.check_subtree_exists_path_not_found(path.clone(), transaction)
+        );
+
+        let mut elements = Vec::new();
+        storage_context_optional_tx!(self.db, path.clone(), None, transaction, storage, {
+            let storage = storage.unwrap_add_cost(&mut cost);
+            let mut iter = Element::iterator(storage.raw_iter()).unwrap_add_cost(&mut cost);
+            while let Some((key, element)) = cost_return_on_error!(&mut cost, iter.next_element()) {
+                if !element.is_tree() {
+                    elements.push((key, element));
+                }
+            }
+        });
+
+        let path = path.to_vec();
+        let mut ops = Vec::new();
+        for (key, element) in elements {
+            match f(element.clone()) {
+                Ok(Some(migrated)) => {
+                    if migrated != element {
+                        ops.push(GroveDbOp::insert_op(path.clone(), key, migrated));
+                    }
+                }
+                Ok(None) => ops.push(GroveDbOp::delete_op(path.clone(), key)),
+                Err(e) => return Err(e).wrap_with_cost(cost),
+            }
+        }
+
+        self.apply_batch(ops, None, transaction).add_cost(cost)
+    }
+}
+
+#[cfg(feature = "full")]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{make_test_grovedb, TEST_LEAF};
+
+    #[test]
+    fn test_migrate_subtree() {
+        let db = make_test_grovedb();
+        let expected_db = make_test_grovedb();
+
+        for i in 0u8..5 {
+            db.insert(
+                [TEST_LEAF].as_ref(),
+                &[i],
+                Element::new_item(vec![i]),
+                None,
+                None,
+            )
+            .unwrap()
+            .expect("successful insert");
+            expected_db
+                .insert(
+                    [TEST_LEAF].as_ref(),
+                    &[i],
+                    Element::new_item(vec![i, 0xff]),
+                    None,
+                    None,
+                )
+                .unwrap()
+                .expect("successful insert");
+        }
+        for grove in [&db, &expected_db] {
+            grove
+                .insert(
+                    [TEST_LEAF].as_ref(),
+                    b"subtree",
+                    Element::empty_tree(),
+                    None,
+                    None,
+                )
+                .unwrap()
+                .expect("successful insert");
+        }
+
+        let root_hash = db.root_hash(None).unwrap().unwrap();
+        db.migrate_subtree([TEST_LEAF].as_ref(), None, |element| match element {
+            Element::Item(mut value, flags) => {
+                value.push(0xff);
+                Ok(Some(Element::Item(value, flags)))
+            }
+            _ => Err(Error::InvalidInput("expected only items to be migrated")),
+        })
+        .unwrap()
+        .expect("successful migration");
+
+        for i in 0u8..5 {
+            assert_eq!(
+                db.get([TEST_LEAF].as_ref(), &[i], None)
+                    .unwrap()
+                    .expect("successful get"),
+                Element::new_item(vec![i, 0xff])
+            );
+        }
+        let migrated_root_hash = db.root_hash(None).unwrap().unwrap();
+        assert_ne!(migrated_root_hash, root_hash);
+        assert_eq!(
+            migrated_root_hash,
+            expected_db.root_hash(None).unwrap().unwrap()
+        );
+
+        // Returning no element deletes it
+        db.migrate_subtree([TEST_LEAF].as_ref(), None, |_| Ok(None))
+            .unwrap()
+            .expect("successful migration");
+        assert!(db.get([TEST_LEAF].as_ref(), &[0], None).unwrap().is_err());
+        assert!(db
+            .get([TEST_LEAF].as_ref(), b"subtree", None)
+            .unwrap()
+            .is_ok());
+    }
+}
@@ -0,0 +1,166 @@
+// MIT LICENSE
+//
+// Copyright (c) 2021 Dash Core Group
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Moving an element between subtrees
+
+#[cfg(feature = "full")]
+use grovedb_costs::{cost_return_on_error, CostResult, CostsExt, OperationCost};
+#[cfg(feature = "full")]
+use grovedb_path::SubtreePath;
+
+#[cfg(feature = "full")]
+use crate::{batch::GroveDbOp, Error, GroveDb, TransactionArg};
+
+#[cfg(feature = "full")]
+impl GroveDb {
+    /// Moves the element at `from_key` of the subtree at `from_path` to
+    /// `to_key` of the subtree at `to_path`. The raw element is inserted at
+    /// the destination and deleted from the source as a single batch, so both
+    /// branches are updated and their hashes propagated together.
+    ///
+    /// References are moved as they are, relative ones will resolve from
+    /// their new location. Trees can't be moved as their subtree data is
+    /// stored under a prefix derived from their path.
+    pub fn move_element<'b, B, P>(
+        &self,
+        from_path: P,
+        from_key: &[u8],
+        to_path: P,
+        to_key: &[u8],
+        transaction: TransactionArg,
+    ) -> CostResult<(), Error>
+    where
+        B: AsRef<[u8]> + 'b,
+        P: Into<SubtreePath<'b, B>>,
+    {
+        let mut cost = OperationCost::default();
+        let from_path: SubtreePath<B> = from_path.into();
+        let to_path: SubtreePath<B> = to_path.into();
+
+        let element = cost_return_on_error!(
+            &mut cost,
+            self.get_raw(from_path.clone(), from_key, transaction)
+        );
+        if element.is_tree() {
+            return Err(Error::NotSupported("trees can not be moved")).wrap_with_cost(cost);
+        }
+
+        let from_path = from_path.to_vec();
+        let to_path = to_path.to_vec();
+        if from_path == to_path && from_key == to_key {
+            return Ok(()).wrap_with_cost(cost);
+        }
+
+        let ops = vec![
+            GroveDbOp::insert_op(to_path, to_key.to_vec(), element),
+            GroveDbOp::delete_op(from_path, from_key.to_vec()),
+        ];
+        self.apply_batch(ops, None, transaction).add_cost(cost)
+    }
+}
+
+#[cfg(feature = "full")]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        tests::{make_test_grovedb, ANOTHER_TEST_LEAF, TEST_LEAF},
+        Element,
+    };
+
+    #[test]
+    fn test_move_element() {
+        let db = make_test_grovedb();
+        db.insert(
+            [TEST_LEAF].as_ref(),
+            b"key",
+            Element::new_item(b"value".to_vec()),
+            None,
+            None,
+        )
+        .unwrap()
+        .expect("successful insert");
+
+        let subtree_root_hash = |path: &[u8]| {
+            db.open_non_transactional_merk_at_path([path].as_ref().into(), None)
+                .unwrap()
+                .expect("cannot open merk")
+                .root_hash()
+                .unwrap()
+        };
+        let source_hash = subtree_root_hash(TEST_LEAF);
+        let destination_hash = subtree_root_hash(ANOTHER_TEST_LEAF);
+        let root_hash = db.root_hash(None).unwrap().unwrap();
+
+        db.move_element(
+            [TEST_LEAF].as_ref(),
+            b"key",
+            [ANOTHER_TEST_LEAF].as_ref(),
+            b"moved",
+            None,
+        )
+        .unwrap()
+        .expect("successful move");
+
+        assert!(matches!(
+            db.get([TEST_LEAF].as_ref(), b"key", None).unwrap(),
+            Err(Error::PathKeyNotFound(_))
+        ));
+        assert_eq!(
+            db.get([ANOTHER_TEST_LEAF].as_ref(), b"moved", None)
+                .unwrap()
+                .expect("successful get"),
+            Element::new_item(b"value".to_vec())
+        );
+        assert_ne!(subtree_root_hash(TEST_LEAF), source_hash);
+        assert_ne!(subtree_root_hash(ANOTHER_TEST_LEAF), destination_hash);
+        assert_ne!(db.root_hash(None).unwrap().unwrap(), root_hash);
+
+        // Subtrees stay where they are
+        db.insert(
+            [TEST_LEAF].as_ref(),
+            b"subtree",
+            Element::empty_tree(),
+            None,
+            None,
+        )
+        .unwrap()
+        .expect("successful insert");
+        assert!(matches!(
+            db.move_element(
+                [TEST_LEAF].as_ref(),
+                b"subtree",
+                [ANOTHER_TEST_LEAF].as_ref(),
+                b"subtree",
+                None,
+            )
+            .unwrap(),
+            Err(Error::NotSupported(_))
+        ));
+    }
+}
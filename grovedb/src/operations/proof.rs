@@ -28,6 +28,8 @@
 
 //! Proof operations
 
+#[cfg(any(feature = "full", feature = "verify"))]
+mod chunked;
 #[cfg(feature = "full")]
 mod generate;
 #[cfg(any(feature = "full", feature = "verify"))]
@@ -0,0 +1,293 @@
+// MIT LICENSE
+//
+// Copyright (c) 2021 Dash Core Group
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Proofs of a path query split into chunks over the keys of its top subtree
+
+#[cfg(feature = "full")]
+use std::ops::Range;
+
+use bincode::Options;
+#[cfg(feature = "full")]
+use grovedb_costs::{
+    cost_return_on_error, cost_return_on_error_no_add, CostResult, CostsExt, OperationCost,
+};
+use grovedb_merk::proofs::query::QueryItem;
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "full")]
+use crate::query_result_type::QueryResultType;
+use crate::{query_result_type::PathKeyOptionalElementTrio, Error, GroveDb, PathQuery};
+
+/// Proof of the part of a path query under the keys of its top subtree which
+/// are within `[lower_bound, upper_bound)`, missing bounds being unbounded
+#[derive(Serialize, Deserialize)]
+struct ProofChunk {
+    lower_bound: Option<Vec<u8>>,
+    upper_bound: Option<Vec<u8>>,
+    proof: Vec<u8>,
+}
+
+fn chunk_options() -> impl Options {
+    bincode::DefaultOptions::default()
+        .with_varint_encoding()
+        .reject_trailing_bytes()
+}
+
+/// Limits and offsets apply to the whole result set, so they can't be split
+/// between chunks
+fn check_chunkable(path_query: &PathQuery) -> Result<(), Error> {
+    let sized_query = &path_query.query;
+    if sized_query.limit.is_some()
+        || sized_query.offset.is_some()
+        || sized_query.max_result_bytes.is_some()
+    {
+        return Err(Error::NotSupported(
+            "limits and offsets are not supported for chunked proofs",
+        ));
+    }
+    if sized_query.keys_only {
+        return Err(Error::NotSupported(
+            "keys only queries are not supported for chunked proofs",
+        ));
+    }
+    Ok(())
+}
+
+/// Restrict the path query to the keys of its top subtree within the bounds
+fn chunk_path_query(
+    path_query: &PathQuery,
+    lower_bound: Option<&[u8]>,
+    upper_bound: Option<&[u8]>,
+) -> PathQuery {
+    let bounds = match (lower_bound, upper_bound) {
+        (None, None) => return path_query.clone(),
+        (Some(lower), None) => QueryItem::RangeFrom(lower.to_vec()..),
+        (None, Some(upper)) => QueryItem::RangeTo(..upper.to_vec()),
+        (Some(lower), Some(upper)) => QueryItem::Range(lower.to_vec()..upper.to_vec()),
+    };
+    let mut chunk_path_query = path_query.clone();
+    chunk_path_query.query.query.items = path_query
+        .query
+        .query
+        .items
+        .iter()
+        .filter_map(|item| item.intersect(&bounds).in_both)
+        .collect();
+    chunk_path_query
+}
+
+#[cfg(feature = "full")]
+impl GroveDb {
+    /// Generate a proof for a path query split into chunks of at most
+    /// `max_chunk_bytes`, to be verified together with
+    /// [`GroveDb::verify_query_chunked`]. Every chunk is a proof of the query
+    /// restricted to a range of keys of the top subtree, so a chunk can only
+    /// be split down to the proof under a single key, which may exceed the
+    /// maximum size.
+    ///
+    /// Limits and offsets are not supported.
+    pub fn prove_query_chunked(
+        &self,
+        path_query: &PathQuery,
+        max_chunk_bytes: usize,
+    ) -> CostResult<Vec<Vec<u8>>, Error> {
+        let mut cost = OperationCost::default();
+        cost_return_on_error_no_add!(&cost, check_chunkable(path_query));
+
+        let keys = cost_return_on_error!(&mut cost, self.top_subtree_keys(path_query));
+        let mut chunks = Vec::new();
+        cost_return_on_error!(
+            &mut cost,
+            self.prove_chunks(
+                path_query,
+                &keys,
+                0..keys.len(),
+                max_chunk_bytes,
+                &mut chunks
+            )
+        );
+        if !path_query.query.query.left_to_right {
+            chunks.reverse();
+        }
+
+        Ok(chunks).wrap_with_cost(cost)
+    }
+
+    /// Get the keys of the top subtree matched by the path query, in ascending
+    /// order
+    fn top_subtree_keys(&self, path_query: &PathQuery) -> CostResult<Vec<Vec<u8>>, Error> {
+        let mut top_path_query = path_query.clone();
+        let query = &mut top_path_query.query.query;
+        query.default_subquery_branch = Default::default();
+        query.conditional_subquery_branches = None;
+        query.left_to_right = true;
+
+        self.query_raw(
+            &top_path_query,
+            true,
+            QueryResultType::QueryKeyElementPairResultType,
+            None,
+        )
+        .map(|result| match result {
+            Ok((elements, _)) => Ok(elements.to_keys()),
+            // A missing subtree is proven with a single chunk
+            Err(
+                Error::PathNotFound(_)
+                | Error::PathKeyNotFound(_)
+                | Error::PathParentLayerNotFound(_)
+                | Error::InvalidParentLayerPath(_),
+            ) => Ok(Vec::new()),
+            Err(e) => Err(e),
+        })
+    }
+
+    /// Prove the query restricted to the given range of keys as one chunk,
+    /// splitting it in halves until the chunks fit in `max_chunk_bytes`
+    fn prove_chunks(
+        &self,
+        path_query: &PathQuery,
+        keys: &[Vec<u8>],
+        range: Range<usize>,
+        max_chunk_bytes: usize,
+        chunks: &mut Vec<Vec<u8>>,
+    ) -> CostResult<(), Error> {
+        let mut cost = OperationCost::default();
+
+        let lower_bound = (range.start > 0).then(|| &keys[range.start]);
+        let upper_bound = keys.get(range.end);
+        let proof = cost_return_on_error!(
+            &mut cost,
+            self.prove_internal(
+                &chunk_path_query(
+                    path_query,
+                    lower_bound.map(Vec::as_slice),
+                    upper_bound.map(Vec::as_slice),
+                ),
+                false
+            )
+        );
+        let chunk = cost_return_on_error_no_add!(
+            &cost,
+            chunk_options()
+                .serialize(&ProofChunk {
+                    lower_bound: lower_bound.cloned(),
+                    upper_bound: upper_bound.cloned(),
+                    proof,
+                })
+                .map_err(|e| Error::CorruptedData(format!("unable to serialize proof chunk: {e}")))
+        );
+
+        if chunk.len() > max_chunk_bytes && range.len() > 1 {
+            let middle = range.start + range.len() / 2;
+            cost_return_on_error!(
+                &mut cost,
+                self.prove_chunks(
+                    path_query,
+                    keys,
+                    range.start..middle,
+                    max_chunk_bytes,
+                    chunks
+                )
+            );
+            cost_return_on_error!(
+                &mut cost,
+                self.prove_chunks(path_query, keys, middle..range.end, max_chunk_bytes, chunks)
+            );
+        } else {
+            chunks.push(chunk);
+        }
+
+        Ok(()).wrap_with_cost(cost)
+    }
+}
+
+impl GroveDb {
+    /// Verify the chunks of a proof generated with
+    /// [`GroveDb::prove_query_chunked`], in the order they were generated.
+    /// Returns the root hash and the deserialized elements of all chunks.
+    pub fn verify_query_chunked(
+        chunks: &[Vec<u8>],
+        path_query: &PathQuery,
+    ) -> Result<([u8; 32], Vec<PathKeyOptionalElementTrio>), Error> {
+        check_chunkable(path_query)?;
+        let left_to_right = path_query.query.query.left_to_right;
+
+        let mut root_hash = None;
+        let mut results = Vec::new();
+        // Bound the next chunk must start at in query order, `None` once a
+        // chunk reached the end of the key space
+        let mut next_leading_bound: Option<Option<Vec<u8>>> = Some(None);
+        for chunk in chunks {
+            let chunk: ProofChunk = chunk_options()
+                .deserialize(chunk)
+                .map_err(|_| Error::CorruptedData("invalid proof chunk".to_owned()))?;
+            let (leading_bound, trailing_bound) = if left_to_right {
+                (&chunk.lower_bound, &chunk.upper_bound)
+            } else {
+                (&chunk.upper_bound, &chunk.lower_bound)
+            };
+            if next_leading_bound.as_ref() != Some(leading_bound) {
+                return Err(Error::InvalidProof(
+                    "proof chunks don't cover the query in order",
+                ));
+            }
+            if let (Some(lower), Some(upper)) = (&chunk.lower_bound, &chunk.upper_bound) {
+                if lower >= upper {
+                    return Err(Error::InvalidProof("proof chunk has an empty key range"));
+                }
+            }
+            next_leading_bound = trailing_bound.clone().map(Some);
+
+            let chunk_path_query = chunk_path_query(
+                path_query,
+                chunk.lower_bound.as_deref(),
+                chunk.upper_bound.as_deref(),
+            );
+            if chunk_path_query.query.query.items.is_empty() {
+                return Err(Error::InvalidProof(
+                    "proof chunk key range is outside of the query",
+                ));
+            }
+            let (chunk_root_hash, chunk_results) =
+                Self::verify_query(&chunk.proof, &chunk_path_query)?;
+            if *root_hash.get_or_insert(chunk_root_hash) != chunk_root_hash {
+                return Err(Error::InvalidProof(
+                    "proof chunks have different root hashes",
+                ));
+            }
+            results.extend(chunk_results);
+        }
+        if next_leading_bound.is_some() {
+            return Err(Error::InvalidProof(
+                "proof chunks don't cover the query in order",
+            ));
+        }
+
+        Ok((root_hash.unwrap_or_default(), results))
+    }
+}
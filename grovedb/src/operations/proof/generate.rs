@@ -35,13 +35,14 @@
 use grovedb_costs::cost_return_on_error_default;
 #[cfg(feature = "full")]
 use grovedb_costs::{
-    cost_return_on_error, cost_return_on_error_no_add, CostResult, CostsExt, OperationCost,
+    cost_return_on_error, cost_return_on_error_no_add, CostContext, CostResult, CostsExt,
+    OperationCost,
 };
 #[cfg(feature = "full")]
 use grovedb_merk::{
     proofs::{encode_into, Node, Op},
     tree::value_hash,
-    KVIterator, Merk, ProofWithoutEncodingResult,
+    Error as MerkError, KVIterator, Merk, ProofWithoutEncodingResult,
 };
 use grovedb_path::SubtreePath;
 #[cfg(feature = "full")]
@@ -55,7 +56,7 @@ use crate::{
         reduce_limit_and_offset_by, write_to_vec, ProofTokenType, EMPTY_TREE_HASH,
     },
     reference_path::path_from_reference_path_type,
-    Element, Error, GroveDb, PathQuery, Query,
+    Element, Error, GroveDb, PathQuery, Query, QueryPlanStep, TransactionArg,
 };
 use crate::{
     operations::proof::util::{write_slice_of_slice_to_slice, write_slice_to_vec},
@@ -95,28 +96,99 @@ impl GroveDb {
     /// doesn't allow for subset verification
     /// Proofs generated with this can only be verified by the path query used
     /// to generate them.
+    ///
+    /// Queries with a limit of 0 and unlimited queries spanning the whole
+    /// database are rejected with [`Error::InvalidInput`].
+    ///
+    /// A result bytes budget is turned into a limit with
+    /// [`GroveDb::limit_to_result_bytes`] first, so the proof covers the
+    /// results a query returns, and has to be verified with the limited query.
     pub fn prove_query(&self, query: &PathQuery) -> CostResult<Vec<u8>, Error> {
+        if query.query.max_result_bytes.is_some() {
+            let mut cost = OperationCost::default();
+            let query = cost_return_on_error!(&mut cost, self.limit_to_result_bytes(query, None));
+            return self.prove_query(&query).add_cost(cost);
+        }
+        cost_return_on_error_default!(query.check_provable());
         self.prove_internal(query, false)
     }
 
+    /// Generate a minimalistic proof for a single key in the subtree at the
+    /// given path. The proof shows either the element stored under the key
+    /// or the absence of the key, and is verified with
+    /// [`GroveDb::verify_key`].
+    pub fn prove_key<'b, B, P>(&self, path: P, key: &[u8]) -> CostResult<Vec<u8>, Error>
+    where
+        B: AsRef<[u8]> + 'b,
+        P: Into<SubtreePath<'b, B>>,
+    {
+        let path_query = PathQuery::new_single_key(path.into().to_vec(), key.to_vec());
+        self.prove_query(&path_query)
+    }
+
+    /// Get an element together with the proof of [`GroveDb::prove_key`]
+    /// binding it to the root hash, for clients interested in a single key.
+    /// References are followed as with [`GroveDb::get`], the proof carries
+    /// the referenced element so [`GroveDb::verify_key`] yields the same one.
+    /// Proofs can't be generated in a transaction yet.
+    pub fn get_with_proof<'b, B, P>(
+        &self,
+        path: P,
+        key: &[u8],
+        transaction: TransactionArg,
+    ) -> CostResult<(Element, Vec<u8>), Error>
+    where
+        B: AsRef<[u8]> + 'b,
+        P: Into<SubtreePath<'b, B>>,
+    {
+        if transaction.is_some() {
+            return Err(Error::NotSupported(
+                "transactions are not currently supported",
+            ))
+            .wrap_with_cost(Default::default());
+        }
+        let mut cost = OperationCost::default();
+        let path: SubtreePath<B> = path.into();
+
+        let element = cost_return_on_error!(&mut cost, self.get(path.clone(), key, None));
+        let proof = cost_return_on_error!(&mut cost, self.prove_key(path, key));
+
+        Ok((element, proof)).wrap_with_cost(cost)
+    }
+
     /// Generate a verbose proof for a given path query
     /// Any path query that is a subset of the original proof generating path
-    /// query can be used to verify this (subset verification)
+    /// query can be used to verify this (subset verification). A result bytes
+    /// budget is turned into a limit as for [`GroveDb::prove_query`].
     pub fn prove_verbose(&self, query: &PathQuery) -> CostResult<Vec<u8>, Error> {
+        if query.query.max_result_bytes.is_some() {
+            let mut cost = OperationCost::default();
+            let query = cost_return_on_error!(&mut cost, self.limit_to_result_bytes(query, None));
+            return self.prove_verbose(&query).add_cost(cost);
+        }
         // TODO: we need to solve the localized limit and offset problem.
         //      when using a path query that has a limit and offset value,
         //      to get the expected behaviour, you need to know exactly
         //      how the proving internals work and how your state looks.
+        cost_return_on_error_default!(query.check_provable());
         self.prove_internal(query, true)
     }
 
     /// Generates a verbose or non verbose proof based on a bool
-    fn prove_internal(&self, query: &PathQuery, is_verbose: bool) -> CostResult<Vec<u8>, Error> {
+    pub(super) fn prove_internal(
+        &self,
+        query: &PathQuery,
+        is_verbose: bool,
+    ) -> CostResult<Vec<u8>, Error> {
         let mut cost = OperationCost::default();
 
+        cost_return_on_error_no_add!(&cost, query.query.check_keys_only());
+        cost_return_on_error_no_add!(&cost, self.check_subquery_depth(query));
+
         let mut proof_result =
             cost_return_on_error_default!(prepend_version_to_bytes(vec![], PROOF_VERSION));
 
+        let mut proof_nodes: usize = 0;
         let mut limit: Option<u16> = query.query.limit;
         let mut offset: Option<u16> = query.query.offset;
 
@@ -139,7 +211,8 @@ impl GroveDb {
                     self.generate_and_store_absent_path_proof(
                         &path_slices,
                         &mut proof_result,
-                        is_verbose
+                        is_verbose,
+                        &mut proof_nodes
                     )
                 );
                 // return the absence proof no need to continue proof generation
@@ -165,12 +238,13 @@ impl GroveDb {
                 &mut limit,
                 &mut offset,
                 true,
-                is_verbose
+                is_verbose,
+                &mut proof_nodes
             )
         );
         cost_return_on_error!(
             &mut cost,
-            self.prove_path(&mut proof_result, path_slices, is_verbose)
+            self.prove_path(&mut proof_result, path_slices, is_verbose, &mut proof_nodes)
         );
 
         Ok(proof_result).wrap_with_cost(cost)
@@ -187,6 +261,7 @@ impl GroveDb {
         current_offset: &mut Option<u16>,
         is_first_call: bool,
         is_verbose: bool,
+        proof_nodes: &mut usize,
     ) -> CostResult<(), Error> {
         let mut cost = OperationCost::default();
         let mut to_add_to_result_set: u16 = 0;
@@ -216,7 +291,9 @@ impl GroveDb {
                         ProofTokenType::SizedMerk,
                         proofs,
                         is_verbose,
-                        path.iter().last().unwrap_or(&(&[][..]))
+                        query.query.keys_only,
+                        path.iter().last().unwrap_or(&(&[][..])),
+                        proof_nodes
                     )
                 );
             }
@@ -234,15 +311,31 @@ impl GroveDb {
             let element = cost_return_on_error_no_add!(&cost, raw_decode(&value_bytes));
             match element {
                 Element::Tree(root_key, _) | Element::SumTree(root_key, ..) => {
-                    let (mut subquery_path, subquery_value) =
-                        Element::subquery_paths_and_value_for_sized_query(&query.query, &key);
-
-                    if subquery_value.is_none() && subquery_path.is_none() {
-                        // this element should be added to the result set
-                        // hence we have to update the limit and offset value
-                        reduce_limit_and_offset_by(current_limit, current_offset, 1);
-                        continue;
-                    }
+                    let step = cost_return_on_error_no_add!(
+                        &cost,
+                        QueryPlanStep::for_key(&query.query, &key)
+                    );
+                    let (subquery_path, subquery, branch_limit) = match step {
+                        QueryPlanStep::Element => {
+                            // this element should be added to the result set
+                            // hence we have to update the limit and offset value
+                            reduce_limit_and_offset_by(current_limit, current_offset, 1);
+                            continue;
+                        }
+                        QueryPlanStep::Subquery {
+                            path,
+                            query: subquery,
+                        } => {
+                            let branch_limit =
+                                Element::subquery_branch_for_sized_query(&query.query, &key).limit;
+                            (path, subquery, branch_limit)
+                        }
+                        QueryPlanStep::Key { path, key } => {
+                            let mut key_as_query = Query::new();
+                            key_as_query.insert_key(key);
+                            (path, key_as_query, None)
+                        }
+                    };
 
                     if root_key.is_none() {
                         continue;
@@ -261,7 +354,9 @@ impl GroveDb {
                                 ProofTokenType::Merk,
                                 proofs,
                                 is_verbose,
-                                path.iter().last().unwrap_or(&Default::default())
+                                false,
+                                path.iter().last().unwrap_or(&Default::default()),
+                                proof_nodes
                             )
                         );
                     }
@@ -269,122 +364,55 @@ impl GroveDb {
                     let mut new_path = path.clone();
                     new_path.push(key.as_ref());
 
-                    let mut query = subquery_value;
-
-                    if query.is_some() {
-                        if let Some(subquery_path) = &subquery_path {
-                            for subkey in subquery_path.iter() {
-                                let inner_subtree = cost_return_on_error!(
-                                    &mut cost,
-                                    self.open_non_transactional_merk_at_path(
-                                        new_path.as_slice().into(),
-                                        None,
-                                    )
-                                );
-
-                                let mut key_as_query = Query::new();
-                                key_as_query.insert_key(subkey.clone());
-
-                                cost_return_on_error!(
-                                    &mut cost,
-                                    self.generate_and_store_merk_proof(
-                                        &new_path.as_slice().into(),
-                                        &inner_subtree,
-                                        &key_as_query,
-                                        (None, None),
-                                        ProofTokenType::Merk,
-                                        proofs,
-                                        is_verbose,
-                                        new_path.iter().last().unwrap_or(&Default::default())
-                                    )
-                                );
-
-                                new_path.push(subkey);
-
-                                if self
-                                    .check_subtree_exists_path_not_found(
-                                        new_path.as_slice().into(),
-                                        None,
-                                    )
-                                    .unwrap_add_cost(&mut cost)
-                                    .is_err()
-                                {
-                                    encountered_absence = true;
-                                    break;
-                                }
-                            }
-
-                            if encountered_absence {
-                                continue;
-                            }
-                        }
-                    } else if let Some(subquery_path) = &mut subquery_path {
-                        if subquery_path.is_empty() {
-                            // nothing to do on this path, since subquery path is empty
-                            // and there is no consecutive subquery value
-                            continue;
-                        }
-
-                        let last_key = subquery_path.remove(subquery_path.len() - 1);
-
-                        for subkey in subquery_path.iter() {
-                            let inner_subtree = cost_return_on_error!(
-                                &mut cost,
-                                self.open_non_transactional_merk_at_path(
-                                    new_path.as_slice().into(),
-                                    None
-                                )
-                            );
-
-                            let mut key_as_query = Query::new();
-                            key_as_query.insert_key(subkey.clone());
-
-                            cost_return_on_error!(
-                                &mut cost,
-                                self.generate_and_store_merk_proof(
-                                    &new_path.as_slice().into(),
-                                    &inner_subtree,
-                                    &key_as_query,
-                                    (None, None),
-                                    ProofTokenType::Merk,
-                                    proofs,
-                                    is_verbose,
-                                    new_path.iter().last().unwrap_or(&Default::default())
-                                )
-                            );
+                    for subkey in subquery_path.iter() {
+                        let inner_subtree = cost_return_on_error!(
+                            &mut cost,
+                            self.open_non_transactional_merk_at_path(
+                                new_path.as_slice().into(),
+                                None
+                            )
+                        );
 
-                            new_path.push(subkey);
+                        let mut key_as_query = Query::new();
+                        key_as_query.insert_key(subkey.clone());
 
-                            // check if the new path points to a valid subtree
-                            // if it does not, we should stop proof generation on this path
-                            if self
-                                .check_subtree_exists_path_not_found(
-                                    new_path.as_slice().into(),
-                                    None,
-                                )
-                                .unwrap_add_cost(&mut cost)
-                                .is_err()
-                            {
-                                encountered_absence = true;
-                                break;
-                            }
-                        }
+                        cost_return_on_error!(
+                            &mut cost,
+                            self.generate_and_store_merk_proof(
+                                &new_path.as_slice().into(),
+                                &inner_subtree,
+                                &key_as_query,
+                                (None, None),
+                                ProofTokenType::Merk,
+                                proofs,
+                                is_verbose,
+                                false,
+                                new_path.iter().last().unwrap_or(&Default::default()),
+                                proof_nodes
+                            )
+                        );
 
-                        if encountered_absence {
-                            continue;
+                        new_path.push(subkey);
+
+                        // check if the new path points to a valid subtree
+                        // if it does not, we should stop proof generation on this path
+                        if self
+                            .check_subtree_exists_path_not_found(new_path.as_slice().into(), None)
+                            .unwrap_add_cost(&mut cost)
+                            .is_err()
+                        {
+                            encountered_absence = true;
+                            break;
                         }
+                    }
 
-                        let mut key_as_query = Query::new();
-                        key_as_query.insert_key(last_key);
-                        query = Some(key_as_query);
-                    } else {
-                        return Err(Error::CorruptedCodeExecution("subquery_path must exist"))
-                            .wrap_with_cost(cost);
+                    if encountered_absence {
+                        continue;
                     }
 
                     let new_path_owned = new_path.iter().map(|a| a.to_vec()).collect();
 
-                    let new_path_query = PathQuery::new_unsized(new_path_owned, query.unwrap());
+                    let new_path_query = PathQuery::new_unsized(new_path_owned, subquery);
 
                     if self
                         .check_subtree_exists_path_not_found(new_path.as_slice().into(), None)
@@ -394,19 +422,36 @@ impl GroveDb {
                         continue;
                     }
 
+                    // A branch limit caps what the subquery of this element may
+                    // prove, the same way as when querying
+                    let mut branch_limit = branch_limit.map(|branch_limit| {
+                        current_limit.map_or(branch_limit, |l| l.min(branch_limit))
+                    });
+                    let subquery_limit = if branch_limit.is_some() {
+                        &mut branch_limit
+                    } else {
+                        &mut *current_limit
+                    };
+                    let limit_before_subquery = *subquery_limit;
+
                     cost_return_on_error!(
                         &mut cost,
                         self.prove_subqueries(
                             proofs,
                             new_path,
                             &new_path_query,
-                            current_limit,
+                            subquery_limit,
                             current_offset,
                             false,
                             is_verbose,
+                            proof_nodes
                         )
                     );
 
+                    if let (Some(before), Some(after)) = (limit_before_subquery, branch_limit) {
+                        *current_limit = current_limit.map(|l| l - (before - after));
+                    }
+
                     if *current_limit == Some(0) {
                         break;
                     }
@@ -430,7 +475,9 @@ impl GroveDb {
                     ProofTokenType::SizedMerk,
                     proofs,
                     is_verbose,
-                    path.iter().last().unwrap_or(&Default::default())
+                    query.query.keys_only,
+                    path.iter().last().unwrap_or(&Default::default()),
+                    proof_nodes
                 )
             );
 
@@ -451,6 +498,7 @@ impl GroveDb {
         proof_result: &mut Vec<u8>,
         path_slices: Vec<&[u8]>,
         is_verbose: bool,
+        proof_nodes: &mut usize,
     ) -> CostResult<(), Error> {
         let mut cost = OperationCost::default();
 
@@ -474,7 +522,9 @@ impl GroveDb {
                     ProofTokenType::Merk,
                     proof_result,
                     is_verbose,
-                    path_slice.iter().last().unwrap_or(&Default::default())
+                    false,
+                    path_slice.iter().last().unwrap_or(&Default::default()),
+                    proof_nodes
                 )
             );
             split_path = path_slice.split_last();
@@ -493,7 +543,9 @@ impl GroveDb {
         proof_token_type: ProofTokenType,
         proofs: &mut Vec<u8>,
         is_verbose: bool,
+        keys_only: bool,
         key: &[u8],
+        proof_nodes: &mut usize,
     ) -> CostResult<(Option<u16>, Option<u16>), Error>
     where
         S: StorageContext<'a> + 'a,
@@ -509,12 +561,40 @@ impl GroveDb {
 
         let mut cost = OperationCost::default();
 
-        let mut proof_result = subtree
-            .prove_without_encoding(query.clone(), limit_offset.0, limit_offset.1)
-            .unwrap()
-            .expect("should generate proof");
+        // Merk stops as soon as the nodes left in the budget are used up
+        let node_budget = self
+            .max_proof_nodes
+            .map(|max_nodes| max_nodes.saturating_sub(*proof_nodes));
+        let mut proof_result = match subtree
+            .prove_without_encoding_within_node_budget(
+                query.clone(),
+                limit_offset.0,
+                limit_offset.1,
+                node_budget,
+            )
+            .unwrap_add_cost(&mut cost)
+        {
+            Ok(proof_result) => proof_result,
+            Err(MerkError::ProofNodeLimitExceeded) => {
+                return Err(Error::ProofTooLarge {
+                    max_nodes: self.max_proof_nodes.unwrap_or_default(),
+                })
+                .wrap_with_cost(cost);
+            }
+            Err(e) => return Err(Error::MerkError(e)).wrap_with_cost(cost),
+        };
+
+        *proof_nodes += proof_result
+            .proof
+            .iter()
+            .filter(|op| matches!(op, Op::Push(_) | Op::PushInverted(_)))
+            .count();
 
-        cost_return_on_error!(&mut cost, self.post_process_proof(path, &mut proof_result));
+        if keys_only {
+            Self::remove_values_from_proof(&mut proof_result).unwrap_add_cost(&mut cost);
+        } else {
+            cost_return_on_error!(&mut cost, self.post_process_proof(path, &mut proof_result));
+        }
 
         let mut proof_bytes = Vec::with_capacity(128);
         encode_into(proof_result.proof.iter(), &mut proof_bytes);
@@ -554,6 +634,7 @@ impl GroveDb {
         path_slices: &[&[u8]],
         proof_result: &mut Vec<u8>,
         is_verbose: bool,
+        proof_nodes: &mut usize,
     ) -> CostResult<(), Error> {
         let mut cost = OperationCost::default();
 
@@ -592,7 +673,9 @@ impl GroveDb {
                     ProofTokenType::Merk,
                     proof_result,
                     is_verbose,
-                    current_path.iter().last().unwrap_or(&(&[][..]))
+                    false,
+                    current_path.iter().last().unwrap_or(&(&[][..])),
+                    proof_nodes
                 )
             );
 
@@ -609,6 +692,29 @@ impl GroveDb {
         Ok(()).wrap_with_cost(cost)
     }
 
+    /// Converts Node::KV and Node::KVValueHash to Node::KVDigest, leaving
+    /// only the key and the value hash needed to bind it to the root hash
+    fn remove_values_from_proof(proof_result: &mut ProofWithoutEncodingResult) -> CostContext<()> {
+        let mut cost = OperationCost::default();
+
+        for op in proof_result.proof.iter_mut() {
+            if let Op::Push(node) | Op::PushInverted(node) = op {
+                match node {
+                    Node::KV(key, value) => {
+                        let hash = value_hash(value).unwrap_add_cost(&mut cost);
+                        *node = Node::KVDigest(std::mem::take(key), hash);
+                    }
+                    Node::KVValueHash(key, _, hash)
+                    | Node::KVValueHashFeatureType(key, _, hash, _) => {
+                        *node = Node::KVDigest(std::mem::take(key), *hash);
+                    }
+                    _ => {}
+                }
+            }
+        }
+        ().wrap_with_cost(cost)
+    }
+
     /// Converts Items to Node::KV from Node::KVValueHash
     /// Converts References to Node::KVRefValueHash and sets the value to the
     /// referenced element
@@ -625,7 +731,7 @@ impl GroveDb {
                     Node::KV(key, value) | Node::KVValueHash(key, value, ..) => {
                         let elem = Element::deserialize(value);
                         match elem {
-                            Ok(Element::Reference(reference_path, ..)) => {
+                            Ok(Element::Reference(reference_path, max_reference_hop, _)) => {
                                 let absolute_path = cost_return_on_error!(
                                     &mut cost,
                                     path_from_reference_path_type(
@@ -638,8 +744,9 @@ impl GroveDb {
 
                                 let referenced_elem = cost_return_on_error!(
                                     &mut cost,
-                                    self.follow_reference(
+                                    self.follow_reference_with_max_hops(
                                         absolute_path.as_slice().into(),
+                                        max_reference_hop,
                                         true,
                                         None
                                     )
@@ -727,7 +834,9 @@ mod tests {
             ProofTokenType::Merk,
             &mut proof,
             true,
+            false,
             b"innertree",
+            &mut 0,
         )
         .unwrap()
         .unwrap();
@@ -761,7 +870,9 @@ mod tests {
             ProofTokenType::Merk,
             &mut proof,
             true,
+            false,
             &[],
+            &mut 0,
         )
         .unwrap()
         .unwrap();
@@ -807,7 +918,9 @@ mod tests {
             ProofTokenType::Merk,
             &mut proofs,
             true,
+            false,
             path.iter().last().unwrap_or(&(&[][..])),
+            &mut 0,
         )
         .unwrap()
         .unwrap();
@@ -827,7 +940,9 @@ mod tests {
             ProofTokenType::Merk,
             &mut proofs,
             true,
+            false,
             path.iter().last().unwrap_or(&(&[][..])),
+            &mut 0,
         )
         .unwrap()
         .unwrap();
@@ -847,7 +962,9 @@ mod tests {
             ProofTokenType::Merk,
             &mut proofs,
             true,
+            false,
             path.iter().last().unwrap_or(&(&[][..])),
+            &mut 0,
         )
         .unwrap()
         .unwrap();
@@ -33,6 +33,7 @@ use std::io::Write;
 
 use grovedb_merk::{
     proofs::query::{Key, Path, ProvedKeyValue},
+    tree::{combine_hash, value_hash},
     CryptoHash,
 };
 #[cfg(any(feature = "full", feature = "verify"))]
@@ -371,6 +372,14 @@ impl ProvedPathKeyValue {
             .map(|pkv| Self::from_proved_key_value(path.clone(), pkv))
             .collect()
     }
+
+    /// Checks that `root_hash` is the root hash of the subtree of this proved
+    /// tree element. The value hash of a tree element commits to the root hash
+    /// of its subtree, so subtree hashes returned by a query are bound by the
+    /// proof of the same query.
+    pub fn proves_subtree_root_hash(&self, root_hash: &CryptoHash) -> bool {
+        combine_hash(value_hash(&self.value).value(), root_hash).value() == &self.proof
+    }
 }
 
 #[cfg(test)]
@@ -39,6 +39,7 @@ use grovedb_merk::{
     tree::{combine_hash, value_hash as value_hash_fn},
     CryptoHash,
 };
+use grovedb_path::SubtreePath;
 
 use crate::{
     operations::proof::util::{
@@ -46,7 +47,7 @@ use crate::{
     },
     query_result_type::PathKeyOptionalElementTrio,
     versioning::read_and_consume_proof_version,
-    SizedQuery,
+    Cursor, SizedQuery,
 };
 #[cfg(any(feature = "full", feature = "verify"))]
 use crate::{
@@ -70,6 +71,11 @@ impl GroveDb {
         proof: &[u8],
         query: &PathQuery,
     ) -> Result<([u8; 32], Vec<PathKeyOptionalElementTrio>), Error> {
+        if query.query.keys_only {
+            return Err(Error::NotSupported(
+                "keys only queries have no elements to deserialize, use verify_query_raw",
+            ));
+        }
         let (root_hash, proved_path_key_values) = Self::verify_query_raw(proof, query)?;
         let path_key_optional_elements = proved_path_key_values
             .into_iter()
@@ -78,6 +84,27 @@ impl GroveDb {
         Ok((root_hash, path_key_optional_elements))
     }
 
+    /// Verify a proof generated with [`GroveDb::prove_key`].
+    /// Returns the root hash and the proven element, `None` means the key is
+    /// proven to be absent.
+    pub fn verify_key<'b, B, P>(
+        proof: &[u8],
+        path: P,
+        key: &[u8],
+    ) -> Result<([u8; 32], Option<Element>), Error>
+    where
+        B: AsRef<[u8]> + 'b,
+        P: Into<SubtreePath<'b, B>>,
+    {
+        let path_query = PathQuery::new_single_key(path.into().to_vec(), key.to_vec());
+        let (root_hash, result_set) = Self::verify_query(proof, &path_query)?;
+        let element = result_set
+            .into_iter()
+            .find(|(_, result_key, _)| result_key == key)
+            .and_then(|(.., element)| element);
+        Ok((root_hash, element))
+    }
+
     /// Verify proof for a given path query returns serialized elements
     pub fn verify_query_raw(
         proof: &[u8],
@@ -89,6 +116,69 @@ impl GroveDb {
         Ok((hash, verifier.result_set))
     }
 
+    /// Verify proof for a given path query, returning the root hash and the
+    /// serialized results as `(path, key, value)` triples, where the path is
+    /// the full path of the subtree holding the result. A merged query can
+    /// span several subtrees holding the same key, the path tells their
+    /// results apart.
+    pub fn verify_query_with_paths(
+        proof: &[u8],
+        query: &PathQuery,
+    ) -> Result<([u8; 32], Vec<(Path, Vec<u8>, Vec<u8>)>), Error> {
+        let (root_hash, result_set) = Self::verify_query_raw(proof, query)?;
+        let results = result_set
+            .into_iter()
+            .map(|proved_path_key_value| {
+                (
+                    proved_path_key_value.path,
+                    proved_path_key_value.key,
+                    proved_path_key_value.value,
+                )
+            })
+            .collect();
+        Ok((root_hash, results))
+    }
+
+    /// Verify the proof of a page of a path query, returning the root hash,
+    /// the serialized results and the cursor to query the next page with, as
+    /// [`GroveDb::query_raw_with_cursor`] does.
+    pub fn verify_query_with_cursor(
+        proof: &[u8],
+        query: &PathQuery,
+    ) -> Result<([u8; 32], ProvedPathKeyValues, Option<Cursor>), Error> {
+        query.check_cursor_pagination()?;
+        let (root_hash, result_set) = Self::verify_query_raw(proof, query)?;
+        let cursor = query.page_cursor(
+            result_set.len(),
+            result_set.last().map(|proved| proved.key.as_slice()),
+        );
+        Ok((root_hash, result_set, cursor))
+    }
+
+    /// Verify proof for a given path query, yielding the proven serialized
+    /// key/value pairs through an iterator.
+    /// The whole proof is checked before this returns, so the root hash can be
+    /// trusted before the first item is pulled. The iterator owns the verified
+    /// results and does not borrow `proof`, which may be dropped right away.
+    pub fn verify_query_streaming(
+        proof: &[u8],
+        query: &PathQuery,
+    ) -> Result<
+        (
+            [u8; 32],
+            impl Iterator<Item = Result<(Vec<u8>, Vec<u8>), Error>>,
+        ),
+        Error,
+    > {
+        let (root_hash, proved_path_key_values) = Self::verify_query_raw(proof, query)?;
+        let results = proved_path_key_values
+            .into_iter()
+            .map(|proved_path_key_value| {
+                Ok((proved_path_key_value.key, proved_path_key_value.value))
+            });
+        Ok((root_hash, results))
+    }
+
     /// Verify proof given multiple path queries.
     /// If we have more than one path query we merge before performing
     /// verification.
@@ -104,6 +194,30 @@ impl GroveDb {
         }
     }
 
+    /// Verify proof given a path query, telling apart a missing subtree from
+    /// an existing one holding nothing in the queried range.
+    /// Returns the root hash and `None` if the proof shows the path does not
+    /// exist, otherwise the deserialized elements. As Merk range proofs carry
+    /// the nodes bounding the range, an empty result set proves that the
+    /// committed subtree has no element matching the query.
+    pub fn verify_query_with_path_presence(
+        proof: &[u8],
+        query: &PathQuery,
+    ) -> Result<([u8; 32], Option<Vec<PathKeyOptionalElementTrio>>), Error> {
+        let mut verifier = ProofVerifier::new(query);
+        let hash = verifier.execute_proof(proof, query, false)?;
+        if verifier.path_is_absent {
+            return Ok((hash, None));
+        }
+
+        let path_key_optional_elements = verifier
+            .result_set
+            .into_iter()
+            .map(|pkv| pkv.try_into())
+            .collect::<Result<Vec<PathKeyOptionalElementTrio>, Error>>()?;
+        Ok((hash, Some(path_key_optional_elements)))
+    }
+
     /// Given a verbose proof, we can verify it with a subset path query.
     /// Returning the root hash and the deserialized result set.
     pub fn verify_subset_query(
@@ -240,6 +354,8 @@ struct ProofVerifier {
     limit: Option<u16>,
     offset: Option<u16>,
     result_set: ProvedPathKeyValues,
+    path_is_absent: bool,
+    keys_only: bool,
 }
 
 #[cfg(any(feature = "full", feature = "verify"))]
@@ -250,6 +366,8 @@ impl ProofVerifier {
             limit: query.query.limit,
             offset: query.query.offset,
             result_set: vec![],
+            path_is_absent: false,
+            keys_only: query.query.keys_only,
         }
     }
 
@@ -260,6 +378,7 @@ impl ProofVerifier {
         query: &PathQuery,
         is_verbose: bool,
     ) -> Result<[u8; 32], Error> {
+        query.query.check_keys_only()?;
         let (proof_version, proof) = read_and_consume_proof_version(proof)?;
         let mut proof_reader = ProofReader::new_with_verbose_status(proof, is_verbose);
 
@@ -313,6 +432,7 @@ impl ProofVerifier {
         let (proof_token_type, proof, _) = proof_reader.read_proof()?;
 
         let root_hash = if proof_token_type == AbsentPath {
+            self.path_is_absent = true;
             self.verify_absent_path(&mut proof_reader, path_slices)?
         } else {
             let path_owned = query.path.iter().map(|a| a.to_vec()).collect();
@@ -400,6 +520,8 @@ impl ProofVerifier {
                                     &query.query,
                                     key.as_slice(),
                                 );
+                            let branch_limit =
+                                Element::subquery_branch_for_sized_query(&query.query, &key).limit;
 
                             if subquery_value.is_none() && subquery_path.is_none() {
                                 // add this element to the result set
@@ -501,6 +623,15 @@ impl ProofVerifier {
                             let (child_proof_token_type, child_proof) = proof_reader
                                 .read_next_proof(new_path.last().unwrap_or(&Default::default()))?;
 
+                            // A branch limit caps what the subquery of this element may
+                            // return, the same way as when querying
+                            let outer_limit = self.limit;
+                            if let Some(branch_limit) = branch_limit {
+                                self.limit =
+                                    Some(outer_limit.map_or(branch_limit, |l| l.min(branch_limit)));
+                            }
+                            let limit_before_subquery = self.limit;
+
                             let child_hash = self.execute_subquery_proof(
                                 child_proof_token_type,
                                 child_proof,
@@ -509,6 +640,14 @@ impl ProofVerifier {
                                 new_path,
                             )?;
 
+                            if branch_limit.is_some() {
+                                if let (Some(before), Some(after)) =
+                                    (limit_before_subquery, self.limit)
+                                {
+                                    self.limit = outer_limit.map(|l| l - (before - after));
+                                }
+                            }
+
                             let combined_child_hash = combine_hash(
                                 value_hash_fn(&current_value_bytes).value(),
                                 &child_hash,
@@ -852,13 +991,15 @@ impl ProofVerifier {
             offset = self.offset;
         }
 
-        let (hash, result) =
+        let execution = if is_sized_proof && self.keys_only {
+            grovedb_merk::execute_keys_only_proof(proof, query, limit, left_to_right)
+        } else {
             grovedb_merk::execute_proof(proof, query, limit, offset, left_to_right)
-                .unwrap()
-                .map_err(|e| {
-                    eprintln!("{e}");
-                    Error::InvalidProof("invalid proof verification parameters")
-                })?;
+        };
+        let (hash, result) = execution.unwrap().map_err(|e| {
+            eprintln!("{e}");
+            Error::InvalidProof("invalid proof verification parameters")
+        })?;
 
         // convert the result set to proved_path_key_values
         let proved_path_key_values =
@@ -28,6 +28,9 @@
 
 //! Queries
 
+#[cfg(feature = "full")]
+mod plan;
+
 use std::cmp::Ordering;
 
 #[cfg(any(feature = "full", feature = "verify"))]
@@ -36,6 +39,9 @@ use grovedb_merk::proofs::query::SubqueryBranch;
 #[cfg(any(feature = "full", feature = "verify"))]
 use grovedb_merk::proofs::Query;
 
+#[cfg(feature = "full")]
+pub use plan::QueryPlanStep;
+
 #[cfg(any(feature = "full", feature = "verify"))]
 use crate::query_result_type::PathKey;
 #[cfg(any(feature = "full", feature = "verify"))]
@@ -59,6 +65,10 @@ pub struct PathQuery {
 #[derive(Debug, Clone)]
 /// Holds a query to apply to a tree and an optional limit/offset value.
 /// Limit and offset values affect the size of the result set.
+///
+/// A query that isn't [left to right](Query::left_to_right) walks keys in
+/// descending order, so results come from the high end, in descending order,
+/// with limit and offset applied from there. Proofs follow the same order.
 pub struct SizedQuery {
     /// Query
     pub query: Query,
@@ -66,6 +76,20 @@ pub struct SizedQuery {
     pub limit: Option<u16>,
     /// Offset
     pub offset: Option<u16>,
+    /// Maximum total serialized size of the result elements, results stop
+    /// before the first element that doesn't fit. Proofs are made for the
+    /// results which fit, as if limited to their number, see
+    /// [`GroveDb::limit_to_result_bytes`](crate::GroveDb::limit_to_result_bytes).
+    pub max_result_bytes: Option<u32>,
+    /// Leave values out of proofs, proving only which keys match, verified
+    /// with [`GroveDb::verify_query_raw`](crate::GroveDb::verify_query_raw).
+    /// Subqueries and offsets are not supported.
+    pub keys_only: bool,
+    /// Return the root hash of the subtree of every tree element in the
+    /// results, see [`QueryResultElements::subtree_root_hashes`].
+    ///
+    /// [`QueryResultElements::subtree_root_hashes`]: crate::query_result_type::QueryResultElements::subtree_root_hashes
+    pub include_subtree_hashes: bool,
 }
 
 #[cfg(any(feature = "full", feature = "verify"))]
@@ -76,6 +100,9 @@ impl SizedQuery {
             query,
             limit,
             offset,
+            max_result_bytes: None,
+            keys_only: false,
+            include_subtree_hashes: false,
         }
     }
 
@@ -85,6 +112,9 @@ impl SizedQuery {
             query: Query::new_single_key(key),
             limit: None,
             offset: None,
+            max_result_bytes: None,
+            keys_only: false,
+            include_subtree_hashes: false,
         }
     }
 
@@ -94,17 +124,172 @@ impl SizedQuery {
             query: Query::new_single_query_item(query_item),
             limit: None,
             offset: None,
+            max_result_bytes: None,
+            keys_only: false,
+            include_subtree_hashes: false,
         }
     }
+
+    /// Resume the query after the page a [`Cursor`] was returned for. Keys up
+    /// to the cursor, in the direction of the query, are taken out of the
+    /// query items, so the next page is found with a seek rather than by
+    /// skipping the results already seen like an offset does.
+    pub fn after_cursor(mut self, cursor: &Cursor) -> Self {
+        let remaining = if self.query.left_to_right {
+            QueryItem::RangeAfter(cursor.key.clone()..)
+        } else {
+            QueryItem::RangeTo(..cursor.key.clone())
+        };
+        self.query.items = self
+            .query
+            .items
+            .iter()
+            .filter_map(|item| item.intersect(&remaining).in_both)
+            .collect();
+        self
+    }
+
+    /// Checks that a keys only query can be proven. Values are only left out
+    /// of the proof of the queried subtree, so it can't have subqueries, and
+    /// without values keys skipped by an offset can't be told apart from
+    /// results.
+    pub(crate) fn check_keys_only(&self) -> Result<(), Error> {
+        if self.keys_only && (self.query.has_subquery() || self.offset.is_some()) {
+            return Err(Error::NotSupported(
+                "subqueries and offsets are not supported for keys only queries",
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(any(feature = "full", feature = "verify"))]
+/// Position of a page of query results, to continue the query from with
+/// [`SizedQuery::after_cursor`]. It holds the last key of the page, so the
+/// same page of the same state always yields the same cursor.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cursor {
+    key: Vec<u8>,
+}
+
+#[cfg(any(feature = "full", feature = "verify"))]
+impl Cursor {
+    /// Restore a cursor from its bytes, as returned by [`Cursor::as_bytes`]
+    pub fn from_bytes(bytes: Vec<u8>) -> Self {
+        Self { key: bytes }
+    }
+
+    /// Bytes of the cursor, to pass it along with a page
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.key
+    }
 }
 
 #[cfg(any(feature = "full", feature = "verify"))]
 impl PathQuery {
+    /// Checks that the query can be paginated with cursors. Pages are made by
+    /// the limit, and results must be keys of the queried subtree, which a
+    /// cursor can point between.
+    pub(crate) fn check_cursor_pagination(&self) -> Result<(), Error> {
+        if self.query.limit.is_none() {
+            return Err(Error::InvalidInput(
+                "a limit must be set to paginate with cursors",
+            ));
+        }
+        if self.query.query.has_subquery()
+            || self.query.offset.is_some()
+            || self.query.max_result_bytes.is_some()
+        {
+            return Err(Error::NotSupported(
+                "subqueries, offsets and result sizes are not supported with cursors",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Cursor of a page of results ending with `last_key`, if it filled the
+    /// limit and so more results may follow
+    pub(crate) fn page_cursor(
+        &self,
+        results_len: usize,
+        last_key: Option<&[u8]>,
+    ) -> Option<Cursor> {
+        let limit = self.query.limit? as usize;
+        if results_len < limit {
+            return None;
+        }
+        last_key.map(|key| Cursor { key: key.to_vec() })
+    }
+
     /// New path query
     pub const fn new(path: Vec<Vec<u8>>, query: SizedQuery) -> Self {
         Self { path, query }
     }
 
+    /// Checks that subqueries are nested at most `max_depth` levels deep.
+    /// Nested queries are walked without recursion, so checking an untrusted
+    /// query can't overflow the stack.
+    #[cfg(feature = "full")]
+    pub(crate) fn check_subquery_depth(&self, max_depth: usize) -> Result<(), Error> {
+        let mut queries = vec![(&self.query.query, 0)];
+        while let Some((query, depth)) = queries.pop() {
+            let branches = std::iter::once(&query.default_subquery_branch).chain(
+                query
+                    .conditional_subquery_branches
+                    .iter()
+                    .flat_map(|branches| branches.values()),
+            );
+            for branch in branches {
+                if branch.subquery_path.is_none() && branch.subquery.is_none() {
+                    continue;
+                }
+                if depth >= max_depth {
+                    return Err(Error::QueryTooDeep { max_depth });
+                }
+                if let Some(subquery) = &branch.subquery {
+                    queries.push((subquery, depth + 1));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Rejects queries which shouldn't be proven when received from untrusted
+    /// clients: a limit of 0, which can't match anything, and a query of an
+    /// unbounded range of root keys with an unbounded subquery and no limit,
+    /// which would prove the whole database. The latter can still be proven in
+    /// chunks with [`GroveDb::prove_query_chunked`].
+    ///
+    /// [`GroveDb::prove_query_chunked`]: crate::GroveDb::prove_query_chunked
+    #[cfg(feature = "full")]
+    pub(crate) fn check_provable(&self) -> Result<(), Error> {
+        if self.query.limit == Some(0) {
+            return Err(Error::InvalidInput(
+                "proofs can't be made with a limit of 0",
+            ));
+        }
+        // A range open on either side can match most keys of a subtree
+        let unbounded = |query: &Query| {
+            query
+                .items
+                .iter()
+                .any(|item| item.lower_unbounded() || item.upper_unbounded())
+        };
+        let query = &self.query.query;
+        let subquery_branch = &query.default_subquery_branch;
+        if self.path.is_empty()
+            && self.query.limit.is_none()
+            && unbounded(query)
+            && subquery_branch.subquery_path.is_none()
+            && subquery_branch.subquery.as_deref().map_or(false, unbounded)
+        {
+            return Err(Error::InvalidInput(
+                "proofs of unbounded root ranges with an unbounded subquery must have a limit",
+            ));
+        }
+        Ok(())
+    }
+
     /// New path query with a single key
     pub fn new_single_key(path: Vec<Vec<u8>>, key: Vec<u8>) -> Self {
         Self {
@@ -185,6 +370,7 @@ impl PathQuery {
             let SubqueryBranch {
                 subquery_path,
                 subquery,
+                limit,
             } = sub_path_query;
             let mut subquery_path =
                 subquery_path.ok_or(Error::CorruptedCodeExecution("subquery path must exist"))?;
@@ -198,9 +384,13 @@ impl PathQuery {
             let subquery_branch = SubqueryBranch {
                 subquery_path: rest_of_path,
                 subquery,
+                limit,
             };
             merged_query.merge_conditional_boxed_subquery(QueryItem::Key(key), subquery_branch);
         }
+        // Conditional branches come out in the order path queries were given,
+        // sort them so the merged query doesn't depend on it
+        sort_conditional_subquery_branches(&mut merged_query);
 
         Ok(PathQuery::new_unsized(common_path, merged_query))
     }
@@ -208,6 +398,9 @@ impl PathQuery {
     /// Given a set of path queries, this returns an array of path keys that are
     /// common across all the path queries.
     /// Also returns the point at which they stopped being equal.
+    /// If the shortest path is a prefix of all the others, it is the common
+    /// path and the returned index is its length, so its query is applied at
+    /// the level of the common path and the other paths become subqueries.
     fn get_common_path(path_queries: &[&PathQuery]) -> (Vec<Vec<u8>>, usize) {
         let min_path_length = path_queries
             .iter()
@@ -218,6 +411,8 @@ impl PathQuery {
         let mut common_path = vec![];
         let mut level = 0;
 
+        // Stopping at the shortest path keeps the index a valid start index
+        // for every path query, even when one ends where the others go on
         while level < min_path_length {
             let keys_at_level = path_queries
                 .iter()
@@ -254,6 +449,7 @@ impl PathQuery {
             Ordering::Equal => Ok(SubqueryBranch {
                 subquery_path: None,
                 subquery: Some(Box::new(self.query.query.clone())),
+                limit: None,
             }),
             Ordering::Less => Err(Error::CorruptedCodeExecution(
                 "invalid start index for path query merge",
@@ -264,12 +460,31 @@ impl PathQuery {
                 Ok(SubqueryBranch {
                     subquery_path: Some(remainder.to_vec()),
                     subquery: Some(Box::new(self.query.query.clone())),
+                    limit: None,
                 })
             }
         }
     }
 }
 
+#[cfg(any(feature = "full", feature = "verify"))]
+/// Sorts conditional subquery branches of the query and of all its subqueries
+/// by their query items. Branches produced by a merge don't overlap, so their
+/// order doesn't change which branch applies to a key.
+fn sort_conditional_subquery_branches(query: &mut Query) {
+    if let Some(subquery) = query.default_subquery_branch.subquery.as_mut() {
+        sort_conditional_subquery_branches(subquery);
+    }
+    if let Some(conditional_subquery_branches) = query.conditional_subquery_branches.as_mut() {
+        conditional_subquery_branches.sort_keys();
+        for subquery_branch in conditional_subquery_branches.values_mut() {
+            if let Some(subquery) = subquery_branch.subquery.as_mut() {
+                sort_conditional_subquery_branches(subquery);
+            }
+        }
+    }
+}
+
 #[cfg(feature = "full")]
 #[cfg(test)]
 mod tests {
@@ -643,6 +858,94 @@ mod tests {
         compare_result_tuples(result_set_merged, expected_result_set);
     }
 
+    #[test]
+    fn test_get_common_path_with_prefix_path() {
+        let path_query_one =
+            PathQuery::new_unsized(vec![b"a".to_vec(), b"b".to_vec()], Query::new());
+        let path_query_two = PathQuery::new_unsized(
+            vec![b"a".to_vec(), b"b".to_vec(), b"c".to_vec()],
+            Query::new(),
+        );
+
+        for path_queries in [
+            [&path_query_one, &path_query_two],
+            [&path_query_two, &path_query_one],
+        ] {
+            let (common_path, next_index) = PathQuery::get_common_path(&path_queries);
+            assert_eq!(common_path, vec![b"a".to_vec(), b"b".to_vec()]);
+            assert_eq!(next_index, 2);
+        }
+    }
+
+    #[test]
+    fn test_prefix_path_merge() {
+        // [a, b, Q]
+        // [a, b, c, Q2]
+        let temp_db = make_deep_tree();
+
+        let mut query_one = Query::new();
+        query_one.insert_key(b"deeper_1".to_vec());
+        let path_query_one = PathQuery::new_unsized(
+            vec![b"deep_leaf".to_vec(), b"deep_node_1".to_vec()],
+            query_one,
+        );
+
+        let mut query_two = Query::new();
+        query_two.insert_all();
+        let path_query_two = PathQuery::new_unsized(
+            vec![
+                b"deep_leaf".to_vec(),
+                b"deep_node_1".to_vec(),
+                b"deeper_2".to_vec(),
+            ],
+            query_two.clone(),
+        );
+
+        let merged_path_query = PathQuery::merge(vec![&path_query_one, &path_query_two])
+            .expect("should merge path queries");
+        assert_eq!(
+            merged_path_query.path,
+            vec![b"deep_leaf".to_vec(), b"deep_node_1".to_vec()]
+        );
+        assert_eq!(
+            merged_path_query.query.query.items,
+            vec![
+                QueryItem::Key(b"deeper_1".to_vec()),
+                QueryItem::Key(b"deeper_2".to_vec())
+            ]
+        );
+        let conditional_subquery_branches = merged_path_query
+            .query
+            .query
+            .conditional_subquery_branches
+            .as_ref()
+            .expect("expected a conditional subquery for the longer path");
+        assert_eq!(conditional_subquery_branches.len(), 1);
+        let subquery_branch = conditional_subquery_branches
+            .get(&QueryItem::Key(b"deeper_2".to_vec()))
+            .expect("expected a subquery for the next key of the longer path");
+        assert_eq!(subquery_branch.subquery_path, None);
+        assert_eq!(subquery_branch.subquery.as_deref(), Some(&query_two));
+
+        let proof = temp_db.prove_query(&merged_path_query).unwrap().unwrap();
+        let (_, result_set) = GroveDb::verify_query_raw(proof.as_slice(), &merged_path_query)
+            .expect("should execute proof");
+        assert_eq!(result_set.len(), 4);
+        let keys = result_set
+            .into_iter()
+            .map(|result| result.key)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            keys,
+            vec![
+                b"deeper_1".to_vec(),
+                b"key4".to_vec(),
+                b"key5".to_vec(),
+                b"key6".to_vec()
+            ]
+        );
+    }
+
     #[test]
     fn test_same_path_and_different_path_query_merge() {
         let temp_db = make_deep_tree();
@@ -838,4 +1141,64 @@ mod tests {
             .expect("should execute proof");
         assert_eq!(result_set.len(), 4);
     }
+
+    #[test]
+    fn test_merge_is_independent_of_input_order() {
+        let temp_db = make_deep_tree();
+
+        let path_query_one = PathQuery::new_unsized(
+            vec![
+                b"deep_leaf".to_vec(),
+                b"deep_node_1".to_vec(),
+                b"deeper_1".to_vec(),
+            ],
+            Query::new_single_query_item(QueryItem::RangeFull(RangeFull)),
+        );
+        let path_query_two = PathQuery::new_unsized(
+            vec![
+                b"deep_leaf".to_vec(),
+                b"deep_node_2".to_vec(),
+                b"deeper_3".to_vec(),
+            ],
+            Query::new_single_query_item(QueryItem::RangeFull(RangeFull)),
+        );
+        let path_query_three = PathQuery::new_unsized(
+            vec![
+                b"deep_leaf".to_vec(),
+                b"deep_node_1".to_vec(),
+                b"deeper_2".to_vec(),
+            ],
+            Query::new_single_query_item(QueryItem::RangeFull(RangeFull)),
+        );
+
+        let merged_in_order =
+            PathQuery::merge(vec![&path_query_one, &path_query_two, &path_query_three])
+                .expect("expect to merge path queries");
+        let merged_out_of_order =
+            PathQuery::merge(vec![&path_query_two, &path_query_three, &path_query_one])
+                .expect("expect to merge path queries");
+        assert_eq!(
+            format!("{:?}", merged_in_order),
+            format!("{:?}", merged_out_of_order)
+        );
+
+        let conditional_subquery_branches = merged_out_of_order
+            .query
+            .query
+            .conditional_subquery_branches
+            .as_ref()
+            .expect("expected to have conditional subquery branches");
+        assert_eq!(
+            conditional_subquery_branches.keys().collect::<Vec<_>>(),
+            vec![
+                &QueryItem::Key(b"deep_node_1".to_vec()),
+                &QueryItem::Key(b"deep_node_2".to_vec())
+            ]
+        );
+
+        let proof = temp_db.prove_query(&merged_out_of_order).unwrap().unwrap();
+        let (_, result_set) = GroveDb::verify_query_raw(proof.as_slice(), &merged_out_of_order)
+            .expect("should execute proof");
+        assert_eq!(result_set.len(), 9);
+    }
 }
@@ -0,0 +1,93 @@
+// MIT LICENSE
+//
+// Copyright (c) 2021 Dash Core Group
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Steps taken for tree elements matched by a query, shared by proof
+//! generation and query execution
+
+use grovedb_merk::proofs::Query;
+
+use crate::{query_result_type::Path, Element, Error, SizedQuery};
+
+/// What to do with a tree element matched by a query.
+///
+/// Both [GroveDb::prove_query] and the plain query methods decide how to go
+/// through a matched tree with it, so a proof covers exactly the subtrees and
+/// keys a direct query reads.
+///
+/// [GroveDb::prove_query]: crate::GroveDb::prove_query
+#[derive(Debug, Clone, PartialEq)]
+pub enum QueryPlanStep {
+    /// The element has no subquery and is part of the result set itself
+    Element,
+    /// Apply `query` to the subtree under the element key followed by `path`
+    Subquery {
+        /// Keys to follow from the subtree of the element
+        path: Path,
+        /// Query to apply at the end of the path
+        query: Query,
+    },
+    /// Get the single `key` from the subtree under the element key followed
+    /// by `path`, used for a subquery path without a subquery
+    Key {
+        /// Keys to follow from the subtree of the element
+        path: Path,
+        /// Key to get at the end of the path
+        key: Vec<u8>,
+    },
+}
+
+impl QueryPlanStep {
+    /// Returns the step to take for a tree element under `key` matched by
+    /// `sized_query` at any level of a path query
+    pub fn for_key(sized_query: &SizedQuery, key: &[u8]) -> Result<Self, Error> {
+        let (subquery_path, subquery) =
+            Element::subquery_paths_and_value_for_sized_query(sized_query, key);
+        Self::from_subquery_branch(subquery_path, subquery)
+    }
+
+    /// Returns the step for a subquery path and subquery as set on a
+    /// subquery branch
+    pub fn from_subquery_branch(
+        subquery_path: Option<Path>,
+        subquery: Option<Query>,
+    ) -> Result<Self, Error> {
+        match (subquery_path, subquery) {
+            (None, None) => Ok(QueryPlanStep::Element),
+            (path, Some(query)) => Ok(QueryPlanStep::Subquery {
+                path: path.unwrap_or_default(),
+                query,
+            }),
+            (Some(mut path), None) => match path.pop() {
+                Some(key) => Ok(QueryPlanStep::Key { path, key }),
+                None => Err(Error::CorruptedCodeExecution(
+                    "subquery_paths can not be empty",
+                )),
+            },
+        }
+    }
+}
@@ -34,6 +34,7 @@ use std::{
 };
 
 pub use grovedb_merk::proofs::query::{Key, Path, PathKey};
+use grovedb_merk::CryptoHash;
 
 use crate::{operations::proof::util::ProvedPathKeyValue, Element, Error};
 
@@ -52,17 +53,29 @@ pub enum QueryResultType {
 pub struct QueryResultElements {
     /// Elements
     pub elements: Vec<QueryResultElement>,
+    /// Root hashes of the subtrees of tree elements, in the same order as
+    /// `elements` and `None` for other elements. Only filled for queries
+    /// setting [`SizedQuery::include_subtree_hashes`], empty otherwise.
+    ///
+    /// [`SizedQuery::include_subtree_hashes`]: crate::SizedQuery::include_subtree_hashes
+    pub subtree_root_hashes: Vec<Option<CryptoHash>>,
 }
 
 impl QueryResultElements {
     /// New
     pub fn new() -> Self {
-        QueryResultElements { elements: vec![] }
+        QueryResultElements {
+            elements: vec![],
+            subtree_root_hashes: vec![],
+        }
     }
 
     /// From elements
     pub(crate) fn from_elements(elements: Vec<QueryResultElement>) -> Self {
-        QueryResultElements { elements }
+        QueryResultElements {
+            elements,
+            subtree_root_hashes: vec![],
+        }
     }
 
     /// Length
@@ -207,6 +220,15 @@ pub enum QueryResultElement {
 
 #[cfg(feature = "full")]
 impl QueryResultElement {
+    /// Element of the result
+    pub fn element(&self) -> &Element {
+        match self {
+            QueryResultElement::ElementResultItem(element)
+            | QueryResultElement::KeyElementPairResultItem((_, element))
+            | QueryResultElement::PathKeyElementTrioResultItem((_, _, element)) => element,
+        }
+    }
+
     /// Map element
     pub fn map_element(
         self,
@@ -242,6 +242,68 @@ impl ReferencePathType {
     }
 }
 
+#[cfg(feature = "full")]
+impl ReferencePathType {
+    /// Checks the reference is structurally sound, i.e. that it cannot resolve
+    /// to an empty path wherever it's stored. Upstream heights can only be
+    /// checked knowing where the reference is stored, see
+    /// [`ReferencePathType::validate_at`].
+    ///
+    /// References are checked when built with [`Element::try_new_reference`]
+    /// and when inserted, stored ones are read back unchecked.
+    ///
+    /// [`Element::try_new_reference`]: crate::Element::try_new_reference
+    pub fn validate(&self) -> Result<(), Error> {
+        match self {
+            ReferencePathType::AbsolutePathReference(path) if path.is_empty() => Err(
+                Error::InvalidPath("absolute reference path is empty".to_owned()),
+            ),
+            ReferencePathType::UpstreamRootHeightReference(0, path) if path.is_empty() => {
+                Err(Error::InvalidPath(
+                    "upstream root height reference keeps and appends nothing".to_owned(),
+                ))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks the reference is well-formed for a reference stored in the
+    /// subtree at `current_path`.
+    pub fn validate_at<B: AsRef<[u8]>>(&self, current_path: &[B]) -> Result<(), Error> {
+        self.validate()?;
+        match self {
+            ReferencePathType::UpstreamRootHeightReference(height, _)
+                if usize::from(*height) > current_path.len() =>
+            {
+                Err(Error::InvalidPath(format!(
+                    "upstream root height {} is out of range for a path of length {}",
+                    height,
+                    current_path.len()
+                )))
+            }
+            ReferencePathType::UpstreamFromElementHeightReference(height, path)
+                if usize::from(*height) > current_path.len()
+                    || (usize::from(*height) == current_path.len() && path.is_empty()) =>
+            {
+                Err(Error::InvalidPath(format!(
+                    "upstream from element height {} is out of range for a path of length {}",
+                    height,
+                    current_path.len()
+                )))
+            }
+            ReferencePathType::CousinReference(_)
+            | ReferencePathType::RemovedCousinReference(_)
+                if current_path.is_empty() =>
+            {
+                Err(Error::InvalidPath(
+                    "cousin reference stored in the root tree has no parent to swap".to_owned(),
+                ))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
 #[cfg(feature = "full")]
 #[cfg(test)]
 mod tests {
@@ -186,6 +186,7 @@ impl<'db> Restorer<'db> {
                 .map_err(|e| RestorerError(e.to_string()))?,
                 None,
                 root_hash,
+                false,
             )),
             current_merk_chunk_index: 0,
             current_merk_path: vec![],
@@ -273,6 +274,7 @@ impl<'db> Restorer<'db> {
                     merk,
                     Some(combining_value),
                     expected_hash,
+                    false,
                 ));
                 self.current_merk_chunk_index = 0;
                 self.current_merk_path = next_path;
@@ -0,0 +1,238 @@
+// MIT LICENSE
+//
+// Copyright (c) 2021 Dash Core Group
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Subtree handle
+//! Read-only access to a single subtree's Merk, opened once and reused across
+//! reads
+
+use grovedb_costs::{cost_return_on_error, CostResult, CostsExt, OperationCost};
+use grovedb_merk::{CryptoHash, Merk};
+use grovedb_path::SubtreePath;
+use grovedb_storage::{
+    rocksdb_storage::{PrefixedRocksDbStorageContext, PrefixedRocksDbTransactionContext},
+    RawIterator, StorageContext,
+};
+
+use crate::{query_result_type::KeyElementPair, Element, Error, GroveDb, TransactionArg};
+
+/// Merk of the opened subtree, depending on whether it was opened on a
+/// transaction
+enum SubtreeMerk<'db> {
+    Transactional(Merk<PrefixedRocksDbTransactionContext<'db>>),
+    NonTransactional(Merk<PrefixedRocksDbStorageContext<'db>>),
+}
+
+/// Read-only handle to an opened subtree.
+///
+/// The subtree's Merk is opened once when the handle is created, so several
+/// reads through the handle don't pay for re-opening it each time. The handle
+/// exposes no way to write to the subtree.
+pub struct SubtreeHandle<'db> {
+    merk: SubtreeMerk<'db>,
+}
+
+impl<'db> SubtreeHandle<'db> {
+    /// Get the raw element stored at `key`, or `None` if there is none.
+    /// References are returned as is and not followed.
+    pub fn get(&self, key: &[u8]) -> CostResult<Option<Element>, Error> {
+        match &self.merk {
+            SubtreeMerk::Transactional(merk) => Element::get_optional(merk, key, true),
+            SubtreeMerk::NonTransactional(merk) => Element::get_optional(merk, key, true),
+        }
+    }
+
+    /// Root hash of the subtree
+    pub fn root_hash(&self) -> CostResult<CryptoHash, Error> {
+        match &self.merk {
+            SubtreeMerk::Transactional(merk) => merk.root_hash(),
+            SubtreeMerk::NonTransactional(merk) => merk.root_hash(),
+        }
+        .map(Ok)
+    }
+
+    /// All key-element pairs of the subtree in key order
+    pub fn iter(&self) -> CostResult<Vec<KeyElementPair>, Error> {
+        match &self.merk {
+            SubtreeMerk::Transactional(merk) => collect_elements(merk.storage.raw_iter()),
+            SubtreeMerk::NonTransactional(merk) => collect_elements(merk.storage.raw_iter()),
+        }
+    }
+}
+
+fn collect_elements<I: RawIterator>(raw_iter: I) -> CostResult<Vec<KeyElementPair>, Error> {
+    let mut cost = OperationCost::default();
+    let mut elements = Vec::new();
+
+    let mut element_iterator = Element::iterator(raw_iter).unwrap_add_cost(&mut cost);
+    while let Some(pair) = cost_return_on_error!(&mut cost, element_iterator.next_element()) {
+        elements.push(pair);
+    }
+
+    Ok(elements).wrap_with_cost(cost)
+}
+
+impl GroveDb {
+    /// Open the subtree at `path` once and return a read-only handle to it.
+    /// Reads through the handle reuse the opened Merk instead of opening it
+    /// for every call.
+    pub fn open_subtree_readonly<'db, 'b, B, P>(
+        &'db self,
+        path: P,
+        transaction: TransactionArg<'db, 'db>,
+    ) -> CostResult<SubtreeHandle<'db>, Error>
+    where
+        B: AsRef<[u8]> + 'b,
+        P: Into<SubtreePath<'b, B>>,
+    {
+        let path = path.into();
+        if let Some(tx) = transaction {
+            self.open_transactional_merk_at_path(path, tx, None)
+                .map_ok(SubtreeMerk::Transactional)
+        } else {
+            self.open_non_transactional_merk_at_path(path, None)
+                .map_ok(SubtreeMerk::NonTransactional)
+        }
+        .map_ok(|merk| SubtreeHandle { merk })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use grovedb_costs::OperationCost;
+    use pretty_assertions::assert_eq;
+
+    use crate::{
+        tests::{make_test_grovedb, TEST_LEAF},
+        Element,
+    };
+
+    #[test]
+    fn test_subtree_handle_reads_match_get_raw() {
+        let db = make_test_grovedb();
+        db.insert(
+            [TEST_LEAF].as_ref(),
+            b"innertree",
+            Element::empty_tree(),
+            None,
+            None,
+        )
+        .unwrap()
+        .expect("successful subtree insert");
+        let keys: [&[u8]; 3] = [b"key1", b"key2", b"key3"];
+        for key in keys {
+            db.insert(
+                [TEST_LEAF, b"innertree"].as_ref(),
+                key,
+                Element::new_item(key.to_vec()),
+                None,
+                None,
+            )
+            .unwrap()
+            .expect("successful item insert");
+        }
+        let path = [TEST_LEAF, b"innertree"];
+
+        let mut individual_cost = OperationCost::default();
+        let mut individual_elements = Vec::new();
+        for key in keys {
+            let element = db
+                .get_raw(path.as_ref().into(), key, None)
+                .unwrap_add_cost(&mut individual_cost)
+                .expect("successful get_raw");
+            individual_elements.push(element);
+        }
+
+        let mut handle_cost = OperationCost::default();
+        let handle = db
+            .open_subtree_readonly(path.as_ref(), None)
+            .unwrap_add_cost(&mut handle_cost)
+            .expect("successful subtree open");
+        let mut handle_elements = Vec::new();
+        for key in keys {
+            let element = handle
+                .get(key)
+                .unwrap_add_cost(&mut handle_cost)
+                .expect("successful get")
+                .expect("element exists");
+            handle_elements.push(element);
+        }
+        assert_eq!(handle_elements, individual_elements);
+        assert!(handle_cost.seek_count < individual_cost.seek_count);
+
+        assert_eq!(
+            handle.get(b"missing").unwrap().expect("successful get"),
+            None
+        );
+        let merk_root_hash = db
+            .open_non_transactional_merk_at_path(path.as_ref().into(), None)
+            .unwrap()
+            .expect("successful merk open")
+            .root_hash()
+            .unwrap();
+        assert_eq!(
+            handle.root_hash().unwrap().expect("successful root hash"),
+            merk_root_hash
+        );
+        assert_eq!(
+            handle.iter().unwrap().expect("successful iteration"),
+            keys.iter()
+                .map(|key| (key.to_vec(), Element::new_item(key.to_vec())))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_subtree_handle_on_transaction() {
+        let db = make_test_grovedb();
+        let tx = db.start_transaction();
+        db.insert(
+            [TEST_LEAF].as_ref(),
+            b"key",
+            Element::new_item(b"value".to_vec()),
+            None,
+            Some(&tx),
+        )
+        .unwrap()
+        .expect("successful insert");
+
+        let handle = db
+            .open_subtree_readonly([TEST_LEAF].as_ref(), Some(&tx))
+            .unwrap()
+            .expect("successful subtree open");
+        assert_eq!(
+            handle.get(b"key").unwrap().expect("successful get"),
+            Some(Element::new_item(b"value".to_vec()))
+        );
+
+        let handle = db
+            .open_subtree_readonly([TEST_LEAF].as_ref(), None)
+            .unwrap()
+            .expect("successful subtree open");
+        assert_eq!(handle.get(b"key").unwrap().expect("successful get"), None);
+    }
+}
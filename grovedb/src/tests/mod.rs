@@ -47,6 +47,7 @@ use tempfile::TempDir;
 use self::common::EMPTY_PATH;
 use super::*;
 use crate::{
+    batch::GroveDbOp, operations::insert::InsertOptions,
     query_result_type::QueryResultType::QueryKeyElementPairResultType,
     reference_path::ReferencePathType, tests::common::compare_result_tuples,
 };
@@ -813,6 +814,229 @@ fn test_follow_references() {
     );
 }
 
+#[test]
+fn test_follow_references_shares_resolved_chains() {
+    let db = make_test_grovedb();
+    let element = Element::new_item(b"ayy".to_vec());
+
+    db.insert([TEST_LEAF].as_ref(), b"target", element.clone(), None, None)
+        .unwrap()
+        .expect("successful value insert");
+    db.insert(
+        [TEST_LEAF].as_ref(),
+        b"shared",
+        Element::new_reference(ReferencePathType::AbsolutePathReference(vec![
+            TEST_LEAF.to_vec(),
+            b"target".to_vec(),
+        ])),
+        None,
+        None,
+    )
+    .unwrap()
+    .expect("successful reference insert");
+
+    let other_element = Element::new_item(b"other".to_vec());
+    db.insert(
+        [ANOTHER_TEST_LEAF].as_ref(),
+        b"plain",
+        other_element.clone(),
+        None,
+        None,
+    )
+    .unwrap()
+    .expect("successful value insert");
+
+    let mut refs = Vec::new();
+    for i in 0..4u8 {
+        let key = vec![b'r', i];
+        db.insert(
+            [ANOTHER_TEST_LEAF].as_ref(),
+            &key,
+            Element::new_reference(ReferencePathType::AbsolutePathReference(vec![
+                TEST_LEAF.to_vec(),
+                b"shared".to_vec(),
+            ])),
+            None,
+            None,
+        )
+        .unwrap()
+        .expect("successful reference insert");
+        refs.push((vec![ANOTHER_TEST_LEAF.to_vec()], key));
+    }
+    refs.insert(2, (vec![ANOTHER_TEST_LEAF.to_vec()], b"plain".to_vec()));
+
+    let mut individual_cost = OperationCost::default();
+    let individually: Vec<Element> = refs
+        .iter()
+        .map(|(path, key)| {
+            db.get(path.as_slice(), key, None)
+                .unwrap_add_cost(&mut individual_cost)
+                .expect("successful get")
+        })
+        .collect();
+
+    let mut batch_cost = OperationCost::default();
+    let batched = db
+        .follow_references(&refs, None)
+        .unwrap_add_cost(&mut batch_cost)
+        .expect("successful batch resolution");
+
+    assert_eq!(batched, individually);
+    assert_eq!(
+        batched,
+        vec![
+            element.clone(),
+            element.clone(),
+            other_element,
+            element.clone(),
+            element
+        ]
+    );
+    assert!(batch_cost.seek_count < individual_cost.seek_count);
+}
+
+#[test]
+fn test_get_multi() {
+    let db = make_test_grovedb();
+    let item = Element::new_item(b"ayy".to_vec());
+    let other_item = Element::new_item(b"other".to_vec());
+
+    for key in [b"key1".as_slice(), b"key2", b"key3"] {
+        db.insert([TEST_LEAF].as_ref(), key, item.clone(), None, None)
+            .unwrap()
+            .expect("successful value insert");
+    }
+    db.insert(
+        [ANOTHER_TEST_LEAF].as_ref(),
+        b"other",
+        other_item.clone(),
+        None,
+        None,
+    )
+    .unwrap()
+    .expect("successful value insert");
+    db.insert(
+        [ANOTHER_TEST_LEAF].as_ref(),
+        b"reference",
+        Element::new_reference(ReferencePathType::SiblingReference(b"other".to_vec())),
+        None,
+        None,
+    )
+    .unwrap()
+    .expect("successful reference insert");
+
+    let requests = vec![
+        (vec![TEST_LEAF.to_vec()], b"key1".to_vec()),
+        (vec![ANOTHER_TEST_LEAF.to_vec()], b"reference".to_vec()),
+        (vec![TEST_LEAF.to_vec()], b"missing".to_vec()),
+        (vec![TEST_LEAF.to_vec()], b"key2".to_vec()),
+        (vec![ANOTHER_TEST_LEAF.to_vec()], b"other".to_vec()),
+        (vec![TEST_LEAF.to_vec()], b"key3".to_vec()),
+        (
+            vec![TEST_LEAF.to_vec(), b"missing_subtree".to_vec()],
+            b"key".to_vec(),
+        ),
+    ];
+
+    let mut individual_cost = OperationCost::default();
+    for (path, key) in &requests {
+        let _ = db
+            .get(path.as_slice(), key, None)
+            .unwrap_add_cost(&mut individual_cost);
+    }
+
+    let mut multi_cost = OperationCost::default();
+    let results = db
+        .get_multi(&requests, None)
+        .unwrap_add_cost(&mut multi_cost)
+        .expect("successful multi get");
+
+    assert_eq!(results.len(), requests.len());
+    assert_eq!(results[0].as_ref().expect("expected an item"), &item);
+    assert_eq!(results[1].as_ref().expect("expected an item"), &other_item);
+    assert!(matches!(results[2], Err(Error::PathKeyNotFound(_))));
+    assert_eq!(results[3].as_ref().expect("expected an item"), &item);
+    assert_eq!(results[4].as_ref().expect("expected an item"), &other_item);
+    assert_eq!(results[5].as_ref().expect("expected an item"), &item);
+    assert!(matches!(results[6], Err(Error::PathParentLayerNotFound(_))));
+    assert!(multi_cost.seek_count < individual_cost.seek_count);
+}
+
+#[test]
+fn test_find_dangling_references() {
+    let db = make_test_grovedb();
+    let reference_to = |key: &[u8]| {
+        Element::new_reference(ReferencePathType::AbsolutePathReference(vec![
+            TEST_LEAF.to_vec(),
+            key.to_vec(),
+        ]))
+    };
+
+    for key in [b"kept", b"gone"] {
+        db.insert(
+            [TEST_LEAF].as_ref(),
+            key,
+            Element::new_item(key.to_vec()),
+            None,
+            None,
+        )
+        .unwrap()
+        .expect("successful value insert");
+    }
+    db.insert(
+        [TEST_LEAF].as_ref(),
+        b"innertree",
+        Element::empty_tree(),
+        None,
+        None,
+    )
+    .unwrap()
+    .expect("successful subtree insert");
+    for (path, key, target) in [
+        (vec![TEST_LEAF], b"ref_kept".as_slice(), b"kept".as_slice()),
+        (vec![TEST_LEAF], b"ref_gone".as_slice(), b"gone".as_slice()),
+        (
+            vec![TEST_LEAF, b"innertree"],
+            b"inner_ref_gone".as_slice(),
+            b"gone".as_slice(),
+        ),
+    ] {
+        db.insert(path.as_slice(), key, reference_to(target), None, None)
+            .unwrap()
+            .expect("successful reference insert");
+    }
+
+    assert_eq!(
+        db.find_dangling_references([TEST_LEAF].as_ref(), true, None)
+            .unwrap()
+            .expect("successful scan"),
+        vec![]
+    );
+
+    db.delete([TEST_LEAF].as_ref(), b"gone", None, None)
+        .unwrap()
+        .expect("successful delete");
+
+    assert_eq!(
+        db.find_dangling_references([TEST_LEAF].as_ref(), false, None)
+            .unwrap()
+            .expect("successful scan"),
+        vec![(vec![TEST_LEAF.to_vec()], b"ref_gone".to_vec())]
+    );
+    assert_eq!(
+        db.find_dangling_references([TEST_LEAF].as_ref(), true, None)
+            .unwrap()
+            .expect("successful scan"),
+        vec![
+            (vec![TEST_LEAF.to_vec()], b"ref_gone".to_vec()),
+            (
+                vec![TEST_LEAF.to_vec(), b"innertree".to_vec()],
+                b"inner_ref_gone".to_vec()
+            ),
+        ]
+    );
+}
+
 #[test]
 fn test_reference_must_point_to_item() {
     let db = make_test_grovedb();
@@ -883,603 +1107,827 @@ fn test_too_many_indirections() {
         .get([TEST_LEAF].as_ref(), &keygen(MAX_REFERENCE_HOPS + 1), None)
         .unwrap();
 
-    assert!(matches!(result, Err(Error::ReferenceLimit)));
+    match result {
+        Err(Error::ReferenceChainTooLong { hops, last_path }) => {
+            assert_eq!(hops, MAX_REFERENCE_HOPS);
+            assert_eq!(last_path, vec![TEST_LEAF.to_vec(), keygen(1)]);
+        }
+        other => panic!("expected reference chain to be too long, got {:?}", other),
+    }
 }
 
 #[test]
-fn test_reference_value_affects_state() {
-    let db_one = make_test_grovedb();
-    db_one
-        .insert(
-            [TEST_LEAF].as_ref(),
-            b"key1",
-            Element::new_item(vec![0]),
-            None,
-            None,
-        )
-        .unwrap()
-        .expect("should insert item");
-    db_one
-        .insert(
-            [ANOTHER_TEST_LEAF].as_ref(),
-            b"ref",
-            Element::new_reference(ReferencePathType::AbsolutePathReference(vec![
-                TEST_LEAF.to_vec(),
-                b"key1".to_vec(),
-            ])),
-            None,
-            None,
-        )
-        .unwrap()
-        .expect("should insert item");
-
-    let db_two = make_test_grovedb();
-    db_two
-        .insert(
-            [TEST_LEAF].as_ref(),
-            b"key1",
-            Element::new_item(vec![0]),
-            None,
-            None,
-        )
-        .unwrap()
-        .expect("should insert item");
-    db_two
-        .insert(
-            [ANOTHER_TEST_LEAF].as_ref(),
-            b"ref",
-            Element::new_reference(ReferencePathType::UpstreamRootHeightReference(
-                0,
-                vec![TEST_LEAF.to_vec(), b"key1".to_vec()],
-            )),
-            None,
-            None,
-        )
-        .unwrap()
-        .expect("should insert item");
+fn test_cyclic_reference_is_not_reported_as_too_long() {
+    let db = make_test_grovedb();
+    db.insert(
+        [TEST_LEAF].as_ref(),
+        b"b",
+        Element::new_item(b"value".to_vec()),
+        None,
+        None,
+    )
+    .unwrap()
+    .expect("successful item insert");
+    db.insert(
+        [TEST_LEAF].as_ref(),
+        b"a",
+        Element::new_reference(ReferencePathType::SiblingReference(b"b".to_vec())),
+        None,
+        None,
+    )
+    .unwrap()
+    .expect("successful reference insert");
+    // Replacing the target with a reference back closes the cycle
+    db.insert(
+        [TEST_LEAF].as_ref(),
+        b"b",
+        Element::new_reference(ReferencePathType::SiblingReference(b"a".to_vec())),
+        None,
+        None,
+    )
+    .unwrap()
+    .expect("successful reference insert");
 
-    assert_ne!(
-        db_one
-            .root_hash(None)
-            .unwrap()
-            .expect("should return root hash"),
-        db_two
-            .root_hash(None)
-            .unwrap()
-            .expect("should return toor hash")
-    );
+    let result = db.get([TEST_LEAF].as_ref(), b"a", None).unwrap();
+    assert!(matches!(result, Err(Error::CyclicReference)));
 }
 
 #[test]
-fn test_tree_structure_is_persistent() {
-    let tmp_dir = TempDir::new().unwrap();
-    let element = Element::new_item(b"ayy".to_vec());
-    // Create a scoped GroveDB
-    let prev_root_hash = {
-        let mut db = GroveDb::open(tmp_dir.path()).unwrap();
-        add_test_leaves(&mut db);
+fn test_follow_reference_chain_repeatedly() {
+    use crate::operations::get::MAX_REFERENCE_HOPS;
+    let db = make_test_grovedb();
 
-        // Insert some nested subtrees
+    let keygen = |idx| format!("key{}", idx).bytes().collect::<Vec<u8>>();
+
+    db.insert(
+        [TEST_LEAF].as_ref(),
+        b"key0",
+        Element::new_item(b"value".to_vec()),
+        None,
+        None,
+    )
+    .unwrap()
+    .expect("successful item insert");
+    for i in 1..=MAX_REFERENCE_HOPS {
         db.insert(
             [TEST_LEAF].as_ref(),
-            b"key1",
-            Element::empty_tree(),
-            None,
-            None,
-        )
-        .unwrap()
-        .expect("successful subtree 1 insert");
-        db.insert(
-            [TEST_LEAF, b"key1"].as_ref(),
-            b"key2",
-            Element::empty_tree(),
-            None,
-            None,
-        )
-        .unwrap()
-        .expect("successful subtree 2 insert");
-        // Insert an element into subtree
-        db.insert(
-            [TEST_LEAF, b"key1", b"key2"].as_ref(),
-            b"key3",
-            element.clone(),
+            &keygen(i),
+            Element::new_reference(ReferencePathType::SiblingReference(keygen(i - 1))),
             None,
             None,
         )
         .unwrap()
-        .expect("successful value insert");
-        assert_eq!(
-            db.get([TEST_LEAF, b"key1", b"key2"].as_ref(), b"key3", None)
-                .unwrap()
-                .expect("successful get 1"),
-            element
-        );
-        db.root_hash(None).unwrap().unwrap()
-    };
-    // Open a persisted GroveDB
-    let db = GroveDb::open(tmp_dir).unwrap();
-    assert_eq!(
-        db.get([TEST_LEAF, b"key1", b"key2"].as_ref(), b"key3", None)
-            .unwrap()
-            .expect("successful get 2"),
-        element
-    );
-    assert!(db
-        .get([TEST_LEAF, b"key1", b"key2"].as_ref(), b"key4", None)
-        .unwrap()
-        .is_err());
-    assert_eq!(prev_root_hash, db.root_hash(None).unwrap().unwrap());
+        .expect("successful reference insert");
+    }
+    db.insert(
+        [TEST_LEAF].as_ref(),
+        b"cycle_b",
+        Element::new_item(b"value".to_vec()),
+        None,
+        None,
+    )
+    .unwrap()
+    .expect("successful item insert");
+    db.insert(
+        [TEST_LEAF].as_ref(),
+        b"cycle_a",
+        Element::new_reference(ReferencePathType::SiblingReference(b"cycle_b".to_vec())),
+        None,
+        None,
+    )
+    .unwrap()
+    .expect("successful reference insert");
+    db.insert(
+        [TEST_LEAF].as_ref(),
+        b"cycle_b",
+        Element::new_reference(ReferencePathType::SiblingReference(b"cycle_a".to_vec())),
+        None,
+        None,
+    )
+    .unwrap()
+    .expect("successful reference insert");
+
+    // Cycles are still detected while the visited paths are reused between
+    // calls, and don't leave paths behind for the next resolution
+    for _ in 0..1000 {
+        assert_eq!(
+            db.get([TEST_LEAF].as_ref(), &keygen(MAX_REFERENCE_HOPS), None)
+                .unwrap()
+                .expect("expected to resolve the chain"),
+            Element::new_item(b"value".to_vec())
+        );
+        assert!(matches!(
+            db.get([TEST_LEAF].as_ref(), b"cycle_a", None).unwrap(),
+            Err(Error::CyclicReference)
+        ));
+    }
 }
 
 #[test]
-fn test_root_tree_leaves_are_noted() {
+fn test_follow_reference_within_transaction() {
     let db = make_test_grovedb();
-    db.check_subtree_exists_path_not_found([TEST_LEAF].as_ref().into(), None)
-        .unwrap()
-        .expect("should exist");
-    db.check_subtree_exists_path_not_found([ANOTHER_TEST_LEAF].as_ref().into(), None)
+    let tx = db.start_transaction();
+
+    // The target, its subtree and the references all only exist in the
+    // transaction
+    db.insert(
+        [TEST_LEAF].as_ref(),
+        b"subtree",
+        Element::empty_tree(),
+        None,
+        Some(&tx),
+    )
+    .unwrap()
+    .expect("successful subtree insert");
+    db.insert(
+        [TEST_LEAF, b"subtree"].as_ref(),
+        b"target",
+        Element::new_item(b"value".to_vec()),
+        None,
+        Some(&tx),
+    )
+    .unwrap()
+    .expect("successful item insert");
+    db.insert(
+        [ANOTHER_TEST_LEAF].as_ref(),
+        b"reference",
+        Element::new_reference(ReferencePathType::AbsolutePathReference(vec![
+            TEST_LEAF.to_vec(),
+            b"subtree".to_vec(),
+            b"target".to_vec(),
+        ])),
+        None,
+        Some(&tx),
+    )
+    .unwrap()
+    .expect("successful reference insert");
+    db.insert(
+        [ANOTHER_TEST_LEAF].as_ref(),
+        b"chained_reference",
+        Element::new_reference(ReferencePathType::SiblingReference(b"reference".to_vec())),
+        None,
+        Some(&tx),
+    )
+    .unwrap()
+    .expect("successful reference insert");
+
+    for key in [b"reference".as_slice(), b"chained_reference"] {
+        assert_eq!(
+            db.get([ANOTHER_TEST_LEAF].as_ref(), key, Some(&tx))
+                .unwrap()
+                .expect("expected to resolve the reference before commit"),
+            Element::new_item(b"value".to_vec())
+        );
+        assert!(db
+            .get([ANOTHER_TEST_LEAF].as_ref(), key, None)
+            .unwrap()
+            .is_err());
+    }
+
+    db.commit_transaction(tx)
         .unwrap()
-        .expect("should exist");
+        .expect("successful commit");
+    assert_eq!(
+        db.get([ANOTHER_TEST_LEAF].as_ref(), b"chained_reference", None)
+            .unwrap()
+            .expect("expected to resolve the reference after commit"),
+        Element::new_item(b"value".to_vec())
+    );
 }
 
 #[test]
-fn test_proof_for_invalid_path_root_key() {
+fn test_reference_max_hops() {
     let db = make_test_grovedb();
+    let item = Element::new_item(b"value".to_vec());
+    db.insert([TEST_LEAF].as_ref(), b"target", item.clone(), None, None)
+        .unwrap()
+        .expect("successful item insert");
+    db.insert(
+        [TEST_LEAF].as_ref(),
+        b"direct",
+        Element::new_reference_with_hops(
+            ReferencePathType::SiblingReference(b"target".to_vec()),
+            Some(1),
+        ),
+        None,
+        None,
+    )
+    .unwrap()
+    .expect("successful reference insert");
+    db.insert(
+        [ANOTHER_TEST_LEAF].as_ref(),
+        b"chained",
+        Element::new_reference_with_hops(
+            ReferencePathType::AbsolutePathReference(vec![TEST_LEAF.to_vec(), b"direct".to_vec()]),
+            Some(1),
+        ),
+        None,
+        None,
+    )
+    .unwrap()
+    .expect("successful reference insert");
+    db.insert(
+        [ANOTHER_TEST_LEAF].as_ref(),
+        b"unlimited",
+        Element::new_reference(ReferencePathType::AbsolutePathReference(vec![
+            TEST_LEAF.to_vec(),
+            b"direct".to_vec(),
+        ])),
+        None,
+        None,
+    )
+    .unwrap()
+    .expect("successful reference insert");
 
-    let query = Query::new();
-    let path_query = PathQuery::new_unsized(vec![b"invalid_path_key".to_vec()], query);
-
-    let proof = db.prove_query(&path_query).unwrap().unwrap();
-    let (hash, result_set) =
-        GroveDb::verify_query_raw(proof.as_slice(), &path_query).expect("should execute proof");
+    assert_eq!(
+        db.get([TEST_LEAF].as_ref(), b"direct", None)
+            .unwrap()
+            .expect("expected a direct reference to resolve"),
+        item
+    );
+    assert!(matches!(
+        db.get([ANOTHER_TEST_LEAF].as_ref(), b"chained", None)
+            .unwrap(),
+        Err(Error::ReferenceChainTooLong { hops: 1, .. })
+    ));
+    assert_eq!(
+        db.get([ANOTHER_TEST_LEAF].as_ref(), b"unlimited", None)
+            .unwrap()
+            .expect("expected an unannotated reference to resolve"),
+        item
+    );
 
-    assert_eq!(hash, db.root_hash(None).unwrap().unwrap());
-    assert_eq!(result_set.len(), 0);
+    // Batched resolution honors the hops of each starting reference, whether
+    // its chain is walked or was already resolved for another one
+    let direct = (vec![TEST_LEAF.to_vec()], b"direct".to_vec());
+    let chained = (vec![ANOTHER_TEST_LEAF.to_vec()], b"chained".to_vec());
+    let unlimited = (vec![ANOTHER_TEST_LEAF.to_vec()], b"unlimited".to_vec());
+    assert_eq!(
+        db.follow_references(&[direct, unlimited.clone()], None)
+            .unwrap()
+            .expect("expected references to resolve"),
+        vec![item.clone(), item]
+    );
+    assert!(matches!(
+        db.follow_references(&[chained.clone()], None).unwrap(),
+        Err(Error::ReferenceChainTooLong { hops: 1, .. })
+    ));
+    assert!(matches!(
+        db.follow_references(&[unlimited, chained], None).unwrap(),
+        Err(Error::ReferenceChainTooLong { hops: 1, .. })
+    ));
 }
 
 #[test]
-fn test_proof_for_invalid_path() {
-    let db = make_deep_tree();
-
-    let query = Query::new();
-    let path_query =
-        PathQuery::new_unsized(vec![b"deep_leaf".to_vec(), b"invalid_key".to_vec()], query);
+fn test_compact_subtree() {
+    let db = make_test_grovedb();
+    db.insert(
+        [TEST_LEAF].as_ref(),
+        b"subtree",
+        Element::empty_tree(),
+        None,
+        None,
+    )
+    .unwrap()
+    .expect("successful subtree insert");
+    db.insert(
+        [TEST_LEAF].as_ref(),
+        b"kept",
+        Element::new_item(b"value".to_vec()),
+        None,
+        None,
+    )
+    .unwrap()
+    .expect("successful item insert");
 
-    let proof = db.prove_query(&path_query).unwrap().unwrap();
-    let (hash, result_set) =
-        GroveDb::verify_query_raw(proof.as_slice(), &path_query).expect("should execute proof");
+    let keys = (0..200u32).map(|i| i.to_be_bytes().to_vec());
+    for key in keys.clone() {
+        db.insert(
+            [TEST_LEAF, b"subtree"].as_ref(),
+            &key,
+            Element::new_item(vec![0; 64]),
+            None,
+            None,
+        )
+        .unwrap()
+        .expect("successful item insert");
+    }
+    db.flush().expect("cannot flush");
+    for key in keys {
+        db.delete([TEST_LEAF, b"subtree"].as_ref(), &key, None, None)
+            .unwrap()
+            .expect("successful delete");
+    }
+    let root_hash = db.root_hash(None).unwrap().unwrap();
 
-    assert_eq!(hash, db.root_hash(None).unwrap().unwrap());
-    assert_eq!(result_set.len(), 0);
+    db.compact_subtree([TEST_LEAF, b"subtree"].as_ref())
+        .expect("cannot compact subtree");
+    db.compact_subtree(EMPTY_PATH)
+        .expect("cannot compact root tree");
 
-    let query = Query::new();
-    let path_query = PathQuery::new_unsized(
-        vec![
-            b"deep_leaf".to_vec(),
-            b"deep_node_1".to_vec(),
-            b"invalid_key".to_vec(),
-        ],
-        query,
+    // Compaction doesn't change the data
+    assert_eq!(db.root_hash(None).unwrap().unwrap(), root_hash);
+    assert!(db
+        .is_empty_tree([TEST_LEAF, b"subtree"].as_ref(), None)
+        .unwrap()
+        .expect("cannot check the subtree"));
+    assert_eq!(
+        db.get([TEST_LEAF].as_ref(), b"kept", None)
+            .unwrap()
+            .expect("successful get"),
+        Element::new_item(b"value".to_vec())
     );
+}
 
-    let proof = db.prove_query(&path_query).unwrap().unwrap();
-    let (hash, result_set) =
-        GroveDb::verify_query_raw(proof.as_slice(), &path_query).expect("should execute proof");
+#[test]
+fn test_open_read_only() {
+    let tmp_dir = TempDir::new().unwrap();
+    {
+        let mut db = GroveDb::open(tmp_dir.path()).unwrap();
+        add_test_leaves(&mut db);
+        db.insert(
+            [TEST_LEAF].as_ref(),
+            b"key",
+            Element::new_item(b"value".to_vec()),
+            None,
+            None,
+        )
+        .unwrap()
+        .expect("successful item insert");
+    }
 
-    assert_eq!(hash, db.root_hash(None).unwrap().unwrap());
-    assert_eq!(result_set.len(), 0);
+    let db = GroveDb::open_read_only(tmp_dir.path()).expect("should open read only");
+    assert!(db.is_read_only());
+    let root_hash = db.root_hash(None).unwrap().expect("should get root hash");
 
-    let query = Query::new();
-    let path_query = PathQuery::new_unsized(
-        vec![
-            b"deep_leaf".to_vec(),
-            b"deep_node_1".to_vec(),
-            b"deeper_1".to_vec(),
-            b"invalid_key".to_vec(),
-        ],
-        query,
+    assert_eq!(
+        db.get([TEST_LEAF].as_ref(), b"key", None)
+            .unwrap()
+            .expect("should read an item"),
+        Element::new_item(b"value".to_vec())
     );
 
-    let proof = db.prove_query(&path_query).unwrap().unwrap();
-    let (hash, result_set) =
-        GroveDb::verify_query_raw(proof.as_slice(), &path_query).expect("should execute proof");
+    let mut query = Query::new();
+    query.insert_all();
+    let path_query = PathQuery::new_unsized(vec![TEST_LEAF.to_vec()], query);
+    let (elements, _) = db
+        .query_raw(&path_query, true, QueryKeyElementPairResultType, None)
+        .unwrap()
+        .expect("should query");
+    assert_eq!(elements.len(), 1);
+    let proof = db.prove_query(&path_query).unwrap().expect("should prove");
+    let (hash, _) = GroveDb::verify_query(&proof, &path_query).expect("should verify");
+    assert_eq!(hash, root_hash);
 
-    assert_eq!(hash, db.root_hash(None).unwrap().unwrap());
-    assert_eq!(result_set.len(), 0);
+    assert!(matches!(
+        db.insert(
+            [TEST_LEAF].as_ref(),
+            b"another_key",
+            Element::new_item(b"value".to_vec()),
+            None,
+            None,
+        )
+        .unwrap(),
+        Err(Error::ReadOnly)
+    ));
+    assert!(matches!(
+        db.delete([TEST_LEAF].as_ref(), b"key", None, None).unwrap(),
+        Err(Error::ReadOnly)
+    ));
+    assert!(matches!(
+        db.apply_batch(
+            vec![GroveDbOp::insert_op(
+                vec![TEST_LEAF.to_vec()],
+                b"another_key".to_vec(),
+                Element::new_item(b"value".to_vec()),
+            )],
+            None,
+            None,
+        )
+        .unwrap(),
+        Err(Error::ReadOnly)
+    ));
+    assert!(matches!(
+        db.put_aux(b"aux", b"value", None, None).unwrap(),
+        Err(Error::ReadOnly)
+    ));
 
-    let query = Query::new();
-    let path_query = PathQuery::new_unsized(
-        vec![
-            b"deep_leaf".to_vec(),
-            b"early_invalid_key".to_vec(),
-            b"deeper_1".to_vec(),
-            b"invalid_key".to_vec(),
-        ],
-        query,
-    );
+    assert!(matches!(db.try_start_transaction(), Err(Error::ReadOnly)));
 
-    let proof = db.prove_query(&path_query).unwrap().unwrap();
-    let (hash, result_set) =
-        GroveDb::verify_query_raw(proof.as_slice(), &path_query).expect("should execute proof");
+    assert_eq!(
+        db.root_hash(None).unwrap().expect("should get root hash"),
+        root_hash
+    );
+    assert!(db
+        .get([TEST_LEAF].as_ref(), b"another_key", None)
+        .unwrap()
+        .is_err());
 
-    assert_eq!(hash, db.root_hash(None).unwrap().unwrap());
-    assert_eq!(result_set.len(), 0);
+    let missing_dir = TempDir::new().unwrap();
+    assert!(GroveDb::open_read_only(missing_dir.path().join("missing")).is_err());
 }
 
 #[test]
-fn test_proof_for_non_existent_data() {
-    let temp_db = make_test_grovedb();
-
-    let mut query = Query::new();
-    query.insert_key(b"key1".to_vec());
-
-    // path to empty subtree
-    let path_query = PathQuery::new_unsized(vec![TEST_LEAF.to_vec()], query);
-
-    let proof = temp_db.prove_query(&path_query).unwrap().unwrap();
-    let (hash, result_set) =
-        GroveDb::verify_query_raw(proof.as_slice(), &path_query).expect("should execute proof");
-
-    assert_eq!(hash, temp_db.root_hash(None).unwrap().unwrap());
-    assert_eq!(result_set.len(), 0);
-}
-
-#[test]
-fn test_path_query_proofs_without_subquery_with_reference() {
-    // Tree Structure
-    // root
-    //     test_leaf
-    //         innertree
-    //             k1,v1
-    //             k2,v2
-    //             k3,v3
-    //     another_test_leaf
-    //         innertree2
-    //             k3,v3
-    //             k4, reference to k1 in innertree
-    //             k5, reference to k4 in innertree3
-    //         innertree3
-    //             k4,v4
-
-    // Insert elements into grovedb instance
-    let temp_db = make_test_grovedb();
-    // Insert level 1 nodes
-    temp_db
-        .insert(
+fn test_open_read_only_handles_at_once() {
+    let tmp_dir = TempDir::new().unwrap();
+    {
+        let mut db = GroveDb::open(tmp_dir.path()).unwrap();
+        add_test_leaves(&mut db);
+        db.insert(
             [TEST_LEAF].as_ref(),
-            b"innertree",
-            Element::empty_tree(),
+            b"key",
+            Element::new_item(b"value".to_vec()),
             None,
             None,
         )
         .unwrap()
-        .expect("successful subtree insert");
-    temp_db
-        .insert(
-            [ANOTHER_TEST_LEAF].as_ref(),
-            b"innertree2",
-            Element::empty_tree(),
+        .expect("successful item insert");
+    }
+
+    let first = GroveDb::open_read_only(tmp_dir.path()).expect("should open read only");
+    let second = GroveDb::open_read_only(tmp_dir.path()).expect("should open read only");
+    let root_hash = first
+        .root_hash(None)
+        .unwrap()
+        .expect("should get root hash");
+    assert_eq!(
+        second
+            .root_hash(None)
+            .unwrap()
+            .expect("should get root hash"),
+        root_hash
+    );
+
+    // Read only handles don't lock the database, nor see writes made after
+    // they were opened
+    {
+        let db = GroveDb::open(tmp_dir.path()).expect("should open next to read only handles");
+        db.insert(
+            [TEST_LEAF].as_ref(),
+            b"another_key",
+            Element::new_item(b"value".to_vec()),
             None,
             None,
         )
         .unwrap()
-        .expect("successful subtree insert");
-    temp_db
-        .insert(
-            [ANOTHER_TEST_LEAF].as_ref(),
-            b"innertree3",
-            Element::empty_tree(),
+        .expect("successful item insert");
+    }
+    for db in [&first, &second] {
+        assert_eq!(
+            db.get([TEST_LEAF].as_ref(), b"key", None)
+                .unwrap()
+                .expect("should read an item"),
+            Element::new_item(b"value".to_vec())
+        );
+        assert_eq!(
+            db.root_hash(None).unwrap().expect("should get root hash"),
+            root_hash
+        );
+    }
+}
+
+#[test]
+fn test_insert_rejects_invalid_reference_paths() {
+    let db = make_test_grovedb();
+    let empty_path = ReferencePathType::AbsolutePathReference(vec![]);
+    assert!(matches!(
+        Element::try_new_reference(empty_path.clone(), None, None),
+        Err(Error::InvalidPath(_))
+    ));
+    assert!(matches!(
+        db.insert(
+            [TEST_LEAF].as_ref(),
+            b"empty",
+            Element::new_reference(empty_path.clone()),
             None,
             None,
         )
-        .unwrap()
-        .expect("successful subtree insert");
-    // Insert level 2 nodes
-    temp_db
-        .insert(
-            [TEST_LEAF, b"innertree"].as_ref(),
-            b"key1",
-            Element::new_item(b"value1".to_vec()),
+        .unwrap(),
+        Err(Error::InvalidPath(_))
+    ));
+    assert!(matches!(
+        db.apply_batch(
+            vec![GroveDbOp::insert_op(
+                vec![TEST_LEAF.to_vec()],
+                b"empty".to_vec(),
+                Element::new_reference(empty_path),
+            )],
             None,
             None,
         )
-        .unwrap()
-        .expect("successful subtree insert");
-    temp_db
-        .insert(
-            [TEST_LEAF, b"innertree"].as_ref(),
-            b"key2",
-            Element::new_item(b"value2".to_vec()),
+        .unwrap(),
+        Err(Error::InvalidPath(_))
+    ));
+
+    // Going up more levels than the reference is stored at
+    assert!(matches!(
+        db.insert(
+            [TEST_LEAF].as_ref(),
+            b"too_high",
+            Element::new_reference(ReferencePathType::UpstreamRootHeightReference(
+                2,
+                vec![b"key".to_vec()],
+            )),
             None,
             None,
         )
-        .unwrap()
-        .expect("successful subtree insert");
-    temp_db
-        .insert(
-            [TEST_LEAF, b"innertree"].as_ref(),
-            b"key3",
-            Element::new_item(b"value3".to_vec()),
+        .unwrap(),
+        Err(Error::InvalidPath(_))
+    ));
+}
+
+#[test]
+fn test_interrupted_commit_keeps_previous_state() {
+    let tmp_dir = TempDir::new().unwrap();
+    let root_hash = {
+        let mut db = GroveDb::open(tmp_dir.path()).expect("should create a database");
+        add_test_leaves(&mut db);
+        assert!(!db.had_interrupted_commit());
+        db.insert(
+            [TEST_LEAF].as_ref(),
+            b"key",
+            Element::new_item(b"value".to_vec()),
             None,
             None,
         )
         .unwrap()
-        .expect("successful subtree insert");
-    temp_db
+        .expect("successful item insert");
+        let root_hash = db.root_hash(None).unwrap().expect("should get root hash");
+
+        // RocksDB refuses synced writes without the WAL, so the batch write fails
+        // after the commit was started
+        db.set_write_options(WriteOptions {
+            sync: true,
+            disable_wal: true,
+        });
+        assert!(db
+            .insert(
+                [TEST_LEAF].as_ref(),
+                b"key2",
+                Element::new_item(b"value2".to_vec()),
+                None,
+                None,
+            )
+            .unwrap()
+            .is_err());
+        assert_eq!(
+            db.root_hash(None).unwrap().expect("should get root hash"),
+            root_hash
+        );
+        root_hash
+    };
+
+    {
+        let db = GroveDb::open_read_only(tmp_dir.path()).expect("should open read only");
+        assert!(db.had_interrupted_commit());
+    }
+
+    {
+        let db = GroveDb::open(tmp_dir.path()).expect("should reopen");
+        assert!(db.had_interrupted_commit());
+        assert_eq!(
+            db.root_hash(None).unwrap().expect("should get root hash"),
+            root_hash
+        );
+        assert!(matches!(
+            db.get([TEST_LEAF].as_ref(), b"key2", None).unwrap(),
+            Err(Error::PathKeyNotFound(_))
+        ));
+    }
+
+    // The marker is cleared once reported, and completed commits leave none
+    let db = GroveDb::open(tmp_dir.path()).expect("should reopen");
+    assert!(!db.had_interrupted_commit());
+}
+
+#[test]
+fn test_reference_value_affects_state() {
+    let db_one = make_test_grovedb();
+    db_one
         .insert(
-            [ANOTHER_TEST_LEAF, b"innertree2"].as_ref(),
-            b"key3",
-            Element::new_item(b"value3".to_vec()),
+            [TEST_LEAF].as_ref(),
+            b"key1",
+            Element::new_item(vec![0]),
             None,
             None,
         )
         .unwrap()
-        .expect("successful subtree insert");
-    temp_db
+        .expect("should insert item");
+    db_one
         .insert(
-            [ANOTHER_TEST_LEAF, b"innertree2"].as_ref(),
-            b"key4",
+            [ANOTHER_TEST_LEAF].as_ref(),
+            b"ref",
             Element::new_reference(ReferencePathType::AbsolutePathReference(vec![
                 TEST_LEAF.to_vec(),
-                b"innertree".to_vec(),
                 b"key1".to_vec(),
             ])),
             None,
             None,
         )
         .unwrap()
-        .expect("successful subtree insert");
-    temp_db
+        .expect("should insert item");
+
+    let db_two = make_test_grovedb();
+    db_two
         .insert(
-            [ANOTHER_TEST_LEAF, b"innertree3"].as_ref(),
-            b"key4",
-            Element::new_item(b"value4".to_vec()),
+            [TEST_LEAF].as_ref(),
+            b"key1",
+            Element::new_item(vec![0]),
             None,
             None,
         )
         .unwrap()
-        .expect("successful subtree insert");
-    temp_db
+        .expect("should insert item");
+    db_two
         .insert(
-            [ANOTHER_TEST_LEAF, b"innertree2"].as_ref(),
-            b"key5",
-            Element::new_reference(ReferencePathType::AbsolutePathReference(vec![
-                ANOTHER_TEST_LEAF.to_vec(),
-                b"innertree3".to_vec(),
-                b"key4".to_vec(),
-            ])),
+            [ANOTHER_TEST_LEAF].as_ref(),
+            b"ref",
+            Element::new_reference(ReferencePathType::UpstreamRootHeightReference(
+                0,
+                vec![TEST_LEAF.to_vec(), b"key1".to_vec()],
+            )),
             None,
             None,
         )
         .unwrap()
-        .expect("successful subtree insert");
-
-    // Single key query
-    let mut query = Query::new();
-    query.insert_range_from(b"key4".to_vec()..);
-
-    let path_query = PathQuery::new_unsized(
-        vec![ANOTHER_TEST_LEAF.to_vec(), b"innertree2".to_vec()],
-        query,
-    );
-
-    let proof = temp_db.prove_query(&path_query).unwrap().unwrap();
-    assert_eq!(
-        hex::encode(&proof),
-        "010285010198ebd6dc7e1c82951c41fcfa6487711cac6a399ebb01bb979cb\
-        e4a51e0b2f08d06046b6579340009000676616c75653100bf2f052b01c2b\
-        b83ff3a40504d42b5b9141c582a3e0c98679189b33a24478a6f1006046b6\
-        579350009000676616c75653400f084ffdbc429a89c9b6620e7224d73c2e\
-        e505eb7e6fb5eb574e1a8dc8b0d0884110158040a696e6e6572747265653\
-        200080201046b657934008ba21f835b2ff60f16b7fccfbda107bec3da0c4\
-        709357d40de223d769547ec21013a090155ea7d14038c7062d94930798f8\
-        85a19d6ebff8a87489a1debf665604711015e02cfb7d035b8f4a3631be46\
-        c597510a16770c15c74331b3dc8dcb577a206e49675040a746573745f6c6\
-        5616632000e02010a696e6e657274726565320049870f2813c0c3c5c105a\
-        988c0ef1372178245152fa9a43b209a6b6d95589bdc11"
-    );
-    let (hash, result_set) =
-        GroveDb::verify_query_raw(proof.as_slice(), &path_query).expect("should execute proof");
-
-    assert_eq!(hash, temp_db.root_hash(None).unwrap().unwrap());
-    let r1 = Element::new_item(b"value1".to_vec()).serialize().unwrap();
-    let r2 = Element::new_item(b"value4".to_vec()).serialize().unwrap();
+        .expect("should insert item");
 
-    compare_result_tuples(
-        result_set,
-        vec![(b"key4".to_vec(), r1), (b"key5".to_vec(), r2)],
+    assert_ne!(
+        db_one
+            .root_hash(None)
+            .unwrap()
+            .expect("should return root hash"),
+        db_two
+            .root_hash(None)
+            .unwrap()
+            .expect("should return toor hash")
     );
 }
 
 #[test]
-fn test_path_query_proofs_without_subquery() {
-    // Tree Structure
-    // root
-    //     test_leaf
-    //         innertree
-    //             k1,v1
-    //             k2,v2
-    //             k3,v3
-    //     another_test_leaf
-    //         innertree2
-    //             k3,v3
-    //         innertree3
-    //             k4,v4
-
-    // Insert elements into grovedb instance
-    let temp_db = make_test_grovedb();
-    // Insert level 1 nodes
-    temp_db
-        .insert(
+fn test_tree_structure_is_persistent() {
+    let tmp_dir = TempDir::new().unwrap();
+    let element = Element::new_item(b"ayy".to_vec());
+    // Create a scoped GroveDB
+    let prev_root_hash = {
+        let mut db = GroveDb::open(tmp_dir.path()).unwrap();
+        add_test_leaves(&mut db);
+
+        // Insert some nested subtrees
+        db.insert(
             [TEST_LEAF].as_ref(),
-            b"innertree",
-            Element::empty_tree(),
-            None,
-            None,
-        )
-        .unwrap()
-        .expect("successful subtree insert");
-    temp_db
-        .insert(
-            [ANOTHER_TEST_LEAF].as_ref(),
-            b"innertree2",
-            Element::empty_tree(),
-            None,
-            None,
-        )
-        .unwrap()
-        .expect("successful subtree insert");
-    temp_db
-        .insert(
-            [ANOTHER_TEST_LEAF].as_ref(),
-            b"innertree3",
-            Element::empty_tree(),
-            None,
-            None,
-        )
-        .unwrap()
-        .expect("successful subtree insert");
-    // Insert level 2 nodes
-    temp_db
-        .insert(
-            [TEST_LEAF, b"innertree"].as_ref(),
             b"key1",
-            Element::new_item(b"value1".to_vec()),
+            Element::empty_tree(),
             None,
             None,
         )
         .unwrap()
-        .expect("successful subtree insert");
-    temp_db
-        .insert(
-            [TEST_LEAF, b"innertree"].as_ref(),
+        .expect("successful subtree 1 insert");
+        db.insert(
+            [TEST_LEAF, b"key1"].as_ref(),
             b"key2",
-            Element::new_item(b"value2".to_vec()),
+            Element::empty_tree(),
             None,
             None,
         )
         .unwrap()
-        .expect("successful subtree insert");
-    temp_db
-        .insert(
-            [TEST_LEAF, b"innertree"].as_ref(),
+        .expect("successful subtree 2 insert");
+        // Insert an element into subtree
+        db.insert(
+            [TEST_LEAF, b"key1", b"key2"].as_ref(),
             b"key3",
-            Element::new_item(b"value3".to_vec()),
+            element.clone(),
             None,
             None,
         )
         .unwrap()
-        .expect("successful subtree insert");
-    temp_db
-        .insert(
-            [ANOTHER_TEST_LEAF, b"innertree2"].as_ref(),
-            b"key3",
-            Element::new_item(b"value3".to_vec()),
-            None,
-            None,
-        )
+        .expect("successful value insert");
+        assert_eq!(
+            db.get([TEST_LEAF, b"key1", b"key2"].as_ref(), b"key3", None)
+                .unwrap()
+                .expect("successful get 1"),
+            element
+        );
+        db.root_hash(None).unwrap().unwrap()
+    };
+    // Open a persisted GroveDB
+    let db = GroveDb::open(tmp_dir).unwrap();
+    assert_eq!(
+        db.get([TEST_LEAF, b"key1", b"key2"].as_ref(), b"key3", None)
+            .unwrap()
+            .expect("successful get 2"),
+        element
+    );
+    assert!(db
+        .get([TEST_LEAF, b"key1", b"key2"].as_ref(), b"key4", None)
         .unwrap()
-        .expect("successful subtree insert");
-    temp_db
-        .insert(
-            [ANOTHER_TEST_LEAF, b"innertree3"].as_ref(),
-            b"key4",
-            Element::new_item(b"value4".to_vec()),
-            None,
-            None,
-        )
+        .is_err());
+    assert_eq!(prev_root_hash, db.root_hash(None).unwrap().unwrap());
+}
+
+#[test]
+fn test_root_tree_leaves_are_noted() {
+    let db = make_test_grovedb();
+    db.check_subtree_exists_path_not_found([TEST_LEAF].as_ref().into(), None)
         .unwrap()
-        .expect("successful subtree insert");
+        .expect("should exist");
+    db.check_subtree_exists_path_not_found([ANOTHER_TEST_LEAF].as_ref().into(), None)
+        .unwrap()
+        .expect("should exist");
+}
 
-    // Single key query
-    let mut query = Query::new();
-    query.insert_key(b"key1".to_vec());
+#[test]
+fn test_proof_for_invalid_path_root_key() {
+    let db = make_test_grovedb();
 
-    let path_query = PathQuery::new_unsized(vec![TEST_LEAF.to_vec(), b"innertree".to_vec()], query);
+    let query = Query::new();
+    let path_query = PathQuery::new_unsized(vec![b"invalid_path_key".to_vec()], query);
 
-    let proof = temp_db.prove_query(&path_query).unwrap().unwrap();
-    assert_eq!(
-        hex::encode(proof.as_slice()),
-        "01025503046b6579310009000676616c7565310002018655e18e4555b0b65\
-        bbcec64c749db6b9ad84231969fb4fbe769a3093d10f2100198ebd6dc7e1\
-        c82951c41fcfa6487711cac6a399ebb01bb979cbe4a51e0b2f08d1101350\
-        409696e6e65727472656500080201046b657932004910536da659a3dbdbc\
-        f68c4a6630e72de4ba20cfc60b08b3dd45b4225a599b6015c04097465737\
-        45f6c656166000d020109696e6e65727472656500fafa16d06e8d8696dae\
-        443731ae2a4eae521e4a9a79c331c8a7e22e34c0f1a6e01b55f830550604\
-        719833d54ce2bf139aff4bb699fa4111b9741633554318792c511"
-    );
+    let proof = db.prove_query(&path_query).unwrap().unwrap();
     let (hash, result_set) =
         GroveDb::verify_query_raw(proof.as_slice(), &path_query).expect("should execute proof");
 
-    assert_eq!(hash, temp_db.root_hash(None).unwrap().unwrap());
-    let r1 = Element::new_item(b"value1".to_vec()).serialize().unwrap();
-    compare_result_tuples(result_set, vec![(b"key1".to_vec(), r1)]);
+    assert_eq!(hash, db.root_hash(None).unwrap().unwrap());
+    assert_eq!(result_set.len(), 0);
+}
 
-    // Range query + limit
-    let mut query = Query::new();
-    query.insert_range_after(b"key1".to_vec()..);
-    let path_query = PathQuery::new(
-        vec![TEST_LEAF.to_vec(), b"innertree".to_vec()],
-        SizedQuery::new(query, Some(1), None),
-    );
+#[test]
+fn test_proof_for_invalid_path() {
+    let db = make_deep_tree();
 
-    let proof = temp_db.prove_query(&path_query).unwrap().unwrap();
+    let query = Query::new();
+    let path_query =
+        PathQuery::new_unsized(vec![b"deep_leaf".to_vec(), b"invalid_key".to_vec()], query);
+
+    let proof = db.prove_query(&path_query).unwrap().unwrap();
     let (hash, result_set) =
         GroveDb::verify_query_raw(proof.as_slice(), &path_query).expect("should execute proof");
 
-    assert_eq!(hash, temp_db.root_hash(None).unwrap().unwrap());
-    let r1 = Element::new_item(b"value2".to_vec()).serialize().unwrap();
-    compare_result_tuples(result_set, vec![(b"key2".to_vec(), r1)]);
+    assert_eq!(hash, db.root_hash(None).unwrap().unwrap());
+    assert_eq!(result_set.len(), 0);
 
-    // Range query + offset + limit
-    let mut query = Query::new();
-    query.insert_range_after(b"key1".to_vec()..);
-    let path_query = PathQuery::new(
-        vec![TEST_LEAF.to_vec(), b"innertree".to_vec()],
-        SizedQuery::new(query, Some(1), Some(1)),
+    let query = Query::new();
+    let path_query = PathQuery::new_unsized(
+        vec![
+            b"deep_leaf".to_vec(),
+            b"deep_node_1".to_vec(),
+            b"invalid_key".to_vec(),
+        ],
+        query,
     );
 
-    let proof = temp_db.prove_query(&path_query).unwrap().unwrap();
+    let proof = db.prove_query(&path_query).unwrap().unwrap();
     let (hash, result_set) =
         GroveDb::verify_query_raw(proof.as_slice(), &path_query).expect("should execute proof");
 
-    assert_eq!(hash, temp_db.root_hash(None).unwrap().unwrap());
-    let r1 = Element::new_item(b"value3".to_vec()).serialize().unwrap();
-    compare_result_tuples(result_set, vec![(b"key3".to_vec(), r1)]);
+    assert_eq!(hash, db.root_hash(None).unwrap().unwrap());
+    assert_eq!(result_set.len(), 0);
 
-    // Range query + direction + limit
-    let mut query = Query::new_with_direction(false);
-    query.insert_all();
-    let path_query = PathQuery::new(
-        vec![TEST_LEAF.to_vec(), b"innertree".to_vec()],
-        SizedQuery::new(query, Some(2), None),
+    let query = Query::new();
+    let path_query = PathQuery::new_unsized(
+        vec![
+            b"deep_leaf".to_vec(),
+            b"deep_node_1".to_vec(),
+            b"deeper_1".to_vec(),
+            b"invalid_key".to_vec(),
+        ],
+        query,
     );
 
-    let proof = temp_db.prove_query(&path_query).unwrap().unwrap();
+    let proof = db.prove_query(&path_query).unwrap().unwrap();
     let (hash, result_set) =
         GroveDb::verify_query_raw(proof.as_slice(), &path_query).expect("should execute proof");
 
-    assert_eq!(hash, temp_db.root_hash(None).unwrap().unwrap());
-    let r1 = Element::new_item(b"value3".to_vec()).serialize().unwrap();
-    let r2 = Element::new_item(b"value2".to_vec()).serialize().unwrap();
-    compare_result_tuples(
-        result_set,
-        vec![(b"key3".to_vec(), r1), (b"key2".to_vec(), r2)],
+    assert_eq!(hash, db.root_hash(None).unwrap().unwrap());
+    assert_eq!(result_set.len(), 0);
+
+    let query = Query::new();
+    let path_query = PathQuery::new_unsized(
+        vec![
+            b"deep_leaf".to_vec(),
+            b"early_invalid_key".to_vec(),
+            b"deeper_1".to_vec(),
+            b"invalid_key".to_vec(),
+        ],
+        query,
     );
+
+    let proof = db.prove_query(&path_query).unwrap().unwrap();
+    let (hash, result_set) =
+        GroveDb::verify_query_raw(proof.as_slice(), &path_query).expect("should execute proof");
+
+    assert_eq!(hash, db.root_hash(None).unwrap().unwrap());
+    assert_eq!(result_set.len(), 0);
 }
 
 #[test]
-fn test_path_query_proofs_with_default_subquery() {
-    let temp_db = make_deep_tree();
+fn test_proof_for_non_existent_data() {
+    let temp_db = make_test_grovedb();
 
     let mut query = Query::new();
-    query.insert_all();
-
-    let mut subq = Query::new();
-    subq.insert_all();
-    query.set_subquery(subq);
+    query.insert_key(b"key1".to_vec());
 
+    // path to empty subtree
     let path_query = PathQuery::new_unsized(vec![TEST_LEAF.to_vec()], query);
 
     let proof = temp_db.prove_query(&path_query).unwrap().unwrap();
@@ -1487,825 +1935,2120 @@ fn test_path_query_proofs_with_default_subquery() {
         GroveDb::verify_query_raw(proof.as_slice(), &path_query).expect("should execute proof");
 
     assert_eq!(hash, temp_db.root_hash(None).unwrap().unwrap());
-    assert_eq!(result_set.len(), 5);
+    assert_eq!(result_set.len(), 0);
+}
 
-    let keys = [
-        b"key1".to_vec(),
-        b"key2".to_vec(),
-        b"key3".to_vec(),
-        b"key4".to_vec(),
-        b"key5".to_vec(),
-    ];
-    let values = [
-        b"value1".to_vec(),
-        b"value2".to_vec(),
-        b"value3".to_vec(),
-        b"value4".to_vec(),
-        b"value5".to_vec(),
-    ];
-    let elements = values.map(|x| Element::new_item(x).serialize().unwrap());
-    let expected_result_set: Vec<(Vec<u8>, Vec<u8>)> = keys.into_iter().zip(elements).collect();
-    compare_result_tuples(result_set, expected_result_set);
-
-    let mut query = Query::new();
-    query.insert_range_after(b"innertree".to_vec()..);
-
-    let mut subq = Query::new();
-    subq.insert_all();
-    query.set_subquery(subq);
-
-    let path_query = PathQuery::new_unsized(vec![TEST_LEAF.to_vec()], query);
-
-    let proof = temp_db.prove_query(&path_query).unwrap().unwrap();
-    let (hash, result_set) =
-        GroveDb::verify_query_raw(proof.as_slice(), &path_query).expect("should execute proof");
-
-    assert_eq!(hash, temp_db.root_hash(None).unwrap().unwrap());
-    assert_eq!(result_set.len(), 2);
-
-    let keys = [b"key4".to_vec(), b"key5".to_vec()];
-    let values = [b"value4".to_vec(), b"value5".to_vec()];
-    let elements = values.map(|x| Element::new_item(x).serialize().unwrap());
-    let expected_result_set: Vec<(Vec<u8>, Vec<u8>)> = keys.into_iter().zip(elements).collect();
-    compare_result_tuples(result_set, expected_result_set);
+#[test]
+fn test_proof_of_empty_range_between_existing_keys() {
+    let db = make_test_grovedb();
+    for key in [b"a", b"c", b"d"] {
+        db.insert(
+            [TEST_LEAF].as_ref(),
+            key,
+            Element::new_item(key.to_vec()),
+            None,
+            None,
+        )
+        .unwrap()
+        .expect("successful insert");
+    }
 
-    // range subquery
     let mut query = Query::new();
-    query.insert_all();
-
-    let mut subq = Query::new();
-    subq.insert_range_after_to_inclusive(b"key1".to_vec()..=b"key4".to_vec());
-    query.set_subquery(subq);
-
+    query.insert_range(b"aa".to_vec()..b"c".to_vec());
     let path_query = PathQuery::new_unsized(vec![TEST_LEAF.to_vec()], query);
 
-    let proof = temp_db.prove_query(&path_query).unwrap().unwrap();
-    let (hash, result_set) = GroveDb::verify_query_raw(proof.as_slice(), &path_query).expect(
-        "should
-    execute proof",
-    );
-
-    assert_eq!(hash, temp_db.root_hash(None).unwrap().unwrap());
-    assert_eq!(result_set.len(), 3);
-
-    let keys = [b"key2".to_vec(), b"key3".to_vec(), b"key4".to_vec()];
-    let values = [b"value2".to_vec(), b"value3".to_vec(), b"value4".to_vec()];
-    let elements = values.map(|x| Element::new_item(x).serialize().unwrap());
-    let expected_result_set: Vec<(Vec<u8>, Vec<u8>)> = keys.into_iter().zip(elements).collect();
-    compare_result_tuples(result_set, expected_result_set);
+    let proof = db.prove_query(&path_query).unwrap().unwrap();
+    let (hash, result_set) = GroveDb::verify_query_with_path_presence(&proof, &path_query)
+        .expect("should execute proof");
+    assert_eq!(hash, db.root_hash(None).unwrap().unwrap());
+    assert_eq!(result_set, Some(vec![]));
 
-    // deep tree test
+    // A proof which does not cover the whole range can't pass for an empty one
     let mut query = Query::new();
-    query.insert_all();
-
-    let mut subq = Query::new();
-    subq.insert_all();
-
-    let mut sub_subquery = Query::new();
-    sub_subquery.insert_all();
-
-    subq.set_subquery(sub_subquery);
-    query.set_subquery(subq);
-
-    let path_query = PathQuery::new_unsized(vec![DEEP_LEAF.to_vec()], query);
-
-    let proof = temp_db.prove_query(&path_query).unwrap().unwrap();
-    let (hash, result_set) =
-        GroveDb::verify_query_raw(proof.as_slice(), &path_query).expect("should execute proof");
-
-    assert_eq!(hash, temp_db.root_hash(None).unwrap().unwrap());
-    assert_eq!(result_set.len(), 11);
-
-    let keys = [
-        b"key1".to_vec(),
-        b"key2".to_vec(),
-        b"key3".to_vec(),
-        b"key4".to_vec(),
-        b"key5".to_vec(),
-        b"key6".to_vec(),
-        b"key7".to_vec(),
-        b"key8".to_vec(),
-        b"key9".to_vec(),
-        b"key10".to_vec(),
-        b"key11".to_vec(),
-    ];
-    let values = [
-        b"value1".to_vec(),
-        b"value2".to_vec(),
-        b"value3".to_vec(),
-        b"value4".to_vec(),
-        b"value5".to_vec(),
-        b"value6".to_vec(),
-        b"value7".to_vec(),
-        b"value8".to_vec(),
-        b"value9".to_vec(),
-        b"value10".to_vec(),
-        b"value11".to_vec(),
-    ];
-    let elements = values.map(|x| Element::new_item(x).serialize().unwrap());
-    let expected_result_set: Vec<(Vec<u8>, Vec<u8>)> = keys.into_iter().zip(elements).collect();
-    compare_result_tuples(result_set, expected_result_set);
+    query.insert_range(b"aa".to_vec()..b"d".to_vec());
+    let wider_path_query = PathQuery::new_unsized(vec![TEST_LEAF.to_vec()], query);
+    assert!(GroveDb::verify_query_with_path_presence(&proof, &wider_path_query).is_err());
 }
 
 #[test]
-fn test_path_query_proofs_with_subquery_path() {
-    let temp_db = make_deep_tree();
+fn test_proof_of_empty_subtree_and_absent_path() {
+    let db = make_test_grovedb();
+    db.insert(
+        [TEST_LEAF].as_ref(),
+        b"innertree",
+        Element::empty_tree(),
+        None,
+        None,
+    )
+    .unwrap()
+    .expect("successful subtree insert");
 
     let mut query = Query::new();
     query.insert_all();
+    let path_query = PathQuery::new_unsized(
+        vec![TEST_LEAF.to_vec(), b"innertree".to_vec()],
+        query.clone(),
+    );
 
-    let mut subq = Query::new();
-    subq.insert_all();
+    let proof = db.prove_query(&path_query).unwrap().unwrap();
+    let (hash, result_set) = GroveDb::verify_query_with_path_presence(&proof, &path_query)
+        .expect("should execute proof");
+    assert_eq!(hash, db.root_hash(None).unwrap().unwrap());
+    assert_eq!(result_set, Some(vec![]));
 
-    query.set_subquery_key(b"deeper_1".to_vec());
-    query.set_subquery(subq);
+    let path_query =
+        PathQuery::new_unsized(vec![TEST_LEAF.to_vec(), b"missingtree".to_vec()], query);
 
-    let path_query = PathQuery::new_unsized(vec![DEEP_LEAF.to_vec()], query);
+    let proof = db.prove_query(&path_query).unwrap().unwrap();
+    let (hash, result_set) = GroveDb::verify_query_with_path_presence(&proof, &path_query)
+        .expect("should execute proof");
+    assert_eq!(hash, db.root_hash(None).unwrap().unwrap());
+    assert_eq!(result_set, None);
+}
 
-    let proof = temp_db.prove_query(&path_query).unwrap().unwrap();
-    let (hash, result_set) =
-        GroveDb::verify_query_raw(proof.as_slice(), &path_query).expect("should execute proof");
+#[test]
+fn test_prove_key() {
+    let db = make_test_grovedb();
+    db.insert(
+        [TEST_LEAF].as_ref(),
+        b"key1",
+        Element::new_item(b"value1".to_vec()),
+        None,
+        None,
+    )
+    .unwrap()
+    .expect("successful insert");
 
-    assert_eq!(hash, temp_db.root_hash(None).unwrap().unwrap());
-    assert_eq!(result_set.len(), 3);
+    let proof = db
+        .prove_key([TEST_LEAF].as_ref(), b"key1")
+        .unwrap()
+        .unwrap();
+    let (hash, element) = GroveDb::verify_key(proof.as_slice(), [TEST_LEAF].as_ref(), b"key1")
+        .expect("should execute proof");
+    assert_eq!(hash, db.root_hash(None).unwrap().unwrap());
+    assert_eq!(element, Some(Element::new_item(b"value1".to_vec())));
 
-    let keys = [b"key1".to_vec(), b"key2".to_vec(), b"key3".to_vec()];
-    let values = [b"value1".to_vec(), b"value2".to_vec(), b"value3".to_vec()];
-    let elements = values.map(|x| Element::new_item(x).serialize().unwrap());
-    let expected_result_set: Vec<(Vec<u8>, Vec<u8>)> = keys.into_iter().zip(elements).collect();
-    compare_result_tuples(result_set, expected_result_set);
+    // Absence of a never inserted key
+    let proof = db
+        .prove_key([TEST_LEAF].as_ref(), b"key2")
+        .unwrap()
+        .unwrap();
+    let (hash, element) = GroveDb::verify_key(proof.as_slice(), [TEST_LEAF].as_ref(), b"key2")
+        .expect("should execute proof");
+    assert_eq!(hash, db.root_hash(None).unwrap().unwrap());
+    assert_eq!(element, None);
+}
 
-    // test subquery path with valid n > 1 valid translation
-    let mut query = Query::new();
-    query.insert_all();
+#[test]
+fn test_get_with_proof() {
+    let db = make_test_grovedb();
+    db.insert(
+        [TEST_LEAF].as_ref(),
+        b"key1",
+        Element::new_item(b"value1".to_vec()),
+        None,
+        None,
+    )
+    .unwrap()
+    .expect("successful insert");
+    db.insert(
+        [ANOTHER_TEST_LEAF].as_ref(),
+        b"reference",
+        Element::new_reference(ReferencePathType::AbsolutePathReference(vec![
+            TEST_LEAF.to_vec(),
+            b"key1".to_vec(),
+        ])),
+        None,
+        None,
+    )
+    .unwrap()
+    .expect("successful insert");
+    let root_hash = db.root_hash(None).unwrap().unwrap();
+
+    for (path, key) in [
+        (TEST_LEAF, b"key1".as_ref()),
+        (ANOTHER_TEST_LEAF, b"reference"),
+    ] {
+        let (element, proof) = db
+            .get_with_proof([path].as_ref(), key, None)
+            .unwrap()
+            .expect("successful get with proof");
+        assert_eq!(element, Element::new_item(b"value1".to_vec()));
+        let (hash, proved_element) = GroveDb::verify_key(proof.as_slice(), [path].as_ref(), key)
+            .expect("should execute proof");
+        assert_eq!(hash, root_hash);
+        assert_eq!(proved_element, Some(element));
+    }
 
-    let mut subq = Query::new();
-    subq.insert_all();
+    assert!(db
+        .get_with_proof([TEST_LEAF].as_ref(), b"key2", None)
+        .unwrap()
+        .is_err());
+}
 
-    query.set_subquery_path(vec![b"deep_node_1".to_vec(), b"deeper_1".to_vec()]);
-    query.set_subquery(subq);
+#[test]
+fn test_single_key_path_query_proof() {
+    let db = make_deep_tree();
 
-    let path_query = PathQuery::new_unsized(vec![], query);
-    let proof = temp_db.prove_query(&path_query).unwrap().unwrap();
+    let path_query = PathQuery::new_single_key(
+        vec![
+            DEEP_LEAF.to_vec(),
+            b"deep_node_1".to_vec(),
+            b"deeper_2".to_vec(),
+        ],
+        b"key5".to_vec(),
+    );
+    let proof = db.prove_query(&path_query).unwrap().unwrap();
     let (hash, result_set) =
-        GroveDb::verify_query_raw(proof.as_slice(), &path_query).expect("should execute proof");
-    assert_eq!(hash, temp_db.root_hash(None).unwrap().unwrap());
-    assert_eq!(result_set.len(), 3);
-
-    let keys = [b"key1".to_vec(), b"key2".to_vec(), b"key3".to_vec()];
-    let values = [b"value1".to_vec(), b"value2".to_vec(), b"value3".to_vec()];
-    let elements = values.map(|x| Element::new_item(x).serialize().unwrap());
-    let expected_result_set: Vec<(Vec<u8>, Vec<u8>)> = keys.into_iter().zip(elements).collect();
-    compare_result_tuples(result_set, expected_result_set);
-
-    // test subquery path with empty subquery path
-    let mut query = Query::new();
-    query.insert_all();
-
-    let mut subq = Query::new();
-    subq.insert_all();
-
-    query.set_subquery_path(vec![]);
-    query.set_subquery(subq);
+        GroveDb::verify_query(proof.as_slice(), &path_query).expect("should execute proof");
+    assert_eq!(hash, db.root_hash(None).unwrap().unwrap());
+    assert_eq!(result_set.len(), 1);
+    let (path, key, element) = &result_set[0];
+    assert_eq!(path, &path_query.path);
+    assert_eq!(key, b"key5");
+    assert_eq!(element, &Some(Element::new_item(b"value5".to_vec())));
+}
 
-    let path_query =
-        PathQuery::new_unsized(vec![b"deep_leaf".to_vec(), b"deep_node_1".to_vec()], query);
-    let proof = temp_db.prove_query(&path_query).unwrap().unwrap();
-    let (hash, result_set) =
-        GroveDb::verify_query_raw(proof.as_slice(), &path_query).expect("should execute proof");
-    assert_eq!(hash, temp_db.root_hash(None).unwrap().unwrap());
-    assert_eq!(result_set.len(), 6);
+#[test]
+fn test_path_query_proofs_without_subquery_with_reference() {
+    // Tree Structure
+    // root
+    //     test_leaf
+    //         innertree
+    //             k1,v1
+    //             k2,v2
+    //             k3,v3
+    //     another_test_leaf
+    //         innertree2
+    //             k3,v3
+    //             k4, reference to k1 in innertree
+    //             k5, reference to k4 in innertree3
+    //         innertree3
+    //             k4,v4
 
-    let keys = [
-        b"key1".to_vec(),
-        b"key2".to_vec(),
-        b"key3".to_vec(),
-        b"key4".to_vec(),
-        b"key5".to_vec(),
-        b"key6".to_vec(),
-    ];
+    // Insert elements into grovedb instance
+    let temp_db = make_test_grovedb();
+    // Insert level 1 nodes
+    temp_db
+        .insert(
+            [TEST_LEAF].as_ref(),
+            b"innertree",
+            Element::empty_tree(),
+            None,
+            None,
+        )
+        .unwrap()
+        .expect("successful subtree insert");
+    temp_db
+        .insert(
+            [ANOTHER_TEST_LEAF].as_ref(),
+            b"innertree2",
+            Element::empty_tree(),
+            None,
+            None,
+        )
+        .unwrap()
+        .expect("successful subtree insert");
+    temp_db
+        .insert(
+            [ANOTHER_TEST_LEAF].as_ref(),
+            b"innertree3",
+            Element::empty_tree(),
+            None,
+            None,
+        )
+        .unwrap()
+        .expect("successful subtree insert");
+    // Insert level 2 nodes
+    temp_db
+        .insert(
+            [TEST_LEAF, b"innertree"].as_ref(),
+            b"key1",
+            Element::new_item(b"value1".to_vec()),
+            None,
+            None,
+        )
+        .unwrap()
+        .expect("successful subtree insert");
+    temp_db
+        .insert(
+            [TEST_LEAF, b"innertree"].as_ref(),
+            b"key2",
+            Element::new_item(b"value2".to_vec()),
+            None,
+            None,
+        )
+        .unwrap()
+        .expect("successful subtree insert");
+    temp_db
+        .insert(
+            [TEST_LEAF, b"innertree"].as_ref(),
+            b"key3",
+            Element::new_item(b"value3".to_vec()),
+            None,
+            None,
+        )
+        .unwrap()
+        .expect("successful subtree insert");
+    temp_db
+        .insert(
+            [ANOTHER_TEST_LEAF, b"innertree2"].as_ref(),
+            b"key3",
+            Element::new_item(b"value3".to_vec()),
+            None,
+            None,
+        )
+        .unwrap()
+        .expect("successful subtree insert");
+    temp_db
+        .insert(
+            [ANOTHER_TEST_LEAF, b"innertree2"].as_ref(),
+            b"key4",
+            Element::new_reference(ReferencePathType::AbsolutePathReference(vec![
+                TEST_LEAF.to_vec(),
+                b"innertree".to_vec(),
+                b"key1".to_vec(),
+            ])),
+            None,
+            None,
+        )
+        .unwrap()
+        .expect("successful subtree insert");
+    temp_db
+        .insert(
+            [ANOTHER_TEST_LEAF, b"innertree3"].as_ref(),
+            b"key4",
+            Element::new_item(b"value4".to_vec()),
+            None,
+            None,
+        )
+        .unwrap()
+        .expect("successful subtree insert");
+    temp_db
+        .insert(
+            [ANOTHER_TEST_LEAF, b"innertree2"].as_ref(),
+            b"key5",
+            Element::new_reference(ReferencePathType::AbsolutePathReference(vec![
+                ANOTHER_TEST_LEAF.to_vec(),
+                b"innertree3".to_vec(),
+                b"key4".to_vec(),
+            ])),
+            None,
+            None,
+        )
+        .unwrap()
+        .expect("successful subtree insert");
+
+    // Single key query
+    let mut query = Query::new();
+    query.insert_range_from(b"key4".to_vec()..);
+
+    let path_query = PathQuery::new_unsized(
+        vec![ANOTHER_TEST_LEAF.to_vec(), b"innertree2".to_vec()],
+        query,
+    );
+
+    let proof = temp_db.prove_query(&path_query).unwrap().unwrap();
+    assert_eq!(
+        hex::encode(&proof),
+        "010285010198ebd6dc7e1c82951c41fcfa6487711cac6a399ebb01bb979cb\
+        e4a51e0b2f08d06046b6579340009000676616c75653100bf2f052b01c2b\
+        b83ff3a40504d42b5b9141c582a3e0c98679189b33a24478a6f1006046b6\
+        579350009000676616c75653400f084ffdbc429a89c9b6620e7224d73c2e\
+        e505eb7e6fb5eb574e1a8dc8b0d0884110158040a696e6e6572747265653\
+        200080201046b657934008ba21f835b2ff60f16b7fccfbda107bec3da0c4\
+        709357d40de223d769547ec21013a090155ea7d14038c7062d94930798f8\
+        85a19d6ebff8a87489a1debf665604711015e02cfb7d035b8f4a3631be46\
+        c597510a16770c15c74331b3dc8dcb577a206e49675040a746573745f6c6\
+        5616632000e02010a696e6e657274726565320049870f2813c0c3c5c105a\
+        988c0ef1372178245152fa9a43b209a6b6d95589bdc11"
+    );
+    let (hash, result_set) =
+        GroveDb::verify_query_raw(proof.as_slice(), &path_query).expect("should execute proof");
+
+    assert_eq!(hash, temp_db.root_hash(None).unwrap().unwrap());
+    let r1 = Element::new_item(b"value1".to_vec()).serialize().unwrap();
+    let r2 = Element::new_item(b"value4".to_vec()).serialize().unwrap();
+
+    compare_result_tuples(
+        result_set,
+        vec![(b"key4".to_vec(), r1), (b"key5".to_vec(), r2)],
+    );
+}
+
+#[test]
+fn test_path_query_proofs_without_subquery() {
+    // Tree Structure
+    // root
+    //     test_leaf
+    //         innertree
+    //             k1,v1
+    //             k2,v2
+    //             k3,v3
+    //     another_test_leaf
+    //         innertree2
+    //             k3,v3
+    //         innertree3
+    //             k4,v4
+
+    // Insert elements into grovedb instance
+    let temp_db = make_test_grovedb();
+    // Insert level 1 nodes
+    temp_db
+        .insert(
+            [TEST_LEAF].as_ref(),
+            b"innertree",
+            Element::empty_tree(),
+            None,
+            None,
+        )
+        .unwrap()
+        .expect("successful subtree insert");
+    temp_db
+        .insert(
+            [ANOTHER_TEST_LEAF].as_ref(),
+            b"innertree2",
+            Element::empty_tree(),
+            None,
+            None,
+        )
+        .unwrap()
+        .expect("successful subtree insert");
+    temp_db
+        .insert(
+            [ANOTHER_TEST_LEAF].as_ref(),
+            b"innertree3",
+            Element::empty_tree(),
+            None,
+            None,
+        )
+        .unwrap()
+        .expect("successful subtree insert");
+    // Insert level 2 nodes
+    temp_db
+        .insert(
+            [TEST_LEAF, b"innertree"].as_ref(),
+            b"key1",
+            Element::new_item(b"value1".to_vec()),
+            None,
+            None,
+        )
+        .unwrap()
+        .expect("successful subtree insert");
+    temp_db
+        .insert(
+            [TEST_LEAF, b"innertree"].as_ref(),
+            b"key2",
+            Element::new_item(b"value2".to_vec()),
+            None,
+            None,
+        )
+        .unwrap()
+        .expect("successful subtree insert");
+    temp_db
+        .insert(
+            [TEST_LEAF, b"innertree"].as_ref(),
+            b"key3",
+            Element::new_item(b"value3".to_vec()),
+            None,
+            None,
+        )
+        .unwrap()
+        .expect("successful subtree insert");
+    temp_db
+        .insert(
+            [ANOTHER_TEST_LEAF, b"innertree2"].as_ref(),
+            b"key3",
+            Element::new_item(b"value3".to_vec()),
+            None,
+            None,
+        )
+        .unwrap()
+        .expect("successful subtree insert");
+    temp_db
+        .insert(
+            [ANOTHER_TEST_LEAF, b"innertree3"].as_ref(),
+            b"key4",
+            Element::new_item(b"value4".to_vec()),
+            None,
+            None,
+        )
+        .unwrap()
+        .expect("successful subtree insert");
+
+    // Single key query
+    let mut query = Query::new();
+    query.insert_key(b"key1".to_vec());
+
+    let path_query = PathQuery::new_unsized(vec![TEST_LEAF.to_vec(), b"innertree".to_vec()], query);
+
+    let proof = temp_db.prove_query(&path_query).unwrap().unwrap();
+    assert_eq!(
+        hex::encode(proof.as_slice()),
+        "01025503046b6579310009000676616c7565310002018655e18e4555b0b65\
+        bbcec64c749db6b9ad84231969fb4fbe769a3093d10f2100198ebd6dc7e1\
+        c82951c41fcfa6487711cac6a399ebb01bb979cbe4a51e0b2f08d1101350\
+        409696e6e65727472656500080201046b657932004910536da659a3dbdbc\
+        f68c4a6630e72de4ba20cfc60b08b3dd45b4225a599b6015c04097465737\
+        45f6c656166000d020109696e6e65727472656500fafa16d06e8d8696dae\
+        443731ae2a4eae521e4a9a79c331c8a7e22e34c0f1a6e01b55f830550604\
+        719833d54ce2bf139aff4bb699fa4111b9741633554318792c511"
+    );
+    let (hash, result_set) =
+        GroveDb::verify_query_raw(proof.as_slice(), &path_query).expect("should execute proof");
+
+    assert_eq!(hash, temp_db.root_hash(None).unwrap().unwrap());
+    let r1 = Element::new_item(b"value1".to_vec()).serialize().unwrap();
+    compare_result_tuples(result_set, vec![(b"key1".to_vec(), r1)]);
+
+    // Range query + limit
+    let mut query = Query::new();
+    query.insert_range_after(b"key1".to_vec()..);
+    let path_query = PathQuery::new(
+        vec![TEST_LEAF.to_vec(), b"innertree".to_vec()],
+        SizedQuery::new(query, Some(1), None),
+    );
+
+    let proof = temp_db.prove_query(&path_query).unwrap().unwrap();
+    let (hash, result_set) =
+        GroveDb::verify_query_raw(proof.as_slice(), &path_query).expect("should execute proof");
+
+    assert_eq!(hash, temp_db.root_hash(None).unwrap().unwrap());
+    let r1 = Element::new_item(b"value2".to_vec()).serialize().unwrap();
+    compare_result_tuples(result_set, vec![(b"key2".to_vec(), r1)]);
+
+    // Range query + offset + limit
+    let mut query = Query::new();
+    query.insert_range_after(b"key1".to_vec()..);
+    let path_query = PathQuery::new(
+        vec![TEST_LEAF.to_vec(), b"innertree".to_vec()],
+        SizedQuery::new(query, Some(1), Some(1)),
+    );
+
+    let proof = temp_db.prove_query(&path_query).unwrap().unwrap();
+    let (hash, result_set) =
+        GroveDb::verify_query_raw(proof.as_slice(), &path_query).expect("should execute proof");
+
+    assert_eq!(hash, temp_db.root_hash(None).unwrap().unwrap());
+    let r1 = Element::new_item(b"value3".to_vec()).serialize().unwrap();
+    compare_result_tuples(result_set, vec![(b"key3".to_vec(), r1)]);
+
+    // Range query + direction + limit
+    let mut query = Query::new_with_direction(false);
+    query.insert_all();
+    let path_query = PathQuery::new(
+        vec![TEST_LEAF.to_vec(), b"innertree".to_vec()],
+        SizedQuery::new(query, Some(2), None),
+    );
+
+    let proof = temp_db.prove_query(&path_query).unwrap().unwrap();
+    let (hash, result_set) =
+        GroveDb::verify_query_raw(proof.as_slice(), &path_query).expect("should execute proof");
+
+    assert_eq!(hash, temp_db.root_hash(None).unwrap().unwrap());
+    let r1 = Element::new_item(b"value3".to_vec()).serialize().unwrap();
+    let r2 = Element::new_item(b"value2".to_vec()).serialize().unwrap();
+    compare_result_tuples(
+        result_set,
+        vec![(b"key3".to_vec(), r1), (b"key2".to_vec(), r2)],
+    );
+}
+
+#[test]
+fn test_path_query_proofs_with_default_subquery() {
+    let temp_db = make_deep_tree();
+
+    let mut query = Query::new();
+    query.insert_all();
+
+    let mut subq = Query::new();
+    subq.insert_all();
+    query.set_subquery(subq);
+
+    let path_query = PathQuery::new_unsized(vec![TEST_LEAF.to_vec()], query);
+
+    let proof = temp_db.prove_query(&path_query).unwrap().unwrap();
+    let (hash, result_set) =
+        GroveDb::verify_query_raw(proof.as_slice(), &path_query).expect("should execute proof");
+
+    assert_eq!(hash, temp_db.root_hash(None).unwrap().unwrap());
+    assert_eq!(result_set.len(), 5);
+
+    let keys = [
+        b"key1".to_vec(),
+        b"key2".to_vec(),
+        b"key3".to_vec(),
+        b"key4".to_vec(),
+        b"key5".to_vec(),
+    ];
+    let values = [
+        b"value1".to_vec(),
+        b"value2".to_vec(),
+        b"value3".to_vec(),
+        b"value4".to_vec(),
+        b"value5".to_vec(),
+    ];
+    let elements = values.map(|x| Element::new_item(x).serialize().unwrap());
+    let expected_result_set: Vec<(Vec<u8>, Vec<u8>)> = keys.into_iter().zip(elements).collect();
+    compare_result_tuples(result_set, expected_result_set);
+
+    let mut query = Query::new();
+    query.insert_range_after(b"innertree".to_vec()..);
+
+    let mut subq = Query::new();
+    subq.insert_all();
+    query.set_subquery(subq);
+
+    let path_query = PathQuery::new_unsized(vec![TEST_LEAF.to_vec()], query);
+
+    let proof = temp_db.prove_query(&path_query).unwrap().unwrap();
+    let (hash, result_set) =
+        GroveDb::verify_query_raw(proof.as_slice(), &path_query).expect("should execute proof");
+
+    assert_eq!(hash, temp_db.root_hash(None).unwrap().unwrap());
+    assert_eq!(result_set.len(), 2);
+
+    let keys = [b"key4".to_vec(), b"key5".to_vec()];
+    let values = [b"value4".to_vec(), b"value5".to_vec()];
+    let elements = values.map(|x| Element::new_item(x).serialize().unwrap());
+    let expected_result_set: Vec<(Vec<u8>, Vec<u8>)> = keys.into_iter().zip(elements).collect();
+    compare_result_tuples(result_set, expected_result_set);
+
+    // range subquery
+    let mut query = Query::new();
+    query.insert_all();
+
+    let mut subq = Query::new();
+    subq.insert_range_after_to_inclusive(b"key1".to_vec()..=b"key4".to_vec());
+    query.set_subquery(subq);
+
+    let path_query = PathQuery::new_unsized(vec![TEST_LEAF.to_vec()], query);
+
+    let proof = temp_db.prove_query(&path_query).unwrap().unwrap();
+    let (hash, result_set) = GroveDb::verify_query_raw(proof.as_slice(), &path_query).expect(
+        "should
+    execute proof",
+    );
+
+    assert_eq!(hash, temp_db.root_hash(None).unwrap().unwrap());
+    assert_eq!(result_set.len(), 3);
+
+    let keys = [b"key2".to_vec(), b"key3".to_vec(), b"key4".to_vec()];
+    let values = [b"value2".to_vec(), b"value3".to_vec(), b"value4".to_vec()];
+    let elements = values.map(|x| Element::new_item(x).serialize().unwrap());
+    let expected_result_set: Vec<(Vec<u8>, Vec<u8>)> = keys.into_iter().zip(elements).collect();
+    compare_result_tuples(result_set, expected_result_set);
+
+    // deep tree test
+    let mut query = Query::new();
+    query.insert_all();
+
+    let mut subq = Query::new();
+    subq.insert_all();
+
+    let mut sub_subquery = Query::new();
+    sub_subquery.insert_all();
+
+    subq.set_subquery(sub_subquery);
+    query.set_subquery(subq);
+
+    let path_query = PathQuery::new_unsized(vec![DEEP_LEAF.to_vec()], query);
+
+    let proof = temp_db.prove_query(&path_query).unwrap().unwrap();
+    let (hash, result_set) =
+        GroveDb::verify_query_raw(proof.as_slice(), &path_query).expect("should execute proof");
+
+    assert_eq!(hash, temp_db.root_hash(None).unwrap().unwrap());
+    assert_eq!(result_set.len(), 11);
+
+    let keys = [
+        b"key1".to_vec(),
+        b"key2".to_vec(),
+        b"key3".to_vec(),
+        b"key4".to_vec(),
+        b"key5".to_vec(),
+        b"key6".to_vec(),
+        b"key7".to_vec(),
+        b"key8".to_vec(),
+        b"key9".to_vec(),
+        b"key10".to_vec(),
+        b"key11".to_vec(),
+    ];
+    let values = [
+        b"value1".to_vec(),
+        b"value2".to_vec(),
+        b"value3".to_vec(),
+        b"value4".to_vec(),
+        b"value5".to_vec(),
+        b"value6".to_vec(),
+        b"value7".to_vec(),
+        b"value8".to_vec(),
+        b"value9".to_vec(),
+        b"value10".to_vec(),
+        b"value11".to_vec(),
+    ];
+    let elements = values.map(|x| Element::new_item(x).serialize().unwrap());
+    let expected_result_set: Vec<(Vec<u8>, Vec<u8>)> = keys.into_iter().zip(elements).collect();
+    compare_result_tuples(result_set, expected_result_set);
+}
+
+#[test]
+fn test_path_query_proofs_with_subquery_path() {
+    let temp_db = make_deep_tree();
+
+    let mut query = Query::new();
+    query.insert_all();
+
+    let mut subq = Query::new();
+    subq.insert_all();
+
+    query.set_subquery_key(b"deeper_1".to_vec());
+    query.set_subquery(subq);
+
+    let path_query = PathQuery::new_unsized(vec![DEEP_LEAF.to_vec()], query);
+
+    let proof = temp_db.prove_query(&path_query).unwrap().unwrap();
+    let (hash, result_set) =
+        GroveDb::verify_query_raw(proof.as_slice(), &path_query).expect("should execute proof");
+
+    assert_eq!(hash, temp_db.root_hash(None).unwrap().unwrap());
+    assert_eq!(result_set.len(), 3);
+
+    let keys = [b"key1".to_vec(), b"key2".to_vec(), b"key3".to_vec()];
+    let values = [b"value1".to_vec(), b"value2".to_vec(), b"value3".to_vec()];
+    let elements = values.map(|x| Element::new_item(x).serialize().unwrap());
+    let expected_result_set: Vec<(Vec<u8>, Vec<u8>)> = keys.into_iter().zip(elements).collect();
+    compare_result_tuples(result_set, expected_result_set);
+
+    // test subquery path with valid n > 1 valid translation
+    let mut query = Query::new();
+    query.insert_all();
+
+    let mut subq = Query::new();
+    subq.insert_all();
+
+    query.set_subquery_path(vec![b"deep_node_1".to_vec(), b"deeper_1".to_vec()]);
+    query.set_subquery(subq);
+
+    let path_query = PathQuery::new_unsized(vec![], query);
+    let proof = temp_db.prove_query(&path_query).unwrap().unwrap();
+    let (hash, result_set) =
+        GroveDb::verify_query_raw(proof.as_slice(), &path_query).expect("should execute proof");
+    assert_eq!(hash, temp_db.root_hash(None).unwrap().unwrap());
+    assert_eq!(result_set.len(), 3);
+
+    let keys = [b"key1".to_vec(), b"key2".to_vec(), b"key3".to_vec()];
+    let values = [b"value1".to_vec(), b"value2".to_vec(), b"value3".to_vec()];
+    let elements = values.map(|x| Element::new_item(x).serialize().unwrap());
+    let expected_result_set: Vec<(Vec<u8>, Vec<u8>)> = keys.into_iter().zip(elements).collect();
+    compare_result_tuples(result_set, expected_result_set);
+
+    // test subquery path with empty subquery path
+    let mut query = Query::new();
+    query.insert_all();
+
+    let mut subq = Query::new();
+    subq.insert_all();
+
+    query.set_subquery_path(vec![]);
+    query.set_subquery(subq);
+
+    let path_query =
+        PathQuery::new_unsized(vec![b"deep_leaf".to_vec(), b"deep_node_1".to_vec()], query);
+    let proof = temp_db.prove_query(&path_query).unwrap().unwrap();
+    let (hash, result_set) =
+        GroveDb::verify_query_raw(proof.as_slice(), &path_query).expect("should execute proof");
+    assert_eq!(hash, temp_db.root_hash(None).unwrap().unwrap());
+    assert_eq!(result_set.len(), 6);
+
+    let keys = [
+        b"key1".to_vec(),
+        b"key2".to_vec(),
+        b"key3".to_vec(),
+        b"key4".to_vec(),
+        b"key5".to_vec(),
+        b"key6".to_vec(),
+    ];
+    let values = [
+        b"value1".to_vec(),
+        b"value2".to_vec(),
+        b"value3".to_vec(),
+        b"value4".to_vec(),
+        b"value5".to_vec(),
+        b"value6".to_vec(),
+    ];
+    let elements = values.map(|x| Element::new_item(x).serialize().unwrap());
+    let expected_result_set: Vec<(Vec<u8>, Vec<u8>)> = keys.into_iter().zip(elements).collect();
+    compare_result_tuples(result_set, expected_result_set);
+
+    // test subquery path with an invalid translation
+    // should generate a valid absence proof with an empty result set
+    let mut query = Query::new();
+    query.insert_all();
+
+    let mut subq = Query::new();
+    subq.insert_all();
+
+    query.set_subquery_path(vec![
+        b"deep_node_1".to_vec(),
+        b"deeper_10".to_vec(),
+        b"another_invalid_key".to_vec(),
+    ]);
+    query.set_subquery(subq);
+
+    let path_query = PathQuery::new_unsized(vec![], query);
+    let proof = temp_db.prove_query(&path_query).unwrap().unwrap();
+    let (hash, result_set) =
+        GroveDb::verify_query_raw(proof.as_slice(), &path_query).expect("should execute proof");
+    assert_eq!(hash, temp_db.root_hash(None).unwrap().unwrap());
+    assert_eq!(result_set.len(), 0);
+}
+
+#[test]
+fn test_path_query_proofs_with_key_and_subquery() {
+    let temp_db = make_deep_tree();
+
+    let mut query = Query::new();
+    query.insert_key(b"deep_node_1".to_vec());
+
+    let mut subq = Query::new();
+    subq.insert_all();
+
+    query.set_subquery_key(b"deeper_1".to_vec());
+    query.set_subquery(subq);
+
+    let path_query = PathQuery::new_unsized(vec![DEEP_LEAF.to_vec()], query);
+
+    let proof = temp_db.prove_query(&path_query).unwrap().unwrap();
+    let (hash, result_set) =
+        GroveDb::verify_query_raw(proof.as_slice(), &path_query).expect("should execute proof");
+
+    assert_eq!(hash, temp_db.root_hash(None).unwrap().unwrap());
+    assert_eq!(result_set.len(), 3);
+
+    let keys = [b"key1".to_vec(), b"key2".to_vec(), b"key3".to_vec()];
+    let values = [b"value1".to_vec(), b"value2".to_vec(), b"value3".to_vec()];
+    let elements = values.map(|x| Element::new_item(x).serialize().unwrap());
+    let expected_result_set: Vec<(Vec<u8>, Vec<u8>)> = keys.into_iter().zip(elements).collect();
+    compare_result_tuples(result_set, expected_result_set);
+}
+
+#[test]
+fn test_path_query_proofs_with_conditional_subquery() {
+    let temp_db = make_deep_tree();
+
+    let mut query = Query::new();
+    query.insert_all();
+
+    let mut subquery = Query::new();
+    subquery.insert_all();
+
+    let mut final_subquery = Query::new();
+    final_subquery.insert_all();
+
+    subquery.add_conditional_subquery(
+        QueryItem::Key(b"deeper_4".to_vec()),
+        None,
+        Some(final_subquery),
+    );
+
+    query.set_subquery(subquery);
+
+    let path_query = PathQuery::new_unsized(vec![DEEP_LEAF.to_vec()], query);
+    let proof = temp_db.prove_query(&path_query).unwrap().unwrap();
+    let (hash, result_set) =
+        GroveDb::verify_query_raw(proof.as_slice(), &path_query).expect("should execute proof");
+
+    assert_eq!(hash, temp_db.root_hash(None).unwrap().unwrap());
+
+    let keys = [
+        b"deeper_1".to_vec(),
+        b"deeper_2".to_vec(),
+        b"deeper_3".to_vec(),
+        b"key10".to_vec(),
+        b"key11".to_vec(),
+    ];
+    assert_eq!(result_set.len(), keys.len());
+
+    // TODO: Is this defined behaviour
+    for (index, key) in keys.iter().enumerate() {
+        assert_eq!(&result_set[index].key, key);
+    }
+
+    // Default + Conditional subquery
+    let mut query = Query::new();
+    query.insert_all();
+
+    let mut subquery = Query::new();
+    subquery.insert_all();
+
+    let mut final_conditional_subquery = Query::new();
+    final_conditional_subquery.insert_all();
+
+    let mut final_default_subquery = Query::new();
+    final_default_subquery.insert_range_inclusive(b"key3".to_vec()..=b"key6".to_vec());
+
+    subquery.add_conditional_subquery(
+        QueryItem::Key(b"deeper_4".to_vec()),
+        None,
+        Some(final_conditional_subquery),
+    );
+    subquery.set_subquery(final_default_subquery);
+
+    query.set_subquery(subquery);
+
+    let path_query = PathQuery::new_unsized(vec![DEEP_LEAF.to_vec()], query);
+    let proof = temp_db.prove_query(&path_query).unwrap().unwrap();
+    let (hash, result_set) =
+        GroveDb::verify_query_raw(proof.as_slice(), &path_query).expect("should execute proof");
+
+    assert_eq!(hash, temp_db.root_hash(None).unwrap().unwrap());
+    assert_eq!(result_set.len(), 6);
+
+    let keys = [
+        b"key3".to_vec(),
+        b"key4".to_vec(),
+        b"key5".to_vec(),
+        b"key6".to_vec(),
+        b"key10".to_vec(),
+        b"key11".to_vec(),
+    ];
     let values = [
-        b"value1".to_vec(),
-        b"value2".to_vec(),
         b"value3".to_vec(),
         b"value4".to_vec(),
         b"value5".to_vec(),
         b"value6".to_vec(),
+        b"value10".to_vec(),
+        b"value11".to_vec(),
+    ];
+    let elements = values
+        .map(|x| Element::new_item(x).serialize().unwrap())
+        .to_vec();
+    // compare_result_sets(&elements, &result_set);
+    let expected_result_set: Vec<(Vec<u8>, Vec<u8>)> = keys.into_iter().zip(elements).collect();
+    compare_result_tuples(result_set, expected_result_set);
+}
+
+#[test]
+fn test_path_query_proofs_with_sized_query() {
+    let temp_db = make_deep_tree();
+
+    let mut query = Query::new();
+    query.insert_all();
+
+    let mut subquery = Query::new();
+    subquery.insert_all();
+
+    let mut final_conditional_subquery = Query::new();
+    final_conditional_subquery.insert_all();
+
+    let mut final_default_subquery = Query::new();
+    final_default_subquery.insert_range_inclusive(b"key3".to_vec()..=b"key6".to_vec());
+
+    subquery.add_conditional_subquery(
+        QueryItem::Key(b"deeper_4".to_vec()),
+        None,
+        Some(final_conditional_subquery),
+    );
+    subquery.set_subquery(final_default_subquery);
+
+    query.set_subquery(subquery);
+
+    let path_query = PathQuery::new(
+        vec![DEEP_LEAF.to_vec()],
+        SizedQuery::new(query, Some(3), Some(1)),
+    );
+    let proof = temp_db.prove_query(&path_query).unwrap().unwrap();
+    let (hash, result_set) =
+        GroveDb::verify_query_raw(proof.as_slice(), &path_query).expect("should execute proof");
+
+    assert_eq!(hash, temp_db.root_hash(None).unwrap().unwrap());
+    assert_eq!(result_set.len(), 3);
+
+    let keys = [b"key4".to_vec(), b"key5".to_vec(), b"key6".to_vec()];
+    let values = [b"value4".to_vec(), b"value5".to_vec(), b"value6".to_vec()];
+    let elements = values.map(|x| Element::new_item(x).serialize().unwrap());
+    let expected_result_set: Vec<(Vec<u8>, Vec<u8>)> = keys.into_iter().zip(elements).collect();
+    compare_result_tuples(result_set, expected_result_set);
+}
+
+#[test]
+fn test_path_query_proofs_with_direction() {
+    let temp_db = make_deep_tree();
+
+    let mut query = Query::new_with_direction(false);
+    query.insert_all();
+
+    let mut subquery = Query::new_with_direction(false);
+    subquery.insert_all();
+
+    let mut final_conditional_subquery = Query::new_with_direction(false);
+    final_conditional_subquery.insert_all();
+
+    let mut final_default_subquery = Query::new_with_direction(false);
+    final_default_subquery.insert_range_inclusive(b"key3".to_vec()..=b"key6".to_vec());
+
+    subquery.add_conditional_subquery(
+        QueryItem::Key(b"deeper_4".to_vec()),
+        None,
+        Some(final_conditional_subquery),
+    );
+    subquery.set_subquery(final_default_subquery);
+
+    query.set_subquery(subquery);
+
+    let path_query = PathQuery::new(
+        vec![DEEP_LEAF.to_vec()],
+        SizedQuery::new(query, Some(3), Some(1)),
+    );
+    let proof = temp_db.prove_query(&path_query).unwrap().unwrap();
+    let (hash, result_set) =
+        GroveDb::verify_query_raw(proof.as_slice(), &path_query).expect("should execute proof");
+
+    assert_eq!(hash, temp_db.root_hash(None).unwrap().unwrap());
+    assert_eq!(result_set.len(), 3);
+
+    let keys = [b"key10".to_vec(), b"key6".to_vec(), b"key5".to_vec()];
+    let values = [b"value10".to_vec(), b"value6".to_vec(), b"value5".to_vec()];
+    let elements = values.map(|x| Element::new_item(x).serialize().unwrap());
+    let expected_result_set: Vec<(Vec<u8>, Vec<u8>)> = keys.into_iter().zip(elements).collect();
+    compare_result_tuples(result_set, expected_result_set);
+
+    // combined directions
+    let mut query = Query::new();
+    query.insert_all();
+
+    let mut subq = Query::new_with_direction(false);
+    subq.insert_all();
+
+    let mut sub_subquery = Query::new();
+    sub_subquery.insert_all();
+
+    subq.set_subquery(sub_subquery);
+    query.set_subquery(subq);
+
+    let path_query = PathQuery::new_unsized(vec![DEEP_LEAF.to_vec()], query);
+
+    let proof = temp_db.prove_query(&path_query).unwrap().unwrap();
+    let (hash, result_set) =
+        GroveDb::verify_query_raw(proof.as_slice(), &path_query).expect("should execute proof");
+
+    assert_eq!(hash, temp_db.root_hash(None).unwrap().unwrap());
+    assert_eq!(result_set.len(), 11);
+
+    let keys = [
+        b"key4".to_vec(),
+        b"key5".to_vec(),
+        b"key6".to_vec(),
+        b"key1".to_vec(),
+        b"key2".to_vec(),
+        b"key3".to_vec(),
+        b"key10".to_vec(),
+        b"key11".to_vec(),
+        b"key7".to_vec(),
+        b"key8".to_vec(),
+        b"key9".to_vec(),
+    ];
+    let values = [
+        b"value4".to_vec(),
+        b"value5".to_vec(),
+        b"value6".to_vec(),
+        b"value1".to_vec(),
+        b"value2".to_vec(),
+        b"value3".to_vec(),
+        b"value10".to_vec(),
+        b"value11".to_vec(),
+        b"value7".to_vec(),
+        b"value8".to_vec(),
+        b"value9".to_vec(),
     ];
     let elements = values.map(|x| Element::new_item(x).serialize().unwrap());
     let expected_result_set: Vec<(Vec<u8>, Vec<u8>)> = keys.into_iter().zip(elements).collect();
     compare_result_tuples(result_set, expected_result_set);
+}
+
+#[test]
+fn test_checkpoint() {
+    let db = make_test_grovedb();
+    let element1 = Element::new_item(b"ayy".to_vec());
+
+    db.insert(EMPTY_PATH, b"key1", Element::empty_tree(), None, None)
+        .unwrap()
+        .expect("cannot insert a subtree 1 into GroveDB");
+    db.insert(
+        [b"key1".as_ref()].as_ref(),
+        b"key2",
+        Element::empty_tree(),
+        None,
+        None,
+    )
+    .unwrap()
+    .expect("cannot insert a subtree 2 into GroveDB");
+    db.insert(
+        [b"key1".as_ref(), b"key2".as_ref()].as_ref(),
+        b"key3",
+        element1.clone(),
+        None,
+        None,
+    )
+    .unwrap()
+    .expect("cannot insert an item into GroveDB");
 
-    // test subquery path with an invalid translation
-    // should generate a valid absence proof with an empty result set
-    let mut query = Query::new();
-    query.insert_all();
+    assert_eq!(
+        db.get([b"key1".as_ref(), b"key2".as_ref()].as_ref(), b"key3", None)
+            .unwrap()
+            .expect("cannot get from grovedb"),
+        element1
+    );
 
-    let mut subq = Query::new();
-    subq.insert_all();
+    let tempdir_parent = TempDir::new().expect("cannot open tempdir");
+    let checkpoint_tempdir = tempdir_parent.path().join("checkpoint");
+    db.create_checkpoint(&checkpoint_tempdir)
+        .expect("cannot create checkpoint");
 
-    query.set_subquery_path(vec![
-        b"deep_node_1".to_vec(),
-        b"deeper_10".to_vec(),
-        b"another_invalid_key".to_vec(),
-    ]);
-    query.set_subquery(subq);
+    let checkpoint_db =
+        GroveDb::open(checkpoint_tempdir).expect("cannot open grovedb from checkpoint");
 
-    let path_query = PathQuery::new_unsized(vec![], query);
-    let proof = temp_db.prove_query(&path_query).unwrap().unwrap();
-    let (hash, result_set) =
-        GroveDb::verify_query_raw(proof.as_slice(), &path_query).expect("should execute proof");
-    assert_eq!(hash, temp_db.root_hash(None).unwrap().unwrap());
-    assert_eq!(result_set.len(), 0);
-}
+    assert_eq!(
+        db.get([b"key1".as_ref(), b"key2".as_ref()].as_ref(), b"key3", None)
+            .unwrap()
+            .expect("cannot get from grovedb"),
+        element1
+    );
+    assert_eq!(
+        checkpoint_db
+            .get([b"key1".as_ref(), b"key2".as_ref()].as_ref(), b"key3", None)
+            .unwrap()
+            .expect("cannot get from checkpoint"),
+        element1
+    );
 
-#[test]
-fn test_path_query_proofs_with_key_and_subquery() {
-    let temp_db = make_deep_tree();
+    let element2 = Element::new_item(b"ayy2".to_vec());
+    let element3 = Element::new_item(b"ayy3".to_vec());
 
-    let mut query = Query::new();
-    query.insert_key(b"deep_node_1".to_vec());
+    checkpoint_db
+        .insert(
+            [b"key1".as_ref()].as_ref(),
+            b"key4",
+            element2.clone(),
+            None,
+            None,
+        )
+        .unwrap()
+        .expect("cannot insert into checkpoint");
 
-    let mut subq = Query::new();
-    subq.insert_all();
+    db.insert(
+        [b"key1".as_ref()].as_ref(),
+        b"key4",
+        element3.clone(),
+        None,
+        None,
+    )
+    .unwrap()
+    .expect("cannot insert into GroveDB");
 
-    query.set_subquery_key(b"deeper_1".to_vec());
-    query.set_subquery(subq);
+    assert_eq!(
+        checkpoint_db
+            .get([b"key1".as_ref()].as_ref(), b"key4", None)
+            .unwrap()
+            .expect("cannot get from checkpoint"),
+        element2,
+    );
 
-    let path_query = PathQuery::new_unsized(vec![DEEP_LEAF.to_vec()], query);
+    assert_eq!(
+        db.get([b"key1".as_ref()].as_ref(), b"key4", None)
+            .unwrap()
+            .expect("cannot get from GroveDB"),
+        element3
+    );
 
-    let proof = temp_db.prove_query(&path_query).unwrap().unwrap();
-    let (hash, result_set) =
-        GroveDb::verify_query_raw(proof.as_slice(), &path_query).expect("should execute proof");
+    checkpoint_db
+        .insert(
+            [b"key1".as_ref()].as_ref(),
+            b"key5",
+            element3.clone(),
+            None,
+            None,
+        )
+        .unwrap()
+        .expect("cannot insert into checkpoint");
 
-    assert_eq!(hash, temp_db.root_hash(None).unwrap().unwrap());
-    assert_eq!(result_set.len(), 3);
+    db.insert([b"key1".as_ref()].as_ref(), b"key6", element3, None, None)
+        .unwrap()
+        .expect("cannot insert into GroveDB");
 
-    let keys = [b"key1".to_vec(), b"key2".to_vec(), b"key3".to_vec()];
-    let values = [b"value1".to_vec(), b"value2".to_vec(), b"value3".to_vec()];
-    let elements = values.map(|x| Element::new_item(x).serialize().unwrap());
-    let expected_result_set: Vec<(Vec<u8>, Vec<u8>)> = keys.into_iter().zip(elements).collect();
-    compare_result_tuples(result_set, expected_result_set);
+    assert!(matches!(
+        checkpoint_db
+            .get([b"key1".as_ref()].as_ref(), b"key6", None)
+            .unwrap(),
+        Err(Error::PathKeyNotFound(_))
+    ));
+
+    assert!(matches!(
+        db.get([b"key1".as_ref()].as_ref(), b"key5", None).unwrap(),
+        Err(Error::PathKeyNotFound(_))
+    ));
 }
 
 #[test]
-fn test_path_query_proofs_with_conditional_subquery() {
-    let temp_db = make_deep_tree();
+fn test_is_empty_tree() {
+    let db = make_test_grovedb();
 
-    let mut query = Query::new();
-    query.insert_all();
+    // Create an empty tree with no elements
+    db.insert(
+        [TEST_LEAF].as_ref(),
+        b"innertree",
+        Element::empty_tree(),
+        None,
+        None,
+    )
+    .unwrap()
+    .unwrap();
 
-    let mut subquery = Query::new();
-    subquery.insert_all();
+    assert!(db
+        .is_empty_tree([TEST_LEAF, b"innertree"].as_ref(), None)
+        .unwrap()
+        .expect("path is valid tree"));
 
-    let mut final_subquery = Query::new();
-    final_subquery.insert_all();
+    // add an element to the tree to make it non empty
+    db.insert(
+        [TEST_LEAF, b"innertree"].as_ref(),
+        b"key1",
+        Element::new_item(b"hello".to_vec()),
+        None,
+        None,
+    )
+    .unwrap()
+    .unwrap();
+    assert!(!db
+        .is_empty_tree([TEST_LEAF, b"innertree"].as_ref(), None)
+        .unwrap()
+        .expect("path is valid tree"));
+}
 
-    subquery.add_conditional_subquery(
-        QueryItem::Key(b"deeper_4".to_vec()),
+#[test]
+fn test_cost_observer_reports_operation_costs() {
+    let mut db = make_test_grovedb();
+    db.insert(
+        [TEST_LEAF].as_ref(),
+        b"key1",
+        Element::new_item(b"hello".to_vec()),
         None,
-        Some(final_subquery),
-    );
+        None,
+    )
+    .unwrap()
+    .expect("successful insert");
 
-    query.set_subquery(subquery);
+    let observed = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+    let observed_by_callback = observed.clone();
+    db.set_cost_observer(Box::new(move |operation, cost| {
+        observed_by_callback
+            .lock()
+            .unwrap()
+            .push((operation.to_owned(), cost.clone()));
+    }));
 
-    let path_query = PathQuery::new_unsized(vec![DEEP_LEAF.to_vec()], query);
-    let proof = temp_db.prove_query(&path_query).unwrap().unwrap();
-    let (hash, result_set) =
-        GroveDb::verify_query_raw(proof.as_slice(), &path_query).expect("should execute proof");
+    let delete_cost = db
+        .delete([TEST_LEAF].as_ref(), b"key1", None, None)
+        .cost_as_result()
+        .expect("successful delete");
+    assert_eq!(
+        *observed.lock().unwrap(),
+        vec![("delete".to_owned(), delete_cost)]
+    );
 
-    assert_eq!(hash, temp_db.root_hash(None).unwrap().unwrap());
+    db.clear_cost_observer();
+    db.get([TEST_LEAF].as_ref(), b"key1", None)
+        .unwrap()
+        .expect_err("key was deleted");
+    assert_eq!(observed.lock().unwrap().len(), 1);
+}
 
-    let keys = [
-        b"deeper_1".to_vec(),
-        b"deeper_2".to_vec(),
-        b"deeper_3".to_vec(),
-        b"key10".to_vec(),
-        b"key11".to_vec(),
-    ];
-    assert_eq!(result_set.len(), keys.len());
+#[test]
+fn test_subtree_root_key() {
+    let db = make_test_grovedb();
 
-    // TODO: Is this defined behaviour
-    for (index, key) in keys.iter().enumerate() {
-        assert_eq!(&result_set[index].key, key);
+    db.insert(
+        [TEST_LEAF].as_ref(),
+        b"innertree",
+        Element::empty_tree(),
+        None,
+        None,
+    )
+    .unwrap()
+    .unwrap();
+    assert_eq!(
+        db.subtree_root_key([TEST_LEAF, b"innertree"].as_ref(), None)
+            .unwrap()
+            .expect("path is valid tree"),
+        None
+    );
+
+    for key in [b"key1", b"key2", b"key3"] {
+        db.insert(
+            [TEST_LEAF, b"innertree"].as_ref(),
+            key,
+            Element::new_item(b"hello".to_vec()),
+            None,
+            None,
+        )
+        .unwrap()
+        .unwrap();
     }
 
-    // Default + Conditional subquery
-    let mut query = Query::new();
-    query.insert_all();
+    let root_key = db
+        .subtree_root_key([TEST_LEAF, b"innertree"].as_ref(), None)
+        .unwrap()
+        .expect("path is valid tree");
+    let merk = db
+        .open_non_transactional_merk_at_path([TEST_LEAF, b"innertree"].as_ref().into(), None)
+        .unwrap()
+        .expect("cannot open merk");
+    assert!(root_key.is_some());
+    assert_eq!(root_key, merk.root_key());
 
-    let mut subquery = Query::new();
-    subquery.insert_all();
+    // The parent element keeps the same root key to reopen the layered Merk
+    let tree = db
+        .get_raw([TEST_LEAF].as_ref().into(), b"innertree", None)
+        .unwrap()
+        .expect("cannot get tree element");
+    assert_eq!(tree, Element::Tree(root_key, None));
+}
+
+#[test]
+fn transaction_should_be_aborted_when_rollback_is_called() {
+    let item_key = b"key3";
+
+    let db = make_test_grovedb();
+    let transaction = db.start_transaction();
+
+    let element1 = Element::new_item(b"ayy".to_vec());
+
+    let result = db
+        .insert(
+            [TEST_LEAF].as_ref(),
+            item_key,
+            element1,
+            None,
+            Some(&transaction),
+        )
+        .unwrap();
+
+    assert!(matches!(result, Ok(())));
+
+    db.rollback_transaction(&transaction).unwrap();
 
-    let mut final_conditional_subquery = Query::new();
-    final_conditional_subquery.insert_all();
+    let result = db
+        .get([TEST_LEAF].as_ref(), item_key, Some(&transaction))
+        .unwrap();
+    assert!(matches!(result, Err(Error::PathKeyNotFound(_))));
+}
 
-    let mut final_default_subquery = Query::new();
-    final_default_subquery.insert_range_inclusive(b"key3".to_vec()..=b"key6".to_vec());
+#[test]
+fn transaction_should_be_partially_undone_when_rolled_back_to_savepoint() {
+    let db = make_test_grovedb();
+    let transaction = db.start_transaction();
 
-    subquery.add_conditional_subquery(
-        QueryItem::Key(b"deeper_4".to_vec()),
+    db.insert(
+        [TEST_LEAF].as_ref(),
+        b"key1",
+        Element::new_item(b"ayy".to_vec()),
         None,
-        Some(final_conditional_subquery),
-    );
-    subquery.set_subquery(final_default_subquery);
+        Some(&transaction),
+    )
+    .unwrap()
+    .expect("successful insert");
+    let root_hash = db.root_hash(Some(&transaction)).unwrap().unwrap();
 
-    query.set_subquery(subquery);
+    let savepoint = db.savepoint(&transaction).expect("cannot create savepoint");
 
-    let path_query = PathQuery::new_unsized(vec![DEEP_LEAF.to_vec()], query);
-    let proof = temp_db.prove_query(&path_query).unwrap().unwrap();
-    let (hash, result_set) =
-        GroveDb::verify_query_raw(proof.as_slice(), &path_query).expect("should execute proof");
+    db.insert(
+        [TEST_LEAF].as_ref(),
+        b"key2",
+        Element::new_item(b"lmao".to_vec()),
+        None,
+        Some(&transaction),
+    )
+    .unwrap()
+    .expect("successful insert");
 
-    assert_eq!(hash, temp_db.root_hash(None).unwrap().unwrap());
-    assert_eq!(result_set.len(), 6);
+    db.rollback_to_savepoint(&transaction, savepoint)
+        .expect("cannot rollback to savepoint");
 
-    let keys = [
-        b"key3".to_vec(),
-        b"key4".to_vec(),
-        b"key5".to_vec(),
-        b"key6".to_vec(),
-        b"key10".to_vec(),
-        b"key11".to_vec(),
-    ];
-    let values = [
-        b"value3".to_vec(),
-        b"value4".to_vec(),
-        b"value5".to_vec(),
-        b"value6".to_vec(),
-        b"value10".to_vec(),
-        b"value11".to_vec(),
-    ];
-    let elements = values
-        .map(|x| Element::new_item(x).serialize().unwrap())
-        .to_vec();
-    // compare_result_sets(&elements, &result_set);
-    let expected_result_set: Vec<(Vec<u8>, Vec<u8>)> = keys.into_iter().zip(elements).collect();
-    compare_result_tuples(result_set, expected_result_set);
+    assert_eq!(
+        db.get([TEST_LEAF].as_ref(), b"key1", Some(&transaction))
+            .unwrap()
+            .expect("earlier write should survive"),
+        Element::new_item(b"ayy".to_vec())
+    );
+    assert!(matches!(
+        db.get([TEST_LEAF].as_ref(), b"key2", Some(&transaction))
+            .unwrap(),
+        Err(Error::PathKeyNotFound(_))
+    ));
+    assert_eq!(
+        root_hash,
+        db.root_hash(Some(&transaction)).unwrap().unwrap()
+    );
 }
 
 #[test]
-fn test_path_query_proofs_with_sized_query() {
-    let temp_db = make_deep_tree();
+fn transaction_should_be_aborted() {
+    let db = make_test_grovedb();
+    let transaction = db.start_transaction();
 
-    let mut query = Query::new();
-    query.insert_all();
+    let item_key = b"key3";
+    let element = Element::new_item(b"ayy".to_vec());
 
-    let mut subquery = Query::new();
-    subquery.insert_all();
+    db.insert(
+        [TEST_LEAF].as_ref(),
+        item_key,
+        element,
+        None,
+        Some(&transaction),
+    )
+    .unwrap()
+    .unwrap();
 
-    let mut final_conditional_subquery = Query::new();
-    final_conditional_subquery.insert_all();
+    drop(transaction);
 
-    let mut final_default_subquery = Query::new();
-    final_default_subquery.insert_range_inclusive(b"key3".to_vec()..=b"key6".to_vec());
+    // Transactional data shouldn't be committed to the main database
+    let result = db.get([TEST_LEAF].as_ref(), item_key, None).unwrap();
+    assert!(matches!(result, Err(Error::PathKeyNotFound(_))));
+}
 
-    subquery.add_conditional_subquery(
-        QueryItem::Key(b"deeper_4".to_vec()),
+#[test]
+fn test_subtree_pairs_iterator() {
+    let db = make_test_grovedb();
+    let element = Element::new_item(b"ayy".to_vec());
+    let element2 = Element::new_item(b"lmao".to_vec());
+
+    // Insert some nested subtrees
+    db.insert(
+        [TEST_LEAF].as_ref(),
+        b"subtree1",
+        Element::empty_tree(),
         None,
-        Some(final_conditional_subquery),
+        None,
+    )
+    .unwrap()
+    .expect("successful subtree 1 insert");
+    db.insert(
+        [TEST_LEAF, b"subtree1"].as_ref(),
+        b"subtree11",
+        Element::empty_tree(),
+        None,
+        None,
+    )
+    .unwrap()
+    .expect("successful subtree 2 insert");
+    // Insert an element into subtree
+    db.insert(
+        [TEST_LEAF, b"subtree1", b"subtree11"].as_ref(),
+        b"key1",
+        element.clone(),
+        None,
+        None,
+    )
+    .unwrap()
+    .expect("successful value insert");
+    assert_eq!(
+        db.get(
+            [TEST_LEAF, b"subtree1", b"subtree11"].as_ref(),
+            b"key1",
+            None
+        )
+        .unwrap()
+        .expect("successful get 1"),
+        element
     );
-    subquery.set_subquery(final_default_subquery);
+    db.insert(
+        [TEST_LEAF, b"subtree1", b"subtree11"].as_ref(),
+        b"key0",
+        element.clone(),
+        None,
+        None,
+    )
+    .unwrap()
+    .expect("successful value insert");
+    db.insert(
+        [TEST_LEAF, b"subtree1"].as_ref(),
+        b"subtree12",
+        Element::empty_tree(),
+        None,
+        None,
+    )
+    .unwrap()
+    .expect("successful subtree 3 insert");
+    db.insert(
+        [TEST_LEAF, b"subtree1"].as_ref(),
+        b"key1",
+        element.clone(),
+        None,
+        None,
+    )
+    .unwrap()
+    .expect("successful value insert");
+    db.insert(
+        [TEST_LEAF, b"subtree1"].as_ref(),
+        b"key2",
+        element2.clone(),
+        None,
+        None,
+    )
+    .unwrap()
+    .expect("successful value insert");
 
-    query.set_subquery(subquery);
+    // Iterate over subtree1 to see if keys of other subtrees messed up
+    // let mut iter = db
+    //     .elements_iterator([TEST_LEAF, b"subtree1"].as_ref(), None)
+    //     .expect("cannot create iterator");
+    let storage_context = db
+        .grove_db
+        .db
+        .get_storage_context([TEST_LEAF, b"subtree1"].as_ref().into(), None)
+        .unwrap();
+    let mut iter = Element::iterator(storage_context.raw_iter()).unwrap();
+    assert_eq!(
+        iter.next_element().unwrap().unwrap(),
+        Some((b"key1".to_vec(), element))
+    );
+    assert_eq!(
+        iter.next_element().unwrap().unwrap(),
+        Some((b"key2".to_vec(), element2))
+    );
+    let subtree_element = iter.next_element().unwrap().unwrap().unwrap();
+    assert_eq!(subtree_element.0, b"subtree11".to_vec());
+    assert!(matches!(subtree_element.1, Element::Tree(..)));
+    let subtree_element = iter.next_element().unwrap().unwrap().unwrap();
+    assert_eq!(subtree_element.0, b"subtree12".to_vec());
+    assert!(matches!(subtree_element.1, Element::Tree(..)));
+    assert!(matches!(iter.next_element().unwrap(), Ok(None)));
+}
 
-    let path_query = PathQuery::new(
-        vec![DEEP_LEAF.to_vec()],
-        SizedQuery::new(query, Some(3), Some(1)),
+#[test]
+fn test_find_subtrees() {
+    let element = Element::new_item(b"ayy".to_vec());
+    let db = make_test_grovedb();
+    // Insert some nested subtrees
+    db.insert(
+        [TEST_LEAF].as_ref(),
+        b"key1",
+        Element::empty_tree(),
+        None,
+        None,
+    )
+    .unwrap()
+    .expect("successful subtree 1 insert");
+    db.insert(
+        [TEST_LEAF, b"key1"].as_ref(),
+        b"key2",
+        Element::empty_tree(),
+        None,
+        None,
+    )
+    .unwrap()
+    .expect("successful subtree 2 insert");
+    // Insert an element into subtree
+    db.insert(
+        [TEST_LEAF, b"key1", b"key2"].as_ref(),
+        b"key3",
+        element,
+        None,
+        None,
+    )
+    .unwrap()
+    .expect("successful value insert");
+    db.insert(
+        [TEST_LEAF].as_ref(),
+        b"key4",
+        Element::empty_tree(),
+        None,
+        None,
+    )
+    .unwrap()
+    .expect("successful subtree 3 insert");
+    let subtrees = db
+        .find_subtrees(&[TEST_LEAF].as_ref().into(), None)
+        .unwrap()
+        .expect("cannot get subtrees");
+    assert_eq!(
+        vec![
+            vec![TEST_LEAF],
+            vec![TEST_LEAF, b"key1"],
+            vec![TEST_LEAF, b"key4"],
+            vec![TEST_LEAF, b"key1", b"key2"],
+        ],
+        subtrees
     );
-    let proof = temp_db.prove_query(&path_query).unwrap().unwrap();
-    let (hash, result_set) =
-        GroveDb::verify_query_raw(proof.as_slice(), &path_query).expect("should execute proof");
-
-    assert_eq!(hash, temp_db.root_hash(None).unwrap().unwrap());
-    assert_eq!(result_set.len(), 3);
-
-    let keys = [b"key4".to_vec(), b"key5".to_vec(), b"key6".to_vec()];
-    let values = [b"value4".to_vec(), b"value5".to_vec(), b"value6".to_vec()];
-    let elements = values.map(|x| Element::new_item(x).serialize().unwrap());
-    let expected_result_set: Vec<(Vec<u8>, Vec<u8>)> = keys.into_iter().zip(elements).collect();
-    compare_result_tuples(result_set, expected_result_set);
 }
 
 #[test]
-fn test_path_query_proofs_with_direction() {
-    let temp_db = make_deep_tree();
-
-    let mut query = Query::new_with_direction(false);
-    query.insert_all();
-
-    let mut subquery = Query::new_with_direction(false);
-    subquery.insert_all();
-
-    let mut final_conditional_subquery = Query::new_with_direction(false);
-    final_conditional_subquery.insert_all();
-
-    let mut final_default_subquery = Query::new_with_direction(false);
-    final_default_subquery.insert_range_inclusive(b"key3".to_vec()..=b"key6".to_vec());
-
-    subquery.add_conditional_subquery(
-        QueryItem::Key(b"deeper_4".to_vec()),
+fn test_find_subtrees_order_is_breadth_first() {
+    let db = make_test_grovedb();
+    // Tree Structure
+    // test_leaf
+    //     key1
+    //         key2
+    //             key3
+    //     key4
+    //         key5
+    let subtrees_to_insert: [(Vec<&[u8]>, &[u8]); 5] = [
+        (vec![TEST_LEAF], b"key1"),
+        (vec![TEST_LEAF, b"key1"], b"key2"),
+        (vec![TEST_LEAF, b"key1", b"key2"], b"key3"),
+        (vec![TEST_LEAF], b"key4"),
+        (vec![TEST_LEAF, b"key4"], b"key5"),
+    ];
+    for (path, key) in subtrees_to_insert {
+        db.insert(path.as_slice(), key, Element::empty_tree(), None, None)
+            .unwrap()
+            .expect("successful subtree insert");
+    }
+    db.insert(
+        [TEST_LEAF, b"key4"].as_ref(),
+        b"item",
+        Element::new_item(b"ayy".to_vec()),
         None,
-        Some(final_conditional_subquery),
-    );
-    subquery.set_subquery(final_default_subquery);
-
-    query.set_subquery(subquery);
-
-    let path_query = PathQuery::new(
-        vec![DEEP_LEAF.to_vec()],
-        SizedQuery::new(query, Some(3), Some(1)),
-    );
-    let proof = temp_db.prove_query(&path_query).unwrap().unwrap();
-    let (hash, result_set) =
-        GroveDb::verify_query_raw(proof.as_slice(), &path_query).expect("should execute proof");
-
-    assert_eq!(hash, temp_db.root_hash(None).unwrap().unwrap());
-    assert_eq!(result_set.len(), 3);
-
-    let keys = [b"key10".to_vec(), b"key6".to_vec(), b"key5".to_vec()];
-    let values = [b"value10".to_vec(), b"value6".to_vec(), b"value5".to_vec()];
-    let elements = values.map(|x| Element::new_item(x).serialize().unwrap());
-    let expected_result_set: Vec<(Vec<u8>, Vec<u8>)> = keys.into_iter().zip(elements).collect();
-    compare_result_tuples(result_set, expected_result_set);
-
-    // combined directions
-    let mut query = Query::new();
-    query.insert_all();
-
-    let mut subq = Query::new_with_direction(false);
-    subq.insert_all();
-
-    let mut sub_subquery = Query::new();
-    sub_subquery.insert_all();
-
-    subq.set_subquery(sub_subquery);
-    query.set_subquery(subq);
-
-    let path_query = PathQuery::new_unsized(vec![DEEP_LEAF.to_vec()], query);
+        None,
+    )
+    .unwrap()
+    .expect("successful value insert");
 
-    let proof = temp_db.prove_query(&path_query).unwrap().unwrap();
-    let (hash, result_set) =
-        GroveDb::verify_query_raw(proof.as_slice(), &path_query).expect("should execute proof");
+    let expected: Vec<Vec<&[u8]>> = vec![
+        vec![TEST_LEAF],
+        vec![TEST_LEAF, b"key1"],
+        vec![TEST_LEAF, b"key4"],
+        vec![TEST_LEAF, b"key1", b"key2"],
+        vec![TEST_LEAF, b"key4", b"key5"],
+        vec![TEST_LEAF, b"key1", b"key2", b"key3"],
+    ];
 
-    assert_eq!(hash, temp_db.root_hash(None).unwrap().unwrap());
-    assert_eq!(result_set.len(), 11);
+    let subtrees = db
+        .find_subtrees(&[TEST_LEAF].as_ref().into(), None)
+        .unwrap()
+        .expect("cannot get subtrees");
+    assert_eq!(expected, subtrees);
 
-    let keys = [
-        b"key4".to_vec(),
-        b"key5".to_vec(),
-        b"key6".to_vec(),
-        b"key1".to_vec(),
-        b"key2".to_vec(),
-        b"key3".to_vec(),
-        b"key10".to_vec(),
-        b"key11".to_vec(),
-        b"key7".to_vec(),
-        b"key8".to_vec(),
-        b"key9".to_vec(),
-    ];
-    let values = [
-        b"value4".to_vec(),
-        b"value5".to_vec(),
-        b"value6".to_vec(),
-        b"value1".to_vec(),
-        b"value2".to_vec(),
-        b"value3".to_vec(),
-        b"value10".to_vec(),
-        b"value11".to_vec(),
-        b"value7".to_vec(),
-        b"value8".to_vec(),
-        b"value9".to_vec(),
-    ];
-    let elements = values.map(|x| Element::new_item(x).serialize().unwrap());
-    let expected_result_set: Vec<(Vec<u8>, Vec<u8>)> = keys.into_iter().zip(elements).collect();
-    compare_result_tuples(result_set, expected_result_set);
+    let transaction = db.start_transaction();
+    let subtrees = db
+        .find_subtrees(&[TEST_LEAF].as_ref().into(), Some(&transaction))
+        .unwrap()
+        .expect("cannot get subtrees");
+    assert_eq!(expected, subtrees);
 }
 
 #[test]
-fn test_checkpoint() {
-    let db = make_test_grovedb();
-    let element1 = Element::new_item(b"ayy".to_vec());
+fn test_find_subtrees_typed() {
+    use grovedb_merk::TreeFeatureType::{BasicMerk, SummedMerk};
 
-    db.insert(EMPTY_PATH, b"key1", Element::empty_tree(), None, None)
-        .unwrap()
-        .expect("cannot insert a subtree 1 into GroveDB");
+    let db = make_test_grovedb();
+    // Tree Structure
+    // test_leaf
+    //     basic
+    //         inner_sums (sum tree)
+    //     sums (sum tree)
+    //         nested
     db.insert(
-        [b"key1".as_ref()].as_ref(),
-        b"key2",
+        [TEST_LEAF].as_ref(),
+        b"basic",
         Element::empty_tree(),
         None,
         None,
     )
     .unwrap()
-    .expect("cannot insert a subtree 2 into GroveDB");
+    .expect("successful subtree insert");
     db.insert(
-        [b"key1".as_ref(), b"key2".as_ref()].as_ref(),
-        b"key3",
-        element1.clone(),
+        [TEST_LEAF, b"basic"].as_ref(),
+        b"inner_sums",
+        Element::empty_sum_tree(),
         None,
         None,
     )
     .unwrap()
-    .expect("cannot insert an item into GroveDB");
+    .expect("successful subtree insert");
+    db.insert(
+        [TEST_LEAF].as_ref(),
+        b"sums",
+        Element::empty_sum_tree(),
+        None,
+        None,
+    )
+    .unwrap()
+    .expect("successful subtree insert");
+    db.insert(
+        [TEST_LEAF, b"sums"].as_ref(),
+        b"nested",
+        Element::empty_tree(),
+        None,
+        None,
+    )
+    .unwrap()
+    .expect("successful subtree insert");
+    for (key, value) in [(b"a", 3), (b"b", 4)] {
+        db.insert(
+            [TEST_LEAF, b"sums"].as_ref(),
+            key,
+            Element::new_sum_item(value),
+            None,
+            None,
+        )
+        .unwrap()
+        .expect("successful sum item insert");
+    }
 
+    let subtrees = db
+        .find_subtrees_typed(&[TEST_LEAF].as_ref().into(), None)
+        .unwrap()
+        .expect("cannot get subtrees");
+    let expected: Vec<(Vec<&[u8]>, _)> = vec![
+        (vec![TEST_LEAF], BasicMerk),
+        (vec![TEST_LEAF, b"basic"], BasicMerk),
+        (vec![TEST_LEAF, b"sums"], SummedMerk(7)),
+        (vec![TEST_LEAF, b"basic", b"inner_sums"], SummedMerk(0)),
+        (vec![TEST_LEAF, b"sums", b"nested"], BasicMerk),
+    ];
     assert_eq!(
-        db.get([b"key1".as_ref(), b"key2".as_ref()].as_ref(), b"key3", None)
-            .unwrap()
-            .expect("cannot get from grovedb"),
-        element1
+        subtrees,
+        expected
+            .into_iter()
+            .map(|(path, feature_type)| (
+                path.into_iter()
+                    .map(|segment| segment.to_vec())
+                    .collect::<Vec<_>>(),
+                feature_type
+            ))
+            .collect::<Vec<_>>()
     );
 
-    let tempdir_parent = TempDir::new().expect("cannot open tempdir");
-    let checkpoint_tempdir = tempdir_parent.path().join("checkpoint");
-    db.create_checkpoint(&checkpoint_tempdir)
-        .expect("cannot create checkpoint");
-
-    let checkpoint_db =
-        GroveDb::open(checkpoint_tempdir).expect("cannot open grovedb from checkpoint");
-
-    assert_eq!(
-        db.get([b"key1".as_ref(), b"key2".as_ref()].as_ref(), b"key3", None)
-            .unwrap()
-            .expect("cannot get from grovedb"),
-        element1
-    );
-    assert_eq!(
-        checkpoint_db
-            .get([b"key1".as_ref(), b"key2".as_ref()].as_ref(), b"key3", None)
-            .unwrap()
-            .expect("cannot get from checkpoint"),
-        element1
-    );
+    // The type of the starting subtree comes from its parent
+    let subtrees = db
+        .find_subtrees_typed(&[TEST_LEAF, b"sums"].as_ref().into(), None)
+        .unwrap()
+        .expect("cannot get subtrees");
+    assert_eq!(subtrees[0].1, SummedMerk(7));
+    assert_eq!(subtrees.len(), 2);
 
-    let element2 = Element::new_item(b"ayy2".to_vec());
-    let element3 = Element::new_item(b"ayy3".to_vec());
+    let subtrees = db
+        .find_subtrees_typed(&EMPTY_PATH, None)
+        .unwrap()
+        .expect("cannot get subtrees");
+    assert_eq!(subtrees[0], (vec![], BasicMerk));
+}
 
-    checkpoint_db
-        .insert(
-            [b"key1".as_ref()].as_ref(),
-            b"key4",
-            element2.clone(),
+#[test]
+fn test_verify_subtree_integrity_detects_tampered_value() {
+    let db = make_test_grovedb();
+    db.insert(
+        [TEST_LEAF].as_ref(),
+        b"inner",
+        Element::empty_tree(),
+        None,
+        None,
+    )
+    .unwrap()
+    .expect("successful subtree insert");
+    for i in 0..10u8 {
+        db.insert(
+            [TEST_LEAF, b"inner"].as_ref(),
+            &[b'k', i],
+            Element::new_item(vec![b'v', i, 0, 0, 0, 0]),
             None,
             None,
         )
         .unwrap()
-        .expect("cannot insert into checkpoint");
+        .expect("successful item insert");
+    }
+
+    assert!(db
+        .verify_subtree_integrity(EMPTY_PATH, None)
+        .unwrap()
+        .expect("integrity check should not fail"));
+    let tx = db.start_transaction();
+    assert!(db
+        .verify_subtree_integrity([TEST_LEAF].as_ref(), Some(&tx))
+        .unwrap()
+        .expect("integrity check should not fail"));
+    drop(tx);
+
+    // Change the stored value of an item bypassing Merk, so hashes are left intact
+    let batch = StorageBatch::new();
+    let storage = db
+        .db
+        .get_storage_context([TEST_LEAF, b"inner"].as_ref().into(), Some(&batch))
+        .unwrap();
+    let mut node_bytes = storage
+        .get([b'k', 5])
+        .unwrap()
+        .expect("cannot read node")
+        .expect("node should exist");
+    let value_position = node_bytes
+        .windows(6)
+        .position(|w| w == [b'v', 5, 0, 0, 0, 0])
+        .expect("value should be in the encoded node");
+    node_bytes[value_position + 2] = 1;
+    storage
+        .put([b'k', 5], &node_bytes, None, None)
+        .unwrap()
+        .expect("cannot write node");
+    drop(storage);
+    db.db
+        .commit_multi_context_batch(batch, None)
+        .unwrap()
+        .expect("cannot commit batch");
+
+    assert!(!db
+        .verify_subtree_integrity([TEST_LEAF, b"inner"].as_ref(), None)
+        .unwrap()
+        .expect("integrity check should not fail"));
+    assert!(!db
+        .verify_subtree_integrity(EMPTY_PATH, None)
+        .unwrap()
+        .expect("integrity check should not fail"));
+    assert!(db
+        .verify_subtree_integrity([ANOTHER_TEST_LEAF].as_ref(), None)
+        .unwrap()
+        .expect("integrity check should not fail"));
+}
+
+#[test]
+fn test_root_hash_in_transaction_reflects_uncommitted_changes() {
+    let db = make_test_grovedb();
+    let initial_root_hash = db.root_hash(None).unwrap().expect("cannot get root hash");
+
+    let tx = db.start_transaction();
+    assert_eq!(
+        db.root_hash_in_transaction(&tx)
+            .unwrap()
+            .expect("cannot get root hash"),
+        initial_root_hash
+    );
 
     db.insert(
-        [b"key1".as_ref()].as_ref(),
-        b"key4",
-        element3.clone(),
-        None,
+        [TEST_LEAF].as_ref(),
+        b"key",
+        Element::new_item(b"value".to_vec()),
         None,
+        Some(&tx),
     )
     .unwrap()
-    .expect("cannot insert into GroveDB");
+    .expect("successful insert");
 
+    let tx_root_hash = db
+        .root_hash_in_transaction(&tx)
+        .unwrap()
+        .expect("cannot get root hash");
+    assert_ne!(tx_root_hash, initial_root_hash);
     assert_eq!(
-        checkpoint_db
-            .get([b"key1".as_ref()].as_ref(), b"key4", None)
-            .unwrap()
-            .expect("cannot get from checkpoint"),
-        element2,
+        db.root_hash(None).unwrap().expect("cannot get root hash"),
+        initial_root_hash
     );
 
+    db.commit_transaction(tx)
+        .unwrap()
+        .expect("cannot commit transaction");
     assert_eq!(
-        db.get([b"key1".as_ref()].as_ref(), b"key4", None)
-            .unwrap()
-            .expect("cannot get from GroveDB"),
-        element3
+        db.root_hash(None).unwrap().expect("cannot get root hash"),
+        tx_root_hash
     );
+}
 
-    checkpoint_db
-        .insert(
-            [b"key1".as_ref()].as_ref(),
-            b"key5",
-            element3.clone(),
-            None,
-            None,
-        )
-        .unwrap()
-        .expect("cannot insert into checkpoint");
+#[test]
+fn test_verify_query_streaming_matches_verify_query() {
+    let db = make_deep_tree();
 
-    db.insert([b"key1".as_ref()].as_ref(), b"key6", element3, None, None)
-        .unwrap()
-        .expect("cannot insert into GroveDB");
+    let mut items_query = Query::new();
+    items_query.insert_all();
+    let mut subquery = Query::new();
+    subquery.insert_all();
+    subquery.set_subquery(items_query);
+    let mut query = Query::new();
+    query.insert_all();
+    query.set_subquery(subquery);
+    let path_query = PathQuery::new_unsized(vec![DEEP_LEAF.to_vec()], query);
 
-    assert!(matches!(
-        checkpoint_db
-            .get([b"key1".as_ref()].as_ref(), b"key6", None)
-            .unwrap(),
-        Err(Error::PathKeyNotFound(_))
-    ));
+    let proof = db.prove_query(&path_query).unwrap().unwrap();
+    let (hash, result_set) =
+        GroveDb::verify_query(proof.as_slice(), &path_query).expect("should execute proof");
+    let (streamed_hash, streamed) = GroveDb::verify_query_streaming(proof.as_slice(), &path_query)
+        .expect("should execute proof");
+    drop(proof);
 
-    assert!(matches!(
-        db.get([b"key1".as_ref()].as_ref(), b"key5", None).unwrap(),
-        Err(Error::PathKeyNotFound(_))
-    ));
+    assert_eq!(streamed_hash, hash);
+    assert_eq!(hash, db.root_hash(None).unwrap().unwrap());
+    let streamed = streamed
+        .collect::<Result<Vec<_>, Error>>()
+        .expect("should stream results");
+    assert_eq!(streamed.len(), 11);
+    assert_eq!(streamed.len(), result_set.len());
+    for ((key, value), (_, expected_key, expected_element)) in streamed.iter().zip(result_set) {
+        assert_eq!(key, &expected_key);
+        assert_eq!(
+            Some(Element::deserialize(value).expect("should deserialize element")),
+            expected_element
+        );
+    }
 }
 
 #[test]
-fn test_is_empty_tree() {
+fn test_root_subtree_has_root_key() {
+    let db = make_test_grovedb();
+    let storage = db.db.get_storage_context(EMPTY_PATH, None).unwrap();
+    let root_merk = Merk::open_base(storage, false)
+        .unwrap()
+        .expect("expected to get root merk");
+    let (_, root_key, _) = root_merk
+        .root_hash_key_and_sum()
+        .unwrap()
+        .expect("expected to get root hash, key and sum");
+    assert!(root_key.is_some())
+}
+
+#[test]
+fn test_get_subtree() {
     let db = make_test_grovedb();
+    let element = Element::new_item(b"ayy".to_vec());
 
-    // Create an empty tree with no elements
+    // Returns error is subtree is not valid
+    {
+        let subtree = db.get([TEST_LEAF].as_ref(), b"invalid_tree", None).unwrap();
+        assert!(subtree.is_err());
+
+        // Doesn't return an error for subtree that exists but empty
+        let subtree = db.get(EMPTY_PATH, TEST_LEAF, None).unwrap();
+        assert!(subtree.is_ok());
+    }
+
+    // Insert some nested subtrees
     db.insert(
         [TEST_LEAF].as_ref(),
-        b"innertree",
+        b"key1",
         Element::empty_tree(),
         None,
         None,
     )
     .unwrap()
-    .unwrap();
+    .expect("successful subtree 1 insert");
 
-    assert!(db
-        .is_empty_tree([TEST_LEAF, b"innertree"].as_ref(), None)
+    let key1_tree = db
+        .get(EMPTY_PATH, TEST_LEAF, None)
         .unwrap()
-        .expect("path is valid tree"));
+        .expect("expected to get a root tree");
+
+    assert!(
+        matches!(key1_tree, Element::Tree(Some(_), _)),
+        "{}",
+        format!(
+            "expected tree with root key, got {:?}",
+            if let Element::Tree(tree, ..) = key1_tree {
+                format!("{:?}", tree)
+            } else {
+                "not a tree".to_string()
+            }
+        )
+    );
 
-    // add an element to the tree to make it non empty
     db.insert(
-        [TEST_LEAF, b"innertree"].as_ref(),
-        b"key1",
-        Element::new_item(b"hello".to_vec()),
+        [TEST_LEAF, b"key1"].as_ref(),
+        b"key2",
+        Element::empty_tree(),
         None,
         None,
     )
     .unwrap()
-    .unwrap();
-    assert!(!db
-        .is_empty_tree([TEST_LEAF, b"innertree"].as_ref(), None)
-        .unwrap()
-        .expect("path is valid tree"));
-}
-
-#[test]
-fn transaction_should_be_aborted_when_rollback_is_called() {
-    let item_key = b"key3";
-
-    let db = make_test_grovedb();
-    let transaction = db.start_transaction();
-
-    let element1 = Element::new_item(b"ayy".to_vec());
-
-    let result = db
-        .insert(
-            [TEST_LEAF].as_ref(),
-            item_key,
-            element1,
-            None,
-            Some(&transaction),
-        )
-        .unwrap();
-
-    assert!(matches!(result, Ok(())));
-
-    db.rollback_transaction(&transaction).unwrap();
+    .expect("successful subtree 2 insert");
 
-    let result = db
-        .get([TEST_LEAF].as_ref(), item_key, Some(&transaction))
-        .unwrap();
-    assert!(matches!(result, Err(Error::PathKeyNotFound(_))));
-}
+    // Insert an element into subtree
+    db.insert(
+        [TEST_LEAF, b"key1", b"key2"].as_ref(),
+        b"key3",
+        element.clone(),
+        None,
+        None,
+    )
+    .unwrap()
+    .expect("successful value insert");
+    db.insert(
+        [TEST_LEAF].as_ref(),
+        b"key4",
+        Element::empty_tree(),
+        None,
+        None,
+    )
+    .unwrap()
+    .expect("successful subtree 3 insert");
 
-#[test]
-fn transaction_should_be_aborted() {
-    let db = make_test_grovedb();
+    // Retrieve subtree instance
+    // Check if it returns the same instance that was inserted
+    {
+        let subtree_storage = db
+            .grove_db
+            .db
+            .get_storage_context([TEST_LEAF, b"key1", b"key2"].as_ref().into(), None)
+            .unwrap();
+        let subtree =
+            Merk::open_layered_with_root_key(subtree_storage, Some(b"key3".to_vec()), false)
+                .unwrap()
+                .expect("cannot open merk");
+        let result_element = Element::get(&subtree, b"key3", true).unwrap().unwrap();
+        assert_eq!(result_element, Element::new_item(b"ayy".to_vec()));
+    }
+    // Insert a new tree with transaction
     let transaction = db.start_transaction();
 
-    let item_key = b"key3";
-    let element = Element::new_item(b"ayy".to_vec());
+    db.insert(
+        [TEST_LEAF, b"key1"].as_ref(),
+        b"innertree",
+        Element::empty_tree(),
+        None,
+        Some(&transaction),
+    )
+    .unwrap()
+    .expect("successful subtree insert");
 
     db.insert(
-        [TEST_LEAF].as_ref(),
-        item_key,
+        [TEST_LEAF, b"key1", b"innertree"].as_ref(),
+        b"key4",
         element,
         None,
         Some(&transaction),
     )
     .unwrap()
-    .unwrap();
+    .expect("successful value insert");
 
-    drop(transaction);
+    // Retrieve subtree instance with transaction
+    let subtree_storage = db
+        .grove_db
+        .db
+        .get_transactional_storage_context(
+            [TEST_LEAF, b"key1", b"innertree"].as_ref().into(),
+            None,
+            &transaction,
+        )
+        .unwrap();
+    let subtree = Merk::open_layered_with_root_key(subtree_storage, Some(b"key4".to_vec()), false)
+        .unwrap()
+        .expect("cannot open merk");
+    let result_element = Element::get(&subtree, b"key4", true).unwrap().unwrap();
+    assert_eq!(result_element, Element::new_item(b"ayy".to_vec()));
 
-    // Transactional data shouldn't be committed to the main database
-    let result = db.get([TEST_LEAF].as_ref(), item_key, None).unwrap();
-    assert!(matches!(result, Err(Error::PathKeyNotFound(_))));
+    // Should be able to retrieve instances created before transaction
+    let subtree_storage = db
+        .grove_db
+        .db
+        .get_storage_context([TEST_LEAF, b"key1", b"key2"].as_ref().into(), None)
+        .unwrap();
+    let subtree = Merk::open_layered_with_root_key(subtree_storage, Some(b"key3".to_vec()), false)
+        .unwrap()
+        .expect("cannot open merk");
+    let result_element = Element::get(&subtree, b"key3", true).unwrap().unwrap();
+    assert_eq!(result_element, Element::new_item(b"ayy".to_vec()));
 }
 
 #[test]
-fn test_subtree_pairs_iterator() {
+fn test_get_full_query() {
     let db = make_test_grovedb();
-    let element = Element::new_item(b"ayy".to_vec());
-    let element2 = Element::new_item(b"lmao".to_vec());
 
-    // Insert some nested subtrees
+    // Insert a couple of subtrees first
     db.insert(
         [TEST_LEAF].as_ref(),
-        b"subtree1",
-        Element::empty_tree(),
-        None,
-        None,
-    )
-    .unwrap()
-    .expect("successful subtree 1 insert");
-    db.insert(
-        [TEST_LEAF, b"subtree1"].as_ref(),
-        b"subtree11",
+        b"key1",
         Element::empty_tree(),
         None,
         None,
     )
     .unwrap()
-    .expect("successful subtree 2 insert");
-    // Insert an element into subtree
-    db.insert(
-        [TEST_LEAF, b"subtree1", b"subtree11"].as_ref(),
-        b"key1",
-        element.clone(),
+    .expect("successful subtree insert");
+    db.insert(
+        [TEST_LEAF].as_ref(),
+        b"key2",
+        Element::empty_tree(),
         None,
         None,
     )
     .unwrap()
-    .expect("successful value insert");
-    assert_eq!(
-        db.get(
-            [TEST_LEAF, b"subtree1", b"subtree11"].as_ref(),
-            b"key1",
-            None
-        )
-        .unwrap()
-        .expect("successful get 1"),
-        element
-    );
+    .expect("successful subtree insert");
+    // Insert some elements into subtree
     db.insert(
-        [TEST_LEAF, b"subtree1", b"subtree11"].as_ref(),
-        b"key0",
-        element.clone(),
+        [TEST_LEAF, b"key1"].as_ref(),
+        b"key3",
+        Element::new_item(b"ayya".to_vec()),
         None,
         None,
     )
     .unwrap()
     .expect("successful value insert");
     db.insert(
-        [TEST_LEAF, b"subtree1"].as_ref(),
-        b"subtree12",
-        Element::empty_tree(),
+        [TEST_LEAF, b"key1"].as_ref(),
+        b"key4",
+        Element::new_item(b"ayyb".to_vec()),
         None,
         None,
     )
     .unwrap()
-    .expect("successful subtree 3 insert");
+    .expect("successful value insert");
     db.insert(
-        [TEST_LEAF, b"subtree1"].as_ref(),
-        b"key1",
-        element.clone(),
+        [TEST_LEAF, b"key1"].as_ref(),
+        b"key5",
+        Element::new_item(b"ayyc".to_vec()),
         None,
         None,
     )
     .unwrap()
     .expect("successful value insert");
     db.insert(
-        [TEST_LEAF, b"subtree1"].as_ref(),
-        b"key2",
-        element2.clone(),
+        [TEST_LEAF, b"key2"].as_ref(),
+        b"key6",
+        Element::new_item(b"ayyd".to_vec()),
         None,
         None,
     )
     .unwrap()
     .expect("successful value insert");
 
-    // Iterate over subtree1 to see if keys of other subtrees messed up
-    // let mut iter = db
-    //     .elements_iterator([TEST_LEAF, b"subtree1"].as_ref(), None)
-    //     .expect("cannot create iterator");
-    let storage_context = db
-        .grove_db
-        .db
-        .get_storage_context([TEST_LEAF, b"subtree1"].as_ref().into(), None)
-        .unwrap();
-    let mut iter = Element::iterator(storage_context.raw_iter()).unwrap();
-    assert_eq!(
-        iter.next_element().unwrap().unwrap(),
-        Some((b"key1".to_vec(), element))
-    );
+    //          Test_Leaf
+    // ___________________________
+    //         /        \
+    //     key1           key2
+    // ___________________________
+    //      |              |
+    //     key4          key6
+    //     / \
+    //   key3 key5
+    //
+
+    let path1 = vec![TEST_LEAF.to_vec(), b"key1".to_vec()];
+    let path2 = vec![TEST_LEAF.to_vec(), b"key2".to_vec()];
+    let mut query1 = Query::new();
+    let mut query2 = Query::new();
+    query1.insert_range_inclusive(b"key3".to_vec()..=b"key4".to_vec());
+    query2.insert_key(b"key6".to_vec());
+
+    let path_query1 = PathQuery::new_unsized(path1, query1);
+    // should get back key3, key4
+    let path_query2 = PathQuery::new_unsized(path2, query2);
+    // should get back key6
+
     assert_eq!(
-        iter.next_element().unwrap().unwrap(),
-        Some((b"key2".to_vec(), element2))
+        db.query_many_raw(
+            &[&path_query1, &path_query2],
+            true,
+            QueryKeyElementPairResultType,
+            None
+        )
+        .unwrap()
+        .expect("expected successful get_query")
+        .to_key_elements(),
+        vec![
+            (b"key3".to_vec(), Element::new_item(b"ayya".to_vec())),
+            (b"key4".to_vec(), Element::new_item(b"ayyb".to_vec())),
+            (b"key6".to_vec(), Element::new_item(b"ayyd".to_vec())),
+        ]
     );
-    let subtree_element = iter.next_element().unwrap().unwrap().unwrap();
-    assert_eq!(subtree_element.0, b"subtree11".to_vec());
-    assert!(matches!(subtree_element.1, Element::Tree(..)));
-    let subtree_element = iter.next_element().unwrap().unwrap().unwrap();
-    assert_eq!(subtree_element.0, b"subtree12".to_vec());
-    assert!(matches!(subtree_element.1, Element::Tree(..)));
-    assert!(matches!(iter.next_element().unwrap(), Ok(None)));
 }
 
 #[test]
-fn test_find_subtrees() {
+fn test_aux_uses_separate_cf() {
     let element = Element::new_item(b"ayy".to_vec());
     let db = make_test_grovedb();
     // Insert some nested subtrees
@@ -2331,579 +4074,1126 @@ fn test_find_subtrees() {
     db.insert(
         [TEST_LEAF, b"key1", b"key2"].as_ref(),
         b"key3",
-        element,
+        element.clone(),
         None,
         None,
     )
     .unwrap()
     .expect("successful value insert");
-    db.insert(
-        [TEST_LEAF].as_ref(),
-        b"key4",
-        Element::empty_tree(),
-        None,
-        None,
-    )
-    .unwrap()
-    .expect("successful subtree 3 insert");
-    let subtrees = db
-        .find_subtrees(&[TEST_LEAF].as_ref().into(), None)
+
+    db.put_aux(b"key1", b"a", None, None)
         .unwrap()
-        .expect("cannot get subtrees");
+        .expect("cannot put aux");
+    db.put_aux(b"key2", b"b", None, None)
+        .unwrap()
+        .expect("cannot put aux");
+    db.put_aux(b"key3", b"c", None, None)
+        .unwrap()
+        .expect("cannot put aux");
+    db.delete_aux(b"key3", None, None)
+        .unwrap()
+        .expect("cannot delete from aux");
+
     assert_eq!(
-        vec![
-            vec![TEST_LEAF],
-            vec![TEST_LEAF, b"key1"],
-            vec![TEST_LEAF, b"key4"],
-            vec![TEST_LEAF, b"key1", b"key2"],
-        ],
-        subtrees
+        db.get([TEST_LEAF, b"key1", b"key2"].as_ref(), b"key3", None)
+            .unwrap()
+            .expect("cannot get element"),
+        element
+    );
+    assert_eq!(
+        db.get_aux(b"key1", None)
+            .unwrap()
+            .expect("cannot get from aux"),
+        Some(b"a".to_vec())
+    );
+    assert_eq!(
+        db.get_aux(b"key2", None)
+            .unwrap()
+            .expect("cannot get from aux"),
+        Some(b"b".to_vec())
+    );
+    assert_eq!(
+        db.get_aux(b"key3", None)
+            .unwrap()
+            .expect("cannot get from aux"),
+        None
+    );
+    assert_eq!(
+        db.get_aux(b"key4", None)
+            .unwrap()
+            .expect("cannot get from aux"),
+        None
     );
 }
 
 #[test]
-fn test_root_subtree_has_root_key() {
+fn test_aux_with_transaction() {
+    let element = Element::new_item(b"ayy".to_vec());
+    let aux_value = b"ayylmao".to_vec();
+    let key = b"key".to_vec();
     let db = make_test_grovedb();
-    let storage = db.db.get_storage_context(EMPTY_PATH, None).unwrap();
-    let root_merk = Merk::open_base(storage, false)
+    let transaction = db.start_transaction();
+
+    // Insert a regular data with aux data in the same transaction
+    db.insert(
+        [TEST_LEAF].as_ref(),
+        &key,
+        element,
+        None,
+        Some(&transaction),
+    )
+    .unwrap()
+    .expect("unable to insert");
+    db.put_aux(&key, &aux_value, None, Some(&transaction))
         .unwrap()
-        .expect("expected to get root merk");
-    let (_, root_key, _) = root_merk
-        .root_hash_key_and_sum()
+        .expect("unable to insert aux value");
+    assert_eq!(
+        db.get_aux(&key, Some(&transaction))
+            .unwrap()
+            .expect("unable to get aux value"),
+        Some(aux_value.clone())
+    );
+    // Cannot reach the data outside of transaction
+    assert_eq!(
+        db.get_aux(&key, None)
+            .unwrap()
+            .expect("unable to get aux value"),
+        None
+    );
+    // And should be able to get data when committed
+    db.commit_transaction(transaction)
         .unwrap()
-        .expect("expected to get root hash, key and sum");
-    assert!(root_key.is_some())
+        .expect("unable to commit transaction");
+    assert_eq!(
+        db.get_aux(&key, None)
+            .unwrap()
+            .expect("unable to get committed aux value"),
+        Some(aux_value)
+    );
 }
 
 #[test]
-fn test_get_subtree() {
+fn test_root_hash() {
     let db = make_test_grovedb();
-    let element = Element::new_item(b"ayy".to_vec());
-
-    // Returns error is subtree is not valid
-    {
-        let subtree = db.get([TEST_LEAF].as_ref(), b"invalid_tree", None).unwrap();
-        assert!(subtree.is_err());
-
-        // Doesn't return an error for subtree that exists but empty
-        let subtree = db.get(EMPTY_PATH, TEST_LEAF, None).unwrap();
-        assert!(subtree.is_ok());
-    }
-
-    // Insert some nested subtrees
+    // Check hashes are different if tree is edited
+    let old_root_hash = db.root_hash(None).unwrap();
     db.insert(
         [TEST_LEAF].as_ref(),
         b"key1",
-        Element::empty_tree(),
+        Element::new_item(b"ayy".to_vec()),
         None,
         None,
-    )
-    .unwrap()
-    .expect("successful subtree 1 insert");
-
-    let key1_tree = db
-        .get(EMPTY_PATH, TEST_LEAF, None)
-        .unwrap()
-        .expect("expected to get a root tree");
-
-    assert!(
-        matches!(key1_tree, Element::Tree(Some(_), _)),
-        "{}",
-        format!(
-            "expected tree with root key, got {:?}",
-            if let Element::Tree(tree, ..) = key1_tree {
-                format!("{:?}", tree)
-            } else {
-                "not a tree".to_string()
-            }
-        )
-    );
+    )
+    .unwrap()
+    .expect("unable to insert an item");
+    assert_ne!(old_root_hash.unwrap(), db.root_hash(None).unwrap().unwrap());
+
+    // Check isolation
+    let transaction = db.start_transaction();
 
     db.insert(
-        [TEST_LEAF, b"key1"].as_ref(),
+        [TEST_LEAF].as_ref(),
         b"key2",
-        Element::empty_tree(),
-        None,
+        Element::new_item(b"ayy".to_vec()),
         None,
+        Some(&transaction),
     )
     .unwrap()
-    .expect("successful subtree 2 insert");
+    .expect("unable to insert an item");
+    let root_hash_outside = db.root_hash(None).unwrap().unwrap();
+    assert_ne!(
+        db.root_hash(Some(&transaction)).unwrap().unwrap(),
+        root_hash_outside
+    );
 
-    // Insert an element into subtree
+    assert_eq!(db.root_hash(None).unwrap().unwrap(), root_hash_outside);
+    db.commit_transaction(transaction).unwrap().unwrap();
+    assert_ne!(db.root_hash(None).unwrap().unwrap(), root_hash_outside);
+}
+
+#[test]
+fn test_get_non_existing_root_leaf() {
+    let db = make_test_grovedb();
+    assert!(matches!(db.get(EMPTY_PATH, b"ayy", None).unwrap(), Err(_)));
+}
+
+#[test]
+fn test_check_subtree_exists_function() {
+    let db = make_test_grovedb();
     db.insert(
-        [TEST_LEAF, b"key1", b"key2"].as_ref(),
-        b"key3",
-        element.clone(),
+        [TEST_LEAF].as_ref(),
+        b"key_scalar",
+        Element::new_item(b"ayy".to_vec()),
         None,
         None,
     )
     .unwrap()
-    .expect("successful value insert");
+    .expect("cannot insert item");
     db.insert(
         [TEST_LEAF].as_ref(),
-        b"key4",
+        b"key_subtree",
         Element::empty_tree(),
         None,
         None,
     )
     .unwrap()
-    .expect("successful subtree 3 insert");
+    .expect("cannot insert item");
 
-    // Retrieve subtree instance
-    // Check if it returns the same instance that was inserted
-    {
-        let subtree_storage = db
-            .grove_db
-            .db
-            .get_storage_context([TEST_LEAF, b"key1", b"key2"].as_ref().into(), None)
-            .unwrap();
-        let subtree =
-            Merk::open_layered_with_root_key(subtree_storage, Some(b"key3".to_vec()), false)
-                .unwrap()
-                .expect("cannot open merk");
-        let result_element = Element::get(&subtree, b"key3", true).unwrap().unwrap();
-        assert_eq!(result_element, Element::new_item(b"ayy".to_vec()));
-    }
-    // Insert a new tree with transaction
-    let transaction = db.start_transaction();
+    // Empty tree path means root always exist
+    assert!(db
+        .check_subtree_exists_invalid_path(EMPTY_PATH.into(), None)
+        .unwrap()
+        .is_ok());
+
+    // TEST_LEAF should be a tree
+    assert!(db
+        .check_subtree_exists_invalid_path([TEST_LEAF].as_ref().into(), None)
+        .unwrap()
+        .is_ok());
+
+    // TEST_LEAF.key_subtree should be a tree
+    assert!(db
+        .check_subtree_exists_invalid_path([TEST_LEAF, b"key_subtree"].as_ref().into(), None)
+        .unwrap()
+        .is_ok());
+
+    // TEST_LEAF.key_scalar should NOT be a tree
+    assert!(matches!(
+        db.check_subtree_exists_invalid_path([TEST_LEAF, b"key_scalar"].as_ref().into(), None)
+            .unwrap(),
+        Err(Error::InvalidPath(_))
+    ));
+}
 
+#[test]
+fn test_tree_value_exists_method_no_tx() {
+    let db = make_test_grovedb();
+    // Test keys in non-root tree
     db.insert(
-        [TEST_LEAF, b"key1"].as_ref(),
-        b"innertree",
-        Element::empty_tree(),
+        [TEST_LEAF].as_ref(),
+        b"key",
+        Element::new_item(b"ayy".to_vec()),
+        None,
         None,
-        Some(&transaction),
     )
     .unwrap()
-    .expect("successful subtree insert");
+    .expect("cannot insert item");
+    assert!(db
+        .has_raw([TEST_LEAF].as_ref(), b"key", None)
+        .unwrap()
+        .unwrap());
+    assert!(!db
+        .has_raw([TEST_LEAF].as_ref(), b"badkey", None)
+        .unwrap()
+        .unwrap());
+
+    // Test keys for a root tree
+    db.insert(EMPTY_PATH, b"leaf", Element::empty_tree(), None, None)
+        .unwrap()
+        .expect("cannot insert item");
+
+    assert!(db.has_raw(EMPTY_PATH, b"leaf", None).unwrap().unwrap());
+    assert!(db.has_raw(EMPTY_PATH, TEST_LEAF, None).unwrap().unwrap());
+    assert!(!db.has_raw(EMPTY_PATH, b"badleaf", None).unwrap().unwrap());
+}
 
+#[test]
+fn test_tree_value_exists_method_tx() {
+    let db = make_test_grovedb();
+    let tx = db.start_transaction();
+    // Test keys in non-root tree
     db.insert(
-        [TEST_LEAF, b"key1", b"innertree"].as_ref(),
-        b"key4",
-        element,
+        [TEST_LEAF].as_ref(),
+        b"key",
+        Element::new_item(b"ayy".to_vec()),
         None,
-        Some(&transaction),
+        Some(&tx),
     )
     .unwrap()
-    .expect("successful value insert");
+    .expect("cannot insert item");
+    assert!(db
+        .has_raw([TEST_LEAF].as_ref(), b"key", Some(&tx))
+        .unwrap()
+        .unwrap());
+    assert!(!db
+        .has_raw([TEST_LEAF].as_ref(), b"key", None)
+        .unwrap()
+        .unwrap());
 
-    // Retrieve subtree instance with transaction
-    let subtree_storage = db
-        .grove_db
-        .db
-        .get_transactional_storage_context(
-            [TEST_LEAF, b"key1", b"innertree"].as_ref().into(),
-            None,
-            &transaction,
-        )
-        .unwrap();
-    let subtree = Merk::open_layered_with_root_key(subtree_storage, Some(b"key4".to_vec()), false)
+    // Test keys for a root tree
+    db.insert(EMPTY_PATH, b"leaf", Element::empty_tree(), None, Some(&tx))
         .unwrap()
-        .expect("cannot open merk");
-    let result_element = Element::get(&subtree, b"key4", true).unwrap().unwrap();
-    assert_eq!(result_element, Element::new_item(b"ayy".to_vec()));
+        .expect("cannot insert item");
+    assert!(db.has_raw(EMPTY_PATH, b"leaf", Some(&tx)).unwrap().unwrap());
+    assert!(!db.has_raw(EMPTY_PATH, b"leaf", None).unwrap().unwrap());
 
-    // Should be able to retrieve instances created before transaction
-    let subtree_storage = db
-        .grove_db
-        .db
-        .get_storage_context([TEST_LEAF, b"key1", b"key2"].as_ref().into(), None)
-        .unwrap();
-    let subtree = Merk::open_layered_with_root_key(subtree_storage, Some(b"key3".to_vec()), false)
+    db.commit_transaction(tx)
         .unwrap()
-        .expect("cannot open merk");
-    let result_element = Element::get(&subtree, b"key3", true).unwrap().unwrap();
-    assert_eq!(result_element, Element::new_item(b"ayy".to_vec()));
+        .expect("cannot commit transaction");
+    assert!(db
+        .has_raw([TEST_LEAF].as_ref(), b"key", None)
+        .unwrap()
+        .unwrap());
+    assert!(db.has_raw(EMPTY_PATH, b"leaf", None).unwrap().unwrap());
 }
 
 #[test]
-fn test_get_full_query() {
+fn test_tree_value_exists_method_tx_delete() {
     let db = make_test_grovedb();
+    let tx = db.start_transaction();
 
-    // Insert a couple of subtrees first
     db.insert(
         [TEST_LEAF].as_ref(),
-        b"key1",
-        Element::empty_tree(),
-        None,
+        b"key",
+        Element::new_item(b"ayy".to_vec()),
         None,
+        Some(&tx),
     )
     .unwrap()
-    .expect("successful subtree insert");
+    .expect("cannot insert item");
+    assert!(db
+        .has_raw([TEST_LEAF].as_ref(), b"key", Some(&tx))
+        .unwrap()
+        .unwrap());
+
+    db.delete([TEST_LEAF].as_ref(), b"key", None, Some(&tx))
+        .unwrap()
+        .expect("cannot delete item");
+    assert!(!db
+        .has_raw([TEST_LEAF].as_ref(), b"key", Some(&tx))
+        .unwrap()
+        .unwrap());
+    assert!(!db
+        .has_raw([TEST_LEAF].as_ref(), b"key", None)
+        .unwrap()
+        .unwrap());
+}
+
+#[test]
+fn test_empty_key_insert_get_delete() {
+    let db = make_test_grovedb();
+
     db.insert(
         [TEST_LEAF].as_ref(),
-        b"key2",
-        Element::empty_tree(),
+        b"",
+        Element::new_item(b"empty".to_vec()),
         None,
         None,
     )
     .unwrap()
-    .expect("successful subtree insert");
-    // Insert some elements into subtree
+    .expect("successful insert under empty key");
+    assert_eq!(
+        db.get([TEST_LEAF].as_ref(), b"", None)
+            .unwrap()
+            .expect("successful get"),
+        Element::new_item(b"empty".to_vec())
+    );
+    assert!(db
+        .has_raw([TEST_LEAF].as_ref(), b"", None)
+        .unwrap()
+        .expect("successful has_raw"));
+
+    db.delete([TEST_LEAF].as_ref(), b"", None, None)
+        .unwrap()
+        .expect("successful delete");
+    assert!(matches!(
+        db.get([TEST_LEAF].as_ref(), b"", None).unwrap(),
+        Err(Error::PathKeyNotFound(_))
+    ));
+
+    // A subtree under an empty key is distinct from its parent
+    db.insert([TEST_LEAF].as_ref(), b"", Element::empty_tree(), None, None)
+        .unwrap()
+        .expect("successful subtree insert under empty key");
     db.insert(
-        [TEST_LEAF, b"key1"].as_ref(),
-        b"key3",
-        Element::new_item(b"ayya".to_vec()),
+        [TEST_LEAF, b""].as_ref(),
+        b"",
+        Element::new_item(b"nested".to_vec()),
+        None,
+        None,
+    )
+    .unwrap()
+    .expect("successful insert into subtree with empty key");
+    assert_eq!(
+        db.get([TEST_LEAF, b""].as_ref(), b"", None)
+            .unwrap()
+            .expect("successful get"),
+        Element::new_item(b"nested".to_vec())
+    );
+    assert!(matches!(
+        db.get([TEST_LEAF].as_ref(), b"", None).unwrap(),
+        Ok(Element::Tree(Some(_), _))
+    ));
+
+    db.delete([TEST_LEAF, b""].as_ref(), b"", None, None)
+        .unwrap()
+        .expect("successful delete");
+    db.delete([TEST_LEAF].as_ref(), b"", None, None)
+        .unwrap()
+        .expect("successful delete of emptied subtree");
+    assert!(db
+        .get([TEST_LEAF, b""].as_ref(), b"", None)
+        .unwrap()
+        .is_err());
+
+    // The same holds within a transaction
+    let tx = db.start_transaction();
+    db.insert(
+        [TEST_LEAF].as_ref(),
+        b"",
+        Element::new_item(b"tx".to_vec()),
+        None,
+        Some(&tx),
+    )
+    .unwrap()
+    .expect("successful insert under empty key");
+    assert_eq!(
+        db.get([TEST_LEAF].as_ref(), b"", Some(&tx))
+            .unwrap()
+            .expect("successful get"),
+        Element::new_item(b"tx".to_vec())
+    );
+    assert!(matches!(
+        db.get([TEST_LEAF].as_ref(), b"", None).unwrap(),
+        Err(Error::PathKeyNotFound(_))
+    ));
+    db.delete([TEST_LEAF].as_ref(), b"", None, Some(&tx))
+        .unwrap()
+        .expect("successful delete");
+    assert!(matches!(
+        db.get([TEST_LEAF].as_ref(), b"", Some(&tx)).unwrap(),
+        Err(Error::PathKeyNotFound(_))
+    ));
+}
+
+#[test]
+fn test_empty_key_batch_insert_and_delete() {
+    let db = make_test_grovedb();
+
+    db.apply_batch(
+        vec![GroveDbOp::insert_op(
+            vec![TEST_LEAF.to_vec()],
+            vec![],
+            Element::new_item(b"empty".to_vec()),
+        )],
         None,
         None,
     )
     .unwrap()
-    .expect("successful value insert");
-    db.insert(
-        [TEST_LEAF, b"key1"].as_ref(),
-        b"key4",
-        Element::new_item(b"ayyb".to_vec()),
+    .expect("successful batch insert under empty key");
+    assert_eq!(
+        db.get([TEST_LEAF].as_ref(), b"", None)
+            .unwrap()
+            .expect("successful get"),
+        Element::new_item(b"empty".to_vec())
+    );
+
+    db.apply_batch(
+        vec![GroveDbOp::delete_op(vec![TEST_LEAF.to_vec()], vec![])],
         None,
         None,
     )
     .unwrap()
-    .expect("successful value insert");
+    .expect("successful batch delete under empty key");
+    assert!(matches!(
+        db.get([TEST_LEAF].as_ref(), b"", None).unwrap(),
+        Err(Error::PathKeyNotFound(_))
+    ));
+}
+
+#[test]
+fn test_optimistic_transactions_conflict_on_commit() {
+    let db = make_test_grovedb();
+    let tx1 = db.start_transaction();
+    let tx2 = db.start_transaction();
+
+    // Both writes proceed, conflicts are only detected on commit
     db.insert(
-        [TEST_LEAF, b"key1"].as_ref(),
-        b"key5",
-        Element::new_item(b"ayyc".to_vec()),
-        None,
+        [TEST_LEAF].as_ref(),
+        b"key",
+        Element::new_item(b"first".to_vec()),
         None,
+        Some(&tx1),
     )
     .unwrap()
-    .expect("successful value insert");
+    .expect("successful insert");
     db.insert(
-        [TEST_LEAF, b"key2"].as_ref(),
-        b"key6",
-        Element::new_item(b"ayyd".to_vec()),
-        None,
+        [TEST_LEAF].as_ref(),
+        b"key",
+        Element::new_item(b"second".to_vec()),
         None,
+        Some(&tx2),
     )
     .unwrap()
-    .expect("successful value insert");
+    .expect("successful insert");
 
-    //          Test_Leaf
-    // ___________________________
-    //         /        \
-    //     key1           key2
-    // ___________________________
-    //      |              |
-    //     key4          key6
-    //     / \
-    //   key3 key5
-    //
+    db.commit_transaction(tx1)
+        .unwrap()
+        .expect("first commit succeeds");
+    assert!(db.commit_transaction(tx2).unwrap().is_err());
+    assert_eq!(
+        db.get([TEST_LEAF].as_ref(), b"key", None)
+            .unwrap()
+            .expect("successful get"),
+        Element::new_item(b"first".to_vec())
+    );
+}
 
-    let path1 = vec![TEST_LEAF.to_vec(), b"key1".to_vec()];
-    let path2 = vec![TEST_LEAF.to_vec(), b"key2".to_vec()];
-    let mut query1 = Query::new();
-    let mut query2 = Query::new();
-    query1.insert_range_inclusive(b"key3".to_vec()..=b"key4".to_vec());
-    query2.insert_key(b"key6".to_vec());
+#[test]
+fn test_path_errors_carry_path_and_key() {
+    let db = make_test_grovedb();
 
-    let path_query1 = PathQuery::new_unsized(path1, query1);
-    // should get back key3, key4
-    let path_query2 = PathQuery::new_unsized(path2, query2);
-    // should get back key6
+    match db.get([TEST_LEAF].as_ref(), b"missing", None).unwrap() {
+        Err(Error::PathKeyNotFound(detail)) => {
+            assert_eq!(detail.path, vec![TEST_LEAF.to_vec()]);
+            assert_eq!(detail.key, Some(b"missing".to_vec()));
+            assert_eq!(
+                Error::PathKeyNotFound(detail).to_string(),
+                "path key not found: key not found in Merk for get: 6d697373696e67"
+            );
+        }
+        result => panic!("expected a missing key, got {:?}", result),
+    }
 
-    assert_eq!(
-        db.query_many_raw(
-            &[&path_query1, &path_query2],
-            true,
-            QueryKeyElementPairResultType,
-            None
-        )
+    match db
+        .get([TEST_LEAF, b"missing"].as_ref(), b"key", None)
         .unwrap()
-        .expect("expected successful get_query")
-        .to_key_elements(),
-        vec![
-            (b"key3".to_vec(), Element::new_item(b"ayya".to_vec())),
-            (b"key4".to_vec(), Element::new_item(b"ayyb".to_vec())),
-            (b"key6".to_vec(), Element::new_item(b"ayyd".to_vec())),
-        ]
-    );
+    {
+        Err(Error::PathParentLayerNotFound(detail)) => {
+            assert_eq!(detail.path, vec![TEST_LEAF.to_vec(), b"missing".to_vec()]);
+            assert_eq!(detail.key, None);
+            assert!(detail
+                .message
+                .starts_with("could not get key 6d697373696e67 for parent"));
+            assert_eq!(
+                Error::PathParentLayerNotFound(detail.clone()).to_string(),
+                format!("path parent layer not found: {}", detail.message)
+            );
+        }
+        result => panic!("expected a missing parent layer, got {:?}", result),
+    }
+
+    match db
+        .check_subtree_exists_path_not_found([TEST_LEAF, b"missing"].as_ref().into(), None)
+        .unwrap()
+    {
+        Err(Error::PathNotFound(detail)) => {
+            assert_eq!(detail.path, vec![TEST_LEAF.to_vec(), b"missing".to_vec()]);
+            assert_eq!(detail.key, None);
+            assert_eq!(
+                Error::PathNotFound(detail).to_string(),
+                "path not found: subtree doesn't exist at path [\"746573745f6c656166\", \
+                 \"6d697373696e67\"]"
+            );
+        }
+        result => panic!("expected a missing path, got {:?}", result),
+    }
 }
 
 #[test]
-fn test_aux_uses_separate_cf() {
-    let element = Element::new_item(b"ayy".to_vec());
+fn test_transaction_is_empty() {
     let db = make_test_grovedb();
-    // Insert some nested subtrees
+    let tx = db.start_transaction();
+    assert!(db.transaction_is_empty(&tx));
+
     db.insert(
         [TEST_LEAF].as_ref(),
-        b"key1",
-        Element::empty_tree(),
-        None,
+        b"key",
+        Element::new_item(b"value".to_vec()),
         None,
+        Some(&tx),
     )
     .unwrap()
-    .expect("successful subtree 1 insert");
+    .expect("successful insert");
+    assert!(!db.transaction_is_empty(&tx));
+
+    db.delete([TEST_LEAF].as_ref(), b"key", None, Some(&tx))
+        .unwrap()
+        .expect("successful delete");
+    assert!(!db.transaction_is_empty(&tx));
+}
+
+#[test]
+fn test_last_key_in_subtree() {
+    let db = make_test_grovedb();
     db.insert(
-        [TEST_LEAF, b"key1"].as_ref(),
-        b"key2",
+        [TEST_LEAF].as_ref(),
+        b"innertree",
         Element::empty_tree(),
         None,
         None,
     )
     .unwrap()
-    .expect("successful subtree 2 insert");
-    // Insert an element into subtree
-    db.insert(
-        [TEST_LEAF, b"key1", b"key2"].as_ref(),
-        b"key3",
-        element.clone(),
-        None,
-        None,
-    )
-    .unwrap()
-    .expect("successful value insert");
+    .expect("successful subtree insert");
+    assert_eq!(
+        db.last_key_in_subtree([TEST_LEAF, b"innertree"].as_ref(), None)
+            .unwrap()
+            .expect("successful last key lookup"),
+        None
+    );
 
-    db.put_aux(b"key1", b"a", None, None)
-        .unwrap()
-        .expect("cannot put aux");
-    db.put_aux(b"key2", b"b", None, None)
-        .unwrap()
-        .expect("cannot put aux");
-    db.put_aux(b"key3", b"c", None, None)
-        .unwrap()
-        .expect("cannot put aux");
-    db.delete_aux(b"key3", None, None)
+    for key in [b"key2", b"key9", b"key1", b"key5"] {
+        db.insert(
+            [TEST_LEAF, b"innertree"].as_ref(),
+            key,
+            Element::new_item(b"value".to_vec()),
+            None,
+            None,
+        )
         .unwrap()
-        .expect("cannot delete from aux");
-
+        .expect("successful item insert");
+    }
     assert_eq!(
-        db.get([TEST_LEAF, b"key1", b"key2"].as_ref(), b"key3", None)
+        db.last_key_in_subtree([TEST_LEAF, b"innertree"].as_ref(), None)
             .unwrap()
-            .expect("cannot get element"),
-        element
+            .expect("successful last key lookup"),
+        Some(b"key9".to_vec())
     );
+    // Keys of nested subtrees don't leak into their parent
     assert_eq!(
-        db.get_aux(b"key1", None)
+        db.last_key_in_subtree([TEST_LEAF].as_ref(), None)
             .unwrap()
-            .expect("cannot get from aux"),
-        Some(b"a".to_vec())
+            .expect("successful last key lookup"),
+        Some(b"innertree".to_vec())
     );
+
+    let tx = db.start_transaction();
+    db.insert(
+        [TEST_LEAF, b"innertree"].as_ref(),
+        b"keyz",
+        Element::new_item(b"value".to_vec()),
+        None,
+        Some(&tx),
+    )
+    .unwrap()
+    .expect("successful item insert");
     assert_eq!(
-        db.get_aux(b"key2", None)
+        db.last_key_in_subtree([TEST_LEAF, b"innertree"].as_ref(), Some(&tx))
             .unwrap()
-            .expect("cannot get from aux"),
-        Some(b"b".to_vec())
+            .expect("successful last key lookup"),
+        Some(b"keyz".to_vec())
     );
+    assert!(db
+        .last_key_in_subtree([TEST_LEAF, b"missing"].as_ref(), None)
+        .unwrap()
+        .is_err());
+}
+
+#[test]
+fn test_resolved_element_size() {
+    let db = make_test_grovedb();
+    let item = Element::new_item_with_flags(b"a fairly long item value".to_vec(), Some(vec![7]));
+    db.insert([TEST_LEAF].as_ref(), b"item", item.clone(), None, None)
+        .unwrap()
+        .expect("successful item insert");
+    db.insert(
+        [TEST_LEAF].as_ref(),
+        b"ref1",
+        Element::new_reference(ReferencePathType::SiblingReference(b"item".to_vec())),
+        None,
+        None,
+    )
+    .unwrap()
+    .expect("successful reference insert");
+    db.insert(
+        [TEST_LEAF].as_ref(),
+        b"ref2",
+        Element::new_reference(ReferencePathType::SiblingReference(b"ref1".to_vec())),
+        None,
+        None,
+    )
+    .unwrap()
+    .expect("successful reference insert");
+
+    let direct = db.resolved_element_size([TEST_LEAF].as_ref(), b"item", None);
     assert_eq!(
-        db.get_aux(b"key3", None)
-            .unwrap()
-            .expect("cannot get from aux"),
-        None
+        direct.value.expect("successful size lookup"),
+        item.serialized_size() as u32
     );
+
+    let two_hops = db.resolved_element_size([TEST_LEAF].as_ref(), b"ref2", None);
     assert_eq!(
-        db.get_aux(b"key4", None)
-            .unwrap()
-            .expect("cannot get from aux"),
-        None
+        two_hops.value.expect("successful size lookup"),
+        item.serialized_size() as u32
     );
+    assert!(two_hops.cost.seek_count > direct.cost.seek_count);
+    assert!(two_hops.cost.storage_loaded_bytes > direct.cost.storage_loaded_bytes);
 }
 
 #[test]
-fn test_aux_with_transaction() {
-    let element = Element::new_item(b"ayy".to_vec());
-    let aux_value = b"ayylmao".to_vec();
-    let key = b"key".to_vec();
+fn test_walk_subtrees_prunes_branches() {
+    let db = make_deep_tree();
+    let pruned = vec![DEEP_LEAF.to_vec(), b"deep_node_1".to_vec()];
+    let mut visited: Vec<Vec<Vec<u8>>> = Vec::new();
+
+    db.walk_subtrees(&EMPTY_PATH, None, |path| {
+        visited.push(path.to_vec());
+        Ok(path != pruned.as_slice())
+    })
+    .unwrap()
+    .expect("successful walk");
+
+    let deep_node_2 = vec![DEEP_LEAF.to_vec(), b"deep_node_2".to_vec()];
+    assert!(visited.contains(&pruned));
+    assert!(visited.contains(&deep_node_2));
+    assert!(!visited
+        .iter()
+        .any(|path| path.len() > 2 && path[..2] == pruned[..]));
+    assert!(visited.contains(&vec![
+        DEEP_LEAF.to_vec(),
+        b"deep_node_2".to_vec(),
+        b"deeper_3".to_vec()
+    ]));
+    assert!(visited.contains(&vec![
+        DEEP_LEAF.to_vec(),
+        b"deep_node_2".to_vec(),
+        b"deeper_4".to_vec()
+    ]));
+
+    let mut calls = 0;
+    let result = db
+        .walk_subtrees(&EMPTY_PATH, None, |_| {
+            calls += 1;
+            if calls == 3 {
+                Err(Error::InternalError("stop"))
+            } else {
+                Ok(true)
+            }
+        })
+        .unwrap();
+    assert!(matches!(result, Err(Error::InternalError("stop"))));
+    assert_eq!(calls, 3);
+}
+
+#[test]
+fn test_check_top_level_subtree_exists_without_opening_merk() {
     let db = make_test_grovedb();
-    let transaction = db.start_transaction();
+    db.insert(
+        EMPTY_PATH,
+        b"root_item",
+        Element::new_item(b"value".to_vec()),
+        None,
+        None,
+    )
+    .unwrap()
+    .expect("successful item insert");
+
+    let fast_path = db.check_subtree_exists_path_not_found([TEST_LEAF].as_ref().into(), None);
+    assert!(fast_path.value.is_ok());
+
+    let merk_path = db.get_raw(EMPTY_PATH, TEST_LEAF, None);
+    assert!(merk_path.value.expect("expected tree").is_tree());
+    assert!(fast_path.cost.seek_count < merk_path.cost.seek_count);
+
+    let tx = db.start_transaction();
+    assert!(db
+        .check_subtree_exists_path_not_found([ANOTHER_TEST_LEAF].as_ref().into(), Some(&tx))
+        .unwrap()
+        .is_ok());
+    assert!(matches!(
+        db.check_subtree_exists_path_not_found([b"missing".as_ref()].as_ref().into(), None)
+            .unwrap(),
+        Err(Error::PathNotFound(_))
+    ));
+    assert!(matches!(
+        db.check_subtree_exists_path_not_found([b"root_item".as_ref()].as_ref().into(), None)
+            .unwrap(),
+        Err(Error::PathNotFound(_))
+    ));
+}
+
+#[test]
+fn test_post_commit_hook() {
+    let mut db = make_test_grovedb();
+    let summaries = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+    let hook_summaries = summaries.clone();
+    db.set_post_commit_hook(Box::new(move |summary: &CommitSummary| {
+        hook_summaries.lock().unwrap().push(summary.clone());
+    }));
+
+    // Rolled back transactions don't invoke the hook
+    let tx = db.start_transaction();
+    db.insert(
+        [TEST_LEAF].as_ref(),
+        b"key0",
+        Element::new_item(b"value0".to_vec()),
+        None,
+        Some(&tx),
+    )
+    .unwrap()
+    .expect("successful insert");
+    db.rollback_transaction(&tx).expect("successful rollback");
+    drop(tx);
+    assert!(summaries.lock().unwrap().is_empty());
+
+    let tx = db.start_transaction();
+    for (key, value) in [(b"key1", b"value1"), (b"key2", b"value2")] {
+        db.insert(
+            [TEST_LEAF].as_ref(),
+            key,
+            Element::new_item(value.to_vec()),
+            None,
+            Some(&tx),
+        )
+        .unwrap()
+        .expect("successful insert");
+    }
+    db.commit_transaction(tx)
+        .unwrap()
+        .expect("successful commit");
 
-    // Insert a regular data with aux data in the same transaction
+    {
+        let summaries = summaries.lock().unwrap();
+        assert_eq!(summaries.len(), 1);
+        assert_eq!(
+            summaries[0].root_hash,
+            db.root_hash(None).unwrap().expect("root hash")
+        );
+        // The test leaf and the root tree were written
+        assert_eq!(summaries[0].affected_subtrees, 2);
+    }
+
+    // Writes done without a transaction invoke the hook as well
     db.insert(
         [TEST_LEAF].as_ref(),
-        &key,
-        element,
+        b"key3",
+        Element::new_item(b"value3".to_vec()),
+        None,
         None,
-        Some(&transaction),
     )
     .unwrap()
-    .expect("unable to insert");
-    db.put_aux(&key, &aux_value, None, Some(&transaction))
-        .unwrap()
-        .expect("unable to insert aux value");
-    assert_eq!(
-        db.get_aux(&key, Some(&transaction))
-            .unwrap()
-            .expect("unable to get aux value"),
-        Some(aux_value.clone())
-    );
-    // Cannot reach the data outside of transaction
-    assert_eq!(
-        db.get_aux(&key, None)
-            .unwrap()
-            .expect("unable to get aux value"),
-        None
-    );
-    // And should be able to get data when committed
-    db.commit_transaction(transaction)
-        .unwrap()
-        .expect("unable to commit transaction");
+    .expect("successful insert");
+    let summaries = summaries.lock().unwrap();
+    assert_eq!(summaries.len(), 2);
     assert_eq!(
-        db.get_aux(&key, None)
-            .unwrap()
-            .expect("unable to get committed aux value"),
-        Some(aux_value)
+        summaries[1].root_hash,
+        db.root_hash(None).unwrap().expect("root hash")
     );
+    assert_eq!(summaries[1].affected_subtrees, 2);
 }
 
 #[test]
-fn test_root_hash() {
-    let db = make_test_grovedb();
-    // Check hashes are different if tree is edited
-    let old_root_hash = db.root_hash(None).unwrap();
+fn test_changes_since() {
+    let mut db = make_test_grovedb();
+    db.enable_change_journal(10);
+
     db.insert(
         [TEST_LEAF].as_ref(),
         b"key1",
-        Element::new_item(b"ayy".to_vec()),
+        Element::new_item(b"value1".to_vec()),
         None,
         None,
     )
     .unwrap()
-    .expect("unable to insert an item");
-    assert_ne!(old_root_hash.unwrap(), db.root_hash(None).unwrap().unwrap());
-
-    // Check isolation
-    let transaction = db.start_transaction();
+    .expect("successful insert");
+    let first_root_hash = db.root_hash(None).unwrap().expect("root hash");
 
+    let tx = db.start_transaction();
     db.insert(
         [TEST_LEAF].as_ref(),
         b"key2",
-        Element::new_item(b"ayy".to_vec()),
+        Element::new_item(b"value2".to_vec()),
         None,
-        Some(&transaction),
+        Some(&tx),
     )
     .unwrap()
-    .expect("unable to insert an item");
-    let root_hash_outside = db.root_hash(None).unwrap().unwrap();
-    assert_ne!(
-        db.root_hash(Some(&transaction)).unwrap().unwrap(),
-        root_hash_outside
+    .expect("successful insert");
+    db.delete([TEST_LEAF].as_ref(), b"key1", None, Some(&tx))
+        .unwrap()
+        .expect("successful delete");
+    // Changes of a pending transaction aren't visible outside of it
+    assert!(db
+        .changes_since(&first_root_hash, None)
+        .unwrap()
+        .expect("root hash is journaled")
+        .is_empty());
+    db.commit_transaction(tx)
+        .unwrap()
+        .expect("successful commit");
+
+    assert_eq!(
+        db.changes_since(&first_root_hash, None)
+            .unwrap()
+            .expect("root hash is journaled"),
+        vec![
+            (vec![TEST_LEAF.to_vec()], b"key2".to_vec()),
+            (vec![TEST_LEAF.to_vec()], b"key1".to_vec()),
+        ]
     );
+    let root_hash = db.root_hash(None).unwrap().expect("root hash");
+    assert!(db
+        .changes_since(&root_hash, None)
+        .unwrap()
+        .expect("root hash is journaled")
+        .is_empty());
 
-    assert_eq!(db.root_hash(None).unwrap().unwrap(), root_hash_outside);
-    db.commit_transaction(transaction).unwrap().unwrap();
-    assert_ne!(db.root_hash(None).unwrap().unwrap(), root_hash_outside);
-}
+    // Entries are written along with the changes, so rolled back ones aren't
+    // journaled
+    let tx = db.start_transaction();
+    db.insert(
+        [TEST_LEAF].as_ref(),
+        b"key4",
+        Element::new_item(b"value4".to_vec()),
+        None,
+        Some(&tx),
+    )
+    .unwrap()
+    .expect("successful insert");
+    db.rollback_transaction(&tx).expect("successful rollback");
+    drop(tx);
+    assert!(db
+        .changes_since(&root_hash, None)
+        .unwrap()
+        .expect("root hash is journaled")
+        .is_empty());
 
-#[test]
-fn test_get_non_existing_root_leaf() {
-    let db = make_test_grovedb();
-    assert!(matches!(db.get(EMPTY_PATH, b"ayy", None).unwrap(), Err(_)));
+    // Entries past the retention are dropped
+    db.enable_change_journal(1);
+    db.insert(
+        [TEST_LEAF].as_ref(),
+        b"key3",
+        Element::new_item(b"value3".to_vec()),
+        None,
+        None,
+    )
+    .unwrap()
+    .expect("successful insert");
+    assert!(matches!(
+        db.changes_since(&first_root_hash, None).unwrap(),
+        Err(Error::InvalidInput(_))
+    ));
+    assert!(matches!(
+        db.changes_since(&root_hash, None).unwrap(),
+        Err(Error::InvalidInput(_))
+    ));
+    let root_hash = db.root_hash(None).unwrap().expect("root hash");
+    assert!(db
+        .changes_since(&root_hash, None)
+        .unwrap()
+        .expect("root hash is journaled")
+        .is_empty());
 }
 
 #[test]
-fn test_check_subtree_exists_function() {
-    let db = make_test_grovedb();
+fn test_changes_since_covers_every_write() {
+    let mut db = make_test_grovedb();
+    db.enable_change_journal(10);
+    let root_hash = db.root_hash(None).unwrap().expect("root hash");
     db.insert(
         [TEST_LEAF].as_ref(),
-        b"key_scalar",
-        Element::new_item(b"ayy".to_vec()),
+        b"key1",
+        Element::new_item(b"value1".to_vec()),
         None,
         None,
     )
     .unwrap()
-    .expect("cannot insert item");
-    db.insert(
+    .expect("successful insert");
+    let first_root_hash = db.root_hash(None).unwrap().expect("root hash");
+
+    db.insert_many(
         [TEST_LEAF].as_ref(),
-        b"key_subtree",
-        Element::empty_tree(),
+        vec![
+            (b"key2".to_vec(), Element::new_item(b"value2".to_vec())),
+            (b"key3".to_vec(), Element::new_item(b"value3".to_vec())),
+        ],
         None,
         None,
     )
     .unwrap()
-    .expect("cannot insert item");
-
-    // Empty tree path means root always exist
-    assert!(db
-        .check_subtree_exists_invalid_path(EMPTY_PATH.into(), None)
-        .unwrap()
-        .is_ok());
+    .expect("successful insert");
+    let second_root_hash = db.root_hash(None).unwrap().expect("root hash");
+    assert_eq!(
+        db.changes_since(&first_root_hash, None)
+            .unwrap()
+            .expect("root hash is journaled"),
+        vec![
+            (vec![TEST_LEAF.to_vec()], b"key2".to_vec()),
+            (vec![TEST_LEAF.to_vec()], b"key3".to_vec()),
+        ]
+    );
 
-    // TEST_LEAF should be a tree
+    // Operations added once the partial batch is paused are journaled too
+    db.apply_partial_batch(
+        vec![GroveDbOp::insert_op(
+            vec![TEST_LEAF.to_vec()],
+            b"key4".to_vec(),
+            Element::new_item(b"value4".to_vec()),
+        )],
+        None,
+        |_cost, _left_over_ops| {
+            Ok(vec![GroveDbOp::insert_op(
+                vec![ANOTHER_TEST_LEAF.to_vec()],
+                b"key5".to_vec(),
+                Element::new_item(b"value5".to_vec()),
+            )])
+        },
+        None,
+    )
+    .unwrap()
+    .expect("successful batch");
+    assert_eq!(
+        db.changes_since(&second_root_hash, None)
+            .unwrap()
+            .expect("root hash is journaled"),
+        vec![
+            (vec![TEST_LEAF.to_vec()], b"key4".to_vec()),
+            (vec![ANOTHER_TEST_LEAF.to_vec()], b"key5".to_vec()),
+        ]
+    );
+    let third_root_hash = db.root_hash(None).unwrap().expect("root hash");
     assert!(db
-        .check_subtree_exists_invalid_path([TEST_LEAF].as_ref().into(), None)
+        .changes_since(&third_root_hash, None)
         .unwrap()
-        .is_ok());
+        .expect("root hash is journaled")
+        .is_empty());
 
-    // TEST_LEAF.key_subtree should be a tree
-    assert!(db
-        .check_subtree_exists_invalid_path([TEST_LEAF, b"key_subtree"].as_ref().into(), None)
+    // Aux data isn't part of the state, so nothing is journaled for it
+    db.put_aux(b"aux", b"value", None, None)
         .unwrap()
-        .is_ok());
-
-    // TEST_LEAF.key_scalar should NOT be a tree
-    assert!(matches!(
-        db.check_subtree_exists_invalid_path([TEST_LEAF, b"key_scalar"].as_ref().into(), None)
-            .unwrap(),
-        Err(Error::InvalidPath(_))
-    ));
+        .expect("successful aux put");
+    db.insert_and_return_previous(
+        [TEST_LEAF].as_ref(),
+        b"key1",
+        Element::new_item(b"other value".to_vec()),
+        None,
+        None,
+    )
+    .unwrap()
+    .expect("successful insert");
+    assert_eq!(
+        db.changes_since(&root_hash, None)
+            .unwrap()
+            .expect("root hash is journaled")
+            .len(),
+        6
+    );
+    assert_eq!(
+        db.changes_since(&third_root_hash, None)
+            .unwrap()
+            .expect("root hash is journaled"),
+        vec![(vec![TEST_LEAF.to_vec()], b"key1".to_vec())]
+    );
 }
 
 #[test]
-fn test_tree_value_exists_method_no_tx() {
-    let db = make_test_grovedb();
-    // Test keys in non-root tree
+fn test_merk_cache() {
+    let mut db = make_test_grovedb();
+    db.set_merk_cache_capacity(8);
     db.insert(
         [TEST_LEAF].as_ref(),
-        b"key",
-        Element::new_item(b"ayy".to_vec()),
+        b"inner",
+        Element::empty_tree(),
         None,
         None,
     )
     .unwrap()
-    .expect("cannot insert item");
-    assert!(db
-        .has_raw([TEST_LEAF].as_ref(), b"key", None)
-        .unwrap()
-        .unwrap());
-    assert!(!db
-        .has_raw([TEST_LEAF].as_ref(), b"badkey", None)
-        .unwrap()
-        .unwrap());
+    .expect("successful insert");
+    db.insert(
+        [TEST_LEAF, b"inner"].as_ref(),
+        b"key1",
+        Element::new_item(b"value1".to_vec()),
+        None,
+        None,
+    )
+    .unwrap()
+    .expect("successful insert");
 
-    // Test keys for a root tree
-    db.insert(EMPTY_PATH, b"leaf", Element::empty_tree(), None, None)
+    // The parent tree element is only read the first time the subtree is opened
+    let first = db.get_raw([TEST_LEAF, b"inner"].as_ref().into(), b"key1", None);
+    let second = db.get_raw([TEST_LEAF, b"inner"].as_ref().into(), b"key1", None);
+    assert_eq!(
+        first.value.expect("successful get"),
+        second.value.expect("successful get")
+    );
+    assert!(second.cost.seek_count < first.cost.seek_count);
+    assert!(second.cost.storage_loaded_bytes < first.cost.storage_loaded_bytes);
+    let third = db.get_raw([TEST_LEAF, b"inner"].as_ref().into(), b"key1", None);
+    assert_eq!(second.cost, third.cost);
+
+    // Inserting more keys moves the subtree root, writes must invalidate the
+    // cached root key so they are found
+    for key in [b"key2", b"key3"] {
+        db.insert(
+            [TEST_LEAF, b"inner"].as_ref(),
+            key,
+            Element::new_item(key.to_vec()),
+            None,
+            None,
+        )
         .unwrap()
-        .expect("cannot insert item");
-
-    assert!(db.has_raw(EMPTY_PATH, b"leaf", None).unwrap().unwrap());
-    assert!(db.has_raw(EMPTY_PATH, TEST_LEAF, None).unwrap().unwrap());
-    assert!(!db.has_raw(EMPTY_PATH, b"badleaf", None).unwrap().unwrap());
+        .expect("successful insert");
+    }
+    assert_eq!(
+        db.get_raw([TEST_LEAF, b"inner"].as_ref().into(), b"key3", None)
+            .unwrap()
+            .expect("successful get"),
+        Element::new_item(b"key3".to_vec())
+    );
+    assert_eq!(
+        db.get_raw([TEST_LEAF, b"inner"].as_ref().into(), b"key1", None)
+            .unwrap()
+            .expect("successful get"),
+        Element::new_item(b"value1".to_vec())
+    );
 }
 
 #[test]
-fn test_tree_value_exists_method_tx() {
-    let db = make_test_grovedb();
-    let tx = db.start_transaction();
-    // Test keys in non-root tree
+fn test_merk_cache_ignores_uncommitted_writes() {
+    let mut db = make_test_grovedb();
+    db.set_merk_cache_capacity(8);
     db.insert(
         [TEST_LEAF].as_ref(),
-        b"key",
-        Element::new_item(b"ayy".to_vec()),
+        b"inner",
+        Element::empty_tree(),
+        None,
         None,
-        Some(&tx),
     )
     .unwrap()
-    .expect("cannot insert item");
-    assert!(db
-        .has_raw([TEST_LEAF].as_ref(), b"key", Some(&tx))
-        .unwrap()
-        .unwrap());
-    assert!(!db
-        .has_raw([TEST_LEAF].as_ref(), b"key", None)
-        .unwrap()
-        .unwrap());
+    .expect("successful insert");
+    db.insert(
+        [TEST_LEAF, b"inner"].as_ref(),
+        b"key1",
+        Element::new_item(b"value1".to_vec()),
+        None,
+        None,
+    )
+    .unwrap()
+    .expect("successful insert");
 
-    // Test keys for a root tree
-    db.insert(EMPTY_PATH, b"leaf", Element::empty_tree(), None, Some(&tx))
-        .unwrap()
-        .expect("cannot insert item");
-    assert!(db.has_raw(EMPTY_PATH, b"leaf", Some(&tx)).unwrap().unwrap());
-    assert!(!db.has_raw(EMPTY_PATH, b"leaf", None).unwrap().unwrap());
+    // The reference is checked against the emptied tree written just before, so
+    // the insert fails and nothing is committed
+    let result = db
+        .insert_many(
+            [TEST_LEAF].as_ref(),
+            vec![
+                (b"inner".to_vec(), Element::empty_tree()),
+                (
+                    b"ref".to_vec(),
+                    Element::new_reference(ReferencePathType::AbsolutePathReference(vec![
+                        TEST_LEAF.to_vec(),
+                        b"inner".to_vec(),
+                        b"key1".to_vec(),
+                    ])),
+                ),
+            ],
+            Some(InsertOptions {
+                validate_insertion_does_not_override_tree: false,
+                ..Default::default()
+            }),
+            None,
+        )
+        .unwrap();
+    assert!(matches!(result, Err(Error::MissingReference(_))));
 
-    db.commit_transaction(tx)
-        .unwrap()
-        .expect("cannot commit transaction");
+    // The uncommitted root key of the emptied tree must not have been cached
+    assert_eq!(
+        db.get_raw([TEST_LEAF, b"inner"].as_ref().into(), b"key1", None)
+            .unwrap()
+            .expect("successful get"),
+        Element::new_item(b"value1".to_vec())
+    );
     assert!(db
-        .has_raw([TEST_LEAF].as_ref(), b"key", None)
+        .get_raw([TEST_LEAF].as_ref().into(), b"ref", None)
         .unwrap()
-        .unwrap());
-    assert!(db.has_raw(EMPTY_PATH, b"leaf", None).unwrap().unwrap());
+        .is_err());
 }
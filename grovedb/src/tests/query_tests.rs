@@ -28,6 +28,7 @@
 
 //! Query tests
 
+use grovedb_costs::OperationCost;
 use grovedb_merk::proofs::{query::QueryItem, Query};
 use rand::Rng;
 use tempfile::TempDir;
@@ -40,7 +41,8 @@ use crate::{
         common::compare_result_sets, make_deep_tree, make_test_grovedb, TempGroveDb,
         ANOTHER_TEST_LEAF, TEST_LEAF,
     },
-    Element, GroveDb, PathQuery, SizedQuery,
+    Cursor, Element, Error, GroveDb, PathQuery, QueryPlanStep, SizedQuery,
+    WorstCaseLayerInformation,
 };
 
 fn populate_tree_for_non_unique_range_subquery(db: &TempGroveDb) {
@@ -1520,6 +1522,9 @@ fn test_correct_child_root_hash_propagation_for_parent_in_same_batch() {
             query: query.clone(),
             limit: Some(100),
             offset: Some(0),
+            max_result_bytes: None,
+            keys_only: false,
+            include_subtree_hashes: false,
         },
     );
 
@@ -2036,10 +2041,71 @@ fn test_proof_with_limit_zero() {
         SizedQuery::new(query, Some(0), Some(0)),
     );
 
-    let proof = db.prove_query(&path_query).unwrap().unwrap();
-    let (hash, result_set) = GroveDb::verify_query_raw(&proof, &path_query).unwrap();
+    // A limit of 0 matches nothing, the query is rejected before proving
+    assert!(matches!(
+        db.prove_query(&path_query).unwrap(),
+        Err(Error::InvalidInput(_))
+    ));
+}
+
+#[test]
+fn test_prove_query_rejects_unbounded_root_query() {
+    let db = make_deep_tree();
+    let mut subquery = Query::new();
+    subquery.insert_all();
+    let mut query = Query::new();
+    query.insert_all();
+    query.set_subquery(subquery);
+
+    // Every key of every root subtree
+    let path_query = PathQuery::new_unsized(vec![], query.clone());
+    let cost = db.prove_query(&path_query);
+    assert_eq!(cost.cost, OperationCost::default());
+    assert!(matches!(cost.value, Err(Error::InvalidInput(_))));
+    assert!(matches!(
+        db.prove_verbose(&path_query).unwrap(),
+        Err(Error::InvalidInput(_))
+    ));
+
+    // Open ranges match as much as a full range does
+    let mut subquery = Query::new();
+    subquery.insert_item(QueryItem::RangeAfter(vec![0]..));
+    let mut open_query = Query::new();
+    open_query.insert_item(QueryItem::RangeFrom(vec![0]..));
+    open_query.set_subquery(subquery);
+    let path_query = PathQuery::new_unsized(vec![], open_query);
+    assert!(matches!(
+        db.prove_query(&path_query).unwrap(),
+        Err(Error::InvalidInput(_))
+    ));
+
+    // The same query with a limit can be proven
+    let path_query = PathQuery::new(vec![], SizedQuery::new(query, Some(100), None));
+    let proof = db
+        .prove_query(&path_query)
+        .unwrap()
+        .expect("expected successful proving");
+    let (hash, result_set) =
+        GroveDb::verify_query_raw(&proof, &path_query).expect("expected successful verification");
     assert_eq!(hash, db.root_hash(None).unwrap().unwrap());
-    assert_eq!(result_set.len(), 0);
+    assert!(!result_set.is_empty());
+
+    // And so can a query narrowed down by a subquery path
+    let mut subquery = Query::new();
+    subquery.insert_all();
+    let mut query = Query::new();
+    query.insert_all();
+    query.set_subquery_path(vec![b"deep_node_1".to_vec(), b"deeper_1".to_vec()]);
+    query.set_subquery(subquery);
+    let path_query = PathQuery::new_unsized(vec![], query);
+    let proof = db
+        .prove_query(&path_query)
+        .unwrap()
+        .expect("expected successful proving");
+    let (hash, result_set) =
+        GroveDb::verify_query_raw(&proof, &path_query).expect("expected successful verification");
+    assert_eq!(hash, db.root_hash(None).unwrap().unwrap());
+    assert_eq!(result_set.len(), 3);
 }
 
 #[test]
@@ -2658,3 +2724,866 @@ fn test_query_b_depends_on_query_a() {
     assert_eq!(age_result[0].2, Some(Element::new_item(vec![12])));
     assert_eq!(age_result[1].2, Some(Element::new_item(vec![46])));
 }
+
+#[test]
+fn test_subquery_branch_limit_applies_per_branch() {
+    let db = make_deep_tree();
+
+    let mut subquery = Query::new();
+    subquery.insert_all();
+
+    let mut query = Query::new();
+    query.insert_all();
+    query.set_subquery(subquery);
+    query.set_subquery_limit(Some(1));
+
+    let path_query =
+        PathQuery::new_unsized(vec![b"deep_leaf".to_vec(), b"deep_node_1".to_vec()], query);
+    assert_eq!(path_query.query.limit, None);
+
+    let (elements, _) = db
+        .query_raw(
+            &path_query,
+            true,
+            QueryResultType::QueryPathKeyElementTrioResultType,
+            None,
+        )
+        .unwrap()
+        .expect("expected successful get_path_query");
+
+    let results: Vec<_> = elements
+        .to_path_key_elements()
+        .into_iter()
+        .map(|(path, key, _)| (path.last().cloned().unwrap(), key))
+        .collect();
+    assert_eq!(
+        results,
+        vec![
+            (b"deeper_1".to_vec(), b"key1".to_vec()),
+            (b"deeper_2".to_vec(), b"key4".to_vec()),
+        ]
+    );
+
+    let proof = db.prove_query(&path_query).unwrap().unwrap();
+    let (hash, proved) = GroveDb::verify_query(&proof, &path_query).unwrap();
+    assert_eq!(hash, db.root_hash(None).unwrap().unwrap());
+    let proved_results: Vec<_> = proved
+        .into_iter()
+        .map(|(path, key, _)| (path.last().cloned().unwrap(), key))
+        .collect();
+    assert_eq!(proved_results, results);
+}
+
+#[test]
+fn test_key_prefix_query() {
+    let db = make_test_grovedb();
+    for key in [b"user:1".as_slice(), b"user:2", b"post:1"] {
+        db.insert(
+            [TEST_LEAF].as_ref(),
+            key,
+            Element::new_item(key.to_vec()),
+            None,
+            None,
+        )
+        .unwrap()
+        .expect("successful item insert");
+    }
+
+    let mut query = Query::new();
+    query.insert_item(QueryItem::KeyPrefix(b"user:".to_vec()));
+    let path_query = PathQuery::new_unsized(vec![TEST_LEAF.to_vec()], query);
+
+    let (elements, _) = db
+        .query_raw(
+            &path_query,
+            true,
+            QueryResultType::QueryKeyElementPairResultType,
+            None,
+        )
+        .unwrap()
+        .expect("expected successful get_path_query");
+    let keys: Vec<_> = elements
+        .to_key_elements()
+        .into_iter()
+        .map(|(key, _)| key)
+        .collect();
+    assert_eq!(keys, vec![b"user:1".to_vec(), b"user:2".to_vec()]);
+
+    let proof = db.prove_query(&path_query).unwrap().unwrap();
+    let (hash, result_set) = GroveDb::verify_query_raw(&proof, &path_query).unwrap();
+    assert_eq!(hash, db.root_hash(None).unwrap().unwrap());
+    let proved_keys: Vec<_> = result_set.into_iter().map(|proved| proved.key).collect();
+    assert_eq!(proved_keys, keys);
+}
+
+#[test]
+fn test_worst_case_query_cost_dominates_actual_cost() {
+    let db = make_deep_tree();
+
+    let mut query = Query::new();
+    query.insert_all();
+    let mut subquery = Query::new();
+    subquery.insert_all();
+    query.set_subquery(subquery);
+    let path_query =
+        PathQuery::new_unsized(vec![b"deep_leaf".to_vec(), b"deep_node_1".to_vec()], query);
+
+    let actual = db.query_raw(
+        &path_query,
+        true,
+        QueryResultType::QueryElementResultType,
+        None,
+    );
+    let (elements, _) = actual.value.expect("expected successful query");
+    assert_eq!(elements.len(), 6);
+
+    let estimate = GroveDb::worst_case_query_cost(
+        &path_query,
+        &WorstCaseLayerInformation::MaxElementsNumber(8),
+    );
+    assert!(estimate.seek_count >= actual.cost.seek_count);
+    assert!(estimate.storage_loaded_bytes >= actual.cost.storage_loaded_bytes);
+    assert!(estimate.hash_node_calls >= actual.cost.hash_node_calls);
+}
+
+#[test]
+fn test_query_with_max_result_bytes() {
+    let db = make_test_grovedb();
+    let items: Vec<Element> = (0u8..4).map(|i| Element::new_item(vec![i; 100])).collect();
+    for (i, item) in items.iter().enumerate() {
+        db.insert([TEST_LEAF].as_ref(), &[i as u8], item.clone(), None, None)
+            .unwrap()
+            .expect("successful item insert");
+    }
+    let item_size = items[0].serialized_size() as u32;
+
+    let mut query = Query::new();
+    query.insert_all();
+    let mut path_query = PathQuery::new_unsized(vec![TEST_LEAF.to_vec()], query);
+    path_query.query.max_result_bytes = Some(item_size * 3 - 1);
+
+    let (elements, _) = db
+        .query_raw(
+            &path_query,
+            true,
+            QueryResultType::QueryKeyElementPairResultType,
+            None,
+        )
+        .unwrap()
+        .expect("expected successful query");
+    assert_eq!(
+        elements.to_key_elements(),
+        vec![(vec![0], items[0].clone()), (vec![1], items[1].clone())]
+    );
+
+    // Two results fit, which is the limit proofs are made with
+    let limited_path_query = db
+        .limit_to_result_bytes(&path_query, None)
+        .unwrap()
+        .expect("expected successful query");
+    assert_eq!(limited_path_query.query.limit, Some(2));
+    assert_eq!(limited_path_query.query.max_result_bytes, None);
+
+    let proof = db
+        .prove_query(&path_query)
+        .unwrap()
+        .expect("expected successful proving");
+    let (hash, result_set) = GroveDb::verify_query(&proof, &limited_path_query)
+        .expect("expected successful verification");
+    assert_eq!(hash, db.root_hash(None).unwrap().unwrap());
+    let proved: Vec<(Vec<u8>, Element)> = result_set
+        .into_iter()
+        .map(|(_, key, element)| (key, element.expect("expected proved element")))
+        .collect();
+    assert_eq!(
+        proved,
+        vec![(vec![0], items[0].clone()), (vec![1], items[1].clone())]
+    );
+
+    let proof = db
+        .prove_verbose(&path_query)
+        .unwrap()
+        .expect("expected successful proving");
+    let (hash, result_set) = GroveDb::verify_subset_query(&proof, &limited_path_query)
+        .expect("expected successful verification");
+    assert_eq!(hash, db.root_hash(None).unwrap().unwrap());
+    assert_eq!(result_set.len(), 2);
+}
+
+#[test]
+fn test_query_with_max_result_bytes_stops_in_subqueries() {
+    let db = make_deep_tree();
+
+    let mut query = Query::new();
+    query.insert_all();
+    let mut subquery = Query::new();
+    subquery.insert_all();
+    let mut subsubquery = Query::new();
+    subsubquery.insert_all();
+    subquery.set_subquery(subsubquery);
+    query.set_subquery(subquery);
+    let mut path_query = PathQuery::new_unsized(vec![b"deep_leaf".to_vec()], query);
+    let item_size = Element::new_item(b"value1".to_vec()).serialized_size() as u32;
+    path_query.query.max_result_bytes = Some(item_size * 4);
+
+    let (elements, _) = db
+        .query_raw(
+            &path_query,
+            true,
+            QueryResultType::QueryElementResultType,
+            None,
+        )
+        .unwrap()
+        .expect("expected successful query");
+    assert_eq!(
+        elements.to_elements(),
+        vec![
+            Element::new_item(b"value1".to_vec()),
+            Element::new_item(b"value2".to_vec()),
+            Element::new_item(b"value3".to_vec()),
+            Element::new_item(b"value4".to_vec()),
+        ]
+    );
+}
+
+#[test]
+fn test_descending_query_with_limit_returns_greatest_keys() {
+    let db = make_test_grovedb();
+    for i in 0u8..10 {
+        db.insert(
+            [TEST_LEAF].as_ref(),
+            &[i],
+            Element::new_item(vec![i]),
+            None,
+            None,
+        )
+        .unwrap()
+        .expect("successful item insert");
+    }
+
+    let mut query = Query::new_with_direction(false);
+    query.insert_all();
+    let path_query = PathQuery::new(
+        vec![TEST_LEAF.to_vec()],
+        SizedQuery::new(query, Some(3), None),
+    );
+
+    let (elements, _) = db
+        .query_raw(
+            &path_query,
+            true,
+            QueryResultType::QueryKeyElementPairResultType,
+            None,
+        )
+        .unwrap()
+        .expect("expected successful query");
+    let expected = vec![
+        (vec![9], Element::new_item(vec![9])),
+        (vec![8], Element::new_item(vec![8])),
+        (vec![7], Element::new_item(vec![7])),
+    ];
+    assert_eq!(elements.to_key_elements(), expected);
+
+    let proof = db
+        .prove_query(&path_query)
+        .unwrap()
+        .expect("expected successful proving");
+    let (hash, result_set) =
+        GroveDb::verify_query(&proof, &path_query).expect("expected successful verification");
+    assert_eq!(hash, db.root_hash(None).unwrap().unwrap());
+    let proved: Vec<(Vec<u8>, Element)> = result_set
+        .into_iter()
+        .map(|(_, key, element)| (key, element.expect("expected proved element")))
+        .collect();
+    assert_eq!(proved, expected);
+}
+
+#[test]
+fn test_chunked_proof_matches_monolithic_proof() {
+    let db = make_deep_tree();
+
+    for left_to_right in [true, false] {
+        let mut inner_subquery = Query::new_with_direction(left_to_right);
+        inner_subquery.insert_all();
+        let mut subquery = Query::new_with_direction(left_to_right);
+        subquery.insert_all();
+        subquery.set_subquery(inner_subquery);
+        let mut query = Query::new_with_direction(left_to_right);
+        query.insert_all();
+        query.set_subquery(subquery);
+        let path_query = PathQuery::new_unsized(vec![], query.clone());
+
+        // The whole database can only be proven at once with a limit
+        let limited_path_query =
+            PathQuery::new(vec![], SizedQuery::new(query, Some(u16::MAX), None));
+        let proof = db
+            .prove_query(&limited_path_query)
+            .unwrap()
+            .expect("successful proof");
+        let (root_hash, expected_results) =
+            GroveDb::verify_query(&proof, &limited_path_query).expect("successful verification");
+
+        // Every root tree key gets its own chunk
+        let chunks = db
+            .prove_query_chunked(&path_query, 1)
+            .unwrap()
+            .expect("successful proof");
+        assert_eq!(chunks.len(), 3);
+        let (chunks_root_hash, results) =
+            GroveDb::verify_query_chunked(&chunks, &path_query).expect("successful verification");
+        assert_eq!(chunks_root_hash, root_hash);
+        assert_eq!(results, expected_results);
+
+        // A single chunk when the whole proof fits
+        let chunks = db
+            .prove_query_chunked(&path_query, usize::MAX)
+            .unwrap()
+            .expect("successful proof");
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(
+            GroveDb::verify_query_chunked(&chunks, &path_query)
+                .expect("successful verification")
+                .1,
+            expected_results
+        );
+    }
+
+    // Chunks must cover the whole query
+    let mut query = Query::new();
+    query.insert_all();
+    let path_query = PathQuery::new_unsized(vec![], query);
+    let mut chunks = db
+        .prove_query_chunked(&path_query, 1)
+        .unwrap()
+        .expect("successful proof");
+    chunks.remove(1);
+    assert!(GroveDb::verify_query_chunked(&chunks, &path_query).is_err());
+}
+
+#[test]
+fn test_keys_only_query_proof() {
+    let db = make_test_grovedb();
+    for i in 0u8..10 {
+        db.insert(
+            [TEST_LEAF].as_ref(),
+            &[i],
+            Element::new_item(vec![i; 100]),
+            None,
+            None,
+        )
+        .unwrap()
+        .expect("successful item insert");
+    }
+
+    let mut query = Query::new();
+    query.insert_range(vec![2]..vec![7]);
+    let path_query = PathQuery::new(
+        vec![TEST_LEAF.to_vec()],
+        SizedQuery::new(query, Some(4), None),
+    );
+    let mut keys_only_path_query = path_query.clone();
+    keys_only_path_query.query.keys_only = true;
+
+    let proof = db
+        .prove_query(&path_query)
+        .unwrap()
+        .expect("expected successful proving");
+    let keys_only_proof = db
+        .prove_query(&keys_only_path_query)
+        .unwrap()
+        .expect("expected successful proving");
+    assert!(keys_only_proof.len() < proof.len());
+
+    let (hash, result_set) =
+        GroveDb::verify_query_raw(&proof, &path_query).expect("expected successful verification");
+    let (keys_only_hash, keys_only_result_set) =
+        GroveDb::verify_query_raw(&keys_only_proof, &keys_only_path_query)
+            .expect("expected successful verification");
+    assert_eq!(keys_only_hash, db.root_hash(None).unwrap().unwrap());
+    assert_eq!(keys_only_hash, hash);
+    assert_eq!(
+        keys_only_result_set
+            .iter()
+            .map(|proved| proved.key.clone())
+            .collect::<Vec<_>>(),
+        result_set
+            .iter()
+            .map(|proved| proved.key.clone())
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(keys_only_result_set.len(), 4);
+    assert!(keys_only_result_set
+        .iter()
+        .all(|proved| proved.value.is_empty()));
+
+    // A keys only proof doesn't verify as a proof of values
+    assert!(GroveDb::verify_query_raw(&keys_only_proof, &path_query).is_err());
+
+    // Subqueries are not supported
+    let mut query = Query::new();
+    query.insert_all();
+    query.set_subquery(Query::new());
+    let mut path_query = PathQuery::new_unsized(vec![], query);
+    path_query.query.keys_only = true;
+    assert!(matches!(
+        db.prove_query(&path_query).unwrap(),
+        Err(Error::NotSupported(_))
+    ));
+}
+
+#[test]
+fn test_max_subquery_depth() {
+    let mut db = make_deep_tree();
+    db.set_max_subquery_depth(2);
+
+    let nested_query = |levels: usize| {
+        let mut query = Query::new();
+        query.insert_all();
+        for _ in 0..levels {
+            let mut parent = Query::new();
+            parent.insert_all();
+            parent.set_subquery(query);
+            query = parent;
+        }
+        PathQuery::new_unsized(vec![b"deep_leaf".to_vec()], query)
+    };
+
+    // Two levels of subqueries reach the items of the deeper trees
+    let path_query = nested_query(2);
+    let (elements, _) = db
+        .query_raw(
+            &path_query,
+            true,
+            QueryResultType::QueryElementResultType,
+            None,
+        )
+        .unwrap()
+        .expect("expected successful query");
+    assert_eq!(elements.len(), 11);
+    let proof = db
+        .prove_query(&path_query)
+        .unwrap()
+        .expect("expected successful proving");
+    let (hash, result_set) =
+        GroveDb::verify_query(&proof, &path_query).expect("expected successful verification");
+    assert_eq!(hash, db.root_hash(None).unwrap().unwrap());
+    assert_eq!(result_set.len(), 11);
+
+    let path_query = nested_query(3);
+    assert!(matches!(
+        db.query_raw(
+            &path_query,
+            true,
+            QueryResultType::QueryElementResultType,
+            None,
+        )
+        .unwrap(),
+        Err(Error::QueryTooDeep { max_depth: 2 })
+    ));
+    assert!(matches!(
+        db.prove_query(&path_query).unwrap(),
+        Err(Error::QueryTooDeep { max_depth: 2 })
+    ));
+
+    db.clear_max_subquery_depth();
+    assert!(db
+        .query_raw(
+            &path_query,
+            true,
+            QueryResultType::QueryElementResultType,
+            None,
+        )
+        .unwrap()
+        .is_ok());
+}
+
+#[test]
+fn test_cursor_pagination() {
+    let db = make_test_grovedb();
+    for i in 0u8..20 {
+        db.insert(
+            [TEST_LEAF].as_ref(),
+            &[i],
+            Element::new_item(vec![i]),
+            None,
+            None,
+        )
+        .unwrap()
+        .expect("successful insert");
+    }
+
+    for left_to_right in [true, false] {
+        let mut query = Query::new_with_direction(left_to_right);
+        query.insert_all();
+        let (unpaginated, _) = db
+            .query_raw(
+                &PathQuery::new_unsized(vec![TEST_LEAF.to_vec()], query.clone()),
+                true,
+                QueryResultType::QueryKeyElementPairResultType,
+                None,
+            )
+            .unwrap()
+            .expect("expected successful query");
+        let unpaginated = unpaginated.to_key_elements();
+        assert_eq!(unpaginated.len(), 20);
+
+        let mut paginated = Vec::new();
+        let mut sized_query = SizedQuery::new(query, Some(5), None);
+        let mut pages = 0;
+        loop {
+            let path_query = PathQuery::new(vec![TEST_LEAF.to_vec()], sized_query.clone());
+            let (page, cursor) = db
+                .query_raw_with_cursor(&path_query, true, None)
+                .unwrap()
+                .expect("expected successful query");
+
+            // Proofs of the page yield the same results and cursor
+            let proof = db
+                .prove_query(&path_query)
+                .unwrap()
+                .expect("expected successful proving");
+            let (hash, proved, proved_cursor) =
+                GroveDb::verify_query_with_cursor(&proof, &path_query)
+                    .expect("expected successful verification");
+            assert_eq!(hash, db.root_hash(None).unwrap().unwrap());
+            assert!(proved
+                .iter()
+                .map(|proved| &proved.key)
+                .eq(page.iter().map(|(key, _)| key)));
+            assert_eq!(proved_cursor, cursor);
+
+            paginated.extend(page);
+            pages += 1;
+            match cursor {
+                Some(cursor) => {
+                    // Cursors are stable for the same state
+                    let cursor = Cursor::from_bytes(cursor.as_bytes().to_vec());
+                    sized_query = sized_query.after_cursor(&cursor);
+                }
+                None => break,
+            }
+        }
+
+        // 4 full pages, then an empty one as a full page can't tell whether
+        // it is the last
+        assert_eq!(pages, 5);
+        assert_eq!(paginated, unpaginated);
+    }
+
+    // Cursors are made of subtree keys and paginate by the limit
+    let mut query = Query::new();
+    query.insert_all();
+    query.set_subquery(Query::new());
+    assert!(matches!(
+        db.query_raw_with_cursor(
+            &PathQuery::new(
+                vec![TEST_LEAF.to_vec()],
+                SizedQuery::new(query, Some(5), None)
+            ),
+            true,
+            None,
+        )
+        .unwrap(),
+        Err(Error::NotSupported(_))
+    ));
+    let mut query = Query::new();
+    query.insert_all();
+    assert!(matches!(
+        db.query_raw_with_cursor(
+            &PathQuery::new_unsized(vec![TEST_LEAF.to_vec()], query),
+            true,
+            None,
+        )
+        .unwrap(),
+        Err(Error::InvalidInput(_))
+    ));
+}
+
+#[test]
+fn test_verify_query_with_paths() {
+    let db = make_deep_tree();
+
+    // Both subtrees hold `key3`
+    let mut query = Query::new();
+    query.insert_key(b"key3".to_vec());
+    let path_query_one = PathQuery::new_unsized(
+        vec![TEST_LEAF.to_vec(), b"innertree".to_vec()],
+        query.clone(),
+    );
+    let path_query_two = PathQuery::new_unsized(
+        vec![ANOTHER_TEST_LEAF.to_vec(), b"innertree2".to_vec()],
+        query,
+    );
+    let merged_path_query = PathQuery::merge(vec![&path_query_one, &path_query_two])
+        .expect("should merge path queries");
+
+    let proof = db.prove_query(&merged_path_query).unwrap().unwrap();
+    let (hash, result_set) =
+        GroveDb::verify_query_with_paths(&proof, &merged_path_query).expect("should verify proof");
+    assert_eq!(hash, db.root_hash(None).unwrap().unwrap());
+
+    let value = Element::new_item(b"value3".to_vec()).serialize().unwrap();
+    assert_eq!(
+        result_set,
+        vec![
+            (
+                vec![TEST_LEAF.to_vec(), b"innertree".to_vec()],
+                b"key3".to_vec(),
+                value.clone()
+            ),
+            (
+                vec![ANOTHER_TEST_LEAF.to_vec(), b"innertree2".to_vec()],
+                b"key3".to_vec(),
+                value
+            ),
+        ]
+    );
+}
+
+#[test]
+fn test_query_plan_step_shared_by_proof_and_query() {
+    let db = make_deep_tree();
+
+    let mut deeper_query = Query::new();
+    deeper_query.insert_all();
+    let mut subquery = Query::new();
+    subquery.insert_all();
+    subquery.set_subquery(deeper_query.clone());
+    let mut query = Query::new();
+    query.insert_all();
+    query.set_subquery(subquery);
+    query.add_conditional_subquery(
+        QueryItem::Key(b"deep_node_1".to_vec()),
+        Some(vec![b"deeper_1".to_vec()]),
+        Some(deeper_query.clone()),
+    );
+    let path_query = PathQuery::new_unsized(vec![b"deep_leaf".to_vec()], query);
+
+    assert_eq!(
+        QueryPlanStep::for_key(&path_query.query, b"deep_node_1").expect("should plan step"),
+        QueryPlanStep::Subquery {
+            path: vec![b"deeper_1".to_vec()],
+            query: deeper_query,
+        }
+    );
+
+    let (elements, _) = db
+        .query_raw(
+            &path_query,
+            true,
+            QueryResultType::QueryPathKeyElementTrioResultType,
+            None,
+        )
+        .unwrap()
+        .expect("should query");
+    let queried: Vec<_> = elements
+        .to_path_key_elements_btree_map()
+        .into_keys()
+        .collect();
+
+    let proof = db.prove_query(&path_query).unwrap().unwrap();
+    let (hash, result_set) =
+        GroveDb::verify_query_with_paths(&proof, &path_query).expect("should verify proof");
+    assert_eq!(hash, db.root_hash(None).unwrap().unwrap());
+    let proved: Vec<_> = result_set
+        .into_iter()
+        .map(|(path, key, _)| (path, key))
+        .collect::<std::collections::BTreeSet<_>>()
+        .into_iter()
+        .collect();
+
+    // deeper_1 through the conditional branch, deeper_3 and deeper_4 through
+    // the default one
+    assert_eq!(queried.len(), 8);
+    assert_eq!(queried, proved);
+    let touched_paths: std::collections::BTreeSet<_> =
+        proved.into_iter().map(|(path, _)| path).collect();
+    assert_eq!(
+        touched_paths.into_iter().collect::<Vec<_>>(),
+        vec![
+            vec![
+                b"deep_leaf".to_vec(),
+                b"deep_node_1".to_vec(),
+                b"deeper_1".to_vec()
+            ],
+            vec![
+                b"deep_leaf".to_vec(),
+                b"deep_node_2".to_vec(),
+                b"deeper_3".to_vec()
+            ],
+            vec![
+                b"deep_leaf".to_vec(),
+                b"deep_node_2".to_vec(),
+                b"deeper_4".to_vec()
+            ],
+        ]
+    );
+}
+
+#[test]
+fn test_max_proof_nodes() {
+    let mut db = make_test_grovedb();
+    populate_tree_for_non_unique_range_subquery(&db);
+
+    // Proves the 750 items of the 15 subtrees
+    let mut query = Query::new();
+    query.insert_all();
+    let mut subquery = Query::new();
+    subquery.insert_all();
+    query.set_subquery_key(b"\0".to_vec());
+    query.set_subquery(subquery);
+    let path_query = PathQuery::new_unsized(vec![TEST_LEAF.to_vec()], query);
+
+    db.set_max_proof_nodes(100);
+    let too_large = db.prove_query(&path_query);
+    assert!(matches!(
+        too_large.value,
+        Err(Error::ProofTooLarge { max_nodes: 100 })
+    ));
+
+    db.set_max_proof_nodes(10_000);
+    let proven = db.prove_query(&path_query);
+    // Generation stopped early instead of going through every subtree
+    assert!(too_large.cost.seek_count < proven.cost.seek_count / 2);
+    let proof = proven.unwrap().expect("expected successful proving");
+    let (hash, result_set) =
+        GroveDb::verify_query(&proof, &path_query).expect("expected successful verification");
+    assert_eq!(hash, db.root_hash(None).unwrap().unwrap());
+    assert_eq!(result_set.len(), 750);
+
+    db.clear_max_proof_nodes();
+    assert_eq!(
+        db.prove_query(&path_query)
+            .unwrap()
+            .expect("expected successful proving"),
+        proof
+    );
+}
+
+#[test]
+fn test_query_with_subtree_hashes() {
+    let db = make_test_grovedb();
+    // test_leaf
+    //     a: tree with items
+    //     b: sum tree with sum items
+    //     c: empty tree
+    //     d: item
+    db.insert(
+        [TEST_LEAF].as_ref(),
+        b"a",
+        Element::empty_tree(),
+        None,
+        None,
+    )
+    .unwrap()
+    .expect("successful subtree insert");
+    db.insert(
+        [TEST_LEAF].as_ref(),
+        b"b",
+        Element::empty_sum_tree(),
+        None,
+        None,
+    )
+    .unwrap()
+    .expect("successful subtree insert");
+    db.insert(
+        [TEST_LEAF].as_ref(),
+        b"c",
+        Element::empty_tree(),
+        None,
+        None,
+    )
+    .unwrap()
+    .expect("successful subtree insert");
+    db.insert(
+        [TEST_LEAF].as_ref(),
+        b"d",
+        Element::new_item(b"value".to_vec()),
+        None,
+        None,
+    )
+    .unwrap()
+    .expect("successful item insert");
+    for i in 0u8..3 {
+        db.insert(
+            [TEST_LEAF, b"a"].as_ref(),
+            &[i],
+            Element::new_item(vec![i]),
+            None,
+            None,
+        )
+        .unwrap()
+        .expect("successful item insert");
+        db.insert(
+            [TEST_LEAF, b"b"].as_ref(),
+            &[i],
+            Element::new_sum_item(i as i64),
+            None,
+            None,
+        )
+        .unwrap()
+        .expect("successful sum item insert");
+    }
+
+    let subtree_root_hash = |key: &[u8]| {
+        db.open_non_transactional_merk_at_path([TEST_LEAF, key].as_ref().into(), None)
+            .unwrap()
+            .expect("should open a subtree")
+            .root_hash()
+            .unwrap()
+    };
+    let expected_hashes = vec![
+        Some(subtree_root_hash(b"a")),
+        Some(subtree_root_hash(b"b")),
+        Some([0; 32]),
+        None,
+    ];
+
+    let mut query = Query::new();
+    query.insert_all();
+    let mut path_query = PathQuery::new_unsized(vec![TEST_LEAF.to_vec()], query);
+
+    // Hashes are only collected when asked for
+    let (elements, _) = db
+        .query_raw(
+            &path_query,
+            true,
+            QueryResultType::QueryKeyElementPairResultType,
+            None,
+        )
+        .unwrap()
+        .expect("expected successful get_path_query");
+    assert!(elements.subtree_root_hashes.is_empty());
+
+    path_query.query.include_subtree_hashes = true;
+    let (elements, _) = db
+        .query_raw(
+            &path_query,
+            true,
+            QueryResultType::QueryKeyElementPairResultType,
+            None,
+        )
+        .unwrap()
+        .expect("expected successful get_path_query");
+    assert_eq!(elements.subtree_root_hashes, expected_hashes);
+    assert_eq!(
+        elements.to_keys(),
+        vec![b"a".to_vec(), b"b".to_vec(), b"c".to_vec(), b"d".to_vec()]
+    );
+
+    // The proof of the same query binds the returned hashes
+    let proof = db
+        .prove_query(&path_query)
+        .unwrap()
+        .expect("expected successful proving");
+    let (hash, result_set) =
+        GroveDb::verify_query_raw(&proof, &path_query).expect("expected successful verification");
+    assert_eq!(hash, db.root_hash(None).unwrap().unwrap());
+    assert_eq!(result_set.len(), 4);
+    for (proved, expected_hash) in result_set.iter().zip(&expected_hashes) {
+        if let Some(expected_hash) = expected_hash {
+            assert!(proved.proves_subtree_root_hash(expected_hash));
+        }
+    }
+    assert!(!result_set[0].proves_subtree_root_hash(&expected_hashes[1].unwrap()));
+}
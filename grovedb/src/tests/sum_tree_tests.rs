@@ -37,7 +37,7 @@ use grovedb_storage::StorageBatch;
 use crate::{
     batch::GroveDbOp,
     reference_path::ReferencePathType,
-    tests::{make_test_grovedb, TEST_LEAF},
+    tests::{common::EMPTY_PATH, make_test_grovedb, TEST_LEAF},
     Element, Error, GroveDb, PathQuery,
 };
 
@@ -781,3 +781,194 @@ fn test_sum_tree_with_batches() {
         .expect("should open tree");
     assert_eq!(sum_tree.sum().expect("expected to get sum"), Some(41));
 }
+
+#[test]
+fn test_subtree_sum_tracks_sum_items() {
+    let db = make_test_grovedb();
+    db.insert(
+        [TEST_LEAF].as_ref(),
+        b"sumtree",
+        Element::empty_sum_tree(),
+        None,
+        None,
+    )
+    .unwrap()
+    .expect("should insert sum tree");
+    db.insert(
+        [TEST_LEAF].as_ref(),
+        b"tree",
+        Element::empty_tree(),
+        None,
+        None,
+    )
+    .unwrap()
+    .expect("should insert tree");
+
+    assert_eq!(
+        db.subtree_sum([TEST_LEAF, b"sumtree"].as_ref(), None)
+            .unwrap()
+            .expect("should get sum"),
+        0
+    );
+    assert!(matches!(
+        db.subtree_sum([TEST_LEAF, b"tree"].as_ref(), None).unwrap(),
+        Err(Error::NotASumTree(_))
+    ));
+
+    let items: [(&[u8], i64); 3] = [(b"a", 15), (b"b", -4), (b"c", 30)];
+    for (key, value) in items {
+        db.insert(
+            [TEST_LEAF, b"sumtree"].as_ref(),
+            key,
+            Element::new_sum_item(value),
+            None,
+            None,
+        )
+        .unwrap()
+        .expect("should insert sum item");
+    }
+    assert_eq!(
+        db.subtree_sum([TEST_LEAF, b"sumtree"].as_ref(), None)
+            .unwrap()
+            .expect("should get sum"),
+        41
+    );
+    assert_eq!(
+        db.get([TEST_LEAF, b"sumtree"].as_ref(), b"b", None)
+            .unwrap()
+            .expect("should get sum item"),
+        Element::new_sum_item(-4)
+    );
+
+    db.delete([TEST_LEAF, b"sumtree"].as_ref(), b"c", None, None)
+        .unwrap()
+        .expect("should delete sum item");
+    assert_eq!(
+        db.subtree_sum([TEST_LEAF, b"sumtree"].as_ref(), None)
+            .unwrap()
+            .expect("should get sum"),
+        11
+    );
+    assert_eq!(
+        db.get([TEST_LEAF].as_ref(), b"sumtree", None)
+            .unwrap()
+            .expect("should get sum tree")
+            .sum_value_or_default(),
+        11
+    );
+
+    assert!(matches!(
+        db.subtree_sum([TEST_LEAF, b"missing"].as_ref(), None)
+            .unwrap(),
+        Err(Error::PathNotFound(_)) | Err(Error::PathKeyNotFound(_))
+    ));
+}
+
+#[test]
+fn test_require_sum_tree() {
+    let db = make_test_grovedb();
+    db.insert(
+        [TEST_LEAF].as_ref(),
+        b"sumtree",
+        Element::empty_sum_tree(),
+        None,
+        None,
+    )
+    .unwrap()
+    .expect("should insert sum tree");
+    db.insert(
+        [TEST_LEAF].as_ref(),
+        b"tree",
+        Element::empty_tree(),
+        None,
+        None,
+    )
+    .unwrap()
+    .expect("should insert tree");
+
+    db.require_sum_tree([TEST_LEAF, b"sumtree"].as_ref(), None)
+        .unwrap()
+        .expect("sum tree should pass");
+    assert!(matches!(
+        db.require_sum_tree([TEST_LEAF, b"tree"].as_ref(), None)
+            .unwrap(),
+        Err(Error::NotASumTree(_))
+    ));
+    assert!(matches!(
+        db.require_sum_tree(EMPTY_PATH, None).unwrap(),
+        Err(Error::NotASumTree(_))
+    ));
+    assert!(matches!(
+        db.require_sum_tree([TEST_LEAF, b"missing"].as_ref(), None)
+            .unwrap(),
+        Err(Error::PathNotFound(_))
+    ));
+
+    // Sum operations surface the same error for a basic tree
+    let tx = db.start_transaction();
+    assert!(matches!(
+        db.subtree_sum([TEST_LEAF, b"tree"].as_ref(), Some(&tx))
+            .unwrap(),
+        Err(Error::NotASumTree(_))
+    ));
+}
+
+#[test]
+fn test_sum_tree_overflow_rejects_insert() {
+    let db = make_test_grovedb();
+    db.insert(
+        [TEST_LEAF].as_ref(),
+        b"sumtree",
+        Element::empty_sum_tree(),
+        None,
+        None,
+    )
+    .unwrap()
+    .expect("should insert sum tree");
+
+    let items: [(&[u8], i64); 2] = [(b"a", i64::MAX / 2), (b"b", i64::MAX / 2)];
+    for (key, value) in items {
+        db.insert(
+            [TEST_LEAF, b"sumtree"].as_ref(),
+            key,
+            Element::new_sum_item(value),
+            None,
+            None,
+        )
+        .unwrap()
+        .expect("should insert sum item");
+    }
+    let root_hash = db.root_hash(None).unwrap().unwrap();
+
+    assert!(matches!(
+        db.insert(
+            [TEST_LEAF, b"sumtree"].as_ref(),
+            b"c",
+            Element::new_sum_item(2),
+            None,
+            None,
+        )
+        .unwrap(),
+        Err(Error::Overflow(_))
+    ));
+
+    assert_eq!(db.root_hash(None).unwrap().unwrap(), root_hash);
+    assert!(matches!(
+        db.get([TEST_LEAF, b"sumtree"].as_ref(), b"c", None)
+            .unwrap(),
+        Err(Error::PathKeyNotFound(_))
+    ));
+    assert_eq!(
+        db.subtree_sum([TEST_LEAF, b"sumtree"].as_ref(), None)
+            .unwrap()
+            .expect("should get sum"),
+        i64::MAX - 1
+    );
+    assert_eq!(
+        db.get([TEST_LEAF].as_ref(), b"sumtree", None)
+            .unwrap()
+            .expect("should get sum tree")
+            .sum_value_or_default(),
+        i64::MAX - 1
+    );
+}
@@ -73,10 +73,14 @@ macro_rules! storage_context_with_parent_optional_tx {
                         &mut $cost,
                         Element::get_from_storage(&parent_storage, parent_key).map_err(|e| {
                             Error::PathParentLayerNotFound(
-                                format!(
-				    "could not get key for parent of subtree optional on tx: {}",
-				    e
-				)
+                                crate::PathErrorDetail::new(
+                                    $path.to_vec(),
+                                    None,
+                                    format!(
+                                        "could not get key for parent of subtree optional on tx: {}",
+                                        e
+                                    ),
+                                )
                             )
                         })
                     );
@@ -114,10 +118,14 @@ macro_rules! storage_context_with_parent_optional_tx {
                         &mut $cost,
 			Element::get_from_storage(&parent_storage, parent_key).map_err(|e| {
                             Error::PathParentLayerNotFound(
-                                format!(
-				    "could not get key for parent of subtree optional no tx: {}",
-				    e
-				)
+                                crate::PathErrorDetail::new(
+                                    $path.to_vec(),
+                                    None,
+                                    format!(
+                                        "could not get key for parent of subtree optional no tx: {}",
+                                        e
+                                    ),
+                                )
                             )
                         })
                     );
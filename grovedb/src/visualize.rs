@@ -30,7 +30,6 @@
 
 use std::io::{Result, Write};
 
-use bincode::Options;
 use grovedb_merk::{Merk, VisualizeableMerk};
 use grovedb_path::SubtreePathBuilder;
 use grovedb_storage::StorageContext;
@@ -214,11 +213,7 @@ impl Visualize for GroveDb {
 #[allow(dead_code)]
 pub fn visualize_merk_stdout<'db, S: StorageContext<'db>>(merk: &Merk<S>) {
     visualize_stdout(&VisualizeableMerk::new(merk, |bytes: &[u8]| {
-        bincode::DefaultOptions::default()
-            .with_varint_encoding()
-            .reject_trailing_bytes()
-            .deserialize::<Element>(bytes)
-            .expect("unable to deserialize Element")
+        Element::deserialize(bytes).expect("unable to deserialize Element")
     }));
 }
 
@@ -555,7 +555,10 @@ pub fn restore_500_1(c: &mut Criterion) {
                 (storage, merk.chunks().unwrap().into_iter())
             },
             |data| {
-                let tx = data.0.start_transaction();
+                let tx = data
+                    .0
+                    .start_transaction()
+                    .expect("cannot start transaction");
                 let ctx = data
                     .0
                     .get_immediate_storage_context(SubtreePath::empty(), &tx)
@@ -93,6 +93,10 @@ pub enum Error {
     #[error("request amount exceeded error {0}")]
     RequestAmountExceeded(String),
 
+    /// Proof generation was stopped for including more nodes than its budget
+    #[error("proof node limit exceeded error")]
+    ProofNodeLimitExceeded,
+
     /// Invalid operation error
     #[error("invalid operation error {0}")]
     InvalidOperation(&'static str),
@@ -161,16 +161,17 @@ impl EstimatedLayerSizes {
     pub fn subtree_with_feature_and_flags_size(&self) -> Result<u32, Error> {
         match self {
             EstimatedLayerSizes::AllSubtrees(_, estimated_sum_trees, flags_size) => {
+                // 1 for the element format version tag
                 // 1 for enum type
                 // 1 for empty
                 // 1 for flags size
-                Ok(estimated_sum_trees.estimated_size()? + flags_size.unwrap_or_default() + 3)
+                Ok(estimated_sum_trees.estimated_size()? + flags_size.unwrap_or_default() + 4)
             }
             EstimatedLayerSizes::Mix { subtrees_size, .. } => match subtrees_size {
                 None => Err(Error::WrongEstimatedCostsElementTypeForLevel(
                     "this layer is a mix but doesn't have subtrees",
                 )),
-                Some((_, est, fs, _)) => Ok(est.estimated_size()? + fs.unwrap_or_default() + 3),
+                Some((_, est, fs, _)) => Ok(est.estimated_size()? + fs.unwrap_or_default() + 4),
             },
             _ => Err(Error::WrongEstimatedCostsElementTypeForLevel(
                 "this layer needs to have trees",
@@ -182,23 +183,26 @@ impl EstimatedLayerSizes {
     pub fn value_with_feature_and_flags_size(&self) -> Result<u32, Error> {
         match self {
             EstimatedLayerSizes::AllItems(_, average_value_size, flags_size) => {
+                // 1 for the element format version tag
                 // 1 for enum type
                 // 1 for value size
                 // 1 for flags size
-                Ok(*average_value_size + flags_size.unwrap_or_default() + 3)
+                Ok(*average_value_size + flags_size.unwrap_or_default() + 4)
             }
             EstimatedLayerSizes::AllReference(_, average_value_size, flags_size) => {
+                // 1 for the element format version tag
                 // 1 for enum type
                 // 1 for value size
                 // 1 for flags size
                 // 2 for reference hops
-                Ok(*average_value_size + flags_size.unwrap_or_default() + 5)
+                Ok(*average_value_size + flags_size.unwrap_or_default() + 6)
             }
             EstimatedLayerSizes::AllSubtrees(_, estimated_sum_trees, flags_size) => {
+                // 1 for the element format version tag
                 // 1 for enum type
                 // 1 for empty
                 // 1 for flags size
-                Ok(estimated_sum_trees.estimated_size()? + flags_size.unwrap_or_default() + 3)
+                Ok(estimated_sum_trees.estimated_size()? + flags_size.unwrap_or_default() + 4)
             }
             EstimatedLayerSizes::Mix {
                 subtrees_size,
@@ -207,18 +211,18 @@ impl EstimatedLayerSizes {
             } => {
                 let (item_size, item_weight) = items_size
                     .as_ref()
-                    .map(|(_, vs, fs, weight)| (vs + fs.unwrap_or_default() + 3, *weight as u32))
+                    .map(|(_, vs, fs, weight)| (vs + fs.unwrap_or_default() + 4, *weight as u32))
                     .unwrap_or_default();
 
                 let (ref_size, ref_weight) = references_size
                     .as_ref()
-                    .map(|(_, vs, fs, weight)| (vs + fs.unwrap_or_default() + 5, *weight as u32))
+                    .map(|(_, vs, fs, weight)| (vs + fs.unwrap_or_default() + 6, *weight as u32))
                     .unwrap_or_default();
 
                 let (subtree_size, subtree_weight) = match subtrees_size {
                     None => None,
                     Some((_, est, fs, weight)) => Some((
-                        est.estimated_size()? + fs.unwrap_or_default() + 3,
+                        est.estimated_size()? + fs.unwrap_or_default() + 4,
                         *weight as u32,
                     )),
                 }
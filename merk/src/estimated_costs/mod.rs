@@ -44,11 +44,12 @@ pub mod worst_case_costs;
 
 #[cfg(feature = "full")]
 /// The cost of a subtree layer
-/// It is 3 because we have:
+/// It is 4 because we have:
+/// 1 byte for the element format version tag
 /// 1 byte for the element type
 /// 1 byte for the root key option
 /// 1 byte for the flag option
-pub const LAYER_COST_SIZE: u32 = 3;
+pub const LAYER_COST_SIZE: u32 = 4;
 
 #[cfg(any(feature = "full", feature = "verify"))]
 /// The cost of a sum value
@@ -73,9 +73,9 @@ pub use ed;
 #[cfg(feature = "full")]
 pub use error::Error;
 #[cfg(any(feature = "full", feature = "verify"))]
-pub use proofs::query::execute_proof;
-#[cfg(any(feature = "full", feature = "verify"))]
 pub use proofs::query::verify_query;
+#[cfg(any(feature = "full", feature = "verify"))]
+pub use proofs::query::{execute_keys_only_proof, execute_proof};
 #[cfg(feature = "full")]
 pub use tree::{
     BatchEntry, Link, MerkBatch, Op, PanicSource, HASH_BLOCK_SIZE, HASH_BLOCK_SIZE_U32,
@@ -0,0 +1,125 @@
+// MIT LICENSE
+//
+// Copyright (c) 2021 Dash Core Group
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Integrity verification of Merk trees stored on disk.
+
+#[cfg(feature = "full")]
+use grovedb_costs::{cost_return_on_error, CostResult, CostsExt, OperationCost};
+#[cfg(feature = "full")]
+use grovedb_storage::StorageContext;
+
+#[cfg(feature = "full")]
+use super::Merk;
+#[cfg(feature = "full")]
+use crate::{
+    error::Error,
+    tree::{kv_digest_to_kv_hash, node_hash, Tree, NULL_HASH},
+    CryptoHash,
+};
+
+#[cfg(feature = "full")]
+impl<'db, S> Merk<S>
+where
+    S: StorageContext<'db>,
+{
+    /// Re-reads every node of the tree from storage and recomputes its hash
+    /// from the key/value hash and the hashes of its children, the same way
+    /// chunk verification does. Returns `false` if a recomputed hash differs
+    /// from what is stored in the node, in the parent's link or in the root
+    /// hash.
+    ///
+    /// Merk doesn't know how values relate to their value hashes (they may be
+    /// combined with a subtree root hash or a referenced value), so
+    /// `verify_value_hash` is called with the key, value and stored value hash
+    /// of each node to check that part.
+    pub fn verify_integrity<F>(&self, mut verify_value_hash: F) -> CostResult<bool, Error>
+    where
+        F: FnMut(&[u8], &[u8], &CryptoHash) -> CostResult<bool, Error>,
+    {
+        let mut cost = OperationCost::default();
+
+        let recomputed_root_hash = match self.root_key() {
+            Some(root_key) => cost_return_on_error!(
+                &mut cost,
+                self.verify_node_integrity(&root_key, &mut verify_value_hash)
+            ),
+            None => Some(NULL_HASH),
+        };
+        let root_hash = self.root_hash().unwrap_add_cost(&mut cost);
+
+        Ok(recomputed_root_hash == Some(root_hash)).wrap_with_cost(cost)
+    }
+
+    /// Verifies the node with the given key and all of its descendants,
+    /// returning the recomputed node hash or `None` if any check failed.
+    fn verify_node_integrity<F>(
+        &self,
+        key: &[u8],
+        verify_value_hash: &mut F,
+    ) -> CostResult<Option<CryptoHash>, Error>
+    where
+        F: FnMut(&[u8], &[u8], &CryptoHash) -> CostResult<bool, Error>,
+    {
+        let mut cost = OperationCost::default();
+
+        let Some(node) = cost_return_on_error!(&mut cost, Tree::get(&self.storage, key)) else {
+            return Ok(None).wrap_with_cost(cost);
+        };
+        let kv = &node.inner.kv;
+
+        if !cost_return_on_error!(
+            &mut cost,
+            verify_value_hash(kv.key(), kv.value_as_slice(), kv.value_hash())
+        ) {
+            return Ok(None).wrap_with_cost(cost);
+        }
+
+        let kv_hash = kv_digest_to_kv_hash(kv.key(), kv.value_hash()).unwrap_add_cost(&mut cost);
+        if &kv_hash != kv.hash() {
+            return Ok(None).wrap_with_cost(cost);
+        }
+
+        let mut child_hashes = [NULL_HASH; 2];
+        for (child_hash, left) in child_hashes.iter_mut().zip([true, false]) {
+            if let Some(link) = node.link(left) {
+                let recomputed = cost_return_on_error!(
+                    &mut cost,
+                    self.verify_node_integrity(link.key(), verify_value_hash)
+                );
+                match recomputed {
+                    Some(hash) if &hash == link.hash() => *child_hash = hash,
+                    _ => return Ok(None).wrap_with_cost(cost),
+                }
+            }
+        }
+
+        let hash =
+            node_hash(&kv_hash, &child_hashes[0], &child_hashes[1]).unwrap_add_cost(&mut cost);
+        Ok(Some(hash)).wrap_with_cost(cost)
+    }
+}
@@ -32,6 +32,8 @@ pub mod chunks;
 
 pub(crate) mod defaults;
 
+pub mod integrity;
+
 pub mod options;
 
 pub mod restore;
@@ -964,6 +966,42 @@ where
             .map_ok(|(proof, limit, offset)| ProofWithoutEncodingResult::new(proof, limit, offset))
     }
 
+    /// Same as `prove_without_encoding`, but fails with
+    /// `Error::ProofNodeLimitExceeded` as soon as the proof would contain
+    /// more than `max_nodes` nodes, instead of building it whole.
+    pub fn prove_without_encoding_within_node_budget(
+        &self,
+        query: Query,
+        limit: Option<u16>,
+        offset: Option<u16>,
+        max_nodes: Option<usize>,
+    ) -> CostResult<ProofWithoutEncodingResult, Error> {
+        let left_to_right = query.left_to_right;
+        let query_vec: Vec<QueryItem> = query.into_iter().collect();
+        let mut node_budget = max_nodes;
+
+        self.use_tree_mut(|maybe_tree| {
+            maybe_tree
+                .ok_or(Error::CorruptedCodeExecution(
+                    "Cannot create proof for empty tree",
+                ))
+                .wrap_with_cost(Default::default())
+                .flat_map_ok(|tree| {
+                    let mut ref_walker = RefWalker::new(tree, self.source());
+                    ref_walker.create_proof_within_node_budget(
+                        query_vec.as_slice(),
+                        limit,
+                        offset,
+                        left_to_right,
+                        &mut node_budget,
+                    )
+                })
+                .map_ok(|(proof, _, limit, offset, ..)| {
+                    ProofWithoutEncodingResult::new(proof, limit, offset)
+                })
+        })
+    }
+
     /// Creates a Merkle proof for the list of queried keys. For each key in
     /// the query, if the key is found in the store then the value will be
     /// proven to be in the tree. For each key in the query that does not
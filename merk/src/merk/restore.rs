@@ -32,6 +32,8 @@
 #[cfg(feature = "full")]
 use std::{iter::Peekable, u8};
 
+#[cfg(feature = "full")]
+use grovedb_costs::OperationCost;
 #[cfg(feature = "full")]
 use grovedb_storage::{Batch, StorageContext};
 
@@ -42,7 +44,7 @@ use crate::{
     error::Error,
     merk::MerkSource,
     proofs::{
-        chunk::{verify_leaf, verify_trunk, MIN_TRUNK_HEIGHT},
+        chunk::{execute_leaf, verify_leaf, verify_trunk, MIN_TRUNK_HEIGHT},
         tree::{Child, Tree as ProofTree},
         Node, Op,
     },
@@ -63,6 +65,8 @@ pub struct Restorer<S> {
     merk: Merk<S>,
     expected_root_hash: CryptoHash,
     combining_value: Option<Vec<u8>>,
+    trusted: bool,
+    cost: OperationCost,
 }
 
 #[cfg(feature = "full")]
@@ -72,12 +76,19 @@ impl<'db, S: StorageContext<'db>> Restorer<S> {
     /// `expected_root_hash`, then each subsequent chunk will be compared
     /// against the hashes stored in the trunk, so that the restore process will
     /// never allow malicious peers to send more than a single invalid chunk.
+    ///
+    /// With `trusted` set the per-chunk verification is skipped and only the
+    /// root hash of the finished tree is checked, see `Merk::restore_trusted`
+    /// for why this is only safe for chunks coming from a trusted source.
     pub fn new(
         merk: Merk<S>,
         combining_value: Option<Vec<u8>>,
         expected_root_hash: CryptoHash,
+        trusted: bool,
     ) -> Self {
         Self {
+            trusted,
+            cost: OperationCost::default(),
             expected_root_hash,
             combining_value,
             trunk_height: None,
@@ -117,9 +128,25 @@ impl<'db, S: StorageContext<'db>> Restorer<S> {
 
         self.merk.load_base_root().unwrap()?;
 
+        if self.trusted {
+            let root_hash = self.combined_hash(self.merk.root_hash().unwrap());
+            if root_hash != self.expected_root_hash {
+                return Err(Error::ChunkRestoringError(format!(
+                    "Restored tree did not match expected hash\n\tExpected: {:?}\n\tActual: \
+                     {:?}",
+                    self.expected_root_hash, root_hash
+                )));
+            }
+        }
+
         Ok(self.merk)
     }
 
+    /// Returns the cost of executing and verifying the chunks processed so far.
+    pub fn cost(&self) -> &OperationCost {
+        &self.cost
+    }
+
     /// Returns the number of remaining chunks to be processed. If called before
     /// the first chunk is processed, this method will return `None` since we do
     /// not yet have enough information to know about the number of chunks.
@@ -175,24 +202,19 @@ impl<'db, S: StorageContext<'db>> Restorer<S> {
 
     /// Verifies the trunk then writes its data to the RocksDB.
     fn process_trunk(&mut self, ops: impl IntoIterator<Item = Op>) -> Result<usize, Error> {
-        let (trunk, height) = verify_trunk(ops.into_iter().map(Ok)).unwrap()?;
-
-        let root_hash = if self.combining_value.is_none() {
-            trunk.hash().unwrap()
-        } else {
-            combine_hash(
-                value_hash(self.combining_value.as_ref().expect("confirmed exists")).value(),
-                &trunk.hash().unwrap(),
-            )
-            .value
-        };
-
-        if root_hash != self.expected_root_hash {
-            return Err(Error::ChunkRestoringError(format!(
-                "Proof did not match expected hash\n\tExpected: {:?}\n\tActual: {:?}",
-                self.expected_root_hash,
-                trunk.hash()
-            )));
+        let (trunk, height) =
+            verify_trunk(ops.into_iter().map(Ok)).unwrap_add_cost(&mut self.cost)?;
+
+        if !self.trusted {
+            let trunk_hash = trunk.hash().unwrap_add_cost(&mut self.cost);
+            let root_hash = self.combined_hash(trunk_hash);
+
+            if root_hash != self.expected_root_hash {
+                return Err(Error::ChunkRestoringError(format!(
+                    "Proof did not match expected hash\n\tExpected: {:?}\n\tActual: {:?}",
+                    self.expected_root_hash, trunk_hash
+                )));
+            }
         }
 
         let root_key = trunk.key().to_vec();
@@ -247,7 +269,12 @@ impl<'db, S: StorageContext<'db>> Restorer<S> {
             .peek()
             .expect("Received more chunks than expected");
 
-        let leaf = verify_leaf(ops.into_iter().map(Ok), *leaf_hash).unwrap()?;
+        let leaf = if self.trusted {
+            execute_leaf(ops.into_iter().map(Ok))
+        } else {
+            verify_leaf(ops.into_iter().map(Ok), *leaf_hash)
+        }
+        .unwrap_add_cost(&mut self.cost)?;
         self.rewrite_parent_link(&leaf)?;
         self.write_chunk(leaf)?;
 
@@ -257,6 +284,17 @@ impl<'db, S: StorageContext<'db>> Restorer<S> {
         Ok(self.remaining_chunks_unchecked())
     }
 
+    /// Combines the hash of the restored tree with the value of the element
+    /// holding it, if any, to get the hash its parent commits to.
+    fn combined_hash(&self, tree_hash: CryptoHash) -> CryptoHash {
+        match &self.combining_value {
+            None => tree_hash,
+            Some(combining_value) => {
+                combine_hash(value_hash(combining_value).value(), &tree_hash).value
+            }
+        }
+    }
+
     /// The parent of the root node of the leaf does not know the key of its
     /// children when it is first written. Now that we have verified this leaf,
     /// we can write the key into the parent node's entry. Note that this does
@@ -353,7 +391,21 @@ impl<'db, S: StorageContext<'db>> Merk<S> {
     /// replicate an entire Merk tree. A new Merk instance will be initialized
     /// by creating a RocksDB at `path`.
     pub fn restore(merk: Merk<S>, expected_root_hash: CryptoHash) -> Restorer<S> {
-        Restorer::new(merk, None, expected_root_hash)
+        Restorer::new(merk, None, expected_root_hash, false)
+    }
+
+    /// Creates a `Restorer` for chunks from a trusted source, skipping the
+    /// verification of each chunk and only comparing the root hash of the
+    /// finished tree against `expected_root_hash`.
+    ///
+    /// SECURITY: never use this with chunks received from untrusted peers.
+    /// The final check only covers the hashes written into the trunk, not the
+    /// leaf chunks hanging from it: a leaf chunk with arbitrary keys and
+    /// values is accepted as is, leaving a tree whose root hash matches but
+    /// whose contents don't. Invalid chunks are also only detected once the
+    /// whole tree was written, so nothing can be retried.
+    pub fn restore_trusted(merk: Merk<S>, expected_root_hash: CryptoHash) -> Restorer<S> {
+        Restorer::new(merk, None, expected_root_hash, true)
     }
 }
 
@@ -403,7 +455,9 @@ mod tests {
 
     fn restore_test(batches: &[&MerkBatch<Vec<u8>>], expected_nodes: usize) {
         let storage = TempStorage::new();
-        let tx = storage.start_transaction();
+        let tx = storage
+            .start_transaction()
+            .expect("cannot start transaction");
         let mut original = Merk::open_base(
             storage
                 .get_immediate_storage_context(SubtreePath::empty(), &tx)
@@ -422,7 +476,9 @@ mod tests {
         let chunks = original.chunks().unwrap();
 
         let storage = TempStorage::default();
-        let _tx2 = storage.start_transaction();
+        let _tx2 = storage
+            .start_transaction()
+            .expect("cannot start transaction");
         let ctx = storage
             .get_immediate_storage_context(SubtreePath::empty(), &tx)
             .unwrap();
@@ -483,6 +539,92 @@ mod tests {
         restore_test(&[&make_batch_seq(0..1)], 1);
     }
 
+    #[test]
+    fn restore_trusted_matches_verified_restore() {
+        let mut original = TempMerk::new();
+        original
+            .apply::<_, Vec<_>>(&make_batch_seq(0..10_000), &[], None)
+            .unwrap()
+            .unwrap();
+        original.commit();
+        let chunks = original
+            .chunks()
+            .unwrap()
+            .map(|chunk| chunk.unwrap())
+            .collect::<Vec<_>>();
+
+        let verified_storage = TempStorage::new();
+        let verified_tx = verified_storage
+            .start_transaction()
+            .expect("cannot start transaction");
+        let merk = Merk::open_base(
+            verified_storage
+                .get_immediate_storage_context(SubtreePath::empty(), &verified_tx)
+                .unwrap(),
+            false,
+        )
+        .unwrap()
+        .unwrap();
+        let mut verified_restorer = Merk::restore(merk, original.root_hash().unwrap());
+
+        let trusted_storage = TempStorage::new();
+        let trusted_tx = trusted_storage
+            .start_transaction()
+            .expect("cannot start transaction");
+        let merk = Merk::open_base(
+            trusted_storage
+                .get_immediate_storage_context(SubtreePath::empty(), &trusted_tx)
+                .unwrap(),
+            false,
+        )
+        .unwrap()
+        .unwrap();
+        let mut trusted_restorer = Merk::restore_trusted(merk, original.root_hash().unwrap());
+
+        for chunk in chunks {
+            verified_restorer.process_chunk(chunk.clone()).unwrap();
+            trusted_restorer.process_chunk(chunk).unwrap();
+        }
+        assert!(trusted_restorer.cost().hash_node_calls < verified_restorer.cost().hash_node_calls);
+
+        let verified = verified_restorer.finalize().unwrap();
+        let trusted = trusted_restorer.finalize().unwrap();
+        assert_raw_db_entries_eq(&trusted, &verified, 10_000);
+    }
+
+    #[test]
+    fn restore_trusted_rejects_wrong_root_hash() {
+        let mut original = TempMerk::new();
+        original
+            .apply::<_, Vec<_>>(&make_batch_seq(0..10_000), &[], None)
+            .unwrap()
+            .unwrap();
+        original.commit();
+        let chunks = original.chunks().unwrap();
+
+        let storage = TempStorage::new();
+        let tx = storage
+            .start_transaction()
+            .expect("cannot start transaction");
+        let merk = Merk::open_base(
+            storage
+                .get_immediate_storage_context(SubtreePath::empty(), &tx)
+                .unwrap(),
+            false,
+        )
+        .unwrap()
+        .unwrap();
+        let mut restorer = Merk::restore_trusted(merk, [0; 32]);
+
+        for chunk in chunks {
+            restorer.process_chunk(chunk.unwrap()).unwrap();
+        }
+        assert!(matches!(
+            restorer.finalize(),
+            Err(Error::ChunkRestoringError(_))
+        ));
+    }
+
     fn assert_raw_db_entries_eq(
         restored: &Merk<PrefixedRocksDbImmediateStorageContext>,
         original: &Merk<PrefixedRocksDbImmediateStorageContext>,
@@ -28,6 +28,11 @@
 
 //! Chunk proofs
 
+#[cfg(feature = "full")]
+use std::collections::{HashMap, VecDeque};
+
+#[cfg(feature = "full")]
+use ed::Encode;
 #[cfg(feature = "full")]
 use grovedb_costs::{
     cost_return_on_error, cost_return_on_error_no_add, CostResult, CostsExt, OperationCost,
@@ -253,6 +258,197 @@ pub(crate) fn get_next_chunk(
     Ok(chunk).wrap_with_cost(cost)
 }
 
+/// Node of a chunk built by [get_next_chunk_bounded] along with keys and
+/// hashes of its children, used to abridge the ones which don't fit
+#[cfg(feature = "full")]
+struct BoundedChunkNode {
+    kv: Node,
+    left: Option<(Vec<u8>, CryptoHash)>,
+    right: Option<(Vec<u8>, CryptoHash)>,
+}
+
+#[cfg(feature = "full")]
+impl BoundedChunkNode {
+    /// Reads the node with `key` using the RocksDB iterator
+    fn read(iter: &mut impl RawIterator, key: &[u8]) -> CostResult<Self, Error> {
+        let mut cost = OperationCost::default();
+
+        iter.seek(key).unwrap_add_cost(&mut cost);
+        let found_key = iter.key().unwrap_add_cost(&mut cost);
+        let encoded_node = match iter.value().unwrap_add_cost(&mut cost) {
+            Some(encoded_node) if found_key == Some(key) => encoded_node,
+            _ => {
+                return Err(Error::ChunkingError("Chunk node is missing from storage"))
+                    .wrap_with_cost(cost)
+            }
+        };
+        let node = cost_return_on_error_no_add!(
+            &cost,
+            Tree::decode(key.to_vec(), encoded_node).map_err(EdError)
+        );
+
+        let child = |left| {
+            node.link(left)
+                .map(|link| (link.key().to_vec(), *link.hash()))
+        };
+        Ok(BoundedChunkNode {
+            kv: Node::KVValueHashFeatureType(
+                key.to_vec(),
+                node.value_ref().to_vec(),
+                *node.value_hash(),
+                node.feature_type(),
+            ),
+            left: child(true),
+            right: child(false),
+        })
+        .wrap_with_cost(cost)
+    }
+
+    /// Length of the encoded ops of the node, with its children abridged
+    fn encoding_length(&self, hash_op_length: usize) -> Result<usize, Error> {
+        let children = self.left.iter().chain(self.right.iter()).count();
+        let child_op_length = Encode::encoding_length(&Op::Child).map_err(EdError)?;
+        let kv_op_length = Encode::encoding_length(&Op::Push(self.kv.clone())).map_err(EdError)?;
+        Ok(kv_op_length + children * (hash_op_length + child_op_length))
+    }
+
+    fn children_keys(&self) -> impl Iterator<Item = Vec<u8>> + '_ {
+        self.left
+            .iter()
+            .chain(self.right.iter())
+            .map(|(key, _)| key.clone())
+    }
+
+    /// Pushes the ops of the node subtree, nodes missing from `nodes` are
+    /// abridged and their keys are pushed to `abridged`, left to right
+    fn push_ops(
+        self,
+        nodes: &mut HashMap<Vec<u8>, BoundedChunkNode>,
+        ops: &mut Vec<Op>,
+        abridged: &mut Vec<Vec<u8>>,
+    ) {
+        let has_left = self.left.is_some();
+        if let Some(left) = self.left {
+            Self::push_child_ops(left, nodes, ops, abridged);
+        }
+        ops.push(Op::Push(self.kv));
+        if has_left {
+            ops.push(Op::Parent);
+        }
+        if let Some(right) = self.right {
+            Self::push_child_ops(right, nodes, ops, abridged);
+            ops.push(Op::Child);
+        }
+    }
+
+    fn push_child_ops(
+        (key, hash): (Vec<u8>, CryptoHash),
+        nodes: &mut HashMap<Vec<u8>, BoundedChunkNode>,
+        ops: &mut Vec<Op>,
+        abridged: &mut Vec<Vec<u8>>,
+    ) {
+        match nodes.remove(&key) {
+            Some(child) => child.push_ops(nodes, ops, abridged),
+            None => {
+                ops.push(Op::Push(Node::Hash(hash)));
+                abridged.push(key);
+            }
+        }
+    }
+}
+
+/// Builds a chunk proof of the subtree rooted at the last key of `stack`,
+/// bounded by the encoded byte size of its ops. Nodes are taken breadth first
+/// from the subtree root as long as they fit into `max_bytes`, subtrees of the
+/// remaining children are abridged to `Hash` nodes and their roots are pushed
+/// to `stack` to be built as the following chunks. Returns the ops and the key
+/// of the next chunk root, or `None` once the whole subtree is chunked.
+///
+/// `stack` must initially contain only the key of the subtree root and be
+/// passed unchanged between calls. Chunks are built depth first, left to
+/// right, so each of them can be verified on its own with
+/// [verify_bounded_chunk] against the hash of the subtree or the hash its root
+/// was abridged to. A chunk always contains at least one node, so it may
+/// exceed `max_bytes` only if a single node's ops are bigger than the budget.
+#[cfg(feature = "full")]
+pub fn get_next_chunk_bounded(
+    iter: &mut impl RawIterator,
+    stack: &mut Vec<Vec<u8>>,
+    max_bytes: usize,
+) -> CostResult<(Vec<Op>, Option<Vec<u8>>), Error> {
+    let mut cost = OperationCost::default();
+
+    let root_key = match stack.pop() {
+        Some(root_key) => root_key,
+        None => return Ok((vec![], None)).wrap_with_cost(cost),
+    };
+    let hash_op_length = cost_return_on_error_no_add!(
+        &cost,
+        Encode::encoding_length(&Op::Push(Node::Hash(Default::default()))).map_err(EdError)
+    );
+
+    let root = cost_return_on_error!(&mut cost, BoundedChunkNode::read(iter, &root_key));
+    let mut chunk_bytes = cost_return_on_error_no_add!(&cost, root.encoding_length(hash_op_length));
+    let mut queue: VecDeque<Vec<u8>> = root.children_keys().collect();
+    let mut nodes = HashMap::new();
+    while let Some(key) = queue.pop_front() {
+        let node = cost_return_on_error!(&mut cost, BoundedChunkNode::read(iter, &key));
+        let node_bytes = cost_return_on_error_no_add!(&cost, node.encoding_length(hash_op_length));
+        // The node replaces the `Hash` node its subtree was abridged to
+        if chunk_bytes + node_bytes > max_bytes + hash_op_length {
+            continue;
+        }
+        chunk_bytes = chunk_bytes + node_bytes - hash_op_length;
+        queue.extend(node.children_keys());
+        nodes.insert(key, node);
+    }
+
+    let mut chunk = Vec::new();
+    let mut abridged = Vec::new();
+    root.push_ops(&mut nodes, &mut chunk, &mut abridged);
+    stack.extend(abridged.into_iter().rev());
+
+    Ok((chunk, stack.last().cloned())).wrap_with_cost(cost)
+}
+
+/// Verifies a chunk proof built by [get_next_chunk_bounded] by executing its
+/// operators. Checks that there were no KVHash nodes and the proof hashes to
+/// `expected_hash`. Returns the tree and hashes of the abridged subtrees, left
+/// to right, which are the expected hashes of the chunks built for them.
+#[cfg(feature = "full")]
+pub fn verify_bounded_chunk<I: Iterator<Item = Result<Op, Error>>>(
+    ops: I,
+    expected_hash: CryptoHash,
+) -> CostResult<(ProofTree, Vec<CryptoHash>), Error> {
+    let mut cost = OperationCost::default();
+
+    let mut abridged = Vec::new();
+    let tree = cost_return_on_error!(
+        &mut cost,
+        execute(ops, false, |node| match node {
+            Node::Hash(hash) => {
+                abridged.push(*hash);
+                Ok(())
+            }
+            Node::KVValueHash(..) | Node::KV(..) | Node::KVValueHashFeatureType(..) => Ok(()),
+            _ => Err(Error::ChunkRestoringError(
+                "Chunks must contain full nodes or abridged subtrees".to_string(),
+            )),
+        })
+    );
+
+    let hash = tree.hash().unwrap_add_cost(&mut cost);
+    if hash != expected_hash {
+        return Err(Error::ChunkRestoringError(format!(
+            "Chunk proof did not match expected hash\n\tExpected: {:?}\n\tActual: {:?}",
+            expected_hash, hash
+        )))
+        .wrap_with_cost(cost);
+    }
+
+    Ok((tree, abridged)).wrap_with_cost(cost)
+}
+
 /// Verifies a leaf chunk proof by executing its operators. Checks that there
 /// were no abridged nodes (Hash or KVHash) and the proof hashes to
 /// `expected_hash`.
@@ -262,13 +458,7 @@ pub(crate) fn verify_leaf<I: Iterator<Item = Result<Op, Error>>>(
     ops: I,
     expected_hash: CryptoHash,
 ) -> CostResult<ProofTree, Error> {
-    execute(ops, false, |node| match node {
-        Node::KVValueHash(..) | Node::KV(..) | Node::KVValueHashFeatureType(..) => Ok(()),
-        _ => Err(Error::ChunkRestoringError(
-            "Leaf chunks must contain full subtree".to_string(),
-        )),
-    })
-    .flat_map_ok(|tree| {
+    execute_leaf(ops).flat_map_ok(|tree| {
         tree.hash().map(|hash| {
             if hash != expected_hash {
                 Error::ChunkRestoringError(format!(
@@ -283,6 +473,48 @@ pub(crate) fn verify_leaf<I: Iterator<Item = Result<Op, Error>>>(
     })
 }
 
+/// Verifies a leaf chunk proof by executing its operators. Checks that there
+/// were no abridged nodes (Hash or KVHash), the proof hashes to
+/// `expected_hash` and the chunk contains exactly `expected_count` key/value
+/// nodes.
+#[cfg(feature = "full")]
+pub fn verify_leaf_with_count<I: Iterator<Item = Result<Op, Error>>>(
+    ops: I,
+    expected_hash: CryptoHash,
+    expected_count: usize,
+) -> CostResult<ProofTree, Error> {
+    verify_leaf(ops, expected_hash).map(|result| {
+        let tree = result?;
+        let mut count = 0;
+        tree.visit_refs(&mut |_| {
+            count += 1;
+            Ok(())
+        })?;
+        if count != expected_count {
+            return Err(Error::ChunkRestoringError(format!(
+                "Leaf chunk contained {} nodes, expected {}",
+                count, expected_count
+            )));
+        }
+        Ok(tree)
+    })
+}
+
+/// Executes a leaf chunk proof, only checking that there were no abridged
+/// nodes (Hash or KVHash). Unlike `verify_leaf` the resulting tree is not
+/// hashed, so nothing ties it to the trunk it is supposed to hang from.
+#[cfg(feature = "full")]
+pub(crate) fn execute_leaf<I: Iterator<Item = Result<Op, Error>>>(
+    ops: I,
+) -> CostResult<ProofTree, Error> {
+    execute(ops, false, |node| match node {
+        Node::KVValueHash(..) | Node::KV(..) | Node::KVValueHashFeatureType(..) => Ok(()),
+        _ => Err(Error::ChunkRestoringError(
+            "Leaf chunks must contain full subtree".to_string(),
+        )),
+    })
+}
+
 /// Verifies a trunk chunk proof by executing its operators. Ensures the
 /// resulting tree contains a valid height proof, the trunk is the correct
 /// height, and all of its inner nodes are not abridged. Returns the tree and
@@ -642,4 +874,90 @@ mod tests {
         assert_eq!(counts.hash, 0);
         assert_eq!(counts.kv_hash, 0);
     }
+
+    #[test]
+    fn leaf_chunk_node_count() {
+        let mut merk = TempMerk::new();
+        let batch = make_batch_seq(0..31);
+        merk.apply::<_, Vec<_>>(batch.as_slice(), &[], None)
+            .unwrap()
+            .unwrap();
+
+        merk.commit();
+
+        let mut iter = merk.storage.raw_iter();
+        iter.seek_to_first().unwrap();
+        let chunk = get_next_chunk(&mut iter, None).unwrap().unwrap();
+        drop(iter);
+
+        let root_hash = merk.root_hash().unwrap();
+        assert!(
+            verify_leaf_with_count(chunk.clone().into_iter().map(Ok), root_hash, 31)
+                .unwrap()
+                .is_ok()
+        );
+        assert!(matches!(
+            verify_leaf_with_count(chunk.clone().into_iter().map(Ok), root_hash, 30).unwrap(),
+            Err(Error::ChunkRestoringError(_))
+        ));
+        assert!(matches!(
+            verify_leaf_with_count(chunk.into_iter().map(Ok), root_hash, 32).unwrap(),
+            Err(Error::ChunkRestoringError(_))
+        ));
+    }
+
+    #[test]
+    fn bounded_chunks_verify_on_their_own() {
+        let mut merk = TempMerk::new();
+        let batch = make_batch_seq(0..31);
+        merk.apply::<_, Vec<_>>(batch.as_slice(), &[], None)
+            .unwrap()
+            .unwrap();
+        merk.commit();
+
+        let max_bytes = 600;
+        let mut iter = merk.storage.raw_iter();
+        let mut stack = vec![merk.root_key().expect("tree is not empty")];
+        let mut expected_hashes = vec![merk.root_hash().unwrap()];
+
+        let (chunk, _) = get_next_chunk_bounded(&mut iter, &mut stack.clone(), max_bytes)
+            .unwrap()
+            .unwrap();
+        assert!(matches!(
+            verify_bounded_chunk(chunk.into_iter().map(Ok), [0; 32]).unwrap(),
+            Err(Error::ChunkRestoringError(_))
+        ));
+
+        let mut chunks = 0;
+        let mut kv_nodes = 0;
+        loop {
+            let (chunk, next_key) = get_next_chunk_bounded(&mut iter, &mut stack, max_bytes)
+                .unwrap()
+                .unwrap();
+            let chunk_bytes: usize = chunk
+                .iter()
+                .map(|op| Encode::encoding_length(op).unwrap())
+                .sum();
+            assert!(chunk_bytes <= max_bytes);
+            chunks += 1;
+
+            let expected_hash = expected_hashes.pop().expect("chunk hash is expected");
+            let (tree, abridged) = verify_bounded_chunk(chunk.into_iter().map(Ok), expected_hash)
+                .unwrap()
+                .unwrap();
+            let counts = count_node_types(tree);
+            assert_eq!(counts.hash, abridged.len());
+            assert_eq!(counts.kv_hash, 0);
+            kv_nodes += counts.kv_value_hash_feature_type;
+            expected_hashes.extend(abridged.into_iter().rev());
+
+            assert_eq!(next_key, stack.last().cloned());
+            if next_key.is_none() {
+                break;
+            }
+        }
+        assert!(chunks > 1);
+        assert!(expected_hashes.is_empty());
+        assert_eq!(kv_nodes, 31);
+    }
 }
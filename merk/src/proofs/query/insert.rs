@@ -123,6 +123,17 @@ impl Query {
         self.insert_item(range);
     }
 
+    /// Adds a key prefix to the query, so that all the entries in the tree
+    /// with keys starting with the prefix will be included in the resulting
+    /// proof.
+    ///
+    /// If the query already includes a range that overlaps with the keys
+    /// under the prefix, they will be joined together.
+    pub fn insert_key_prefix(&mut self, prefix: Vec<u8>) {
+        let range = QueryItem::KeyPrefix(prefix);
+        self.insert_item(range);
+    }
+
     /// Adds a range of all potential values to the query, so that the query
     /// will return all values
     ///
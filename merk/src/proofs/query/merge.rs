@@ -8,6 +8,15 @@ use crate::proofs::{
     Query,
 };
 
+/// Merged branches must return everything either side would have returned,
+/// so the more permissive of the two limits wins
+fn merge_limits(left: Option<u16>, right: Option<u16>) -> Option<u16> {
+    match (left, right) {
+        (Some(left), Some(right)) => Some(left.max(right)),
+        _ => None,
+    }
+}
+
 impl SubqueryBranch {
     fn merge_subquery(
         &self,
@@ -33,6 +42,7 @@ impl SubqueryBranch {
                 SubqueryBranch {
                     subquery_path: None,
                     subquery,
+                    limit: merge_limits(self.limit, other.limit),
                 }
             }
             (Some(our_subquery_path), Some(their_subquery_path)) => {
@@ -45,6 +55,7 @@ impl SubqueryBranch {
                     SubqueryBranch {
                         subquery_path: Some(our_subquery_path.clone()),
                         subquery,
+                        limit: merge_limits(self.limit, other.limit),
                     }
                 } else {
                     // We need to find the common path between the two subqueries
@@ -86,6 +97,7 @@ impl SubqueryBranch {
                             SubqueryBranch {
                                 subquery_path: maybe_left_path_leftovers,
                                 subquery: self.subquery.clone(),
+                                limit: self.limit,
                             },
                         );
                         let right_top_key = right_path_leftovers.remove(0);
@@ -101,11 +113,13 @@ impl SubqueryBranch {
                             SubqueryBranch {
                                 subquery_path: maybe_right_path_leftovers,
                                 subquery: other.subquery.clone(),
+                                limit: other.limit,
                             },
                         );
                         SubqueryBranch {
                             subquery_path,
                             subquery: Some(Box::new(merged_query)),
+                            limit: merge_limits(self.limit, other.limit),
                         }
                     } else if right_path_leftovers.is_empty() {
                         // this means our subquery path was longer
@@ -126,11 +140,13 @@ impl SubqueryBranch {
                             SubqueryBranch {
                                 subquery_path: maybe_left_path_leftovers,
                                 subquery: self.subquery.clone(),
+                                limit: self.limit,
                             },
                         );
                         SubqueryBranch {
                             subquery_path,
                             subquery: Some(merged_query),
+                            limit: merge_limits(self.limit, other.limit),
                         }
                     } else if left_path_leftovers.is_empty() {
                         let mut merged_query = self.subquery.clone().unwrap_or_default();
@@ -151,11 +167,13 @@ impl SubqueryBranch {
                             SubqueryBranch {
                                 subquery_path: maybe_right_path_leftovers,
                                 subquery: other.subquery.clone(),
+                                limit: other.limit,
                             },
                         );
                         SubqueryBranch {
                             subquery_path,
                             subquery: Some(merged_query),
+                            limit: merge_limits(self.limit, other.limit),
                         }
                     } else {
                         unreachable!("Unreachable as both paths being equal already covered");
@@ -190,12 +208,14 @@ impl SubqueryBranch {
                     SubqueryBranch {
                         subquery_path: maybe_our_subquery_path,
                         subquery: self.subquery.clone(),
+                        limit: self.limit,
                     },
                 );
 
                 SubqueryBranch {
                     subquery_path: None,
                     subquery: Some(merged_subquery),
+                    limit: merge_limits(self.limit, other.limit),
                 }
             }
             (None, Some(their_subquery_path)) => {
@@ -226,12 +246,14 @@ impl SubqueryBranch {
                     SubqueryBranch {
                         subquery_path: maybe_their_subquery_path,
                         subquery: other.subquery.clone(),
+                        limit: other.limit,
                     },
                 );
 
                 SubqueryBranch {
                     subquery_path: None,
                     subquery: Some(merged_subquery),
+                    limit: merge_limits(self.limit, other.limit),
                 }
             }
         }
@@ -260,6 +282,7 @@ impl Query {
     /// present. Merging involves creating conditional subqueries in the
     /// subqueries subqueries and paths.
     pub fn merge_default_subquery_branch(&mut self, other_default_subquery_branch: SubqueryBranch) {
+        let our_limit = self.default_subquery_branch.limit;
         match (
             &self.default_subquery_branch.subquery_path,
             &other_default_subquery_branch.subquery_path,
@@ -313,6 +336,7 @@ impl Query {
                             SubqueryBranch {
                                 subquery_path: maybe_left_path_leftovers,
                                 subquery: self.default_subquery_branch.subquery.clone(),
+                                limit: our_limit,
                             },
                         );
                         let right_top_key = right_path_leftovers.remove(0);
@@ -327,6 +351,7 @@ impl Query {
                             SubqueryBranch {
                                 subquery_path: maybe_right_path_leftovers,
                                 subquery: other_default_subquery_branch.subquery.clone(),
+                                limit: other_default_subquery_branch.limit,
                             },
                         );
                     } else if right_path_leftovers.is_empty() {
@@ -349,6 +374,7 @@ impl Query {
                             SubqueryBranch {
                                 subquery_path: maybe_left_path_leftovers,
                                 subquery: left_subquery,
+                                limit: our_limit,
                             },
                         );
                     } else if left_path_leftovers.is_empty() {
@@ -368,6 +394,7 @@ impl Query {
                             SubqueryBranch {
                                 subquery_path: maybe_right_path_leftovers,
                                 subquery: other_default_subquery_branch.subquery.clone(),
+                                limit: other_default_subquery_branch.limit,
                             },
                         );
                     } else {
@@ -400,6 +427,7 @@ impl Query {
                     SubqueryBranch {
                         subquery_path: maybe_our_subquery_path,
                         subquery: other_default_subquery_branch.subquery.clone(),
+                        limit: other_default_subquery_branch.limit,
                     },
                 );
             }
@@ -425,10 +453,13 @@ impl Query {
                     SubqueryBranch {
                         subquery_path: maybe_their_subquery_path,
                         subquery: other_default_subquery_branch.subquery.clone(),
+                        limit: other_default_subquery_branch.limit,
                     },
                 );
             }
         }
+        self.default_subquery_branch.limit =
+            merge_limits(our_limit, other_default_subquery_branch.limit);
     }
 
     pub fn merge_multiple(mut queries: Vec<Query>) -> Self {
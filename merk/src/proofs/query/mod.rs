@@ -47,7 +47,10 @@ use std::cmp::Ordering;
 use std::collections::HashSet;
 
 #[cfg(any(feature = "full", feature = "verify"))]
-use grovedb_costs::{cost_return_on_error, CostContext, CostResult, CostsExt, OperationCost};
+use grovedb_costs::{
+    cost_return_on_error, cost_return_on_error_no_add, CostContext, CostResult, CostsExt,
+    OperationCost,
+};
 #[cfg(any(feature = "full", feature = "verify"))]
 use indexmap::IndexMap;
 #[cfg(feature = "full")]
@@ -59,7 +62,9 @@ pub use query_item::QueryItem;
 #[cfg(any(feature = "full", feature = "verify"))]
 use verify::ProofAbsenceLimitOffset;
 #[cfg(any(feature = "full", feature = "verify"))]
-pub use verify::{execute_proof, verify_query, ProofVerificationResult, ProvedKeyValue};
+pub use verify::{
+    execute_keys_only_proof, execute_proof, verify_query, ProofVerificationResult, ProvedKeyValue,
+};
 #[cfg(feature = "full")]
 use {super::Op, std::collections::LinkedList};
 
@@ -90,6 +95,9 @@ pub struct SubqueryBranch {
     pub subquery_path: Option<Path>,
     /// Subquery
     pub subquery: Option<Box<Query>>,
+    /// Maximum number of results this branch may contribute for each element
+    /// it is applied to, independently of the global limit
+    pub limit: Option<u16>,
 }
 
 #[cfg(any(feature = "full", feature = "verify"))]
@@ -355,6 +363,13 @@ impl Query {
         self.default_subquery_branch.subquery = Some(Box::new(subquery));
     }
 
+    /// Sets a limit on the default subquery branch. Each element that is
+    /// subqueried through the default branch contributes at most `limit`
+    /// results, regardless of the global limit of the query.
+    pub fn set_subquery_limit(&mut self, limit: Option<u16>) {
+        self.default_subquery_branch.limit = limit;
+    }
+
     /// Adds a conditional subquery. A conditional subquery replaces the default
     /// subquery and subquery_path if the item matches for the key. If
     /// multiple conditional subquery items match, then the first one that
@@ -371,6 +386,7 @@ impl Query {
                 SubqueryBranch {
                     subquery_path,
                     subquery: subquery.map(Box::new),
+                    limit: None,
                 },
             );
         } else {
@@ -380,6 +396,7 @@ impl Query {
                 SubqueryBranch {
                     subquery_path,
                     subquery: subquery.map(Box::new),
+                    limit: None,
                 },
             );
             self.conditional_subquery_branches = Some(conditional_subquery_branches);
@@ -414,6 +431,7 @@ impl<Q: Into<QueryItem>> From<Vec<Q>> for Query {
             default_subquery_branch: SubqueryBranch {
                 subquery_path: None,
                 subquery: None,
+                limit: None,
             },
             conditional_subquery_branches: None,
             left_to_right: true,
@@ -533,6 +551,21 @@ where
         limit: Option<u16>,
         offset: Option<u16>,
         left_to_right: bool,
+    ) -> CostResult<ProofAbsenceLimitOffset, Error> {
+        self.create_proof_within_node_budget(query, limit, offset, left_to_right, &mut None)
+    }
+
+    /// Same as `create_proof`, but stops with
+    /// `Error::ProofNodeLimitExceeded` as soon as more nodes than the
+    /// remaining `node_budget` would be pushed to the proof
+    #[cfg(feature = "full")]
+    pub(crate) fn create_proof_within_node_budget(
+        &mut self,
+        query: &[QueryItem],
+        limit: Option<u16>,
+        offset: Option<u16>,
+        left_to_right: bool,
+        node_budget: &mut Option<usize>,
     ) -> CostResult<ProofAbsenceLimitOffset, Error> {
         let mut cost = OperationCost::default();
 
@@ -560,6 +593,7 @@ where
                 let item = &query[index];
                 let (left_bound, left_not_inclusive) = item.lower_bound();
                 let (right_bound, right_inclusive) = item.upper_bound();
+                let right_bound = right_bound.as_deref();
 
                 if left_bound.is_some()
                     && left_bound.unwrap() == self.tree().key()
@@ -612,12 +646,26 @@ where
         let (mut proof, left_absence, mut new_limit, mut new_offset) = if left_to_right {
             cost_return_on_error!(
                 &mut cost,
-                self.create_child_proof(proof_direction, left_items, limit, offset, left_to_right)
+                self.create_child_proof(
+                    proof_direction,
+                    left_items,
+                    limit,
+                    offset,
+                    left_to_right,
+                    node_budget,
+                )
             )
         } else {
             cost_return_on_error!(
                 &mut cost,
-                self.create_child_proof(proof_direction, right_items, limit, offset, left_to_right)
+                self.create_child_proof(
+                    proof_direction,
+                    right_items,
+                    limit,
+                    offset,
+                    left_to_right,
+                    node_budget,
+                )
             )
         };
 
@@ -668,6 +716,7 @@ where
                     new_limit,
                     new_offset,
                     left_to_right,
+                    node_budget,
                 )
             )
         } else {
@@ -679,12 +728,14 @@ where
                     new_limit,
                     new_offset,
                     left_to_right,
+                    node_budget,
                 )
             )
         };
 
         let (has_left, has_right) = (!proof.is_empty(), !right_proof.is_empty());
 
+        cost_return_on_error_no_add!(&cost, take_proof_node(node_budget));
         proof.push_back(match search {
             Ok(_) => {
                 if node_on_non_inclusive_bounds || skip_current_node {
@@ -750,17 +801,27 @@ where
         limit: Option<u16>,
         offset: Option<u16>,
         left_to_right: bool,
+        node_budget: &mut Option<usize>,
     ) -> CostResult<ProofAbsenceLimitOffset, Error> {
         if !query.is_empty() {
             self.walk(left).flat_map_ok(|child_opt| {
                 if let Some(mut child) = child_opt {
-                    child.create_proof(query, limit, offset, left_to_right)
+                    child.create_proof_within_node_budget(
+                        query,
+                        limit,
+                        offset,
+                        left_to_right,
+                        node_budget,
+                    )
                 } else {
                     Ok((LinkedList::new(), (true, true), limit, offset))
                         .wrap_with_cost(Default::default())
                 }
             })
         } else if let Some(link) = self.tree().link(left) {
+            if let Err(e) = take_proof_node(node_budget) {
+                return Err(e).wrap_with_cost(Default::default());
+            }
             let mut proof = LinkedList::new();
             proof.push_back(if left_to_right {
                 Op::Push(link.to_hash_node())
@@ -775,6 +836,18 @@ where
     }
 }
 
+/// Takes a node from the budget of a proof, if it has one
+#[cfg(feature = "full")]
+fn take_proof_node(node_budget: &mut Option<usize>) -> Result<(), Error> {
+    if let Some(remaining) = node_budget {
+        if *remaining == 0 {
+            return Err(Error::ProofNodeLimitExceeded);
+        }
+        *remaining -= 1;
+    }
+    Ok(())
+}
+
 #[cfg(feature = "full")]
 #[allow(deprecated)]
 #[cfg(test)]
@@ -5067,6 +5140,37 @@ mod test {
         assert_eq!(res.offset, None);
     }
 
+    #[test]
+    fn proof_within_node_budget() {
+        let mut tree = make_6_node_tree();
+        let mut walker = RefWalker::new(&mut tree, PanicSource {});
+
+        let queryitems = vec![QueryItem::RangeFull(..)];
+        let (proof, ..) = walker
+            .create_proof_within_node_budget(queryitems.as_slice(), None, None, true, &mut Some(6))
+            .unwrap()
+            .expect("create_proof errored");
+        assert_eq!(
+            proof.iter().filter(|op| matches!(op, Op::Push(_))).count(),
+            6
+        );
+
+        let mut node_budget = Some(5);
+        assert!(matches!(
+            walker
+                .create_proof_within_node_budget(
+                    queryitems.as_slice(),
+                    None,
+                    None,
+                    true,
+                    &mut node_budget
+                )
+                .unwrap(),
+            Err(Error::ProofNodeLimitExceeded)
+        ));
+        assert_eq!(node_budget, Some(0));
+    }
+
     #[test]
     fn proof_with_offset() {
         let mut tree = make_6_node_tree();
@@ -3,6 +3,8 @@ use std::{
     ops::{Range, RangeFrom, RangeFull, RangeInclusive, RangeTo, RangeToInclusive},
 };
 
+use grovedb_path::key_successor;
+
 use crate::proofs::query::query_item::{
     intersect::RangeSetItem::{
         ExclusiveEnd, ExclusiveStart, Inclusive, UnboundedEnd, UnboundedStart,
@@ -417,6 +419,11 @@ impl QueryItem {
                 start: RangeSetItem::ExclusiveStart(range.start().clone()),
                 end: RangeSetItem::Inclusive(range.end().clone()),
             },
+            QueryItem::KeyPrefix(prefix) => RangeSet {
+                start: RangeSetItem::Inclusive(prefix.clone()),
+                end: key_successor(prefix)
+                    .map_or(RangeSetItem::UnboundedEnd, RangeSetItem::ExclusiveEnd),
+            },
         }
     }
 
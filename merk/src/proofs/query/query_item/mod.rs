@@ -3,6 +3,7 @@ pub mod intersect;
 mod merge;
 
 use std::{
+    borrow::Cow,
     cmp,
     cmp::Ordering,
     hash::Hash,
@@ -11,6 +12,8 @@ use std::{
 
 #[cfg(any(feature = "full", feature = "verify"))]
 use grovedb_costs::{CostContext, CostsExt, OperationCost};
+#[cfg(any(feature = "full", feature = "verify"))]
+use grovedb_path::key_successor;
 #[cfg(feature = "full")]
 use grovedb_storage::RawIterator;
 
@@ -31,6 +34,9 @@ pub enum QueryItem {
     RangeAfter(RangeFrom<Vec<u8>>),
     RangeAfterTo(Range<Vec<u8>>),
     RangeAfterToInclusive(RangeInclusive<Vec<u8>>),
+    /// All keys starting with the given bytes, which is the half-open range
+    /// from the prefix to its lexicographic successor
+    KeyPrefix(Vec<u8>),
 }
 
 #[cfg(any(feature = "full", feature = "verify"))]
@@ -67,6 +73,7 @@ impl QueryItem {
             QueryItem::RangeAfter(range) => (Some(range.start.as_ref()), true),
             QueryItem::RangeAfterTo(range) => (Some(range.start.as_ref()), true),
             QueryItem::RangeAfterToInclusive(range) => (Some(range.start().as_ref()), true),
+            QueryItem::KeyPrefix(prefix) => (Some(prefix.as_slice()), false),
         }
     }
 
@@ -83,27 +90,34 @@ impl QueryItem {
             QueryItem::RangeAfter(_) => false,
             QueryItem::RangeAfterTo(_) => false,
             QueryItem::RangeAfterToInclusive(_) => false,
+            QueryItem::KeyPrefix(_) => false,
         }
     }
 
+    /// The upper bound is owned only for `KeyPrefix`, whose end is computed
+    /// from the prefix.
     #[cfg(any(feature = "full", feature = "verify"))]
-    pub fn upper_bound(&self) -> (Option<&[u8]>, bool) {
+    pub fn upper_bound(&self) -> (Option<Cow<[u8]>>, bool) {
         match self {
-            QueryItem::Key(key) => (Some(key.as_slice()), true),
-            QueryItem::Range(range) => (Some(range.end.as_ref()), false),
-            QueryItem::RangeInclusive(range) => (Some(range.end().as_ref()), true),
+            QueryItem::Key(key) => (Some(key.as_slice().into()), true),
+            QueryItem::Range(range) => (Some(range.end.as_slice().into()), false),
+            QueryItem::RangeInclusive(range) => (Some(range.end().as_slice().into()), true),
             QueryItem::RangeFull(_) => (None, true),
             QueryItem::RangeFrom(_) => (None, true),
-            QueryItem::RangeTo(range) => (Some(range.end.as_ref()), false),
-            QueryItem::RangeToInclusive(range) => (Some(range.end.as_ref()), true),
+            QueryItem::RangeTo(range) => (Some(range.end.as_slice().into()), false),
+            QueryItem::RangeToInclusive(range) => (Some(range.end.as_slice().into()), true),
             QueryItem::RangeAfter(_) => (None, true),
-            QueryItem::RangeAfterTo(range) => (Some(range.end.as_ref()), false),
-            QueryItem::RangeAfterToInclusive(range) => (Some(range.end().as_ref()), true),
+            QueryItem::RangeAfterTo(range) => (Some(range.end.as_slice().into()), false),
+            QueryItem::RangeAfterToInclusive(range) => (Some(range.end().as_slice().into()), true),
+            QueryItem::KeyPrefix(prefix) => match key_successor(prefix) {
+                Some(end) => (Some(end.into()), false),
+                None => (None, true),
+            },
         }
     }
 
     #[cfg(any(feature = "full", feature = "verify"))]
-    pub const fn upper_unbounded(&self) -> bool {
+    pub fn upper_unbounded(&self) -> bool {
         match self {
             QueryItem::Key(_) => false,
             QueryItem::Range(_) => false,
@@ -115,6 +129,7 @@ impl QueryItem {
             QueryItem::RangeAfter(_) => true,
             QueryItem::RangeAfterTo(_) => false,
             QueryItem::RangeAfterToInclusive(_) => false,
+            QueryItem::KeyPrefix(prefix) => key_successor(prefix).is_none(),
         }
     }
 
@@ -122,6 +137,7 @@ impl QueryItem {
     pub fn contains(&self, key: &[u8]) -> bool {
         let (lower_bound, lower_bound_non_inclusive) = self.lower_bound();
         let (upper_bound, upper_bound_inclusive) = self.upper_bound();
+        let upper_bound = upper_bound.as_deref();
         (self.lower_unbounded()
             || Some(key) > lower_bound
             || (Some(key) == lower_bound && !lower_bound_non_inclusive))
@@ -143,6 +159,7 @@ impl QueryItem {
             QueryItem::RangeAfter(_) => 7,
             QueryItem::RangeAfterTo(_) => 8,
             QueryItem::RangeAfterToInclusive(_) => 9,
+            QueryItem::KeyPrefix(_) => 10,
         }
     }
 
@@ -159,6 +176,7 @@ impl QueryItem {
             QueryItem::RangeAfter(range) => range.hash(state),
             QueryItem::RangeAfterTo(range) => range.hash(state),
             QueryItem::RangeAfterToInclusive(range) => range.hash(state),
+            QueryItem::KeyPrefix(prefix) => prefix.hash(state),
         }
     }
 
@@ -375,6 +393,16 @@ impl QueryItem {
                     iter.seek_for_prev(end)
                 }
             }
+            QueryItem::KeyPrefix(prefix) => {
+                if left_to_right {
+                    iter.seek(prefix)
+                } else {
+                    match key_successor(prefix) {
+                        Some(end) => iter.seek(end).flat_map(|_| iter.prev()),
+                        None => iter.seek_to_last(),
+                    }
+                }
+            }
         }
     }
 
@@ -463,6 +491,9 @@ impl QueryItem {
                     }
                 }
             }
+            // keys starting with the prefix are contiguous, so iteration in
+            // either direction ends on the first key without it
+            QueryItem::KeyPrefix(prefix) => key.starts_with(prefix),
         };
 
         is_valid.wrap_with_cost(cost)
@@ -598,4 +629,31 @@ mod test {
         );
         assert!(QueryItem::Range(vec![20]..vec![30]) > QueryItem::Range(vec![10]..vec![20]));
     }
+
+    #[test]
+    fn key_prefix_bounds() {
+        let prefix = QueryItem::KeyPrefix(vec![1, 2]);
+        assert_eq!(prefix.lower_bound(), (Some([1, 2].as_slice()), false));
+        assert_eq!(prefix.upper_bound().0.as_deref(), Some([1, 3].as_slice()));
+        assert!(prefix.contains(&[1, 2]));
+        assert!(prefix.contains(&[1, 2, 255]));
+        assert!(!prefix.contains(&[1, 3]));
+        assert!(!prefix.contains(&[1]));
+
+        let trailing_max = QueryItem::KeyPrefix(vec![1, 255]);
+        assert_eq!(
+            trailing_max.upper_bound().0.as_deref(),
+            Some([2].as_slice())
+        );
+        assert!(trailing_max.contains(&[1, 255, 255]));
+        assert!(!trailing_max.contains(&[2]));
+
+        let unbounded = QueryItem::KeyPrefix(vec![255]);
+        assert!(unbounded.upper_unbounded());
+        assert!(unbounded.contains(&[255, 0]));
+        assert!(!unbounded.contains(&[254]));
+
+        assert!(prefix.collides_with(&QueryItem::Key(vec![1, 2, 7])));
+        assert!(!prefix.collides_with(&QueryItem::Key(vec![1, 3])));
+    }
 }
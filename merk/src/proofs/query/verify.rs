@@ -54,8 +54,37 @@ pub fn execute_proof(
     limit: Option<u16>,
     offset: Option<u16>,
     left_to_right: bool,
+) -> CostResult<(MerkHash, ProofVerificationResult), Error> {
+    execute_proof_internal(bytes, query, limit, offset, left_to_right, false)
+}
+
+#[cfg(any(feature = "full", feature = "verify"))]
+/// Verifies an encoded proof of the keys matched by the query, where matched
+/// nodes may only carry their value hash (`Node::KVDigest`) instead of their
+/// value. Values are left out of the returned result set.
+///
+/// Without values, digests of nodes skipped by an offset can't be told apart
+/// from matched ones, so there is no offset.
+pub fn execute_keys_only_proof(
+    bytes: &[u8],
+    query: &Query,
+    limit: Option<u16>,
+    left_to_right: bool,
+) -> CostResult<(MerkHash, ProofVerificationResult), Error> {
+    execute_proof_internal(bytes, query, limit, None, left_to_right, true)
+}
+
+#[cfg(any(feature = "full", feature = "verify"))]
+fn execute_proof_internal(
+    bytes: &[u8],
+    query: &Query,
+    limit: Option<u16>,
+    offset: Option<u16>,
+    left_to_right: bool,
+    keys_only: bool,
 ) -> CostResult<(MerkHash, ProofVerificationResult), Error> {
     let mut cost = OperationCost::default();
+    let no_value = Vec::new();
 
     let mut output = Vec::with_capacity(query.len());
     let mut last_push = None;
@@ -76,6 +105,7 @@ pub fn execute_proof(
                 let query_item = *item;
                 let (lower_bound, start_non_inclusive) = query_item.lower_bound();
                 let (upper_bound, end_inclusive) = query_item.upper_bound();
+                let upper_bound = upper_bound.as_deref();
 
                 // terminate if we encounter a node before the current query item.
                 // this means a node less than the current query item for left to right.
@@ -135,7 +165,7 @@ pub fn execute_proof(
                             // ignoring the case when the upper bound is unbounded
                             // as it's not possible the get an exact key match for
                             // an unbounded value
-                            _ if Some(key.as_slice()) == query_item.upper_bound().0 => {}
+                            _ if Some(key.as_slice()) == upper_bound => {}
 
                             // lower bound is proven - this is the rightmost node
                             // in the tree
@@ -160,9 +190,7 @@ pub fn execute_proof(
                 }
 
                 if left_to_right {
-                    if query_item.upper_bound().0.is_some()
-                        && Some(key.as_slice()) >= query_item.upper_bound().0
-                    {
+                    if upper_bound.is_some() && Some(key.as_slice()) >= upper_bound {
                         // at or past upper bound of range (or this was an exact
                         // match on a single-key queryitem), advance to next query
                         // item
@@ -241,7 +269,24 @@ pub fn execute_proof(
             Ok(())
         };
 
-        if let Node::KV(key, value) = node {
+        if keys_only {
+            match node {
+                Node::KV(key, value) => {
+                    execute_node(key, Some(&no_value), value_hash(value).unwrap())?
+                }
+                Node::KVValueHash(key, _, value_hash)
+                | Node::KVDigest(key, value_hash)
+                | Node::KVRefValueHash(key, _, value_hash) => {
+                    execute_node(key, Some(&no_value), *value_hash)?
+                }
+                _ if in_range => {
+                    return Err(Error::InvalidProofError(
+                        "Proof is missing data for query for range".to_string(),
+                    ));
+                }
+                _ => {}
+            }
+        } else if let Node::KV(key, value) = node {
             execute_node(key, Some(value), value_hash(value).unwrap())?;
         } else if let Node::KVValueHash(key, value, value_hash) = node {
             execute_node(key, Some(value), *value_hash)?;
@@ -177,6 +177,18 @@ impl Tree {
         }
     }
 
+    /// Returns the stored hash of the left child, if any, without walking it.
+    #[cfg(any(feature = "full", feature = "verify"))]
+    pub fn left_hash(&self) -> Option<&CryptoHash> {
+        self.left.as_ref().map(|child| &child.hash)
+    }
+
+    /// Returns the stored hash of the right child, if any, without walking it.
+    #[cfg(any(feature = "full", feature = "verify"))]
+    pub fn right_hash(&self) -> Option<&CryptoHash> {
+        self.right.as_ref().map(|child| &child.hash)
+    }
+
     /// Returns a mutable reference to the child on the given side, if any.
     #[cfg(any(feature = "full", feature = "verify"))]
     pub(crate) fn child_mut(&mut self, left: bool) -> &mut Option<Child> {
@@ -537,6 +549,29 @@ mod test {
         assert!(iter.next().is_none());
     }
 
+    #[test]
+    fn child_hash_accessors() {
+        let make_node = |i| -> ProofTree { Node::KV(vec![i], vec![]).into() };
+
+        let left = make_node(0);
+        let right = make_node(2);
+        let left_hash = left.hash().unwrap();
+        let right_hash = right.hash().unwrap();
+
+        let mut tree = make_node(1);
+        assert_eq!(tree.left_hash(), None);
+        assert_eq!(tree.right_hash(), None);
+
+        tree.attach(true, left).unwrap().unwrap();
+        assert_eq!(tree.left_hash(), Some(&left_hash));
+        assert_eq!(tree.right_hash(), None);
+
+        tree.attach(false, right).unwrap().unwrap();
+        assert_eq!(tree.left_hash(), Some(&left_hash));
+        assert_eq!(tree.right_hash(), Some(&right_hash));
+        assert_ne!(left_hash, right_hash);
+    }
+
     #[test]
     fn visit_nodes() {
         let tree = make_7_node_prooftree();
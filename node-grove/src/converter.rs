@@ -204,6 +204,9 @@ fn js_object_to_query<'a, C: Context<'a>>(
                 let to = js_object_get_vec_u8(item, "to", cx)?;
                 query.insert_range_after_to_inclusive(after..=to);
             }
+            "keyPrefix" => {
+                query.insert_key_prefix(js_object_get_vec_u8(item, "prefix", cx)?);
+            }
             _ => {
                 cx.throw_range_error("query item type is not supported")?;
             }
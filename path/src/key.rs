@@ -0,0 +1,63 @@
+// MIT LICENSE
+//
+// Copyright (c) 2021 Dash Core Group
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Helpers for working with raw storage keys.
+
+/// Smallest key greater than every key starting with `key`, used as an
+/// exclusive upper bound for range queries and prefix scans. It is obtained
+/// by incrementing the last byte which isn't `0xff` and dropping the ones
+/// after it.
+///
+/// Returns `None` if there is no such key because `key` is empty or made of
+/// `0xff` bytes only, so the bound is unlimited.
+pub fn key_successor(key: &[u8]) -> Option<Vec<u8>> {
+    let mut successor = key.to_vec();
+    while let Some(last) = successor.pop() {
+        if last != u8::MAX {
+            successor.push(last + 1);
+            return Some(successor);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_successor() {
+        assert_eq!(key_successor(&[0x01]), Some(vec![0x02]));
+        assert_eq!(key_successor(&[0x01, 0xff]), Some(vec![0x02]));
+        assert_eq!(key_successor(&[0x01, 0xfe]), Some(vec![0x01, 0xff]));
+        assert_eq!(key_successor(&[0x00, 0xff, 0xff]), Some(vec![0x01]));
+        assert_eq!(key_successor(&[0xff]), None);
+        assert_eq!(key_successor(&[0xff, 0xff]), None);
+        assert_eq!(key_successor(&[]), None);
+    }
+}
@@ -30,13 +30,17 @@
 
 #![deny(missing_docs)]
 
+mod key;
 mod subtree_path;
 mod subtree_path_builder;
+mod subtree_path_encoding;
 mod subtree_path_iter;
 mod util;
 
-pub use subtree_path::SubtreePath;
+pub use key::key_successor;
+pub use subtree_path::{PathTooDeepError, SubtreePath};
 pub use subtree_path_builder::SubtreePathBuilder;
+pub use subtree_path_encoding::{decode_subtree_path, DecodeError, OwnedSubtreePath};
 pub use subtree_path_iter::SubtreePathIter;
 
 #[cfg(test)]
@@ -62,6 +66,7 @@ mod tests {
         let subtree_path_builder = subtree_path_ref.derive_owned();
         assert_eq!(calculate_hash(&path), calculate_hash(&subtree_path_ref));
         assert_eq!(calculate_hash(&path), calculate_hash(&subtree_path_builder));
+        assert_eq!(calculate_hash(&path), calculate_hash(&reference));
     }
 
     #[test]
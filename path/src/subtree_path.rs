@@ -34,7 +34,11 @@
 //! combined with it's various `From` implementations it can cover slices, owned
 //! subtree paths and other path references if use as generic [Into].
 
-use std::hash::{Hash, Hasher};
+use std::{
+    fmt,
+    hash::{Hash, Hasher},
+    sync::Arc,
+};
 
 use crate::{
     subtree_path_builder::{SubtreePathBuilder, SubtreePathRelative},
@@ -42,6 +46,26 @@ use crate::{
     SubtreePathIter,
 };
 
+/// Error of [SubtreePath::derive_child_checked] when the derived path would
+/// be deeper than allowed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PathTooDeepError {
+    /// Maximum number of path segments that was exceeded.
+    pub max_depth: usize,
+}
+
+impl fmt::Display for PathTooDeepError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "subtree path exceeds the maximum depth of {} segments",
+            self.max_depth
+        )
+    }
+}
+
+impl std::error::Error for PathTooDeepError {}
+
 /// Path to a GroveDB's subtree with no owned data and cheap to clone.
 #[derive(Debug)]
 pub struct SubtreePath<'b, B> {
@@ -106,21 +130,36 @@ impl<'s, 'b, B> From<&'s SubtreePathBuilder<'b, B>> for SubtreePath<'s, B> {
     }
 }
 
-/// Hash order is the same as iteration order: from most deep path segment up to
-/// root.
+/// Collect path segments in forward order, as used by `PathQuery` paths. The
+/// segments are copied straight out of the path, without an intermediate owned
+/// path.
+impl<'b, B: AsRef<[u8]>> From<&SubtreePath<'b, B>> for Vec<Vec<u8>> {
+    fn from(value: &SubtreePath<'b, B>) -> Self {
+        let mut path: Vec<Vec<u8>> = value
+            .clone()
+            .into_reverse_iter()
+            .map(|segment| segment.to_vec())
+            .collect();
+        path.reverse();
+        path
+    }
+}
+
+/// Hashes exactly like the `Vec<Vec<u8>>` returned by [SubtreePath::to_vec],
+/// so a path can be hashed to look up maps keyed by owned paths and the
+/// other way around.
 impl<'b, B: AsRef<[u8]>> Hash for SubtreePath<'b, B> {
     fn hash<H: Hasher>(&self, state: &mut H) {
-        match &self.ref_variant {
-            SubtreePathInner::Slice(slice) => slice
-                .iter()
-                .map(AsRef::as_ref)
-                .rev()
-                .for_each(|s| s.hash(state)),
-            SubtreePathInner::SubtreePath(path) => path.hash(state),
-            SubtreePathInner::SubtreePathIter(path_iter) => {
-                path_iter.clone().for_each(|s| s.hash(state))
+        // `[u8]` and `Vec<u8>` hash alike, so do vectors of them
+        let segments: Vec<&[u8]> = match &self.ref_variant {
+            SubtreePathInner::Slice(slice) => slice.iter().map(AsRef::as_ref).collect(),
+            _ => {
+                let mut segments: Vec<&[u8]> = self.clone().into_reverse_iter().collect();
+                segments.reverse();
+                segments
             }
-        }
+        };
+        segments.hash(state);
     }
 }
 
@@ -165,6 +204,12 @@ impl<'b, B: AsRef<[u8]>> SubtreePath<'b, B> {
         }
     }
 
+    /// Get a derived path with a reference counted child path segment added,
+    /// so the same segment can be shared by many paths without copying it.
+    pub fn derive_child_shared(&'b self, segment: Arc<[u8]>) -> SubtreePathBuilder<'b, B> {
+        self.derive_owned_with_child(CowLike::Shared(segment))
+    }
+
     /// Get a derived subtree path for a parent with care for base path slice
     /// case. The main difference from [SubtreePath::derive_parent] is that
     /// lifetime of returned [Self] if not limited to the scope where this
@@ -172,42 +217,106 @@ impl<'b, B: AsRef<[u8]>> SubtreePath<'b, B> {
     /// without keeping previous result as it still will link to `'b`
     /// (latest [SubtreePath] or initial slice of data).
     pub fn derive_parent(&self) -> Option<(SubtreePath<'b, B>, &'b [u8])> {
-        match &self.ref_variant {
-            SubtreePathInner::Slice(path) => path
-                .split_last()
-                .map(|(tail, rest)| (SubtreePathInner::Slice(rest).into(), tail.as_ref())),
-            SubtreePathInner::SubtreePath(path) => path.derive_parent(),
-            SubtreePathInner::SubtreePathIter(iter) => {
-                let mut derived_iter = iter.clone();
-                derived_iter.next().map(|segment| {
-                    (
-                        SubtreePathInner::SubtreePathIter(derived_iter).into(),
-                        segment,
-                    )
-                })
+        let mut path = self.clone();
+        // Derivations with no segments of their own are skipped in a loop, so
+        // a long chain of them can't overflow the stack
+        loop {
+            match path.ref_variant {
+                SubtreePathInner::Slice(path) => {
+                    return path
+                        .split_last()
+                        .map(|(tail, rest)| (SubtreePathInner::Slice(rest).into(), tail.as_ref()))
+                }
+                SubtreePathInner::SubtreePath(builder) => match builder.relative {
+                    SubtreePathRelative::Empty => path = builder.base.clone(),
+                    _ => return builder.derive_parent(),
+                },
+                SubtreePathInner::SubtreePathIter(mut derived_iter) => {
+                    return derived_iter.next().map(|segment| {
+                        (
+                            SubtreePathInner::SubtreePathIter(derived_iter).into(),
+                            segment,
+                        )
+                    })
+                }
             }
         }
     }
 
+    /// Get a derived path with a child path segment added, failing if this
+    /// path already has `max_depth` segments. Meant for paths built from
+    /// untrusted input, which could otherwise be made arbitrarily deep.
+    pub fn derive_child_checked<'s, S>(
+        &'b self,
+        segment: S,
+        max_depth: usize,
+    ) -> Result<SubtreePathBuilder<'b, B>, PathTooDeepError>
+    where
+        S: Into<CowLike<'s>>,
+        's: 'b,
+    {
+        if self.clone().into_reverse_iter().take(max_depth).count() == max_depth {
+            return Err(PathTooDeepError { max_depth });
+        }
+        Ok(self.derive_owned_with_child(segment))
+    }
+
     /// Get a reverse path segments iterator.
     pub fn into_reverse_iter(self) -> SubtreePathIter<'b, B> {
-        match self.ref_variant {
-            SubtreePathInner::Slice(slice) => SubtreePathIter::new(slice.iter()),
-            SubtreePathInner::SubtreePath(path) => path.reverse_iter(),
-            SubtreePathInner::SubtreePathIter(iter) => iter,
+        let mut path = self;
+        // Same as for `derive_parent`, derivations with no segments of their
+        // own are skipped without recursion
+        loop {
+            match path.ref_variant {
+                SubtreePathInner::Slice(slice) => return SubtreePathIter::new(slice.iter()),
+                SubtreePathInner::SubtreePath(builder) => match &builder.relative {
+                    SubtreePathRelative::Empty => path = builder.base.clone(),
+                    SubtreePathRelative::Single(item) => {
+                        return SubtreePathIter::new_with_next(item.as_ref(), &builder.base)
+                    }
+                    SubtreePathRelative::Multi(bytes) => {
+                        return SubtreePathIter::new_with_next(bytes.reverse_iter(), &builder.base)
+                    }
+                },
+                SubtreePathInner::SubtreePathIter(iter) => return iter,
+            }
         }
     }
 
     /// Retuns `true` if the subtree path is empty, so it points to the root
     /// tree.
     pub fn is_root(&self) -> bool {
-        match &self.ref_variant {
-            SubtreePathInner::Slice(s) => s.is_empty(),
-            SubtreePathInner::SubtreePath(path) => path.is_root(),
-            SubtreePathInner::SubtreePathIter(iter) => iter.is_empty(),
+        let mut path = self.clone();
+        loop {
+            match path.ref_variant {
+                SubtreePathInner::Slice(s) => return s.is_empty(),
+                SubtreePathInner::SubtreePath(builder) => match builder.relative {
+                    SubtreePathRelative::Empty => path = builder.base.clone(),
+                    _ => return false,
+                },
+                SubtreePathInner::SubtreePathIter(iter) => return iter.is_empty(),
+            }
         }
     }
 
+    /// Returns `true` if this path is a proper ancestor of `other`, so
+    /// `other` is deeper and starts with all the segments of this path.
+    pub fn is_ancestor_of<BO: AsRef<[u8]>>(&self, other: &SubtreePath<BO>) -> bool {
+        other
+            .strip_prefix(self)
+            .is_some_and(|relative| !relative.is_empty())
+    }
+
+    /// Returns the segments remaining once `prefix` is removed from the start
+    /// of this path, `None` if `prefix` is neither this path nor one of its
+    /// ancestors. An equal path leaves no segments.
+    pub fn strip_prefix<BP: AsRef<[u8]>>(&self, prefix: &SubtreePath<BP>) -> Option<Vec<Vec<u8>>> {
+        let path = self.to_vec();
+        let prefix = prefix.to_vec();
+        path.strip_prefix(prefix.as_slice())
+            .map(|relative| relative.to_vec())
+    }
+
     /// Collect path as a vector of vectors, but this actually negates all the
     /// benefits of this library.
     pub fn to_vec(&self) -> Vec<Vec<u8>> {
@@ -229,6 +338,47 @@ impl<'b, B: AsRef<[u8]>> SubtreePath<'b, B> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::util::calculate_hash;
+
+    #[test]
+    fn hash_matches_owned_path() {
+        let base: SubtreePath<_> = (&[b"one" as &[u8], b"two"]).into();
+        assert_eq!(calculate_hash(&base), calculate_hash(&base.to_vec()));
+
+        let mut builder = base.derive_owned_with_child(b"three");
+        builder.push_segment(b"four");
+        assert_eq!(calculate_hash(&builder), calculate_hash(&builder.to_vec()));
+
+        let parent = builder.derive_parent().unwrap().0;
+        assert_eq!(calculate_hash(&parent), calculate_hash(&parent.to_vec()));
+
+        // Segments boundaries are part of the hash
+        let merged: SubtreePath<_> = (&[b"onetwo" as &[u8]]).into();
+        assert_ne!(calculate_hash(&base), calculate_hash(&merged));
+
+        assert_eq!(
+            calculate_hash(&SubtreePath::empty()),
+            calculate_hash(&Vec::<Vec<u8>>::new())
+        );
+    }
+
+    #[test]
+    fn shared_segment_hashes_like_owned_and_borrowed() {
+        let base: SubtreePath<_> = (&[b"one" as &[u8], b"two"]).into();
+        let namespace: Arc<[u8]> = Arc::from(b"three".as_ref());
+
+        let shared = base.derive_child_shared(namespace.clone());
+        let other_shared = base.derive_child_shared(namespace);
+        let owned = base.derive_owned_with_child(b"three".to_vec());
+        let borrowed = base.derive_owned_with_child(b"three");
+
+        assert_eq!(calculate_hash(&shared), calculate_hash(&owned));
+        assert_eq!(calculate_hash(&shared), calculate_hash(&borrowed));
+        assert_eq!(calculate_hash(&shared), calculate_hash(&other_shared));
+        assert_eq!(shared, owned);
+        assert_eq!(shared.to_vec(), borrowed.to_vec());
+        assert!(shared.reverse_iter().eq(borrowed.reverse_iter()));
+    }
 
     #[test]
     fn to_vec() {
@@ -254,4 +404,113 @@ mod tests {
             ],
         );
     }
+
+    #[test]
+    fn into_forward_ordered_segments() {
+        let base: SubtreePath<_> = (&[b"one" as &[u8], b"two"]).into();
+        let mut builder = base.derive_owned_with_child(b"three");
+        builder.push_segment(b"four");
+        let path: SubtreePath<_> = (&builder).into();
+
+        let as_vec: Vec<Vec<u8>> = (&path).into();
+        assert_eq!(
+            as_vec,
+            vec![
+                b"one".to_vec(),
+                b"two".to_vec(),
+                b"three".to_vec(),
+                b"four".to_vec(),
+            ],
+        );
+        assert_eq!(as_vec, path.to_vec());
+    }
+
+    #[test]
+    fn long_derivation_chain() {
+        const DEPTH: u32 = 10_000;
+
+        // Every derivation borrows the previous one, leaking them is the
+        // simplest way to build a chain this long
+        let mut builder: &'static SubtreePathBuilder<'static, [u8; 0]> =
+            Box::leak(Box::new(SubtreePathBuilder::new()));
+        for i in 0..DEPTH {
+            let child = Box::leak(Box::new(
+                builder.derive_owned_with_child(i.to_be_bytes().to_vec()),
+            ));
+            // Derivations with no segments of their own used to be followed
+            // recursively
+            builder = Box::leak(Box::new(SubtreePath::from(&*child).derive_owned()));
+        }
+        let path = SubtreePath::from(builder);
+
+        let as_vec = path.to_vec();
+        assert_eq!(as_vec.len(), DEPTH as usize);
+        assert_eq!(as_vec[0], 0u32.to_be_bytes());
+        assert_eq!(as_vec[DEPTH as usize - 1], (DEPTH - 1).to_be_bytes());
+        assert_eq!(path.clone().into_reverse_iter().count(), DEPTH as usize);
+        assert_eq!(calculate_hash(&path), calculate_hash(&as_vec));
+        assert!(!path.is_root());
+
+        let (parent, segment) = path.derive_parent().unwrap();
+        assert_eq!(segment, (DEPTH - 1).to_be_bytes());
+        assert_eq!(parent.to_vec(), as_vec[..DEPTH as usize - 1]);
+    }
+
+    #[test]
+    fn derive_child_checked() {
+        let base: SubtreePath<_> = (&[b"one" as &[u8], b"two"]).into();
+
+        let child = base
+            .derive_child_checked(b"three", 3)
+            .expect("path is within the limit");
+        assert_eq!(
+            child.derive_child_checked(b"four", 3),
+            Err(PathTooDeepError { max_depth: 3 })
+        );
+        assert!(child.derive_child_checked(b"four", 4).is_ok());
+        assert_eq!(
+            base.derive_child_checked(b"three", 2),
+            Err(PathTooDeepError { max_depth: 2 })
+        );
+        assert_eq!(
+            SubtreePath::empty().derive_child_checked(b"one", 0),
+            Err(PathTooDeepError { max_depth: 0 })
+        );
+    }
+
+    #[test]
+    fn strip_prefix() {
+        let base: SubtreePath<_> = (&[b"one" as &[u8], b"two"]).into();
+        let mut builder = base.derive_owned_with_child(b"three");
+        builder.push_segment(b"four");
+        let path: SubtreePath<_> = (&builder).into();
+
+        assert_eq!(
+            path.strip_prefix(&base),
+            Some(vec![b"three".to_vec(), b"four".to_vec()])
+        );
+        assert!(base.is_ancestor_of(&path));
+
+        let other: SubtreePath<_> = (&[b"one" as &[u8], b"three"]).into();
+        assert_eq!(path.strip_prefix(&other), None);
+        assert!(!other.is_ancestor_of(&path));
+        assert_eq!(base.strip_prefix(&path), None);
+        assert!(!path.is_ancestor_of(&base));
+
+        let same_segments = [
+            b"one".to_vec(),
+            b"two".to_vec(),
+            b"three".to_vec(),
+            b"four".to_vec(),
+        ];
+        let same: SubtreePath<_> = (&same_segments).into();
+        assert_eq!(path.strip_prefix(&same), Some(Vec::new()));
+        assert!(!same.is_ancestor_of(&path));
+
+        assert_eq!(
+            path.strip_prefix(&SubtreePath::empty()),
+            Some(path.to_vec())
+        );
+        assert!(SubtreePath::empty().is_ancestor_of(&path));
+    }
 }
@@ -32,7 +32,7 @@
 use std::hash::{Hash, Hasher};
 
 use crate::{
-    subtree_path::SubtreePathInner,
+    subtree_path::{PathTooDeepError, SubtreePathInner},
     util::{CompactBytes, CowLike},
     SubtreePath, SubtreePathIter,
 };
@@ -48,10 +48,11 @@ pub struct SubtreePathBuilder<'b, B> {
 
 /// Hash order is the same as iteration order: from most deep path segment up to
 /// root.
+/// Hashes like the [SubtreePath] referring to it and so like its `to_vec`
+/// result.
 impl<'b, B: AsRef<[u8]>> Hash for SubtreePathBuilder<'b, B> {
     fn hash<H: Hasher>(&self, state: &mut H) {
-        self.relative.hash(state);
-        self.base.hash(state);
+        SubtreePath::from(self).hash(state);
     }
 }
 
@@ -107,18 +108,6 @@ pub(crate) enum SubtreePathRelative<'r> {
     Multi(CompactBytes),
 }
 
-impl Hash for SubtreePathRelative<'_> {
-    fn hash<H: Hasher>(&self, state: &mut H) {
-        match self {
-            SubtreePathRelative::Empty => {}
-            SubtreePathRelative::Single(segment) => segment.hash(state),
-            SubtreePathRelative::Multi(bytes) => {
-                bytes.reverse_iter().for_each(|segment| segment.hash(state))
-            }
-        }
-    }
-}
-
 impl SubtreePathBuilder<'static, [u8; 0]> {
     /// Creates empty subtree path
     pub fn new() -> Self {
@@ -195,17 +184,26 @@ impl<'b, B: AsRef<[u8]>> SubtreePathBuilder<'b, B> {
         }
     }
 
+    /// Get a derived path with a child path segment added, failing if this
+    /// path already has `max_depth` segments.
+    pub fn derive_child_checked<'s, S>(
+        &'b self,
+        segment: S,
+        max_depth: usize,
+    ) -> Result<SubtreePathBuilder<'b, B>, PathTooDeepError>
+    where
+        S: Into<CowLike<'s>>,
+        's: 'b,
+    {
+        if self.reverse_iter().take(max_depth).count() == max_depth {
+            return Err(PathTooDeepError { max_depth });
+        }
+        Ok(self.derive_owned_with_child(segment))
+    }
+
     /// Returns an iterator for the subtree path by path segments.
     pub fn reverse_iter(&'b self) -> SubtreePathIter<'b, B> {
-        match &self.relative {
-            SubtreePathRelative::Empty => self.base.clone().into_reverse_iter(),
-            SubtreePathRelative::Single(item) => {
-                SubtreePathIter::new_with_next(item.as_ref(), &self.base)
-            }
-            SubtreePathRelative::Multi(bytes) => {
-                SubtreePathIter::new_with_next(bytes.reverse_iter(), &self.base)
-            }
-        }
+        SubtreePath::from(self).into_reverse_iter()
     }
 
     /// Collect path as a vector of vectors, but this actually negates all the
@@ -0,0 +1,214 @@
+// MIT LICENSE
+//
+// Copyright (c) 2023 Dash Core Group
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Compact binary representation of subtree paths.
+//!
+//! The encoding starts with a varint number of path segments followed by each
+//! segment, from the root down, as a varint length and the segment bytes.
+//! Because it depends only on the segments, logically equal paths produce the
+//! same bytes no matter how they were built.
+
+use std::fmt;
+
+use crate::{SubtreePath, SubtreePathBuilder};
+
+/// Subtree path that owns all of its segments, as returned by
+/// [decode_subtree_path].
+pub type OwnedSubtreePath = SubtreePathBuilder<'static, [u8; 0]>;
+
+/// Error returned when bytes are not a valid subtree path encoding.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodeError {
+    /// Input ended before the whole path was read.
+    UnexpectedEnd,
+    /// Varint doesn't fit into `u64` or is not terminated.
+    InvalidVarint,
+    /// Input has bytes left after the last path segment.
+    TrailingBytes(usize),
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::UnexpectedEnd => write!(f, "unexpected end of subtree path encoding"),
+            DecodeError::InvalidVarint => write!(f, "invalid varint in subtree path encoding"),
+            DecodeError::TrailingBytes(n) => {
+                write!(f, "{n} trailing bytes after subtree path encoding")
+            }
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+impl<'b, B: AsRef<[u8]>> SubtreePath<'b, B> {
+    /// Encode the path into a compact length-prefixed byte representation
+    /// that can be read back with [decode_subtree_path].
+    pub fn encode(&self) -> Vec<u8> {
+        let mut segments: Vec<&[u8]> = self.clone().into_reverse_iter().collect();
+        segments.reverse();
+
+        let mut result =
+            Vec::with_capacity(segments.iter().map(|s| s.len() + 1).sum::<usize>() + 1);
+        encode_varint(segments.len() as u64, &mut result);
+        for segment in segments {
+            encode_varint(segment.len() as u64, &mut result);
+            result.extend_from_slice(segment);
+        }
+        result
+    }
+}
+
+/// Decode a subtree path previously encoded with [SubtreePath::encode].
+pub fn decode_subtree_path(bytes: &[u8]) -> Result<OwnedSubtreePath, DecodeError> {
+    let mut rest = bytes;
+    let mut path = SubtreePathBuilder::new();
+
+    let segments_count = decode_varint(&mut rest)?;
+    for _ in 0..segments_count {
+        let len = decode_varint(&mut rest)?;
+        let len = usize::try_from(len).map_err(|_| DecodeError::UnexpectedEnd)?;
+        if rest.len() < len {
+            return Err(DecodeError::UnexpectedEnd);
+        }
+        let (segment, tail) = rest.split_at(len);
+        path.push_segment(segment);
+        rest = tail;
+    }
+
+    if !rest.is_empty() {
+        return Err(DecodeError::TrailingBytes(rest.len()));
+    }
+
+    Ok(path)
+}
+
+fn encode_varint(mut value: u64, out: &mut Vec<u8>) {
+    while value >= 0x80 {
+        out.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn decode_varint(bytes: &mut &[u8]) -> Result<u64, DecodeError> {
+    let mut value = 0u64;
+    for (i, byte) in bytes.iter().enumerate() {
+        let shift = 7 * i as u32;
+        if shift >= 64 || (shift == 63 && byte & 0x7f > 1) {
+            return Err(DecodeError::InvalidVarint);
+        }
+        value |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            *bytes = &bytes[i + 1..];
+            return Ok(value);
+        }
+    }
+    Err(DecodeError::UnexpectedEnd)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_path_round_trip() {
+        let path = SubtreePath::empty();
+        let encoded = path.encode();
+        assert_eq!(encoded, vec![0]);
+
+        let decoded = decode_subtree_path(&encoded).expect("valid encoding");
+        assert!(decoded.is_root());
+        assert_eq!(decoded, path);
+    }
+
+    #[test]
+    fn single_segment_round_trip() {
+        let path = SubtreePath::from([b"one"].as_ref());
+        let encoded = path.encode();
+        assert_eq!(encoded, vec![1, 3, b'o', b'n', b'e']);
+
+        let decoded = decode_subtree_path(&encoded).expect("valid encoding");
+        assert_eq!(decoded.to_vec(), vec![b"one".to_vec()]);
+    }
+
+    #[test]
+    fn multi_segment_round_trip() {
+        let long_segment = vec![7u8; 300];
+        let path_vec = vec![
+            b"one".to_vec(),
+            Vec::new(),
+            long_segment.clone(),
+            b"four".to_vec(),
+        ];
+        let path = SubtreePath::from(path_vec.as_slice());
+        let encoded = path.encode();
+
+        let decoded = decode_subtree_path(&encoded).expect("valid encoding");
+        assert_eq!(decoded.to_vec(), path_vec);
+        assert_eq!(SubtreePath::from(&decoded).encode(), encoded);
+    }
+
+    #[test]
+    fn equal_paths_encode_identically() {
+        let slice_path = SubtreePath::from([b"one" as &[u8], b"two", b"three"].as_ref());
+
+        let base = SubtreePath::from([b"one"].as_ref());
+        let derived = base.derive_owned_with_child(b"two");
+        let mut derived_further = derived.derive_owned();
+        derived_further.push_segment(b"three");
+
+        assert_eq!(
+            slice_path.encode(),
+            SubtreePath::from(&derived_further).encode()
+        );
+    }
+
+    #[test]
+    fn truncated_input_fails() {
+        let path = SubtreePath::from([b"one", b"two"].as_ref());
+        let encoded = path.encode();
+
+        assert_eq!(
+            decode_subtree_path(&encoded[..encoded.len() - 1]),
+            Err(DecodeError::UnexpectedEnd)
+        );
+        assert_eq!(decode_subtree_path(&[]), Err(DecodeError::UnexpectedEnd));
+        assert_eq!(
+            decode_subtree_path(&[0x80]),
+            Err(DecodeError::UnexpectedEnd)
+        );
+
+        let mut with_trailing = encoded;
+        with_trailing.push(0);
+        assert_eq!(
+            decode_subtree_path(&with_trailing),
+            Err(DecodeError::TrailingBytes(1))
+        );
+    }
+}
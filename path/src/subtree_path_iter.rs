@@ -86,33 +86,26 @@ impl<'b, B: AsRef<[u8]>> Iterator for SubtreePathIter<'b, B> {
     type Item = &'b [u8];
 
     fn next(&mut self) -> Option<Self::Item> {
-        match &mut self.current_iter {
-            CurrentSubtreePathIter::Single(item) => {
-                let path_segment = *item;
-                if let Some(next_path) = self.next_subtree_path {
-                    *self = next_path.clone().into_reverse_iter();
+        // Exhausted parts of the path are left in a loop rather than by
+        // recursion, to iterate long paths in constant stack space
+        loop {
+            let item = match &mut self.current_iter {
+                CurrentSubtreePathIter::Single(item) => {
+                    let path_segment = *item;
+                    if let Some(next_path) = self.next_subtree_path {
+                        *self = next_path.clone().into_reverse_iter();
+                    }
+                    return Some(path_segment);
                 }
-                Some(path_segment)
-            }
-            CurrentSubtreePathIter::Slice(slice_iter) => {
-                if let Some(item) = slice_iter.next_back() {
-                    Some(item.as_ref())
-                } else if let Some(next_path) = self.next_subtree_path {
-                    *self = next_path.clone().into_reverse_iter();
-                    self.next()
-                } else {
-                    None
-                }
-            }
-            CurrentSubtreePathIter::OwnedBytes(bytes_iter) => {
-                if let Some(item) = bytes_iter.next() {
-                    Some(item)
-                } else if let Some(next_path) = self.next_subtree_path {
-                    *self = next_path.clone().into_reverse_iter();
-                    self.next()
-                } else {
-                    None
+                CurrentSubtreePathIter::Slice(slice_iter) => {
+                    slice_iter.next_back().map(|item| item.as_ref())
                 }
+                CurrentSubtreePathIter::OwnedBytes(bytes_iter) => bytes_iter.next(),
+            };
+            match (item, self.next_subtree_path) {
+                (Some(item), _) => return Some(item),
+                (None, Some(next_path)) => *self = next_path.clone().into_reverse_iter(),
+                (None, None) => return None,
             }
         }
     }
@@ -26,11 +26,13 @@
 // IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
 // DEALINGS IN THE SOFTWARE.
 
-//! Module for [CowLike]: simple abstraction over owned and borrowed bytes.
+//! Module for [CowLike]: simple abstraction over owned, shared and borrowed
+//! bytes.
 
 use std::{
     hash::{Hash, Hasher},
     ops::Deref,
+    sync::Arc,
 };
 
 /// A smart pointer that follows the semantics of [Cow](std::borrow::Cow) except
@@ -38,6 +40,8 @@ use std::{
 #[derive(Debug)]
 pub enum CowLike<'b> {
     Owned(Vec<u8>),
+    /// Reference counted bytes, cloning them doesn't copy the data.
+    Shared(Arc<[u8]>),
     Borrowed(&'b [u8]),
 }
 
@@ -47,6 +51,7 @@ impl Deref for CowLike<'_> {
     fn deref(&self) -> &Self::Target {
         match self {
             Self::Owned(v) => v.as_slice(),
+            Self::Shared(s) => s,
             Self::Borrowed(s) => s,
         }
     }
@@ -70,6 +75,12 @@ impl From<Vec<u8>> for CowLike<'static> {
     }
 }
 
+impl From<Arc<[u8]>> for CowLike<'static> {
+    fn from(value: Arc<[u8]>) -> Self {
+        Self::Shared(value)
+    }
+}
+
 impl<'b> From<&'b [u8]> for CowLike<'b> {
     fn from(value: &'b [u8]) -> Self {
         Self::Borrowed(value)
@@ -91,7 +102,9 @@ mod tests {
     fn test_cowlike_hashes() {
         let owned = CowLike::Owned(vec![1u8, 3, 3, 7]);
         let borrowed = CowLike::Borrowed(&[1u8, 3, 3, 7]);
+        let shared = CowLike::Shared(Arc::from([1u8, 3, 3, 7].as_ref()));
 
         assert_eq!(calculate_hash(&owned), calculate_hash(&borrowed));
+        assert_eq!(calculate_hash(&owned), calculate_hash(&shared));
     }
 }
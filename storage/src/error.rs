@@ -34,6 +34,23 @@ pub enum Error {
     /// Storage Error
     #[error("storage error: {0}")]
     StorageError(String),
+    /// Operation is not supported by the storage backend
+    #[error("not supported: {0}")]
+    NotSupported(&'static str),
+    /// Write attempted on a storage opened read only
+    #[error("storage is read only")]
+    ReadOnly,
+    /// Savepoint was already rolled back to or belongs to another transaction
+    #[error("invalid savepoint")]
+    InvalidSavepoint,
+    /// Subtree prefix has an unexpected length
+    #[error("invalid prefix: expected {expected} bytes, got {actual}")]
+    InvalidPrefix {
+        /// Length of a subtree prefix
+        expected: usize,
+        /// Length of the given prefix
+        actual: usize,
+    },
     /// Cost Error
     #[error("cost error: {0}")]
     CostError(grovedb_costs::error::Error),
@@ -36,7 +36,12 @@ pub mod rocksdb_storage;
 mod storage;
 pub mod worst_case_costs;
 
+pub use grovedb_path::key_successor;
+
 pub use crate::{
     error::Error,
-    storage::{Batch, ChildrenSizes, RawIterator, Storage, StorageBatch, StorageContext},
+    storage::{
+        Batch, ChildrenSizes, RawIterator, SavepointId, Storage, StorageBatch, StorageColumn,
+        StorageContext,
+    },
 };
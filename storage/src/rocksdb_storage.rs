@@ -27,16 +27,18 @@
 // DEALINGS IN THE SOFTWARE.
 
 //! GroveDB storage layer implemented over RocksDB backend.
+mod changeset;
 mod storage;
 mod storage_context;
 pub mod test_utils;
 #[cfg(test)]
 mod tests;
 
+pub use changeset::{ChangesetColumnFamily, ChangesetEntry};
 pub use rocksdb::{Error, WriteBatchWithTransaction};
 pub use storage_context::{
-    PrefixedRocksDbBatch, PrefixedRocksDbImmediateStorageContext, PrefixedRocksDbRawIterator,
-    PrefixedRocksDbStorageContext, PrefixedRocksDbTransactionContext,
+    make_prefixed_key_checked, PrefixedRocksDbBatch, PrefixedRocksDbImmediateStorageContext,
+    PrefixedRocksDbRawIterator, PrefixedRocksDbStorageContext, PrefixedRocksDbTransactionContext,
 };
 
-pub use self::storage::RocksDbStorage;
+pub use self::storage::{DbRawIterator, RocksDbStorage, RocksDbTransaction, WriteOptions};
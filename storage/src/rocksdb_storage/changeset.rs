@@ -0,0 +1,151 @@
+// MIT LICENSE
+//
+// Copyright (c) 2021 Dash Core Group
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Changesets extracted from pending RocksDB write batches.
+
+use std::mem;
+
+use integer_encoding::VarInt;
+
+use super::storage::SubtreePrefix;
+use crate::error::Error;
+
+/// Size of the write batch header: sequence number and records count
+const WRITE_BATCH_HEADER_LEN: usize = 12;
+
+// Record tags, as defined by RocksDB's `ValueType`
+const TAG_DELETION: u8 = 0x0;
+const TAG_VALUE: u8 = 0x1;
+const TAG_LOG_DATA: u8 = 0x3;
+const TAG_COLUMN_FAMILY_DELETION: u8 = 0x4;
+const TAG_COLUMN_FAMILY_VALUE: u8 = 0x5;
+const TAG_SINGLE_DELETION: u8 = 0x7;
+const TAG_COLUMN_FAMILY_SINGLE_DELETION: u8 = 0x8;
+const TAG_NOOP: u8 = 0xD;
+
+/// Column family a changeset entry applies to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ChangesetColumnFamily {
+    /// Subtrees data
+    Default,
+    /// Auxiliary data
+    Aux,
+    /// Subtrees roots
+    Roots,
+    /// Metadata
+    Meta,
+}
+
+/// Single write operation pending in a transaction
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChangesetEntry {
+    /// Column family the operation writes to
+    pub column_family: ChangesetColumnFamily,
+    /// Key including its subtree prefix
+    pub key: Vec<u8>,
+    /// Value for a put, `None` for a delete
+    pub value: Option<Vec<u8>>,
+}
+
+impl ChangesetEntry {
+    /// Prefix of the subtree the entry belongs to, `None` for metadata which
+    /// isn't kept per subtree
+    pub fn subtree_prefix(&self) -> Option<&[u8]> {
+        match self.column_family {
+            ChangesetColumnFamily::Meta => None,
+            _ => self.key.get(..mem::size_of::<SubtreePrefix>()),
+        }
+    }
+}
+
+/// Raw write batch record: column family id, key and value for puts
+pub(crate) type WriteBatchRecord = (u32, Vec<u8>, Option<Vec<u8>>);
+
+/// Decodes put and delete records of a serialized RocksDB write batch in the
+/// order they were written.
+pub(crate) fn decode_write_batch(data: &[u8]) -> Result<Vec<WriteBatchRecord>, Error> {
+    let mut records = Vec::new();
+    let mut rest = data
+        .get(WRITE_BATCH_HEADER_LEN..)
+        .ok_or_else(|| malformed("header is truncated"))?;
+
+    while let Some((&tag, tail)) = rest.split_first() {
+        rest = tail;
+        let column_family_id = match tag {
+            TAG_COLUMN_FAMILY_VALUE
+            | TAG_COLUMN_FAMILY_DELETION
+            | TAG_COLUMN_FAMILY_SINGLE_DELETION => read_varint(&mut rest)?,
+            _ => 0,
+        };
+        match tag {
+            TAG_VALUE | TAG_COLUMN_FAMILY_VALUE => {
+                let key = read_slice(&mut rest)?.to_vec();
+                let value = read_slice(&mut rest)?.to_vec();
+                records.push((column_family_id, key, Some(value)));
+            }
+            TAG_DELETION
+            | TAG_COLUMN_FAMILY_DELETION
+            | TAG_SINGLE_DELETION
+            | TAG_COLUMN_FAMILY_SINGLE_DELETION => {
+                let key = read_slice(&mut rest)?.to_vec();
+                records.push((column_family_id, key, None));
+            }
+            TAG_LOG_DATA => {
+                read_slice(&mut rest)?;
+            }
+            TAG_NOOP => {}
+            _ => {
+                return Err(malformed(&format!("unsupported record tag {:#x}", tag)));
+            }
+        }
+    }
+
+    Ok(records)
+}
+
+fn read_varint(data: &mut &[u8]) -> Result<u32, Error> {
+    let bytes = *data;
+    let (value, read) = u32::decode_var(bytes).ok_or_else(|| malformed("invalid varint"))?;
+    *data = &bytes[read..];
+    Ok(value)
+}
+
+fn read_slice<'a>(data: &mut &'a [u8]) -> Result<&'a [u8], Error> {
+    let len = read_varint(data)? as usize;
+    let bytes: &'a [u8] = *data;
+    if bytes.len() < len {
+        return Err(malformed("record is truncated"));
+    }
+    let (slice, rest) = bytes.split_at(len);
+    *data = rest;
+    Ok(slice)
+}
+
+fn malformed(reason: &str) -> Error {
+    Error::StorageError(format!("malformed write batch: {}", reason))
+}
@@ -28,7 +28,14 @@
 
 //! Implementation for a storage abstraction over RocksDB.
 
-use std::path::Path;
+use std::{
+    ops::Deref,
+    path::Path,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex, MutexGuard,
+    },
+};
 
 use error::Error;
 use grovedb_costs::{
@@ -40,11 +47,14 @@ use grovedb_path::SubtreePath;
 use integer_encoding::VarInt;
 use lazy_static::lazy_static;
 use rocksdb::{
-    checkpoint::Checkpoint, ColumnFamily, ColumnFamilyDescriptor, OptimisticTransactionDB,
-    Transaction, WriteBatchWithTransaction,
+    checkpoint::Checkpoint, properties::ESTIMATE_LIVE_DATA_SIZE, CStrLike, ColumnFamily,
+    ColumnFamilyDescriptor, DBRawIteratorWithThreadMode, OptimisticTransactionDB,
+    OptimisticTransactionOptions, Transaction, WriteBatchWithTransaction, DB,
+    DEFAULT_COLUMN_FAMILY_NAME,
 };
 
 use super::{
+    changeset::{decode_write_batch, ChangesetColumnFamily, ChangesetEntry},
     PrefixedRocksDbImmediateStorageContext, PrefixedRocksDbStorageContext,
     PrefixedRocksDbTransactionContext,
 };
@@ -53,7 +63,7 @@ use crate::{
     error::Error::{CostError, RocksDBError},
     storage::AbstractBatchOperation,
     worst_case_costs::WorstKeyLength,
-    Storage, StorageBatch,
+    SavepointId, Storage, StorageBatch, StorageColumn,
 };
 
 const BLAKE_BLOCK_LEN: usize = 64;
@@ -75,6 +85,12 @@ pub(crate) const ROOTS_CF_NAME: &str = "roots";
 /// Name of column family used to store metadata
 pub(crate) const META_CF_NAME: &str = "meta";
 
+/// Prefix of metadata keys set before a batch is written and deleted by the
+/// batch itself. Each commit appends its own number, so concurrent commits
+/// don't clear each other's marker. Markers are shorter than subtree
+/// prefixes, so they can't clash with metadata of storage contexts.
+const COMMIT_IN_PROGRESS_KEY: &[u8] = b"commit_in_progress";
+
 lazy_static! {
     static ref DEFAULT_OPTS: rocksdb::Options = {
         let mut opts = rocksdb::Options::default();
@@ -88,32 +104,264 @@ lazy_static! {
     };
 }
 
-/// Type alias for a database
-pub(crate) type Db = OptimisticTransactionDB;
+/// RocksDB database of a storage
+pub(crate) enum Db {
+    /// Database with optimistic transactions support
+    Transactional(OptimisticTransactionDB),
+    /// Database opened in RocksDB read only mode, which doesn't take the
+    /// database lock, so any number of such handles can be opened at once
+    ReadOnly(DB),
+}
 
 /// Type alias for a transaction
-pub(crate) type Tx<'db> = Transaction<'db, Db>;
+pub(crate) type Tx<'db> = Transaction<'db, OptimisticTransactionDB>;
+
+/// RocksDB transaction along with the stack of its savepoints, so savepoints
+/// which were already rolled back or belong to another transaction are
+/// rejected instead of undoing unrelated operations
+pub struct RocksDbTransaction<'db> {
+    inner: Tx<'db>,
+    savepoints: Mutex<Vec<u64>>,
+}
+
+impl<'db> Deref for RocksDbTransaction<'db> {
+    type Target = Tx<'db>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+/// Locks savepoints stack of a transaction. It's only modified along with the
+/// RocksDB one, so it's still consistent if the lock was poisoned.
+fn savepoints_stack<'a>(transaction: &'a RocksDbTransaction<'_>) -> MutexGuard<'a, Vec<u64>> {
+    transaction
+        .savepoints
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Applies `$body` to the RocksDB handle of either kind of [Db]
+macro_rules! with_db {
+    ($db:expr, $inner:ident => $body:expr) => {
+        match $db {
+            Db::Transactional($inner) => $body,
+            Db::ReadOnly($inner) => $body,
+        }
+    };
+}
+
+impl Db {
+    /// Database accepting writes, read only ones are rejected with
+    /// [`Error::ReadOnly`]
+    fn transactional(&self) -> Result<&OptimisticTransactionDB, Error> {
+        match self {
+            Db::Transactional(db) => Ok(db),
+            Db::ReadOnly(_) => Err(Error::ReadOnly),
+        }
+    }
+
+    pub(crate) fn cf_handle(&self, name: &str) -> Option<&ColumnFamily> {
+        with_db!(self, db => db.cf_handle(name))
+    }
+
+    pub(crate) fn get<K: AsRef<[u8]>>(&self, key: K) -> Result<Option<Vec<u8>>, rocksdb::Error> {
+        with_db!(self, db => db.get(key))
+    }
+
+    pub(crate) fn get_cf<K: AsRef<[u8]>>(
+        &self,
+        column_family: &ColumnFamily,
+        key: K,
+    ) -> Result<Option<Vec<u8>>, rocksdb::Error> {
+        with_db!(self, db => db.get_cf(column_family, key))
+    }
+
+    pub(crate) fn raw_iterator(&self) -> DbRawIterator<'_> {
+        match self {
+            Db::Transactional(db) => DbRawIterator::Transactional(db.raw_iterator()),
+            Db::ReadOnly(db) => DbRawIterator::ReadOnly(db.raw_iterator()),
+        }
+    }
+
+    pub(crate) fn raw_iterator_cf(&self, column_family: &ColumnFamily) -> DbRawIterator<'_> {
+        match self {
+            Db::Transactional(db) => {
+                DbRawIterator::Transactional(db.raw_iterator_cf(column_family))
+            }
+            Db::ReadOnly(db) => DbRawIterator::ReadOnly(db.raw_iterator_cf(column_family)),
+        }
+    }
+
+    pub(crate) fn property_int_value(
+        &self,
+        name: impl CStrLike,
+    ) -> Result<Option<u64>, rocksdb::Error> {
+        with_db!(self, db => db.property_int_value(name))
+    }
+
+    pub(crate) fn property_int_value_cf(
+        &self,
+        column_family: &ColumnFamily,
+        name: impl CStrLike,
+    ) -> Result<Option<u64>, rocksdb::Error> {
+        with_db!(self, db => db.property_int_value_cf(column_family, name))
+    }
+}
+
+/// Raw iterator over a [Db]
+pub enum DbRawIterator<'db> {
+    /// Iterator over a database with transactions support
+    Transactional(DBRawIteratorWithThreadMode<'db, OptimisticTransactionDB>),
+    /// Iterator over a read only database
+    ReadOnly(DBRawIteratorWithThreadMode<'db, DB>),
+}
+
+/// Applies `$body` to the RocksDB iterator of either kind of [DbRawIterator]
+macro_rules! with_iterator {
+    ($iterator:expr, $inner:ident => $body:expr) => {
+        match $iterator {
+            DbRawIterator::Transactional($inner) => $body,
+            DbRawIterator::ReadOnly($inner) => $body,
+        }
+    };
+}
+
+impl<'db> DbRawIterator<'db> {
+    pub(crate) fn seek<K: AsRef<[u8]>>(&mut self, key: K) {
+        with_iterator!(self, iterator => iterator.seek(key))
+    }
+
+    pub(crate) fn seek_for_prev<K: AsRef<[u8]>>(&mut self, key: K) {
+        with_iterator!(self, iterator => iterator.seek_for_prev(key))
+    }
+
+    pub(crate) fn seek_to_last(&mut self) {
+        with_iterator!(self, iterator => iterator.seek_to_last())
+    }
+
+    pub(crate) fn next(&mut self) {
+        with_iterator!(self, iterator => iterator.next())
+    }
+
+    pub(crate) fn prev(&mut self) {
+        with_iterator!(self, iterator => iterator.prev())
+    }
+
+    pub(crate) fn key(&self) -> Option<&[u8]> {
+        with_iterator!(self, iterator => iterator.key())
+    }
+
+    pub(crate) fn value(&self) -> Option<&[u8]> {
+        with_iterator!(self, iterator => iterator.value())
+    }
+}
+
+/// Durability policy applied to writes committed to RocksDB
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct WriteOptions {
+    /// Sync the write-ahead log to disk before a write is acknowledged
+    pub sync: bool,
+    /// Skip the write-ahead log, writes which were not flushed are lost on
+    /// crash. Meant for bulk loads followed by a flush.
+    pub disable_wal: bool,
+}
+
+impl WriteOptions {
+    fn to_rocksdb(self) -> rocksdb::WriteOptions {
+        let mut options = rocksdb::WriteOptions::default();
+        options.set_sync(self.sync);
+        options.disable_wal(self.disable_wal);
+        options
+    }
+}
 
 /// Storage which uses RocksDB as its backend.
 pub struct RocksDbStorage {
-    db: OptimisticTransactionDB,
+    db: Db,
+    write_options: WriteOptions,
+    write_generation: AtomicU64,
+    next_savepoint_id: AtomicU64,
+    next_commit_id: AtomicU64,
 }
 
 impl RocksDbStorage {
     /// Create RocksDb storage with default parameters using `path`.
     pub fn default_rocksdb_with_path<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
-        let db = Db::open_cf_descriptors(
+        let db = OptimisticTransactionDB::open_cf_descriptors(
             &DEFAULT_OPTS,
             &path,
-            [
-                ColumnFamilyDescriptor::new(AUX_CF_NAME, DEFAULT_OPTS.clone()),
-                ColumnFamilyDescriptor::new(ROOTS_CF_NAME, DEFAULT_OPTS.clone()),
-                ColumnFamilyDescriptor::new(META_CF_NAME, DEFAULT_OPTS.clone()),
-            ],
+            Self::column_family_descriptors(&DEFAULT_OPTS),
+        )
+        .map_err(RocksDBError)?;
+
+        Ok(Self::with_db(Db::Transactional(db)))
+    }
+
+    /// Open existing RocksDb storage at `path` in RocksDB read only mode. All
+    /// writes and transactions are rejected with [`Error::ReadOnly`], so a
+    /// replica or an analysis tool can't modify it by accident.
+    ///
+    /// The database lock isn't taken, so several read only handles can be
+    /// opened at once, next to a writable one. Each handle sees the data as
+    /// of when it was opened.
+    pub fn read_only_rocksdb_with_path<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let mut opts = DEFAULT_OPTS.clone();
+        opts.create_if_missing(false);
+        opts.create_missing_column_families(false);
+        let db = DB::open_cf_descriptors_read_only(
+            &opts,
+            &path,
+            Self::column_family_descriptors(&opts),
+            false,
         )
         .map_err(RocksDBError)?;
 
-        Ok(RocksDbStorage { db })
+        Ok(Self::with_db(Db::ReadOnly(db)))
+    }
+
+    fn column_family_descriptors(opts: &rocksdb::Options) -> [ColumnFamilyDescriptor; 3] {
+        [
+            ColumnFamilyDescriptor::new(AUX_CF_NAME, opts.clone()),
+            ColumnFamilyDescriptor::new(ROOTS_CF_NAME, opts.clone()),
+            ColumnFamilyDescriptor::new(META_CF_NAME, opts.clone()),
+        ]
+    }
+
+    fn with_db(db: Db) -> Self {
+        RocksDbStorage {
+            db,
+            write_options: WriteOptions::default(),
+            write_generation: AtomicU64::new(0),
+            next_savepoint_id: AtomicU64::new(0),
+            next_commit_id: AtomicU64::new(0),
+        }
+    }
+
+    /// Returns `true` if the storage was opened read only, see
+    /// [`RocksDbStorage::read_only_rocksdb_with_path`]
+    pub fn is_read_only(&self) -> bool {
+        matches!(self.db, Db::ReadOnly(_))
+    }
+
+    /// Counter increased every time writes done outside of a transaction or a
+    /// transaction are committed, so data read from storage can be cached as
+    /// long as it doesn't change. Transactions can only be committed with
+    /// [`Storage::commit_transaction`], which increases it even if the commit
+    /// fails.
+    pub fn write_generation(&self) -> u64 {
+        self.write_generation.load(Ordering::Acquire)
+    }
+
+    /// Sets the durability policy for batches committed from now on and for
+    /// transactions started from now on
+    pub fn set_write_options(&mut self, write_options: WriteOptions) {
+        self.write_options = write_options;
+    }
+
+    /// Durability policy used for writes
+    pub fn write_options(&self) -> WriteOptions {
+        self.write_options
     }
 
     fn build_prefix_body<B>(path: SubtreePath<B>) -> (Vec<u8>, usize)
@@ -388,12 +636,37 @@ impl RocksDbStorage {
     /// Commits a write batch
     pub fn commit_db_write_batch(
         &self,
-        db_batch: WriteBatchWithTransaction<true>,
+        mut db_batch: WriteBatchWithTransaction<true>,
         pending_costs: OperationCost,
         transaction: Option<&<RocksDbStorage as Storage>::Transaction>,
     ) -> CostResult<(), Error> {
+        let db = match self.db.transactional() {
+            Ok(db) => db,
+            Err(e) if !db_batch.is_empty() => {
+                return Err(e).wrap_with_cost(OperationCost::default())
+            }
+            Err(_) => return Ok(()).wrap_with_cost(pending_costs),
+        };
         let result = match transaction {
-            None => self.db.write(db_batch),
+            None => {
+                // The whole batch goes through a single RocksDB write, so it applies
+                // fully or not at all; the marker tells a commit which never finished
+                // apart from one which never started
+                let marker_result = if db_batch.is_empty() {
+                    Ok(())
+                } else {
+                    let marker =
+                        commit_in_progress_key(self.next_commit_id.fetch_add(1, Ordering::Relaxed));
+                    let mut marker_options = rocksdb::WriteOptions::default();
+                    marker_options.set_sync(self.write_options.sync);
+                    db_batch.delete_cf(cf_meta(&self.db), &marker);
+                    db.put_cf_opt(cf_meta(&self.db), &marker, [], &marker_options)
+                };
+                let result = marker_result
+                    .and_then(|_| db.write_opt(db_batch, &self.write_options.to_rocksdb()));
+                self.write_generation.fetch_add(1, Ordering::AcqRel);
+                result
+            }
             Some(transaction) => transaction.rebuild_from_writebatch(&db_batch),
         };
 
@@ -405,32 +678,218 @@ impl RocksDbStorage {
                 .wrap_with_cost(OperationCost::default())
         }
     }
+
+    /// Returns `true` if a batch commit was started but didn't complete, for
+    /// instance because the process crashed or the write failed. Batches are
+    /// written atomically, so the data is still at its state from before that
+    /// commit. Transactions aren't marked, as RocksDB commits them atomically
+    /// and a failed commit is reported to the caller.
+    pub fn has_interrupted_commit(&self) -> Result<bool, Error> {
+        Ok(!self.interrupted_commit_markers()?.is_empty())
+    }
+
+    /// Clears the interrupted commit markers once they were handled
+    pub fn clear_interrupted_commit(&self) -> Result<(), Error> {
+        let db = self.db.transactional()?;
+        let mut db_batch = WriteBatchWithTransaction::<true>::default();
+        for marker in self.interrupted_commit_markers()? {
+            db_batch.delete_cf(cf_meta(&self.db), marker);
+        }
+        db.write_opt(db_batch, &self.write_options.to_rocksdb())
+            .map_err(RocksDBError)
+    }
+
+    fn interrupted_commit_markers(&self) -> Result<Vec<Vec<u8>>, Error> {
+        let marker_len = commit_in_progress_key(0).len();
+        let mut markers = Vec::new();
+        let mut iter = self.db.raw_iterator_cf(cf_meta(&self.db));
+        iter.seek(COMMIT_IN_PROGRESS_KEY);
+        while let Some(key) = iter.key() {
+            if !key.starts_with(COMMIT_IN_PROGRESS_KEY) {
+                break;
+            }
+            if key.len() == marker_len {
+                markers.push(key.to_vec());
+            }
+            iter.next();
+        }
+        Ok(markers)
+    }
+
+    /// Returns the writes a transaction would apply on commit, in the order
+    /// they were made. Keys are returned with their subtree prefixes.
+    pub fn transaction_changeset(
+        &self,
+        transaction: &<RocksDbStorage as Storage>::Transaction,
+    ) -> Result<Vec<ChangesetEntry>, Error> {
+        let column_families = [
+            (
+                Self::column_family_id(cf_aux(&self.db))?,
+                ChangesetColumnFamily::Aux,
+            ),
+            (
+                Self::column_family_id(cf_roots(&self.db))?,
+                ChangesetColumnFamily::Roots,
+            ),
+            (
+                Self::column_family_id(cf_meta(&self.db))?,
+                ChangesetColumnFamily::Meta,
+            ),
+        ];
+
+        decode_write_batch(transaction.get_writebatch().data())?
+            .into_iter()
+            .map(|(column_family_id, key, value)| {
+                let column_family = if column_family_id == 0 {
+                    ChangesetColumnFamily::Default
+                } else {
+                    column_families
+                        .iter()
+                        .find(|(id, _)| *id == column_family_id)
+                        .map(|(_, column_family)| *column_family)
+                        .ok_or_else(|| {
+                            Error::StorageError(format!(
+                                "unknown column family id {}",
+                                column_family_id
+                            ))
+                        })?
+                };
+                Ok(ChangesetEntry {
+                    column_family,
+                    key,
+                    value,
+                })
+            })
+            .collect()
+    }
+
+    /// Number of subtrees which data or roots are written by the batch
+    pub fn batch_affected_subtrees(&self, batch: &StorageBatch) -> usize {
+        batch.data_and_roots_key_prefixes(blake3::OUT_LEN).len()
+    }
+
+    /// Returns `true` if no writes were recorded in the transaction, so
+    /// committing it would be a no-op. Writes cancelling each other out still
+    /// count as recorded.
+    pub fn transaction_is_empty(
+        &self,
+        transaction: &<RocksDbStorage as Storage>::Transaction,
+    ) -> bool {
+        transaction.get_writebatch().is_empty()
+    }
+
+    /// Column family ids are assigned by RocksDB on creation, so the id used in
+    /// write batch records is recovered by encoding a probe write
+    fn column_family_id(column_family: &ColumnFamily) -> Result<u32, Error> {
+        let mut probe = WriteBatchWithTransaction::<true>::default();
+        probe.put_cf(column_family, b"", b"");
+        decode_write_batch(probe.data())?
+            .first()
+            .map(|(id, ..)| *id)
+            .ok_or_else(|| Error::StorageError("empty probe write batch".to_owned()))
+    }
 }
 
 impl<'db> Storage<'db> for RocksDbStorage {
     type BatchStorageContext = PrefixedRocksDbStorageContext<'db>;
     type BatchTransactionalStorageContext = PrefixedRocksDbTransactionContext<'db>;
     type ImmediateStorageContext = PrefixedRocksDbImmediateStorageContext<'db>;
-    type Transaction = Tx<'db>;
+    type Transaction = RocksDbTransaction<'db>;
 
-    fn start_transaction(&'db self) -> Self::Transaction {
-        self.db.transaction()
+    fn start_transaction(&'db self) -> Result<Self::Transaction, Error> {
+        let inner = self.db.transactional()?.transaction_opt(
+            &self.write_options.to_rocksdb(),
+            &OptimisticTransactionOptions::default(),
+        );
+        Ok(RocksDbTransaction {
+            inner,
+            savepoints: Mutex::new(Vec::new()),
+        })
     }
 
     fn commit_transaction(&self, transaction: Self::Transaction) -> CostResult<(), Error> {
         // All transaction costs were provided on method calls
-        transaction
-            .commit()
-            .map_err(RocksDBError)
-            .wrap_with_cost(Default::default())
+        let result = transaction.inner.commit().map_err(RocksDBError);
+        self.write_generation.fetch_add(1, Ordering::AcqRel);
+        result.wrap_with_cost(Default::default())
     }
 
     fn rollback_transaction(&self, transaction: &Self::Transaction) -> Result<(), Error> {
-        transaction.rollback().map_err(RocksDBError)
+        let mut savepoints = savepoints_stack(transaction);
+        transaction.rollback().map_err(RocksDBError)?;
+        // RocksDB drops savepoints of a rolled back transaction
+        savepoints.clear();
+        Ok(())
+    }
+
+    fn savepoint(&self, transaction: &Self::Transaction) -> Result<SavepointId, Error> {
+        let mut savepoints = savepoints_stack(transaction);
+        let id = self.next_savepoint_id.fetch_add(1, Ordering::Relaxed);
+        transaction.set_savepoint();
+        savepoints.push(id);
+        Ok(SavepointId(id))
+    }
+
+    fn rollback_to(
+        &self,
+        transaction: &Self::Transaction,
+        savepoint: SavepointId,
+    ) -> Result<(), Error> {
+        let mut savepoints = savepoints_stack(transaction);
+        let Some(position) = savepoints.iter().position(|id| *id == savepoint.0) else {
+            return Err(Error::InvalidSavepoint);
+        };
+        // Savepoints created after the requested one are undone along with it
+        while savepoints.len() > position {
+            transaction.rollback_to_savepoint().map_err(RocksDBError)?;
+            savepoints.pop();
+        }
+        Ok(())
     }
 
     fn flush(&self) -> Result<(), Error> {
-        self.db.flush().map_err(RocksDBError)
+        self.db.transactional()?.flush().map_err(RocksDBError)
+    }
+
+    fn flush_cf(&self, column: StorageColumn) -> Result<(), Error> {
+        let column_family = match column {
+            StorageColumn::Data => cf_default(&self.db),
+            StorageColumn::Aux => cf_aux(&self.db),
+            StorageColumn::Roots => cf_roots(&self.db),
+            StorageColumn::Meta => cf_meta(&self.db),
+        };
+        self.db
+            .transactional()?
+            .flush_cf(column_family)
+            .map_err(RocksDBError)
+    }
+
+    fn compact_range(&self, start: Option<&[u8]>, end: Option<&[u8]>) -> Result<(), Error> {
+        // Subtrees data, auxiliary data and roots are stored under subtree
+        // prefixes, unlike metadata
+        let db = self.db.transactional()?;
+        for column_family in [cf_default(&self.db), cf_aux(&self.db), cf_roots(&self.db)] {
+            db.compact_range_cf(column_family, start, end);
+        }
+        Ok(())
+    }
+
+    fn estimate_live_data_size(&self) -> Result<u64, Error> {
+        // RocksDB estimates from SST files only, data still in memtables is
+        // not accounted for until flushed
+        let mut size = self
+            .db
+            .property_int_value(ESTIMATE_LIVE_DATA_SIZE)
+            .map_err(RocksDBError)?
+            .unwrap_or_default();
+        for column_family in [cf_aux(&self.db), cf_roots(&self.db), cf_meta(&self.db)] {
+            size += self
+                .db
+                .property_int_value_cf(column_family, ESTIMATE_LIVE_DATA_SIZE)
+                .map_err(RocksDBError)?
+                .unwrap_or_default();
+        }
+        Ok(size)
     }
 
     fn get_storage_context<'b, B>(
@@ -497,12 +956,19 @@ impl<'db> Storage<'db> for RocksDbStorage {
     }
 
     fn create_checkpoint<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
-        Checkpoint::new(&self.db)
+        with_db!(&self.db, db => Checkpoint::new(db))
             .and_then(|x| x.create_checkpoint(path))
             .map_err(RocksDBError)
     }
 }
 
+/// Get subtrees data column family
+fn cf_default(storage: &Db) -> &ColumnFamily {
+    storage
+        .cf_handle(DEFAULT_COLUMN_FAMILY_NAME)
+        .expect("default column family must exist")
+}
+
 /// Get auxiliary data column family
 fn cf_aux(storage: &Db) -> &ColumnFamily {
     storage
@@ -517,6 +983,13 @@ fn cf_roots(storage: &Db) -> &ColumnFamily {
         .expect("roots column family must exist")
 }
 
+/// Metadata key marking the commit numbered `commit_id` as in progress
+fn commit_in_progress_key(commit_id: u64) -> Vec<u8> {
+    let mut key = COMMIT_IN_PROGRESS_KEY.to_vec();
+    key.extend_from_slice(&commit_id.to_be_bytes());
+    key
+}
+
 /// Get metadata column family
 fn cf_meta(storage: &Db) -> &ColumnFamily {
     storage
@@ -526,6 +999,8 @@ fn cf_meta(storage: &Db) -> &ColumnFamily {
 
 #[cfg(test)]
 mod tests {
+    use rocksdb::properties::NUM_ENTRIES_ACTIVE_MEM_TABLE;
+
     use super::*;
     use crate::{
         rocksdb_storage::{test_utils::TempStorage, RocksDbStorage},
@@ -649,4 +1124,96 @@ mod tests {
 
         assert_eq!(iteration_cost_before, iteration_cost_after);
     }
+
+    #[test]
+    fn test_write_generation() {
+        let storage = TempStorage::new();
+        let generation = storage.write_generation();
+
+        // Batches committed in a transaction aren't written until it is
+        let tx = storage
+            .start_transaction()
+            .expect("cannot start transaction");
+        let batch = StorageBatch::new();
+        storage
+            .get_transactional_storage_context(SubtreePath::empty(), Some(&batch), &tx)
+            .unwrap()
+            .put_aux(b"key", b"value", None)
+            .unwrap()
+            .expect("cannot put aux");
+        storage
+            .commit_multi_context_batch(batch, Some(&tx))
+            .unwrap()
+            .expect("cannot commit batch");
+        assert_eq!(storage.write_generation(), generation);
+        storage
+            .commit_transaction(tx)
+            .unwrap()
+            .expect("cannot commit transaction");
+        assert_eq!(storage.write_generation(), generation + 1);
+
+        let batch = StorageBatch::new();
+        storage
+            .get_storage_context(SubtreePath::empty(), Some(&batch))
+            .unwrap()
+            .put_aux(b"key", b"other value", None)
+            .unwrap()
+            .expect("cannot put aux");
+        storage
+            .commit_multi_context_batch(batch, None)
+            .unwrap()
+            .expect("cannot commit batch");
+        assert_eq!(storage.write_generation(), generation + 2);
+    }
+
+    #[test]
+    fn test_flush_cf() {
+        let storage = TempStorage::new();
+        let batch = StorageBatch::new();
+        let context = storage
+            .get_storage_context(SubtreePath::empty(), Some(&batch))
+            .unwrap();
+        context
+            .put_aux(b"key", b"value", None)
+            .unwrap()
+            .expect("cannot put aux");
+        context
+            .put_meta(b"key", b"value", None)
+            .unwrap()
+            .expect("cannot put meta");
+        storage
+            .commit_multi_context_batch(batch, None)
+            .unwrap()
+            .expect("cannot commit batch");
+
+        let memtable_entries = |column_family: &ColumnFamily| {
+            storage
+                .db
+                .property_int_value_cf(column_family, NUM_ENTRIES_ACTIVE_MEM_TABLE)
+                .expect("cannot get property")
+                .expect("property must be known")
+        };
+        assert_eq!(memtable_entries(cf_aux(&storage.db)), 1);
+        assert_eq!(memtable_entries(cf_meta(&storage.db)), 1);
+
+        // Only the flushed column family is written out of its memtable
+        storage
+            .flush_cf(StorageColumn::Meta)
+            .expect("cannot flush meta");
+        assert_eq!(memtable_entries(cf_aux(&storage.db)), 1);
+        assert_eq!(memtable_entries(cf_meta(&storage.db)), 0);
+
+        // The data is still there
+        let context = storage
+            .get_storage_context(SubtreePath::empty(), None)
+            .unwrap();
+        assert_eq!(
+            context.get_meta(b"key").unwrap().expect("cannot get meta"),
+            Some(b"value".to_vec())
+        );
+        assert_eq!(
+            context.get_aux(b"key").unwrap().expect("cannot get aux"),
+            Some(b"value".to_vec())
+        );
+    }
 }
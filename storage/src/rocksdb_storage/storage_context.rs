@@ -40,11 +40,41 @@ pub use context_no_tx::PrefixedRocksDbStorageContext;
 pub use context_tx::PrefixedRocksDbTransactionContext;
 pub use raw_iterator::PrefixedRocksDbRawIterator;
 
+use grovedb_costs::OperationCost;
+
 use super::storage::SubtreePrefix;
+use crate::error::Error;
 
 /// Make prefixed key
 pub fn make_prefixed_key<K: AsRef<[u8]>>(prefix: &SubtreePrefix, key: K) -> Vec<u8> {
-    let mut prefix_vec = prefix.to_vec();
-    prefix_vec.extend_from_slice(key.as_ref());
+    let key = key.as_ref();
+    let mut prefix_vec = Vec::with_capacity(prefix.len() + key.len());
+    prefix_vec.extend_from_slice(prefix);
+    prefix_vec.extend_from_slice(key);
     prefix_vec
 }
+
+/// Cost of a read returning `value`, the same whether the value comes from
+/// storage or from the pending operations of a batch
+fn get_cost(value: &Result<Option<Vec<u8>>, Error>) -> OperationCost {
+    OperationCost {
+        seek_count: 1,
+        storage_loaded_bytes: value
+            .as_ref()
+            .ok()
+            .and_then(Option::as_ref)
+            .map(|x| x.len() as u32)
+            .unwrap_or(0),
+        ..Default::default()
+    }
+}
+
+/// Make prefixed key from a prefix of unchecked length, failing with
+/// [`Error::InvalidPrefix`] if it isn't exactly a subtree prefix long
+pub fn make_prefixed_key_checked<K: AsRef<[u8]>>(prefix: &[u8], key: K) -> Result<Vec<u8>, Error> {
+    let prefix: &SubtreePrefix = prefix.try_into().map_err(|_| Error::InvalidPrefix {
+        expected: std::mem::size_of::<SubtreePrefix>(),
+        actual: prefix.len(),
+    })?;
+    Ok(make_prefixed_key(prefix, key))
+}
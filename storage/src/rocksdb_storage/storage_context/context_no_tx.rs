@@ -33,13 +33,17 @@ use grovedb_costs::{
     storage_cost::key_value_cost::KeyValueStorageCost, ChildrenSizesWithIsSumTree, CostResult,
     CostsExt, OperationCost,
 };
-use rocksdb::{ColumnFamily, DBRawIteratorWithThreadMode};
+use rocksdb::ColumnFamily;
 
-use super::{batch::PrefixedMultiContextBatchPart, make_prefixed_key, PrefixedRocksDbRawIterator};
+use super::{
+    batch::PrefixedMultiContextBatchPart, get_cost, make_prefixed_key, PrefixedRocksDbRawIterator,
+};
 use crate::{
     error,
     error::Error::RocksDBError,
-    rocksdb_storage::storage::{Db, SubtreePrefix, AUX_CF_NAME, META_CF_NAME, ROOTS_CF_NAME},
+    rocksdb_storage::storage::{
+        Db, DbRawIterator, SubtreePrefix, AUX_CF_NAME, META_CF_NAME, ROOTS_CF_NAME,
+    },
     StorageBatch, StorageContext,
 };
 
@@ -87,7 +91,7 @@ impl<'db> PrefixedRocksDbStorageContext<'db> {
 
 impl<'db> StorageContext<'db> for PrefixedRocksDbStorageContext<'db> {
     type Batch = PrefixedMultiContextBatchPart;
-    type RawIterator = PrefixedRocksDbRawIterator<DBRawIteratorWithThreadMode<'db, Db>>;
+    type RawIterator = PrefixedRocksDbRawIterator<DbRawIterator<'db>>;
 
     fn put<K: AsRef<[u8]>>(
         &self,
@@ -107,6 +111,17 @@ impl<'db> StorageContext<'db> for PrefixedRocksDbStorageContext<'db> {
         Ok(()).wrap_with_cost(OperationCost::default())
     }
 
+    fn multi_put(&self, entries: &[(&[u8], &[u8])]) -> CostResult<(), Error> {
+        if let Some(existing_batch) = self.batch {
+            existing_batch.multi_put(
+                entries
+                    .iter()
+                    .map(|(key, value)| (make_prefixed_key(&self.prefix, key), value.to_vec())),
+            );
+        }
+        Ok(()).wrap_with_cost(OperationCost::default())
+    }
+
     fn put_aux<K: AsRef<[u8]>>(
         &self,
         key: K,
@@ -200,67 +215,60 @@ impl<'db> StorageContext<'db> for PrefixedRocksDbStorageContext<'db> {
     }
 
     fn get<K: AsRef<[u8]>>(&self, key: K) -> CostResult<Option<Vec<u8>>, Error> {
-        self.storage
-            .get(make_prefixed_key(&self.prefix, key))
-            .map_err(RocksDBError)
-            .wrap_fn_cost(|value| OperationCost {
-                seek_count: 1,
-                storage_loaded_bytes: value
-                    .as_ref()
-                    .ok()
-                    .and_then(Option::as_ref)
-                    .map(|x| x.len() as u32)
-                    .unwrap_or(0),
-                ..Default::default()
-            })
+        let prefixed_key = make_prefixed_key(&self.prefix, key);
+        match self
+            .batch
+            .and_then(|batch| batch.get_pending(&prefixed_key))
+        {
+            Some(pending_value) => Ok(pending_value),
+            None => self.storage.get(prefixed_key).map_err(RocksDBError),
+        }
+        .wrap_fn_cost(get_cost)
     }
 
     fn get_aux<K: AsRef<[u8]>>(&self, key: K) -> CostResult<Option<Vec<u8>>, Error> {
-        self.storage
-            .get_cf(self.cf_aux(), make_prefixed_key(&self.prefix, key))
-            .map_err(RocksDBError)
-            .wrap_fn_cost(|value| OperationCost {
-                seek_count: 1,
-                storage_loaded_bytes: value
-                    .as_ref()
-                    .ok()
-                    .and_then(Option::as_ref)
-                    .map(|x| x.len() as u32)
-                    .unwrap_or(0),
-                ..Default::default()
-            })
+        let prefixed_key = make_prefixed_key(&self.prefix, key);
+        match self
+            .batch
+            .and_then(|batch| batch.get_pending_aux(&prefixed_key))
+        {
+            Some(pending_value) => Ok(pending_value),
+            None => self
+                .storage
+                .get_cf(self.cf_aux(), prefixed_key)
+                .map_err(RocksDBError),
+        }
+        .wrap_fn_cost(get_cost)
     }
 
     fn get_root<K: AsRef<[u8]>>(&self, key: K) -> CostResult<Option<Vec<u8>>, Error> {
-        self.storage
-            .get_cf(self.cf_roots(), make_prefixed_key(&self.prefix, key))
-            .map_err(RocksDBError)
-            .wrap_fn_cost(|value| OperationCost {
-                seek_count: 1,
-                storage_loaded_bytes: value
-                    .as_ref()
-                    .ok()
-                    .and_then(Option::as_ref)
-                    .map(|x| x.len() as u32)
-                    .unwrap_or(0),
-                ..Default::default()
-            })
+        let prefixed_key = make_prefixed_key(&self.prefix, key);
+        match self
+            .batch
+            .and_then(|batch| batch.get_pending_root(&prefixed_key))
+        {
+            Some(pending_value) => Ok(pending_value),
+            None => self
+                .storage
+                .get_cf(self.cf_roots(), prefixed_key)
+                .map_err(RocksDBError),
+        }
+        .wrap_fn_cost(get_cost)
     }
 
     fn get_meta<K: AsRef<[u8]>>(&self, key: K) -> CostResult<Option<Vec<u8>>, Error> {
-        self.storage
-            .get_cf(self.cf_meta(), make_prefixed_key(&self.prefix, key))
-            .map_err(RocksDBError)
-            .wrap_fn_cost(|value| OperationCost {
-                seek_count: 1,
-                storage_loaded_bytes: value
-                    .as_ref()
-                    .ok()
-                    .and_then(Option::as_ref)
-                    .map(|x| x.len() as u32)
-                    .unwrap_or(0),
-                ..Default::default()
-            })
+        let prefixed_key = make_prefixed_key(&self.prefix, key);
+        match self
+            .batch
+            .and_then(|batch| batch.get_pending_meta(&prefixed_key))
+        {
+            Some(pending_value) => Ok(pending_value),
+            None => self
+                .storage
+                .get_cf(self.cf_meta(), prefixed_key)
+                .map_err(RocksDBError),
+        }
+        .wrap_fn_cost(get_cost)
     }
 
     fn new_batch(&self) -> Self::Batch {
@@ -283,4 +291,25 @@ impl<'db> StorageContext<'db> for PrefixedRocksDbStorageContext<'db> {
             raw_iterator: self.storage.raw_iterator(),
         }
     }
+
+    fn raw_iter_aux(&self) -> Self::RawIterator {
+        PrefixedRocksDbRawIterator {
+            prefix: self.prefix.clone(),
+            raw_iterator: self.storage.raw_iterator_cf(self.cf_aux()),
+        }
+    }
+
+    fn raw_iter_roots(&self) -> Self::RawIterator {
+        PrefixedRocksDbRawIterator {
+            prefix: self.prefix.clone(),
+            raw_iterator: self.storage.raw_iterator_cf(self.cf_roots()),
+        }
+    }
+
+    fn raw_iter_meta(&self) -> Self::RawIterator {
+        PrefixedRocksDbRawIterator {
+            prefix: self.prefix.clone(),
+            raw_iterator: self.storage.raw_iterator_cf(self.cf_meta()),
+        }
+    }
 }
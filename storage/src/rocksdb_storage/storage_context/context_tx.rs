@@ -35,7 +35,9 @@ use grovedb_costs::{
 };
 use rocksdb::{ColumnFamily, DBRawIteratorWithThreadMode};
 
-use super::{batch::PrefixedMultiContextBatchPart, make_prefixed_key, PrefixedRocksDbRawIterator};
+use super::{
+    batch::PrefixedMultiContextBatchPart, get_cost, make_prefixed_key, PrefixedRocksDbRawIterator,
+};
 use crate::{
     error,
     error::Error::RocksDBError,
@@ -134,6 +136,17 @@ impl<'db> StorageContext<'db> for PrefixedRocksDbTransactionContext<'db> {
         Ok(()).wrap_with_cost(OperationCost::default())
     }
 
+    fn multi_put(&self, entries: &[(&[u8], &[u8])]) -> CostResult<(), Error> {
+        if let Some(existing_batch) = self.batch {
+            existing_batch.multi_put(
+                entries
+                    .iter()
+                    .map(|(key, value)| (make_prefixed_key(&self.prefix, key), value.to_vec())),
+            );
+        }
+        Ok(()).wrap_with_cost(OperationCost::default())
+    }
+
     fn put_aux<K: AsRef<[u8]>>(
         &self,
         key: K,
@@ -231,67 +244,60 @@ impl<'db> StorageContext<'db> for PrefixedRocksDbTransactionContext<'db> {
     }
 
     fn get<K: AsRef<[u8]>>(&self, key: K) -> CostResult<Option<Vec<u8>>, Error> {
-        self.transaction
-            .get(make_prefixed_key(&self.prefix, key))
-            .map_err(RocksDBError)
-            .wrap_fn_cost(|value| OperationCost {
-                seek_count: 1,
-                storage_loaded_bytes: value
-                    .as_ref()
-                    .ok()
-                    .and_then(Option::as_ref)
-                    .map(|x| x.len() as u32)
-                    .unwrap_or(0),
-                ..Default::default()
-            })
+        let prefixed_key = make_prefixed_key(&self.prefix, key);
+        match self
+            .batch
+            .and_then(|batch| batch.get_pending(&prefixed_key))
+        {
+            Some(pending_value) => Ok(pending_value),
+            None => self.transaction.get(prefixed_key).map_err(RocksDBError),
+        }
+        .wrap_fn_cost(get_cost)
     }
 
     fn get_aux<K: AsRef<[u8]>>(&self, key: K) -> CostResult<Option<Vec<u8>>, Error> {
-        self.transaction
-            .get_cf(self.cf_aux(), make_prefixed_key(&self.prefix, key))
-            .map_err(RocksDBError)
-            .wrap_fn_cost(|value| OperationCost {
-                seek_count: 1,
-                storage_loaded_bytes: value
-                    .as_ref()
-                    .ok()
-                    .and_then(Option::as_ref)
-                    .map(|x| x.len() as u32)
-                    .unwrap_or(0),
-                ..Default::default()
-            })
+        let prefixed_key = make_prefixed_key(&self.prefix, key);
+        match self
+            .batch
+            .and_then(|batch| batch.get_pending_aux(&prefixed_key))
+        {
+            Some(pending_value) => Ok(pending_value),
+            None => self
+                .transaction
+                .get_cf(self.cf_aux(), prefixed_key)
+                .map_err(RocksDBError),
+        }
+        .wrap_fn_cost(get_cost)
     }
 
     fn get_root<K: AsRef<[u8]>>(&self, key: K) -> CostResult<Option<Vec<u8>>, Error> {
-        self.transaction
-            .get_cf(self.cf_roots(), make_prefixed_key(&self.prefix, key))
-            .map_err(RocksDBError)
-            .wrap_fn_cost(|value| OperationCost {
-                seek_count: 1,
-                storage_loaded_bytes: value
-                    .as_ref()
-                    .ok()
-                    .and_then(Option::as_ref)
-                    .map(|x| x.len() as u32)
-                    .unwrap_or(0),
-                ..Default::default()
-            })
+        let prefixed_key = make_prefixed_key(&self.prefix, key);
+        match self
+            .batch
+            .and_then(|batch| batch.get_pending_root(&prefixed_key))
+        {
+            Some(pending_value) => Ok(pending_value),
+            None => self
+                .transaction
+                .get_cf(self.cf_roots(), prefixed_key)
+                .map_err(RocksDBError),
+        }
+        .wrap_fn_cost(get_cost)
     }
 
     fn get_meta<K: AsRef<[u8]>>(&self, key: K) -> CostResult<Option<Vec<u8>>, Error> {
-        self.transaction
-            .get_cf(self.cf_meta(), make_prefixed_key(&self.prefix, key))
-            .map_err(RocksDBError)
-            .wrap_fn_cost(|value| OperationCost {
-                seek_count: 1,
-                storage_loaded_bytes: value
-                    .as_ref()
-                    .ok()
-                    .and_then(Option::as_ref)
-                    .map(|x| x.len() as u32)
-                    .unwrap_or(0),
-                ..Default::default()
-            })
+        let prefixed_key = make_prefixed_key(&self.prefix, key);
+        match self
+            .batch
+            .and_then(|batch| batch.get_pending_meta(&prefixed_key))
+        {
+            Some(pending_value) => Ok(pending_value),
+            None => self
+                .transaction
+                .get_cf(self.cf_meta(), prefixed_key)
+                .map_err(RocksDBError),
+        }
+        .wrap_fn_cost(get_cost)
     }
 
     fn new_batch(&self) -> Self::Batch {
@@ -315,4 +321,25 @@ impl<'db> StorageContext<'db> for PrefixedRocksDbTransactionContext<'db> {
             raw_iterator: self.transaction.raw_iterator(),
         }
     }
+
+    fn raw_iter_aux(&self) -> Self::RawIterator {
+        PrefixedRocksDbRawIterator {
+            prefix: self.prefix.clone(),
+            raw_iterator: self.transaction.raw_iterator_cf(self.cf_aux()),
+        }
+    }
+
+    fn raw_iter_roots(&self) -> Self::RawIterator {
+        PrefixedRocksDbRawIterator {
+            prefix: self.prefix.clone(),
+            raw_iterator: self.transaction.raw_iterator_cf(self.cf_roots()),
+        }
+    }
+
+    fn raw_iter_meta(&self) -> Self::RawIterator {
+        PrefixedRocksDbRawIterator {
+            prefix: self.prefix.clone(),
+            raw_iterator: self.transaction.raw_iterator_cf(self.cf_meta()),
+        }
+    }
 }
@@ -33,7 +33,8 @@ use rocksdb::DBRawIteratorWithThreadMode;
 
 use super::make_prefixed_key;
 use crate::{
-    rocksdb_storage::storage::{Db, SubtreePrefix, Tx},
+    key_successor,
+    rocksdb_storage::storage::{DbRawIterator, SubtreePrefix, Tx},
     RawIterator,
 };
 
@@ -46,7 +47,7 @@ pub struct PrefixedRocksDbRawIterator<I> {
     pub(super) raw_iterator: I,
 }
 
-impl<'a> RawIterator for PrefixedRocksDbRawIterator<DBRawIteratorWithThreadMode<'a, Db>> {
+impl<'a> RawIterator for PrefixedRocksDbRawIterator<DbRawIterator<'a>> {
     fn seek_to_first(&mut self) -> CostContext<()> {
         self.raw_iterator.seek(&self.prefix);
         ().wrap_with_cost(OperationCost::with_seek_count(1))
@@ -76,6 +77,27 @@ impl<'a> RawIterator for PrefixedRocksDbRawIterator<DBRawIteratorWithThreadMode<
         ().wrap_with_cost(OperationCost::with_seek_count(1))
     }
 
+    fn seek_last_for_prefix(&mut self, prefix: &[u8]) -> CostContext<()> {
+        let mut cost = OperationCost::default();
+
+        match key_successor(&make_prefixed_key(&self.prefix, prefix)) {
+            Some(upper_bound) => {
+                self.raw_iterator.seek_for_prev(&upper_bound);
+                cost.seek_count += 1;
+                if self.raw_iterator.key() == Some(upper_bound.as_slice()) {
+                    self.raw_iterator.prev();
+                    cost.seek_count += 1;
+                }
+            }
+            None => {
+                self.raw_iterator.seek_to_last();
+                cost.seek_count += 1;
+            }
+        }
+
+        ().wrap_with_cost(cost)
+    }
+
     fn next(&mut self) -> CostContext<()> {
         self.raw_iterator.next();
         ().wrap_with_cost(OperationCost::with_seek_count(1))
@@ -197,6 +219,27 @@ impl<'a> RawIterator for PrefixedRocksDbRawIterator<DBRawIteratorWithThreadMode<
         ().wrap_with_cost(OperationCost::with_seek_count(1))
     }
 
+    fn seek_last_for_prefix(&mut self, prefix: &[u8]) -> CostContext<()> {
+        let mut cost = OperationCost::default();
+
+        match key_successor(&make_prefixed_key(&self.prefix, prefix)) {
+            Some(upper_bound) => {
+                self.raw_iterator.seek_for_prev(&upper_bound);
+                cost.seek_count += 1;
+                if self.raw_iterator.key() == Some(upper_bound.as_slice()) {
+                    self.raw_iterator.prev();
+                    cost.seek_count += 1;
+                }
+            }
+            None => {
+                self.raw_iterator.seek_to_last();
+                cost.seek_count += 1;
+            }
+        }
+
+        ().wrap_with_cost(cost)
+    }
+
     fn next(&mut self) -> CostContext<()> {
         self.raw_iterator.next();
         ().wrap_with_cost(OperationCost::with_seek_count(1))
@@ -33,12 +33,17 @@ use crate::Batch;
 
 mod immediate_storage {
     use super::*;
-    use crate::{RawIterator, Storage, StorageContext};
+    use crate::{
+        rocksdb_storage::{ChangesetColumnFamily, ChangesetEntry, RocksDbStorage},
+        RawIterator, Storage, StorageContext,
+    };
 
     #[test]
     fn test_aux_cf_methods() {
         let storage = TempStorage::new();
-        let tx = storage.start_transaction();
+        let tx = storage
+            .start_transaction()
+            .expect("cannot start transaction");
         let context_ayya = storage
             .get_immediate_storage_context([b"ayya"].as_ref().into(), &tx)
             .unwrap();
@@ -78,11 +83,15 @@ mod immediate_storage {
             .unwrap()
             .expect("cannot commit transaction");
 
-        let tx2 = storage.start_transaction();
+        let tx2 = storage
+            .start_transaction()
+            .expect("cannot start transaction");
         let context_ayya_after_tx = storage
             .get_immediate_storage_context([b"ayya"].as_ref().into(), &tx2)
             .unwrap();
-        let tx3 = storage.start_transaction();
+        let tx3 = storage
+            .start_transaction()
+            .expect("cannot start transaction");
         let context_ayya_after_no_tx = storage
             .get_immediate_storage_context([b"ayya"].as_ref().into(), &tx3)
             .unwrap();
@@ -127,7 +136,9 @@ mod immediate_storage {
     #[test]
     fn test_roots_cf_methods() {
         let storage = TempStorage::new();
-        let tx = storage.start_transaction();
+        let tx = storage
+            .start_transaction()
+            .expect("cannot start transaction");
         let context_ayya = storage
             .get_immediate_storage_context([b"ayya"].as_ref().into(), &tx)
             .unwrap();
@@ -167,11 +178,15 @@ mod immediate_storage {
             .unwrap()
             .expect("cannot commit transaction");
 
-        let tx2 = storage.start_transaction();
+        let tx2 = storage
+            .start_transaction()
+            .expect("cannot start transaction");
         let context_ayya_after_tx = storage
             .get_immediate_storage_context([b"ayya"].as_ref().into(), &tx2)
             .unwrap();
-        let tx3 = storage.start_transaction();
+        let tx3 = storage
+            .start_transaction()
+            .expect("cannot start transaction");
         let context_ayya_after_no_tx = storage
             .get_immediate_storage_context([b"ayya"].as_ref().into(), &tx3)
             .unwrap();
@@ -216,7 +231,9 @@ mod immediate_storage {
     #[test]
     fn test_meta_cf_methods() {
         let storage = TempStorage::new();
-        let tx = storage.start_transaction();
+        let tx = storage
+            .start_transaction()
+            .expect("cannot start transaction");
         let context_ayya = storage
             .get_immediate_storage_context([b"ayya"].as_ref().into(), &tx)
             .unwrap();
@@ -284,7 +301,9 @@ mod immediate_storage {
     #[test]
     fn test_default_cf_methods() {
         let storage = TempStorage::new();
-        let tx = storage.start_transaction();
+        let tx = storage
+            .start_transaction()
+            .expect("cannot start transaction");
         let context_ayya = storage
             .get_immediate_storage_context([b"ayya"].as_ref().into(), &tx)
             .unwrap();
@@ -352,7 +371,9 @@ mod immediate_storage {
     #[test]
     fn test_batch() {
         let storage = TempStorage::new();
-        let tx = storage.start_transaction();
+        let tx = storage
+            .start_transaction()
+            .expect("cannot start transaction");
         let context_ayya = storage
             .get_immediate_storage_context([b"ayya"].as_ref().into(), &tx)
             .unwrap();
@@ -398,7 +419,9 @@ mod immediate_storage {
             .unwrap()
             .expect("cannot commit transaction");
 
-        let tx = storage.start_transaction();
+        let tx = storage
+            .start_transaction()
+            .expect("cannot start transaction");
         let context_ayya = storage
             .get_immediate_storage_context([b"ayya"].as_ref().into(), &tx)
             .unwrap();
@@ -421,7 +444,9 @@ mod immediate_storage {
     #[test]
     fn test_raw_iterator() {
         let storage = TempStorage::new();
-        let tx = storage.start_transaction();
+        let tx = storage
+            .start_transaction()
+            .expect("cannot start transaction");
         let context = storage
             .get_immediate_storage_context([b"someprefix"].as_ref().into(), &tx)
             .unwrap();
@@ -472,7 +497,9 @@ mod immediate_storage {
 
         // Test uncommited changes
         {
-            let tx = storage.start_transaction();
+            let tx = storage
+                .start_transaction()
+                .expect("cannot start transaction");
             let context_tx = storage
                 .get_immediate_storage_context([b"someprefix"].as_ref().into(), &tx)
                 .unwrap();
@@ -528,7 +555,9 @@ mod immediate_storage {
                 (b"key3", b"value3"),
             ];
             let mut expected_iter = expected.into_iter();
-            let tx = storage.start_transaction();
+            let tx = storage
+                .start_transaction()
+                .expect("cannot start transaction");
             let context = storage
                 .get_immediate_storage_context([b"someprefix"].as_ref().into(), &tx)
                 .unwrap();
@@ -545,11 +574,259 @@ mod immediate_storage {
             assert!(expected_iter.next().is_none());
         }
     }
+
+    #[test]
+    fn test_raw_iterator_peek_next_key() {
+        let storage = TempStorage::new();
+        let tx = storage
+            .start_transaction()
+            .expect("cannot start transaction");
+        let context = storage
+            .get_immediate_storage_context([b"someprefix"].as_ref().into(), &tx)
+            .unwrap();
+        for key in [b"key0", b"key1", b"key2"] {
+            context
+                .put(key, b"value", None, None)
+                .unwrap()
+                .expect("expected successful insertion");
+        }
+        // A neighbouring subtree must not be visible when peeking past the end
+        storage
+            .get_immediate_storage_context([b"zsomeprefix"].as_ref().into(), &tx)
+            .unwrap()
+            .put(b"key3", b"value", None, None)
+            .unwrap()
+            .expect("expected successful insertion");
+
+        let mut iter = context.raw_iter();
+        iter.seek_to_first().unwrap();
+        while iter.valid().unwrap() {
+            let current = iter.key().unwrap().unwrap().to_vec();
+            let peeked = iter.peek_next_key().unwrap();
+            assert_eq!(iter.key().unwrap(), Some(current.as_slice()));
+
+            iter.next().unwrap();
+            assert_eq!(iter.key().unwrap().map(|k| k.to_vec()), peeked);
+        }
+        assert_eq!(iter.peek_next_key().unwrap(), None);
+    }
+
+    #[test]
+    fn test_raw_iterator_seek_last_for_prefix() {
+        let storage = TempStorage::new();
+        let tx = storage
+            .start_transaction()
+            .expect("cannot start transaction");
+        let context = storage
+            .get_immediate_storage_context([b"someprefix"].as_ref().into(), &tx)
+            .unwrap();
+        for key in [b"a1", b"a2", b"b1", b"b2", b"b\xff", b"c1"] {
+            context
+                .put(key, b"value", None, None)
+                .unwrap()
+                .expect("expected successful insertion");
+        }
+        storage
+            .get_immediate_storage_context([b"zsomeprefix"].as_ref().into(), &tx)
+            .unwrap()
+            .put(b"z1", b"value", None, None)
+            .unwrap()
+            .expect("expected successful insertion");
+
+        let mut iter = context.raw_iter();
+        iter.seek_last_for_prefix(b"a").unwrap();
+        assert_eq!(iter.key().unwrap(), Some(b"a2".as_ref()));
+        iter.seek_last_for_prefix(b"b").unwrap();
+        assert_eq!(iter.key().unwrap(), Some(b"b\xff".as_ref()));
+        iter.seek_last_for_prefix(b"c1").unwrap();
+        assert_eq!(iter.key().unwrap(), Some(b"c1".as_ref()));
+
+        // An empty prefix selects the last record of the subtree
+        iter.seek_last_for_prefix(b"").unwrap();
+        assert_eq!(iter.key().unwrap(), Some(b"c1".as_ref()));
+
+        // Without a matching record the iterator stops before the prefix
+        iter.seek_last_for_prefix(b"bb").unwrap();
+        assert_eq!(iter.key().unwrap(), Some(b"b2".as_ref()));
+        iter.seek_last_for_prefix(b"0").unwrap();
+        assert!(!iter.valid().unwrap());
+    }
+
+    #[test]
+    fn test_raw_iterators_over_column_families() {
+        fn collect<I: RawIterator>(mut iter: I) -> Vec<(Vec<u8>, Vec<u8>)> {
+            let mut result = Vec::new();
+            iter.seek_to_first().unwrap();
+            while iter.valid().unwrap() {
+                result.push((
+                    iter.key().unwrap().unwrap().to_vec(),
+                    iter.value().unwrap().unwrap().to_vec(),
+                ));
+                iter.next().unwrap();
+            }
+            result
+        }
+
+        let storage = TempStorage::new();
+        let tx = storage
+            .start_transaction()
+            .expect("cannot start transaction");
+        let context = storage
+            .get_immediate_storage_context([b"someprefix"].as_ref().into(), &tx)
+            .unwrap();
+
+        context
+            .put(b"data", b"datavalue", None, None)
+            .unwrap()
+            .expect("cannot insert into data cf");
+        context
+            .put_aux(b"aux", b"auxvalue", None)
+            .unwrap()
+            .expect("cannot insert into aux cf");
+        context
+            .put_root(b"root", b"rootvalue", None)
+            .unwrap()
+            .expect("cannot insert into roots cf");
+        context
+            .put_meta(b"meta", b"metavalue", None)
+            .unwrap()
+            .expect("cannot insert into meta cf");
+
+        let entry = |k: &[u8], v: &[u8]| vec![(k.to_vec(), v.to_vec())];
+
+        assert_eq!(collect(context.raw_iter()), entry(b"data", b"datavalue"));
+        assert_eq!(collect(context.raw_iter_aux()), entry(b"aux", b"auxvalue"));
+        assert_eq!(
+            collect(context.raw_iter_roots()),
+            entry(b"root", b"rootvalue")
+        );
+        assert_eq!(
+            collect(context.raw_iter_meta()),
+            entry(b"meta", b"metavalue")
+        );
+
+        storage
+            .commit_transaction(tx)
+            .unwrap()
+            .expect("cannot commit transaction");
+
+        // Committed data is visible through non-transactional contexts as well
+        let context = storage
+            .get_storage_context([b"someprefix"].as_ref().into(), None)
+            .unwrap();
+        assert_eq!(
+            collect(context.raw_iter_roots()),
+            entry(b"root", b"rootvalue")
+        );
+        assert_eq!(collect(context.raw_iter_aux()), entry(b"aux", b"auxvalue"));
+    }
+
+    #[test]
+    fn test_transaction_changeset() {
+        let storage = TempStorage::new();
+        let tx = storage
+            .start_transaction()
+            .expect("cannot start transaction");
+        let context = storage
+            .get_immediate_storage_context([b"someprefix"].as_ref().into(), &tx)
+            .unwrap();
+
+        context
+            .put(b"key1", b"value1", None, None)
+            .unwrap()
+            .expect("cannot insert into data cf");
+        context
+            .put_aux(b"key2", b"value2", None)
+            .unwrap()
+            .expect("cannot insert into aux cf");
+        context
+            .put_root(b"key3", b"value3", None)
+            .unwrap()
+            .expect("cannot insert into roots cf");
+        context
+            .delete(b"key1", None)
+            .unwrap()
+            .expect("cannot delete from data cf");
+
+        let prefix = RocksDbStorage::build_prefix([b"someprefix"].as_ref().into()).unwrap();
+        let prefixed = |key: &[u8]| {
+            let mut prefixed_key = prefix.to_vec();
+            prefixed_key.extend_from_slice(key);
+            prefixed_key
+        };
+
+        assert_eq!(
+            storage
+                .transaction_changeset(&tx)
+                .expect("cannot get transaction changeset"),
+            vec![
+                ChangesetEntry {
+                    column_family: ChangesetColumnFamily::Default,
+                    key: prefixed(b"key1"),
+                    value: Some(b"value1".to_vec()),
+                },
+                ChangesetEntry {
+                    column_family: ChangesetColumnFamily::Aux,
+                    key: prefixed(b"key2"),
+                    value: Some(b"value2".to_vec()),
+                },
+                ChangesetEntry {
+                    column_family: ChangesetColumnFamily::Roots,
+                    key: prefixed(b"key3"),
+                    value: Some(b"value3".to_vec()),
+                },
+                ChangesetEntry {
+                    column_family: ChangesetColumnFamily::Default,
+                    key: prefixed(b"key1"),
+                    value: None,
+                },
+            ]
+        );
+
+        // A fresh transaction has nothing pending
+        let other_tx = storage
+            .start_transaction()
+            .expect("cannot start transaction");
+        assert!(storage
+            .transaction_changeset(&other_tx)
+            .expect("cannot get transaction changeset")
+            .is_empty());
+    }
+
+    #[test]
+    fn test_transaction_is_empty() {
+        let storage = TempStorage::new();
+        let tx = storage
+            .start_transaction()
+            .expect("cannot start transaction");
+        assert!(storage.transaction_is_empty(&tx));
+
+        let context = storage
+            .get_immediate_storage_context([b"someprefix"].as_ref().into(), &tx)
+            .unwrap();
+        context
+            .put(b"key1", b"value1", None, None)
+            .unwrap()
+            .expect("cannot insert into data cf");
+        assert!(!storage.transaction_is_empty(&tx));
+
+        // The delete cancels the put out, but both writes are still recorded
+        context
+            .delete(b"key1", None)
+            .unwrap()
+            .expect("cannot delete from data cf");
+        assert!(!storage.transaction_is_empty(&tx));
+    }
 }
 
 mod batch_no_transaction {
+    use grovedb_costs::OperationCost;
+
     use super::*;
-    use crate::{Batch, Storage, StorageBatch, StorageContext};
+    use crate::{
+        rocksdb_storage::{RocksDbStorage, WriteOptions},
+        Batch, RawIterator, Storage, StorageBatch, StorageContext,
+    };
 
     #[test]
     fn test_various_cf_methods() {
@@ -595,9 +872,18 @@ mod batch_no_transaction {
             .unwrap()
             .expect("cannot insert data");
 
-        // There is no "staging" data for batch contexts: `get` will access only
-        // pre-batch data (thus `None` until commit).
-        assert!(context_ayya
+        // Batch contexts read their pending writes, other contexts only see them
+        // once the batch is commited
+        assert_eq!(
+            context_ayya
+                .get_aux(b"key1")
+                .unwrap()
+                .expect("cannot get from aux cf"),
+            Some(b"ayyavalue1".to_vec())
+        );
+        assert!(storage
+            .get_storage_context([b"ayya"].as_ref().into(), None)
+            .unwrap()
             .get_aux(b"key1")
             .unwrap()
             .expect("cannot get from aux cf")
@@ -744,16 +1030,24 @@ mod batch_no_transaction {
         // it in a single run to the database
         assert_eq!(batch.len(), 6);
 
-        assert!(context_ayya
+        // Staged data is only visible through contexts of the same batch
+        let context_ayya_without_batch = storage
+            .get_storage_context([b"ayya"].as_ref().into(), None)
+            .unwrap();
+        assert!(context_ayya_without_batch
             .get(b"key1")
             .unwrap()
             .expect("cannot get data")
             .is_none());
-        assert!(context_ayya
+        assert!(context_ayya_without_batch
             .get(b"key3")
             .unwrap()
             .expect("cannot get data")
             .is_none());
+        assert_eq!(
+            context_ayya.get(b"key3").unwrap().expect("cannot get data"),
+            Some(b"ayyavalue3".to_vec())
+        );
 
         storage
             .commit_multi_context_batch(batch, None)
@@ -773,16 +1067,313 @@ mod batch_no_transaction {
             b"ayyavalue3"
         );
     }
+
+    #[test]
+    fn test_batch_context_reads_its_writes() {
+        let storage = TempStorage::new();
+        let batch = StorageBatch::new();
+        let context = storage
+            .get_storage_context([b"ayya"].as_ref().into(), Some(&batch))
+            .unwrap();
+        context
+            .put(b"key1", b"value1", None, None)
+            .unwrap()
+            .expect("cannot insert data");
+        context
+            .put_meta(b"key2", b"value2", None)
+            .unwrap()
+            .expect("cannot insert into meta cf");
+        storage
+            .commit_multi_context_batch(batch, None)
+            .unwrap()
+            .expect("cannot commit batch");
+
+        let batch = StorageBatch::new();
+        let context = storage
+            .get_storage_context([b"ayya"].as_ref().into(), Some(&batch))
+            .unwrap();
+        context
+            .put(b"key1", b"new value1", None, None)
+            .unwrap()
+            .expect("cannot insert data");
+        context
+            .put(b"key3", b"value3", None, None)
+            .unwrap()
+            .expect("cannot insert data");
+        context
+            .delete_meta(b"key2", None)
+            .unwrap()
+            .expect("cannot delete from meta cf");
+
+        assert_eq!(
+            context.get(b"key1").unwrap().expect("cannot get data"),
+            Some(b"new value1".to_vec())
+        );
+        assert_eq!(
+            context.get(b"key3").unwrap().expect("cannot get data"),
+            Some(b"value3".to_vec())
+        );
+        assert!(context
+            .get_meta(b"key2")
+            .unwrap()
+            .expect("cannot get from meta cf")
+            .is_none());
+        // Reading a pending write costs as much as reading from storage
+        assert_eq!(
+            context.get(b"key3").cost,
+            OperationCost {
+                seek_count: 1,
+                storage_loaded_bytes: 6,
+                ..Default::default()
+            }
+        );
+
+        // Data is still untouched in storage until the batch is commited
+        let context_without_batch = storage
+            .get_storage_context([b"ayya"].as_ref().into(), None)
+            .unwrap();
+        assert_eq!(
+            context_without_batch
+                .get(b"key1")
+                .unwrap()
+                .expect("cannot get data"),
+            Some(b"value1".to_vec())
+        );
+        assert_eq!(
+            context_without_batch
+                .get_meta(b"key2")
+                .unwrap()
+                .expect("cannot get from meta cf"),
+            Some(b"value2".to_vec())
+        );
+    }
+
+    #[test]
+    fn test_multi_put() {
+        let storage = TempStorage::new();
+        let batch = StorageBatch::new();
+        let context_ayya = storage
+            .get_storage_context([b"ayya"].as_ref().into(), Some(&batch))
+            .unwrap();
+        let context_ayyb = storage
+            .get_storage_context([b"ayyb"].as_ref().into(), Some(&batch))
+            .unwrap();
+
+        let entries: Vec<(Vec<u8>, Vec<u8>)> = (0u8..100)
+            .map(|i| (vec![i], format!("value{i}").into_bytes()))
+            .collect();
+        let borrowed_entries: Vec<(&[u8], &[u8])> = entries
+            .iter()
+            .map(|(key, value)| (key.as_slice(), value.as_slice()))
+            .collect();
+        context_ayya
+            .multi_put(&borrowed_entries)
+            .unwrap()
+            .expect("cannot insert data");
+        for (key, value) in entries.iter() {
+            context_ayyb
+                .put(key, value, None, None)
+                .unwrap()
+                .expect("cannot insert data");
+        }
+        assert_eq!(batch.len(), 200);
+
+        storage
+            .commit_multi_context_batch(batch, None)
+            .unwrap()
+            .expect("cannot commit batch");
+
+        let collect_entries = |path: &[u8]| {
+            let context = storage
+                .get_storage_context([path].as_ref().into(), None)
+                .unwrap();
+            let mut iter = context.raw_iter();
+            iter.seek_to_first().unwrap();
+            let mut stored = Vec::new();
+            while iter.valid().unwrap() {
+                stored.push((
+                    iter.key().unwrap().expect("expected key").to_vec(),
+                    iter.value().unwrap().expect("expected value").to_vec(),
+                ));
+                iter.next().unwrap();
+            }
+            stored
+        };
+        let stored = collect_entries(b"ayya");
+        assert_eq!(stored, entries);
+        assert_eq!(stored, collect_entries(b"ayyb"));
+    }
+
+    #[test]
+    fn test_estimate_live_data_size_grows_with_data() {
+        fn insert_values(storage: &TempStorage, prefix: &[u8]) {
+            let batch = StorageBatch::new();
+            let context = storage
+                .get_storage_context([prefix].as_ref().into(), Some(&batch))
+                .unwrap();
+            for i in 0..1000u32 {
+                context
+                    .put(i.to_be_bytes(), &[i as u8; 100], None, None)
+                    .unwrap()
+                    .expect("cannot insert data");
+            }
+            storage
+                .commit_multi_context_batch(batch, None)
+                .unwrap()
+                .expect("cannot commit multi context batch");
+            // The estimate only accounts for data flushed to SST files
+            storage.flush().expect("cannot flush storage");
+        }
+
+        let storage = TempStorage::new();
+
+        insert_values(&storage, b"first");
+        let first_estimate = storage
+            .estimate_live_data_size()
+            .expect("cannot estimate live data size");
+        assert!(first_estimate > 0);
+
+        insert_values(&storage, b"second");
+        let second_estimate = storage
+            .estimate_live_data_size()
+            .expect("cannot estimate live data size");
+        assert!(second_estimate > first_estimate);
+    }
+
+    #[test]
+    fn test_write_options() {
+        let dir = tempfile::TempDir::new().expect("cannot create tempdir");
+        let mut storage = RocksDbStorage::default_rocksdb_with_path(dir.path())
+            .expect("cannot open rocksdb storage");
+
+        // Writes go through the write-ahead log by default
+        assert_eq!(
+            storage.write_options(),
+            WriteOptions {
+                sync: false,
+                disable_wal: false,
+            }
+        );
+
+        let bulk_load = WriteOptions {
+            sync: false,
+            disable_wal: true,
+        };
+        storage.set_write_options(bulk_load);
+        assert_eq!(storage.write_options(), bulk_load);
+        {
+            let batch = StorageBatch::new();
+            let context = storage
+                .get_storage_context([b"bulk"].as_ref().into(), Some(&batch))
+                .unwrap();
+            context
+                .put(b"batchkey", b"batchvalue", None, None)
+                .unwrap()
+                .expect("cannot insert data");
+            storage
+                .commit_multi_context_batch(batch, None)
+                .unwrap()
+                .expect("cannot commit multi context batch");
+        }
+
+        let synced = WriteOptions {
+            sync: true,
+            disable_wal: false,
+        };
+        storage.set_write_options(synced);
+        assert_eq!(storage.write_options(), synced);
+        {
+            let transaction = storage
+                .start_transaction()
+                .expect("cannot start transaction");
+            let context = storage
+                .get_immediate_storage_context([b"bulk"].as_ref().into(), &transaction)
+                .unwrap();
+            context
+                .put(b"txkey", b"txvalue", None, None)
+                .unwrap()
+                .expect("cannot insert data");
+            storage
+                .commit_transaction(transaction)
+                .unwrap()
+                .expect("cannot commit transaction");
+        }
+
+        // RocksDB refuses synced writes without the WAL, so both commit paths
+        // must fail if the options are really passed through
+        storage.set_write_options(WriteOptions {
+            sync: true,
+            disable_wal: true,
+        });
+        {
+            let batch = StorageBatch::new();
+            let context = storage
+                .get_storage_context([b"bulk"].as_ref().into(), Some(&batch))
+                .unwrap();
+            context
+                .put(b"rejectedbatchkey", b"value", None, None)
+                .unwrap()
+                .expect("cannot insert data");
+            assert!(storage
+                .commit_multi_context_batch(batch, None)
+                .unwrap()
+                .is_err());
+        }
+        {
+            let transaction = storage
+                .start_transaction()
+                .expect("cannot start transaction");
+            let context = storage
+                .get_immediate_storage_context([b"bulk"].as_ref().into(), &transaction)
+                .unwrap();
+            context
+                .put(b"rejectedtxkey", b"value", None, None)
+                .unwrap()
+                .expect("cannot insert data");
+            assert!(storage.commit_transaction(transaction).unwrap().is_err());
+        }
+
+        // Data written without the WAL survives once flushed
+        storage.flush().expect("cannot flush storage");
+        drop(storage);
+
+        let storage = RocksDbStorage::default_rocksdb_with_path(dir.path())
+            .expect("cannot reopen rocksdb storage");
+        assert_eq!(storage.write_options(), WriteOptions::default());
+        let context = storage
+            .get_storage_context([b"bulk"].as_ref().into(), None)
+            .unwrap();
+        assert_eq!(
+            context.get(b"batchkey").unwrap().expect("cannot get data"),
+            Some(b"batchvalue".to_vec())
+        );
+        assert_eq!(
+            context.get(b"txkey").unwrap().expect("cannot get data"),
+            Some(b"txvalue".to_vec())
+        );
+        assert!(context
+            .get(b"rejectedbatchkey")
+            .unwrap()
+            .expect("cannot get data")
+            .is_none());
+        assert!(context
+            .get(b"rejectedtxkey")
+            .unwrap()
+            .expect("cannot get data")
+            .is_none());
+    }
 }
 
 mod batch_transaction {
     use super::*;
-    use crate::{Batch, RawIterator, Storage, StorageBatch, StorageContext};
+    use crate::{error::Error, Batch, RawIterator, Storage, StorageBatch, StorageContext};
 
     #[test]
     fn test_transaction_properties() {
         let storage = TempStorage::new();
-        let transaction = storage.start_transaction();
+        let transaction = storage
+            .start_transaction()
+            .expect("cannot start transaction");
 
         let batch = StorageBatch::new();
         let batch_tx = StorageBatch::new();
@@ -959,7 +1550,9 @@ mod batch_transaction {
     #[test]
     fn test_db_batch_in_transaction_merged_into_context_batch() {
         let storage = TempStorage::new();
-        let transaction = storage.start_transaction();
+        let transaction = storage
+            .start_transaction()
+            .expect("cannot start transaction");
         let batch = StorageBatch::new();
 
         let context_ayya = storage
@@ -1069,4 +1662,223 @@ mod batch_transaction {
             Some(b"value2".to_vec())
         );
     }
+
+    #[test]
+    fn test_transaction_savepoints() {
+        let storage = TempStorage::new();
+        let transaction = storage
+            .start_transaction()
+            .expect("cannot start transaction");
+
+        let batch = StorageBatch::new();
+        let context = storage
+            .get_transactional_storage_context(
+                [b"ayya"].as_ref().into(),
+                Some(&batch),
+                &transaction,
+            )
+            .unwrap();
+        context
+            .put(b"key1", b"value1", None, None)
+            .unwrap()
+            .expect("cannot insert data");
+        storage
+            .commit_multi_context_batch(batch, Some(&transaction))
+            .unwrap()
+            .expect("cannot commit multi-context batch");
+
+        let outer_savepoint = storage
+            .savepoint(&transaction)
+            .expect("cannot create savepoint");
+
+        let batch = StorageBatch::new();
+        let context = storage
+            .get_transactional_storage_context(
+                [b"ayya"].as_ref().into(),
+                Some(&batch),
+                &transaction,
+            )
+            .unwrap();
+        context
+            .put(b"key2", b"value2", None, None)
+            .unwrap()
+            .expect("cannot insert data");
+        storage
+            .commit_multi_context_batch(batch, Some(&transaction))
+            .unwrap()
+            .expect("cannot commit multi-context batch");
+
+        let inner_savepoint = storage
+            .savepoint(&transaction)
+            .expect("cannot create savepoint");
+
+        let batch = StorageBatch::new();
+        let context = storage
+            .get_transactional_storage_context(
+                [b"ayya"].as_ref().into(),
+                Some(&batch),
+                &transaction,
+            )
+            .unwrap();
+        context
+            .put(b"key3", b"value3", None, None)
+            .unwrap()
+            .expect("cannot insert data");
+        context
+            .delete(b"key1", None)
+            .unwrap()
+            .expect("cannot delete data");
+        storage
+            .commit_multi_context_batch(batch, Some(&transaction))
+            .unwrap()
+            .expect("cannot commit multi-context batch");
+
+        storage
+            .rollback_to(&transaction, inner_savepoint)
+            .expect("cannot rollback to savepoint");
+
+        let context = storage
+            .get_transactional_storage_context([b"ayya"].as_ref().into(), None, &transaction)
+            .unwrap();
+        assert_eq!(
+            context.get(b"key1").unwrap().expect("cannot get data"),
+            Some(b"value1".to_vec())
+        );
+        assert_eq!(
+            context.get(b"key2").unwrap().expect("cannot get data"),
+            Some(b"value2".to_vec())
+        );
+        assert_eq!(
+            context.get(b"key3").unwrap().expect("cannot get data"),
+            None
+        );
+
+        storage
+            .rollback_to(&transaction, outer_savepoint)
+            .expect("cannot rollback to savepoint");
+
+        let context = storage
+            .get_transactional_storage_context([b"ayya"].as_ref().into(), None, &transaction)
+            .unwrap();
+        assert_eq!(
+            context.get(b"key1").unwrap().expect("cannot get data"),
+            Some(b"value1".to_vec())
+        );
+        assert_eq!(
+            context.get(b"key2").unwrap().expect("cannot get data"),
+            None
+        );
+
+        storage
+            .commit_transaction(transaction)
+            .unwrap()
+            .expect("cannot commit transaction");
+
+        let context = storage
+            .get_storage_context([b"ayya"].as_ref().into(), None)
+            .unwrap();
+        assert_eq!(
+            context.get(b"key1").unwrap().expect("cannot get data"),
+            Some(b"value1".to_vec())
+        );
+        assert_eq!(
+            context.get(b"key2").unwrap().expect("cannot get data"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_invalid_savepoints_are_rejected() {
+        let storage = TempStorage::new();
+        let transaction = storage
+            .start_transaction()
+            .expect("cannot start transaction");
+        let other_transaction = storage
+            .start_transaction()
+            .expect("cannot start transaction");
+
+        let foreign_savepoint = storage
+            .savepoint(&other_transaction)
+            .expect("cannot create savepoint");
+        assert!(matches!(
+            storage.rollback_to(&transaction, foreign_savepoint),
+            Err(Error::InvalidSavepoint)
+        ));
+
+        let outer_savepoint = storage
+            .savepoint(&transaction)
+            .expect("cannot create savepoint");
+        let context = storage
+            .get_immediate_storage_context([b"ayya"].as_ref().into(), &transaction)
+            .unwrap();
+        context
+            .put(b"key1", b"value1", None, None)
+            .unwrap()
+            .expect("cannot insert data");
+
+        let inner_savepoint = storage
+            .savepoint(&transaction)
+            .expect("cannot create savepoint");
+        context
+            .put(b"key2", b"value2", None, None)
+            .unwrap()
+            .expect("cannot insert data");
+
+        // Rolling back to the outer savepoint discards the inner one as well
+        storage
+            .rollback_to(&transaction, outer_savepoint)
+            .expect("cannot rollback to savepoint");
+        assert_eq!(
+            context.get(b"key1").unwrap().expect("cannot get data"),
+            None
+        );
+        assert_eq!(
+            context.get(b"key2").unwrap().expect("cannot get data"),
+            None
+        );
+
+        context
+            .put(b"key3", b"value3", None, None)
+            .unwrap()
+            .expect("cannot insert data");
+        assert!(matches!(
+            storage.rollback_to(&transaction, inner_savepoint),
+            Err(Error::InvalidSavepoint)
+        ));
+        assert_eq!(
+            context.get(b"key3").unwrap().expect("cannot get data"),
+            Some(b"value3".to_vec())
+        );
+    }
+}
+
+mod prefixed_key {
+    use crate::{
+        error::Error,
+        rocksdb_storage::{make_prefixed_key_checked, RocksDbStorage},
+    };
+
+    #[test]
+    fn test_checked_prefixed_key() {
+        let prefix = RocksDbStorage::build_prefix([b"ayy"].as_ref().into()).unwrap();
+        let prefixed_key =
+            make_prefixed_key_checked(&prefix, b"key").expect("prefix has the right length");
+        assert_eq!(&prefixed_key[..prefix.len()], prefix.as_slice());
+        assert_eq!(&prefixed_key[prefix.len()..], b"key");
+
+        assert!(matches!(
+            make_prefixed_key_checked(&prefix[1..], b"key"),
+            Err(Error::InvalidPrefix {
+                expected: 32,
+                actual: 31
+            })
+        ));
+        assert!(matches!(
+            make_prefixed_key_checked(&[prefix.as_slice(), &[0]].concat(), b"key"),
+            Err(Error::InvalidPrefix {
+                expected: 32,
+                actual: 33
+            })
+        ));
+    }
 }
@@ -30,13 +30,13 @@
 
 use std::{
     cell::RefCell,
-    collections::{btree_map::IntoValues, BTreeMap},
+    collections::{btree_map::IntoValues, BTreeMap, BTreeSet},
     path::Path,
 };
 
 use grovedb_costs::{
-    storage_cost::key_value_cost::KeyValueStorageCost, ChildrenSizesWithIsSumTree, CostContext,
-    CostResult, OperationCost,
+    cost_return_on_error, storage_cost::key_value_cost::KeyValueStorageCost,
+    ChildrenSizesWithIsSumTree, CostContext, CostResult, CostsExt, OperationCost,
 };
 use grovedb_path::SubtreePath;
 use grovedb_visualize::visualize_to_vec;
@@ -60,8 +60,8 @@ pub trait Storage<'db> {
     /// is replication process.
     type ImmediateStorageContext: StorageContext<'db>;
 
-    /// Starts a new transaction
-    fn start_transaction(&'db self) -> Self::Transaction;
+    /// Starts a new transaction, fails for storages which can't be written to
+    fn start_transaction(&'db self) -> Result<Self::Transaction, Error>;
 
     /// Consumes and commits a transaction
     fn commit_transaction(&self, transaction: Self::Transaction) -> CostResult<(), Error>;
@@ -69,6 +69,23 @@ pub trait Storage<'db> {
     /// Rollback a transaction
     fn rollback_transaction(&self, transaction: &Self::Transaction) -> Result<(), Error>;
 
+    /// Records the current state of a transaction so it can be restored later
+    /// with [Storage::rollback_to]. Savepoints are nested: each rollback undoes
+    /// operations since the most recently created savepoint that wasn't rolled
+    /// back yet.
+    fn savepoint(&self, transaction: &Self::Transaction) -> Result<SavepointId, Error>;
+
+    /// Undo all operations done in the transaction since the `savepoint` was
+    /// created, the savepoint is consumed along with savepoints created after
+    /// it. Earlier savepoints and operations done before the `savepoint` are
+    /// kept. Fails with [Error::InvalidSavepoint] if the `savepoint` isn't on
+    /// the transaction savepoints stack anymore or was never there.
+    fn rollback_to(
+        &self,
+        transaction: &Self::Transaction,
+        savepoint: SavepointId,
+    ) -> Result<(), Error>;
+
     /// Consumes and applies multi-context batch.
     fn commit_multi_context_batch(
         &self,
@@ -79,6 +96,29 @@ pub trait Storage<'db> {
     /// Forces data to be written
     fn flush(&self) -> Result<(), Error>;
 
+    /// Forces data of a single column to be written, for instance metadata
+    /// after a bookkeeping write without flushing subtrees data. Backends
+    /// which can't flush columns separately flush everything.
+    fn flush_cf(&self, _column: StorageColumn) -> Result<(), Error> {
+        self.flush()
+    }
+
+    /// Compacts the stored data in the key range from `start` to `end`, a
+    /// `None` bound leaves the range open on that side, so space held by
+    /// deleted data is reclaimed without waiting for background compaction.
+    /// Backends which don't need compaction do nothing.
+    fn compact_range(&self, _start: Option<&[u8]>, _end: Option<&[u8]>) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Returns an estimate of the live data size held by the storage, in
+    /// bytes. Backends that can't tell return [`Error::NotSupported`].
+    fn estimate_live_data_size(&self) -> Result<u64, Error> {
+        Err(Error::NotSupported(
+            "live data size estimation is not supported by this storage",
+        ))
+    }
+
     /// Make storage context for a subtree with path, keeping all write
     /// operations inside a `batch` if provided.
     fn get_storage_context<'b, B>(
@@ -119,6 +159,26 @@ pub trait Storage<'db> {
 
 pub use grovedb_costs::ChildrenSizes;
 
+/// Handle to a transaction savepoint created with [Storage::savepoint].
+/// Savepoints form a stack, rolling back to a savepoint also discards the ones
+/// created after it.
+#[must_use = "a savepoint is useless unless rolled back to"]
+#[derive(Debug, PartialEq, Eq)]
+pub struct SavepointId(pub(crate) u64);
+
+/// Separately stored column of the storage data
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StorageColumn {
+    /// Subtrees data
+    Data,
+    /// Auxiliary data
+    Aux,
+    /// Subtrees roots
+    Roots,
+    /// Metadata
+    Meta,
+}
+
 /// Storage context.
 /// Provides operations expected from a database abstracting details such as
 /// whether it is a transaction or not.
@@ -139,6 +199,16 @@ pub trait StorageContext<'db> {
         cost_info: Option<KeyValueStorageCost>,
     ) -> CostResult<(), Error>;
 
+    /// Put several `(key, value)` entries into data storage_cost at once,
+    /// without children sizes or cost info
+    fn multi_put(&self, entries: &[(&[u8], &[u8])]) -> CostResult<(), Error> {
+        let mut cost = OperationCost::default();
+        for (key, value) in entries {
+            cost_return_on_error!(&mut cost, self.put(key, value, None, None));
+        }
+        Ok(()).wrap_with_cost(cost)
+    }
+
     /// Put `value` into auxiliary data storage_cost with `key`
     fn put_aux<K: AsRef<[u8]>>(
         &self,
@@ -211,6 +281,15 @@ pub trait StorageContext<'db> {
 
     /// Get raw iterator over storage_cost
     fn raw_iter(&self) -> Self::RawIterator;
+
+    /// Get raw iterator over auxiliary data storage_cost
+    fn raw_iter_aux(&self) -> Self::RawIterator;
+
+    /// Get raw iterator over trees roots storage_cost
+    fn raw_iter_roots(&self) -> Self::RawIterator;
+
+    /// Get raw iterator over GroveDB metadata storage_cost
+    fn raw_iter_meta(&self) -> Self::RawIterator;
 }
 
 /// Database batch (not to be confused with multi-tree operations batch).
@@ -266,6 +345,11 @@ pub trait RawIterator {
     /// Move iterator backward until `key` is hit.
     fn seek_for_prev<K: AsRef<[u8]>>(&mut self, key: K) -> CostContext<()>;
 
+    /// Move iterator to the greatest record whose key starts with `prefix`.
+    /// If there is no such record the iterator ends up on the closest record
+    /// before where it would be, so callers should check the key they get.
+    fn seek_last_for_prefix(&mut self, prefix: &[u8]) -> CostContext<()>;
+
     /// Move iterator to next record.
     fn next(&mut self) -> CostContext<()>;
 
@@ -280,6 +364,31 @@ pub trait RawIterator {
 
     /// Check if raw iterator points into a valid record
     fn valid(&self) -> CostContext<bool>;
+
+    /// Return key of the record following the one raw iterator points at,
+    /// leaving the iterator position unchanged.
+    ///
+    /// RocksDB iterators cannot be cloned, so this advances the iterator to
+    /// read the next key and then seeks back to the current one: peeking costs
+    /// two seeks and two key loads on top of what `next` and `key` would.
+    /// Returns `None` if the iterator is not valid or is at the last record.
+    fn peek_next_key(&mut self) -> CostContext<Option<Vec<u8>>> {
+        let mut cost = OperationCost::default();
+
+        let current_key = match self.key().unwrap_add_cost(&mut cost) {
+            Some(key) => key.to_vec(),
+            None => return None.wrap_with_cost(cost),
+        };
+
+        self.next().unwrap_add_cost(&mut cost);
+        let next_key = self
+            .key()
+            .unwrap_add_cost(&mut cost)
+            .map(|key| key.to_vec());
+        self.seek(current_key).unwrap_add_cost(&mut cost);
+
+        next_key.wrap_with_cost(cost)
+    }
 }
 
 /// Structure to hold deferred database operations in "batched" storage_cost
@@ -310,6 +419,23 @@ impl std::fmt::Debug for Operations {
     }
 }
 
+/// Value of the last operation on `key`, `Some(None)` for a delete
+fn pending_value(
+    operations: &BTreeMap<Vec<u8>, AbstractBatchOperation>,
+    key: &[u8],
+) -> Option<Option<Vec<u8>>> {
+    operations.get(key).map(|operation| match operation {
+        AbstractBatchOperation::Put { value, .. }
+        | AbstractBatchOperation::PutAux { value, .. }
+        | AbstractBatchOperation::PutRoot { value, .. }
+        | AbstractBatchOperation::PutMeta { value, .. } => Some(value.clone()),
+        AbstractBatchOperation::Delete { .. }
+        | AbstractBatchOperation::DeleteAux { .. }
+        | AbstractBatchOperation::DeleteRoot { .. }
+        | AbstractBatchOperation::DeleteMeta { .. } => None,
+    })
+}
+
 impl StorageBatch {
     /// Create empty batch.
     pub fn new() -> Self {
@@ -327,6 +453,19 @@ impl StorageBatch {
             + operations.meta.len()
     }
 
+    /// Distinct `prefix_len` bytes long prefixes of the keys written to subtrees
+    /// data or roots
+    pub(crate) fn data_and_roots_key_prefixes(&self, prefix_len: usize) -> BTreeSet<Vec<u8>> {
+        let operations = self.operations.borrow();
+        operations
+            .data
+            .keys()
+            .chain(operations.roots.keys())
+            .filter_map(|key| key.get(..prefix_len))
+            .map(|prefix| prefix.to_vec())
+            .collect()
+    }
+
     /// Add deferred `put` operation
     pub(crate) fn put(
         &self,
@@ -346,6 +485,44 @@ impl StorageBatch {
         );
     }
 
+    /// Add deferred `put` operations for several entries, without children
+    /// sizes or cost info
+    pub(crate) fn multi_put(&self, entries: impl IntoIterator<Item = (Vec<u8>, Vec<u8>)>) {
+        let data = &mut self.operations.borrow_mut().data;
+        for (key, value) in entries {
+            data.insert(
+                key.clone(),
+                AbstractBatchOperation::Put {
+                    key,
+                    value,
+                    children_sizes: None,
+                    cost_info: None,
+                },
+            );
+        }
+    }
+
+    /// Value pending in the batch for `key` of data storage_cost:
+    /// `Some(None)` if it's deleted, `None` if the key isn't touched
+    pub(crate) fn get_pending(&self, key: &[u8]) -> Option<Option<Vec<u8>>> {
+        pending_value(&self.operations.borrow().data, key)
+    }
+
+    /// Value pending in the batch for `key` of aux storage_cost
+    pub(crate) fn get_pending_aux(&self, key: &[u8]) -> Option<Option<Vec<u8>>> {
+        pending_value(&self.operations.borrow().aux, key)
+    }
+
+    /// Value pending in the batch for `key` of roots storage_cost
+    pub(crate) fn get_pending_root(&self, key: &[u8]) -> Option<Option<Vec<u8>>> {
+        pending_value(&self.operations.borrow().roots, key)
+    }
+
+    /// Value pending in the batch for `key` of metadata storage_cost
+    pub(crate) fn get_pending_meta(&self, key: &[u8]) -> Option<Option<Vec<u8>>> {
+        pending_value(&self.operations.borrow().meta, key)
+    }
+
     /// Add deferred `put` operation for aux storage_cost
     pub(crate) fn put_aux(
         &self,